@@ -0,0 +1,4 @@
+-- Migration 041: Per-token SSE write coalescing config
+-- NULL = flush every chunk immediately (default behaviour).
+-- Example: {"max_bytes": 1024, "max_delay_ms": 20}
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS stream_flush JSONB;
@@ -0,0 +1,3 @@
+-- Migration 042: Flag audit entries where the provider response could not be
+-- translated to OpenAI format and the raw provider body was returned instead.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS translation_fallback BOOLEAN NOT NULL DEFAULT false;
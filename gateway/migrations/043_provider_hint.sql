@@ -0,0 +1,7 @@
+-- Migration 043: Per-token provider hint + effective provider in audit logs
+-- NULL provider_hint = detect provider from the model name / upstream URL.
+-- Example: 'openai' for fine-tunes like ft:gpt-4o:acme::abc
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS provider_hint VARCHAR(32);
+
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS provider VARCHAR(32);
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS provider_hinted BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,3 @@
+-- Migration 044: Record X-Properties keys rejected by the require_properties action
+-- Example: '{cost_center,environment}'
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS missing_properties TEXT[];
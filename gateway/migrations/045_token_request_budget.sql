@@ -0,0 +1,4 @@
+-- Migration 045: Per-token end-to-end request budget (seconds), including HITL wait
+-- NULL = no budget. Example: 120 → a request approved after 2 minutes is aborted
+-- with 408 request_budget_exceeded instead of being forwarded upstream.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS request_budget_secs INTEGER;
@@ -0,0 +1,26 @@
+-- Migration 046: Upstream health history for SLA reporting
+-- Samples are per-minute aggregates of real traffic with a bounded latency
+-- reservoir; transitions are circuit-breaker open/close events.
+-- Rows older than TRUEFLOW_UPSTREAM_HEALTH_RETENTION_DAYS (default 30) are pruned hourly.
+CREATE TABLE IF NOT EXISTS upstream_health_samples (
+    id BIGSERIAL PRIMARY KEY,
+    upstream_url TEXT NOT NULL,
+    bucket_start TIMESTAMPTZ NOT NULL,
+    requests INTEGER NOT NULL,
+    failures INTEGER NOT NULL,
+    latencies_ms INTEGER[] NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX IF NOT EXISTS idx_upstream_health_samples_url_time
+    ON upstream_health_samples (upstream_url, bucket_start DESC);
+
+CREATE TABLE IF NOT EXISTS upstream_health_transitions (
+    id BIGSERIAL PRIMARY KEY,
+    upstream_url TEXT NOT NULL,
+    token_id TEXT NOT NULL,
+    healthy BOOLEAN NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_upstream_health_transitions_url_time
+    ON upstream_health_transitions (upstream_url, occurred_at DESC);
@@ -0,0 +1,4 @@
+-- Migration 047: Explicit policy evaluation order
+-- Policies attached to a token are evaluated in ascending priority; ties keep
+-- the order of the token's policy_ids list. Later body overrides win.
+ALTER TABLE policies ADD COLUMN IF NOT EXISTS priority INTEGER NOT NULL DEFAULT 0;
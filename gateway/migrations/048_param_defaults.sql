@@ -0,0 +1,7 @@
+-- Migration 048: Per-token request parameter defaults
+-- tokens.param_defaults: JSON object merged into the request body for absent keys only.
+-- Example: '{"temperature": 0.2, "max_tokens": 1024}'
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS param_defaults JSONB;
+
+-- audit_logs.param_defaults_applied: which of those keys were actually filled in.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS param_defaults_applied TEXT[];
@@ -0,0 +1,4 @@
+-- Migration 049: Per-credential model restrictions
+-- NULL = credential may serve any model. Patterns use the same globs as
+-- tokens.allowed_models. Example: '{gpt-4o-mini,text-embedding-*}'
+ALTER TABLE credentials ADD COLUMN IF NOT EXISTS allowed_models TEXT[];
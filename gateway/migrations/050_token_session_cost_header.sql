@@ -0,0 +1,4 @@
+-- Migration 050: Opt-in cumulative session cost response header
+-- When true, non-streaming responses for requests with X-Session-Id carry
+-- X-TrueFlow-Session-Cost-USD with the session's running total.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS session_cost_header BOOLEAN NOT NULL DEFAULT false;
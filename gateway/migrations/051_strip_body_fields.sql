@@ -0,0 +1,7 @@
+-- Migration 051: Per-token request body field stripping
+-- tokens.strip_body_fields: top-level keys removed before forwarding upstream.
+-- Example: '{x_internal_trace,vendor_metadata}'
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS strip_body_fields TEXT[];
+
+-- audit_logs.body_fields_stripped: which of those keys were present and removed.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS body_fields_stripped TEXT[];
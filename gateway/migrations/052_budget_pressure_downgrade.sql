@@ -0,0 +1,9 @@
+-- Migration 052: Per-token model downgrade under budget pressure
+-- tokens.budget_pressure_model_map: requested model -> cheaper substitute.
+-- Example: '{"gpt-4o": "gpt-4o-mini", "claude-3-5-sonnet-20241022": "claude-3-5-haiku-20241022"}'
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS budget_pressure_model_map JSONB;
+-- tokens.budget_pressure_threshold_pct: remaining-budget % that triggers the map (NULL = 20).
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS budget_pressure_threshold_pct INTEGER;
+
+-- audit_logs.model_downgraded_from: the model the client asked for, when it was swapped.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS model_downgraded_from TEXT;
@@ -0,0 +1,4 @@
+-- Migration 053: Opt-in TTFT comment on streaming responses
+-- tokens.stream_ttft_comment: when true, SSE responses begin with a
+-- `: ttft=<ms>ms` comment line (ignored by compliant SSE clients).
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS stream_ttft_comment BOOLEAN NOT NULL DEFAULT false;
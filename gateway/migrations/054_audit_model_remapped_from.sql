@@ -0,0 +1,4 @@
+-- Migration 054: Record deprecated-model remaps in the audit log
+-- audit_logs.model_remapped_from: the deprecated model the client sent when the
+-- deprecated_model_map system setting rewrote it to its replacement.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS model_remapped_from TEXT;
@@ -0,0 +1,6 @@
+-- Migration 055: Per-token upstream override for integration testing
+-- tokens.test_upstream_override: replaces the upstream URL (e.g. a local mock
+-- server) when the gateway runs with TRUEFLOW_ALLOW_TEST_OVERRIDES set.
+-- audit_logs.test_upstream_override: the override URL used, if any.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS test_upstream_override TEXT;
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS test_upstream_override TEXT;
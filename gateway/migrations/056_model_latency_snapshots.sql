@@ -0,0 +1,14 @@
+-- Migration 056: Persisted latency distributions for warm-starting routing
+-- Replaced on every latency cache refresh (every 5 minutes) and loaded at
+-- startup, so lowest_latency routing has data before the first refresh.
+-- upstream_url = '' is the all-upstreams rollup per model.
+CREATE TABLE IF NOT EXISTS model_latency_snapshots (
+    model        TEXT NOT NULL,
+    upstream_url TEXT NOT NULL DEFAULT '',
+    p50_ms       DOUBLE PRECISION NOT NULL,
+    p95_ms       DOUBLE PRECISION NOT NULL,
+    p99_ms       DOUBLE PRECISION NOT NULL,
+    sample_count BIGINT NOT NULL,
+    captured_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (model, upstream_url)
+);
@@ -0,0 +1,9 @@
+-- Migration 057: Context-window pre-flight check
+-- tokens.context_window_action: 'reject' or 'trim' when the estimated prompt
+-- plus max_tokens exceeds the model's context window; NULL skips the check.
+-- audit_logs: the estimate, the window it was checked against, and how many
+-- of the oldest messages the trim action removed.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS context_window_action TEXT;
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS context_estimated_tokens INTEGER;
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS context_window_tokens INTEGER;
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS context_messages_trimmed INTEGER;
@@ -0,0 +1,5 @@
+-- Migration 058: Configurable spend-cap enforcement order
+-- tokens.enforcement_order: 'budget_first' checks the token spend cap and
+-- project hard cap before policy evaluation; NULL or 'policies_first' keeps
+-- the original order (policies and rate limits first).
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS enforcement_order TEXT;
@@ -0,0 +1,4 @@
+-- Migration 059: Correlation header passthrough
+-- tokens.forward_trace_headers: client headers (e.g. X-Correlation-Id) copied
+-- to the upstream request alongside the W3C trace context; NULL forwards none.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS forward_trace_headers TEXT[];
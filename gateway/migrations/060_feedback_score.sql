@@ -0,0 +1,4 @@
+-- Migration 060: Client-reported quality scores
+-- audit_logs.feedback_score: value of X-TrueFlow-Feedback-Score, compared
+-- across experiment variants by GET /analytics/experiments/:name/compare.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS feedback_score DOUBLE PRECISION;
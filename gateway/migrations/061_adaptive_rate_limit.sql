@@ -0,0 +1,5 @@
+-- Migration 061: Opt-in adaptive (AIMD) rate limiting per token
+-- tokens.adaptive_rate_limit: JSON config; the effective request limit shrinks
+-- while upstream latency is above a threshold and grows back as it recovers.
+-- NULL disables adaptive limiting.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS adaptive_rate_limit JSONB;
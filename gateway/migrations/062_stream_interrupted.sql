@@ -0,0 +1,4 @@
+-- Migration 062: Mid-stream upstream failures
+-- audit_logs.partial_content_len: content length received before a streaming
+-- response was cut off; set with error_type = 'stream_interrupted'.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS partial_content_len INTEGER;
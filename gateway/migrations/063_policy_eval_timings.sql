@@ -0,0 +1,4 @@
+-- Migration 063: Policy evaluation budget overruns
+-- audit_logs.policy_eval_timings: per-action pre-flight durations, recorded
+-- when evaluation exceeded TRUEFLOW_POLICY_EVAL_BUDGET_MS.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS policy_eval_timings JSONB;
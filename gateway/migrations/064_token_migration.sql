@@ -0,0 +1,7 @@
+-- Migration 064: Gradual traffic migration between credentials/upstreams
+-- tokens.migration: {target_credential_id, target_upstream_url, percentage};
+-- that share of requests (stable-hashed per session/user) goes to the target.
+-- NULL disables the migration.
+-- audit_logs.migration_path: 'target' or 'current' for tokens with a migration.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS migration JSONB;
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS migration_path TEXT;
@@ -0,0 +1,3 @@
+-- Migration 065: Record what the coerce_response_schema action changed
+-- Example: '{"/score: string -> number","/confidence: filled with 0"}'
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS schema_coercions TEXT[];
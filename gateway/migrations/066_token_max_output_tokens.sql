@@ -0,0 +1,8 @@
+-- Migration 066: Per-token ceiling on output tokens
+-- The request's max_tokens / max_completion_tokens / max_output_tokens is
+-- clamped to the ceiling (or the ceiling injected when none is sent).
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS max_output_tokens_ceiling INTEGER;
+
+-- Example: '{"ceiling": 1024, "clamped": {"max_tokens": 8000}}'
+--          '{"ceiling": 1024, "injected": "max_completion_tokens"}'
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS max_tokens_clamp JSONB;
@@ -0,0 +1,3 @@
+-- Migration 067: Per-token framing of streamed responses
+-- 'openai_sse' (default when NULL), 'ndjson' or 'jsonlines'
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS stream_output_format TEXT;
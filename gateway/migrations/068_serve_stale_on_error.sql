@@ -0,0 +1,5 @@
+-- Migration 068: Serve stale cached responses when the upstream fails
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS serve_stale_on_error BOOLEAN NOT NULL DEFAULT false;
+
+-- true when the response came from the stale cache after an upstream failure
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS stale_cache_served BOOLEAN NOT NULL DEFAULT false;
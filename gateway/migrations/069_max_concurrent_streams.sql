@@ -0,0 +1,2 @@
+-- Migration 069: Per-token cap on concurrent streaming responses
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS max_concurrent_streams INTEGER;
@@ -0,0 +1,6 @@
+-- Migration 070: Per-request cost ceiling
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS max_cost_per_request_usd NUMERIC(12, 6);
+
+-- Pre-flight worst-case estimate, and whether the actual cost overran the ceiling
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS request_cost_estimate_usd NUMERIC(12, 6);
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS request_cost_cap_exceeded BOOLEAN NOT NULL DEFAULT false;
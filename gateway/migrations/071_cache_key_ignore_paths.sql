@@ -0,0 +1,5 @@
+-- Migration 071: Per-token response cache key normalization
+-- tokens.cache_key_ignore_paths: JSON pointers removed from the request body
+-- before the cache key is hashed, so volatile fields don't defeat caching.
+-- Example: '{/metadata/request_id,/messages/0/timestamp}'
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS cache_key_ignore_paths TEXT[];
@@ -0,0 +1,5 @@
+-- Migration 072: Provider-account rate limits per credential and model
+-- NULL = no shared limit. JSON array of {model, rpm, tpm, queue_ms}; model
+-- uses the same globs as allowed_models.
+-- Example: '[{"model": "gpt-4o*", "rpm": 500, "tpm": 30000}]'
+ALTER TABLE credentials ADD COLUMN IF NOT EXISTS model_rate_limits JSONB;
@@ -0,0 +1,2 @@
+-- Migration 073: Stored credential the upstream rejected with 401/403
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS rejected_credential_id UUID;
@@ -0,0 +1,5 @@
+-- Migration 074: Emulate response_format json_object for providers without native JSON mode
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS json_mode_fallback BOOLEAN NOT NULL DEFAULT false;
+
+-- true when JSON mode was emulated with a system instruction and post-flight extraction
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS json_mode_emulated BOOLEAN NOT NULL DEFAULT false;
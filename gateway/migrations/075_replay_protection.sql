@@ -0,0 +1,4 @@
+-- Migration 075: Nonce-based replay protection
+-- tokens.replay_window_secs: when set, proxied requests must carry a unique
+-- X-TrueFlow-Nonce and an X-TrueFlow-Timestamp within this many seconds.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS replay_window_secs INTEGER;
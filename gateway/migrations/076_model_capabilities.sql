@@ -0,0 +1,5 @@
+-- Migration 076: Model capability pre-flight check
+-- tokens.capability_action: 'strip' or 'reject' when a request uses tools,
+-- image inputs or JSON mode the target model doesn't support (per the
+-- built-in table and the model_capabilities setting); NULL skips the check.
+ALTER TABLE tokens ADD COLUMN IF NOT EXISTS capability_action TEXT;
@@ -0,0 +1,5 @@
+-- Migration 077: Record content removed by the model capability check
+-- {"features": [...], "parts": [{"message", "part", "kind", "source"}]} when
+-- tokens.capability_action = 'strip' removed tools, JSON mode, or replaced
+-- image/audio parts with a text placeholder; NULL otherwise.
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS capability_stripped JSONB;
@@ -0,0 +1,5 @@
+-- Migration 078: Project default policies
+-- Policies merged into every token created in the project unless the caller
+-- passes skip_defaults. Copied into tokens.policy_ids at creation, so later
+-- changes to this list don't affect existing tokens.
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS default_policy_ids UUID[] NOT NULL DEFAULT '{}';
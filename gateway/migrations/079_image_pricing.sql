@@ -0,0 +1,5 @@
+-- Migration 079: Per-image pricing
+-- Image endpoints (/v1/images/generations) are billed per image. The price
+-- applies when model_pattern is contained in "<model>/<quality>/<size>"
+-- (e.g. "dall-e-3/hd/1792x1024"); the longest matching pattern wins.
+ALTER TABLE model_pricing ADD COLUMN IF NOT EXISTS image_per_unit NUMERIC(12, 6);
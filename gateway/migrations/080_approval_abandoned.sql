@@ -0,0 +1,13 @@
+-- Migration 080: Abandoned HITL approvals
+-- HITL approvals whose caller disconnected before a decision are marked
+-- 'abandoned'. They stay in the table for audit but no longer hold their
+-- idempotency key, so a retry with the same key opens a fresh approval.
+ALTER TABLE approval_requests DROP CONSTRAINT IF EXISTS approval_requests_status_check;
+ALTER TABLE approval_requests ADD CONSTRAINT approval_requests_status_check
+    CHECK (status IN ('pending', 'approved', 'rejected', 'expired', 'abandoned'));
+
+ALTER TABLE approval_requests
+    DROP CONSTRAINT IF EXISTS approval_requests_token_id_idempotency_key_key;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_approvals_idempotency
+    ON approval_requests(token_id, idempotency_key)
+    WHERE status <> 'abandoned';
@@ -0,0 +1,4 @@
+-- Migration 081: Flag audit entries billed on estimated token counts
+-- true when a streamed response carried no usage and the prompt/completion
+-- token counts (and cost) were estimated by the gateway
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS tokens_estimated BOOLEAN NOT NULL DEFAULT false;
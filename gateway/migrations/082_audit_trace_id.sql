@@ -0,0 +1,3 @@
+-- Migration 082: W3C trace id on audit entries
+-- The trace id of the client's `traceparent` header, when one was sent
+ALTER TABLE audit_logs ADD COLUMN IF NOT EXISTS trace_id TEXT;
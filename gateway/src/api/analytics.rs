@@ -1,11 +1,16 @@
 use crate::api::handlers::{verify_project_ownership, PaginationParams};
 use crate::api::AuthContext;
+use crate::models::analytics::{
+    forecast_spend, SpendForecast, DEFAULT_FORECAST_DAYS, MAX_FORECAST_DAYS,
+};
 use crate::AppState;
 use axum::{
-    extract::{Extension, Query, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
     Json,
 };
+use chrono::Datelike;
+use serde::Deserialize;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -82,3 +87,68 @@ pub async fn get_latency_percentiles(
 
     Ok(Json(stats))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastParams {
+    pub project_id: Option<Uuid>,
+    /// Trailing days the burn rate is averaged over. Default 7, max 90.
+    pub days: Option<u32>,
+}
+
+/// GET /api/v1/analytics/tokens/:id/forecast — projected month-end spend
+/// at the token's trailing daily burn rate
+pub async fn get_token_forecast(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(token_id): Path<String>,
+    Query(params): Query<ForecastParams>,
+) -> Result<Json<SpendForecast>, StatusCode> {
+    auth.require_scope("analytics:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    let project_id = params
+        .project_id
+        .unwrap_or_else(|| auth.default_project_id());
+    verify_project_ownership(&state, auth.org_id, project_id).await?;
+    let days = params.days.unwrap_or(DEFAULT_FORECAST_DAYS);
+    if days == 0 || days > MAX_FORECAST_DAYS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let token = state
+        .db
+        .get_token(&token_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("get_token_forecast: get_token failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|t| t.project_id == project_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let today = chrono::Utc::now().date_naive();
+    let month_start = today.with_day(1).unwrap_or(today);
+    let since = month_start.min(today - chrono::Duration::days(days as i64));
+    let daily = state
+        .db
+        .get_token_daily_costs(project_id, &token_id, since)
+        .await
+        .map_err(|e| {
+            tracing::error!("get_token_forecast failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let caps = crate::middleware::spend::get_spend_status(state.db.pool(), &state.cache, &token_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("get_token_forecast: spend caps failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(forecast_spend(
+        &token_id,
+        &daily,
+        days,
+        today,
+        token.created_at.date_naive(),
+        caps.monthly_limit_usd,
+    )))
+}
@@ -3,19 +3,29 @@
 //! Endpoints:
 //!   GET  /api/v1/config/export         — export all policies + tokens as YAML
 //!   POST /api/v1/config/import         — import (upsert) config from YAML/JSON body
+//!                                         (`?dry_run=true` validates and reports only)
 //!   GET  /api/v1/config/export/policies — export policies only
 //!   GET  /api/v1/config/export/tokens   — export tokens only (no secrets)
 //!
+//! Export query params:
+//!   ?format=yaml|json|ndjson  — `ndjson` streams one record per line without
+//!                               buffering the whole config in memory
+//!   ?include=policies,tokens  — sections to export (default: both)
+//!   ?page=N&page_size=M       — paginate each section (1-based, yaml/json only)
+//!
 //! The YAML schema is stable across gateway versions. It is explicitly versioned
 //! so that future breaking changes can be detected and rejected gracefully.
+//! Every export format — including a single page or an NDJSON stream — is
+//! accepted as-is by `/config/import`.
 
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use axum::{
-    body::Body,
+    body::{Body, Bytes},
     extract::{Extension, Query, State},
     http::{header, StatusCode},
-    response::Response,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -23,6 +33,8 @@ use uuid::Uuid;
 
 use crate::api::handlers::verify_project_ownership;
 use crate::api::AuthContext;
+use crate::middleware::policy::parse_window_secs;
+use crate::models::policy::{Action, RetryConfig, Rule};
 use crate::AppState;
 
 // Default project ID used when no project_id is specified in the query.
@@ -30,6 +42,11 @@ use crate::AppState;
 // middleware which already checks the admin API key, so no AuthContext needed.
 // const DEFAULT_PROJECT: &str = "00000000-0000-0000-0000-000000000001";
 
+/// Default page size for paginated exports and NDJSON streaming chunks.
+const EXPORT_PAGE_SIZE: u32 = 500;
+/// Upper bound for a caller-supplied `page_size`.
+const MAX_EXPORT_PAGE_SIZE: u32 = 1000;
+
 // ── Config Document Schema ────────────────────────────────────
 
 /// The top-level exported config document.
@@ -43,10 +60,28 @@ pub struct ConfigDocument {
     /// All tokens in the project (no credentials — those are managed separately).
     #[serde(default)]
     pub tokens: Vec<TokenExport>,
+    /// Set on paginated exports when another page exists. Ignored on import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_page: Option<u32>,
 }
 
-/// Serialized representation of a policy for export/import.
+/// One line of an NDJSON export. The header line must come first.
+///
+/// ```text
+/// {"kind":"header","version":"1"}
+/// {"kind":"policy","name":"pii-guard","mode":"enforce",...}
+/// {"kind":"token","name":"billing-agent","upstream_url":"...",...}
+/// ```
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NdjsonRecord {
+    Header { version: String },
+    Policy(PolicyExport),
+    Token(TokenExport),
+}
+
+/// Serialized representation of a policy for export/import.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct PolicyExport {
     /// Policy name — used as the unique key on import (upsert).
     pub name: String,
@@ -59,6 +94,9 @@ pub struct PolicyExport {
     /// Optional retry configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry: Option<serde_json::Value>,
+    /// Evaluation order among a token's policies (lower first).
+    #[serde(default)]
+    pub priority: i32,
 }
 
 /// Serialized representation of a token for export/import.
@@ -75,6 +113,10 @@ pub struct TokenExport {
     /// Optional log level: "metadata" | "redacted" | "full"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub log_level: Option<String>,
+    /// Name of the credential the token uses (resolved on import). Absent
+    /// for passthrough tokens; on import, absent leaves the credential as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
 }
 
 // ── Query Params ──────────────────────────────────────────────
@@ -86,29 +128,107 @@ pub struct ExportQuery {
     pub format: String,
     /// Optional project ID filter. Defaults to the default project.
     pub project_id: Option<Uuid>,
+    /// Comma-separated sections to export: "policies", "tokens". Defaults to both.
+    pub include: Option<String>,
+    /// 1-based page number. When absent the full config is exported.
+    pub page: Option<u32>,
+    /// Items per section per page (default 500, max 1000).
+    pub page_size: Option<u32>,
 }
 
 fn default_format() -> String {
     "yaml".to_string()
 }
 
+#[derive(Deserialize, Default)]
+pub struct ImportQuery {
+    /// Validate and report what would change without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Which sections of the config document to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sections {
+    policies: bool,
+    tokens: bool,
+}
+
+impl Sections {
+    const ALL: Sections = Sections {
+        policies: true,
+        tokens: true,
+    };
+
+    /// Parse `?include=`. Unknown section names are rejected.
+    fn parse(include: Option<&str>) -> Result<Self, StatusCode> {
+        let Some(include) = include.filter(|s| !s.trim().is_empty()) else {
+            return Ok(Self::ALL);
+        };
+        let mut sections = Sections {
+            policies: false,
+            tokens: false,
+        };
+        for part in include.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match part {
+                "policies" => sections.policies = true,
+                "tokens" => sections.tokens = true,
+                other => {
+                    tracing::warn!("config export: unknown include section '{}'", other);
+                    return Err(StatusCode::BAD_REQUEST);
+                }
+            }
+        }
+        Ok(sections)
+    }
+
+    /// Restrict to the sections an endpoint serves.
+    fn intersect(self, other: Sections) -> Sections {
+        Sections {
+            policies: self.policies && other.policies,
+            tokens: self.tokens && other.tokens,
+        }
+    }
+}
+
+/// A single page request: `limit` items per section starting at `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PageRequest {
+    page: u32,
+    limit: i64,
+    offset: i64,
+}
+
+impl PageRequest {
+    fn from_query(params: &ExportQuery) -> Result<Option<Self>, StatusCode> {
+        let Some(page) = params.page else {
+            return Ok(None);
+        };
+        if page == 0 {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let size = params
+            .page_size
+            .unwrap_or(EXPORT_PAGE_SIZE)
+            .clamp(1, MAX_EXPORT_PAGE_SIZE) as i64;
+        Ok(Some(PageRequest {
+            page,
+            limit: size,
+            offset: (page as i64 - 1) * size,
+        }))
+    }
+}
+
 // ── Handlers ──────────────────────────────────────────────────
 
 /// GET /api/v1/config/export
-/// Export the complete config (policies + tokens) as YAML or JSON.
+/// Export the complete config (policies + tokens) as YAML, JSON, or NDJSON.
 pub async fn export_config(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
     Query(params): Query<ExportQuery>,
 ) -> Result<Response, StatusCode> {
-    auth.require_scope("config:read")
-        .map_err(|_| StatusCode::FORBIDDEN)?;
-    let project_id = params
-        .project_id
-        .unwrap_or_else(|| auth.default_project_id());
-    verify_project_ownership(&state, auth.org_id, project_id).await?;
-    let doc = build_config_document(&state, project_id).await?;
-    serialize_and_respond(doc, &params.format)
+    export_sections(state, auth, params, Sections::ALL).await
 }
 
 /// GET /api/v1/config/export/policies
@@ -118,21 +238,11 @@ pub async fn export_policies(
     Extension(auth): Extension<AuthContext>,
     Query(params): Query<ExportQuery>,
 ) -> Result<Response, StatusCode> {
-    auth.require_scope("config:read")
-        .map_err(|_| StatusCode::FORBIDDEN)?;
-    let project_id = params
-        .project_id
-        .unwrap_or_else(|| auth.default_project_id());
-    verify_project_ownership(&state, auth.org_id, project_id).await?;
-    let policies = fetch_policies(&state, project_id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let doc = ConfigDocument {
-        version: "1".to_string(),
-        policies,
-        tokens: vec![],
+    let sections = Sections {
+        policies: true,
+        tokens: false,
     };
-    serialize_and_respond(doc, &params.format)
+    export_sections(state, auth, params, sections).await
 }
 
 /// GET /api/v1/config/export/tokens
@@ -141,6 +251,20 @@ pub async fn export_tokens(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
     Query(params): Query<ExportQuery>,
+) -> Result<Response, StatusCode> {
+    let sections = Sections {
+        policies: false,
+        tokens: true,
+    };
+    export_sections(state, auth, params, sections).await
+}
+
+/// Shared export path: auth, `include` filtering, pagination, and format.
+async fn export_sections(
+    state: Arc<AppState>,
+    auth: AuthContext,
+    params: ExportQuery,
+    served: Sections,
 ) -> Result<Response, StatusCode> {
     auth.require_scope("config:read")
         .map_err(|_| StatusCode::FORBIDDEN)?;
@@ -148,16 +272,15 @@ pub async fn export_tokens(
         .project_id
         .unwrap_or_else(|| auth.default_project_id());
     verify_project_ownership(&state, auth.org_id, project_id).await?;
-    let (tokens, _policies) = tokio::try_join!(
-        fetch_tokens(&state, project_id),
-        fetch_policies(&state, project_id)
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let doc = ConfigDocument {
-        version: "1".to_string(),
-        policies: vec![],
-        tokens,
-    };
+
+    let sections = Sections::parse(params.include.as_deref())?.intersect(served);
+
+    if params.format == "ndjson" {
+        return Ok(ndjson_response(state, project_id, sections));
+    }
+
+    let page = PageRequest::from_query(&params)?;
+    let doc = build_config_document(&state, project_id, sections, page).await?;
     serialize_and_respond(doc, &params.format)
 }
 
@@ -166,13 +289,20 @@ pub async fn export_tokens(
 ///
 /// Content-Type detection:
 ///   - `application/yaml` or `text/yaml` → parse as YAML
+///   - `application/x-ndjson`            → parse as an NDJSON export stream
 ///   - `application/json`                → parse as JSON
 ///   - anything else                      → try YAML first, then JSON
+///
+/// The whole document is validated before anything is written; if any
+/// resource is invalid the response is 422 with the per-resource report and
+/// nothing is imported. With `?dry_run=true` the report is returned (200 if
+/// valid) without touching the database.
 pub async fn import_config(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
+    Query(params): Query<ImportQuery>,
     req: axum::http::Request<Body>,
-) -> Result<Json<ImportResult>, StatusCode> {
+) -> Result<Response, StatusCode> {
     auth.require_scope("config:write")
         .map_err(|_| StatusCode::FORBIDDEN)?;
     let project_id = auth.default_project_id();
@@ -189,7 +319,12 @@ pub async fn import_config(
         .await
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let doc: ConfigDocument = if content_type.contains("json") {
+    let doc: ConfigDocument = if content_type.contains("ndjson") {
+        parse_ndjson(&bytes).map_err(|e| {
+            tracing::warn!("config import: NDJSON parse error: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?
+    } else if content_type.contains("json") {
         serde_json::from_slice(&bytes).map_err(|e| {
             tracing::warn!("config import: JSON parse error: {}", e);
             StatusCode::UNPROCESSABLE_ENTITY
@@ -207,7 +342,29 @@ pub async fn import_config(
         return Err(StatusCode::UNPROCESSABLE_ENTITY);
     }
 
-    import_document(&state, project_id, doc).await
+    let existing = load_existing(&state, project_id).await.map_err(|e| {
+        tracing::error!("config import: loading current config: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let plan = plan_import(&doc, &existing);
+    let valid = plan.iter().all(|item| item.errors.is_empty());
+
+    if params.dry_run || !valid {
+        let status = if valid {
+            StatusCode::OK
+        } else {
+            StatusCode::UNPROCESSABLE_ENTITY
+        };
+        let report = ImportReport {
+            dry_run: params.dry_run,
+            valid,
+            resources: plan,
+        };
+        return Ok((status, Json(report)).into_response());
+    }
+
+    let result = import_document(&state, project_id, doc, &plan, existing).await?;
+    Ok(Json(result).into_response())
 }
 
 // ── Implementation Helpers ────────────────────────────────────
@@ -215,23 +372,70 @@ pub async fn import_config(
 async fn build_config_document(
     state: &AppState,
     project_id: Uuid,
+    sections: Sections,
+    page: Option<PageRequest>,
 ) -> Result<ConfigDocument, StatusCode> {
-    let (policies, tokens) = tokio::try_join!(
-        fetch_policies(state, project_id),
-        fetch_tokens(state, project_id)
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(ConfigDocument {
+    let mut doc = ConfigDocument {
         version: "1".to_string(),
-        policies,
-        tokens,
-    })
+        policies: vec![],
+        tokens: vec![],
+        next_page: None,
+    };
+    let mut has_more = false;
+
+    if sections.policies {
+        match page {
+            Some(p) => {
+                let (policies, more) = fetch_policies_page(state, project_id, p.limit, p.offset)
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                doc.policies = policies;
+                has_more |= more;
+            }
+            None => {
+                doc.policies = fetch_policies(state, project_id)
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            }
+        }
+    }
+
+    if sections.tokens {
+        let names = export_names(state, project_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        match page {
+            Some(p) => {
+                let (tokens, more) =
+                    fetch_tokens_page(state, project_id, &names, p.limit, p.offset)
+                        .await
+                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                doc.tokens = tokens;
+                has_more |= more;
+            }
+            None => {
+                doc.tokens = fetch_tokens(state, project_id, &names)
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            }
+        }
+    }
+
+    if let Some(p) = page {
+        doc.next_page = has_more.then_some(p.page + 1);
+    }
+    Ok(doc)
 }
 
-async fn fetch_policies(state: &AppState, project_id: Uuid) -> anyhow::Result<Vec<PolicyExport>> {
-    // Use large defaults for internal export - we want all policies
-    let rows = state.db.list_policies(project_id, 1000, 0).await?;
+/// Fetch one page of active policies. The flag is true if more rows may follow.
+async fn fetch_policies_page(
+    state: &AppState,
+    project_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<(Vec<PolicyExport>, bool)> {
+    let rows = state.db.list_policies(project_id, limit, offset).await?;
+    let has_more = rows.len() as i64 == limit;
     let exports = rows
         .into_iter()
         .filter(|r| r.is_active)
@@ -241,19 +445,76 @@ async fn fetch_policies(state: &AppState, project_id: Uuid) -> anyhow::Result<Ve
             phase: r.phase,
             rules: r.rules,
             retry: r.retry,
+            priority: r.priority,
         })
         .collect();
-    Ok(exports)
+    Ok((exports, has_more))
 }
 
-async fn fetch_tokens(state: &AppState, project_id: Uuid) -> anyhow::Result<Vec<TokenExport>> {
-    // Fetch tokens - use large defaults for internal export
-    let token_rows = state.db.list_tokens(project_id, 1000, 0).await?;
+async fn fetch_policies(state: &AppState, project_id: Uuid) -> anyhow::Result<Vec<PolicyExport>> {
+    let limit = MAX_EXPORT_PAGE_SIZE as i64;
+    let mut all = Vec::new();
+    let mut offset = 0;
+    loop {
+        let (page, more) = fetch_policies_page(state, project_id, limit, offset).await?;
+        all.extend(page);
+        if !more {
+            return Ok(all);
+        }
+        offset += limit;
+    }
+}
 
-    // Fetch all policies in the project to build an id→name map
-    let policy_rows = state.db.list_policies(project_id, 1000, 0).await?;
-    let policy_name_map: std::collections::HashMap<Uuid, String> =
-        policy_rows.into_iter().map(|r| (r.id, r.name)).collect();
+/// Names of the resources tokens refer to by id.
+struct ExportNames {
+    policies: std::collections::HashMap<Uuid, String>,
+    credentials: std::collections::HashMap<Uuid, String>,
+}
+
+async fn export_names(state: &AppState, project_id: Uuid) -> anyhow::Result<ExportNames> {
+    let policies = policy_name_map(state, project_id).await?;
+    let credentials = state
+        .db
+        .list_credentials(project_id)
+        .await?
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect();
+    Ok(ExportNames {
+        policies,
+        credentials,
+    })
+}
+
+/// id→name for every policy in the project, used to resolve token bindings.
+async fn policy_name_map(
+    state: &AppState,
+    project_id: Uuid,
+) -> anyhow::Result<std::collections::HashMap<Uuid, String>> {
+    let limit = MAX_EXPORT_PAGE_SIZE as i64;
+    let mut map = std::collections::HashMap::new();
+    let mut offset = 0;
+    loop {
+        let rows = state.db.list_policies(project_id, limit, offset).await?;
+        let more = rows.len() as i64 == limit;
+        map.extend(rows.into_iter().map(|r| (r.id, r.name)));
+        if !more {
+            return Ok(map);
+        }
+        offset += limit;
+    }
+}
+
+/// Fetch one page of tokens. The flag is true if more rows may follow.
+async fn fetch_tokens_page(
+    state: &AppState,
+    project_id: Uuid,
+    names: &ExportNames,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<(Vec<TokenExport>, bool)> {
+    let token_rows = state.db.list_tokens(project_id, limit, offset).await?;
+    let has_more = token_rows.len() as i64 == limit;
 
     let exports = token_rows
         .into_iter()
@@ -261,7 +522,7 @@ async fn fetch_tokens(state: &AppState, project_id: Uuid) -> anyhow::Result<Vec<
             let policy_names: Vec<String> = t
                 .policy_ids
                 .iter()
-                .filter_map(|id| policy_name_map.get(id).cloned())
+                .filter_map(|id| names.policies.get(id).cloned())
                 .collect();
 
             let log_level_name = match t.log_level {
@@ -276,107 +537,490 @@ async fn fetch_tokens(state: &AppState, project_id: Uuid) -> anyhow::Result<Vec<
                 upstream_url: t.upstream_url,
                 policies: policy_names,
                 log_level: log_level_name,
+                credential: t
+                    .credential_id
+                    .and_then(|id| names.credentials.get(&id).cloned()),
             }
         })
         .collect();
-    Ok(exports)
+    Ok((exports, has_more))
+}
+
+async fn fetch_tokens(
+    state: &AppState,
+    project_id: Uuid,
+    names: &ExportNames,
+) -> anyhow::Result<Vec<TokenExport>> {
+    let limit = MAX_EXPORT_PAGE_SIZE as i64;
+    let mut all = Vec::new();
+    let mut offset = 0;
+    loop {
+        let (page, more) = fetch_tokens_page(state, project_id, names, limit, offset).await?;
+        all.extend(page);
+        if !more {
+            return Ok(all);
+        }
+        offset += limit;
+    }
+}
+
+// ── NDJSON Streaming ──────────────────────────────────────────
+
+/// Where the NDJSON export stream is up to.
+enum NdjsonStage {
+    Header,
+    Policies {
+        offset: i64,
+    },
+    Tokens {
+        offset: i64,
+        names: Option<Arc<ExportNames>>,
+    },
+}
+
+/// Stream the config as NDJSON, one DB page per chunk, so memory stays
+/// bounded by `EXPORT_PAGE_SIZE` regardless of config size.
+fn ndjson_response(state: Arc<AppState>, project_id: Uuid, sections: Sections) -> Response {
+    let stream = futures::stream::unfold(Some(NdjsonStage::Header), move |stage| {
+        let state = state.clone();
+        async move {
+            let stage = stage?;
+            match next_ndjson_chunk(&state, project_id, sections, stage).await {
+                Ok((chunk, next)) => Some((Ok(chunk), next)),
+                Err(e) => {
+                    // Headers are already sent — abort the body so the client sees a
+                    // truncated stream rather than a silently incomplete export.
+                    tracing::error!("config export: NDJSON stream failed: {}", e);
+                    Some((Err(std::io::Error::other(e.to_string())), None))
+                }
+            }
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/x-ndjson; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"trueflow_config.ndjson\"",
+        )
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+async fn next_ndjson_chunk(
+    state: &AppState,
+    project_id: Uuid,
+    sections: Sections,
+    stage: NdjsonStage,
+) -> anyhow::Result<(Bytes, Option<NdjsonStage>)> {
+    let limit = EXPORT_PAGE_SIZE as i64;
+    let tokens_stage = || {
+        sections.tokens.then_some(NdjsonStage::Tokens {
+            offset: 0,
+            names: None,
+        })
+    };
+
+    let mut buf = Vec::new();
+    let next = match stage {
+        NdjsonStage::Header => {
+            write_ndjson_line(
+                &mut buf,
+                &NdjsonRecord::Header {
+                    version: "1".to_string(),
+                },
+            )?;
+            if sections.policies {
+                Some(NdjsonStage::Policies { offset: 0 })
+            } else {
+                tokens_stage()
+            }
+        }
+        NdjsonStage::Policies { offset } => {
+            let (policies, more) = fetch_policies_page(state, project_id, limit, offset).await?;
+            for policy in policies {
+                write_ndjson_line(&mut buf, &NdjsonRecord::Policy(policy))?;
+            }
+            if more {
+                Some(NdjsonStage::Policies {
+                    offset: offset + limit,
+                })
+            } else {
+                tokens_stage()
+            }
+        }
+        NdjsonStage::Tokens { offset, names } => {
+            let names = match names {
+                Some(names) => names,
+                None => Arc::new(export_names(state, project_id).await?),
+            };
+            let (tokens, more) =
+                fetch_tokens_page(state, project_id, &names, limit, offset).await?;
+            for token in tokens {
+                write_ndjson_line(&mut buf, &NdjsonRecord::Token(token))?;
+            }
+            more.then_some(NdjsonStage::Tokens {
+                offset: offset + limit,
+                names: Some(names),
+            })
+        }
+    };
+    Ok((Bytes::from(buf), next))
+}
+
+fn write_ndjson_line(buf: &mut Vec<u8>, record: &NdjsonRecord) -> serde_json::Result<()> {
+    serde_json::to_writer(&mut *buf, record)?;
+    buf.push(b'\n');
+    Ok(())
+}
+
+/// Reassemble an NDJSON export into a `ConfigDocument` for import.
+fn parse_ndjson(bytes: &[u8]) -> Result<ConfigDocument, String> {
+    let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+    let mut doc = ConfigDocument {
+        version: String::new(),
+        policies: vec![],
+        tokens: vec![],
+        next_page: None,
+    };
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: NdjsonRecord =
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        match record {
+            NdjsonRecord::Header { version } => doc.version = version,
+            NdjsonRecord::Policy(policy) => doc.policies.push(policy),
+            NdjsonRecord::Token(token) => doc.tokens.push(token),
+        }
+    }
+    if doc.version.is_empty() {
+        return Err("missing header record".to_string());
+    }
+    Ok(doc)
+}
+
+/// A stored policy, as import compares against it.
+struct ExistingPolicy {
+    id: Uuid,
+    is_active: bool,
+    export: PolicyExport,
+}
+
+/// The stored fields of a token that import can change.
+struct ExistingToken {
+    id: String,
+    upstream_url: String,
+    policy_ids: Vec<Uuid>,
+    log_level: i16,
+    credential_id: Option<Uuid>,
+}
+
+/// The project's current config, keyed by name.
+#[derive(Default)]
+struct ExistingConfig {
+    policies: HashMap<String, ExistingPolicy>,
+    tokens: HashMap<String, ExistingToken>,
+    credentials: HashMap<String, Uuid>,
+    /// Every credential in the project, for ID references in policy rules.
+    credential_ids: HashSet<Uuid>,
+}
+
+async fn load_existing(state: &AppState, project_id: Uuid) -> anyhow::Result<ExistingConfig> {
+    let policies = state
+        .db
+        .list_policies(project_id, 1000, 0)
+        .await?
+        .into_iter()
+        .map(|p| {
+            let existing = ExistingPolicy {
+                id: p.id,
+                is_active: p.is_active,
+                export: PolicyExport {
+                    name: p.name.clone(),
+                    mode: p.mode,
+                    phase: p.phase,
+                    rules: p.rules,
+                    retry: p.retry,
+                    priority: p.priority,
+                },
+            };
+            (p.name, existing)
+        })
+        .collect();
+    let tokens = state
+        .db
+        .list_tokens(project_id, 1000, 0)
+        .await?
+        .into_iter()
+        .map(|t| {
+            let existing = ExistingToken {
+                id: t.id,
+                upstream_url: t.upstream_url,
+                policy_ids: t.policy_ids,
+                log_level: t.log_level,
+                credential_id: t.credential_id,
+            };
+            (t.name, existing)
+        })
+        .collect();
+    let credentials = state.db.credential_ids_by_name(project_id).await?;
+    let credential_ids = state
+        .db
+        .list_credentials(project_id)
+        .await?
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    Ok(ExistingConfig {
+        policies,
+        tokens,
+        credentials,
+        credential_ids,
+    })
+}
+
+fn log_level_value(name: Option<&str>) -> Result<i16, String> {
+    match name {
+        Some("metadata") => Ok(0),
+        None | Some("redacted") => Ok(1),
+        Some("full") => Ok(2),
+        Some(other) => Err(format!(
+            "invalid log_level '{}' (expected metadata, redacted or full)",
+            other
+        )),
+    }
+}
+
+fn validate_policy(policy: &PolicyExport) -> Vec<String> {
+    let mut errors = Vec::new();
+    if policy.name.trim().is_empty() {
+        errors.push("name must not be empty".to_string());
+    }
+    if policy.mode != "enforce" && policy.mode != "shadow" {
+        errors.push(format!("invalid mode '{}'", policy.mode));
+    }
+    if policy.phase != "pre" && policy.phase != "post" {
+        errors.push(format!("invalid phase '{}'", policy.phase));
+    }
+    match serde_json::from_value::<Vec<Rule>>(policy.rules.clone()) {
+        Ok(rules) => {
+            for action in rules.iter().flat_map(|r| &r.then) {
+                if let Action::RateLimit { window, .. } = action {
+                    if parse_window_secs(window).is_none() {
+                        errors.push(format!(
+                            "invalid rate_limit window '{}' (expected e.g. 30s, 1m, 1h, 1d)",
+                            window
+                        ));
+                    }
+                }
+            }
+        }
+        Err(e) => errors.push(format!("invalid rules: {}", e)),
+    }
+    if let Some(retry) = &policy.retry {
+        if let Err(e) = serde_json::from_value::<RetryConfig>(retry.clone()) {
+            errors.push(format!("invalid retry: {}", e));
+        }
+    }
+    errors
+}
+
+/// Validate `doc` against the current config and decide what importing it
+/// does to each resource. Nothing is written; an item with errors means the
+/// import must not run.
+fn plan_import(doc: &ConfigDocument, existing: &ExistingConfig) -> Vec<ImportItem> {
+    let mut plan = Vec::with_capacity(doc.policies.len() + doc.tokens.len());
+
+    let mut seen = HashSet::new();
+    for policy in &doc.policies {
+        let mut errors = validate_policy(policy);
+        if !seen.insert(policy.name.as_str()) {
+            errors.push("duplicate policy name in document".to_string());
+        }
+        for id in crate::proxy::split::variant_credential_ids(&policy.rules) {
+            if !existing.credential_ids.contains(&id) {
+                errors.push(format!("unknown split variant credential_id {}", id));
+            }
+        }
+        let action = match existing.policies.get(&policy.name) {
+            Some(e) if e.is_active && e.export == *policy => ImportAction::Skip,
+            Some(_) => ImportAction::Update,
+            None => ImportAction::Create,
+        };
+        plan.push(ImportItem {
+            kind: "policy",
+            name: policy.name.clone(),
+            action,
+            errors,
+        });
+    }
+
+    let imported_policies = seen;
+    let mut seen = HashSet::new();
+    for token in &doc.tokens {
+        let mut errors = Vec::new();
+        if token.name.trim().is_empty() {
+            errors.push("name must not be empty".to_string());
+        }
+        if !seen.insert(token.name.as_str()) {
+            errors.push("duplicate token name in document".to_string());
+        }
+        if let Err(e) = url::Url::parse(&token.upstream_url) {
+            errors.push(format!("invalid upstream_url: {}", e));
+        }
+        let log_level = log_level_value(token.log_level.as_deref()).unwrap_or_else(|e| {
+            errors.push(e);
+            1
+        });
+        for name in &token.policies {
+            if !imported_policies.contains(name.as_str()) && !existing.policies.contains_key(name) {
+                errors.push(format!("unknown policy '{}'", name));
+            }
+        }
+        let credential_id = match &token.credential {
+            Some(name) => match existing.credentials.get(name) {
+                Some(id) => Some(*id),
+                None => {
+                    errors.push(format!("unknown credential '{}'", name));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let action = match existing.tokens.get(&token.name) {
+            Some(e) => {
+                // Bindings to policies created by this import always change.
+                let policy_ids: Option<Vec<Uuid>> = token
+                    .policies
+                    .iter()
+                    .map(|name| {
+                        existing
+                            .policies
+                            .get(name)
+                            .filter(|_| !imported_policies.contains(name.as_str()))
+                            .map(|p| p.id)
+                    })
+                    .collect();
+                let unchanged = e.upstream_url == token.upstream_url
+                    && e.log_level == log_level
+                    && policy_ids.as_ref() == Some(&e.policy_ids)
+                    && credential_id.is_none_or(|id| e.credential_id == Some(id));
+                if unchanged {
+                    ImportAction::Skip
+                } else {
+                    ImportAction::Update
+                }
+            }
+            None => ImportAction::Create,
+        };
+        plan.push(ImportItem {
+            kind: "token",
+            name: token.name.clone(),
+            action,
+            errors,
+        });
+    }
+    plan
 }
 
+/// Apply a validated `plan` for `doc`.
 async fn import_document(
     state: &AppState,
     project_id: Uuid,
     doc: ConfigDocument,
-) -> Result<Json<ImportResult>, StatusCode> {
+    plan: &[ImportItem],
+    existing: ExistingConfig,
+) -> Result<ImportResult, StatusCode> {
     let mut result = ImportResult::default();
+    let (policy_plan, token_plan) = plan.split_at(doc.policies.len());
 
     // ── 1. Upsert policies ─────────────────────────────────────
     // Build a map of name→id for resolving token→policy references later.
-    let existing_policies = state
-        .db
-        .list_policies(project_id, 1000, 0)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let mut policy_id_map: std::collections::HashMap<String, Uuid> = existing_policies
+    let mut policy_id_map: HashMap<String, Uuid> = existing
+        .policies
         .iter()
-        .map(|p| (p.name.clone(), p.id))
+        .map(|(name, p)| (name.clone(), p.id))
         .collect();
 
-    for policy in &doc.policies {
+    for (policy, item) in doc.policies.iter().zip(policy_plan) {
         let rules_val = policy.rules.clone();
 
-        if let Some(&existing_id) = policy_id_map.get(&policy.name) {
-            // Update existing policy (no optimistic locking for bulk import)
-            let updated = state
-                .db
-                .update_policy(
-                    existing_id,
-                    project_id,
-                    Some(&policy.mode),
-                    Some(&policy.phase),
-                    Some(rules_val),
-                    policy.retry.clone(),
-                    Some(&policy.name),
-                    None, // No optimistic locking for bulk import
-                )
-                .await
-                .map_err(|e| {
-                    tracing::error!("config import: update policy '{}': {}", policy.name, e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-            match updated {
-                Ok(true) => result.policies_updated += 1,
-                Ok(false) => {} // Not found, skip
-                Err(()) => {}   // Version conflict, skip (shouldn't happen without version)
+        match item.action {
+            ImportAction::Skip => result.policies_unchanged += 1,
+            ImportAction::Update => {
+                let existing_id = policy_id_map[&policy.name];
+                let updated = state
+                    .db
+                    .update_policy(
+                        existing_id,
+                        project_id,
+                        Some(&policy.mode),
+                        Some(&policy.phase),
+                        Some(rules_val),
+                        policy.retry.clone(),
+                        Some(&policy.name),
+                        Some(policy.priority),
+                        None, // No optimistic locking for bulk import
+                    )
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("config import: update policy '{}': {}", policy.name, e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+                match updated {
+                    Ok(true) => result.policies_updated += 1,
+                    Ok(false) => {} // Not found, skip
+                    Err(()) => {}   // Version conflict, skip (shouldn't happen without version)
+                }
+            }
+            ImportAction::Create => {
+                let new_id = state
+                    .db
+                    .insert_policy(
+                        project_id,
+                        &policy.name,
+                        &policy.mode,
+                        &policy.phase,
+                        rules_val,
+                        policy.retry.clone(),
+                        policy.priority,
+                    )
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("config import: insert policy '{}': {}", policy.name, e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+                policy_id_map.insert(policy.name.clone(), new_id);
+                result.policies_created += 1;
             }
-        } else {
-            // Insert new policy
-            let new_id = state
-                .db
-                .insert_policy(
-                    project_id,
-                    &policy.name,
-                    &policy.mode,
-                    &policy.phase,
-                    rules_val,
-                    policy.retry.clone(),
-                )
-                .await
-                .map_err(|e| {
-                    tracing::error!("config import: insert policy '{}': {}", policy.name, e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-            policy_id_map.insert(policy.name.clone(), new_id);
-            result.policies_created += 1;
         }
     }
 
     // ── 2. Upsert tokens ───────────────────────────────────────
-    let existing_tokens = state
-        .db
-        .list_tokens(project_id, 1000, 0)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let existing_token_map: std::collections::HashMap<String, _> = existing_tokens
-        .into_iter()
-        .map(|t| (t.name.clone(), t))
-        .collect();
+    for (token_export, item) in doc.tokens.iter().zip(token_plan) {
+        if item.action == ImportAction::Skip {
+            result.tokens_unchanged += 1;
+            continue;
+        }
 
-    for token_export in &doc.tokens {
-        // Resolve policy names → IDs
+        // Resolve names → IDs (all checked by `plan_import`)
         let policy_ids: Vec<Uuid> = token_export
             .policies
             .iter()
             .filter_map(|name| policy_id_map.get(name).copied())
             .collect();
+        let credential_id = token_export
+            .credential
+            .as_ref()
+            .and_then(|name| existing.credentials.get(name).copied());
+        let log_level = log_level_value(token_export.log_level.as_deref()).unwrap_or(1);
 
-        let log_level: i16 = match token_export.log_level.as_deref() {
-            Some("metadata") => 0,
-            Some("full") => 2,
-            _ => 1, // default: redacted
-        };
-
-        if let Some(existing) = existing_token_map.get(&token_export.name) {
-            // Update token's policy bindings and log level
+        if let Some(existing) = existing.tokens.get(&token_export.name) {
+            // Update token's policy bindings, log level and credential
             let updated = state
                 .db
                 .update_token_config(
@@ -385,6 +1029,7 @@ async fn import_document(
                     policy_ids,
                     log_level,
                     &token_export.upstream_url,
+                    credential_id,
                 )
                 .await
                 .map_err(|e| {
@@ -392,10 +1037,11 @@ async fn import_document(
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?;
             if updated {
+                state.cache.invalidate_token(&existing.id).await;
                 result.tokens_updated += 1;
             }
         } else {
-            // Create a new stub token (no credential — caller must set this separately)
+            // Create a new token (passthrough until a credential is attached)
             let _new_id = state
                 .db
                 .insert_token_stub(
@@ -404,6 +1050,7 @@ async fn import_document(
                     &token_export.upstream_url,
                     policy_ids,
                     log_level,
+                    credential_id,
                 )
                 .await
                 .map_err(|e| {
@@ -414,7 +1061,7 @@ async fn import_document(
         }
     }
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 fn serialize_and_respond(doc: ConfigDocument, format: &str) -> Result<Response, StatusCode> {
@@ -450,6 +1097,335 @@ fn serialize_and_respond(doc: ConfigDocument, format: &str) -> Result<Response,
 pub struct ImportResult {
     pub policies_created: usize,
     pub policies_updated: usize,
+    /// Identical to what is stored; not written.
+    pub policies_unchanged: usize,
     pub tokens_created: usize,
     pub tokens_updated: usize,
+    pub tokens_unchanged: usize,
+}
+
+/// What importing a resource does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Create,
+    Update,
+    /// Identical to the stored resource.
+    Skip,
+}
+
+/// One resource in an import report.
+#[derive(Debug, Serialize)]
+pub struct ImportItem {
+    /// "policy" or "token"
+    pub kind: &'static str,
+    pub name: String,
+    pub action: ImportAction,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Returned by a dry run, or with 422 when the document is invalid.
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub valid: bool,
+    pub resources: Vec<ImportItem>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_parse() {
+        assert_eq!(Sections::parse(None).unwrap(), Sections::ALL);
+        assert_eq!(Sections::parse(Some("")).unwrap(), Sections::ALL);
+        assert_eq!(
+            Sections::parse(Some("tokens")).unwrap(),
+            Sections {
+                policies: false,
+                tokens: true
+            }
+        );
+        assert_eq!(
+            Sections::parse(Some(" policies , tokens ")).unwrap(),
+            Sections::ALL
+        );
+        assert_eq!(
+            Sections::parse(Some("policies,secrets")).unwrap_err(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_page_request_bounds() {
+        let query = |page, page_size| ExportQuery {
+            format: default_format(),
+            project_id: None,
+            include: None,
+            page,
+            page_size,
+        };
+        assert_eq!(
+            PageRequest::from_query(&query(None, Some(10))).unwrap(),
+            None
+        );
+        assert!(PageRequest::from_query(&query(Some(0), None)).is_err());
+
+        let p = PageRequest::from_query(&query(Some(3), Some(50)))
+            .unwrap()
+            .unwrap();
+        assert_eq!((p.limit, p.offset), (50, 100));
+
+        let p = PageRequest::from_query(&query(Some(1), Some(1_000_000)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(p.limit, MAX_EXPORT_PAGE_SIZE as i64);
+    }
+
+    #[test]
+    fn test_ndjson_round_trips_into_import_document() {
+        let mut buf = Vec::new();
+        write_ndjson_line(
+            &mut buf,
+            &NdjsonRecord::Header {
+                version: "1".into(),
+            },
+        )
+        .unwrap();
+        write_ndjson_line(
+            &mut buf,
+            &NdjsonRecord::Policy(PolicyExport {
+                name: "pii-guard".into(),
+                mode: "enforce".into(),
+                phase: "pre".into(),
+                rules: serde_json::json!([]),
+                retry: None,
+                priority: 0,
+            }),
+        )
+        .unwrap();
+        write_ndjson_line(
+            &mut buf,
+            &NdjsonRecord::Token(TokenExport {
+                name: "billing-agent".into(),
+                upstream_url: "https://api.openai.com".into(),
+                policies: vec!["pii-guard".into()],
+                log_level: Some("full".into()),
+                credential: None,
+            }),
+        )
+        .unwrap();
+
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.starts_with(r#"{"kind":"header","version":"1"}"#));
+        assert_eq!(text.lines().count(), 3);
+
+        let doc = parse_ndjson(&buf).unwrap();
+        assert_eq!(doc.version, "1");
+        assert_eq!(doc.policies[0].name, "pii-guard");
+        assert_eq!(doc.tokens[0].policies, vec!["pii-guard"]);
+    }
+
+    #[test]
+    fn test_ndjson_requires_header() {
+        let body = br#"{"kind":"token","name":"t","upstream_url":"https://x"}"#;
+        assert!(parse_ndjson(body).is_err());
+        assert!(parse_ndjson(b"not json\n").is_err());
+    }
+
+    #[test]
+    fn test_paginated_document_is_importable() {
+        let doc = ConfigDocument {
+            version: "1".into(),
+            policies: vec![],
+            tokens: vec![],
+            next_page: Some(2),
+        };
+        let yaml = serde_yaml::to_string(&doc).unwrap();
+        assert!(yaml.contains("next_page: 2"));
+        let back: ConfigDocument = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(back.version, "1");
+
+        let full = ConfigDocument {
+            next_page: None,
+            ..back
+        };
+        assert!(!serde_json::to_string(&full).unwrap().contains("next_page"));
+    }
+
+    fn policy(name: &str, rules: serde_json::Value) -> PolicyExport {
+        PolicyExport {
+            name: name.into(),
+            mode: "enforce".into(),
+            phase: "pre".into(),
+            rules,
+            retry: None,
+            priority: 0,
+        }
+    }
+
+    fn token(name: &str, policies: &[&str], credential: Option<&str>) -> TokenExport {
+        TokenExport {
+            name: name.into(),
+            upstream_url: "https://api.openai.com".into(),
+            policies: policies.iter().map(|p| p.to_string()).collect(),
+            log_level: None,
+            credential: credential.map(String::from),
+        }
+    }
+
+    fn document(policies: Vec<PolicyExport>, tokens: Vec<TokenExport>) -> ConfigDocument {
+        ConfigDocument {
+            version: "1".into(),
+            policies,
+            tokens,
+            next_page: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_reports_dangling_credential() {
+        let existing = ExistingConfig {
+            credentials: HashMap::from([("openai-prod".to_string(), Uuid::new_v4())]),
+            ..Default::default()
+        };
+        let doc = document(
+            vec![],
+            vec![
+                token("billing-agent", &[], Some("openai-prod")),
+                token("support-agent", &[], Some("openai-staging")),
+            ],
+        );
+        let plan = plan_import(&doc, &existing);
+
+        assert!(plan[0].errors.is_empty());
+        assert_eq!(plan[1].action, ImportAction::Create);
+        assert_eq!(plan[1].errors, vec!["unknown credential 'openai-staging'"]);
+        // Any error means `import_config` answers 422 before `import_document`
+        // runs, so nothing is written.
+        assert!(!plan.iter().all(|item| item.errors.is_empty()));
+    }
+
+    #[test]
+    fn test_plan_rejects_split_credential_outside_project() {
+        let own = Uuid::new_v4();
+        let existing = ExistingConfig {
+            credential_ids: HashSet::from([own]),
+            ..Default::default()
+        };
+        let split = |credential_id: Uuid| {
+            serde_json::json!([{
+                "then": {"action": "split", "variants": [
+                    {"weight": 1, "credential_id": credential_id}
+                ]}
+            }])
+        };
+        let foreign = Uuid::new_v4();
+        let doc = document(
+            vec![policy("own", split(own)), policy("foreign", split(foreign))],
+            vec![],
+        );
+        let plan = plan_import(&doc, &existing);
+
+        assert!(plan[0].errors.is_empty());
+        assert_eq!(
+            plan[1].errors,
+            vec![format!("unknown split variant credential_id {}", foreign)]
+        );
+    }
+
+    #[test]
+    fn test_plan_validates_policies_and_references() {
+        let rate_limit = |window: &str| {
+            serde_json::json!([{
+                "then": {"action": "rate_limit", "window": window, "max_requests": 10}
+            }])
+        };
+        let mut bad_mode = policy("bad-mode", serde_json::json!([]));
+        bad_mode.mode = "audit".into();
+        let doc = document(
+            vec![
+                policy("limit", rate_limit("1m")),
+                policy("bad-window", rate_limit("1 minute")),
+                policy("bad-rules", serde_json::json!({"then": "deny"})),
+                bad_mode,
+                policy("limit", rate_limit("1m")),
+            ],
+            vec![token("agent", &["limit", "missing"], None)],
+        );
+        let plan = plan_import(&doc, &ExistingConfig::default());
+
+        assert!(plan[0].errors.is_empty());
+        assert!(plan[1].errors[0].contains("invalid rate_limit window '1 minute'"));
+        assert!(plan[2].errors[0].starts_with("invalid rules"));
+        assert_eq!(plan[3].errors, vec!["invalid mode 'audit'"]);
+        assert_eq!(plan[4].errors, vec!["duplicate policy name in document"]);
+        assert_eq!(plan[5].errors, vec!["unknown policy 'missing'"]);
+    }
+
+    #[test]
+    fn test_plan_actions_against_existing_config() {
+        let stored = policy("pii-guard", serde_json::json!([]));
+        let policy_id = Uuid::new_v4();
+        let credential_id = Uuid::new_v4();
+        let existing = ExistingConfig {
+            policies: HashMap::from([(
+                "pii-guard".to_string(),
+                ExistingPolicy {
+                    id: policy_id,
+                    is_active: true,
+                    export: policy("pii-guard", serde_json::json!([])),
+                },
+            )]),
+            tokens: HashMap::from([(
+                "billing-agent".to_string(),
+                ExistingToken {
+                    id: "tf_v1_billing".into(),
+                    upstream_url: "https://api.openai.com".into(),
+                    policy_ids: vec![policy_id],
+                    log_level: 1,
+                    credential_id: Some(credential_id),
+                },
+            )]),
+            credentials: HashMap::from([("openai-prod".to_string(), credential_id)]),
+            credential_ids: HashSet::from([credential_id]),
+        };
+
+        let mut changed = policy("pii-guard", serde_json::json!([]));
+        changed.priority = 5;
+        let cases = [
+            (document(vec![stored], vec![]), ImportAction::Skip),
+            (document(vec![changed], vec![]), ImportAction::Update),
+            (
+                document(vec![policy("new", serde_json::json!([]))], vec![]),
+                ImportAction::Create,
+            ),
+            (
+                document(vec![], vec![token("billing-agent", &["pii-guard"], None)]),
+                ImportAction::Skip,
+            ),
+            (
+                document(
+                    vec![],
+                    vec![token("billing-agent", &["pii-guard"], Some("openai-prod"))],
+                ),
+                ImportAction::Skip,
+            ),
+            (
+                document(vec![], vec![token("billing-agent", &[], None)]),
+                ImportAction::Update,
+            ),
+            (
+                document(vec![], vec![token("support-agent", &[], None)]),
+                ImportAction::Create,
+            ),
+        ];
+        for (doc, expected) in cases {
+            let plan = plan_import(&doc, &existing);
+            assert_eq!(plan[0].action, expected, "{:?}", plan[0]);
+            assert!(plan[0].errors.is_empty(), "{:?}", plan[0]);
+        }
+    }
 }
@@ -105,7 +105,7 @@ pub async fn create_experiment(
 
     let policy_id = state
         .db
-        .insert_policy(project_id, &policy_name, "enforce", "pre", rules, None)
+        .insert_policy(project_id, &policy_name, "enforce", "pre", rules, None, 0)
         .await
         .map_err(|e| {
             tracing::error!("create_experiment failed: {}", e);
@@ -378,7 +378,17 @@ pub async fn update_experiment(
 
     let updated = state
         .db
-        .update_policy(id, project_id, None, None, Some(rules), None, None, None)
+        .update_policy(
+            id,
+            project_id,
+            None,
+            None,
+            Some(rules),
+            None,
+            None,
+            None,
+            None,
+        )
         .await
         .map_err(|e| {
             tracing::error!("update_experiment failed: {}", e);
@@ -540,7 +540,7 @@ pub async fn enable_guardrails(
             if let Some(existing_policy) = existing_input {
                 state.db.update_policy(
                 existing_policy.id, project_id,
-                None, None, Some(rules_value), None, Some(&policy_name), None,
+                None, None, Some(rules_value), None, Some(&policy_name), None, None,
             ).await.map_err(|e| {
                 tracing::error!(error = %e, "guardrails/enable: failed to update input policy");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -548,7 +548,7 @@ pub async fn enable_guardrails(
                 Some(existing_policy.id)
             } else {
                 let id = state.db.insert_policy(
-                project_id, &policy_name, "enforce", "request", rules_value, None,
+                project_id, &policy_name, "enforce", "request", rules_value, None, 0,
             ).await.map_err(|e| {
                 tracing::error!(error = %e, "guardrails/enable: failed to create input policy");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -568,7 +568,7 @@ pub async fn enable_guardrails(
         if let Some(existing_policy) = existing_output {
             state.db.update_policy(
                 existing_policy.id, project_id,
-                None, None, Some(out_rules_value), None, Some(&out_policy_name), None,
+                None, None, Some(out_rules_value), None, Some(&out_policy_name), None, None,
             ).await.map_err(|e| {
                 tracing::error!(error = %e, "guardrails/enable: failed to update output policy");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -576,7 +576,7 @@ pub async fn enable_guardrails(
             Some(existing_policy.id)
         } else {
             let id = state.db.insert_policy(
-                project_id, &out_policy_name, "enforce", "response", out_rules_value, None,
+                project_id, &out_policy_name, "enforce", "response", out_rules_value, None, 0,
             ).await.map_err(|e| {
                 tracing::error!(error = %e, "guardrails/enable: failed to create output policy");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -615,6 +615,7 @@ pub async fn enable_guardrails(
             tracing::error!(error = %e, "guardrails/enable: failed to attach policy to token");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+    state.cache.invalidate_token(&payload.token_id).await;
 
     let primary_policy_id = policy_id.or(output_policy_id);
     let policy_name = primary_policy_id
@@ -707,6 +708,7 @@ pub async fn disable_guardrails(
             tracing::error!(error = %e, "guardrails/disable: failed to update token");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+    state.cache.invalidate_token(&payload.token_id).await;
 
     // Deactivate the guardrail policies
     for id in &guardrail_ids {
@@ -6,10 +6,15 @@ use axum::{
     Extension, Json,
 };
 
-use super::dtos::{PaginationParams, SpendBreakdownParams, SpendBreakdownResponse};
+use super::dtos::{
+    ExperimentCompareParams, PaginationParams, SpendBreakdownParams, SpendBreakdownResponse,
+    ToolAnalyticsParams, ToolAnalyticsResponse,
+};
 use super::helpers::verify_project_ownership;
 use crate::api::AuthContext;
-use crate::store::postgres::{TokenLatencyStat, TokenStatusStat, TokenSummary, TokenVolumeStat};
+use crate::store::postgres::{
+    TokenLatencyStat, TokenStatusStat, TokenSummary, TokenVolumeStat, UpstreamHealthHistory,
+};
 use crate::AppState;
 
 pub async fn get_org_usage(
@@ -170,6 +175,48 @@ pub async fn get_upstream_health(
     Ok(Json(state.lb.get_all_status()))
 }
 
+/// GET /api/v1/analytics/latency/by-model — the latency cache's current
+/// p50/p95/p99 per model and per model/upstream, as used by `lowest_latency`
+/// routing. Distributions with few samples are flagged `low_sample`.
+pub async fn get_latency_by_model(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<crate::models::latency_cache::LatencySnapshot>, StatusCode> {
+    auth.require_scope("system:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    Ok(Json(state.latency.snapshot().await))
+}
+
+/// GET /api/v1/health/upstreams/:url/history — availability, latency
+/// percentiles and circuit transitions for one upstream over `range` hours.
+/// `:url` must be percent-encoded.
+pub async fn get_upstream_health_history(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(url): Path<String>,
+    Query(range): Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<UpstreamHealthHistory>, StatusCode> {
+    auth.require_scope("system:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+
+    let hours = range
+        .get("range")
+        .and_then(|s| s.parse::<i32>().ok())
+        .unwrap_or(24)
+        .clamp(1, 8760); // 1 hour minimum, 1 year maximum
+
+    let history = state
+        .db
+        .get_upstream_health_history(&url, hours)
+        .await
+        .map_err(|e| {
+            tracing::error!("get_upstream_health_history failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(history))
+}
+
 pub async fn get_analytics_summary(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
@@ -258,6 +305,39 @@ pub async fn get_analytics_experiments(
     Ok(Json(experiments))
 }
 
+/// GET /api/v1/analytics/experiments/:name/compare?hours=720
+///
+/// Per-variant mean cost, latency, error rate and (when clients send
+/// `X-TrueFlow-Feedback-Score`) quality, each compared against the baseline
+/// variant with a z-test significance flag.
+pub async fn get_experiment_comparison(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(name): Path<String>,
+    Query(params): Query<ExperimentCompareParams>,
+) -> Result<Json<crate::models::analytics::ExperimentComparison>, StatusCode> {
+    auth.require_scope("analytics:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    let project_id = params
+        .project_id
+        .unwrap_or_else(|| auth.default_project_id());
+    verify_project_ownership(&state, auth.org_id, project_id).await?;
+    let hours = params.hours.unwrap_or(720).clamp(1, 8760);
+
+    let stats = state
+        .db
+        .get_experiment_variant_stats(project_id, &name, hours)
+        .await
+        .map_err(|e| {
+            tracing::error!("get_experiment_comparison failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    crate::models::analytics::compare_variants(&name, hours, stats)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 /// GET /api/v1/analytics/spend/breakdown?group_by=model|token|tag:KEY&hours=720
 ///
 /// Returns spend grouped by a chosen dimension over a time window.
@@ -324,3 +404,38 @@ pub async fn get_spend_breakdown(
         breakdown: rows,
     }))
 }
+
+/// GET /api/v1/analytics/tools — most-invoked tools and their estimated cost contribution
+pub async fn get_tool_analytics(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Query(params): Query<ToolAnalyticsParams>,
+) -> Result<Json<ToolAnalyticsResponse>, StatusCode> {
+    auth.require_scope("analytics:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    let project_id = params
+        .project_id
+        .unwrap_or_else(|| auth.default_project_id());
+    verify_project_ownership(&state, auth.org_id, project_id).await?;
+
+    let hours = params.hours.unwrap_or(720);
+    if hours <= 0 || hours > 8760 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let tools = state
+        .db
+        .get_tool_usage(project_id, hours)
+        .await
+        .map_err(|e| {
+            tracing::error!("get_tool_analytics failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ToolAnalyticsResponse {
+        hours,
+        total_calls: tools.iter().map(|t| t.call_count).sum(),
+        total_estimated_cost_usd: tools.iter().map(|t| t.estimated_cost_usd).sum(),
+        tools,
+    }))
+}
@@ -5,14 +5,17 @@ use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
     Extension, Json,
 };
 use futures::stream::{self, Stream};
+use serde_json::json;
 use uuid::Uuid;
 
-use super::dtos::PaginationParams;
+use super::dtos::{PaginationParams, ReplayAuditRequest};
 use super::helpers::verify_project_ownership;
 use crate::api::AuthContext;
+use crate::proxy::replay;
 use crate::store::postgres::{AuditLogDetailRow, AuditLogRow};
 use crate::AppState;
 
@@ -71,6 +74,84 @@ pub async fn get_audit_log(
     Ok(Json(log))
 }
 
+/// POST /api/v1/audit/:id/replay — re-run a request captured at log_level 2
+/// through the proxy, optionally under another token of the same project.
+pub async fn replay_audit_log(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id_str): Path<String>,
+    Query(params): Query<PaginationParams>,
+    payload: Option<Json<ReplayAuditRequest>>,
+) -> Result<Response, StatusCode> {
+    auth.require_role("admin")?;
+    auth.require_scope("audit:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    // A replay sends a billed request as a token, so it needs the same
+    // scope as minting one, not just audit access.
+    auth.require_scope("tokens:write")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    let project_id = params
+        .project_id
+        .unwrap_or_else(|| auth.default_project_id());
+    verify_project_ownership(&state, auth.org_id, project_id).await?;
+    let log_id = Uuid::parse_str(&id_str).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+
+    let log = state
+        .db
+        .get_audit_log_detail(log_id, project_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("replay_audit_log: get_audit_log_detail failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // The override must belong to the same project as the entry.
+    if let Some(ref token_id) = payload.token_id {
+        let token = state.db.get_token(token_id).await.map_err(|e| {
+            tracing::error!("replay_audit_log: get_token failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if token.map(|t| t.project_id) != Some(project_id) {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    let request = match replay::rebuild(&log, payload.token_id.as_deref()) {
+        Ok(request) => request,
+        Err(e) => {
+            return Ok((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "error": e.to_string() })),
+            )
+                .into_response())
+        }
+    };
+
+    let audit_id = Uuid::new_v4();
+    tracing::info!(
+        user_id = %auth.user_id.unwrap_or_default(),
+        original_audit_id = %log_id,
+        %audit_id,
+        "replaying audited request"
+    );
+    let outcome = replay::run(request, audit_id, |req| async move {
+        crate::proxy::handler::proxy_handler(
+            State(state.clone()),
+            Some(Extension(crate::RequestId(audit_id))),
+            req.method,
+            req.uri,
+            req.headers,
+            req.body,
+        )
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+    })
+    .await;
+    Ok(Json(outcome).into_response())
+}
+
 pub async fn stream_audit_logs(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
@@ -9,6 +9,7 @@ use uuid::Uuid;
 
 use super::dtos::{
     CreateCredentialRequest, CreateCredentialResponse, DeleteResponse, PaginationParams,
+    UpdateModelRateLimitsRequest,
 };
 use super::helpers::verify_project_ownership;
 use crate::api::AuthContext;
@@ -49,9 +50,12 @@ pub async fn create_credential(
         .unwrap_or_else(|| auth.default_project_id());
     verify_project_ownership(&state, auth.org_id, project_id).await?;
 
-    // Encrypt the secret using the vault
+    // Encrypt the secret using the vault. With an external backend the proxy
+    // reads the secret from there, so the submitted value is not kept.
+    let external_secret = state.config.vault_backend != "builtin";
+    let stored_secret = if external_secret { "" } else { &payload.secret };
     let (encrypted_dek, dek_nonce, encrypted_secret, secret_nonce) =
-        state.vault.encrypt_string(&payload.secret).map_err(|e| {
+        state.vault.encrypt_string(stored_secret).map_err(|e| {
             tracing::error!("credential encryption failed: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
@@ -84,6 +88,20 @@ pub async fn create_credential(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Normalise allowed_models: drop blanks; an empty list means unrestricted
+    let allowed_models = payload
+        .allowed_models
+        .map(|models| {
+            models
+                .into_iter()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|models| !models.is_empty());
+
+    let model_rate_limits = validate_model_rate_limits(payload.model_rate_limits)?;
+
     let new_cred = crate::store::postgres::NewCredential {
         project_id,
         name: payload.name.clone(),
@@ -94,6 +112,8 @@ pub async fn create_credential(
         secret_nonce,
         injection_mode,
         injection_header,
+        allowed_models,
+        model_rate_limits,
     };
 
     let id = state.db.insert_credential(&new_cred).await.map_err(|e| {
@@ -106,11 +126,69 @@ pub async fn create_credential(
         Json(CreateCredentialResponse {
             id,
             name: payload.name,
-            message: "Credential encrypted and stored".to_string(),
+            message: if external_secret {
+                format!(
+                    "Credential registered; its secret is read from the {} vault backend",
+                    state.config.vault_backend
+                )
+            } else {
+                "Credential encrypted and stored".to_string()
+            },
         }),
     ))
 }
 
+/// Validate `model_rate_limits`; `null` and `[]` both mean no limits.
+fn validate_model_rate_limits(
+    limits: Option<serde_json::Value>,
+) -> Result<Option<serde_json::Value>, StatusCode> {
+    let Some(value) = limits.filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let parsed = crate::middleware::model_rate_limit::parse_limits(&value).map_err(|e| {
+        tracing::warn!("invalid model_rate_limits: {}", e);
+        StatusCode::UNPROCESSABLE_ENTITY
+    })?;
+    Ok((!parsed.is_empty()).then_some(value))
+}
+
+/// PUT /api/v1/credentials/:id/model-rate-limits — replace the shared
+/// per-model rate limits of a credential
+pub async fn update_credential_model_rate_limits(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id_str): Path<String>,
+    Json(payload): Json<UpdateModelRateLimitsRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    auth.require_role("admin")?;
+    auth.require_scope("credentials:write")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    let id = Uuid::parse_str(&id_str).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let project_id = auth.default_project_id();
+
+    let limits = validate_model_rate_limits(payload.model_rate_limits)?;
+    let updated = state
+        .db
+        .update_credential_model_rate_limits(id, project_id, limits.as_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("update_credential_model_rate_limits failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !updated {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state
+        .cache
+        .invalidate_credential_model_restriction(id)
+        .await;
+
+    Ok(Json(serde_json::json!({
+        "id": id,
+        "model_rate_limits": limits,
+    })))
+}
+
 /// DELETE /api/v1/credentials/:id — soft-delete a credential
 pub async fn delete_credential(
     State(state): State<Arc<AppState>>,
@@ -131,6 +209,14 @@ pub async fn delete_credential(
             tracing::error!("delete_credential failed: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+    if deleted {
+        // Whichever backend serves secrets, the proxy must stop using this one.
+        state.secrets.invalidate_cached(&id.to_string());
+        state
+            .cache
+            .invalidate_credential_model_restriction(id)
+            .await;
+    }
 
     Ok(Json(DeleteResponse { id, deleted }))
 }
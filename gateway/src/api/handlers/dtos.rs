@@ -9,6 +9,9 @@ pub struct CreateTokenRequest {
     pub upstream_url: String,
     pub project_id: Option<Uuid>,
     pub policy_ids: Option<Vec<Uuid>>,
+    /// Don't merge the project's `default_policy_ids` into `policy_ids`.
+    #[serde(default)]
+    pub skip_defaults: bool,
     /// Numeric log level (0/1/2) — deprecated in favour of log_level_name.
     #[serde(rename = "log_level")]
     pub log_level_num: Option<i16>,
@@ -38,6 +41,68 @@ pub struct CreateTokenRequest {
     pub mcp_allowed_tools: Option<serde_json::Value>,
     /// MCP tool blocklist. Takes priority over allowlist.
     pub mcp_blocked_tools: Option<serde_json::Value>,
+    /// SSE write coalescing: `{"max_bytes": 1024, "max_delay_ms": 20}`.
+    /// Omit to flush every chunk immediately.
+    pub stream_flush: Option<serde_json::Value>,
+    /// Force the provider used for translation and pricing (e.g. `"openai"`
+    /// for `ft:gpt-4o:...` fine-tunes or self-hosted aliases). Omit to auto-detect.
+    pub provider_hint: Option<String>,
+    /// Total end-to-end budget in seconds, including HITL approval wait.
+    /// Approvals that arrive after the budget is spent are not forwarded.
+    pub request_budget_secs: Option<i32>,
+    /// Default request parameters applied only when absent from the client body.
+    /// Example: {"temperature": 0.2, "max_tokens": 1024}
+    pub param_defaults: Option<serde_json::Value>,
+    /// Opt in to the X-TrueFlow-Session-Cost-USD response header (default false).
+    #[serde(default)]
+    pub session_cost_header: bool,
+    /// Top-level request body fields to remove before forwarding upstream.
+    pub strip_body_fields: Option<Vec<String>>,
+    /// Cheaper substitutes applied when remaining budget is low, e.g.
+    /// `{"gpt-4o": "gpt-4o-mini"}`.
+    pub budget_pressure_model_map: Option<serde_json::Value>,
+    /// Remaining-budget percentage at or below which `budget_pressure_model_map`
+    /// applies. Defaults to 20.
+    pub budget_pressure_threshold_pct: Option<i32>,
+    /// Prefix streaming responses with a `: ttft=<ms>ms` SSE comment (default false).
+    #[serde(default)]
+    pub stream_ttft_comment: bool,
+    /// Replacement upstream URL for integration tests. Only honored when
+    /// the gateway runs with TRUEFLOW_ALLOW_TEST_OVERRIDES set.
+    pub test_upstream_override: Option<String>,
+    /// Pre-flight action when the estimated prompt exceeds the model's
+    /// context window: "reject" or "trim". None skips the check.
+    pub context_window_action: Option<String>,
+    /// When spend caps are checked: `policies_first` (default, after the
+    /// policy loop and rate limits) or `budget_first` (before policies and usage counters).
+    pub enforcement_order: Option<String>,
+    /// Client correlation headers (e.g. `X-Correlation-Id`) copied to the
+    /// upstream request alongside the W3C trace context.
+    pub forward_trace_headers: Option<Vec<String>>,
+    /// Opt-in adaptive (AIMD) rate limit; see `AdaptiveRateLimitConfig`. Omit to disable.
+    pub adaptive_rate_limit: Option<serde_json::Value>,
+    /// Gradual traffic migration to a target credential and/or upstream; see `MigrationConfig`. Omit to disable.
+    pub migration: Option<serde_json::Value>,
+    /// Clamp the request's output-token limit to this; injected when absent.
+    pub max_output_tokens_ceiling: Option<i32>,
+    /// Framing of streamed responses: `openai_sse` (default), `ndjson` or `jsonlines`.
+    pub stream_output_format: Option<String>,
+    /// Serve the last cached response, even if expired, when the upstream fails (default false).
+    #[serde(default)]
+    pub serve_stale_on_error: bool,
+    /// Cap on concurrent streaming responses for this token.
+    pub max_concurrent_streams: Option<i32>,
+    /// Maximum estimated cost of a single request, in USD.
+    pub max_cost_per_request_usd: Option<rust_decimal::Decimal>,
+    /// JSON pointer paths stripped before computing the response cache key.
+    pub cache_key_ignore_paths: Option<Vec<String>>,
+    /// Emulate `response_format: json_object` on providers without native JSON mode (default false).
+    #[serde(default)]
+    pub json_mode_fallback: bool,
+    /// Require a unique nonce and a timestamp within this many seconds on every request.
+    pub replay_window_secs: Option<i32>,
+    /// Unsupported model features: `strip` them or `reject` the request.
+    pub capability_action: Option<String>,
 }
 
 impl CreateTokenRequest {
@@ -62,6 +127,76 @@ pub struct CreateTokenResponse {
     pub message: String,
 }
 
+/// One spec in a `POST /tokens/bulk` request.
+#[derive(Deserialize)]
+pub struct BulkTokenSpec {
+    #[serde(flatten)]
+    pub token: CreateTokenRequest,
+    /// Credential name in the token's project, instead of `credential_id`.
+    #[serde(default)]
+    pub credential: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkCreateTokensRequest {
+    pub tokens: Vec<BulkTokenSpec>,
+    /// Create all tokens or none (one transaction). Default: best effort.
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkTokenResult {
+    /// Position of the spec in the request.
+    pub index: usize,
+    pub name: String,
+    /// "created", "error", or "skipped" (valid, but not created because
+    /// another spec of an atomic batch failed).
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BulkCreateTokensResponse {
+    pub atomic: bool,
+    pub total: usize,
+    pub created: usize,
+    pub errors: usize,
+    pub results: Vec<BulkTokenResult>,
+}
+
+impl BulkCreateTokensResponse {
+    pub fn new(atomic: bool, results: Vec<BulkTokenResult>) -> Self {
+        let count = |status: &str| results.iter().filter(|r| r.status == status).count();
+        Self {
+            atomic,
+            total: results.len(),
+            created: count("created"),
+            errors: count("error"),
+            results,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct RotateTokenRequest {
+    /// Keep the old token usable for this many seconds (default 0: revoke now).
+    #[serde(default)]
+    pub grace_seconds: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct RotateTokenResponse {
+    pub token_id: String,
+    pub rotated_from: String,
+    /// When the old token stops working; `None` if it was revoked immediately.
+    pub old_token_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub message: String,
+}
+
 // ── Approval DTOs ───────────────────────────────────────────
 #[derive(Deserialize)]
 pub struct DecisionRequest {
@@ -102,16 +237,47 @@ pub struct SpendBreakdownResponse {
     pub breakdown: Vec<crate::store::postgres::SpendByDimension>,
 }
 
+#[derive(Deserialize)]
+pub struct ToolAnalyticsParams {
+    pub project_id: Option<Uuid>,
+    /// Time window in hours (default: 720 = 30 days, max: 8760 = 1 year)
+    pub hours: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct ExperimentCompareParams {
+    pub project_id: Option<Uuid>,
+    /// Time window in hours (default: 720 = 30 days, max: 8760 = 1 year)
+    pub hours: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct ToolAnalyticsResponse {
+    pub hours: i32,
+    pub total_calls: i64,
+    pub total_estimated_cost_usd: f64,
+    pub tools: Vec<crate::store::postgres::ToolUsageStat>,
+}
+
 // ── Project DTOs ────────────────────────────────────────────
 #[derive(Deserialize)]
 pub struct CreateProjectRequest {
     pub name: String,
 }
 
+#[derive(Deserialize)]
+pub struct UpdateProjectRequest {
+    pub name: Option<String>,
+    /// Policies merged into every token created in the project from now on.
+    /// Must be active policies of the project; `[]` clears the defaults.
+    pub default_policy_ids: Option<Vec<Uuid>>,
+}
+
 #[derive(Serialize)]
 pub struct ProjectResponse {
     pub id: Uuid,
     pub name: String,
+    pub default_policy_ids: Vec<Uuid>,
 }
 
 // ── Session DTOs ────────────────────────────────────────────
@@ -136,6 +302,8 @@ pub struct CreatePolicyRequest {
     pub phase: Option<String>, // "pre" | "post", defaults to "pre"
     pub rules: serde_json::Value,
     pub retry: Option<serde_json::Value>,
+    /// Evaluation order among a token's policies (lower first). Defaults to 0.
+    pub priority: Option<i32>,
     pub project_id: Option<Uuid>,
 }
 
@@ -146,6 +314,22 @@ pub struct UpdatePolicyRequest {
     pub phase: Option<String>,
     pub rules: Option<serde_json::Value>,
     pub retry: Option<serde_json::Value>,
+    pub priority: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct LintPolicyRequest {
+    pub rules: serde_json::Value,
+    /// Per-pattern evaluation budget in milliseconds (default 50, max 1000).
+    pub budget_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct LintPolicyResponse {
+    /// True when every pattern compiled and evaluated within budget.
+    pub valid: bool,
+    pub budget_ms: u64,
+    pub patterns: Vec<crate::middleware::guardrail::PatternLint>,
 }
 
 #[derive(Serialize)]
@@ -171,6 +355,18 @@ pub struct CreateCredentialRequest {
     pub project_id: Option<Uuid>,
     pub injection_mode: Option<String>, // "header" (default) | "bearer"
     pub injection_header: Option<String>, // e.g. "Authorization"
+    /// Model patterns this credential may serve (globs, e.g. "gpt-4o-mini*").
+    /// Enforced regardless of which token uses the credential.
+    pub allowed_models: Option<Vec<String>>,
+    /// Provider-account RPM/TPM limits per model, shared by every token on
+    /// the credential: `[{"model": "gpt-4o*", "rpm": 500, "tpm": 30000}]`.
+    pub model_rate_limits: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateModelRateLimitsRequest {
+    /// New limits; `null` or `[]` removes them.
+    pub model_rate_limits: Option<serde_json::Value>,
 }
 
 #[derive(Serialize)]
@@ -208,6 +404,37 @@ pub struct CreateApiKeyRequest {
     pub scopes: Option<Vec<String>>,
 }
 
+#[derive(Deserialize)]
+pub struct CreateOidcProviderRequest {
+    pub name: String,
+    /// Must be an https URL; matched against the JWT `iss` claim.
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Discovered from the issuer when omitted.
+    pub jwks_uri: Option<String>,
+    pub audience: Option<String>,
+    /// e.g. `{"role": "custom:trueflow_role", "scopes": "custom:trueflow_scopes"}`
+    pub claim_mapping: Option<serde_json::Value>,
+    pub default_role: Option<String>,
+    /// Comma-separated.
+    pub default_scopes: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Omitted fields are left unchanged.
+#[derive(Deserialize)]
+pub struct UpdateOidcProviderRequest {
+    pub name: Option<String>,
+    pub issuer_url: Option<String>,
+    pub client_id: Option<String>,
+    pub jwks_uri: Option<String>,
+    pub audience: Option<String>,
+    pub claim_mapping: Option<serde_json::Value>,
+    pub default_role: Option<String>,
+    pub default_scopes: Option<String>,
+    pub enabled: Option<bool>,
+}
+
 #[derive(Serialize)]
 pub struct CreateApiKeyResponse {
     pub id: Uuid,
@@ -232,6 +459,16 @@ pub struct UpsertSpendCapRequest {
     pub limit_usd: f64,
 }
 
+/// Body for `POST /tokens/:id/spend/simulate`.
+#[derive(Deserialize)]
+pub struct SimulateSpendCapRequest {
+    pub daily_limit_usd: Option<f64>,
+    pub monthly_limit_usd: Option<f64>,
+    pub lifetime_limit_usd: Option<f64>,
+    /// History to replay, in days (1–365). Default 30.
+    pub lookback_days: Option<i64>,
+}
+
 // ── Webhook DTOs ────────────────────────────────────────────
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct WebhookRow {
@@ -249,6 +486,9 @@ pub struct WebhookRow {
 #[derive(Deserialize)]
 pub struct CreateWebhookRequest {
     pub url: String,
+    /// Event types to deliver (see `notification::webhook::EVENT_TYPES`).
+    /// Omitted or empty subscribes to every event.
+    #[serde(alias = "event_types")]
     pub events: Option<Vec<String>>,
 }
 
@@ -270,6 +510,9 @@ pub struct UpsertPricingRequest {
     pub model_pattern: String,
     pub input_per_m: rust_decimal::Decimal,
     pub output_per_m: rust_decimal::Decimal,
+    /// USD per generated image, for image endpoints.
+    #[serde(default)]
+    pub image_per_unit: Option<rust_decimal::Decimal>,
 }
 
 #[derive(Serialize)]
@@ -279,11 +522,52 @@ pub struct PricingEntryResponse {
     pub model_pattern: String,
     pub input_per_m: rust_decimal::Decimal,
     pub output_per_m: rust_decimal::Decimal,
+    pub image_per_unit: Option<rust_decimal::Decimal>,
     pub is_active: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+#[derive(Deserialize)]
+pub struct PricingImportQuery {
+    /// Validate only; nothing is written.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// "csv" or "json". Defaults to CSV for `text/csv` bodies, JSON otherwise.
+    pub format: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PricingImportResponse {
+    pub dry_run: bool,
+    /// True when the rows were written (no errors and not a dry run).
+    pub applied: bool,
+    pub total: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub errors: usize,
+    pub results: Vec<crate::models::pricing_import::PricingImportResult>,
+}
+
+impl PricingImportResponse {
+    pub fn new(
+        dry_run: bool,
+        applied: bool,
+        results: Vec<crate::models::pricing_import::PricingImportResult>,
+    ) -> Self {
+        let count = |status: &str| results.iter().filter(|r| r.status == status).count();
+        Self {
+            dry_run,
+            applied,
+            total: results.len(),
+            created: count("created"),
+            updated: count("updated"),
+            errors: count("error"),
+            results,
+        }
+    }
+}
+
 // ── Settings DTOs ───────────────────────────────────────────
 #[derive(serde::Deserialize)]
 pub struct UpdateSettingsRequest {
@@ -294,3 +578,26 @@ pub struct UpdateSettingsRequest {
 pub struct RehydrateRequest {
     pub tokens: Vec<String>,
 }
+
+/// `POST /system/cache-warm` — either `requests` (replayed under `token_id`)
+/// or `hot: true` (re-prime the current hot keys).
+#[derive(serde::Deserialize)]
+pub struct CacheWarmRequest {
+    #[serde(default)]
+    pub token_id: Option<String>,
+    /// Proxy path the requests are sent to. Default: `/v1/chat/completions`.
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub requests: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub hot: bool,
+}
+
+/// `POST /audit/:id/replay` — optional token to replay under instead of the
+/// original one.
+#[derive(serde::Deserialize, Default)]
+pub struct ReplayAuditRequest {
+    #[serde(default)]
+    pub token_id: Option<String>,
+}
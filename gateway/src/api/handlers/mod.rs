@@ -7,6 +7,7 @@ pub mod dtos;
 mod helpers;
 mod model_access;
 mod notifications;
+mod oidc;
 mod policies;
 mod pricing;
 mod projects;
@@ -14,6 +15,7 @@ mod services;
 mod sessions;
 mod settings;
 mod spend_caps;
+mod stream_test;
 mod teams;
 mod tokens;
 mod webhooks;
@@ -31,15 +33,16 @@ pub use self::projects::{
 
 // ── Re-exports: Tokens ──────────────────────────────────────
 pub use self::tokens::{
-    create_token, get_circuit_breaker, get_token_usage, list_tokens, revoke_token,
-    update_circuit_breaker,
+    create_token, create_tokens_bulk, delete_token_migration, get_circuit_breaker,
+    get_token_migration, get_token_usage, list_tokens, revoke_token, rotate_token,
+    update_circuit_breaker, update_token_migration,
 };
 
 // ── Re-exports: Approvals ───────────────────────────────────
 pub use self::approvals::{decide_approval, list_approvals};
 
 // ── Re-exports: Audit ───────────────────────────────────────
-pub use self::audit::{get_audit_log, list_audit_logs, stream_audit_logs};
+pub use self::audit::{get_audit_log, list_audit_logs, replay_audit_log, stream_audit_logs};
 
 // ── Re-exports: Sessions ────────────────────────────────────
 pub use self::sessions::{
@@ -48,11 +51,13 @@ pub use self::sessions::{
 
 // ── Re-exports: Policies ────────────────────────────────────
 pub use self::policies::{
-    create_policy, delete_policy, list_policies, list_policy_versions, update_policy,
+    create_policy, delete_policy, lint_policy, list_policies, list_policy_versions, update_policy,
 };
 
 // ── Re-exports: Credentials ─────────────────────────────────
-pub use self::credentials::{create_credential, delete_credential, list_credentials};
+pub use self::credentials::{
+    create_credential, delete_credential, list_credentials, update_credential_model_rate_limits,
+};
 
 // ── Re-exports: Notifications ───────────────────────────────
 pub use self::notifications::{
@@ -66,26 +71,37 @@ pub use self::services::{create_service, delete_service, list_services};
 // ── Re-exports: Auth / API Keys ─────────────────────────────
 pub use self::auth::{create_api_key, list_api_keys, revoke_api_key, whoami};
 
+// ── Re-exports: OIDC Providers ──────────────────────────────
+pub use self::oidc::{
+    create_oidc_provider, delete_oidc_provider, list_oidc_providers, update_oidc_provider,
+};
+
 // ── Re-exports: Analytics ───────────────────────────────────
 pub use self::analytics::{
-    get_analytics_experiments, get_analytics_summary, get_analytics_timeseries, get_org_usage,
-    get_spend_breakdown, get_token_analytics, get_token_latency, get_token_status,
-    get_token_volume, get_upstream_health,
+    get_analytics_experiments, get_analytics_summary, get_analytics_timeseries,
+    get_experiment_comparison, get_latency_by_model, get_org_usage, get_spend_breakdown,
+    get_token_analytics, get_token_latency, get_token_status, get_token_volume, get_tool_analytics,
+    get_upstream_health, get_upstream_health_history,
 };
 
+// ── Re-exports: Stream Diagnostics ──────────────────────────
+pub use self::stream_test::stream_test_upstream;
+
 // ── Re-exports: Spend Caps ──────────────────────────────────
-pub use self::spend_caps::{delete_spend_cap, get_spend_caps, upsert_spend_cap};
+pub use self::spend_caps::{
+    delete_spend_cap, get_spend_caps, simulate_spend_cap, upsert_spend_cap,
+};
 
 // ── Re-exports: Webhooks ────────────────────────────────────
 pub use self::webhooks::{create_webhook, delete_webhook, list_webhooks, test_webhook};
 
 // ── Re-exports: Pricing ─────────────────────────────────────
-pub use self::pricing::{delete_pricing, list_pricing, upsert_pricing};
+pub use self::pricing::{delete_pricing, import_pricing, list_pricing, upsert_pricing};
 
 // ── Re-exports: Settings ────────────────────────────────────
 pub use self::settings::{
-    flush_cache, get_anomaly_events, get_cache_stats, get_settings, rehydrate_pii_tokens,
-    update_settings,
+    flush_cache, get_anomaly_events, get_cache_stats, get_effective_config, get_settings,
+    list_cache_warm_candidates, rehydrate_pii_tokens, update_settings, warm_cache,
 };
 
 // ── Re-exports: Model Access Groups ─────────────────────────
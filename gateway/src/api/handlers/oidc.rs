@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::dtos::{CreateOidcProviderRequest, UpdateOidcProviderRequest};
+use crate::api::AuthContext;
+use crate::middleware::oidc;
+use crate::store::postgres::{NewOidcProvider, OidcProviderPatch, OidcProviderRow};
+use crate::AppState;
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn error(status: StatusCode, code: &str, message: impl Into<String>) -> ApiError {
+    (
+        status,
+        Json(json!({ "error": { "code": code, "message": message.into() } })),
+    )
+}
+
+fn require_superadmin(auth: &AuthContext) -> Result<(), ApiError> {
+    auth.require_role("superadmin")
+        .map_err(|s| error(s, "forbidden", "SuperAdmin role required"))
+}
+
+fn invalid(message: impl Into<String>) -> ApiError {
+    error(StatusCode::UNPROCESSABLE_ENTITY, "invalid_config", message)
+}
+
+/// Checks shared by create and update, on the fields being set.
+fn validate_fields(
+    issuer_url: Option<&str>,
+    jwks_uri: Option<&str>,
+    claim_mapping: Option<&serde_json::Value>,
+    default_role: Option<&str>,
+) -> Result<(), ApiError> {
+    if let Some(issuer) = issuer_url {
+        oidc::validate_issuer_url(issuer).map_err(invalid)?;
+    }
+    if let Some(uri) = jwks_uri {
+        let ok = reqwest::Url::parse(uri).is_ok_and(|u| u.scheme() == "https");
+        if !ok {
+            return Err(invalid("jwks_uri must be an https URL"));
+        }
+    }
+    if let Some(mapping) = claim_mapping {
+        let ok = mapping
+            .as_object()
+            .is_some_and(|m| m.values().all(|v| v.is_string()));
+        if !ok {
+            return Err(invalid(
+                "claim_mapping must be an object of attribute → claim name",
+            ));
+        }
+    }
+    if let Some(role) = default_role {
+        if !oidc::PROVIDER_ROLES.contains(&role) {
+            return Err(invalid(format!(
+                "default_role must be one of: {}",
+                oidc::PROVIDER_ROLES.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The provider's keys must be fetchable now, or every login would fail.
+async fn ensure_jwks_reachable(issuer_url: &str, jwks_uri: Option<&str>) -> Result<(), ApiError> {
+    oidc::check_jwks(issuer_url, jwks_uri).await.map_err(|e| {
+        tracing::warn!(issuer = %issuer_url, error = %e, "OIDC provider JWKS check failed");
+        invalid(format!("JWKS is not reachable: {}", e))
+    })?;
+    Ok(())
+}
+
+fn store_error(action: &str, e: anyhow::Error) -> ApiError {
+    if e.to_string().contains("duplicate key") {
+        return error(
+            StatusCode::CONFLICT,
+            "conflict",
+            "An OIDC provider with this issuer already exists",
+        );
+    }
+    tracing::error!("{} OIDC provider failed: {}", action, e);
+    error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "internal_server_error",
+        format!("Failed to {} OIDC provider", action),
+    )
+}
+
+/// GET /api/v1/auth/oidc-providers — list the org's OIDC providers
+pub async fn list_oidc_providers(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<Vec<OidcProviderRow>>, ApiError> {
+    require_superadmin(&auth)?;
+    let rows = state
+        .db
+        .list_oidc_providers(auth.org_id)
+        .await
+        .map_err(|e| store_error("list", e))?;
+    Ok(Json(rows))
+}
+
+/// POST /api/v1/auth/oidc-providers — register an OIDC provider
+pub async fn create_oidc_provider(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Json(payload): Json<CreateOidcProviderRequest>,
+) -> Result<(StatusCode, Json<OidcProviderRow>), ApiError> {
+    require_superadmin(&auth)?;
+    if payload.name.trim().is_empty() || payload.client_id.trim().is_empty() {
+        return Err(invalid("name and client_id are required"));
+    }
+    validate_fields(
+        Some(&payload.issuer_url),
+        payload.jwks_uri.as_deref(),
+        payload.claim_mapping.as_ref(),
+        payload.default_role.as_deref(),
+    )?;
+    ensure_jwks_reachable(&payload.issuer_url, payload.jwks_uri.as_deref()).await?;
+
+    let provider = NewOidcProvider {
+        org_id: auth.org_id,
+        name: payload.name,
+        issuer_url: payload.issuer_url,
+        client_id: payload.client_id,
+        jwks_uri: payload.jwks_uri,
+        audience: payload.audience,
+        claim_mapping: payload.claim_mapping.unwrap_or_else(|| json!({})),
+        default_role: payload.default_role.unwrap_or_else(|| "viewer".to_string()),
+        default_scopes: payload
+            .default_scopes
+            .unwrap_or_else(|| "audit:read".to_string()),
+        enabled: payload.enabled.unwrap_or(true),
+    };
+    let row = state
+        .db
+        .create_oidc_provider(&provider)
+        .await
+        .map_err(|e| store_error("create", e))?;
+
+    tracing::info!(provider_id = %row.id, issuer = %row.issuer_url, "OIDC provider created");
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+/// PUT /api/v1/auth/oidc-providers/:id — update an OIDC provider
+pub async fn update_oidc_provider(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateOidcProviderRequest>,
+) -> Result<Json<OidcProviderRow>, ApiError> {
+    require_superadmin(&auth)?;
+    validate_fields(
+        payload.issuer_url.as_deref(),
+        payload.jwks_uri.as_deref(),
+        payload.claim_mapping.as_ref(),
+        payload.default_role.as_deref(),
+    )?;
+
+    let not_found = || {
+        error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            "OIDC provider not found",
+        )
+    };
+    let current = state
+        .db
+        .get_oidc_provider(id, auth.org_id)
+        .await
+        .map_err(|e| store_error("update", e))?
+        .ok_or_else(not_found)?;
+
+    // Re-check the keys when where they come from changes.
+    if payload.issuer_url.is_some() || payload.jwks_uri.is_some() {
+        let issuer = payload.issuer_url.as_deref().unwrap_or(&current.issuer_url);
+        let jwks_uri = payload.jwks_uri.as_deref().or(current.jwks_uri.as_deref());
+        ensure_jwks_reachable(issuer, jwks_uri).await?;
+    }
+
+    let patch = OidcProviderPatch {
+        name: payload.name,
+        issuer_url: payload.issuer_url,
+        client_id: payload.client_id,
+        jwks_uri: payload.jwks_uri,
+        audience: payload.audience,
+        claim_mapping: payload.claim_mapping,
+        default_role: payload.default_role,
+        default_scopes: payload.default_scopes,
+        enabled: payload.enabled,
+    };
+    let row = state
+        .db
+        .update_oidc_provider(id, auth.org_id, &patch)
+        .await
+        .map_err(|e| store_error("update", e))?
+        .ok_or_else(not_found)?;
+
+    tracing::info!(provider_id = %row.id, "OIDC provider updated");
+    Ok(Json(row))
+}
+
+/// DELETE /api/v1/auth/oidc-providers/:id — remove an OIDC provider
+pub async fn delete_oidc_provider(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    require_superadmin(&auth)?;
+    let found = state
+        .db
+        .delete_oidc_provider(id, auth.org_id)
+        .await
+        .map_err(|e| store_error("delete", e))?;
+    if found {
+        tracing::info!(provider_id = %id, "OIDC provider deleted");
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            "OIDC provider not found",
+        ))
+    }
+}
@@ -10,7 +10,8 @@ use serde_json::json;
 use uuid::Uuid;
 
 use super::dtos::{
-    CreatePolicyRequest, DeleteResponse, PaginationParams, PolicyResponse, UpdatePolicyRequest,
+    CreatePolicyRequest, DeleteResponse, LintPolicyRequest, LintPolicyResponse, PaginationParams,
+    PolicyResponse, UpdatePolicyRequest,
 };
 use super::helpers::verify_project_ownership;
 use crate::api::AuthContext;
@@ -100,6 +101,35 @@ pub async fn create_policy(
         ).into_response();
     }
 
+    if let Some(error) = custom_regex_error(&payload.rules) {
+        tracing::warn!("create_policy: {}", error);
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": error })),
+        )
+            .into_response();
+    }
+
+    match foreign_split_credential(&state, project_id, &payload.rules).await {
+        Ok(None) => {}
+        Ok(Some(id)) => {
+            tracing::warn!("create_policy: split credential {} not in project", id);
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "error": format!("split variant credential_id {} not found in this project", id) })),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!("create_policy: credential lookup failed: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "internal server error" })),
+            )
+                .into_response();
+        }
+    }
+
     match state
         .db
         .insert_policy(
@@ -109,6 +139,7 @@ pub async fn create_policy(
             &phase,
             payload.rules,
             payload.retry,
+            payload.priority.unwrap_or(0),
         )
         .await
     {
@@ -132,6 +163,105 @@ pub async fn create_policy(
     }
 }
 
+/// POST /api/v1/policies/lint — compile and stress-test every regex in a policy
+///
+/// Nothing is persisted. Each pattern is compiled with the runtime size limit and
+/// run against adversarial inputs; patterns that fail to compile, need
+/// backtracking features, or exceed the time budget are reported per location.
+pub async fn lint_policy(
+    Extension(auth): Extension<AuthContext>,
+    Json(payload): Json<LintPolicyRequest>,
+) -> impl IntoResponse {
+    if auth.require_scope("policies:read").is_err() {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    const MAX_RULES_BYTES: usize = 64 * 1024; // same limit as create_policy
+    if payload.rules.to_string().len() > MAX_RULES_BYTES {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": format!("rules JSON exceeds maximum size of {}KB", MAX_RULES_BYTES / 1024) })),
+        )
+            .into_response();
+    }
+
+    let rules: Vec<crate::models::policy::Rule> = match serde_json::from_value(payload.rules) {
+        Ok(r) => r,
+        Err(e) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "error": format!("invalid rules: {}", e) })),
+            )
+                .into_response();
+        }
+    };
+
+    let budget_ms = payload
+        .budget_ms
+        .unwrap_or(crate::middleware::guardrail::DEFAULT_LINT_BUDGET.as_millis() as u64)
+        .clamp(1, 1000);
+    let patterns = tokio::task::spawn_blocking(move || {
+        crate::middleware::guardrail::lint_rules(
+            &rules,
+            std::time::Duration::from_millis(budget_ms),
+        )
+    })
+    .await
+    .unwrap_or_default();
+
+    Json(LintPolicyResponse {
+        valid: patterns.iter().all(|p| p.is_ok()),
+        budget_ms,
+        patterns,
+    })
+    .into_response()
+}
+
+/// First invalid `custom_regexes` entry of a redact action anywhere in `rules`
+/// (including nested `else`/branch actions), described for the API error.
+fn custom_regex_error(rules: &serde_json::Value) -> Option<String> {
+    match rules {
+        serde_json::Value::Array(items) => items.iter().find_map(custom_regex_error),
+        serde_json::Value::Object(obj) => {
+            if let Some(serde_json::Value::Array(entries)) = obj.get("custom_regexes") {
+                for entry in entries {
+                    let name = entry.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                    if name.is_empty() {
+                        return Some("custom_regexes entries need a non-empty name".to_string());
+                    }
+                    let pattern = entry.get("pattern").and_then(|p| p.as_str()).unwrap_or("");
+                    if let Err(e) = crate::middleware::redact::validate_custom_regex(pattern) {
+                        return Some(format!("custom regex '{}' rejected: {}", name, e));
+                    }
+                }
+            }
+            obj.values().find_map(custom_regex_error)
+        }
+        _ => None,
+    }
+}
+
+/// A split variant `credential_id` in `rules` that isn't one of the
+/// project's credentials, if any.
+async fn foreign_split_credential(
+    state: &AppState,
+    project_id: Uuid,
+    rules: &serde_json::Value,
+) -> anyhow::Result<Option<Uuid>> {
+    let ids = crate::proxy::split::variant_credential_ids(rules);
+    if ids.is_empty() {
+        return Ok(None);
+    }
+    let owned: std::collections::HashSet<Uuid> = state
+        .db
+        .list_credentials(project_id)
+        .await?
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    Ok(ids.into_iter().find(|id| !owned.contains(id)))
+}
+
 /// PUT /api/v1/policies/:id — update a policy
 pub async fn update_policy(
     State(state): State<Arc<AppState>>,
@@ -159,6 +289,23 @@ pub async fn update_policy(
         }
     }
 
+    if let Some(error) = payload.rules.as_ref().and_then(custom_regex_error) {
+        tracing::warn!("update_policy: {}", error);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    if let Some(ref rules) = payload.rules {
+        let foreign = foreign_split_credential(&state, project_id, rules)
+            .await
+            .map_err(|e| {
+                tracing::error!("update_policy: credential lookup failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if let Some(id) = foreign {
+            tracing::warn!("update_policy: split credential {} not in project", id);
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
     let updated = state
         .db
         .update_policy(
@@ -169,6 +316,7 @@ pub async fn update_policy(
             payload.rules,
             payload.retry,
             payload.name.as_deref(),
+            payload.priority,
             None, // No optimistic locking for this API endpoint
         )
         .await
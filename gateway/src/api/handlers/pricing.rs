@@ -1,12 +1,14 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     Extension, Json,
 };
 
-use super::dtos::{PricingEntryResponse, UpsertPricingRequest};
+use super::dtos::{
+    PricingEntryResponse, PricingImportQuery, PricingImportResponse, UpsertPricingRequest,
+};
 use crate::api::AuthContext;
 use crate::AppState;
 
@@ -30,6 +32,7 @@ pub async fn list_pricing(
             model_pattern: r.model_pattern,
             input_per_m: r.input_per_m,
             output_per_m: r.output_per_m,
+            image_per_unit: r.image_per_unit,
             is_active: r.is_active,
             created_at: r.created_at,
             updated_at: r.updated_at,
@@ -72,6 +75,7 @@ pub async fn upsert_pricing(
             &payload.model_pattern,
             payload.input_per_m,
             payload.output_per_m,
+            payload.image_per_unit,
         )
         .await
         .map_err(|e| {
@@ -80,21 +84,7 @@ pub async fn upsert_pricing(
         })?;
 
     // Reload cache so cost calculations pick up the change immediately
-    match state.db.list_model_pricing().await {
-        Ok(rows) => {
-            let entries = rows
-                .into_iter()
-                .map(|r| crate::models::pricing_cache::PricingEntry {
-                    provider: r.provider,
-                    model_pattern: r.model_pattern,
-                    input_per_m: r.input_per_m,
-                    output_per_m: r.output_per_m,
-                })
-                .collect();
-            state.pricing.reload(entries).await;
-        }
-        Err(e) => tracing::warn!("Failed to reload pricing cache after upsert: {}", e),
-    }
+    reload_pricing_cache(&state, "upsert").await;
 
     Ok(Json(serde_json::json!({ "success": true })))
 }
@@ -116,22 +106,88 @@ pub async fn delete_pricing(
 
     if deleted {
         // Reload cache so cost calculations pick up the change immediately
-        match state.db.list_model_pricing().await {
-            Ok(rows) => {
-                let entries = rows
-                    .into_iter()
-                    .map(|r| crate::models::pricing_cache::PricingEntry {
-                        provider: r.provider,
-                        model_pattern: r.model_pattern,
-                        input_per_m: r.input_per_m,
-                        output_per_m: r.output_per_m,
-                    })
-                    .collect();
-                state.pricing.reload(entries).await;
-            }
-            Err(e) => tracing::warn!("Failed to reload pricing cache after delete: {}", e),
-        }
+        reload_pricing_cache(&state, "delete").await;
     }
 
     Ok(Json(serde_json::json!({ "id": id, "deleted": deleted })))
 }
+
+/// POST /api/v1/pricing/import — bulk upsert from a CSV or JSON pricing sheet.
+///
+/// The import is all-or-nothing: if any row fails validation nothing is
+/// written and the response (422) carries the per-row errors.
+pub async fn import_pricing(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<PricingImportQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<PricingImportResponse>), StatusCode> {
+    auth.require_role("admin")?;
+    auth.require_scope("pricing:write")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+
+    let is_csv = match query.format.as_deref() {
+        Some("csv") => true,
+        Some("json") => false,
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+        None => headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/csv")),
+    };
+
+    let (rows, mut results) = crate::models::pricing_import::parse(&body, is_csv).map_err(|e| {
+        tracing::warn!("import_pricing: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let errors = results.iter().filter(|r| r.error.is_some()).count();
+    if errors > 0 || query.dry_run {
+        let status = if errors > 0 {
+            StatusCode::UNPROCESSABLE_ENTITY
+        } else {
+            StatusCode::OK
+        };
+        return Ok((
+            status,
+            Json(PricingImportResponse::new(query.dry_run, false, results)),
+        ));
+    }
+
+    let created = state.db.import_model_pricing(&rows).await.map_err(|e| {
+        tracing::error!("import_pricing failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    // Every row is valid here, so results and created line up one-to-one.
+    for (result, was_created) in results.iter_mut().zip(created) {
+        result.status = if was_created { "created" } else { "updated" };
+    }
+
+    reload_pricing_cache(&state, "import").await;
+
+    Ok((
+        StatusCode::OK,
+        Json(PricingImportResponse::new(false, true, results)),
+    ))
+}
+
+/// Refresh the in-memory pricing cache from the database.
+async fn reload_pricing_cache(state: &AppState, after: &str) {
+    match state.db.list_model_pricing().await {
+        Ok(rows) => {
+            let entries = rows
+                .into_iter()
+                .map(|r| crate::models::pricing_cache::PricingEntry {
+                    provider: r.provider,
+                    model_pattern: r.model_pattern,
+                    input_per_m: r.input_per_m,
+                    output_per_m: r.output_per_m,
+                    image_per_unit: r.image_per_unit,
+                })
+                .collect();
+            state.pricing.reload(entries).await;
+        }
+        Err(e) => tracing::warn!("Failed to reload pricing cache after {}: {}", after, e),
+    }
+}
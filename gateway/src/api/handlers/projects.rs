@@ -7,7 +7,7 @@ use axum::{
 };
 use uuid::Uuid;
 
-use super::dtos::{CreateProjectRequest, ProjectResponse};
+use super::dtos::{CreateProjectRequest, ProjectResponse, UpdateProjectRequest};
 use crate::api::{ApiKeyRole, AuthContext};
 use crate::AppState;
 
@@ -28,6 +28,7 @@ pub async fn list_projects(
             .map(|p| ProjectResponse {
                 id: p.id,
                 name: p.name,
+                default_policy_ids: p.default_policy_ids,
             })
             .collect(),
     ))
@@ -56,37 +57,75 @@ pub async fn create_project(
         Json(ProjectResponse {
             id,
             name: payload.name,
+            default_policy_ids: vec![],
         }),
     ))
 }
 
-/// PUT /api/v1/projects/:id — rename a project
+/// PUT /api/v1/projects/:id — rename a project and/or set its default policies
 pub async fn update_project(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
     Path(id_str): Path<String>,
-    Json(payload): Json<CreateProjectRequest>, // Reuse struct since it just needs name
+    Json(payload): Json<UpdateProjectRequest>,
 ) -> Result<Json<ProjectResponse>, StatusCode> {
     auth.require_scope("projects:write")
         .map_err(|_| StatusCode::FORBIDDEN)?;
     let id = Uuid::parse_str(&id_str).map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let updated = state
+    let db_error = |e: anyhow::Error| {
+        tracing::error!("update_project failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    if state
         .db
-        .update_project(id, auth.org_id, &payload.name)
+        .get_project(id, auth.org_id)
         .await
-        .map_err(|e| {
-            tracing::error!("update_project failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    if !updated {
+        .map_err(db_error)?
+        .is_none()
+    {
         return Err(StatusCode::NOT_FOUND);
     }
 
+    if let Some(ref policy_ids) = payload.default_policy_ids {
+        // Defaults are copied into new tokens, so they must be live policies
+        // of this project. Duplicates are dropped.
+        let policy_ids = crate::store::postgres::merge_default_policy_ids(policy_ids, &[]);
+        let active = state
+            .db
+            .active_policy_ids_in_project(id, &policy_ids)
+            .await
+            .map_err(db_error)?;
+        if active.len() != policy_ids.len() {
+            tracing::warn!("update_project: default_policy_ids contains unknown policies");
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        state
+            .db
+            .set_project_default_policy_ids(id, auth.org_id, &policy_ids)
+            .await
+            .map_err(db_error)?;
+    }
+
+    if let Some(ref name) = payload.name {
+        state
+            .db
+            .update_project(id, auth.org_id, name)
+            .await
+            .map_err(db_error)?;
+    }
+
+    let project = state
+        .db
+        .get_project(id, auth.org_id)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
     Ok(Json(ProjectResponse {
         id,
-        name: payload.name,
+        name: project.name,
+        default_policy_ids: project.default_policy_ids,
     }))
 }
 
@@ -2,8 +2,10 @@ use std::sync::Arc;
 
 use axum::{extract::State, http::StatusCode, Extension, Json};
 
-use super::dtos::{RehydrateRequest, UpdateSettingsRequest};
+use super::dtos::{CacheWarmRequest, RehydrateRequest, UpdateSettingsRequest};
+use super::helpers::verify_token_ownership;
 use crate::api::AuthContext;
+use crate::proxy::cache_warm::{self, HotKey, WarmRequest};
 use crate::AppState;
 
 pub async fn get_settings(
@@ -37,6 +39,10 @@ pub async fn update_settings(
         "enable_response_cache",
         "enable_guardrails",
         "slack_webhook_url",
+        crate::models::model_remap::SETTING_KEY,
+        crate::models::tokenizer::SETTING_KEY,
+        crate::models::capabilities::SETTING_KEY,
+        crate::middleware::guardrail::category::SETTING_KEY,
     ];
 
     for key in payload.settings.keys() {
@@ -45,6 +51,51 @@ pub async fn update_settings(
             return Err(StatusCode::UNPROCESSABLE_ENTITY);
         }
     }
+    if let Some(map) = payload
+        .settings
+        .get(crate::models::model_remap::SETTING_KEY)
+    {
+        if let Err(e) = crate::models::model_remap::parse_map(map) {
+            tracing::warn!("update_settings: invalid deprecated_model_map: {}", e);
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+    if let Some(table) = payload.settings.get(crate::models::tokenizer::SETTING_KEY) {
+        if let Err(e) = crate::models::tokenizer::parse_table(table) {
+            tracing::warn!("update_settings: invalid model_context_windows: {}", e);
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+    if let Some(table) = payload
+        .settings
+        .get(crate::models::capabilities::SETTING_KEY)
+    {
+        if let Err(e) = crate::models::capabilities::parse_table(table) {
+            tracing::warn!("update_settings: invalid model_capabilities: {}", e);
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+    if let Some(rules) = payload
+        .settings
+        .get(crate::middleware::guardrail::category::SETTING_KEY)
+    {
+        if let Err(e) = crate::middleware::guardrail::category::CategoryRules::parse(rules) {
+            tracing::warn!("update_settings: invalid content_category_rules: {}", e);
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+    let remap_changed = payload
+        .settings
+        .contains_key(crate::models::model_remap::SETTING_KEY);
+    let context_windows_changed = payload
+        .settings
+        .contains_key(crate::models::tokenizer::SETTING_KEY);
+    let capabilities_changed = payload
+        .settings
+        .contains_key(crate::models::capabilities::SETTING_KEY);
+    let categories_changed = payload
+        .settings
+        .contains_key(crate::middleware::guardrail::category::SETTING_KEY);
 
     for (key, value) in payload.settings {
         state
@@ -57,9 +108,64 @@ pub async fn update_settings(
             })?;
     }
 
+    // Apply the remap, context-window, capability and category tables immediately on this replica;
+    // others pick them up on their next periodic reload.
+    if remap_changed {
+        state.model_remap.reload(&state.db).await;
+    }
+    if context_windows_changed {
+        state.context_windows.reload(&state.db).await;
+    }
+    if capabilities_changed {
+        state.capabilities.reload(&state.db).await;
+    }
+    if categories_changed {
+        state.content_categories.reload(&state.db).await;
+    }
+
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
+/// `GET /api/v1/system/config` — the configuration this replica actually
+/// loaded, with secrets redacted. SuperAdmin only.
+pub async fn get_effective_config(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    auth.require_role("superadmin")?;
+
+    let environment = std::env::var("TRUEFLOW_ENV")
+        .or_else(|_| std::env::var("RUST_ENV"))
+        .unwrap_or_else(|_| "development".into());
+    // Mirrors the CorsLayer set up in main.rs
+    let cors_production = std::env::var("TRUEFLOW_ENV")
+        .map(|v| v == "production")
+        .unwrap_or(false);
+    let dashboard_origin =
+        std::env::var("DASHBOARD_ORIGIN").unwrap_or_else(|_| "http://localhost:3000".into());
+    let mut features = Vec::new();
+    if cfg!(feature = "kafka") {
+        features.push("kafka");
+    }
+    if cfg!(feature = "test-hooks") {
+        features.push("test-hooks");
+    }
+
+    Ok(Json(serde_json::json!({
+        "config": state.config.redacted(),
+        "runtime": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "environment": environment,
+            "cors": {
+                "dashboard_origin": dashboard_origin,
+                "allow_localhost": !cors_production,
+            },
+            "vault_backend": state.config.vault_backend,
+            "features": features,
+        },
+    })))
+}
+
 pub async fn get_cache_stats(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
@@ -153,6 +259,24 @@ pub async fn get_cache_stats(
         keys.len() as u64
     };
 
+    // Token ids are bearer secrets and the limiter covers every project on
+    // the replica: report only the caller's tokens, by name and masked id.
+    let active = state.streams.active_by_token();
+    let ids: Vec<String> = active.keys().cloned().collect();
+    let names = if ids.is_empty() {
+        Vec::new()
+    } else {
+        state
+            .db
+            .get_token_names_in_project(auth.default_project_id(), &ids)
+            .await
+            .map_err(|e| {
+                tracing::error!("get_cache_stats token lookup failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+    };
+    let active_by_token = streams_by_token(&active, &names);
+
     Ok(Json(serde_json::json!({
         "cache_key_count": key_count,
         "estimated_size_bytes": total_bytes,
@@ -166,9 +290,41 @@ pub async fn get_cache_stats(
             "rate_limits": rl_count,
         },
         "sample_entries": sample_keys,
+        "streaming": {
+            "active_streams": state.streams.active(),
+            "max_concurrent_streams": state.config.max_concurrent_streams,
+            "active_by_token": active_by_token,
+        },
     })))
 }
 
+/// Active stream counts for the tokens in `names` (id, name), with each id
+/// masked to its prefix and last four characters.
+fn streams_by_token(
+    active: &std::collections::HashMap<String, u64>,
+    names: &[(String, String)],
+) -> Vec<serde_json::Value> {
+    names
+        .iter()
+        .filter_map(|(id, name)| {
+            let count = *active.get(id)?;
+            Some(serde_json::json!({
+                "token_id": mask_token_id(id),
+                "name": name,
+                "active_streams": count,
+            }))
+        })
+        .collect()
+}
+
+fn mask_token_id(id: &str) -> String {
+    if id.len() > 12 && id.is_ascii() {
+        format!("{}…{}", &id[..6], &id[id.len() - 4..])
+    } else {
+        "****".to_string()
+    }
+}
+
 pub async fn flush_cache(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
@@ -227,6 +383,139 @@ pub async fn flush_cache(
     })))
 }
 
+// ── Cache Warming ───────────────────────────────────────────────────────────
+
+/// Most requests or hot keys warmed per call.
+const MAX_WARM_REQUESTS: usize = 100;
+
+/// Hot keys whose stored request belongs to the caller's project.
+async fn project_hot_keys(
+    state: &Arc<AppState>,
+    auth: &AuthContext,
+    threshold: u64,
+) -> Result<Vec<(HotKey, WarmRequest)>, StatusCode> {
+    let hot = cache_warm::hot_keys(&state.cache, threshold, MAX_WARM_REQUESTS * 10)
+        .await
+        .map_err(|e| {
+            tracing::error!("cache_warm: failed to read hot keys: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let mut out = Vec::new();
+    for key in hot {
+        let Some(request) = cache_warm::stored_request(&state.cache, &key.cache_key).await else {
+            continue;
+        };
+        if verify_token_ownership(state, &request.token_id, auth)
+            .await
+            .is_ok()
+        {
+            out.push((key, request));
+            if out.len() >= MAX_WARM_REQUESTS {
+                break;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// GET /api/v1/system/cache-warm/candidates — hot cache keys (most-missed
+/// first) with the request that would be replayed to warm them.
+pub async fn list_cache_warm_candidates(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    auth.require_role("admin")?;
+    let threshold = state.config.cache_warm_threshold;
+    if threshold == 0 {
+        return Ok(Json(
+            serde_json::json!({ "enabled": false, "candidates": [] }),
+        ));
+    }
+    let candidates: Vec<serde_json::Value> = project_hot_keys(&state, &auth, threshold)
+        .await?
+        .into_iter()
+        .map(|(hot, request)| {
+            serde_json::json!({
+                "cache_key": hot.cache_key,
+                "misses": hot.misses,
+                "token_id": request.token_id,
+                "path": request.path,
+                "model": request.body.get("model"),
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({
+        "enabled": true,
+        "threshold": threshold,
+        "auto_warm": state.config.cache_auto_warm,
+        "candidates": candidates,
+    })))
+}
+
+/// POST /api/v1/system/cache-warm — pre-populate the response cache.
+///
+/// Each request is replayed through the proxy under `token_id`, so it is
+/// policy-checked, billed and audited like client traffic. Requests that are
+/// already cached or not cacheable are not sent upstream.
+pub async fn warm_cache(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Json(payload): Json<CacheWarmRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    auth.require_role("admin")?;
+
+    let mut results: Vec<serde_json::Value> = Vec::new();
+    if payload.hot {
+        if state.config.cache_warm_threshold == 0 {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        for (hot, request) in
+            project_hot_keys(&state, &auth, state.config.cache_warm_threshold).await?
+        {
+            let outcome = cache_warm::warm_request(&state, &request).await;
+            let mut entry = serde_json::to_value(&outcome).unwrap_or_default();
+            entry["cache_key"] = serde_json::json!(hot.cache_key);
+            entry["misses"] = serde_json::json!(hot.misses);
+            results.push(entry);
+        }
+    } else {
+        let token_id = payload
+            .token_id
+            .as_deref()
+            .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+        if payload.requests.is_empty() || payload.requests.len() > MAX_WARM_REQUESTS {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        verify_token_ownership(&state, token_id, &auth).await?;
+        let path = payload
+            .path
+            .unwrap_or_else(|| "/v1/chat/completions".to_string());
+        for (index, body) in payload.requests.into_iter().enumerate() {
+            let request = WarmRequest {
+                token_id: token_id.to_string(),
+                path: path.clone(),
+                body,
+            };
+            let outcome = cache_warm::warm_request(&state, &request).await;
+            let mut entry = serde_json::to_value(&outcome).unwrap_or_default();
+            entry["index"] = serde_json::json!(index);
+            results.push(entry);
+        }
+    }
+
+    let warmed = results.iter().filter(|r| r["outcome"] == "warmed").count();
+    tracing::info!(
+        user_id = %auth.user_id.unwrap_or_default(),
+        warmed,
+        total = results.len(),
+        "response cache warmed"
+    );
+    Ok(Json(serde_json::json!({
+        "warmed": warmed,
+        "results": results,
+    })))
+}
+
 // ── PII Tokenization Vault ──────────────────────────────────────────────────
 
 /// POST /api/v1/pii/rehydrate — reverse PII tokens back to original values.
@@ -390,3 +679,29 @@ pub async fn get_anomaly_events(
         "sigma_threshold": config.sigma_threshold,
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_stream_stats_only_list_project_tokens_masked() {
+        let active = HashMap::from([
+            ("tf_v1_mine_0123456789abcdef".to_string(), 2),
+            ("tf_v1_other_tenant_secret".to_string(), 5),
+        ]);
+        let names = vec![(
+            "tf_v1_mine_0123456789abcdef".to_string(),
+            "ci-agent".to_string(),
+        )];
+        let out = streams_by_token(&active, &names);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0]["token_id"], "tf_v1_…cdef");
+        assert_eq!(out[0]["name"], "ci-agent");
+        assert_eq!(out[0]["active_streams"], 2);
+        let text = serde_json::to_string(&out).unwrap();
+        assert!(!text.contains("0123456789"));
+        assert!(!text.contains("other_tenant"));
+    }
+}
@@ -6,7 +6,7 @@ use axum::{
     Extension, Json,
 };
 
-use super::dtos::UpsertSpendCapRequest;
+use super::dtos::{SimulateSpendCapRequest, UpsertSpendCapRequest};
 use super::helpers::verify_token_ownership;
 use crate::api::AuthContext;
 use crate::AppState;
@@ -73,6 +73,55 @@ pub async fn upsert_spend_cap(
     })
 }
 
+/// POST /api/v1/tokens/:id/spend/simulate — replay historical spend against
+/// proposed caps without changing anything
+pub async fn simulate_spend_cap(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(token_id): Path<String>,
+    Json(payload): Json<SimulateSpendCapRequest>,
+) -> Result<Json<crate::middleware::spend::SpendSimulation>, StatusCode> {
+    auth.require_scope("tokens:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    verify_token_ownership(&state, &token_id, &auth).await?;
+
+    let caps = crate::middleware::spend::SpendCap {
+        daily_limit_usd: payload.daily_limit_usd,
+        monthly_limit_usd: payload.monthly_limit_usd,
+        lifetime_limit_usd: payload.lifetime_limit_usd,
+    };
+    let limits = [
+        caps.daily_limit_usd,
+        caps.monthly_limit_usd,
+        caps.lifetime_limit_usd,
+    ];
+    if limits.iter().all(Option::is_none)
+        || limits.iter().flatten().any(|l| !l.is_finite() || *l <= 0.0)
+    {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let lookback_days = payload.lookback_days.unwrap_or(30);
+    if !(1..=365).contains(&lookback_days) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let hourly = state
+        .db
+        .get_token_hourly_spend(&token_id, lookback_days as i32)
+        .await
+        .map_err(|e| {
+            tracing::error!("simulate_spend_cap failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(crate::middleware::spend::simulate_spend_caps(
+        &caps,
+        &hourly,
+        lookback_days,
+        chrono::Utc::now(),
+    )))
+}
+
 /// DELETE /api/v1/tokens/:id/spend/:period — remove a spend cap
 pub async fn delete_spend_cap(
     State(state): State<Arc<AppState>>,
@@ -0,0 +1,408 @@
+//! Upstream stream diagnostics.
+//!
+//! Sends a tiny streaming completion through a stored credential and reports
+//! whether the upstream actually streams. Reverse proxies that buffer SSE
+//! deliver every event in one burst at the end, which silently turns the
+//! streaming fast path into a slow, all-at-once response.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::helpers::verify_project_ownership;
+use crate::api::AuthContext;
+use crate::proxy::model_router::{self, Provider};
+use crate::store::postgres::TokenRow;
+use crate::AppState;
+
+/// Whole-test deadline, including connection setup.
+const STREAM_TEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Stop reading after this many bytes; a short completion never gets close.
+const MAX_STREAM_BYTES: usize = 256 * 1024;
+/// Events spread over less than this are treated as one burst.
+const MIN_INCREMENTAL_SPREAD_MS: u64 = 10;
+
+#[derive(Deserialize)]
+pub struct StreamTestRequest {
+    pub credential_id: Uuid,
+    /// Upstream base URL. Must already be paired with the credential on one of
+    /// the project's tokens, so a stored key is never sent somewhere new.
+    pub upstream_url: String,
+    pub model: String,
+    pub project_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamTestReport {
+    pub upstream_url: String,
+    pub provider: String,
+    pub model: String,
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    /// "streaming", "buffered", "not_streaming" or "upstream_error".
+    pub verdict: &'static str,
+    /// Time from sending the request to the first SSE event.
+    pub ttft_ms: Option<u64>,
+    pub total_ms: u64,
+    /// Network reads that delivered body bytes.
+    pub chunk_count: u32,
+    /// SSE `data:` events received (excluding `[DONE]`).
+    pub event_count: u32,
+    /// Time between the first and last SSE event.
+    pub event_spread_ms: u64,
+    pub incremental: bool,
+    pub error: Option<String>,
+}
+
+/// POST /api/v1/health/upstreams/stream-test — measure whether an upstream
+/// streams incrementally, using a stored credential.
+pub async fn stream_test_upstream(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Json(payload): Json<StreamTestRequest>,
+) -> Result<Json<StreamTestReport>, StatusCode> {
+    auth.require_role("admin")?;
+    auth.require_scope("credentials:write")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    let project_id = payload
+        .project_id
+        .unwrap_or_else(|| auth.default_project_id());
+    verify_project_ownership(&state, auth.org_id, project_id).await?;
+
+    let upstream_url = payload.upstream_url.trim().to_string();
+    if payload.model.trim().is_empty() || reqwest::Url::parse(&upstream_url).is_err() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    // The credential must belong to this project...
+    let creds = state.db.list_credentials(project_id).await.map_err(|e| {
+        tracing::error!("stream_test: list_credentials failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !creds
+        .iter()
+        .any(|c| c.id == payload.credential_id && c.is_active)
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // ...and already be routed to this upstream by one of its tokens.
+    let tokens = state
+        .db
+        .list_tokens(project_id, 1000, 0)
+        .await
+        .map_err(|e| {
+            tracing::error!("stream_test: list_tokens failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !upstream_bound_to_credential(&tokens, payload.credential_id, &upstream_url) {
+        tracing::warn!(
+            credential_id = %payload.credential_id,
+            upstream = %upstream_url,
+            "stream_test: upstream is not configured for this credential"
+        );
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let (key, _provider, mode, header) = state
+        .secrets
+        .retrieve(&payload.credential_id.to_string())
+        .await
+        .map_err(|e| {
+            tracing::error!("stream_test: credential lookup failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if mode == "sigv4" {
+        // SigV4 signing lives in the proxy path; not worth duplicating here.
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let provider = model_router::detect_provider(&payload.model, &upstream_url);
+    let body = serde_json::json!({
+        "model": payload.model,
+        "messages": [{ "role": "user", "content": "Count from 1 to 20, one number per line." }],
+        "max_tokens": 64,
+        "stream": true,
+    });
+    let body = model_router::translate_request(provider, &body).unwrap_or(body);
+    let mut url = model_router::rewrite_upstream_url(provider, &upstream_url, &payload.model, true);
+    if provider == Provider::Gemini && !url.contains("alt=sse") {
+        url.push_str(if url.contains('?') {
+            "&alt=sse"
+        } else {
+            "?alt=sse"
+        });
+    }
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        reqwest::header::HeaderValue::from_static("application/json"),
+    );
+    inject_credential(&mut headers, &mut url, &key, &mode, &header)?;
+    model_router::inject_provider_headers(provider, &mut headers, true);
+
+    let body_bytes = serde_json::to_vec(&body).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut report = StreamTestReport {
+        upstream_url: upstream_url.clone(),
+        provider: provider.as_str().to_string(),
+        model: payload.model.clone(),
+        status: None,
+        content_type: None,
+        verdict: "upstream_error",
+        ttft_ms: None,
+        total_ms: 0,
+        chunk_count: 0,
+        event_count: 0,
+        event_spread_ms: 0,
+        incremental: false,
+        error: None,
+    };
+
+    let start = Instant::now();
+    let run = async {
+        let resp = state
+            .upstream_client
+            .forward_raw(reqwest::Method::POST, &url, headers, body_bytes.into())
+            .await
+            .map_err(|e| e.to_string())?;
+        report.status = Some(resp.status().as_u16());
+        report.content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let is_error = !resp.status().is_success();
+
+        let mut probe = StreamProbe::default();
+        let mut raw = Vec::new();
+        let mut stream = resp.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| e.to_string())?;
+            probe.observe(start.elapsed().as_millis() as u64, &chunk);
+            if is_error && raw.len() < 512 {
+                raw.extend_from_slice(&chunk);
+            }
+            if probe.bytes >= MAX_STREAM_BYTES {
+                break;
+            }
+        }
+        if is_error {
+            return Err(String::from_utf8_lossy(&raw)
+                .chars()
+                .take(512)
+                .collect::<String>());
+        }
+        Ok(probe)
+    };
+
+    match tokio::time::timeout(STREAM_TEST_TIMEOUT, run).await {
+        Ok(Ok(probe)) => probe.fill_report(&mut report),
+        Ok(Err(e)) => report.error = Some(e.replace(key.as_str(), "[REDACTED]")),
+        Err(_) => {
+            report.error = Some(format!(
+                "timed out after {}s",
+                STREAM_TEST_TIMEOUT.as_secs()
+            ))
+        }
+    }
+    report.total_ms = start.elapsed().as_millis() as u64;
+
+    Ok(Json(report))
+}
+
+/// True if some active token sends `credential_id` to `upstream_url`, either as
+/// its primary upstream or as a weighted upstream target.
+fn upstream_bound_to_credential(
+    tokens: &[TokenRow],
+    credential_id: Uuid,
+    upstream_url: &str,
+) -> bool {
+    let want = upstream_url.trim_end_matches('/');
+    tokens.iter().any(|t| {
+        let primary =
+            t.credential_id == Some(credential_id) && t.upstream_url.trim_end_matches('/') == want;
+        primary
+            || crate::proxy::loadbalancer::parse_upstreams(t.upstreams.as_ref())
+                .iter()
+                .any(|u| {
+                    u.credential_id.or(t.credential_id) == Some(credential_id)
+                        && u.url.trim_end_matches('/') == want
+                })
+    })
+}
+
+/// Apply the credential the same way the proxy does for `mode`.
+fn inject_credential(
+    headers: &mut reqwest::header::HeaderMap,
+    url: &mut String,
+    key: &str,
+    mode: &str,
+    header: &str,
+) -> Result<(), StatusCode> {
+    use reqwest::header::{HeaderName, HeaderValue};
+    let value = match mode {
+        "query" => {
+            let sep = if url.contains('?') { '&' } else { '?' };
+            url.push(sep);
+            url.push_str(&format!("{}={}", header, urlencoding::encode(key)));
+            return Ok(());
+        }
+        "basic" => {
+            use base64::Engine;
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(key)
+            )
+        }
+        "header" => key.to_string(),
+        _ => format!("Bearer {}", key),
+    };
+    let name =
+        HeaderName::from_bytes(header.as_bytes()).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    let value = HeaderValue::from_str(&value).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    headers.insert(name, value);
+    Ok(())
+}
+
+/// Records when SSE events arrive relative to network reads.
+#[derive(Default)]
+struct StreamProbe {
+    bytes: usize,
+    reads: u32,
+    events: u32,
+    /// Reads that completed at least one event.
+    event_reads: u32,
+    first_event_ms: Option<u64>,
+    last_event_ms: Option<u64>,
+    /// Partial line carried over between reads.
+    pending: Vec<u8>,
+}
+
+impl StreamProbe {
+    fn observe(&mut self, elapsed_ms: u64, chunk: &[u8]) {
+        if chunk.is_empty() {
+            return;
+        }
+        self.bytes += chunk.len();
+        self.reads += 1;
+        self.pending.extend_from_slice(chunk);
+
+        let mut events_here = 0;
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                if data.trim() != "[DONE]" {
+                    events_here += 1;
+                }
+            }
+        }
+        if events_here > 0 {
+            self.events += events_here;
+            self.event_reads += 1;
+            self.first_event_ms.get_or_insert(elapsed_ms);
+            self.last_event_ms = Some(elapsed_ms);
+        }
+    }
+
+    fn spread_ms(&self) -> u64 {
+        match (self.first_event_ms, self.last_event_ms) {
+            (Some(first), Some(last)) => last.saturating_sub(first),
+            _ => 0,
+        }
+    }
+
+    /// Incremental = events arrived over several reads spaced out in time.
+    fn incremental(&self) -> bool {
+        self.event_reads >= 2 && self.spread_ms() >= MIN_INCREMENTAL_SPREAD_MS
+    }
+
+    fn fill_report(&self, report: &mut StreamTestReport) {
+        report.ttft_ms = self.first_event_ms;
+        report.chunk_count = self.reads;
+        report.event_count = self.events;
+        report.event_spread_ms = self.spread_ms();
+        report.incremental = self.incremental();
+        report.verdict = match (self.events, report.incremental) {
+            (0, _) => "not_streaming",
+            (_, true) => "streaming",
+            (_, false) => "buffered",
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_detects_incremental_stream() {
+        let mut probe = StreamProbe::default();
+        probe.observe(120, b"data: {\"n\":1}\n\n");
+        probe.observe(160, b"data: {\"n\":2}\n\ndata: {\"n\"");
+        probe.observe(200, b":3}\n\ndata: [DONE]\n\n");
+
+        assert_eq!(probe.events, 3);
+        assert_eq!(probe.first_event_ms, Some(120));
+        assert_eq!(probe.spread_ms(), 80);
+        assert!(probe.incremental());
+    }
+
+    #[test]
+    fn test_probe_flags_single_burst_as_buffered() {
+        let mut probe = StreamProbe::default();
+        let body = b"data: {\"n\":1}\n\ndata: {\"n\":2}\n\ndata: {\"n\":3}\n\ndata: [DONE]\n\n";
+        probe.observe(900, body);
+
+        let mut report = empty_report();
+        probe.fill_report(&mut report);
+        assert_eq!(report.event_count, 3);
+        assert!(!report.incremental);
+        assert_eq!(report.verdict, "buffered");
+    }
+
+    #[test]
+    fn test_probe_without_events_is_not_streaming() {
+        let mut probe = StreamProbe::default();
+        probe.observe(50, b"{\"choices\":[]}");
+
+        let mut report = empty_report();
+        probe.fill_report(&mut report);
+        assert_eq!(report.verdict, "not_streaming");
+        assert_eq!(report.ttft_ms, None);
+    }
+
+    #[test]
+    fn test_inject_credential_modes() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let mut url = "https://api.example.com/v1/chat/completions".to_string();
+        inject_credential(&mut headers, &mut url, "sk-1", "bearer", "Authorization").unwrap();
+        assert_eq!(headers["authorization"], "Bearer sk-1");
+
+        inject_credential(&mut headers, &mut url, "k 2", "query", "key").unwrap();
+        assert!(url.ends_with("?key=k%202"));
+    }
+
+    fn empty_report() -> StreamTestReport {
+        StreamTestReport {
+            upstream_url: String::new(),
+            provider: String::new(),
+            model: String::new(),
+            status: Some(200),
+            content_type: None,
+            verdict: "upstream_error",
+            ttft_ms: None,
+            total_ms: 0,
+            chunk_count: 0,
+            event_count: 0,
+            event_spread_ms: 0,
+            incremental: false,
+            error: None,
+        }
+    }
+}
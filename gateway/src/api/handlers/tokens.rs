@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use axum::{
@@ -7,12 +8,301 @@ use axum::{
 };
 use serde_json::json;
 
-use super::dtos::{CreateTokenRequest, CreateTokenResponse, PaginationParams};
+use super::dtos::{
+    BulkCreateTokensRequest, BulkCreateTokensResponse, BulkTokenResult, BulkTokenSpec,
+    CreateTokenRequest, CreateTokenResponse, PaginationParams, RotateTokenRequest,
+    RotateTokenResponse,
+};
 use super::helpers::{verify_project_ownership, verify_token_ownership};
 use crate::api::AuthContext;
-use crate::store::postgres::TokenRow;
+use crate::store::postgres::{NewToken, TokenRow};
 use crate::AppState;
 
+/// Longest grace window a rotation may leave the old token usable for.
+const MAX_ROTATION_GRACE_SECS: u64 = 30 * 24 * 3600;
+
+/// A fresh `tf_v1_<project prefix>_tok_<random>` token ID.
+fn generate_token_id(project_id: uuid::Uuid) -> String {
+    let proj_short = &project_id.to_string()[..8];
+    let mut random_bytes = [0u8; 16];
+    use aes_gcm::aead::OsRng;
+    use rand::RngCore;
+    OsRng.fill_bytes(&mut random_bytes);
+    format!("tf_v1_{}_tok_{}", proj_short, hex::encode(random_bytes))
+}
+
+/// Why a token-create payload was rejected: the status `create_token`
+/// answers with, and the reason reported per item by `create_tokens_bulk`.
+type SpecError = (StatusCode, &'static str);
+
+/// Check a token-create payload before anything is written.
+fn validate_create_token(payload: &CreateTokenRequest) -> Result<(), SpecError> {
+    // Validate upstream URL (SSRF protection — same as CLI)
+    let url = reqwest::Url::parse(&payload.upstream_url).map_err(|_| {
+        tracing::warn!(
+            "create_token: invalid upstream URL: {}",
+            payload.upstream_url
+        );
+        (StatusCode::BAD_REQUEST, "upstream_url is not a valid URL")
+    })?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "upstream_url must be http or https",
+        ));
+    }
+
+    // P1.4: Validate upstreams list if provided (weight > 0, no duplicate URLs)
+    if let Some(ref upstreams) = payload.upstreams {
+        // Check for zero/negative weights
+        for u in upstreams {
+            if u.weight == 0 {
+                return Err(invalid("upstreams weights must be positive"));
+            }
+        }
+        // Check for duplicate URLs
+        let urls: Vec<_> = upstreams.iter().map(|u| u.url.as_str()).collect();
+        let unique: HashSet<_> = urls.iter().copied().collect();
+        if unique.len() != urls.len() {
+            return Err(invalid("upstreams contains duplicate URLs"));
+        }
+    }
+
+    // Validate stream_flush shape if provided
+    if let Some(ref flush) = payload.stream_flush {
+        if serde_json::from_value::<crate::proxy::stream_bridge::StreamFlushConfig>(flush.clone())
+            .is_err()
+        {
+            return Err(invalid("invalid stream_flush"));
+        }
+    }
+
+    // param_defaults must be an object and may not set routing/framing keys
+    if let Some(ref defaults) = payload.param_defaults {
+        let Some(obj) = defaults.as_object() else {
+            return Err(invalid("param_defaults must be an object"));
+        };
+        if obj
+            .keys()
+            .any(|k| crate::proxy::transform::PARAM_DEFAULTS_RESERVED.contains(&k.as_str()))
+        {
+            return Err(invalid("param_defaults sets a reserved field"));
+        }
+    }
+
+    // strip_body_fields may not remove the fields every request needs
+    if let Some(ref fields) = payload.strip_body_fields {
+        if fields
+            .iter()
+            .any(|f| f.trim().is_empty() || f == "model" || f == "messages")
+        {
+            return Err(invalid("strip_body_fields may not strip model or messages"));
+        }
+    }
+
+    // cache_key_ignore_paths must be JSON pointers below the body root
+    if let Some(ref paths) = payload.cache_key_ignore_paths {
+        if !paths
+            .iter()
+            .all(|p| crate::proxy::response_cache::is_valid_ignore_path(p))
+        {
+            return Err(invalid("cache_key_ignore_paths must be JSON pointers"));
+        }
+    }
+
+    // forward_trace_headers must name valid, non-internal correlation headers
+    if let Some(ref names) = payload.forward_trace_headers {
+        if names.len() > crate::proxy::handler::MAX_FORWARD_TRACE_HEADERS
+            || !names
+                .iter()
+                .all(|n| crate::proxy::handler::is_forwardable_trace_header(n))
+        {
+            return Err(invalid("invalid forward_trace_headers"));
+        }
+    }
+
+    // budget_pressure_model_map must map model names to model names
+    if let Some(ref map) = payload.budget_pressure_model_map {
+        let Some(obj) = map.as_object() else {
+            return Err(invalid("budget_pressure_model_map must be an object"));
+        };
+        if obj.iter().any(|(from, to)| {
+            from.trim().is_empty() || to.as_str().is_none_or(|t| t.trim().is_empty())
+        }) {
+            return Err(invalid("budget_pressure_model_map must map model names"));
+        }
+    }
+    if payload
+        .budget_pressure_threshold_pct
+        .is_some_and(|p| !(1..=99).contains(&p))
+    {
+        return Err(invalid("budget_pressure_threshold_pct must be 1-99"));
+    }
+
+    // Reject provider hints the router can't act on
+    if let Some(ref hint) = payload.provider_hint {
+        if crate::proxy::model_router::Provider::from_name(hint).is_none() {
+            return Err(invalid("unknown provider_hint"));
+        }
+    }
+
+    if payload
+        .replay_window_secs
+        .is_some_and(|w| !(1..=crate::middleware::replay::MAX_REPLAY_WINDOW_SECS).contains(&w))
+    {
+        return Err(invalid("replay_window_secs out of range"));
+    }
+
+    if payload.request_budget_secs.is_some_and(|b| b <= 0) {
+        return Err(invalid("request_budget_secs must be positive"));
+    }
+
+    if payload.max_output_tokens_ceiling.is_some_and(|c| c <= 0) {
+        return Err(invalid("max_output_tokens_ceiling must be positive"));
+    }
+
+    if payload.max_concurrent_streams.is_some_and(|c| c <= 0) {
+        return Err(invalid("max_concurrent_streams must be positive"));
+    }
+
+    if payload
+        .max_cost_per_request_usd
+        .is_some_and(|c| c <= rust_decimal::Decimal::ZERO)
+    {
+        return Err(invalid("max_cost_per_request_usd must be positive"));
+    }
+
+    if let Some(ref cfg) = payload.adaptive_rate_limit {
+        if let Err(e) = crate::proxy::adaptive_limit::AdaptiveRateLimitConfig::from_value(cfg) {
+            tracing::warn!("create_token: invalid adaptive_rate_limit: {}", e);
+            return Err(invalid("invalid adaptive_rate_limit"));
+        }
+    }
+
+    if let Some(ref cfg) = payload.migration {
+        if let Err(e) = crate::proxy::migration::MigrationConfig::from_value(cfg) {
+            tracing::warn!("create_token: invalid migration: {}", e);
+            return Err(invalid("invalid migration"));
+        }
+    }
+
+    if payload
+        .enforcement_order
+        .as_deref()
+        .is_some_and(|o| !crate::middleware::spend::ENFORCEMENT_ORDERS.contains(&o))
+    {
+        return Err(invalid("unknown enforcement_order"));
+    }
+
+    if payload
+        .stream_output_format
+        .as_deref()
+        .is_some_and(|f| !crate::proxy::stream_bridge::STREAM_OUTPUT_FORMATS.contains(&f))
+    {
+        return Err(invalid("unknown stream_output_format"));
+    }
+
+    if payload
+        .context_window_action
+        .as_deref()
+        .is_some_and(|a| !crate::middleware::context_window::ACTIONS.contains(&a))
+    {
+        return Err(invalid("unknown context_window_action"));
+    }
+
+    if payload
+        .capability_action
+        .as_deref()
+        .is_some_and(|a| !crate::middleware::capability::ACTIONS.contains(&a))
+    {
+        return Err(invalid("unknown capability_action"));
+    }
+
+    // test_upstream_override must be an http(s) URL (honored only when the
+    // gateway allows test overrides)
+    if let Some(ref override_url) = payload.test_upstream_override {
+        let ok = reqwest::Url::parse(override_url)
+            .is_ok_and(|u| u.scheme() == "http" || u.scheme() == "https");
+        if !ok {
+            return Err(invalid("test_upstream_override must be an http(s) URL"));
+        }
+    }
+
+    Ok(())
+}
+
+fn invalid(reason: &'static str) -> SpecError {
+    (StatusCode::UNPROCESSABLE_ENTITY, reason)
+}
+
+/// The policies a new token is bound to: the requested ones, plus the
+/// project's defaults unless `skip_defaults` is set.
+async fn resolve_policy_ids(
+    state: &AppState,
+    project_id: uuid::Uuid,
+    payload: &CreateTokenRequest,
+) -> anyhow::Result<Vec<uuid::Uuid>> {
+    let explicit_policy_ids = payload.policy_ids.clone().unwrap_or_default();
+    if payload.skip_defaults {
+        return Ok(explicit_policy_ids);
+    }
+    let defaults = state.db.get_project_default_policy_ids(project_id).await?;
+    Ok(crate::store::postgres::merge_default_policy_ids(
+        &defaults,
+        &explicit_policy_ids,
+    ))
+}
+
+/// The row to insert for a validated token-create payload.
+fn new_token(
+    token_id: String,
+    project_id: uuid::Uuid,
+    policy_ids: Vec<uuid::Uuid>,
+    payload: CreateTokenRequest,
+) -> NewToken {
+    let resolved_log_level = payload.resolved_log_level();
+    NewToken {
+        id: token_id,
+        project_id,
+        name: payload.name,
+        credential_id: payload.credential_id,
+        upstream_url: payload.upstream_url,
+        scopes: serde_json::json!([]),
+        policy_ids,
+        log_level: resolved_log_level,
+        circuit_breaker: payload.circuit_breaker,
+        allowed_models: payload.allowed_models,
+        team_id: payload.team_id,
+        tags: payload.tags,
+        mcp_allowed_tools: payload.mcp_allowed_tools,
+        mcp_blocked_tools: payload.mcp_blocked_tools,
+        stream_flush: payload.stream_flush,
+        provider_hint: payload.provider_hint,
+        request_budget_secs: payload.request_budget_secs,
+        param_defaults: payload.param_defaults,
+        session_cost_header: payload.session_cost_header,
+        strip_body_fields: payload.strip_body_fields,
+        budget_pressure_model_map: payload.budget_pressure_model_map,
+        budget_pressure_threshold_pct: payload.budget_pressure_threshold_pct,
+        stream_ttft_comment: payload.stream_ttft_comment,
+        test_upstream_override: payload.test_upstream_override,
+        context_window_action: payload.context_window_action,
+        enforcement_order: payload.enforcement_order,
+        forward_trace_headers: payload.forward_trace_headers,
+        adaptive_rate_limit: payload.adaptive_rate_limit,
+        migration: payload.migration,
+        max_output_tokens_ceiling: payload.max_output_tokens_ceiling,
+        stream_output_format: payload.stream_output_format,
+        serve_stale_on_error: payload.serve_stale_on_error,
+        max_concurrent_streams: payload.max_concurrent_streams,
+        max_cost_per_request_usd: payload.max_cost_per_request_usd,
+        cache_key_ignore_paths: payload.cache_key_ignore_paths,
+        json_mode_fallback: payload.json_mode_fallback,
+        replay_window_secs: payload.replay_window_secs,
+        capability_action: payload.capability_action,
+    }
+}
+
 pub async fn list_tokens(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
@@ -54,59 +344,17 @@ pub async fn create_token(
         .unwrap_or_else(|| auth.default_project_id());
     verify_project_ownership(&state, auth.org_id, project_id).await?;
 
-    // Validate upstream URL (SSRF protection — same as CLI)
-    let url = reqwest::Url::parse(&payload.upstream_url).map_err(|_| {
-        tracing::warn!(
-            "create_token: invalid upstream URL: {}",
-            payload.upstream_url
-        );
-        StatusCode::BAD_REQUEST
-    })?;
-    if url.scheme() != "http" && url.scheme() != "https" {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
-    // P1.4: Validate upstreams list if provided (weight > 0, no duplicate URLs)
-    if let Some(ref upstreams) = payload.upstreams {
-        // Check for zero/negative weights
-        for u in upstreams {
-            if u.weight == 0 {
-                return Err(StatusCode::UNPROCESSABLE_ENTITY);
-            }
-        }
-        // Check for duplicate URLs
-        let urls: Vec<_> = upstreams.iter().map(|u| u.url.as_str()).collect();
-        let unique: std::collections::HashSet<_> = urls.iter().copied().collect();
-        if unique.len() != urls.len() {
-            return Err(StatusCode::UNPROCESSABLE_ENTITY);
-        }
-    }
+    validate_create_token(&payload).map_err(|(status, _)| status)?;
 
-    // Generate token ID
-    let proj_short = &project_id.to_string()[..8];
-    let mut random_bytes = [0u8; 16];
-    use aes_gcm::aead::OsRng;
-    use rand::RngCore;
-    OsRng.fill_bytes(&mut random_bytes);
-    let token_id = format!("tf_v1_{}_tok_{}", proj_short, hex::encode(random_bytes));
+    let policy_ids = resolve_policy_ids(&state, project_id, &payload)
+        .await
+        .map_err(|e| {
+            tracing::error!("create_token: loading project default policies: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
-    let resolved_log_level = payload.resolved_log_level();
-    let new_token = crate::store::postgres::NewToken {
-        id: token_id.clone(),
-        project_id,
-        name: payload.name.clone(),
-        credential_id: payload.credential_id,
-        upstream_url: payload.upstream_url,
-        scopes: serde_json::json!([]),
-        policy_ids: payload.policy_ids.unwrap_or_default(),
-        log_level: resolved_log_level,
-        circuit_breaker: payload.circuit_breaker,
-        allowed_models: payload.allowed_models,
-        team_id: payload.team_id,
-        tags: payload.tags,
-        mcp_allowed_tools: payload.mcp_allowed_tools,
-        mcp_blocked_tools: payload.mcp_blocked_tools,
-    };
+    let token_id = generate_token_id(project_id);
+    let new_token = new_token(token_id.clone(), project_id, policy_ids, payload);
 
     state.db.insert_token(&new_token).await.map_err(|e| {
         tracing::error!("create_token failed: {}", e);
@@ -117,12 +365,280 @@ pub async fn create_token(
         StatusCode::CREATED,
         Json(CreateTokenResponse {
             token_id: token_id.clone(),
-            name: payload.name,
+            name: new_token.name,
             message: format!("Use: Authorization: Bearer {}", token_id),
         }),
     ))
 }
 
+/// Most specs one `POST /tokens/bulk` may carry.
+const MAX_BULK_TOKENS: usize = 500;
+
+/// Reported for specs of an atomic batch that were valid but not created.
+const SKIPPED_REASON: &str = "not created: another token in the atomic batch failed";
+
+/// A bulk spec after validation: the row to insert, or why it was rejected.
+struct PreparedToken {
+    name: String,
+    row: Result<NewToken, String>,
+}
+
+/// POST /api/v1/tokens/bulk — create many tokens in one call
+///
+/// Each spec is validated and built like `POST /tokens`; `credential` may
+/// name a credential instead of giving `credential_id`. With `atomic: true`
+/// the tokens are inserted in one transaction and none are created if any
+/// spec fails; otherwise every valid spec is created. Answers 201 when all
+/// tokens were created, else 207 with a result per spec.
+pub async fn create_tokens_bulk(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Json(payload): Json<BulkCreateTokensRequest>,
+) -> Result<(StatusCode, Json<BulkCreateTokensResponse>), StatusCode> {
+    auth.require_role("admin")?;
+    auth.require_scope("tokens:write")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    if payload.tokens.is_empty() || payload.tokens.len() > MAX_BULK_TOKENS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut projects = BulkProjects::default();
+    let mut prepared = Vec::with_capacity(payload.tokens.len());
+    for spec in payload.tokens {
+        let name = spec.token.name.clone();
+        let row = prepare_bulk_token(&state, &auth, spec, &mut projects).await?;
+        prepared.push(PreparedToken { name, row });
+    }
+
+    let db = &state.db;
+    let results = if payload.atomic {
+        insert_atomic(
+            prepared,
+            |tokens| async move { db.insert_tokens(&tokens).await },
+        )
+        .await
+    } else {
+        insert_best_effort(
+            prepared,
+            |token| async move { db.insert_token(&token).await },
+        )
+        .await
+    };
+    let response = BulkCreateTokensResponse::new(payload.atomic, results);
+    tracing::info!(
+        total = response.total,
+        created = response.created,
+        errors = response.errors,
+        atomic = response.atomic,
+        "bulk token creation"
+    );
+    let status = if response.created == response.total {
+        StatusCode::CREATED
+    } else {
+        StatusCode::MULTI_STATUS
+    };
+    Ok((status, Json(response)))
+}
+
+/// Per-project lookups shared by the specs of one bulk request.
+#[derive(Default)]
+struct BulkProjects {
+    owned: HashMap<uuid::Uuid, bool>,
+    credentials: HashMap<uuid::Uuid, HashMap<String, uuid::Uuid>>,
+    names: HashSet<(uuid::Uuid, String)>,
+}
+
+/// Validate one bulk spec and build its row. Spec problems are returned as
+/// the item's error; only infrastructure failures fail the whole request.
+async fn prepare_bulk_token(
+    state: &AppState,
+    auth: &AuthContext,
+    spec: BulkTokenSpec,
+    projects: &mut BulkProjects,
+) -> Result<Result<NewToken, String>, StatusCode> {
+    let mut payload = spec.token;
+    let project_id = payload
+        .project_id
+        .unwrap_or_else(|| auth.default_project_id());
+
+    let owned = match projects.owned.get(&project_id) {
+        Some(&owned) => owned,
+        None => {
+            let owned = match verify_project_ownership(state, auth.org_id, project_id).await {
+                Ok(()) => true,
+                Err(StatusCode::FORBIDDEN) => false,
+                Err(status) => return Err(status),
+            };
+            projects.owned.insert(project_id, owned);
+            owned
+        }
+    };
+    if !owned {
+        return Ok(Err(format!("project {} not found", project_id)));
+    }
+
+    if let Err((_, reason)) = validate_create_token(&payload) {
+        return Ok(Err(reason.to_string()));
+    }
+
+    if let Some(name) = spec.credential {
+        if payload.credential_id.is_some() {
+            return Ok(Err("set credential or credential_id, not both".to_string()));
+        }
+        let credentials = match projects.credentials.entry(project_id) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                let by_name = state
+                    .db
+                    .credential_ids_by_name(project_id)
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("create_tokens_bulk: listing credentials: {}", e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+                e.insert(by_name)
+            }
+        };
+        match credentials.get(&name) {
+            Some(&id) => payload.credential_id = Some(id),
+            None => return Ok(Err(format!("unknown credential '{}'", name))),
+        }
+    }
+
+    // Names are unique per project; catch repeats before they hit the DB.
+    if !projects.names.insert((project_id, payload.name.clone())) {
+        return Ok(Err(format!("duplicate name '{}' in request", payload.name)));
+    }
+
+    let policy_ids = resolve_policy_ids(state, project_id, &payload)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "create_tokens_bulk: loading project default policies: {}",
+                e
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let token_id = generate_token_id(project_id);
+    Ok(Ok(new_token(token_id, project_id, policy_ids, payload)))
+}
+
+/// Insert every valid token, each on its own, and report each spec.
+async fn insert_best_effort<F, Fut>(
+    prepared: Vec<PreparedToken>,
+    mut insert: F,
+) -> Vec<BulkTokenResult>
+where
+    F: FnMut(NewToken) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<()>>,
+{
+    let mut results = Vec::with_capacity(prepared.len());
+    for (index, PreparedToken { name, row }) in prepared.into_iter().enumerate() {
+        let result = match row {
+            Ok(token) => {
+                let id = token.id.clone();
+                match insert(token).await {
+                    Ok(()) => bulk_created(index, name, id),
+                    Err(e) => bulk_error(index, name, insert_error_reason(&e)),
+                }
+            }
+            Err(reason) => bulk_error(index, name, reason),
+        };
+        results.push(result);
+    }
+    results
+}
+
+/// Insert the tokens all-or-nothing: nothing is attempted unless every spec
+/// is valid, and a failed insert rolls back the others.
+async fn insert_atomic<F, Fut>(prepared: Vec<PreparedToken>, insert_all: F) -> Vec<BulkTokenResult>
+where
+    F: FnOnce(Vec<NewToken>) -> Fut,
+    Fut: std::future::Future<Output = Result<(), (Option<usize>, anyhow::Error)>>,
+{
+    let names: Vec<String> = prepared.iter().map(|p| p.name.clone()).collect();
+    let failed: Vec<(usize, String)> = prepared
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| p.row.as_ref().err().map(|reason| (i, reason.clone())))
+        .collect();
+    if !failed.is_empty() {
+        return atomic_rollback(names, failed);
+    }
+
+    let tokens: Vec<NewToken> = prepared.into_iter().filter_map(|p| p.row.ok()).collect();
+    let ids: Vec<String> = tokens.iter().map(|t| t.id.clone()).collect();
+    match insert_all(tokens).await {
+        Ok(()) => names
+            .into_iter()
+            .zip(ids)
+            .enumerate()
+            .map(|(index, (name, id))| bulk_created(index, name, id))
+            .collect(),
+        Err((Some(index), e)) => atomic_rollback(names, vec![(index, insert_error_reason(&e))]),
+        Err((None, e)) => {
+            let reason = insert_error_reason(&e);
+            let failed = (0..names.len()).map(|i| (i, reason.clone())).collect();
+            atomic_rollback(names, failed)
+        }
+    }
+}
+
+/// Results of an atomic batch that created nothing: `failed` specs are
+/// errors, the rest are skipped.
+fn atomic_rollback(names: Vec<String>, failed: Vec<(usize, String)>) -> Vec<BulkTokenResult> {
+    let mut failed: HashMap<usize, String> = failed.into_iter().collect();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| match failed.remove(&index) {
+            Some(reason) => bulk_error(index, name, reason),
+            None => BulkTokenResult {
+                index,
+                name,
+                status: "skipped",
+                token_id: None,
+                error: Some(SKIPPED_REASON.to_string()),
+            },
+        })
+        .collect()
+}
+
+fn bulk_created(index: usize, name: String, token_id: String) -> BulkTokenResult {
+    BulkTokenResult {
+        index,
+        name,
+        status: "created",
+        token_id: Some(token_id),
+        error: None,
+    }
+}
+
+fn bulk_error(index: usize, name: String, reason: String) -> BulkTokenResult {
+    BulkTokenResult {
+        index,
+        name,
+        status: "error",
+        token_id: None,
+        error: Some(reason),
+    }
+}
+
+/// Caller-facing reason for a failed insert; details of unexpected database
+/// errors are logged, not returned.
+fn insert_error_reason(e: &anyhow::Error) -> String {
+    let unique_violation = e
+        .downcast_ref::<sqlx::Error>()
+        .and_then(|e| e.as_database_error())
+        .is_some_and(|e| e.is_unique_violation());
+    if unique_violation {
+        "a token with this name already exists in the project".to_string()
+    } else {
+        tracing::error!("create_tokens_bulk: insert failed: {}", e);
+        "internal error".to_string()
+    }
+}
+
 /// DELETE /api/v1/tokens/:id — revoke a token
 pub async fn revoke_token(
     State(state): State<Arc<AppState>>,
@@ -154,12 +670,104 @@ pub async fn revoke_token(
         })?;
 
     if revoked {
+        state.cache.invalidate_token(&id).await;
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err(StatusCode::NOT_FOUND)
     }
 }
 
+/// POST /api/v1/tokens/:id/rotate — mint a new token ID with the same config
+///
+/// The old token is revoked, or with `grace_seconds` kept usable until the
+/// window ends so clients can switch over.
+pub async fn rotate_token(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+    payload: Option<Json<RotateTokenRequest>>,
+) -> Result<(StatusCode, Json<RotateTokenResponse>), StatusCode> {
+    auth.require_role("admin")?;
+    auth.require_scope("tokens:write")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    let grace_seconds = payload
+        .map(|Json(p)| p)
+        .unwrap_or_default()
+        .grace_seconds
+        .unwrap_or(0);
+    if grace_seconds > MAX_ROTATION_GRACE_SECS {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let token = state
+        .db
+        .get_token(&id)
+        .await
+        .map_err(|e| {
+            tracing::error!("rotate_token lookup failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|t| t.is_active)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    verify_project_ownership(&state, auth.org_id, token.project_id).await?;
+
+    let new_id = generate_token_id(token.project_id);
+    let grace_until = (grace_seconds > 0)
+        .then(|| chrono::Utc::now() + chrono::Duration::seconds(grace_seconds as i64));
+    let rotated = state
+        .db
+        .rotate_token(&token, &new_id, grace_until)
+        .await
+        .map_err(|e| {
+            tracing::error!("rotate_token failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !rotated {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state.cache.invalidate_token(&token.id).await;
+    let old_token_expires_at = grace_until.map(|g| token.expires_at.map_or(g, |e| e.min(g)));
+
+    tracing::info!(
+        old_token_id = %token.id,
+        new_token_id = %new_id,
+        project_id = %token.project_id,
+        grace_seconds,
+        "token rotated"
+    );
+    let event = crate::notification::webhook::WebhookEvent::token_rotated(
+        &new_id,
+        &token.name,
+        &token.project_id.to_string(),
+        &token.id,
+        old_token_expires_at,
+    );
+    let state_bg = state.clone();
+    let project_id = token.project_id;
+    tokio::spawn(async move {
+        state_bg
+            .webhook
+            .dispatch(&state_bg.config.webhook_urls, event.clone())
+            .await;
+        match state_bg.db.list_webhook_targets(project_id).await {
+            Ok(targets) => state_bg.webhook.dispatch_signed(&targets, event).await,
+            Err(e) => {
+                tracing::warn!(project_id = %project_id, error = %e, "failed to load project webhooks")
+            }
+        }
+    });
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RotateTokenResponse {
+            token_id: new_id.clone(),
+            rotated_from: token.id,
+            old_token_expires_at,
+            message: format!("Use: Authorization: Bearer {}", new_id),
+        }),
+    ))
+}
+
 pub async fn get_token_usage(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthContext>,
@@ -288,6 +896,262 @@ pub async fn update_circuit_breaker(
         ));
     }
 
+    state.cache.invalidate_token(&token_id).await;
     tracing::info!(token_id = %token_id, "circuit breaker config updated");
     Ok(Json(payload))
 }
+
+pub async fn get_token_migration(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(token_id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    auth.require_scope("tokens:read")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    verify_token_ownership(&state, &token_id, &auth).await?;
+    let token = state
+        .db
+        .get_token(&token_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("get_token_migration: db error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(json!({ "migration": token.migration })))
+}
+
+/// Set or ramp a token's gradual migration. The whole config is replaced, so
+/// ramping means resending it with a new `percentage`.
+pub async fn update_token_migration(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(token_id): Path<String>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    auth.require_scope("tokens:write").map_err(|_| {
+        (StatusCode::FORBIDDEN, Json(json!({ "error": { "code": "forbidden", "message": "tokens:write scope required" } })))
+    })?;
+    verify_token_ownership(&state, &token_id, &auth)
+        .await
+        .map_err(|status| {
+            (
+                status,
+                Json(json!({ "error": { "code": "not_found", "message": "Token not found" } })),
+            )
+        })?;
+
+    let config = crate::proxy::migration::MigrationConfig::from_value(&payload).map_err(|e| {
+        tracing::warn!("update_token_migration: invalid config: {}", e);
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": { "code": "invalid_config", "message": format!("Invalid migration config: {}", e) } })),
+        )
+    })?;
+    let stored = serde_json::to_value(&config).unwrap_or(payload);
+
+    let token = state.db.get_token(&token_id).await
+        .map_err(|e| {
+            tracing::error!("update_token_migration: db error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": { "code": "internal_server_error", "message": "Database error" } })))
+        })?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({ "error": { "code": "not_found", "message": "Token not found" } }))))?;
+
+    let updated = state
+        .db
+        .update_token_migration(&token_id, token.project_id, Some(stored.clone()))
+        .await
+        .map_err(|e| {
+            tracing::error!("update_token_migration: update failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": { "code": "internal_server_error", "message": "Failed to update migration config" } })))
+        })?;
+    if !updated {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": { "code": "not_found", "message": "Token not found" } })),
+        ));
+    }
+
+    state.cache.invalidate_token(&token_id).await;
+    tracing::info!(token_id = %token_id, percentage = config.percentage, "token migration updated");
+    Ok(Json(json!({ "migration": stored })))
+}
+
+/// Stop a migration; every request goes back to the token's current route.
+pub async fn delete_token_migration(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Path(token_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth.require_scope("tokens:write")
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    verify_token_ownership(&state, &token_id, &auth).await?;
+    let token = state
+        .db
+        .get_token(&token_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("delete_token_migration: db error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let updated = state
+        .db
+        .update_token_migration(&token_id, token.project_id, None)
+        .await
+        .map_err(|e| {
+            tracing::error!("delete_token_migration: update failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !updated {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state.cache.invalidate_token(&token_id).await;
+    tracing::info!(token_id = %token_id, "token migration cleared");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, upstream_url: &str) -> BulkTokenSpec {
+        serde_json::from_value(json!({"name": name, "upstream_url": upstream_url})).unwrap()
+    }
+
+    /// What `prepare_bulk_token` yields for a spec, minus the DB lookups.
+    fn prepare(spec: BulkTokenSpec) -> PreparedToken {
+        let name = spec.token.name.clone();
+        let project_id = uuid::Uuid::nil();
+        let row = validate_create_token(&spec.token)
+            .map(|()| {
+                new_token(
+                    generate_token_id(project_id),
+                    project_id,
+                    vec![],
+                    spec.token,
+                )
+            })
+            .map_err(|(_, reason)| reason.to_string());
+        PreparedToken { name, row }
+    }
+
+    #[test]
+    fn test_bulk_request_parses_token_specs() {
+        let req: BulkCreateTokensRequest = serde_json::from_value(json!({
+            "atomic": true,
+            "tokens": [
+                {"name": "agent-1", "upstream_url": "https://api.openai.com", "credential": "openai-prod"},
+                {"name": "agent-2", "upstream_url": "https://api.openai.com", "log_level_name": "full", "max_cost_per_request_usd": 0.5}
+            ]
+        }))
+        .unwrap();
+        assert!(req.atomic);
+        assert_eq!(req.tokens[0].credential.as_deref(), Some("openai-prod"));
+        assert_eq!(req.tokens[1].token.resolved_log_level(), Some(2));
+        assert_eq!(
+            req.tokens[1].token.max_cost_per_request_usd,
+            Some(rust_decimal::Decimal::new(5, 1))
+        );
+    }
+
+    #[test]
+    fn test_validate_test_upstream_override() {
+        let with_override = |url: &str| {
+            let mut s = spec("agent", "https://api.openai.com");
+            s.token.test_upstream_override = Some(url.to_string());
+            validate_create_token(&s.token).map_err(|(_, reason)| reason)
+        };
+        assert!(with_override("http://mock-upstream:9000").is_ok());
+        assert!(with_override("https://mock.internal/v1").is_ok());
+        assert_eq!(
+            with_override("ftp://mock-upstream"),
+            Err("test_upstream_override must be an http(s) URL")
+        );
+        assert_eq!(
+            with_override("not a url"),
+            Err("test_upstream_override must be an http(s) URL")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_atomic_rolls_back_on_one_bad_item() {
+        let prepared = vec![
+            prepare(spec("agent-1", "https://api.openai.com")),
+            prepare(spec("agent-2", "ftp://example.com")),
+            prepare(spec("agent-3", "https://api.openai.com")),
+        ];
+        let results = insert_atomic(prepared, |_| async {
+            panic!("an invalid batch must not be inserted")
+        })
+        .await;
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, ["skipped", "error", "skipped"]);
+        assert_eq!(results[1].name, "agent-2");
+        assert_eq!(
+            results[1].error.as_deref(),
+            Some("upstream_url must be http or https")
+        );
+        assert!(results.iter().all(|r| r.token_id.is_none()));
+
+        // A failure inside the transaction is pinned to the failing spec.
+        let prepared = vec![
+            prepare(spec("agent-1", "https://api.openai.com")),
+            prepare(spec("agent-2", "https://api.openai.com")),
+        ];
+        let results = insert_atomic(prepared, |tokens| async move {
+            assert_eq!(tokens.len(), 2);
+            Err((Some(1), anyhow::anyhow!("connection reset")))
+        })
+        .await;
+        let response = BulkCreateTokensResponse::new(true, results);
+        assert_eq!((response.created, response.errors), (0, 1));
+        assert_eq!(response.results[0].status, "skipped");
+        assert_eq!(response.results[1].status, "error");
+        assert_eq!(response.results[1].error.as_deref(), Some("internal error"));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_best_effort_reports_partial_success() {
+        let prepared = vec![
+            prepare(spec("agent-1", "https://api.openai.com")),
+            prepare(spec("agent-2", "not a url")),
+            prepare(spec("taken", "https://api.openai.com")),
+            prepare(spec("agent-4", "https://api.openai.com")),
+        ];
+        let mut inserted = Vec::new();
+        let results = insert_best_effort(prepared, |token| {
+            let taken = token.name == "taken";
+            inserted.push(token.name);
+            async move {
+                if taken {
+                    anyhow::bail!("duplicate key");
+                }
+                Ok(())
+            }
+        })
+        .await;
+        assert_eq!(inserted, ["agent-1", "taken", "agent-4"]);
+
+        let response = BulkCreateTokensResponse::new(false, results);
+        assert_eq!(
+            (response.total, response.created, response.errors),
+            (4, 2, 2)
+        );
+        let r = &response.results;
+        assert_eq!((r[0].status, r[3].status), ("created", "created"));
+        assert!(r[0]
+            .token_id
+            .as_deref()
+            .unwrap()
+            .starts_with("tf_v1_00000000_tok_"));
+        assert_eq!(
+            r[1].error.as_deref(),
+            Some("upstream_url is not a valid URL")
+        );
+        assert_eq!(r[2].name, "taken");
+        assert_eq!(r[2].error.as_deref(), Some("internal error"));
+        assert_eq!(r[2].index, 2);
+    }
+}
@@ -9,6 +9,7 @@ use axum::{
 use super::dtos::{CreateWebhookRequest, TestWebhookRequest, TestWebhookResponse, WebhookRow};
 use super::helpers::validate_webhook_url;
 use crate::api::AuthContext;
+use crate::notification::webhook::EVENT_TYPES;
 use crate::AppState;
 
 pub async fn list_webhooks(
@@ -50,6 +51,10 @@ pub async fn create_webhook(
 
     let project_id = auth.default_project_id();
     let events = payload.events.unwrap_or_default();
+    if let Some(unknown) = events.iter().find(|e| !EVENT_TYPES.contains(&e.as_str())) {
+        tracing::warn!(event_type = %unknown, "create_webhook: unknown event type");
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
 
     // Generate a 32-byte (256-bit) random signing secret shown once on creation.
     let signing_secret: String =
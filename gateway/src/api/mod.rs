@@ -90,16 +90,25 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/tokens",
             get(handlers::list_tokens).post(handlers::create_token),
         )
+        .route("/tokens/bulk", post(handlers::create_tokens_bulk))
         .route("/tokens/:id", delete(handlers::revoke_token))
+        .route("/tokens/:id/rotate", post(handlers::rotate_token))
         .route("/tokens/:id/usage", get(handlers::get_token_usage))
         .route(
             "/tokens/:id/circuit-breaker",
             get(handlers::get_circuit_breaker).patch(handlers::update_circuit_breaker),
         )
+        .route(
+            "/tokens/:id/migration",
+            get(handlers::get_token_migration)
+                .put(handlers::update_token_migration)
+                .delete(handlers::delete_token_migration),
+        )
         .route(
             "/policies",
             get(handlers::list_policies).post(handlers::create_policy),
         )
+        .route("/policies/lint", post(handlers::lint_policy))
         .route(
             "/policies/:id",
             put(handlers::update_policy).delete(handlers::delete_policy),
@@ -113,6 +122,10 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
             get(handlers::list_credentials).post(handlers::create_credential),
         )
         .route("/credentials/:id", delete(handlers::delete_credential))
+        .route(
+            "/credentials/:id/model-rate-limits",
+            put(handlers::update_credential_model_rate_limits),
+        )
         .route(
             "/projects",
             get(handlers::list_projects).post(handlers::create_project),
@@ -133,6 +146,7 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/approvals/:id/decision", post(handlers::decide_approval))
         .route("/audit", get(handlers::list_audit_logs))
         .route("/audit/:id", get(handlers::get_audit_log))
+        .route("/audit/:id/replay", post(handlers::replay_audit_log))
         .route("/audit/stream", get(handlers::stream_audit_logs))
         .route("/sessions", get(handlers::list_sessions))
         .route("/sessions/:id", get(handlers::get_session))
@@ -175,6 +189,14 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         )
         .route("/auth/keys/:id", delete(handlers::revoke_api_key))
         .route("/auth/whoami", get(handlers::whoami))
+        .route(
+            "/auth/oidc-providers",
+            get(handlers::list_oidc_providers).post(handlers::create_oidc_provider),
+        )
+        .route(
+            "/auth/oidc-providers/:id",
+            put(handlers::update_oidc_provider).delete(handlers::delete_oidc_provider),
+        )
         // Billing (New)
         .route("/billing/usage", get(handlers::get_org_usage))
         // Analytics (New)
@@ -191,12 +213,20 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/analytics/tokens/:id/latency",
             get(handlers::get_token_latency),
         )
+        .route(
+            "/analytics/tokens/:id/forecast",
+            get(analytics::get_token_forecast),
+        )
         .route("/analytics/volume", get(analytics::get_request_volume))
         .route("/analytics/status", get(analytics::get_status_distribution))
         .route(
             "/analytics/latency",
             get(analytics::get_latency_percentiles),
         )
+        .route(
+            "/analytics/latency/by-model",
+            get(handlers::get_latency_by_model),
+        )
         // New Server-Side Analytics (Phase 8)
         .route("/analytics/summary", get(handlers::get_analytics_summary))
         .route(
@@ -207,21 +237,40 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/analytics/experiments",
             get(handlers::get_analytics_experiments),
         )
+        .route(
+            "/analytics/experiments/:name/compare",
+            get(handlers::get_experiment_comparison),
+        )
         .route(
             "/analytics/spend/breakdown",
             get(handlers::get_spend_breakdown),
         )
+        .route("/analytics/tools", get(handlers::get_tool_analytics))
         // Settings & System
         .route(
             "/settings",
             get(handlers::get_settings).put(handlers::update_settings),
         )
+        .route("/system/config", get(handlers::get_effective_config))
         .route("/system/cache-stats", get(handlers::get_cache_stats))
         .route("/system/flush-cache", post(handlers::flush_cache))
+        .route("/system/cache-warm", post(handlers::warm_cache))
+        .route(
+            "/system/cache-warm/candidates",
+            get(handlers::list_cache_warm_candidates),
+        )
         // PII Tokenization Vault
         .route("/pii/rehydrate", post(handlers::rehydrate_pii_tokens))
         // Upstream Health
         .route("/health/upstreams", get(handlers::get_upstream_health))
+        .route(
+            "/health/upstreams/stream-test",
+            post(handlers::stream_test_upstream),
+        )
+        .route(
+            "/health/upstreams/:url/history",
+            get(handlers::get_upstream_health_history),
+        )
         // Anomaly Detection
         .route("/anomalies", get(handlers::get_anomaly_events))
         // Model Access Groups (RBAC Depth)
@@ -260,6 +309,10 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/tokens/:id/spend/:period",
             delete(handlers::delete_spend_cap),
         )
+        .route(
+            "/tokens/:id/spend/simulate",
+            post(handlers::simulate_spend_cap),
+        )
         // Webhooks
         .route(
             "/webhooks",
@@ -272,6 +325,7 @@ pub fn api_router(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/pricing",
             get(handlers::list_pricing).put(handlers::upsert_pricing),
         )
+        .route("/pricing/import", post(handlers::import_pricing))
         .route("/pricing/:id", delete(handlers::delete_pricing))
         // Guardrail Presets — one-call guardrail enablement
         .route("/guardrails/presets", get(guardrail_presets::list_presets))
@@ -366,6 +420,45 @@ async fn fallback_404() -> StatusCode {
     StatusCode::NOT_FOUND
 }
 
+/// Placeholder SuperAdmin key used when neither `TRUEFLOW_ADMIN_KEY` nor
+/// `TRUEFLOW_MASTER_KEY` is set. Only accepted when `TRUEFLOW_DEV_MODE` is on.
+pub const INSECURE_DEFAULT_ADMIN_KEY: &str = "CHANGE_ME_INSECURE_DEFAULT";
+
+/// The SuperAdmin key `admin_auth` compares against.
+pub fn superadmin_env_key() -> String {
+    std::env::var("TRUEFLOW_ADMIN_KEY")
+        .or_else(|_| std::env::var("TRUEFLOW_MASTER_KEY"))
+        .unwrap_or_else(|_| INSECURE_DEFAULT_ADMIN_KEY.to_string())
+}
+
+/// Org whose OIDC providers a JWT is matched against: `X-TrueFlow-Org-Id`,
+/// or the default org. Providers are looked up by (org, issuer), so another
+/// org registering the same issuer can't take over this org's logins.
+/// `None` if the header isn't a UUID.
+fn oidc_org_id(headers: &axum::http::HeaderMap) -> Option<Uuid> {
+    match headers.get("x-trueflow-org-id") {
+        Some(v) => v.to_str().ok().and_then(|s| Uuid::parse_str(s.trim()).ok()),
+        None => Some(Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap()),
+    }
+}
+
+/// Whether `provided` is the SuperAdmin env key.
+///
+/// SEC-08: the insecure default key is refused unless running in dev mode.
+/// SEC-07: constant-time comparison. Uses SHA-256 to normalize both values to
+/// fixed length before comparison, preventing timing side-channel from leaking
+/// key length.
+fn is_superadmin_key(provided: &str, expected: &str, dev_mode: bool) -> bool {
+    use sha2::{Digest, Sha256};
+    use subtle::ConstantTimeEq;
+    if expected == INSECURE_DEFAULT_ADMIN_KEY && !dev_mode {
+        return false;
+    }
+    let hash_a = Sha256::digest(provided.as_bytes());
+    let hash_b = Sha256::digest(expected.as_bytes());
+    hash_a.ct_eq(&hash_b).into()
+}
+
 /// Middleware: validates `X-Admin-Key` (SuperAdmin) or `Authorization: Bearer <api_key>` (RBAC).
 async fn admin_auth(
     State(state): State<Arc<AppState>>,
@@ -388,27 +481,13 @@ async fn admin_auth(
         .map(|t| t.trim());
 
     // Load expected SuperAdmin key
-    let expected_env_key = std::env::var("TRUEFLOW_ADMIN_KEY")
-        .or_else(|_| std::env::var("TRUEFLOW_MASTER_KEY"))
-        .unwrap_or_else(|_| "CHANGE_ME_INSECURE_DEFAULT".to_string());
-
-    // SEC-08: Refuse insecure default key in non-dev environments
-    let insecure_default = expected_env_key == "CHANGE_ME_INSECURE_DEFAULT";
+    let expected_env_key = superadmin_env_key();
 
-    // SEC-07: constant-time comparison for admin key
-    // Uses SHA-256 to normalize both values to fixed length before comparison,
-    // preventing timing side-channel from leaking key length.
-    fn ct_eq(a: &str, b: &str) -> bool {
-        use sha2::{Digest, Sha256};
-        use subtle::ConstantTimeEq;
-        let hash_a = Sha256::digest(a.as_bytes());
-        let hash_b = Sha256::digest(b.as_bytes());
-        hash_a.ct_eq(&hash_b).into()
-    }
+    let dev_mode = state.config.dev_mode;
 
     // ── Path A: SuperAdmin (Env Key) ─────────────────────────────
     if let Some(k) = provided_key_header {
-        if !insecure_default && ct_eq(k, &expected_env_key) {
+        if is_superadmin_key(k, &expected_env_key, dev_mode) {
             let ctx = AuthContext {
                 org_id: Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap(), // Default Org
                 user_id: None,
@@ -422,7 +501,7 @@ async fn admin_auth(
     }
 
     if let Some(k) = bearer_token {
-        if !insecure_default && ct_eq(k, &expected_env_key) {
+        if is_superadmin_key(k, &expected_env_key, dev_mode) {
             let ctx = AuthContext {
                 org_id: Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap(),
                 user_id: None,
@@ -455,8 +534,12 @@ async fn admin_auth(
             };
 
             if let Some(issuer) = iss_peek {
-                // Step 2: Look up provider by issuer
-                match state.db.get_oidc_provider_by_issuer(&issuer).await {
+                // Step 2: Look up the org's provider for the issuer
+                let Some(org_id) = oidc_org_id(req.headers()) else {
+                    tracing::warn!("OIDC: invalid X-TrueFlow-Org-Id header");
+                    return Err(StatusCode::BAD_REQUEST);
+                };
+                match state.db.get_oidc_provider_by_issuer(org_id, &issuer).await {
                     Ok(Some(provider_row)) => {
                         // Convert DB row → OidcProvider
                         let provider = oidc::OidcProvider {
@@ -609,3 +692,53 @@ async fn admin_auth(
     tracing::warn!("admin API: missing auth header");
     Err(StatusCode::UNAUTHORIZED)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insecure_default_admin_key_refused_outside_dev_mode() {
+        assert!(!is_superadmin_key(
+            INSECURE_DEFAULT_ADMIN_KEY,
+            INSECURE_DEFAULT_ADMIN_KEY,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_insecure_default_admin_key_accepted_in_dev_mode() {
+        assert!(is_superadmin_key(
+            INSECURE_DEFAULT_ADMIN_KEY,
+            INSECURE_DEFAULT_ADMIN_KEY,
+            true
+        ));
+        assert!(!is_superadmin_key(
+            "wrong",
+            INSECURE_DEFAULT_ADMIN_KEY,
+            true
+        ));
+    }
+
+    #[test]
+    fn test_configured_admin_key_needs_exact_match() {
+        let key = "tf-admin-7c1e9f";
+        assert!(is_superadmin_key(key, key, false));
+        assert!(!is_superadmin_key("tf-admin-7c1e9", key, false));
+        assert!(!is_superadmin_key("", key, false));
+    }
+
+    #[test]
+    fn test_oidc_org_id_from_header_or_default() {
+        let mut headers = axum::http::HeaderMap::new();
+        assert_eq!(
+            oidc_org_id(&headers),
+            Some(Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap())
+        );
+        let org = Uuid::new_v4();
+        headers.insert("x-trueflow-org-id", org.to_string().parse().unwrap());
+        assert_eq!(oidc_org_id(&headers), Some(org));
+        headers.insert("x-trueflow-org-id", "not-a-uuid".parse().unwrap());
+        assert_eq!(oidc_org_id(&headers), None);
+    }
+}
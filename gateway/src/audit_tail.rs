@@ -0,0 +1,398 @@
+//! `trueflow audit tail` — watch the request feed from a terminal.
+//!
+//! Prints the latest page of `GET /api/v1/audit`, then with `--follow`
+//! connects to the gateway's `GET /api/v1/audit/stream` SSE feed with the
+//! admin key. Entries are filtered client-side, one line per request.
+//! The feed is polled server-side, so entries logged while the CLI is
+//! disconnected are not replayed; the CLI reconnects with backoff.
+
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::Value;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Client-side filters for `audit tail`.
+#[derive(Debug, Default)]
+pub struct TailFilter {
+    pub token: Option<String>,
+    /// Exact status (`"429"`) or class (`"5xx"`).
+    pub status: Option<String>,
+    pub min_cost: Option<f64>,
+}
+
+impl TailFilter {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(ref s) = self.status {
+            let valid = s.parse::<u16>().is_ok()
+                || (s.len() == 3
+                    && s.as_bytes()[0].is_ascii_digit()
+                    && s[1..].eq_ignore_ascii_case("xx"));
+            if !valid {
+                anyhow::bail!(
+                    "--status must be a status code (429) or class (5xx), got '{}'",
+                    s
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, row: &Value) -> bool {
+        if let Some(ref token) = self.token {
+            if row.get("token_id").and_then(Value::as_str) != Some(token.as_str()) {
+                return false;
+            }
+        }
+        if let Some(ref filter) = self.status {
+            let status = row.get("upstream_status").and_then(Value::as_i64);
+            if !status_matches(filter, status) {
+                return false;
+            }
+        }
+        if let Some(min) = self.min_cost {
+            if cost_usd(row).unwrap_or(0.0) < min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn status_matches(filter: &str, status: Option<i64>) -> bool {
+    let Some(status) = status else {
+        return false;
+    };
+    match filter.parse::<i64>() {
+        Ok(code) => status == code,
+        Err(_) => filter
+            .chars()
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .is_some_and(|class| status / 100 == class as i64),
+    }
+}
+
+/// `estimated_cost_usd` is a decimal serialized as a string; accept numbers too.
+fn cost_usd(row: &Value) -> Option<f64> {
+    match row.get("estimated_cost_usd")? {
+        Value::String(s) => s.parse().ok(),
+        v => v.as_f64(),
+    }
+}
+
+fn paint(text: &str, ansi: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", ansi, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// One line per entry: time, status, policy outcome, model, latency, cost,
+/// token and request line. Policy outcomes are color-coded when `color`.
+pub fn format_row(row: &Value, color: bool) -> String {
+    let str_field = |key: &str| row.get(key).and_then(Value::as_str).unwrap_or("-");
+
+    let time = row
+        .get("created_at")
+        .and_then(Value::as_str)
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "--:--:--".to_string());
+
+    let status = row.get("upstream_status").and_then(Value::as_i64);
+    let status_text = status.map_or_else(|| "---".to_string(), |s| s.to_string());
+    let status_ansi = match status {
+        Some(s) if s >= 500 => "31",
+        Some(s) if s >= 400 => "33",
+        Some(_) => "32",
+        None => "2",
+    };
+
+    let policy = str_field("policy_result");
+    let shadow = row
+        .get("shadow_violations")
+        .and_then(Value::as_array)
+        .is_some_and(|v| !v.is_empty());
+    let (policy_text, policy_ansi) = match policy {
+        "allowed" if shadow => ("allowed*".to_string(), "33"),
+        "allowed" => ("allowed".to_string(), "32"),
+        "approved" => ("approved".to_string(), "36"),
+        "denied" | "rejected" => (policy.to_string(), "31"),
+        "timeout" => ("timeout".to_string(), "33"),
+        other => (other.to_string(), "0"),
+    };
+
+    let cost = cost_usd(row).map_or_else(|| "-".to_string(), |c| format!("${:.4}", c));
+    let latency = row
+        .get("response_latency_ms")
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+    let cache = if row.get("cache_hit").and_then(Value::as_bool) == Some(true) {
+        " [cache]"
+    } else {
+        ""
+    };
+
+    format!(
+        "{}  {}  {:<9}  {:<28}  {:>6}ms  {:>9}  {}  {} {}{}",
+        time,
+        paint(&status_text, status_ansi, color),
+        paint(&policy_text, policy_ansi, color),
+        str_field("model"),
+        latency,
+        cost,
+        str_field("token_id"),
+        str_field("method"),
+        str_field("path"),
+        cache,
+    )
+}
+
+/// Extract the JSON rows from one SSE event block. The feed sends `audit`
+/// events whose data is a JSON array of rows; heartbeats are comments.
+pub fn parse_event(block: &str) -> Vec<Value> {
+    let data: String = block
+        .lines()
+        .filter_map(|l| l.strip_prefix("data:"))
+        .map(|d| d.strip_prefix(' ').unwrap_or(d))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if data.is_empty() {
+        return Vec::new();
+    }
+    match serde_json::from_str::<Value>(&data) {
+        Ok(Value::Array(rows)) => rows,
+        Ok(row @ Value::Object(_)) => vec![row],
+        _ => Vec::new(),
+    }
+}
+
+/// How `audit tail` reads the feed.
+#[derive(Debug)]
+pub struct TailOptions {
+    pub project_id: Option<String>,
+    /// Recent entries to print first; `0` skips the backlog.
+    pub lines: i64,
+    pub follow: bool,
+    pub json: bool,
+}
+
+/// URL of the paginated audit list, newest first (`GET /api/v1/audit`).
+pub fn recent_url(base_url: &str, project_id: Option<&str>, lines: i64) -> String {
+    let mut url = format!(
+        "{}/api/v1/audit?limit={}",
+        base_url.trim_end_matches('/'),
+        lines.clamp(1, 200)
+    );
+    if let Some(p) = project_id {
+        url.push_str(&format!("&project_id={}", p));
+    }
+    url
+}
+
+fn print_rows<'a>(
+    rows: impl Iterator<Item = &'a Value>,
+    filter: &TailFilter,
+    json: bool,
+    color: bool,
+) {
+    let mut out = std::io::stdout().lock();
+    for row in rows.filter(|r| filter.matches(r)) {
+        let line = if json {
+            row.to_string()
+        } else {
+            format_row(row, color)
+        };
+        let _ = writeln!(out, "{}", line);
+    }
+    let _ = out.flush();
+}
+
+/// Print the backlog, then follow the feed if asked. Ctrl-C stops cleanly
+/// at any point.
+pub async fn run(
+    base_url: &str,
+    admin_key: &str,
+    filter: TailFilter,
+    opts: TailOptions,
+) -> anyhow::Result<()> {
+    filter.validate()?;
+    tokio::select! {
+        res = tail(base_url, admin_key, &filter, &opts) => res,
+        _ = tokio::signal::ctrl_c() => {
+            eprintln!("audit tail: interrupted");
+            Ok(())
+        }
+    }
+}
+
+async fn tail(
+    base_url: &str,
+    admin_key: &str,
+    filter: &TailFilter,
+    opts: &TailOptions,
+) -> anyhow::Result<()> {
+    let color =
+        !opts.json && std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let client = reqwest::Client::new();
+
+    if opts.lines > 0 {
+        // Same page the dashboard's audit list shows; printed oldest first.
+        let rows: Vec<Value> = client
+            .get(recent_url(base_url, opts.project_id.as_deref(), opts.lines))
+            .header("x-admin-key", admin_key)
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| anyhow::anyhow!("audit list rejected: {}", e))?
+            .json()
+            .await?;
+        print_rows(rows.iter().rev(), filter, opts.json, color);
+    }
+    if !opts.follow {
+        return Ok(());
+    }
+
+    let mut url = format!("{}/api/v1/audit/stream", base_url.trim_end_matches('/'));
+    if let Some(ref p) = opts.project_id {
+        url.push_str(&format!("?project_id={}", p));
+    }
+
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let resp = client
+            .get(&url)
+            .header("x-admin-key", admin_key)
+            .header("accept", "text/event-stream")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        let resp = match resp {
+            Ok(r) => r,
+            Err(e) if e.status().is_some_and(|s| s.is_client_error()) => {
+                // Bad key or project: retrying won't help.
+                anyhow::bail!("audit stream rejected: {}", e);
+            }
+            Err(e) => {
+                eprintln!(
+                    "audit tail: connect failed ({}), retrying in {:?}",
+                    e, backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+        };
+        eprintln!("audit tail: following {} (Ctrl-C to stop)", url);
+        backoff = Duration::from_secs(1);
+
+        let mut body = resp.bytes_stream();
+        // Buffer raw bytes so multi-byte characters split across chunks survive.
+        let mut buf: Vec<u8> = Vec::new();
+        let disconnect = loop {
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    buf.extend_from_slice(&chunk);
+                    while let Some(end) = buf.windows(2).position(|w| w == b"\n\n") {
+                        let block: Vec<u8> = buf.drain(..end + 2).collect();
+                        let block = String::from_utf8_lossy(&block);
+                        print_rows(parse_event(&block).iter(), filter, opts.json, color);
+                    }
+                }
+                Some(Err(e)) => break e.to_string(),
+                None => break "stream closed".to_string(),
+            }
+        };
+        eprintln!(
+            "audit tail: disconnected ({}), reconnecting in {:?}",
+            disconnect, backoff
+        );
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> Value {
+        serde_json::json!({
+            "created_at": "2026-03-14T12:00:00Z",
+            "token_id": "tf_v1_abc",
+            "method": "POST",
+            "path": "/v1/chat/completions",
+            "upstream_status": 429,
+            "response_latency_ms": 812,
+            "policy_result": "denied",
+            "estimated_cost_usd": "0.0125",
+            "model": "gpt-4o",
+            "cache_hit": false
+        })
+    }
+
+    #[test]
+    fn test_tail_filter() {
+        let r = row();
+        assert!(TailFilter::default().matches(&r));
+        let by_token = |t: &str| TailFilter {
+            token: Some(t.into()),
+            ..Default::default()
+        };
+        assert!(by_token("tf_v1_abc").matches(&r));
+        assert!(!by_token("tf_v1_other").matches(&r));
+
+        let by_status = |s: &str| TailFilter {
+            status: Some(s.into()),
+            ..Default::default()
+        };
+        assert!(by_status("429").matches(&r));
+        assert!(by_status("4xx").matches(&r));
+        assert!(!by_status("5xx").matches(&r));
+        assert!(by_status("5XX").validate().is_ok());
+        assert!(by_status("fivexx").validate().is_err());
+
+        let by_cost = |c: f64| TailFilter {
+            min_cost: Some(c),
+            ..Default::default()
+        };
+        assert!(by_cost(0.01).matches(&r));
+        assert!(!by_cost(0.02).matches(&r));
+    }
+
+    #[test]
+    fn test_tail_format_and_parse() {
+        let line = format_row(&row(), false);
+        assert!(line.contains("429"));
+        assert!(line.contains("denied"));
+        assert!(line.contains("$0.0125"));
+        assert!(line.contains("POST /v1/chat/completions"));
+        assert!(format_row(&row(), true).contains("\x1b[31mdenied\x1b[0m"));
+
+        let block = format!(
+            "event: audit\ndata: {}\n\n",
+            Value::Array(vec![row(), row()])
+        );
+        assert_eq!(parse_event(&block).len(), 2);
+        assert!(parse_event(": heartbeat\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_tail_recent_url() {
+        assert_eq!(
+            recent_url("http://gw:8443/", None, 20),
+            "http://gw:8443/api/v1/audit?limit=20"
+        );
+        assert_eq!(
+            recent_url("http://gw:8443", Some("p1"), 5000),
+            "http://gw:8443/api/v1/audit?limit=200&project_id=p1"
+        );
+    }
+}
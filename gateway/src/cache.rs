@@ -5,6 +5,14 @@ use serde::{de::DeserializeOwned, Serialize};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use crate::store::postgres::{CredentialModelRestriction, PgStore, TokenRow};
+
+/// Default number of token rows kept in process by [`TokenCache`].
+pub const DEFAULT_TOKEN_CACHE_CAPACITY: usize = 10_000;
+/// Default lifetime of a cached token row, in both the local tier and Redis.
+/// Kept short so revocations on other instances propagate within seconds.
+pub const DEFAULT_TOKEN_CACHE_TTL_SECS: u64 = 5;
+
 /// Entry stored in the local DashMap with an expiry timestamp.
 #[derive(Clone)]
 pub(crate) struct CacheEntry {
@@ -21,6 +29,7 @@ pub(crate) struct CacheEntry {
 pub struct TieredCache {
     pub(crate) local: Arc<DashMap<String, CacheEntry>>,
     redis: ConnectionManager,
+    tokens: TokenCache,
 }
 
 impl TieredCache {
@@ -28,6 +37,107 @@ impl TieredCache {
         Self {
             local: Arc::new(DashMap::new()),
             redis,
+            tokens: TokenCache::new(DEFAULT_TOKEN_CACHE_CAPACITY, DEFAULT_TOKEN_CACHE_TTL_SECS),
+        }
+    }
+
+    /// Size the hot-token tier used by [`get_token_cached`](Self::get_token_cached).
+    /// A TTL of zero disables token caching (every lookup hits Postgres);
+    /// a capacity of zero keeps only the Redis tier.
+    pub fn with_token_cache(mut self, capacity: usize, ttl_secs: u64) -> Self {
+        self.tokens = TokenCache::new(capacity, ttl_secs);
+        self
+    }
+
+    fn token_key(token_id: &str) -> String {
+        format!("token:{}", token_id)
+    }
+
+    /// Resolve a token through the in-process tier, then Redis, then Postgres,
+    /// populating the faster tiers on the way back. Misses are not cached, so
+    /// a newly created token resolves immediately.
+    pub async fn get_token_cached(
+        &self,
+        db: &PgStore,
+        token_id: &str,
+    ) -> anyhow::Result<Option<TokenRow>> {
+        if self.tokens.ttl.is_zero() {
+            return db.get_token(token_id).await;
+        }
+        if let Some(row) = self.tokens.get(token_id) {
+            return Ok(Some(row));
+        }
+
+        let key = Self::token_key(token_id);
+        let mut conn = self.redis.clone();
+        if let Ok(Some(json)) = conn.get::<_, Option<String>>(&key).await {
+            if let Ok(row) = serde_json::from_str::<TokenRow>(&json) {
+                self.tokens.insert(&row);
+                return Ok(Some(row));
+            }
+        }
+
+        let row = db.get_token(token_id).await?;
+        if let Some(ref row) = row {
+            self.tokens.insert(row);
+            if let Ok(json) = serde_json::to_string(row) {
+                if let Err(e) = conn
+                    .set_ex::<_, _, ()>(&key, json, self.tokens.ttl.as_secs())
+                    .await
+                {
+                    tracing::debug!(token_id = token_id, error = %e, "token cache: redis write failed");
+                }
+            }
+        }
+        Ok(row)
+    }
+
+    /// Drop a token from this instance's tier and from Redis. Call after any
+    /// change to the token row (revoke, rotate, config updates); other
+    /// instances pick the change up once their local entry expires.
+    pub async fn invalidate_token(&self, token_id: &str) {
+        self.tokens.invalidate(token_id);
+        let mut conn = self.redis.clone();
+        if let Err(e) = conn.del::<_, ()>(Self::token_key(token_id)).await {
+            tracing::warn!(token_id = token_id, error = %e, "token cache: redis invalidation failed");
+        }
+    }
+
+    fn credential_models_key(credential_id: uuid::Uuid) -> String {
+        format!("cred_models:{}", credential_id)
+    }
+
+    /// A credential's name, `allowed_models` and `model_rate_limits`, cached
+    /// for the token TTL like the token row that resolved it. Inactive or
+    /// missing credentials are not cached.
+    pub async fn get_credential_model_restriction_cached(
+        &self,
+        db: &PgStore,
+        credential_id: uuid::Uuid,
+    ) -> anyhow::Result<Option<CredentialModelRestriction>> {
+        if self.tokens.ttl.is_zero() {
+            return db.get_credential_model_restriction(credential_id).await;
+        }
+        let key = Self::credential_models_key(credential_id);
+        if let Some(restriction) = self.get::<CredentialModelRestriction>(&key).await {
+            return Ok(Some(restriction));
+        }
+        let restriction = db.get_credential_model_restriction(credential_id).await?;
+        if let Some(ref r) = restriction {
+            if let Err(e) = self.set(&key, r, self.tokens.ttl.as_secs()).await {
+                tracing::debug!(credential_id = %credential_id, error = %e, "credential model cache: redis write failed");
+            }
+        }
+        Ok(restriction)
+    }
+
+    /// Drop a credential's cached model restriction here and in Redis.
+    pub async fn invalidate_credential_model_restriction(&self, credential_id: uuid::Uuid) {
+        let key = Self::credential_models_key(credential_id);
+        self.local.remove(&key);
+        let mut conn = self.redis.clone();
+        if let Err(e) = conn.del::<_, ()>(&key).await {
+            tracing::warn!(credential_id = %credential_id, error = %e, "credential model cache: redis invalidation failed");
         }
     }
 
@@ -101,6 +211,22 @@ impl TieredCache {
         Ok(())
     }
 
+    /// Set `key` in Redis only if it doesn't exist (`SET NX EX`). Returns
+    /// `true` when this call created it. Redis only: a local tier would let
+    /// each replica claim the same key once.
+    pub async fn set_nx(&self, key: &str, ttl_secs: u64) -> anyhow::Result<bool> {
+        let mut conn = self.redis.clone();
+        let created: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_secs.max(1))
+            .query_async(&mut conn)
+            .await?;
+        Ok(created.is_some())
+    }
+
     pub fn invalidate_local(&self, key: &str) {
         self.local.remove(key);
     }
@@ -203,4 +329,317 @@ impl TieredCache {
 
         Ok(count)
     }
+
+    /// Sliding-window sum: records `weight` (e.g. a request's token count)
+    /// and returns the total weight in the window, including this one.
+    /// Weights are kept in the member names of the same sorted-set layout as
+    /// [`increment_sliding_window`](Self::increment_sliding_window).
+    pub async fn add_weighted_sliding_window(
+        &self,
+        key: &str,
+        window_secs: u64,
+        weight: u64,
+    ) -> anyhow::Result<u64> {
+        let mut conn = self.redis.clone();
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)?;
+        let random_suffix: u32 = rand::random();
+        let member = format!("{}_{:08x}:{}", now_ms, random_suffix, weight);
+
+        let script = redis::Script::new(
+            r#"
+            local key = KEYS[1]
+            local now_ms = tonumber(ARGV[1])
+            local window_secs = tonumber(ARGV[2])
+            redis.call("ZADD", key, now_ms, ARGV[3])
+            redis.call("ZREMRANGEBYSCORE", key, "-inf", now_ms - window_secs * 1000)
+            redis.call("EXPIRE", key, window_secs)
+            local total = 0
+            for _, m in ipairs(redis.call("ZRANGE", key, 0, -1)) do
+                total = total + (tonumber(string.match(m, ":(%d+)$")) or 0)
+            end
+            return total
+        "#,
+        );
+
+        let total: u64 = script
+            .key(key)
+            .arg(now_ms)
+            .arg(window_secs)
+            .arg(&member)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(total)
+    }
+
+    /// Total weight currently in a weighted sliding window, without adding
+    /// to it. See [`add_weighted_sliding_window`](Self::add_weighted_sliding_window).
+    pub async fn sum_weighted_sliding_window(
+        &self,
+        key: &str,
+        window_secs: u64,
+    ) -> anyhow::Result<u64> {
+        let mut conn = self.redis.clone();
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)?;
+        let cutoff = now_ms - (window_secs as i64) * 1000;
+        let members: Vec<String> = redis::cmd("ZRANGEBYSCORE")
+            .arg(key)
+            .arg(format!("({}", cutoff))
+            .arg("+inf")
+            .query_async(&mut conn)
+            .await?;
+        Ok(members
+            .iter()
+            .filter_map(|m| m.rsplit_once(':').and_then(|(_, w)| w.parse::<u64>().ok()))
+            .sum())
+    }
+
+    /// Record one request in the `count_key` window and `weight` in the
+    /// `weight_key` window, but only if neither limit would be exceeded.
+    /// Both checks and both writes run in one script, so a rejected request
+    /// consumes nothing. A `None` limit skips that window.
+    pub async fn acquire_sliding_windows(
+        &self,
+        count_key: &str,
+        count_limit: Option<u64>,
+        weight_key: &str,
+        weight_limit: Option<u64>,
+        weight: u64,
+        window_secs: u64,
+    ) -> anyhow::Result<WindowAcquire> {
+        let mut conn = self.redis.clone();
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)?;
+        let random_suffix: u32 = rand::random();
+        let count_member = format!("{}_{:08x}", now_ms, random_suffix);
+        let weight_member = format!("{}_{:08x}:{}", now_ms, random_suffix, weight);
+
+        let script = redis::Script::new(
+            r#"
+            local now_ms = tonumber(ARGV[1])
+            local window_secs = tonumber(ARGV[2])
+            local count_limit = tonumber(ARGV[3])
+            local weight_limit = tonumber(ARGV[4])
+            local weight = tonumber(ARGV[5])
+            local cutoff = now_ms - window_secs * 1000
+            if count_limit >= 0 then
+                redis.call("ZREMRANGEBYSCORE", KEYS[1], "-inf", cutoff)
+                if redis.call("ZCARD", KEYS[1]) >= count_limit then
+                    return 1
+                end
+            end
+            if weight_limit >= 0 then
+                redis.call("ZREMRANGEBYSCORE", KEYS[2], "-inf", cutoff)
+                local total = weight
+                for _, m in ipairs(redis.call("ZRANGE", KEYS[2], 0, -1)) do
+                    total = total + (tonumber(string.match(m, ":(%d+)$")) or 0)
+                end
+                if total > weight_limit then
+                    return 2
+                end
+            end
+            if count_limit >= 0 then
+                redis.call("ZADD", KEYS[1], now_ms, ARGV[6])
+                redis.call("EXPIRE", KEYS[1], window_secs)
+            end
+            if weight_limit >= 0 then
+                redis.call("ZADD", KEYS[2], now_ms, ARGV[7])
+                redis.call("EXPIRE", KEYS[2], window_secs)
+            end
+            return 0
+        "#,
+        );
+
+        let outcome: u8 = script
+            .key(count_key)
+            .key(weight_key)
+            .arg(now_ms)
+            .arg(window_secs)
+            .arg(count_limit.map_or(-1, |l| l as i64))
+            .arg(weight_limit.map_or(-1, |l| l as i64))
+            .arg(weight)
+            .arg(&count_member)
+            .arg(&weight_member)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(match outcome {
+            1 => WindowAcquire::CountFull,
+            2 => WindowAcquire::WeightFull,
+            _ => WindowAcquire::Acquired,
+        })
+    }
+
+    /// Number of entries currently in a sliding window, without recording a
+    /// new one. Used to preview a rate limit without consuming it.
+    pub async fn count_sliding_window(&self, key: &str, window_secs: u64) -> anyhow::Result<u64> {
+        let mut conn = self.redis.clone();
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)?;
+        // Entries at or before the cutoff are the ones the increment script prunes.
+        let cutoff = now_ms - (window_secs as i64) * 1000;
+        let count: u64 = redis::cmd("ZCOUNT")
+            .arg(key)
+            .arg(format!("({}", cutoff))
+            .arg("+inf")
+            .query_async(&mut conn)
+            .await?;
+        Ok(count)
+    }
+}
+
+/// Outcome of [`TieredCache::acquire_sliding_windows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAcquire {
+    /// Both windows had room; the request was recorded in each.
+    Acquired,
+    /// The count window is full; nothing was recorded.
+    CountFull,
+    /// The weight would exceed its limit; nothing was recorded.
+    WeightFull,
+}
+
+/// Bounded in-process tier for hot token rows, keyed by token id.
+///
+/// Entries live for a short TTL. When full, expired entries are swept first,
+/// then the entry closest to expiry (the oldest, since the TTL is uniform)
+/// makes room. Clones share the same map.
+#[derive(Clone)]
+pub struct TokenCache {
+    entries: Arc<DashMap<String, (TokenRow, Instant)>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl TokenCache {
+    pub fn new(capacity: usize, ttl_secs: u64) -> Self {
+        Self {
+            entries: Arc::new(DashMap::new()),
+            capacity,
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.capacity > 0 && !self.ttl.is_zero()
+    }
+
+    /// The cached row if it hasn't expired. Expired entries are removed on lookup.
+    pub fn get(&self, token_id: &str) -> Option<TokenRow> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let now = Instant::now();
+        if let Some(entry) = self.entries.get(token_id) {
+            if entry.1 > now {
+                return Some(entry.0.clone());
+            }
+        }
+        self.entries.remove_if(token_id, |_, e| e.1 <= now);
+        None
+    }
+
+    pub fn insert(&self, row: &TokenRow) {
+        if !self.is_enabled() {
+            return;
+        }
+        let now = Instant::now();
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&row.id) {
+            self.entries.retain(|_, e| e.1 > now);
+            if self.entries.len() >= self.capacity {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|e| e.value().1)
+                    .map(|e| e.key().clone());
+                if let Some(id) = oldest {
+                    self.entries.remove(&id);
+                }
+            }
+        }
+        self.entries
+            .insert(row.id.clone(), (row.clone(), now + self.ttl));
+    }
+
+    pub fn invalidate(&self, token_id: &str) {
+        self.entries.remove(token_id);
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(id: &str) -> TokenRow {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "project_id": uuid::Uuid::nil(),
+            "name": "cached",
+            "upstream_url": "https://api.openai.com",
+            "scopes": [],
+            "policy_ids": [],
+            "is_active": true,
+            "created_at": "2026-01-01T00:00:00Z",
+            "log_level": 1,
+            "session_cost_header": false,
+            "stream_ttft_comment": false,
+            "serve_stale_on_error": false,
+            "json_mode_fallback": false
+        }))
+        .unwrap()
+    }
+
+    /// Revoking on one instance drops its entry at once; another instance's
+    /// entry stops resolving as soon as its TTL runs out.
+    #[test]
+    fn test_revoked_token_stops_resolving_within_ttl() {
+        let here = TokenCache::new(16, DEFAULT_TOKEN_CACHE_TTL_SECS);
+        let elsewhere = TokenCache::new(16, DEFAULT_TOKEN_CACHE_TTL_SECS);
+        here.insert(&token("tf_v1_a"));
+        elsewhere.insert(&token("tf_v1_a"));
+        assert_eq!(here.get("tf_v1_a").unwrap().name, "cached");
+
+        here.clone().invalidate("tf_v1_a");
+        assert!(here.get("tf_v1_a").is_none());
+        assert!(elsewhere.get("tf_v1_a").is_some());
+
+        if let Some(mut e) = elsewhere.entries.get_mut("tf_v1_a") {
+            assert!(e.1 <= Instant::now() + Duration::from_secs(DEFAULT_TOKEN_CACHE_TTL_SECS));
+            e.1 = Instant::now() - Duration::from_millis(1);
+        }
+        assert!(elsewhere.get("tf_v1_a").is_none());
+        assert_eq!(elsewhere.len(), 0);
+    }
+
+    #[test]
+    fn test_token_cache_is_bounded() {
+        let cache = TokenCache::new(2, 5);
+        cache.insert(&token("t1"));
+        cache.insert(&token("t2"));
+        if let Some(mut e) = cache.entries.get_mut("t1") {
+            e.1 = Instant::now() + Duration::from_secs(1);
+        }
+        cache.insert(&token("t3"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("t1").is_none(), "oldest entry is evicted");
+        assert!(cache.get("t3").is_some());
+
+        // Refreshing a cached token doesn't evict anything.
+        cache.insert(&token("t3"));
+        assert_eq!(cache.len(), 2);
+
+        let disabled = TokenCache::new(0, 5);
+        disabled.insert(&token("t1"));
+        assert!(disabled.get("t1").is_none());
+        assert!(TokenCache::new(16, 0).get("t1").is_none());
+    }
 }
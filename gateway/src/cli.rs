@@ -40,6 +40,18 @@ pub enum Commands {
         #[command(subcommand)]
         command: PolicyCommands,
     },
+
+    /// Manage model pricing
+    Pricing {
+        #[command(subcommand)]
+        command: PricingCommands,
+    },
+
+    /// Inspect audit logs
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
 }
 
 #[derive(Subcommand)]
@@ -56,6 +68,9 @@ pub enum TokenCommands {
         project_id: Option<String>,
         #[arg(long, value_delimiter = ',')]
         policy_ids: Option<Vec<String>>,
+        /// Don't attach the project's default policies
+        #[arg(long)]
+        skip_defaults: bool,
     },
     /// List tokens for a project
     List {
@@ -87,6 +102,9 @@ pub enum CredentialCommands {
         /// Header name (or query param name) for injection
         #[arg(long, default_value = "Authorization")]
         header: String,
+        /// Comma-separated model patterns this credential may serve (e.g. gpt-4o-mini,text-embedding-*)
+        #[arg(long, value_delimiter = ',')]
+        allowed_models: Option<Vec<String>>,
     },
     /// List stored credentials (metadata only)
     List {
@@ -141,3 +159,54 @@ pub enum PolicyCommands {
         id: String,
     },
 }
+
+#[derive(Subcommand)]
+pub enum PricingCommands {
+    /// Bulk upsert pricing from a CSV or JSON file
+    /// (columns: provider, model_pattern, input_per_m, output_per_m)
+    Import {
+        /// Path to the pricing file
+        file: String,
+        /// File format: csv or json (default: from the file extension)
+        #[arg(long)]
+        format: Option<String>,
+        /// Validate and report without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// Print the most recent audit entries, optionally following new ones
+    Tail {
+        /// Only show requests made with this token ID
+        #[arg(long)]
+        token: Option<String>,
+        /// Only show this upstream status: an exact code (429) or a class (4xx, 5xx)
+        #[arg(long)]
+        status: Option<String>,
+        /// Only show requests costing at least this many USD
+        #[arg(long)]
+        min_cost: Option<f64>,
+        /// Project to read (default: the admin key's default project)
+        #[arg(long, alias = "project")]
+        project_id: Option<String>,
+        /// Number of recent entries to print before following (max 200)
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: i64,
+        /// Keep running and print new entries as they are logged
+        #[arg(short, long)]
+        follow: bool,
+        /// Gateway base URL
+        #[arg(
+            long,
+            env = "TRUEFLOW_GATEWAY_URL",
+            default_value = "http://localhost:8443"
+        )]
+        url: String,
+        /// Print raw JSON lines instead of formatted output
+        #[arg(long)]
+        json: bool,
+    },
+}
@@ -14,10 +14,15 @@ use thiserror::Error;
 ///     "message":    "Daily spend cap of $50.00 reached (USD)",
 ///     "request_id": "req_01J9...",
 ///     "type":       "billing_error",
-///     "details":    { ... }
+///     "details":    { ... },
+///     "remediation": { "action": "wait_for_reset", ... }
 ///   }
 /// }
 /// ```
+///
+/// `remediation` is present on denials (policy, content, rate limit, spend
+/// cap, size/context limits) and always carries an `action`; see
+/// [`AppError::remediation`].
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("token not found")]
@@ -29,27 +34,94 @@ pub enum AppError {
     #[error("credential missing")]
     CredentialMissing,
 
+    #[error("credential decryption failed: {credential_id}")]
+    CredentialDecryptionFailed { credential_id: String },
+
+    #[error("upstream rejected credential {credential_id} with {upstream_status}")]
+    CredentialAuthFailed {
+        credential_id: String,
+        upstream_status: u16,
+    },
+
     #[error("policy denied: {reason}")]
     PolicyDenied { policy: String, reason: String },
 
     #[error("forbidden: {0}")]
     Forbidden(String),
 
+    #[error("model not allowed: {model}")]
+    ModelNotAllowed { model: String, allowed: Vec<String> },
+
+    #[error("replay protection rejected the request: {}", reason.code())]
+    ReplayRejected {
+        reason: crate::middleware::replay::ReplayRejection,
+        window_secs: u64,
+    },
+
     #[error("approval timeout")]
     ApprovalTimeout,
 
     #[error("approval rejected")]
     ApprovalRejected,
 
+    #[error("request budget of {budget_secs}s exceeded after {elapsed_ms}ms")]
+    RequestBudgetExceeded { budget_secs: u64, elapsed_ms: u64 },
+
+    #[error("policy evaluation exceeded its {budget_ms}ms budget")]
+    PolicyEvalTimeout {
+        /// The slowest policy step, if any was timed.
+        policy: Option<String>,
+        budget_ms: u64,
+        elapsed_ms: u64,
+    },
+
     #[error("rate limit exceeded")]
-    RateLimitExceeded { retry_after_secs: u64 },
+    RateLimitExceeded {
+        retry_after_secs: u64,
+        /// Requests allowed per window, when known.
+        limit: Option<u64>,
+    },
+
+    #[error("concurrent stream limit reached ({scope} limit {limit})")]
+    StreamLimitExceeded {
+        /// `global` or `token`.
+        scope: &'static str,
+        limit: u64,
+    },
 
     #[error("spend cap reached: {message}")]
-    SpendCapReached { message: String },
+    SpendCapReached {
+        message: String,
+        /// Which cap: `daily`, `monthly`, `lifetime`, `project`, `team` or `session`.
+        cap: Option<String>,
+        /// When the cap resets, if it does.
+        resets_at: Option<chrono::DateTime<chrono::Utc>>,
+    },
 
     #[error("payload too large")]
     PayloadTooLarge,
 
+    #[error("payload exceeds the JSON {limit} limit of {max}")]
+    PayloadTooComplex {
+        /// Which limit: `depth` or `array_length`.
+        limit: &'static str,
+        max: usize,
+    },
+
+    #[error("context window of {context_window} tokens exceeded for {model} (~{estimated_tokens} estimated)")]
+    ContextWindowExceeded {
+        model: String,
+        estimated_tokens: u32,
+        context_window: u32,
+    },
+
+    #[error("{model} does not support: {}", features.join(", "))]
+    UnsupportedModelFeature {
+        model: String,
+        /// Feature names: `tools`, `vision`, `json_mode`.
+        features: Vec<String>,
+    },
+
     #[error("content blocked: {reason}")]
     ContentBlocked {
         reason: String,
@@ -111,6 +183,25 @@ impl AppError {
                 "This token has been revoked. Create a new one at your TrueFlow dashboard.".to_string(),
                 None,
             ),
+            AppError::ReplayRejected {
+                reason,
+                window_secs,
+            } => {
+                use crate::middleware::replay::ReplayRejection;
+                let msg = match reason {
+                    ReplayRejection::MissingHeaders => "This token requires replay protection: send a unique X-TrueFlow-Nonce and the current unix time in X-TrueFlow-Timestamp.".to_string(),
+                    ReplayRejection::InvalidHeaders => format!("X-TrueFlow-Nonce must be 1-{} printable ASCII characters and X-TrueFlow-Timestamp an integer unix time in seconds.", crate::middleware::replay::MAX_NONCE_LEN),
+                    ReplayRejection::StaleTimestamp => format!("X-TrueFlow-Timestamp is more than {}s from the gateway clock. Send the current time and check the client clock.", window_secs),
+                    ReplayRejection::NonceReused => "This nonce was already used with this token. Send a new nonce with every request.".to_string(),
+                };
+                (
+                    StatusCode::UNAUTHORIZED,
+                    "authentication_error",
+                    reason.code(),
+                    msg,
+                    Some(json!({ "window_secs": window_secs })),
+                )
+            }
             AppError::CredentialMissing => (
                 StatusCode::BAD_GATEWAY,
                 "configuration_error",
@@ -118,6 +209,36 @@ impl AppError {
                 "The credential linked to this token no longer exists. Re-attach a credential via the dashboard.".to_string(),
                 None,
             ),
+            AppError::CredentialDecryptionFailed { credential_id } => {
+                tracing::error!(
+                    credential_id = %credential_id,
+                    "Credential decryption failed — check TRUEFLOW_MASTER_KEY or the stored ciphertext"
+                );
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "configuration_error",
+                    "credential_decryption_failed",
+                    "The credential linked to this token could not be decrypted. The gateway master key may be misconfigured or the stored credential is corrupted — contact the gateway operator.".to_string(),
+                    None,
+                )
+            }
+            AppError::CredentialAuthFailed {
+                credential_id,
+                upstream_status,
+            } => {
+                tracing::warn!(
+                    credential_id = %credential_id,
+                    upstream_status = upstream_status,
+                    "Upstream rejected stored credential — rotate the key"
+                );
+                (
+                    StatusCode::BAD_GATEWAY,
+                    "configuration_error",
+                    "credential_auth_failed",
+                    "The upstream provider rejected the credential linked to this token. It may be invalid, expired or revoked — contact the gateway operator.".to_string(),
+                    Some(json!({ "upstream_status": upstream_status })),
+                )
+            }
             AppError::PolicyDenied { policy, reason } => (
                 StatusCode::FORBIDDEN,
                 "permission_error",
@@ -132,6 +253,17 @@ impl AppError {
                 reason.clone(),
                 None,
             ),
+            AppError::ModelNotAllowed { model, allowed } => (
+                StatusCode::FORBIDDEN,
+                "permission_error",
+                "model_not_allowed",
+                format!(
+                    "Model '{}' is not allowed by this API key. Allowed: [{}]",
+                    model,
+                    allowed.join(", ")
+                ),
+                Some(json!({ "model": model, "allowed_models": allowed })),
+            ),
             AppError::ApprovalTimeout => (
                 StatusCode::REQUEST_TIMEOUT,
                 "timeout_error",
@@ -139,6 +271,37 @@ impl AppError {
                 "Request timed out waiting for human approval.".to_string(),
                 None,
             ),
+            AppError::RequestBudgetExceeded {
+                budget_secs,
+                elapsed_ms,
+            } => (
+                StatusCode::REQUEST_TIMEOUT,
+                "timeout_error",
+                "request_budget_exceeded",
+                format!(
+                    "Request exceeded the token's end-to-end budget of {}s ({}ms elapsed, including approval wait). It was not forwarded upstream.",
+                    budget_secs, elapsed_ms
+                ),
+                None,
+            ),
+            AppError::PolicyEvalTimeout {
+                policy,
+                budget_ms,
+                elapsed_ms,
+            } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "timeout_error",
+                "policy_evaluation_timeout",
+                format!(
+                    "Policy evaluation exceeded its {}ms budget ({}ms elapsed). The request was not forwarded upstream.",
+                    budget_ms, elapsed_ms
+                ),
+                Some(json!({
+                    "policy": policy,
+                    "budget_ms": budget_ms,
+                    "elapsed_ms": elapsed_ms,
+                })),
+            ),
             AppError::ApprovalRejected => (
                 StatusCode::FORBIDDEN,
                 "permission_error",
@@ -153,7 +316,17 @@ impl AppError {
                 "Rate limit exceeded. Retry after the number of seconds in the Retry-After header.".to_string(),
                 None,
             ),
-            AppError::SpendCapReached { message } => (
+            AppError::StreamLimitExceeded { scope, limit } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limit_error",
+                "concurrent_stream_limit",
+                format!(
+                    "Too many concurrent streaming requests ({} limit of {}). Retry once a stream finishes.",
+                    scope, limit
+                ),
+                Some(json!({ "scope": scope, "limit": limit })),
+            ),
+            AppError::SpendCapReached { message, .. } => (
                 StatusCode::PAYMENT_REQUIRED,
                 "billing_error",
                 "spend_cap_reached",
@@ -167,6 +340,48 @@ impl AppError {
                 "Request body exceeds the maximum allowed size.".to_string(),
                 None,
             ),
+            AppError::PayloadTooComplex { limit, max } => (
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                "payload_too_complex",
+                match *limit {
+                    "depth" => format!("Request body is nested more than {} levels deep.", max),
+                    _ => format!("Request body contains an array with more than {} elements.", max),
+                },
+                Some(json!({ "limit": limit, "max": max })),
+            ),
+            AppError::ContextWindowExceeded {
+                model,
+                estimated_tokens,
+                context_window,
+            } => (
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                "context_length_exceeded",
+                format!(
+                    "This request needs an estimated {} tokens (prompt plus max_tokens), but {}'s context window is {} tokens. Shorten the conversation or lower max_tokens. It was not forwarded upstream.",
+                    estimated_tokens, model, context_window
+                ),
+                Some(json!({
+                    "model": model,
+                    "estimated_tokens": estimated_tokens,
+                    "context_window": context_window,
+                })),
+            ),
+            AppError::UnsupportedModelFeature { model, features } => (
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                "unsupported_model_feature",
+                format!(
+                    "{} does not support {}. Remove the corresponding request fields or choose a model that supports them. It was not forwarded upstream.",
+                    model,
+                    features.join(", ")
+                ),
+                Some(json!({
+                    "model": model,
+                    "unsupported_features": features,
+                })),
+            ),
             AppError::ContentBlocked { reason, details } => (
                 StatusCode::FORBIDDEN,
                 "content_policy_error",
@@ -244,6 +459,9 @@ impl AppError {
         if let Some(d) = details {
             error_obj["details"] = d;
         }
+        if let Some(r) = self.remediation() {
+            error_obj["remediation"] = r;
+        }
 
         let body = Json(json!({ "error": error_obj }));
         let mut response = (status, body).into_response();
@@ -254,7 +472,15 @@ impl AppError {
         }
 
         // Retry-After and X-RateLimit-Reset headers for rate limit responses
-        if let AppError::RateLimitExceeded { retry_after_secs } = &self {
+        if let AppError::StreamLimitExceeded { .. } = &self {
+            response
+                .headers_mut()
+                .insert("retry-after", axum::http::HeaderValue::from_static("1"));
+        }
+        if let AppError::RateLimitExceeded {
+            retry_after_secs, ..
+        } = &self
+        {
             let retry_after = retry_after_secs.to_string();
             let reset_at = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -270,6 +496,100 @@ impl AppError {
 
         response
     }
+
+    /// Machine-readable hint for denied requests, emitted as
+    /// `error.remediation`. `action` is one of:
+    /// - `retry_after`: rate limited; retry after `retry_after_secs`
+    /// - `wait_for_reset` / `raise_cap`: spend cap hit; it resets at
+    ///   `resets_at`, or never (lifetime, project, team and session caps)
+    /// - `remove_content`: request content blocked; remove what's in `matched`
+    /// - `rephrase_request`: the response was blocked by an output guardrail
+    /// - `shorten_request`: prompt plus `max_tokens` over the context window
+    /// - `remove_unsupported_features`: the model lacks `unsupported_features`
+    /// - `reduce_payload`: body over the size, nesting-depth or array-length limit
+    /// - `retry`: timed out (approval wait or request budget); safe to retry
+    /// - `contact_admin`: denied by configuration the caller can't change
+    ///
+    /// `None` for non-denial errors.
+    pub fn remediation(&self) -> Option<Value> {
+        let remediation = match self {
+            AppError::RateLimitExceeded {
+                retry_after_secs,
+                limit,
+            } => json!({
+                "action": "retry_after",
+                "retry_after_secs": retry_after_secs,
+                "limit": limit,
+            }),
+            AppError::StreamLimitExceeded { scope, limit } => json!({
+                "action": "retry_after",
+                "retry_after_secs": 1,
+                "limit": limit,
+                "scope": scope,
+            }),
+            AppError::SpendCapReached { cap, resets_at, .. } => json!({
+                "action": if resets_at.is_some() { "wait_for_reset" } else { "raise_cap" },
+                "cap": cap,
+                "resets_at": resets_at,
+                "retry_after_secs": resets_at
+                    .map(|at| (at - chrono::Utc::now()).num_seconds().max(0)),
+            }),
+            AppError::ContentBlocked { details, .. } => {
+                let detail = |key: &str| details.as_ref().and_then(|d| d.get(key)).cloned();
+                let response_phase = detail("phase").is_some_and(|p| p == "response");
+                json!({
+                    "action": if response_phase { "rephrase_request" } else { "remove_content" },
+                    "policy": detail("policy"),
+                    "matched": detail("matched_patterns")
+                        .or_else(|| detail("detected_pii"))
+                        .unwrap_or_else(|| json!([])),
+                })
+            }
+            AppError::ContextWindowExceeded {
+                estimated_tokens,
+                context_window,
+                ..
+            } => json!({
+                "action": "shorten_request",
+                "context_window": context_window,
+                "estimated_tokens": estimated_tokens,
+                "excess_tokens": estimated_tokens.saturating_sub(*context_window),
+            }),
+            AppError::UnsupportedModelFeature { model, features } => json!({
+                "action": "remove_unsupported_features",
+                "model": model,
+                "unsupported_features": features,
+            }),
+            AppError::PayloadTooLarge => json!({ "action": "reduce_payload" }),
+            AppError::PayloadTooComplex { limit, max } => json!({
+                "action": "reduce_payload",
+                "limit": limit,
+                "max": max,
+            }),
+            AppError::PolicyDenied { policy, .. } => json!({
+                "action": "contact_admin",
+                "policy": policy,
+            }),
+            AppError::Forbidden(_) | AppError::ApprovalRejected => {
+                json!({ "action": "contact_admin" })
+            }
+            AppError::ModelNotAllowed { allowed, .. } => json!({
+                "action": "contact_admin",
+                "allowed_models": allowed,
+            }),
+            AppError::ApprovalTimeout => json!({ "action": "retry" }),
+            AppError::RequestBudgetExceeded { budget_secs, .. } => json!({
+                "action": "retry",
+                "budget_secs": budget_secs,
+            }),
+            AppError::PolicyEvalTimeout { policy, .. } => json!({
+                "action": "contact_admin",
+                "policy": policy,
+            }),
+            _ => return None,
+        };
+        Some(remediation)
+    }
 }
 
 /// Convenience: convert old-style `SpendCapReached` (no message) usages
@@ -277,6 +597,8 @@ impl From<&str> for AppError {
     fn from(msg: &str) -> Self {
         AppError::SpendCapReached {
             message: msg.to_string(),
+            cap: None,
+            resets_at: None,
         }
     }
 }
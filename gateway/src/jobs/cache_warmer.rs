@@ -0,0 +1,75 @@
+//! Background job: keep hot prompts in the response cache.
+//!
+//! Runs when both `TRUEFLOW_CACHE_WARM_THRESHOLD` and `TRUEFLOW_CACHE_AUTO_WARM`
+//! are set. Every 60 seconds re-primes the hot cache keys (see
+//! `proxy::cache_warm`) whose entries have expired, so a popular prompt is
+//! uncached for at most a minute. Keys stop being warmed once they are no
+//! longer missed often enough to stay hot.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+use crate::proxy::cache_warm::{self, WarmOutcome};
+use crate::AppState;
+
+/// Upper bound on keys re-primed per pass.
+const MAX_KEYS_PER_PASS: usize = 50;
+
+/// Spawn the background warmer. Call this once at startup.
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            let state = state.clone();
+            let result = tokio::spawn(async move {
+                let mut interval = time::interval(Duration::from_secs(60));
+                loop {
+                    interval.tick().await;
+                    run_once(&state).await;
+                }
+            })
+            .await;
+            if let Err(e) = result {
+                tracing::error!("Cache warmer job panicked: {:?}", e);
+                time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    });
+}
+
+async fn run_once(state: &Arc<AppState>) {
+    let results = match cache_warm::warm_hot_keys(
+        state,
+        state.config.cache_warm_threshold,
+        MAX_KEYS_PER_PASS,
+    )
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!(error = %e, "cache_warmer: failed to read hot keys");
+            return;
+        }
+    };
+    let warmed = results
+        .iter()
+        .filter(|(_, o)| matches!(o, WarmOutcome::Warmed { .. }))
+        .count();
+    for (hot, outcome) in &results {
+        if let WarmOutcome::Failed { status, reason } = outcome {
+            tracing::warn!(
+                cache_key = %hot.cache_key,
+                status,
+                reason = %reason,
+                "cache_warmer: re-prime failed"
+            );
+        }
+    }
+    if warmed > 0 {
+        tracing::info!(
+            warmed,
+            hot = results.len(),
+            "cache_warmer: re-primed hot keys"
+        );
+    }
+}
@@ -1,4 +1,6 @@
 pub mod approval_expiry;
 pub mod budget_checker;
+pub mod cache_warmer;
 pub mod cleanup;
 pub mod session_cleanup;
+pub mod upstream_health;
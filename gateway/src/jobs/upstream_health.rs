@@ -0,0 +1,69 @@
+//! Background job: persist upstream health history.
+//!
+//! Every 60 seconds drains the load balancer's in-memory `HealthHistory`
+//! (latency samples + circuit transitions) into Postgres. Once an hour,
+//! rows older than `TRUEFLOW_UPSTREAM_HEALTH_RETENTION_DAYS` are pruned.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+use crate::AppState;
+
+/// Flushes per prune pass (60 × 60s = hourly).
+const FLUSHES_PER_PRUNE: u32 = 60;
+
+/// Spawn the background flush task. Call this once at startup.
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            let state = state.clone();
+            let result = tokio::spawn(async move {
+                let mut interval = time::interval(Duration::from_secs(60));
+                let mut ticks: u32 = 0;
+                loop {
+                    interval.tick().await;
+                    flush(&state).await;
+                    ticks += 1;
+                    if ticks >= FLUSHES_PER_PRUNE {
+                        ticks = 0;
+                        prune(&state).await;
+                    }
+                }
+            })
+            .await;
+            if let Err(e) = result {
+                tracing::error!("Upstream health job panicked: {:?}", e);
+                time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    });
+}
+
+async fn flush(state: &AppState) {
+    let (samples, transitions) = state.lb.history().drain();
+    if samples.is_empty() && transitions.is_empty() {
+        return;
+    }
+    if let Err(e) = state
+        .db
+        .insert_upstream_health(&samples, &transitions)
+        .await
+    {
+        tracing::error!(
+            samples = samples.len(),
+            transitions = transitions.len(),
+            "upstream health flush failed: {}",
+            e
+        );
+    }
+}
+
+async fn prune(state: &AppState) {
+    let days = state.config.upstream_health_retention_days as i32;
+    match state.db.prune_upstream_health(days).await {
+        Ok(0) => {}
+        Ok(rows) => tracing::info!(rows, days, "pruned upstream health history"),
+        Err(e) => tracing::error!("upstream health prune failed: {}", e),
+    }
+}
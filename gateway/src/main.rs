@@ -6,9 +6,11 @@ use axum::extract::DefaultBodyLimit;
 use axum::routing::any;
 use clap::Parser;
 use tower_http::cors::CorsLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing::Instrument;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 mod api;
+mod audit_tail;
 mod cache;
 mod cli;
 mod config;
@@ -31,7 +33,10 @@ use vault::builtin::BuiltinStore;
 /// Shared application state passed to handlers and middleware.
 pub struct AppState {
     pub db: PgStore,
-    pub vault: BuiltinStore,
+    pub vault: Arc<BuiltinStore>,
+    /// Where the proxy reads credential secrets (`TRUEFLOW_VAULT_BACKEND`):
+    /// `vault` itself, or GCP Secret Manager.
+    pub secrets: Arc<dyn vault::SecretStore>,
     pub cache: TieredCache,
     pub upstream_client: proxy::upstream::UpstreamClient,
     pub notifier: notification::slack::SlackNotifier,
@@ -41,12 +46,28 @@ pub struct AppState {
     pub pricing: models::pricing_cache::PricingCache,
     /// p50 latency per model (refreshed every 5min from audit_logs).
     pub latency: models::latency_cache::LatencyCache,
+    /// Deprecated model -> replacement (the `deprecated_model_map` setting).
+    pub model_remap: models::model_remap::ModelRemapCache,
+    /// Per-model context-window overrides (`model_context_windows` setting).
+    pub context_windows: models::tokenizer::ContextWindowTable,
+    /// Per-model capability overrides (`model_capabilities` setting).
+    pub capabilities: models::capabilities::CapabilityTable,
+    /// Content-category tagging rules (`content_category_rules` setting).
+    pub content_categories: middleware::guardrail::category::ContentCategoryTable,
     /// Payload storage backend — Postgres (default) or S3/MinIO/local.
     pub payload_store: Arc<PayloadStore>,
     /// Observability exporters: Prometheus, Langfuse, DataDog.
     pub observer: Arc<middleware::observer::ObserverHub>,
     /// MCP server registry — manages connections and cached tool schemas.
     pub mcp_registry: Arc<mcp::registry::McpRegistry>,
+    /// Caps concurrent async-guardrail evaluations (vendor/webhook calls).
+    pub async_guardrail_permits: Arc<tokio::sync::Semaphore>,
+    /// External audit sinks (HTTP batch, Kafka, OTLP logs) fed alongside the DB writer.
+    pub audit_sinks: Arc<middleware::audit_sink::AuditSinkHub>,
+    /// Per-token AIMD controllers for tokens with `adaptive_rate_limit`.
+    pub adaptive_limits: proxy::adaptive_limit::AdaptiveLimiter,
+    /// Active streaming responses, checked against the global and per-token caps.
+    pub streams: proxy::stream_limit::StreamLimiter,
 }
 
 #[tokio::main]
@@ -78,34 +99,37 @@ async fn main() -> anyhow::Result<()> {
         std::env::var("RUST_LOG").unwrap_or_else(|_| "gateway=debug,tower_http=debug".into()),
     );
 
-    // SIEM-ready JSON logs: set TRUEFLOW_LOG_FORMAT=json for structured output
-    // compatible with Splunk, Datadog, ELK, CloudWatch.
-    let use_json = std::env::var("TRUEFLOW_LOG_FORMAT")
-        .map(|v| v.eq_ignore_ascii_case("json"))
-        .unwrap_or(false);
-
-    let json_layer = if use_json {
-        Some(
-            tracing_subscriber::fmt::layer()
-                .json()
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_span_list(true)
-                .flatten_event(true),
-        )
-    } else {
-        None
-    };
-    let plain_layer = if !use_json {
-        Some(tracing_subscriber::fmt::layer())
-    } else {
-        None
+    // SIEM-ready JSON logs: set LOG_FORMAT=json (or TRUEFLOW_LOG_FORMAT=json) for
+    // structured output compatible with Loki, Splunk, Datadog, ELK, CloudWatch.
+    // `pretty` (default) is the human-readable format; `compact` drops span context.
+    let log_format = std::env::var("LOG_FORMAT")
+        .or_else(|_| std::env::var("TRUEFLOW_LOG_FORMAT"))
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let fmt_layer = match log_format.as_str() {
+        "json" => tracing_subscriber::fmt::layer()
+            .json()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .flatten_event(true)
+            .boxed(),
+        "compact" => tracing_subscriber::fmt::layer().compact().boxed(),
+        "" | "pretty" => tracing_subscriber::fmt::layer().boxed(),
+        other => {
+            eprintln!(
+                "⚠️  Unknown LOG_FORMAT '{}' — falling back to pretty.",
+                other
+            );
+            tracing_subscriber::fmt::layer().boxed()
+        }
     };
 
     tracing_subscriber::registry()
         .with(env_filter)
-        .with(json_layer)
-        .with(plain_layer)
+        .with(fmt_layer)
         .with(telemetry_layer)
         .init();
 
@@ -116,7 +140,8 @@ async fn main() -> anyhow::Result<()> {
         Some(cli::Commands::Serve { port }) => run_server(cfg, port).await,
         Some(cli::Commands::Token { command }) => {
             let db = PgStore::connect(&cfg.database_url).await?;
-            let vault = BuiltinStore::new(&cfg.master_key, db.pool().clone())?;
+            let vault = Arc::new(BuiltinStore::new(&cfg.master_key, db.pool().clone())?);
+            let secrets = secret_store(&cfg, &vault)?;
             let redis_client = redis::Client::open(cfg.redis_url.as_str())?;
             let redis_conn = redis::aio::ConnectionManager::new(redis_client).await?;
             let cache = TieredCache::new(redis_conn);
@@ -124,9 +149,12 @@ async fn main() -> anyhow::Result<()> {
             let notifier = notification::slack::SlackNotifier::new(cfg.slack_webhook_url.clone());
 
             let lb_redis = cache.redis();
+            let async_guardrail_permits =
+                Arc::new(tokio::sync::Semaphore::new(cfg.async_guardrail_concurrency));
             let state = Arc::new(AppState {
                 db,
                 vault,
+                secrets,
                 cache,
                 upstream_client,
                 notifier,
@@ -135,9 +163,17 @@ async fn main() -> anyhow::Result<()> {
                 lb: proxy::loadbalancer::LoadBalancer::new_with_redis(lb_redis),
                 pricing: models::pricing_cache::PricingCache::new(),
                 latency: models::latency_cache::LatencyCache::new(),
+                model_remap: models::model_remap::ModelRemapCache::new(),
+                context_windows: models::tokenizer::ContextWindowTable::new(),
+                capabilities: models::capabilities::CapabilityTable::new(),
+                content_categories: middleware::guardrail::category::ContentCategoryTable::new(),
                 payload_store: Arc::new(PayloadStore::from_env().unwrap_or(PayloadStore::Postgres)),
                 observer: Arc::new(middleware::observer::ObserverHub::from_env()),
                 mcp_registry: Arc::new(mcp::registry::McpRegistry::new()),
+                async_guardrail_permits,
+                audit_sinks: Arc::new(middleware::audit_sink::AuditSinkHub::default()),
+                adaptive_limits: proxy::adaptive_limit::AdaptiveLimiter::new(),
+                streams: proxy::stream_limit::StreamLimiter::new(),
             });
 
             handle_token_command(command, &state).await
@@ -152,7 +188,8 @@ async fn main() -> anyhow::Result<()> {
         }
         Some(cli::Commands::Policy { command }) => {
             let db = PgStore::connect(&cfg.database_url).await?;
-            let vault = BuiltinStore::new(&cfg.master_key, db.pool().clone())?;
+            let vault = Arc::new(BuiltinStore::new(&cfg.master_key, db.pool().clone())?);
+            let secrets = secret_store(&cfg, &vault)?;
             let redis_client = redis::Client::open(cfg.redis_url.as_str())?;
             let redis_conn = redis::aio::ConnectionManager::new(redis_client).await?;
             let cache = TieredCache::new(redis_conn);
@@ -160,9 +197,12 @@ async fn main() -> anyhow::Result<()> {
             let notifier = notification::slack::SlackNotifier::new(cfg.slack_webhook_url.clone());
 
             let lb_redis = cache.redis();
+            let async_guardrail_permits =
+                Arc::new(tokio::sync::Semaphore::new(cfg.async_guardrail_concurrency));
             let state = Arc::new(AppState {
                 db,
                 vault,
+                secrets,
                 cache,
                 upstream_client,
                 notifier,
@@ -171,13 +211,26 @@ async fn main() -> anyhow::Result<()> {
                 lb: proxy::loadbalancer::LoadBalancer::new_with_redis(lb_redis),
                 pricing: models::pricing_cache::PricingCache::new(),
                 latency: models::latency_cache::LatencyCache::new(),
+                model_remap: models::model_remap::ModelRemapCache::new(),
+                context_windows: models::tokenizer::ContextWindowTable::new(),
+                capabilities: models::capabilities::CapabilityTable::new(),
+                content_categories: middleware::guardrail::category::ContentCategoryTable::new(),
                 payload_store: Arc::new(PayloadStore::from_env().unwrap_or(PayloadStore::Postgres)),
                 observer: Arc::new(middleware::observer::ObserverHub::from_env()),
                 mcp_registry: Arc::new(mcp::registry::McpRegistry::new()),
+                async_guardrail_permits,
+                audit_sinks: Arc::new(middleware::audit_sink::AuditSinkHub::default()),
+                adaptive_limits: proxy::adaptive_limit::AdaptiveLimiter::new(),
+                streams: proxy::stream_limit::StreamLimiter::new(),
             });
 
             handle_policy_command(command, &state).await
         }
+        Some(cli::Commands::Pricing { command }) => {
+            let db = PgStore::connect(&cfg.database_url).await?;
+            handle_pricing_command(&db, command).await
+        }
+        Some(cli::Commands::Audit { command }) => handle_audit_command(&cfg, command).await,
         None => run_server(cfg, 8443).await,
     };
 
@@ -188,14 +241,68 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
+    if cfg.dev_mode {
+        if api::superadmin_env_key() == api::INSECURE_DEFAULT_ADMIN_KEY {
+            tracing::warn!(
+                "⚠️  DEV MODE: the insecure default admin key CHANGE_ME_INSECURE_DEFAULT is ACCEPTED. \
+                 Anyone who can reach the management API has SuperAdmin access. \
+                 Set TRUEFLOW_ADMIN_KEY and unset TRUEFLOW_DEV_MODE before exposing this gateway."
+            );
+        } else {
+            tracing::warn!("Running in dev mode (TRUEFLOW_DEV_MODE) — not for production use");
+        }
+    } else {
+        tracing::info!("Running in secure mode: the default admin key is refused");
+    }
+
     tracing::info!("Connecting to database...");
-    let db = PgStore::connect(&cfg.database_url).await?;
+    let mut db = PgStore::connect(&cfg.database_url).await?;
+    if let Some(ref read_url) = cfg.database_read_url {
+        tracing::info!("Attaching analytics read replica...");
+        db = db.with_read_replica(read_url).await;
+    }
 
     tracing::info!("Running migrations...");
     db.migrate().await?;
 
     tracing::info!("Initializing vault...");
-    let vault = BuiltinStore::new(&cfg.master_key, db.pool().clone())?;
+    let vault = BuiltinStore::new(&cfg.master_key, db.pool().clone())?
+        .with_cache_ttl(cfg.credential_cache_ttl_secs);
+    if cfg.credential_cache_ttl_secs > 0 {
+        tracing::warn!(
+            ttl_secs = cfg
+                .credential_cache_ttl_secs
+                .min(vault::cache::MAX_SECRET_CACHE_TTL_SECS),
+            "Decrypted-credential cache enabled: plaintext secrets are held in memory for up to the TTL"
+        );
+    }
+    // Test-decrypt a stored credential so a wrong master key shows up at boot
+    // rather than as per-request failures. Not fatal: tokens in passthrough
+    // mode keep working and operators may be mid key-rotation.
+    match vault.self_test().await {
+        Ok(Some(cred_id)) => {
+            tracing::info!(credential_id = %cred_id, "Vault self-test passed");
+        }
+        Ok(None) => tracing::info!("Vault self-test skipped: no credentials stored yet"),
+        Err(e)
+            if e.downcast_ref::<vault::builtin::DecryptionError>()
+                .is_some() =>
+        {
+            tracing::error!(
+                "VAULT SELF-TEST FAILED — stored credentials cannot be decrypted. \
+             TRUEFLOW_MASTER_KEY is likely wrong or the credentials table is corrupted. \
+             Requests using stored credentials will fail with credential_decryption_failed. \
+             Error: {:#}",
+                e
+            )
+        }
+        Err(e) => tracing::warn!("Vault self-test could not run: {:#}", e),
+    }
+    let vault = Arc::new(vault);
+    let secrets = secret_store(&cfg, &vault)?;
+    if cfg.vault_backend != "builtin" {
+        tracing::info!(backend = %cfg.vault_backend, "Credential secrets read from external vault");
+    }
 
     tracing::info!("Connecting to Redis...");
     // Redis is required for rate limiting, caching, and spend cap enforcement.
@@ -206,21 +313,27 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
     // Use tokio::spawn to create connection manager properly in async context if needed,
     // but ConnectionManager::new is async.
     let redis_conn = redis::aio::ConnectionManager::new(redis_client).await?;
-    let cache = TieredCache::new(redis_conn);
+    let cache = TieredCache::new(redis_conn)
+        .with_token_cache(cfg.token_cache_capacity, cfg.token_cache_ttl_secs);
 
     let upstream_client = proxy::upstream::UpstreamClient::new();
     let notifier = notification::slack::SlackNotifier::new(cfg.slack_webhook_url.clone());
 
     let pricing = models::pricing_cache::PricingCache::new();
     let latency = models::latency_cache::LatencyCache::new();
+    let model_remap = models::model_remap::ModelRemapCache::new();
 
     tracing::info!("Initializing payload store...");
     let payload_store = Arc::new(PayloadStore::from_env().context("invalid PAYLOAD_STORE_URL")?);
 
     let lb_redis = cache.redis();
+    let async_guardrail_permits =
+        Arc::new(tokio::sync::Semaphore::new(cfg.async_guardrail_concurrency));
+    let audit_sinks = Arc::new(middleware::audit_sink::AuditSinkHub::from_config(&cfg));
     let state = Arc::new(AppState {
         db,
         vault,
+        secrets,
         cache,
         upstream_client,
         notifier,
@@ -229,9 +342,17 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
         lb: proxy::loadbalancer::LoadBalancer::new_with_redis(lb_redis),
         pricing: pricing.clone(),
         latency: latency.clone(),
+        model_remap: model_remap.clone(),
+        context_windows: models::tokenizer::ContextWindowTable::new(),
+        capabilities: models::capabilities::CapabilityTable::new(),
+        content_categories: middleware::guardrail::category::ContentCategoryTable::new(),
         payload_store,
         observer: Arc::new(middleware::observer::ObserverHub::from_env()),
         mcp_registry: Arc::new(mcp::registry::McpRegistry::new()),
+        async_guardrail_permits,
+        audit_sinks,
+        adaptive_limits: proxy::adaptive_limit::AdaptiveLimiter::new(),
+        streams: proxy::stream_limit::StreamLimiter::new(),
     });
 
     // Load initial pricing from DB into the in-memory cache
@@ -244,6 +365,7 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
                     model_pattern: r.model_pattern,
                     input_per_m: r.input_per_m,
                     output_per_m: r.output_per_m,
+                    image_per_unit: r.image_per_unit,
                 })
                 .collect();
             pricing.reload(entries).await;
@@ -274,6 +396,7 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
                     state.db.clone(),
                     vault_crypto,
                     state.cache.clone(),
+                    state.vault.secret_cache(),
                     rotation_interval,
                 ));
                 scheduler.start();
@@ -291,9 +414,12 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
     let app = axum::Router::new()
         // Health endpoints (no auth)
         .route("/healthz", axum::routing::get(|| async { "ok" }))
-        .route("/readyz", axum::routing::get(readiness_check_layer))
+        .route("/readyz", axum::routing::get(readiness_check))
         // Prometheus metrics (no auth — standard for /metrics)
-        .route("/metrics", axum::routing::get(prometheus_metrics_handler))
+        .route(
+            "/metrics",
+            axum::routing::get(middleware::metrics::metrics_handler),
+        )
         // Realtime WebSocket proxy — must come before the catch-all fallback
         .route(
             "/v1/realtime",
@@ -306,6 +432,12 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
         .with_state(state.clone())
         // Enforce 25 MB body size limit on all routes
         .layer(DefaultBodyLimit::max(25 * 1024 * 1024))
+        // Inside the header middlewares, so security, request-id and cost
+        // headers land on compressed responses unchanged.
+        .layer(middleware::compression::layer(
+            state.config.response_compression,
+            state.config.response_compression_min_bytes,
+        ))
         .layer(tower_http::trace::TraceLayer::new_for_http())
         // SEC-06: Restrict CORS origins.
         // - Dev: allows any localhost:* for convenience
@@ -364,6 +496,22 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
     jobs::session_cleanup::spawn(state.db.pool().clone());
     tracing::info!("Session cleanup job started (orphaned session expiry every 15min)");
 
+    // Phase 5.2: Flush upstream health history (every 60s, pruned hourly)
+    jobs::upstream_health::spawn(state.clone());
+    tracing::info!(
+        retention_days = state.config.upstream_health_retention_days,
+        "Upstream health history job started (flush every 60s)"
+    );
+
+    // Phase 5.3: Re-prime hot cache keys (every 60s, opt-in)
+    if state.config.cache_warm_threshold > 0 && state.config.cache_auto_warm {
+        jobs::cache_warmer::spawn(state.clone());
+        tracing::info!(
+            threshold = state.config.cache_warm_threshold,
+            "Cache warmer job started (every 60s)"
+        );
+    }
+
     // Phase 2.3: Start budget check job (every 15 minutes)
     {
         let budget_pool = state.db.pool().clone();
@@ -381,18 +529,53 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
 
     // Phase 2.5: Start latency cache refresh job for DynamicRoute (every 5 minutes)
     {
-        let latency_pool = state.db.pool().clone();
+        let latency_db = state.db.clone();
         let latency_cache = latency.clone();
         tokio::spawn(async move {
-            // Initial load at startup
-            latency_cache.reload(&latency_pool).await;
+            // Warm start from the last persisted snapshot, then refresh live.
+            latency_cache.load_snapshot(latency_db.pool()).await;
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(300)); // 5min
             loop {
                 interval.tick().await;
-                latency_cache.reload(&latency_pool).await;
+                latency_cache.reload(latency_db.read_pool()).await;
+                if let Err(e) = latency_cache.save_snapshot(latency_db.pool()).await {
+                    tracing::warn!("latency_cache: snapshot save failed: {}", e);
+                }
+            }
+        });
+        tracing::info!("Latency cache refresh job started (p50/p95/p99 per model every 5min)");
+    }
+
+    // Read replica health probe (every 30s): analytics reads fall back to the
+    // primary while the replica is unreachable.
+    if state.config.database_read_url.is_some() {
+        let replica_db = state.db.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+            interval.tick().await; // probed once at startup already
+            loop {
+                interval.tick().await;
+                replica_db.check_read_replica().await;
+            }
+        });
+        tracing::info!("Read replica health probe started (every 30s)");
+    }
+
+    // Deprecated model remap, context-window, capability and content-category tables:
+    // reloaded every 60s so settings changes made through another replica take
+    // effect here too.
+    {
+        let remap_state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                remap_state.model_remap.reload(&remap_state.db).await;
+                remap_state.context_windows.reload(&remap_state.db).await;
+                remap_state.capabilities.reload(&remap_state.db).await;
+                remap_state.content_categories.reload(&remap_state.db).await;
             }
         });
-        tracing::info!("Latency cache refresh job started (p50 per model every 5min)");
     }
 
     // Phase 2.4: Periodic in-memory cache eviction (every 60s)
@@ -426,63 +609,89 @@ async fn run_server(cfg: config::Config, port: u16) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Per-request ID assigned by `request_id_middleware`, available to handlers
+/// as a request extension so audit rows, logs and headers share one value.
+#[derive(Clone, Copy)]
+pub struct RequestId(pub uuid::Uuid);
+
 /// Middleware: injects a unique X-Request-Id into every response.
-/// This allows clients to correlate errors with gateway logs.
+/// This allows clients to correlate errors with gateway logs. Every log line
+/// emitted while handling the request carries the same `request_id` span field.
 async fn request_id_middleware(
-    req: axum::extract::Request,
+    mut req: axum::extract::Request,
     next: axum::middleware::Next,
 ) -> axum::response::Response {
-    let req_id = uuid::Uuid::new_v4().to_string();
-    let mut resp = next.run(req).await;
-    if let Ok(val) = axum::http::HeaderValue::from_str(&req_id) {
-        resp.headers_mut().insert("x-request-id", val);
+    let id = uuid::Uuid::new_v4();
+    let req_id = format!("req_{}", id.simple());
+    req.extensions_mut().insert(RequestId(id));
+    let span = tracing::info_span!(
+        "request",
+        request_id = %req_id,
+        method = %req.method(),
+        path = %req.uri().path(),
+    );
+    let mut resp = next.run(req).instrument(span).await;
+    // Handlers that already set an ID (e.g. error bodies) keep theirs.
+    if !resp.headers().contains_key("x-request-id") {
+        if let Ok(val) = axum::http::HeaderValue::from_str(&req_id) {
+            resp.headers_mut().insert("x-request-id", val);
+        }
     }
     resp
 }
 
-/// Middleware layer that extracts state for readiness check.
-async fn readiness_check_layer(
-    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
-) -> impl axum::response::IntoResponse {
-    readiness_check(&state).await
-}
+/// Per-dependency time limit for the readiness probe.
+const READINESS_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
 
 /// Readiness probe: checks database and Redis connectivity.
-/// Returns 200 if both are healthy, 503 otherwise.
-async fn readiness_check(state: &AppState) -> (axum::http::StatusCode, &'static str) {
-    // Check database connectivity
-    let db_ok = sqlx::query("SELECT 1")
-        .fetch_one(state.db.pool())
-        .await
-        .is_ok();
+/// Returns 200 if both answer within `READINESS_TIMEOUT`, 503 otherwise.
+async fn readiness_check(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+) -> (axum::http::StatusCode, axum::Json<serde_json::Value>) {
+    let db = async {
+        sqlx::query("SELECT 1")
+            .fetch_one(state.db.pool())
+            .await
+            .is_ok()
+    };
+    readiness(db, state.cache.ping(), READINESS_TIMEOUT).await
+}
 
-    // Check Redis connectivity
-    let redis_ok = state.cache.ping().await;
+/// Run the database and Redis checks concurrently, each bounded by
+/// `timeout`; a check that times out counts as failed. The 503 body names
+/// the failed dependencies.
+async fn readiness(
+    db: impl std::future::Future<Output = bool>,
+    redis: impl std::future::Future<Output = bool>,
+    timeout: std::time::Duration,
+) -> (axum::http::StatusCode, axum::Json<serde_json::Value>) {
+    let (db_ok, redis_ok) = tokio::join!(
+        tokio::time::timeout(timeout, db),
+        tokio::time::timeout(timeout, redis)
+    );
+    let (db_ok, redis_ok) = (db_ok.unwrap_or(false), redis_ok.unwrap_or(false));
 
     if db_ok && redis_ok {
-        (axum::http::StatusCode::OK, "ok")
-    } else {
-        let reason = match (db_ok, redis_ok) {
-            (false, false) => "database and redis unavailable",
-            (false, true) => "database unavailable",
-            (true, false) => "redis unavailable",
-            (true, true) => unreachable!(),
-        };
-        tracing::warn!(db_ok, redis_ok, "readiness check failed: {}", reason);
-        (axum::http::StatusCode::SERVICE_UNAVAILABLE, reason)
+        return (
+            axum::http::StatusCode::OK,
+            axum::Json(serde_json::json!({ "status": "ok" })),
+        );
     }
-}
-
-/// GET /metrics — Prometheus text exposition format.
-/// Unauthenticated (standard for Prometheus scrape targets).
-async fn prometheus_metrics_handler() -> axum::response::Response<axum::body::Body> {
-    let body = middleware::metrics::encode_metrics();
-    axum::response::Response::builder()
-        .header("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
-        .body(axum::body::Body::from(body))
-        .unwrap_or_else(|_| {
-            axum::response::Response::new(axum::body::Body::from("# error encoding metrics\n"))
-        })
+    let failed: Vec<&str> = [("database", db_ok), ("redis", redis_ok)]
+        .into_iter()
+        .filter(|(_, ok)| !ok)
+        .map(|(name, _)| name)
+        .collect();
+    tracing::warn!(
+        db_ok,
+        redis_ok,
+        "readiness check failed: {} unavailable",
+        failed.join(", ")
+    );
+    (
+        axum::http::StatusCode::SERVICE_UNAVAILABLE,
+        axum::Json(serde_json::json!({ "status": "unavailable", "failed": failed })),
+    )
 }
 
 /// Middleware: injects security headers into every response.
@@ -586,7 +795,7 @@ async fn handle_policy_command(
             let rules_json = serde_json::to_value(rules)?;
             let id = state
                 .db
-                .insert_policy(pid, &name, &mode, &phase, rules_json, None)
+                .insert_policy(pid, &name, &mode, &phase, rules_json, None, 0)
                 .await?;
             println!(
                 "Policy created:\n  Name:     {}\n  ID:       {}\n  Mode:     {}\n  Phase:    {}",
@@ -638,6 +847,7 @@ async fn handle_token_command(
             upstream,
             project_id,
             policy_ids,
+            skip_defaults,
         } => {
             let pid =
                 project_id.unwrap_or_else(|| "00000000-0000-0000-0000-000000000001".to_string());
@@ -669,6 +879,10 @@ async fn handle_token_command(
                     );
                 }
             }
+            if !skip_defaults {
+                let defaults = state.db.get_project_default_policy_ids(pid).await?;
+                p_ids = crate::store::postgres::merge_default_policy_ids(&defaults, &p_ids);
+            }
 
             let token_id = format!("tf_v1_{}", uuid::Uuid::new_v4().simple());
 
@@ -687,6 +901,30 @@ async fn handle_token_command(
                 tags: None,
                 mcp_allowed_tools: None,
                 mcp_blocked_tools: None,
+                stream_flush: None,
+                provider_hint: None,
+                request_budget_secs: None,
+                param_defaults: None,
+                session_cost_header: false,
+                strip_body_fields: None,
+                budget_pressure_model_map: None,
+                budget_pressure_threshold_pct: None,
+                stream_ttft_comment: false,
+                test_upstream_override: None,
+                context_window_action: None,
+                enforcement_order: None,
+                forward_trace_headers: None,
+                adaptive_rate_limit: None,
+                migration: None,
+                max_output_tokens_ceiling: None,
+                stream_output_format: None,
+                serve_stale_on_error: false,
+                max_concurrent_streams: None,
+                max_cost_per_request_usd: None,
+                cache_key_ignore_paths: None,
+                json_mode_fallback: false,
+                replay_window_secs: None,
+                capability_action: None,
             };
 
             state.db.insert_token(&new_token).await?;
@@ -718,6 +956,7 @@ async fn handle_token_command(
                 .db
                 .revoke_token(&token_id, token_row.project_id)
                 .await?;
+            state.cache.invalidate_token(&token_id).await;
             if revoked {
                 println!("Token revoked.");
             } else {
@@ -741,6 +980,7 @@ async fn handle_credential_command(
             project_id,
             mode,
             header,
+            allowed_models,
         } => {
             let project = parse_project_id(project_id)?;
 
@@ -765,8 +1005,14 @@ async fn handle_credential_command(
                 );
             }
 
+            // An external vault backend holds the secret; don't keep a copy.
+            let stored_key = if cfg.vault_backend == "builtin" {
+                key.as_str()
+            } else {
+                ""
+            };
             let (encrypted_dek, dek_nonce, encrypted_secret, secret_nonce) =
-                encrypt_credential(&cfg.master_key, &key)?;
+                encrypt_credential(&cfg.master_key, stored_key)?;
 
             let cred = store::postgres::NewCredential {
                 project_id: project,
@@ -778,6 +1024,8 @@ async fn handle_credential_command(
                 secret_nonce,
                 injection_mode: mode.clone(),
                 injection_header: header.clone(),
+                allowed_models: allowed_models.filter(|m| !m.is_empty()),
+                model_rate_limits: None,
             };
 
             let id = db.insert_credential(&cred).await?;
@@ -786,6 +1034,9 @@ async fn handle_credential_command(
             println!("  Provider: {}", provider);
             println!("  Mode:     {}", mode);
             println!("  Header:   {}", header);
+            if let Some(ref models) = cred.allowed_models {
+                println!("  Models:   {}", models.join(", "));
+            }
             println!("  ID:       {}", id);
         }
 
@@ -817,6 +1068,97 @@ async fn handle_credential_command(
     Ok(())
 }
 
+async fn handle_pricing_command(db: &PgStore, cmd: cli::PricingCommands) -> anyhow::Result<()> {
+    match cmd {
+        cli::PricingCommands::Import {
+            file,
+            format,
+            dry_run,
+        } => {
+            let text = std::fs::read_to_string(&file)
+                .with_context(|| format!("failed to read {}", file))?;
+            let is_csv = match format.as_deref() {
+                Some("csv") => true,
+                Some("json") => false,
+                Some(other) => anyhow::bail!("invalid format: {}. Must be csv or json", other),
+                None => !file.to_lowercase().ends_with(".json"),
+            };
+
+            let (rows, mut results) =
+                models::pricing_import::parse(&text, is_csv).map_err(|e| anyhow::anyhow!(e))?;
+            let errors = results.iter().filter(|r| r.error.is_some()).count();
+
+            if errors == 0 && !dry_run {
+                let created = db.import_model_pricing(&rows).await?;
+                for (result, was_created) in results.iter_mut().zip(created) {
+                    result.status = if was_created { "created" } else { "updated" };
+                }
+            }
+
+            println!(
+                "{:<5} {:<10} {:<12} {:<32} ERROR",
+                "ROW", "STATUS", "PROVIDER", "MODEL_PATTERN"
+            );
+            for r in &results {
+                println!(
+                    "{:<5} {:<10} {:<12} {:<32} {}",
+                    r.row,
+                    r.status,
+                    r.provider,
+                    r.model_pattern,
+                    r.error.as_deref().unwrap_or("")
+                );
+            }
+
+            if errors > 0 {
+                anyhow::bail!(
+                    "{} of {} rows invalid; nothing imported",
+                    errors,
+                    results.len()
+                );
+            }
+            if dry_run {
+                println!("Dry run: {} rows valid, nothing written.", results.len());
+            } else {
+                println!(
+                    "Imported {} rows. Running gateways pick up the new prices on restart, \
+                     or immediately when imported via POST /api/v1/pricing/import.",
+                    results.len()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_audit_command(cfg: &config::Config, cmd: cli::AuditCommands) -> anyhow::Result<()> {
+    match cmd {
+        cli::AuditCommands::Tail {
+            token,
+            status,
+            min_cost,
+            project_id,
+            lines,
+            follow,
+            url,
+            json,
+        } => {
+            let filter = audit_tail::TailFilter {
+                token,
+                status,
+                min_cost,
+            };
+            let opts = audit_tail::TailOptions {
+                project_id,
+                lines,
+                follow,
+                json,
+            };
+            audit_tail::run(&url, cfg.admin_key(), filter, opts).await
+        }
+    }
+}
+
 async fn handle_approval_command(db: &PgStore, cmd: cli::ApprovalCommands) -> anyhow::Result<()> {
     match cmd {
         cli::ApprovalCommands::List { project_id } => {
@@ -874,6 +1216,32 @@ async fn handle_approval_command(db: &PgStore, cmd: cli::ApprovalCommands) -> an
     Ok(())
 }
 
+/// The credential secret backend selected by `TRUEFLOW_VAULT_BACKEND`.
+fn secret_store(
+    cfg: &config::Config,
+    vault: &Arc<BuiltinStore>,
+) -> anyhow::Result<Arc<dyn vault::SecretStore>> {
+    match cfg.vault_backend.as_str() {
+        "builtin" => Ok(vault.clone()),
+        "gcp" => {
+            let project = cfg
+                .gcp_secret_project
+                .as_deref()
+                .context("TRUEFLOW_VAULT_BACKEND=gcp requires TRUEFLOW_GCP_SECRET_PROJECT")?;
+            let store = vault::gcp_secret_manager::GcpSecretManagerStore::new(
+                project,
+                &cfg.gcp_secret_prefix,
+            )?
+            .with_cache_ttl(cfg.credential_cache_ttl_secs);
+            Ok(Arc::new(store))
+        }
+        other => anyhow::bail!(
+            "unsupported TRUEFLOW_VAULT_BACKEND '{}' (expected builtin or gcp)",
+            other
+        ),
+    }
+}
+
 fn encrypt_credential(
     master_key_hex: &str,
     plaintext: &str,
@@ -887,3 +1255,40 @@ fn parse_project_id(id: Option<String>) -> anyhow::Result<uuid::Uuid> {
     raw.parse()
         .map_err(|_| anyhow::anyhow!("invalid project ID: {}", raw))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_readiness_ok_when_all_dependencies_healthy() {
+        let (status, body) =
+            readiness(async { true }, async { true }, Duration::from_millis(50)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body.0, serde_json::json!({ "status": "ok" }));
+    }
+
+    #[tokio::test]
+    async fn test_readiness_503_names_failed_redis() {
+        let (status, body) =
+            readiness(async { true }, async { false }, Duration::from_millis(50)).await;
+        assert_eq!(status, axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            body.0,
+            serde_json::json!({ "status": "unavailable", "failed": ["redis"] })
+        );
+
+        // A Redis that never answers fails on the timeout instead of hanging the probe.
+        let started = std::time::Instant::now();
+        let (status, body) = readiness(
+            async { true },
+            std::future::pending::<bool>(),
+            Duration::from_millis(50),
+        )
+        .await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(status, axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.0["failed"], serde_json::json!(["redis"]));
+    }
+}
@@ -0,0 +1,264 @@
+//! Structured access log: one JSON line on stdout per proxied request, for
+//! log pipelines that read neither the audit table nor tracing output.
+//!
+//! Enabled with `TRUEFLOW_ACCESS_LOG_JSON`. Lines are serialized from the
+//! finished [`AuditEntry`], so every audited outcome is covered (including
+//! policy denials and upstream timeouts), and request/response bodies and
+//! headers are never written regardless of the token's `log_level`.
+//! Requests rejected before they have an audit entry (unknown token, replay
+//! protection) are logged from a [`Rejection`] instead.
+
+use std::io::Write;
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::audit::{AuditEntry, PolicyResult};
+
+#[derive(Serialize)]
+struct AccessLogLine<'a> {
+    timestamp: chrono::DateTime<chrono::Utc>,
+    request_id: Uuid,
+    token_id: Option<&'a str>,
+    project_id: Option<Uuid>,
+    method: &'a str,
+    path: &'a str,
+    policy_result: &'static str,
+    upstream_status: Option<u16>,
+    latency_ms: u64,
+    /// Estimated cost in USD.
+    cost: Option<f64>,
+    model: Option<&'a str>,
+    cache_hit: bool,
+    /// Error code when the gateway failed or rejected the request.
+    error: Option<&'a str>,
+}
+
+/// A request rejected before an audit entry exists for it. `token_id` and
+/// `project_id` are set once the token has resolved.
+pub struct Rejection<'a> {
+    pub request_id: Uuid,
+    pub token_id: Option<&'a str>,
+    pub project_id: Option<Uuid>,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub latency_ms: u64,
+    /// The client-facing error code, e.g. `token_not_found`.
+    pub error: &'a str,
+}
+
+fn policy_result_name(result: &PolicyResult) -> &'static str {
+    match result {
+        PolicyResult::Allow => "allow",
+        PolicyResult::Deny { .. } => "deny",
+        PolicyResult::ShadowDeny { .. } => "shadow_deny",
+        PolicyResult::HitlApproved => "hitl_approved",
+        PolicyResult::HitlRejected => "hitl_rejected",
+        PolicyResult::HitlTimeout => "hitl_timeout",
+    }
+}
+
+/// Write the access log line for `entry`, newline-terminated.
+pub fn write_line<W: Write>(out: &mut W, entry: &AuditEntry) -> std::io::Result<()> {
+    let line = AccessLogLine {
+        timestamp: entry.timestamp,
+        request_id: entry.request_id,
+        token_id: Some(&entry.token_id),
+        project_id: Some(entry.project_id),
+        method: &entry.method,
+        path: &entry.path,
+        policy_result: policy_result_name(&entry.policy_result),
+        upstream_status: entry.upstream_status,
+        latency_ms: entry.response_latency_ms,
+        cost: entry.estimated_cost_usd.and_then(|c| c.to_f64()),
+        model: entry.model.as_deref(),
+        cache_hit: entry.cache_hit,
+        error: entry.error_type.as_deref(),
+    };
+    serialize(out, &line)
+}
+
+/// Write the access log line for an early rejection, newline-terminated.
+pub fn write_rejection<W: Write>(out: &mut W, rejection: &Rejection<'_>) -> std::io::Result<()> {
+    let line = AccessLogLine {
+        timestamp: chrono::Utc::now(),
+        request_id: rejection.request_id,
+        token_id: rejection.token_id,
+        project_id: rejection.project_id,
+        method: rejection.method,
+        path: rejection.path,
+        policy_result: "rejected",
+        upstream_status: None,
+        latency_ms: rejection.latency_ms,
+        cost: None,
+        model: None,
+        cache_hit: false,
+        error: Some(rejection.error),
+    };
+    serialize(out, &line)
+}
+
+fn serialize<W: Write>(out: &mut W, line: &AccessLogLine<'_>) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *out, line)?;
+    out.write_all(b"\n")
+}
+
+/// Write the access log line for `entry` to stdout.
+pub fn emit(entry: &AuditEntry) {
+    if let Err(e) = write_line(&mut std::io::stdout().lock(), entry) {
+        tracing::warn!(request_id = %entry.request_id, "access log write failed: {}", e);
+    }
+}
+
+/// Write the access log line for an early rejection to stdout.
+pub fn emit_rejection(rejection: &Rejection<'_>) {
+    if let Err(e) = write_rejection(&mut std::io::stdout().lock(), rejection) {
+        tracing::warn!(request_id = %rejection.request_id, "access log write failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::audit::tests::test_audit_entry;
+
+    /// Set in the child process that runs the emitting half of a test.
+    const CHILD_ENV: &str = "TRUEFLOW_ACCESS_LOG_TEST_CHILD";
+
+    /// Whether this process is the child started by [`capture_stdout`].
+    fn in_child() -> bool {
+        std::env::var_os(CHILD_ENV).is_some()
+    }
+
+    /// Re-run `test` in a child process and return the JSON lines it wrote
+    /// to stdout. `emit` writes straight to the process's stdout, past
+    /// libtest's capture, so this reads what a log shipper would see.
+    fn capture_stdout(test: &str) -> Vec<String> {
+        let module = module_path!().split_once("::").unwrap().1;
+        let out = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                format!("{module}::{test}").as_str(),
+                "--exact",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(CHILD_ENV, "1")
+            .output()
+            .unwrap();
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        // libtest may print the test name on the same line first.
+        String::from_utf8(out.stdout)
+            .unwrap()
+            .lines()
+            .filter_map(|l| l.find('{').map(|i| l[i..].to_string()))
+            .collect()
+    }
+
+    const REQUEST_ID: Uuid = Uuid::from_u128(0x0192_8f3a_7c1e_7b6d_9a41_2f5e_8c3d_1b07);
+
+    #[test]
+    fn test_denied_request_line_has_no_bodies() {
+        let mut entry = test_audit_entry(PolicyResult::Deny {
+            policy: "block-gpt4".to_string(),
+            reason: "model not allowed".to_string(),
+        });
+        entry.request_id = REQUEST_ID;
+        entry.log_level = 2;
+        entry.request_body = Some(r#"{"messages":[{"content":"secret prompt"}]}"#.to_string());
+        entry.response_body = Some("secret completion".to_string());
+        entry.request_headers = Some(serde_json::json!({"authorization": "Bearer sk-live"}));
+        entry.upstream_status = None;
+        entry.response_latency_ms = 3;
+        entry.model = Some("gpt-4".to_string());
+        if in_child() {
+            emit(&entry);
+            return;
+        }
+
+        let lines = capture_stdout("test_denied_request_line_has_no_bodies");
+        assert_eq!(lines.len(), 1, "{lines:?}");
+        let text = &lines[0];
+        assert!(!text.contains("secret"));
+        assert!(!text.contains("sk-live"));
+
+        let line: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(line["request_id"], REQUEST_ID.to_string());
+        assert_eq!(line["token_id"], "test-token");
+        assert!(line["project_id"].is_string());
+        assert_eq!(line["method"], "POST");
+        assert_eq!(line["path"], "/v1/chat/completions");
+        assert_eq!(line["policy_result"], "deny");
+        assert!(line["upstream_status"].is_null());
+        assert_eq!(line["latency_ms"], 3);
+        assert_eq!(line["model"], "gpt-4");
+        assert_eq!(line["cache_hit"], false);
+        assert!(line.get("request_body").is_none());
+    }
+
+    #[test]
+    fn test_cost_is_a_number() {
+        let mut entry = test_audit_entry(PolicyResult::Allow);
+        entry.estimated_cost_usd = Some(rust_decimal::Decimal::new(125, 5));
+        entry.upstream_status = Some(200);
+        entry.cache_hit = true;
+        if in_child() {
+            emit(&entry);
+            return;
+        }
+
+        let lines = capture_stdout("test_cost_is_a_number");
+        assert_eq!(lines.len(), 1, "{lines:?}");
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["cost"], 0.00125);
+        assert_eq!(line["upstream_status"], 200);
+        assert_eq!(line["policy_result"], "allow");
+        assert_eq!(line["cache_hit"], true);
+        assert!(line["error"].is_null());
+    }
+
+    #[test]
+    fn test_early_rejection_is_logged() {
+        if in_child() {
+            emit_rejection(&Rejection {
+                request_id: REQUEST_ID,
+                token_id: None,
+                project_id: None,
+                method: "POST",
+                path: "/v1/chat/completions",
+                latency_ms: 1,
+                error: "token_not_found",
+            });
+            emit_rejection(&Rejection {
+                request_id: REQUEST_ID,
+                token_id: Some("tf_v1_proj_tok_abc"),
+                project_id: Some(Uuid::nil()),
+                method: "POST",
+                path: "/v1/chat/completions",
+                latency_ms: 2,
+                error: "nonce_reused",
+            });
+            return;
+        }
+
+        let lines = capture_stdout("test_early_rejection_is_logged");
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        let unknown: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(unknown["request_id"], REQUEST_ID.to_string());
+        assert_eq!(unknown["policy_result"], "rejected");
+        assert_eq!(unknown["error"], "token_not_found");
+        assert!(unknown["token_id"].is_null());
+        assert!(unknown["project_id"].is_null());
+        assert!(unknown["upstream_status"].is_null());
+
+        let replay: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(replay["token_id"], "tf_v1_proj_tok_abc");
+        assert_eq!(replay["project_id"], Uuid::nil().to_string());
+        assert_eq!(replay["error"], "nonce_reused");
+        assert_eq!(replay["latency_ms"], 2);
+    }
+}
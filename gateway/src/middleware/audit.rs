@@ -1,9 +1,25 @@
 use std::sync::Arc;
 
+use crate::middleware::audit_sink::AuditSinkHub;
 use crate::models::audit::{AuditEntry, PolicyResult};
 use crate::store::payload_store::PayloadStore;
 use sqlx::PgPool;
 
+/// Route an audit entry to every configured destination: external sinks
+/// first (non-blocking enqueue), then Postgres unless
+/// `TRUEFLOW_AUDIT_SINK_SKIP_DB` is set and at least one sink is active.
+pub fn dispatch(
+    pool: PgPool,
+    payload_store: Arc<PayloadStore>,
+    sinks: &AuditSinkHub,
+    entry: AuditEntry,
+) {
+    sinks.send(&entry);
+    if !sinks.skip_db() {
+        log_async(pool, payload_store, entry);
+    }
+}
+
 /// Async audit log writer. Fires off a Tokio task to insert
 /// the audit entry into PG with retry on transient failures.
 ///
@@ -67,24 +83,29 @@ pub fn log_async(pool: PgPool, payload_store: Arc<PayloadStore>, entry: AuditEnt
     });
 }
 
-async fn insert_audit_log(
+pub(crate) async fn insert_audit_log(
     pool: &PgPool,
     payload_store: &PayloadStore,
     entry: &AuditEntry,
 ) -> anyhow::Result<()> {
-    let (policy_res, policy_mode, deny_reason) = match &entry.policy_result {
-        PolicyResult::Allow => ("allowed", None, None),
-        PolicyResult::Deny { policy: _, reason } => {
-            ("denied", Some("enforce"), Some(reason.as_str()))
-        }
-        PolicyResult::ShadowDeny { policy: _, reason } => {
-            ("allowed", Some("shadow"), Some(reason.as_str()))
-        }
-        PolicyResult::HitlApproved => ("approved", Some("hitl"), None),
-        PolicyResult::HitlRejected => ("rejected", Some("hitl"), None),
-        PolicyResult::HitlTimeout => ("timeout", Some("hitl"), None),
-    };
+    // Offload first so no connection is held during an object store upload.
+    let placement = place_payload(payload_store, entry).await;
+    let mut conn = pool.acquire().await?;
+    write_audit_rows(&mut conn, entry, placement).await
+}
+
+/// Where an entry's bodies go: an object store key, inline rows, or neither.
+pub(crate) struct PayloadPlacement {
+    payload_url: Option<String>,
+    inline: bool,
+}
 
+/// Offload large bodies to `payload_store`; small ones (or failed offloads)
+/// are stored inline.
+pub(crate) async fn place_payload(
+    payload_store: &PayloadStore,
+    entry: &AuditEntry,
+) -> PayloadPlacement {
     // ── Payload offloading logic ────────────────────────────────────────────────
     // Only attempt to offload when log_level > 0 and bodies exist.
     let mut payload_url: Option<String> = None;
@@ -126,6 +147,37 @@ async fn insert_audit_log(
             should_inline = true;
         }
     }
+    PayloadPlacement {
+        payload_url,
+        inline: should_inline,
+    }
+}
+
+/// Insert an entry's `audit_logs`, `audit_log_bodies` and
+/// `tool_call_details` rows on `conn`, which may be inside a transaction.
+pub(crate) async fn write_audit_rows(
+    conn: &mut sqlx::PgConnection,
+    entry: &AuditEntry,
+    placement: PayloadPlacement,
+) -> anyhow::Result<()> {
+    use sqlx::Connection;
+
+    let PayloadPlacement {
+        payload_url,
+        inline: should_inline,
+    } = placement;
+    let (policy_res, policy_mode, deny_reason) = match &entry.policy_result {
+        PolicyResult::Allow => ("allowed", None, None),
+        PolicyResult::Deny { policy: _, reason } => {
+            ("denied", Some("enforce"), Some(reason.as_str()))
+        }
+        PolicyResult::ShadowDeny { policy: _, reason } => {
+            ("allowed", Some("shadow"), Some(reason.as_str()))
+        }
+        PolicyResult::HitlApproved => ("approved", Some("hitl"), None),
+        PolicyResult::HitlRejected => ("rejected", Some("hitl"), None),
+        PolicyResult::HitlTimeout => ("timeout", Some("hitl"), None),
+    };
 
     // ── Phase 1: Insert metadata into audit_logs ───────────────────────────────
     sqlx::query(
@@ -139,7 +191,7 @@ async fn insert_audit_log(
             user_id, tenant_id, external_request_id, log_level,
             tool_calls, tool_call_count, finish_reason,
             session_id, parent_span_id, error_type, is_streaming,
-            cache_hit, custom_properties, payload_url
+            cache_hit, custom_properties, payload_url, translation_fallback, provider, provider_hinted, missing_properties, param_defaults_applied, body_fields_stripped, model_downgraded_from, model_remapped_from, test_upstream_override, context_estimated_tokens, context_window_tokens, context_messages_trimmed, feedback_score, partial_content_len, policy_eval_timings, migration_path, schema_coercions, max_tokens_clamp, stale_cache_served, request_cost_estimate_usd, request_cost_cap_exceeded, rejected_credential_id, json_mode_emulated, capability_stripped, tokens_estimated, trace_id
         )
         VALUES (
             $1, $2, $3, $4, $5, $6, $7, $8,
@@ -150,7 +202,7 @@ async fn insert_audit_log(
             $27, $28, $29, $30,
             $31, $32, $33,
             $34, $35, $36, $37,
-            $38, $39, $40
+            $38, $39, $40, $41, $42, $43, $44, $45, $46, $47, $48, $49, $50, $51, $52, $53, $54, $55, $56, $57, $58, $59, $60, $61, $62, $63, $64, $65, $66
         )
         "#,
     )
@@ -197,7 +249,33 @@ async fn insert_audit_log(
     // Phase 6 columns
     .bind(&entry.custom_properties)
     .bind(&payload_url)
-    .execute(pool)
+    .bind(entry.translation_fallback)
+    .bind(&entry.provider)
+    .bind(entry.provider_hinted)
+    .bind(&entry.missing_properties)
+    .bind(&entry.param_defaults_applied)
+    .bind(&entry.body_fields_stripped)
+    .bind(&entry.model_downgraded_from)
+    .bind(&entry.model_remapped_from)
+    .bind(&entry.test_upstream_override)
+    .bind(entry.context_estimated_tokens.map(|v| v as i32))
+    .bind(entry.context_window_tokens.map(|v| v as i32))
+    .bind(entry.context_messages_trimmed.map(|v| v as i32))
+    .bind(entry.feedback_score)
+    .bind(entry.partial_content_len.map(|v| v as i32))
+    .bind(&entry.policy_eval_timings)
+    .bind(&entry.migration_path)
+    .bind(&entry.schema_coercions)
+    .bind(&entry.max_tokens_clamp)
+    .bind(entry.stale_cache_served)
+    .bind(entry.request_cost_estimate_usd)
+    .bind(entry.request_cost_cap_exceeded)
+    .bind(entry.rejected_credential_id)
+    .bind(entry.json_mode_emulated)
+    .bind(&entry.capability_stripped)
+    .bind(entry.tokens_estimated)
+    .bind(&entry.trace_id)
+    .execute(&mut *conn)
     .await?;
 
     // ── Phase 2: Inline bodies into audit_log_bodies (small payloads only) ─────
@@ -217,7 +295,7 @@ async fn insert_audit_log(
         .bind(&entry.response_body)
         .bind(&entry.request_headers)
         .bind(&entry.response_headers)
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
     }
     // ── Phase 3: Structured tool-call details ──────────────────────────────────
@@ -249,6 +327,9 @@ async fn insert_audit_log(
                         }
                     });
 
+                // A savepoint, so a failed insert doesn't abort the caller's
+                // transaction.
+                let mut savepoint = conn.begin().await?;
                 let insert_result = sqlx::query(
                     r#"
                     INSERT INTO tool_call_details (
@@ -264,10 +345,11 @@ async fn insert_audit_log(
                 .bind(&tool_call_id)
                 .bind(&arguments)
                 .bind(i as i16)
-                .execute(pool)
+                .execute(&mut *savepoint)
                 .await;
 
                 if let Err(e) = insert_result {
+                    savepoint.rollback().await?;
                     // Non-fatal: don't fail the entire audit if detail insert fails
                     // (e.g., migration not yet applied)
                     tracing::debug!(
@@ -276,6 +358,8 @@ async fn insert_audit_log(
                         "tool_call_details insert failed (migration pending?): {}",
                         e
                     );
+                } else {
+                    savepoint.commit().await?;
                 }
             }
         }
@@ -285,12 +369,12 @@ async fn insert_audit_log(
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use crate::models::audit::{AuditEntry, PolicyResult};
     use uuid::Uuid;
 
     /// Helper to construct a minimal audit entry for testing.
-    fn test_audit_entry(policy_result: PolicyResult) -> AuditEntry {
+    pub(crate) fn test_audit_entry(policy_result: PolicyResult) -> AuditEntry {
         AuditEntry {
             request_id: Uuid::new_v4(),
             project_id: Uuid::new_v4(),
@@ -328,10 +412,36 @@ mod tests {
             finish_reason: Some("stop".to_string()),
             session_id: None,
             parent_span_id: None,
+            trace_id: None,
             error_type: None,
             is_streaming: false,
             ttft_ms: None,
             cache_hit: false,
+            translation_fallback: false,
+            provider: None,
+            provider_hinted: false,
+            missing_properties: None,
+            param_defaults_applied: None,
+            body_fields_stripped: None,
+            model_downgraded_from: None,
+            model_remapped_from: Some("gpt-4-0314".into()),
+            test_upstream_override: None,
+            context_estimated_tokens: None,
+            context_window_tokens: None,
+            context_messages_trimmed: None,
+            feedback_score: Some(0.8),
+            partial_content_len: None,
+            policy_eval_timings: None,
+            migration_path: None,
+            schema_coercions: None,
+            max_tokens_clamp: None,
+            stale_cache_served: false,
+            request_cost_estimate_usd: None,
+            request_cost_cap_exceeded: false,
+            rejected_credential_id: None,
+            json_mode_emulated: false,
+            capability_stripped: None,
+            tokens_estimated: false,
             experiment_name: None,
             variant_name: None,
             custom_properties: None,
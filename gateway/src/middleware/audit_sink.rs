@@ -0,0 +1,1361 @@
+//! External audit sinks — ship audit events to a team's own pipeline.
+//!
+//! Called from `middleware::audit::dispatch` alongside (or instead of) the
+//! Postgres writer. Every event is the same `AuditEntry` serialized to JSON;
+//! the OTLP sink maps it to an OpenTelemetry log record instead.
+//!
+//! Each sink runs its own worker task fed by a bounded queue. The request path
+//! only does a non-blocking `try_send`: when a sink falls behind, new events
+//! are dropped and counted rather than slowing requests down.
+//!
+//! The audit replica is a second copy of the audit log in another region —
+//! a Postgres database with the gateway schema, or an append-only JSONL log in
+//! an object store. Unlike the other sinks, failed replica batches are retried
+//! for up to [`REPLICA_MAX_ATTEMPTS`] attempts, holding back later batches so
+//! the replica receives entries in dispatch order (at least once; Postgres
+//! deduplicates by entry ID, and object keys are derived from the batch, so a
+//! retried batch overwrites itself). A batch that still fails is dropped and
+//! logged, so one bad batch can't stall the replica forever. In `sync` mode each entry is also written immediately,
+//! unbatched, with entries that fail falling back to the retry queue; those
+//! direct writes run concurrently and may land out of order.
+//!
+//! Config env vars:
+//!   TRUEFLOW_AUDIT_SINK_HTTP_URL      = https://logs.example.com/ingest
+//!   TRUEFLOW_AUDIT_SINK_HTTP_AUTH     = "Bearer ..." (optional Authorization header)
+//!   TRUEFLOW_AUDIT_SINK_KAFKA_BROKERS = kafka-1:9092,kafka-2:9092 (`kafka` feature)
+//!   TRUEFLOW_AUDIT_SINK_KAFKA_TOPIC   = trueflow-audit (default)
+//!   TRUEFLOW_AUDIT_SINK_OTLP          = true (OTLP/gRPC to OTEL_EXPORTER_OTLP_ENDPOINT)
+//!   TRUEFLOW_AUDIT_SINK_BATCH_SIZE    = 100 (default)
+//!   TRUEFLOW_AUDIT_SINK_FLUSH_MS      = 1000 (default)
+//!   TRUEFLOW_AUDIT_SINK_BUFFER        = 10000 (default, per sink)
+//!   TRUEFLOW_AUDIT_SINK_SKIP_DB       = false (default)
+//!   TRUEFLOW_AUDIT_REPLICA_URL        = postgres://... | s3://bucket[/prefix]?region=eu-west-1 | file:///path
+//!   TRUEFLOW_AUDIT_REPLICA_MODE       = async (default) | sync
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use opentelemetry_proto::tonic::collector::logs::v1::logs_service_client::LogsServiceClient;
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, InstrumentationScope, KeyValue};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs, SeverityNumber};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use tokio::sync::mpsc;
+
+use crate::config::Config;
+use crate::middleware::audit::{place_payload, write_audit_rows};
+use crate::models::audit::{AuditEntry, PolicyResult};
+use crate::store::payload_store::PayloadStore;
+
+/// Retry backoff for a failed batch delivery (same schedule as the DB writer).
+const BACKOFF_MS: [u64; 3] = [100, 500, 2000];
+
+/// Longest wait between retries of a failed audit replica batch.
+const REPLICA_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Attempts at an audit replica batch before it is dropped (about ten
+/// minutes with [`REPLICA_MAX_BACKOFF`]).
+const REPLICA_MAX_ATTEMPTS: usize = 30;
+
+/// How long a `sync` replica write may take before the entry is queued instead.
+const REPLICA_SYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A serialized audit event, shared across sink queues.
+#[derive(Debug, Clone)]
+pub struct SinkEvent {
+    /// Partitioning key (project ID).
+    pub key: String,
+    /// `AuditEntry` serialized as JSON.
+    pub json: Arc<str>,
+}
+
+/// Destination for batches of audit events.
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Deliver a batch. An error triggers a retry of the whole batch.
+    async fn send_batch(&self, batch: &[SinkEvent]) -> anyhow::Result<()>;
+}
+
+// ── HTTP Sink ────────────────────────────────────────────────
+
+/// POSTs each batch as a JSON array of audit entries.
+pub struct HttpAuditSink {
+    client: reqwest::Client,
+    url: String,
+    auth: Option<String>,
+}
+
+impl HttpAuditSink {
+    pub fn new(url: String, auth: Option<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self { client, url, auth }
+    }
+}
+
+#[async_trait]
+impl AuditSink for HttpAuditSink {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn send_batch(&self, batch: &[SinkEvent]) -> anyhow::Result<()> {
+        // Entries are already JSON — splice them into an array without re-parsing.
+        let mut body =
+            String::with_capacity(batch.iter().map(|e| e.json.len() + 1).sum::<usize>() + 2);
+        body.push('[');
+        for (i, event) in batch.iter().enumerate() {
+            if i > 0 {
+                body.push(',');
+            }
+            body.push_str(&event.json);
+        }
+        body.push(']');
+
+        let mut req = self
+            .client
+            .post(&self.url)
+            .header("content-type", "application/json")
+            .body(body);
+        if let Some(auth) = &self.auth {
+            req = req.header("authorization", auth);
+        }
+
+        let resp = req.send().await?;
+        let status = resp.status();
+        if !status.is_success() {
+            anyhow::bail!("audit sink returned HTTP {}", status);
+        }
+        Ok(())
+    }
+}
+
+// ── Kafka Sink ───────────────────────────────────────────────
+
+/// Produces one Kafka message per audit entry, keyed by project ID.
+#[cfg(feature = "kafka")]
+pub struct KafkaAuditSink {
+    producer: rdkafka::producer::FutureProducer,
+    topic: String,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaAuditSink {
+    pub fn new(brokers: &str, topic: String) -> anyhow::Result<Self> {
+        let producer = rdkafka::config::ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "5000")
+            .create()?;
+        Ok(Self { producer, topic })
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl AuditSink for KafkaAuditSink {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn send_batch(&self, batch: &[SinkEvent]) -> anyhow::Result<()> {
+        use rdkafka::producer::FutureRecord;
+
+        let deliveries = batch.iter().map(|event| {
+            self.producer.send(
+                FutureRecord::to(&self.topic)
+                    .key(&event.key)
+                    .payload(event.json.as_bytes()),
+                Duration::from_secs(0),
+            )
+        });
+        let failed = futures::future::join_all(deliveries)
+            .await
+            .into_iter()
+            .filter_map(|r| r.err())
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+        if let Some(e) = failed.first() {
+            anyhow::bail!("{} of {} messages failed: {}", failed.len(), batch.len(), e);
+        }
+        Ok(())
+    }
+}
+
+// ── Audit Replica Sinks ──────────────────────────────────────
+
+fn decode_batch(sink: &str, batch: &[SinkEvent]) -> Vec<AuditEntry> {
+    batch
+        .iter()
+        .filter_map(
+            |event| match serde_json::from_str::<AuditEntry>(&event.json) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    tracing::warn!(sink, "skipping undecodable audit entry: {}", e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Mirrors entries into the `audit_logs` tables of a second Postgres, with
+/// bodies stored inline. The replica must run the gateway's migrations.
+pub struct PostgresReplicaAuditSink {
+    pool: sqlx::PgPool,
+}
+
+impl PostgresReplicaAuditSink {
+    /// Connects lazily, so an unreachable replica never blocks startup.
+    pub fn new(database_url: &str) -> anyhow::Result<Self> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect_lazy(database_url)?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl AuditSink for PostgresReplicaAuditSink {
+    fn name(&self) -> &'static str {
+        "replica_postgres"
+    }
+
+    async fn send_batch(&self, batch: &[SinkEvent]) -> anyhow::Result<()> {
+        let entries = decode_batch(self.name(), batch);
+        let (Some(from), Some(to)) = (
+            entries.iter().map(|e| e.timestamp).min(),
+            entries.iter().map(|e| e.timestamp).max(),
+        ) else {
+            return Ok(());
+        };
+        // One transaction per batch: a failed batch leaves nothing behind,
+        // and its retry writes it whole. Entries already written by a `sync`
+        // write are skipped.
+        let mut tx = self.pool.begin().await?;
+        let ids: Vec<uuid::Uuid> = entries.iter().map(|e| e.request_id).collect();
+        let existing: Vec<uuid::Uuid> = sqlx::query_scalar(
+            "SELECT id FROM audit_logs WHERE id = ANY($1) AND created_at BETWEEN $2 AND $3",
+        )
+        .bind(&ids)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&mut *tx)
+        .await?;
+        for entry in entries.iter().filter(|e| !existing.contains(&e.request_id)) {
+            let placement = place_payload(&PayloadStore::Postgres, entry).await;
+            write_audit_rows(&mut tx, entry, placement).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Appends each batch to an object store as one JSONL object under
+/// `[{prefix}/]audit-log/{YYYY-MM-DD}/{HH}/`, named after the batch's first
+/// entry.
+pub struct ObjectStoreAuditSink {
+    store: Arc<dyn object_store::ObjectStore>,
+    prefix: String,
+}
+
+impl ObjectStoreAuditSink {
+    /// `url` takes the same forms as `PAYLOAD_STORE_URL` (`s3://`, `file://`).
+    /// A path after an S3 bucket (`s3://bucket/dr/audit?region=...`) becomes
+    /// the key prefix.
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        let (store_url, prefix) = split_key_prefix(url);
+        let (store, _) = crate::store::payload_store::build_object_store(&store_url)?;
+        Ok(Self {
+            store: Arc::from(store),
+            prefix,
+        })
+    }
+
+    /// Object key for a batch. Derived from its first entry, so a retried
+    /// batch overwrites its earlier attempt instead of duplicating it.
+    fn key(&self, first: &AuditEntry) -> String {
+        let key = format!(
+            "audit-log/{}/{}-{}.jsonl",
+            first.timestamp.format("%Y-%m-%d/%H"),
+            first.timestamp.timestamp_millis(),
+            first.request_id
+        );
+        if self.prefix.is_empty() {
+            key
+        } else {
+            format!("{}/{}", self.prefix, key)
+        }
+    }
+}
+
+/// Split the key prefix off an `s3://bucket/prefix?query` URL, returning the
+/// URL without it. Other schemes have no prefix: a `file://` path is the
+/// directory itself.
+fn split_key_prefix(url: &str) -> (String, String) {
+    let Some(rest) = url.strip_prefix("s3://") else {
+        return (url.to_string(), String::new());
+    };
+    let (location, query) = match rest.split_once('?') {
+        Some((location, query)) => (location, Some(query)),
+        None => (rest, None),
+    };
+    let Some((bucket, prefix)) = location.split_once('/') else {
+        return (url.to_string(), String::new());
+    };
+    let store_url = match query {
+        Some(query) => format!("s3://{}?{}", bucket, query),
+        None => format!("s3://{}", bucket),
+    };
+    (store_url, prefix.trim_matches('/').to_string())
+}
+
+#[async_trait]
+impl AuditSink for ObjectStoreAuditSink {
+    fn name(&self) -> &'static str {
+        "replica_object_store"
+    }
+
+    async fn send_batch(&self, batch: &[SinkEvent]) -> anyhow::Result<()> {
+        let Some(first) = batch
+            .iter()
+            .find_map(|e| serde_json::from_str::<AuditEntry>(&e.json).ok())
+        else {
+            return Ok(());
+        };
+        let mut body = String::with_capacity(batch.iter().map(|e| e.json.len() + 1).sum());
+        for event in batch {
+            body.push_str(&event.json);
+            body.push('\n');
+        }
+        let path = object_store::path::Path::from(self.key(&first));
+        self.store.put(&path, body.into_bytes().into()).await?;
+        Ok(())
+    }
+}
+
+// ── OTLP Logs Sink ───────────────────────────────────────────
+
+/// Exports each batch as OpenTelemetry log records over OTLP/gRPC, so audit
+/// entries land in the same collector as the gateway's traces.
+///
+/// Records join the client's trace when it sent a `traceparent`: `trace_id`
+/// is its trace and `span_id` its parent span. Otherwise they carry the trace
+/// context the gateway forwards upstream: `trace_id` is the request ID and
+/// `span_id` the gateway hop (its first 8 bytes).
+/// Severity follows the outcome — INFO for allowed requests, WARN for policy
+/// denials and upstream 4xx, ERROR for upstream 5xx and gateway errors.
+/// Request/response bodies are never exported.
+pub struct OtlpLogsAuditSink {
+    client: LogsServiceClient<tonic::transport::Channel>,
+}
+
+impl OtlpLogsAuditSink {
+    /// Connects lazily, so a collector that is down at startup only delays
+    /// delivery. Must be called from within the Tokio runtime.
+    pub fn new(endpoint: &str) -> anyhow::Result<Self> {
+        let channel = tonic::transport::Endpoint::from_shared(endpoint.to_string())?
+            .timeout(Duration::from_secs(10))
+            .connect_lazy();
+        Ok(Self {
+            client: LogsServiceClient::new(channel),
+        })
+    }
+}
+
+#[async_trait]
+impl AuditSink for OtlpLogsAuditSink {
+    fn name(&self) -> &'static str {
+        "otlp"
+    }
+
+    async fn send_batch(&self, batch: &[SinkEvent]) -> anyhow::Result<()> {
+        let records = batch
+            .iter()
+            .filter_map(
+                |event| match serde_json::from_str::<AuditEntry>(&event.json) {
+                    Ok(entry) => Some(audit_log_record(&entry)),
+                    Err(e) => {
+                        tracing::warn!("otlp audit sink: skipping undecodable entry: {}", e);
+                        None
+                    }
+                },
+            )
+            .collect();
+        let resp = self
+            .client
+            .clone()
+            .export(export_request(records))
+            .await
+            .map_err(|status| anyhow::anyhow!("OTLP export failed: {}", status))?;
+        if let Some(partial) = resp.into_inner().partial_success {
+            if partial.rejected_log_records > 0 {
+                tracing::warn!(
+                    rejected = partial.rejected_log_records,
+                    "otlp audit sink: collector rejected records: {}",
+                    partial.error_message
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn otel_kv(key: &str, value: any_value::Value) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue { value: Some(value) }),
+    }
+}
+
+fn export_request(records: Vec<LogRecord>) -> ExportLogsServiceRequest {
+    ExportLogsServiceRequest {
+        resource_logs: vec![ResourceLogs {
+            resource: Some(Resource {
+                attributes: vec![otel_kv(
+                    "service.name",
+                    any_value::Value::StringValue("trueflow-gateway".into()),
+                )],
+                dropped_attributes_count: 0,
+            }),
+            scope_logs: vec![ScopeLogs {
+                scope: Some(InstrumentationScope {
+                    name: "trueflow.audit".into(),
+                    version: env!("CARGO_PKG_VERSION").into(),
+                    ..Default::default()
+                }),
+                log_records: records,
+                schema_url: String::new(),
+            }],
+            schema_url: String::new(),
+        }],
+    }
+}
+
+/// Map an audit entry to an OTel log record.
+pub(crate) fn audit_log_record(entry: &AuditEntry) -> LogRecord {
+    use any_value::Value::{BoolValue, DoubleValue, IntValue, StringValue};
+    use rust_decimal::prelude::ToPrimitive;
+
+    let (outcome, denied_by) = match &entry.policy_result {
+        PolicyResult::Allow => ("allow", None),
+        PolicyResult::Deny { policy, .. } => ("deny", Some(policy)),
+        PolicyResult::ShadowDeny { policy, .. } => ("shadow_deny", Some(policy)),
+        PolicyResult::HitlApproved => ("hitl_approved", None),
+        PolicyResult::HitlRejected => ("hitl_rejected", None),
+        PolicyResult::HitlTimeout => ("hitl_timeout", None),
+    };
+    let status = entry.upstream_status;
+    let denied = matches!(
+        entry.policy_result,
+        PolicyResult::Deny { .. }
+            | PolicyResult::ShadowDeny { .. }
+            | PolicyResult::HitlRejected
+            | PolicyResult::HitlTimeout
+    );
+    let (severity, severity_text) =
+        if status.is_some_and(|s| s >= 500) || (entry.error_type.is_some() && !denied) {
+            (SeverityNumber::Error, "ERROR")
+        } else if denied || status.is_some_and(|s| s >= 400) {
+            (SeverityNumber::Warn, "WARN")
+        } else {
+            (SeverityNumber::Info, "INFO")
+        };
+
+    let mut attributes = vec![
+        otel_kv(
+            "trueflow.request_id",
+            StringValue(entry.request_id.to_string()),
+        ),
+        otel_kv(
+            "trueflow.project_id",
+            StringValue(entry.project_id.to_string()),
+        ),
+        otel_kv("trueflow.token_id", StringValue(entry.token_id.clone())),
+        otel_kv("trueflow.policy_result", StringValue(outcome.into())),
+        otel_kv("http.request.method", StringValue(entry.method.clone())),
+        otel_kv("url.path", StringValue(entry.path.clone())),
+        otel_kv(
+            "trueflow.latency_ms",
+            IntValue(entry.response_latency_ms as i64),
+        ),
+        otel_kv("trueflow.cache_hit", BoolValue(entry.cache_hit)),
+        otel_kv("trueflow.is_streaming", BoolValue(entry.is_streaming)),
+    ];
+    let mut push = |key: &str, value: Option<any_value::Value>| {
+        if let Some(v) = value {
+            attributes.push(otel_kv(key, v));
+        }
+    };
+    push(
+        "http.response.status_code",
+        status.map(|s| IntValue(s as i64)),
+    );
+    push("trueflow.policy", denied_by.cloned().map(StringValue));
+    push(
+        "trueflow.error_type",
+        entry.error_type.clone().map(StringValue),
+    );
+    push("gen_ai.system", entry.provider.clone().map(StringValue));
+    push("gen_ai.request.model", entry.model.clone().map(StringValue));
+    push(
+        "gen_ai.usage.input_tokens",
+        entry.prompt_tokens.map(|t| IntValue(t as i64)),
+    );
+    push(
+        "gen_ai.usage.output_tokens",
+        entry.completion_tokens.map(|t| IntValue(t as i64)),
+    );
+    push(
+        "gen_ai.response.finish_reasons",
+        entry.finish_reason.clone().map(StringValue),
+    );
+    push(
+        "trueflow.cost_usd",
+        entry
+            .estimated_cost_usd
+            .and_then(|c| c.to_f64())
+            .map(DoubleValue),
+    );
+    push(
+        "trueflow.ttft_ms",
+        entry.ttft_ms.map(|t| IntValue(t as i64)),
+    );
+    push(
+        "trueflow.agent_name",
+        entry.agent_name.clone().map(StringValue),
+    );
+    push("trueflow.user_id", entry.user_id.clone().map(StringValue));
+    push(
+        "trueflow.tenant_id",
+        entry.tenant_id.clone().map(StringValue),
+    );
+    push(
+        "trueflow.session_id",
+        entry.session_id.clone().map(StringValue),
+    );
+    push(
+        "trueflow.parent_span_id",
+        entry.parent_span_id.clone().map(StringValue),
+    );
+    push(
+        "trueflow.external_request_id",
+        entry.external_request_id.clone().map(StringValue),
+    );
+    push(
+        "trueflow.experiment_name",
+        entry.experiment_name.clone().map(StringValue),
+    );
+    push(
+        "trueflow.variant_name",
+        entry.variant_name.clone().map(StringValue),
+    );
+
+    let body = format!(
+        "{} {} {} {}",
+        entry.method,
+        entry.path,
+        status.map_or_else(|| "-".to_string(), |s| s.to_string()),
+        outcome
+    );
+    let time = entry.timestamp.timestamp_nanos_opt().unwrap_or(0).max(0) as u64;
+    let client_trace = entry
+        .trace_id
+        .as_deref()
+        .and_then(|t| hex::decode(t).ok().filter(|b| b.len() == 16))
+        .map(|trace_id| {
+            let span_id = entry
+                .parent_span_id
+                .as_deref()
+                .and_then(|p| hex::decode(p).ok().filter(|b| b.len() == 8));
+            (trace_id, span_id)
+        });
+    let (trace_id, span_id) = match client_trace {
+        Some((trace_id, Some(span_id))) => (trace_id, span_id),
+        Some((trace_id, None)) => (trace_id, entry.request_id.as_bytes()[..8].to_vec()),
+        None => (
+            entry.request_id.as_bytes().to_vec(),
+            entry.request_id.as_bytes()[..8].to_vec(),
+        ),
+    };
+
+    LogRecord {
+        time_unix_nano: time,
+        observed_time_unix_nano: time,
+        severity_number: severity as i32,
+        severity_text: severity_text.to_string(),
+        body: Some(AnyValue {
+            value: Some(StringValue(body)),
+        }),
+        attributes,
+        dropped_attributes_count: 0,
+        flags: 0,
+        span_id,
+        trace_id,
+    }
+}
+
+// ── Hub ──────────────────────────────────────────────────────
+
+/// What a sink worker does when a batch can't be delivered.
+#[derive(Debug, Clone, Copy)]
+enum Retry {
+    /// Retry on the [`BACKOFF_MS`] schedule, then drop the batch.
+    Bounded,
+    /// Retry with exponential backoff up to `max_backoff`, dropping the
+    /// batch after `max_attempts`.
+    Persistent {
+        max_backoff: Duration,
+        max_attempts: usize,
+    },
+}
+
+struct SinkHandle {
+    name: &'static str,
+    tx: mpsc::Sender<SinkEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl SinkHandle {
+    fn spawn(
+        sink: Arc<dyn AuditSink>,
+        buffer: usize,
+        batch_size: usize,
+        flush: Duration,
+        retry: Retry,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(buffer.max(1));
+        let name = sink.name();
+        tokio::spawn(run_worker(sink, rx, batch_size.max(1), flush, retry));
+        SinkHandle {
+            name,
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Queue an event without blocking; count and drop it if the queue is full.
+    fn enqueue(&self, event: SinkEvent) {
+        enqueue(self.name, &self.tx, &self.dropped, event);
+    }
+}
+
+fn enqueue(
+    name: &'static str,
+    tx: &mpsc::Sender<SinkEvent>,
+    dropped: &AtomicU64,
+    event: SinkEvent,
+) {
+    if tx.try_send(event).is_err() {
+        let dropped = dropped.fetch_add(1, Ordering::Relaxed) + 1;
+        // Log the first drop and then every 1000th to avoid log floods.
+        if dropped == 1 || dropped.is_multiple_of(1000) {
+            tracing::warn!(
+                sink = name,
+                dropped_total = dropped,
+                "audit sink queue full, dropping event"
+            );
+        }
+    }
+}
+
+/// The audit replica: a persistently retried queue, plus the sink itself
+/// for `sync` mode's direct writes.
+struct ReplicaHandle {
+    sink: Arc<dyn AuditSink>,
+    sync: bool,
+    queue: SinkHandle,
+}
+
+/// Fan-out to every configured external sink and the audit replica.
+#[derive(Default)]
+pub struct AuditSinkHub {
+    sinks: Vec<SinkHandle>,
+    replica: Option<ReplicaHandle>,
+    skip_db: bool,
+}
+
+impl AuditSinkHub {
+    /// Build sinks from config and start their workers. Sinks that fail to
+    /// initialize are logged and skipped.
+    pub fn from_config(cfg: &Config) -> Self {
+        let mut sinks: Vec<Arc<dyn AuditSink>> = Vec::new();
+
+        if let Some(url) = &cfg.audit_sink_http_url {
+            tracing::info!("HTTP audit sink enabled");
+            sinks.push(Arc::new(HttpAuditSink::new(
+                url.clone(),
+                cfg.audit_sink_http_auth.clone(),
+            )));
+        }
+
+        if let Some(brokers) = &cfg.audit_sink_kafka_brokers {
+            #[cfg(feature = "kafka")]
+            match KafkaAuditSink::new(brokers, cfg.audit_sink_kafka_topic.clone()) {
+                Ok(sink) => {
+                    tracing::info!(topic = %cfg.audit_sink_kafka_topic, "Kafka audit sink enabled");
+                    sinks.push(Arc::new(sink));
+                }
+                Err(e) => tracing::warn!("Kafka audit sink config error, disabling: {}", e),
+            }
+            #[cfg(not(feature = "kafka"))]
+            tracing::warn!(
+                brokers = %brokers,
+                "TRUEFLOW_AUDIT_SINK_KAFKA_BROKERS is set but this build lacks the `kafka` feature — Kafka sink disabled"
+            );
+        }
+
+        if let Some(endpoint) = &cfg.audit_sink_otlp_endpoint {
+            match OtlpLogsAuditSink::new(endpoint) {
+                Ok(sink) => {
+                    tracing::info!(endpoint = %endpoint, "OTLP logs audit sink enabled");
+                    sinks.push(Arc::new(sink));
+                }
+                Err(e) => tracing::warn!("OTLP audit sink config error, disabling: {}", e),
+            }
+        }
+
+        let flush = Duration::from_millis(cfg.audit_sink_flush_ms);
+        let mut hub = Self::with_sinks(
+            sinks,
+            cfg.audit_sink_buffer,
+            cfg.audit_sink_batch_size,
+            flush,
+        );
+
+        if let Some(url) = &cfg.audit_replica_url {
+            let replica: anyhow::Result<Arc<dyn AuditSink>> =
+                if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+                    PostgresReplicaAuditSink::new(url).map(|s| Arc::new(s) as _)
+                } else {
+                    ObjectStoreAuditSink::new(url).map(|s| Arc::new(s) as _)
+                };
+            match replica {
+                Ok(sink) => {
+                    tracing::info!(
+                        sink = sink.name(),
+                        sync = cfg.audit_replica_sync,
+                        "audit replica enabled"
+                    );
+                    hub = hub.with_replica(
+                        sink,
+                        cfg.audit_replica_sync,
+                        cfg.audit_sink_buffer,
+                        cfg.audit_sink_batch_size,
+                        flush,
+                        REPLICA_MAX_BACKOFF,
+                        REPLICA_MAX_ATTEMPTS,
+                    );
+                }
+                Err(e) => tracing::warn!("audit replica config error, disabling: {}", e),
+            }
+        }
+        hub.skip_db = cfg.audit_sink_skip_db && !hub.sinks.is_empty();
+        if cfg.audit_sink_skip_db && hub.sinks.is_empty() {
+            tracing::warn!(
+                "TRUEFLOW_AUDIT_SINK_SKIP_DB ignored: no external audit sink is configured"
+            );
+        }
+        hub
+    }
+
+    /// Start a worker per sink with the given queue capacity and batching.
+    pub fn with_sinks(
+        sinks: Vec<Arc<dyn AuditSink>>,
+        buffer: usize,
+        batch_size: usize,
+        flush: Duration,
+    ) -> Self {
+        let sinks = sinks
+            .into_iter()
+            .map(|sink| SinkHandle::spawn(sink, buffer, batch_size, flush, Retry::Bounded))
+            .collect();
+        Self {
+            sinks,
+            replica: None,
+            skip_db: false,
+        }
+    }
+
+    /// Add the audit replica. Its batches are retried (backing off up to
+    /// `max_backoff`) for up to `max_attempts` attempts; with `sync`, entries
+    /// are also written one at a time as they are dispatched.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_replica(
+        mut self,
+        sink: Arc<dyn AuditSink>,
+        sync: bool,
+        buffer: usize,
+        batch_size: usize,
+        flush: Duration,
+        max_backoff: Duration,
+        max_attempts: usize,
+    ) -> Self {
+        let queue = SinkHandle::spawn(
+            sink.clone(),
+            buffer,
+            batch_size,
+            flush,
+            Retry::Persistent {
+                max_backoff,
+                max_attempts,
+            },
+        );
+        self.replica = Some(ReplicaHandle { sink, sync, queue });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Whether the Postgres audit insert should be skipped.
+    pub fn skip_db(&self) -> bool {
+        self.skip_db
+    }
+
+    /// Events dropped so far because a sink's queue was full, per sink
+    /// (the audit replica last).
+    pub fn dropped_counts(&self) -> Vec<(&'static str, u64)> {
+        self.sinks
+            .iter()
+            .chain(self.replica.as_ref().map(|r| &r.queue))
+            .map(|s| (s.name, s.dropped.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Queue an entry for every sink. Never blocks.
+    pub fn send(&self, entry: &AuditEntry) {
+        if self.sinks.is_empty() && self.replica.is_none() {
+            return;
+        }
+        let json: Arc<str> = match serde_json::to_string(entry) {
+            Ok(s) => s.into(),
+            Err(e) => {
+                tracing::warn!(request_id = %entry.request_id, "audit sink serialization failed: {}", e);
+                return;
+            }
+        };
+        let event = SinkEvent {
+            key: entry.project_id.to_string(),
+            json,
+        };
+        for sink in &self.sinks {
+            sink.enqueue(event.clone());
+        }
+        match &self.replica {
+            Some(replica) if replica.sync => {
+                let sink = replica.sink.clone();
+                let (name, tx, dropped) = (
+                    replica.queue.name,
+                    replica.queue.tx.clone(),
+                    replica.queue.dropped.clone(),
+                );
+                tokio::spawn(async move {
+                    let written = tokio::time::timeout(
+                        REPLICA_SYNC_TIMEOUT,
+                        sink.send_batch(std::slice::from_ref(&event)),
+                    )
+                    .await;
+                    if let Ok(Ok(())) = written {
+                        return;
+                    }
+                    let error = match written {
+                        Ok(Err(e)) => e.to_string(),
+                        _ => "timed out".to_string(),
+                    };
+                    tracing::warn!(
+                        sink = name,
+                        "audit replica write failed, queued for retry: {}",
+                        error
+                    );
+                    enqueue(name, &tx, &dropped, event);
+                });
+            }
+            Some(replica) => replica.queue.enqueue(event),
+            None => {}
+        }
+    }
+}
+
+/// Drain a sink's queue: collect up to `batch_size` events or whatever arrived
+/// within `flush` of the first one, then deliver with retries.
+async fn run_worker(
+    sink: Arc<dyn AuditSink>,
+    mut rx: mpsc::Receiver<SinkEvent>,
+    batch_size: usize,
+    flush: Duration,
+    retry: Retry,
+) {
+    while let Some(first) = rx.recv().await {
+        let mut batch = Vec::with_capacity(batch_size);
+        batch.push(first);
+        let deadline = tokio::time::Instant::now() + flush;
+        while batch.len() < batch_size {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(event)) => batch.push(event),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        deliver(sink.as_ref(), &batch, retry).await;
+    }
+}
+
+async fn deliver(sink: &dyn AuditSink, batch: &[SinkEvent], retry: Retry) {
+    let mut attempt = 0usize;
+    loop {
+        let e = match sink.send_batch(batch).await {
+            Ok(()) => {
+                tracing::debug!(
+                    sink = sink.name(),
+                    events = batch.len(),
+                    "audit batch delivered"
+                );
+                return;
+            }
+            Err(e) => e,
+        };
+        attempt += 1;
+        tracing::warn!(
+            sink = sink.name(),
+            attempt,
+            "audit sink delivery failed, retrying: {}",
+            e
+        );
+        let backoff = match retry {
+            Retry::Bounded if attempt >= BACKOFF_MS.len() => {
+                tracing::error!(
+                    sink = sink.name(),
+                    events = batch.len(),
+                    error = %e,
+                    "AUDIT_SINK_FAILED: all retries exhausted — batch dropped"
+                );
+                return;
+            }
+            Retry::Bounded => Duration::from_millis(BACKOFF_MS[attempt - 1]),
+            Retry::Persistent { max_attempts, .. } if attempt >= max_attempts => {
+                // Name the entries so they can be re-shipped by hand.
+                let request_ids: Vec<String> = batch
+                    .iter()
+                    .filter_map(|e| serde_json::from_str::<AuditEntry>(&e.json).ok())
+                    .map(|e| e.request_id.to_string())
+                    .collect();
+                tracing::error!(
+                    sink = sink.name(),
+                    events = batch.len(),
+                    attempts = attempt,
+                    request_ids = ?request_ids,
+                    error = %e,
+                    "AUDIT_REPLICA_FAILED: batch dropped after repeated failures"
+                );
+                return;
+            }
+            // 100ms, 200ms, 400ms, ... up to max_backoff
+            Retry::Persistent { max_backoff, .. } => {
+                Duration::from_millis(100u64 << (attempt - 1).min(16)).min(max_backoff)
+            }
+        };
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::audit::tests::test_audit_entry;
+    use crate::models::audit::PolicyResult;
+    use tokio::sync::Mutex;
+
+    fn entry(project: uuid::Uuid) -> AuditEntry {
+        let mut entry = test_audit_entry(PolicyResult::Allow);
+        entry.project_id = project;
+        entry
+    }
+
+    #[derive(Default)]
+    struct MemorySink {
+        batches: Mutex<Vec<Vec<SinkEvent>>>,
+        fail_times: AtomicU64,
+        block: Option<Arc<tokio::sync::Notify>>,
+    }
+
+    #[async_trait]
+    impl AuditSink for MemorySink {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+
+        async fn send_batch(&self, batch: &[SinkEvent]) -> anyhow::Result<()> {
+            if let Some(block) = &self.block {
+                block.notified().await;
+            }
+            if self.fail_times.load(Ordering::SeqCst) > 0 {
+                self.fail_times.fetch_sub(1, Ordering::SeqCst);
+                anyhow::bail!("transient");
+            }
+            self.batches.lock().await.push(batch.to_vec());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_are_batched_as_audit_json() {
+        let sink = Arc::new(MemorySink::default());
+        let hub = AuditSinkHub::with_sinks(vec![sink.clone()], 100, 3, Duration::from_millis(50));
+        let project = uuid::Uuid::new_v4();
+        for _ in 0..5 {
+            hub.send(&entry(project));
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let batches = sink.batches.lock().await;
+        let sizes: Vec<usize> = batches.iter().map(|b| b.len()).collect();
+        assert_eq!(
+            sizes,
+            vec![3, 2],
+            "batch_size caps, flush interval drains the rest"
+        );
+        let parsed: AuditEntry = serde_json::from_str(&batches[0][0].json).unwrap();
+        assert_eq!(parsed.project_id, project);
+        assert_eq!(batches[0][0].key, project.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_is_retried() {
+        let sink = Arc::new(MemorySink {
+            fail_times: AtomicU64::new(1),
+            ..Default::default()
+        });
+        let hub = AuditSinkHub::with_sinks(vec![sink.clone()], 10, 10, Duration::from_millis(10));
+        hub.send(&entry(uuid::Uuid::new_v4()));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(sink.batches.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_without_blocking() {
+        let block = Arc::new(tokio::sync::Notify::new());
+        let sink = Arc::new(MemorySink {
+            block: Some(block.clone()),
+            ..Default::default()
+        });
+        let hub = AuditSinkHub::with_sinks(vec![sink.clone()], 2, 1, Duration::from_millis(1));
+        let start = std::time::Instant::now();
+        for _ in 0..50 {
+            hub.send(&entry(uuid::Uuid::new_v4()));
+        }
+        assert!(
+            start.elapsed() < Duration::from_millis(100),
+            "send must not block"
+        );
+        let dropped = hub.dropped_counts()[0].1;
+        assert!(
+            dropped >= 45,
+            "expected most events dropped, got {}",
+            dropped
+        );
+        block.notify_waiters();
+    }
+
+    #[tokio::test]
+    async fn test_http_sink_posts_json_array() {
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer s3cret"))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sink = HttpAuditSink::new(server.uri(), Some("Bearer s3cret".into()));
+        let batch = vec![
+            SinkEvent {
+                key: "p".into(),
+                json: r#"{"a":1}"#.into(),
+            },
+            SinkEvent {
+                key: "p".into(),
+                json: r#"{"a":2}"#.into(),
+            },
+        ];
+        sink.send_batch(&batch).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body, serde_json::json!([{"a": 1}, {"a": 2}]));
+    }
+
+    #[tokio::test]
+    async fn test_http_sink_error_status_fails() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        let sink = HttpAuditSink::new(server.uri(), None);
+        let batch = vec![SinkEvent {
+            key: "p".into(),
+            json: "{}".into(),
+        }];
+        assert!(sink.send_batch(&batch).await.is_err());
+    }
+
+    #[test]
+    fn test_otlp_log_record_mapping() {
+        let mut e = entry(uuid::Uuid::new_v4());
+        e.upstream_status = Some(200);
+        e.model = Some("gpt-4o".into());
+        e.prompt_tokens = Some(120);
+        e.completion_tokens = Some(30);
+        e.estimated_cost_usd = Some(rust_decimal::Decimal::new(125, 5));
+        let record = audit_log_record(&e);
+
+        assert_eq!(record.severity_number, SeverityNumber::Info as i32);
+        assert_eq!(record.trace_id, e.request_id.as_bytes().to_vec());
+        assert_eq!(record.span_id, e.request_id.as_bytes()[..8].to_vec());
+
+        // A client traceparent puts the record in the client's trace.
+        let mut traced = entry(uuid::Uuid::new_v4());
+        traced.trace_id = Some("4bf92f3577b34da6a3ce929d0e0e4736".into());
+        traced.parent_span_id = Some("00f067aa0ba902b7".into());
+        let traced = audit_log_record(&traced);
+        assert_eq!(
+            hex::encode(&traced.trace_id),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(hex::encode(&traced.span_id), "00f067aa0ba902b7");
+        let attr = |key: &str| {
+            record
+                .attributes
+                .iter()
+                .find(|kv| kv.key == key)
+                .and_then(|kv| kv.value.clone())
+                .and_then(|v| v.value)
+        };
+        assert_eq!(
+            attr("gen_ai.request.model"),
+            Some(any_value::Value::StringValue("gpt-4o".into()))
+        );
+        assert_eq!(
+            attr("gen_ai.usage.input_tokens"),
+            Some(any_value::Value::IntValue(120))
+        );
+        assert_eq!(
+            attr("trueflow.cost_usd"),
+            Some(any_value::Value::DoubleValue(0.00125))
+        );
+        assert!(attr("trueflow.policy").is_none());
+
+        e.policy_result = PolicyResult::Deny {
+            policy: "no-prod".into(),
+            reason: "r".into(),
+        };
+        e.upstream_status = None;
+        let denied = audit_log_record(&e);
+        assert_eq!(denied.severity_number, SeverityNumber::Warn as i32);
+        assert_eq!(denied.severity_text, "WARN");
+
+        e.policy_result = PolicyResult::Allow;
+        e.upstream_status = Some(502);
+        assert_eq!(
+            audit_log_record(&e).severity_number,
+            SeverityNumber::Error as i32
+        );
+    }
+
+    #[test]
+    fn test_empty_hub_never_skips_db() {
+        let hub = AuditSinkHub::default();
+        assert!(hub.is_empty());
+        assert!(!hub.skip_db());
+        hub.send(&entry(uuid::Uuid::new_v4()));
+    }
+
+    #[tokio::test]
+    async fn test_replica_retries_until_delivered() {
+        // More failures than the bounded schedule allows.
+        let sink = Arc::new(MemorySink {
+            fail_times: AtomicU64::new(5),
+            ..Default::default()
+        });
+        let hub = AuditSinkHub::default().with_replica(
+            sink.clone(),
+            false,
+            10,
+            10,
+            Duration::from_millis(10),
+            Duration::from_millis(5),
+            100,
+        );
+        hub.send(&entry(uuid::Uuid::new_v4()));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(sink.batches.lock().await.len(), 1);
+        assert!(hub.is_empty(), "the replica doesn't count toward skip_db");
+    }
+
+    #[tokio::test]
+    async fn test_sync_replica_writes_each_entry_immediately() {
+        let sink = Arc::new(MemorySink::default());
+        // A flush interval far longer than the test: only sync writes land.
+        let hub = AuditSinkHub::default().with_replica(
+            sink.clone(),
+            true,
+            10,
+            100,
+            Duration::from_secs(60),
+            Duration::from_millis(5),
+            100,
+        );
+        for _ in 0..3 {
+            hub.send(&entry(uuid::Uuid::new_v4()));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let sizes: Vec<usize> = sink.batches.lock().await.iter().map(|b| b.len()).collect();
+        assert_eq!(sizes, vec![1, 1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_replica_failure_falls_back_to_queue() {
+        let sink = Arc::new(MemorySink {
+            fail_times: AtomicU64::new(1),
+            ..Default::default()
+        });
+        let hub = AuditSinkHub::default().with_replica(
+            sink.clone(),
+            true,
+            10,
+            10,
+            Duration::from_millis(10),
+            Duration::from_millis(5),
+            100,
+        );
+        let project = uuid::Uuid::new_v4();
+        hub.send(&entry(project));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let batches = sink.batches.lock().await;
+        assert_eq!(batches.len(), 1, "written once, by the retry queue");
+        assert_eq!(batches[0][0].key, project.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_object_store_replica_appends_jsonl() {
+        let dir = std::env::temp_dir().join(format!("trueflow-audit-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sink = ObjectStoreAuditSink::new(&format!("file://{}", dir.display())).unwrap();
+
+        let entries = [entry(uuid::Uuid::new_v4()), entry(uuid::Uuid::new_v4())];
+        let batch: Vec<SinkEvent> = entries
+            .iter()
+            .map(|e| SinkEvent {
+                key: e.project_id.to_string(),
+                json: serde_json::to_string(e).unwrap().into(),
+            })
+            .collect();
+        sink.send_batch(&batch).await.unwrap();
+        // A retried batch overwrites the same object.
+        sink.send_batch(&batch).await.unwrap();
+
+        let written = std::fs::read_to_string(dir.join(sink.key(&entries[0]))).unwrap();
+        let lines: Vec<AuditEntry> = written
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].project_id, entries[1].project_id);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_replica_drops_poison_batch_and_moves_on() {
+        let sink = Arc::new(MemorySink {
+            fail_times: AtomicU64::new(3),
+            ..Default::default()
+        });
+        let hub = AuditSinkHub::default().with_replica(
+            sink.clone(),
+            false,
+            10,
+            1,
+            Duration::from_millis(1),
+            Duration::from_millis(5),
+            3,
+        );
+        let (poison, next) = (entry(uuid::Uuid::new_v4()), entry(uuid::Uuid::new_v4()));
+        hub.send(&poison);
+        hub.send(&next);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let batches = sink.batches.lock().await;
+        assert_eq!(batches.len(), 1, "the failing batch is dropped");
+        assert_eq!(batches[0][0].key, next.project_id.to_string());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at TEST_DATABASE_URL"]
+    async fn test_postgres_replica_batch_is_all_or_nothing() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let store = crate::store::postgres::PgStore::connect(&url)
+            .await
+            .unwrap();
+        store.migrate().await.unwrap();
+        let sink = PostgresReplicaAuditSink::new(&url).unwrap();
+        let event = |e: &AuditEntry| SinkEvent {
+            key: e.project_id.to_string(),
+            json: serde_json::to_string(e).unwrap().into(),
+        };
+        let count = |id: uuid::Uuid| {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM audit_logs WHERE id = $1")
+                .bind(id)
+                .fetch_one(&sink.pool)
+        };
+
+        // The repeated entry fails on its primary key, rolling back the first.
+        // Timestamps fall in a partition the migrations create.
+        let in_partition = |mut e: AuditEntry| {
+            e.timestamp = "2026-03-15T12:00:00Z".parse().unwrap();
+            e
+        };
+        let first = in_partition(entry(uuid::Uuid::new_v4()));
+        let second = in_partition(entry(uuid::Uuid::new_v4()));
+        let batch = [event(&first), event(&second), event(&second)];
+        assert!(sink.send_batch(&batch).await.is_err());
+        assert_eq!(count(first.request_id).await.unwrap(), 0);
+
+        sink.send_batch(&batch[..2]).await.unwrap();
+        assert_eq!(count(first.request_id).await.unwrap(), 1);
+        assert_eq!(count(second.request_id).await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_s3_url_path_is_the_key_prefix() {
+        assert_eq!(
+            split_key_prefix("s3://audit-dr/eu/replica/?region=eu-west-1"),
+            (
+                "s3://audit-dr?region=eu-west-1".to_string(),
+                "eu/replica".to_string()
+            )
+        );
+        assert_eq!(
+            split_key_prefix("s3://audit-dr"),
+            ("s3://audit-dr".to_string(), String::new())
+        );
+        assert_eq!(
+            split_key_prefix("file:///var/audit"),
+            ("file:///var/audit".to_string(), String::new())
+        );
+
+        let sink = ObjectStoreAuditSink::new("s3://audit-dr/eu?region=eu-west-1").unwrap();
+        let first = entry(uuid::Uuid::new_v4());
+        assert!(sink.key(&first).starts_with("eu/audit-log/"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Model capability check — pre-flight detection of features the target
+//! model doesn't support (see [`crate::models::capabilities`]).
+//!
+//! Per-token `capability_action` decides what happens to an OpenAI-format
+//! request that uses `tools`, image or audio inputs, or `response_format`
+//! against a model without that capability:
+//! - `strip`: remove the unsupported fields, replace image/audio parts with a
+//!   text placeholder, report the features in `X-TrueFlow-Capability-Stripped`
+//!   and record what was removed in the audit log (`capability_stripped`)
+//! - `reject`: fail with `unsupported_model_feature` before calling upstream
+//!
+//! Unset (the default) skips the check entirely.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::models::capabilities::ModelCapabilities;
+
+/// Accepted values for `tokens.capability_action`.
+pub const ACTIONS: [&str; 2] = ["strip", "reject"];
+
+/// What to do with a request using features the model lacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilityAction {
+    Strip,
+    Reject,
+}
+
+impl CapabilityAction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "strip" => Some(Self::Strip),
+            "reject" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// A request feature gated by [`ModelCapabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Tools,
+    Vision,
+    JsonMode,
+    Audio,
+}
+
+impl Feature {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Feature::Tools => "tools",
+            Feature::Vision => "vision",
+            Feature::JsonMode => "json_mode",
+            Feature::Audio => "audio",
+        }
+    }
+}
+
+/// An image or audio part replaced by a placeholder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StrippedPart {
+    /// Index into `messages`.
+    pub message: usize,
+    /// Index into the message's content parts.
+    pub part: usize,
+    /// `image` or `audio`.
+    pub kind: &'static str,
+    /// The URL (without query string), or media type and size of inline data.
+    pub source: String,
+}
+
+/// Request fields that make up tool use.
+const TOOL_FIELDS: [&str; 5] = [
+    "tools",
+    "tool_choice",
+    "parallel_tool_calls",
+    "functions",
+    "function_call",
+];
+
+/// Content part types carrying an image.
+const IMAGE_PART_TYPES: [&str; 3] = ["image_url", "image", "input_image"];
+
+/// Content part types carrying audio.
+const AUDIO_PART_TYPES: [&str; 2] = ["input_audio", "audio"];
+
+fn uses_tools(body: &Value) -> bool {
+    ["tools", "functions"].iter().any(|k| {
+        body.get(*k)
+            .and_then(Value::as_array)
+            .is_some_and(|a| !a.is_empty())
+    })
+}
+
+fn part_is(part: &Value, types: &[&str]) -> bool {
+    part.get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|t| types.contains(&t))
+}
+
+fn uses_parts(body: &Value, types: &[&str]) -> bool {
+    body.get("messages")
+        .and_then(Value::as_array)
+        .is_some_and(|messages| {
+            messages.iter().any(|m| {
+                m.get("content")
+                    .and_then(Value::as_array)
+                    .is_some_and(|parts| parts.iter().any(|p| part_is(p, types)))
+            })
+        })
+}
+
+fn uses_json_mode(body: &Value) -> bool {
+    matches!(
+        body.pointer("/response_format/type")
+            .and_then(Value::as_str),
+        Some("json_object" | "json_schema")
+    )
+}
+
+/// Features `body` uses that `caps` doesn't support, in a stable order.
+pub fn unsupported_features(body: &Value, caps: &ModelCapabilities) -> Vec<Feature> {
+    let mut out = Vec::new();
+    if !caps.supports_tools && uses_tools(body) {
+        out.push(Feature::Tools);
+    }
+    if !caps.supports_vision && uses_parts(body, &IMAGE_PART_TYPES) {
+        out.push(Feature::Vision);
+    }
+    if !caps.supports_json_mode && uses_json_mode(body) {
+        out.push(Feature::JsonMode);
+    }
+    if !caps.supports_audio && uses_parts(body, &AUDIO_PART_TYPES) {
+        out.push(Feature::Audio);
+    }
+    out
+}
+
+/// Describe inline `data:` content by media type and size, and URLs without
+/// their query string (which may carry a signature).
+fn describe_source(reference: &str) -> String {
+    if let Some(data) = reference.strip_prefix("data:") {
+        let media_type = data.split([';', ',']).next().unwrap_or_default();
+        let len = data.split_once(',').map_or(0, |(_, d)| d.len());
+        return format!("data:{} ({} bytes)", media_type, len);
+    }
+    match url::Url::parse(reference) {
+        Ok(mut url) => {
+            url.set_query(None);
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+fn part_source(part: &Value) -> String {
+    // OpenAI `image_url`/`input_image`, Anthropic `image` blocks and
+    // `input_audio`.
+    if let Some(url) = part
+        .pointer("/image_url/url")
+        .or_else(|| part.get("image_url"))
+        .or_else(|| part.pointer("/source/url"))
+        .and_then(Value::as_str)
+    {
+        return describe_source(url);
+    }
+    if let Some(source) = part.get("source").filter(|s| s.get("data").is_some()) {
+        let media_type = source.get("media_type").and_then(Value::as_str);
+        let len = source
+            .get("data")
+            .and_then(Value::as_str)
+            .map_or(0, str::len);
+        return format!("data:{} ({} bytes)", media_type.unwrap_or("unknown"), len);
+    }
+    if let Some(audio) = part.get("input_audio").or_else(|| part.get("audio")) {
+        let format = audio.get("format").and_then(Value::as_str);
+        let len = audio
+            .get("data")
+            .and_then(Value::as_str)
+            .map_or(0, str::len);
+        return format!("audio/{} ({} bytes)", format.unwrap_or("unknown"), len);
+    }
+    "unknown".to_string()
+}
+
+/// Text part standing in for removed `kind` content.
+fn placeholder(kind: &str) -> Value {
+    serde_json::json!({
+        "type": "text",
+        "text": format!("[{} removed: not supported by this model]", kind),
+    })
+}
+
+/// Replace every part of `types` in message content with a text placeholder.
+fn replace_parts(
+    messages: &mut [Value],
+    types: &[&str],
+    kind: &'static str,
+    stripped: &mut Vec<StrippedPart>,
+) {
+    for (message_index, message) in messages.iter_mut().enumerate() {
+        let Some(parts) = message.get_mut("content").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for (part_index, part) in parts.iter_mut().enumerate() {
+            if !part_is(part, types) {
+                continue;
+            }
+            stripped.push(StrippedPart {
+                message: message_index,
+                part: part_index,
+                kind,
+                source: part_source(part),
+            });
+            *part = placeholder(kind);
+        }
+    }
+}
+
+/// Remove the fields behind `features` from `body`. Image and audio parts
+/// are replaced with a text placeholder and returned.
+pub fn strip_features(body: &mut Value, features: &[Feature]) -> Vec<StrippedPart> {
+    let mut stripped = Vec::new();
+    let Some(obj) = body.as_object_mut() else {
+        return stripped;
+    };
+    for feature in features {
+        match feature {
+            Feature::Tools => {
+                for field in TOOL_FIELDS {
+                    obj.remove(field);
+                }
+            }
+            Feature::JsonMode => {
+                obj.remove("response_format");
+            }
+            Feature::Vision | Feature::Audio => {
+                let Some(messages) = obj.get_mut("messages").and_then(Value::as_array_mut) else {
+                    continue;
+                };
+                let (types, kind): (&[&str], _) = if *feature == Feature::Vision {
+                    (&IMAGE_PART_TYPES, "image")
+                } else {
+                    (&AUDIO_PART_TYPES, "audio")
+                };
+                replace_parts(messages, types, kind, &mut stripped);
+            }
+        }
+    }
+    stripped
+}
+
+/// The audit log `capability_stripped` value: stripped features and parts.
+pub fn audit_record(features: &[Feature], parts: &[StrippedPart]) -> Value {
+    serde_json::json!({
+        "features": features.iter().map(|f| f.as_str()).collect::<Vec<_>>(),
+        "parts": parts,
+    })
+}
+
+/// Comma-separated feature names, for headers and error details.
+pub fn feature_list(features: &[Feature]) -> String {
+    features
+        .iter()
+        .map(|f| f.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NO_FEATURES: ModelCapabilities = ModelCapabilities {
+        supports_tools: false,
+        supports_vision: false,
+        supports_json_mode: false,
+        supports_audio: false,
+        max_context: None,
+    };
+
+    fn request() -> Value {
+        json!({
+            "model": "o1-mini",
+            "tools": [{"type": "function", "function": {"name": "lookup"}}],
+            "tool_choice": "auto",
+            "response_format": {"type": "json_object"},
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "What's in this picture?"},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}},
+                    {"type": "input_audio", "input_audio": {"data": "UklGRg==", "format": "wav"}}
+                ]}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_detects_only_unsupported_features() {
+        let body = request();
+        assert_eq!(
+            unsupported_features(&body, &NO_FEATURES),
+            vec![
+                Feature::Tools,
+                Feature::Vision,
+                Feature::JsonMode,
+                Feature::Audio
+            ]
+        );
+        assert!(unsupported_features(&body, &ModelCapabilities::default()).is_empty());
+
+        let plain = json!({"messages": [{"role": "user", "content": "hi"}], "tools": []});
+        assert!(unsupported_features(&plain, &NO_FEATURES).is_empty());
+        let text_format = json!({"response_format": {"type": "text"}});
+        assert!(unsupported_features(&text_format, &NO_FEATURES).is_empty());
+    }
+
+    #[test]
+    fn test_strip_removes_fields_and_replaces_media_parts() {
+        let mut body = request();
+        let features = unsupported_features(&body, &NO_FEATURES);
+        let stripped = strip_features(&mut body, &features);
+
+        assert!(body.get("tools").is_none());
+        assert!(body.get("tool_choice").is_none());
+        assert!(body.get("response_format").is_none());
+        assert_eq!(
+            body["messages"][0]["content"],
+            json!([{"type": "text", "text": "What's in this picture?"}, placeholder("image")])
+        );
+        assert_eq!(
+            body["messages"][1]["content"],
+            json!([placeholder("image"), placeholder("audio")])
+        );
+        assert!(unsupported_features(&body, &NO_FEATURES).is_empty());
+        assert_eq!(feature_list(&features), "tools,vision,json_mode,audio");
+
+        let sources: Vec<_> = stripped
+            .iter()
+            .map(|p| (p.message, p.part, p.kind, p.source.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                (0, 1, "image", "https://example.com/a.png"),
+                (1, 0, "image", "data:image/png (12 bytes)"),
+                (1, 1, "audio", "audio/wav (8 bytes)"),
+            ]
+        );
+        let record = audit_record(&features, &stripped);
+        assert_eq!(record["features"][3], "audio");
+        assert_eq!(record["parts"][2]["kind"], "audio");
+    }
+
+    #[test]
+    fn test_part_sources_hide_query_and_inline_data() {
+        let signed = json!({"type": "image_url", "image_url": {"url": "https://cdn.example.com/a.png?sig=secret"}});
+        assert_eq!(part_source(&signed), "https://cdn.example.com/a.png");
+        let anthropic = json!({"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "/9j/4AAQ"}});
+        assert_eq!(part_source(&anthropic), "data:image/jpeg (8 bytes)");
+        let responses_api =
+            json!({"type": "input_image", "image_url": "https://example.com/b.png"});
+        assert_eq!(part_source(&responses_api), "https://example.com/b.png");
+    }
+}
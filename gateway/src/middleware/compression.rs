@@ -0,0 +1,170 @@
+//! Response compression for clients that send `Accept-Encoding`.
+//!
+//! Off unless `TRUEFLOW_RESPONSE_COMPRESSION` is set. Only JSON bodies of a
+//! known size at or above `TRUEFLOW_RESPONSE_COMPRESSION_MIN_BYTES` are
+//! compressed (gzip or brotli, whichever the client prefers). Streaming SSE
+//! has no fixed size and a `text/event-stream` type, so it always passes
+//! through untouched, as do bodies that already carry a `Content-Encoding`.
+
+use axum::http::{header, Response};
+use tower_http::compression::{CompressionLayer, Predicate};
+
+/// Default for `TRUEFLOW_RESPONSE_COMPRESSION_MIN_BYTES`. Below roughly 1 KB
+/// the gzip framing eats most of the saving.
+pub const DEFAULT_MIN_BYTES: u64 = 1024;
+
+/// Compress complete JSON responses of at least `min_bytes`.
+#[derive(Debug, Clone, Copy)]
+pub struct JsonCompression {
+    pub enabled: bool,
+    pub min_bytes: u64,
+}
+
+impl Predicate for JsonCompression {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: axum::body::HttpBody,
+    {
+        if !self.enabled {
+            return false;
+        }
+        let is_json = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(is_json_content_type);
+        if !is_json {
+            return false;
+        }
+        // Unknown size means a streamed body: leave it alone.
+        let size = response.body().size_hint().exact().or_else(|| {
+            response
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+        });
+        size.is_some_and(|s| s >= self.min_bytes)
+    }
+}
+
+pub(crate) fn is_json_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    mime == "application/json" || (mime.starts_with("application/") && mime.ends_with("+json"))
+}
+
+/// The layer for the whole router. Always installed; a disabled predicate
+/// makes it a pass-through.
+pub fn layer(enabled: bool, min_bytes: u64) -> CompressionLayer<JsonCompression> {
+    CompressionLayer::new()
+        .gzip(true)
+        .br(true)
+        .no_deflate()
+        .no_zstd()
+        .compress_when(JsonCompression { enabled, min_bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn response(content_type: &str, body: Body) -> Response<Body> {
+        Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_is_json_content_type() {
+        assert!(is_json_content_type("application/json; charset=utf-8"));
+        assert!(is_json_content_type("Application/Problem+JSON"));
+        assert!(!is_json_content_type("multipart/form-data; boundary=x"));
+        assert!(!is_json_content_type("audio/mpeg"));
+    }
+
+    #[test]
+    fn test_compresses_only_large_complete_json() {
+        let on = JsonCompression {
+            enabled: true,
+            min_bytes: 16,
+        };
+        let big = || Body::from(vec![b'a'; 64]);
+        assert!(on.should_compress(&response("application/json", big())));
+        assert!(on.should_compress(&response("application/json; charset=utf-8", big())));
+        assert!(on.should_compress(&response("application/problem+json", big())));
+
+        assert!(!on.should_compress(&response("application/json", Body::from("{}"))));
+        assert!(!on.should_compress(&response("text/event-stream", big())));
+        assert!(!on.should_compress(&response("image/png", big())));
+        assert!(!on.should_compress(&response("application/gzip", big())));
+
+        // Streamed bodies have no exact size.
+        let stream = Body::from_stream(futures::stream::iter(vec![Ok::<_, std::io::Error>(
+            axum::body::Bytes::from(vec![b'a'; 64]),
+        )]));
+        assert!(!on.should_compress(&response("application/json", stream)));
+
+        let off = JsonCompression {
+            enabled: false,
+            min_bytes: 16,
+        };
+        assert!(!off.should_compress(&response("application/json", big())));
+    }
+
+    #[tokio::test]
+    async fn test_layer_keeps_headers_and_skips_sse() {
+        use axum::http::Request;
+        use axum::routing::get;
+        use tower::ServiceExt;
+
+        let json = vec![b' '; 4096];
+        let app = axum::Router::new()
+            .route(
+                "/json",
+                get(move || async move {
+                    (
+                        [
+                            (header::CONTENT_TYPE, "application/json"),
+                            (
+                                header::HeaderName::from_static("x-trueflow-cost-usd"),
+                                "0.01",
+                            ),
+                        ],
+                        json,
+                    )
+                }),
+            )
+            .route(
+                "/sse",
+                get(|| async {
+                    (
+                        [(header::CONTENT_TYPE, "text/event-stream")],
+                        vec![b' '; 4096],
+                    )
+                }),
+            )
+            .layer(layer(true, DEFAULT_MIN_BYTES));
+
+        let req = |path: &str| {
+            Request::builder()
+                .uri(path)
+                .header(header::ACCEPT_ENCODING, "br;q=0.5, gzip")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(req("/json")).await.unwrap();
+        assert_eq!(resp.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(resp.headers()["x-trueflow-cost-usd"], "0.01");
+        assert!(resp.headers().get(header::CONTENT_LENGTH).is_none());
+
+        let resp = app.oneshot(req("/sse")).await.unwrap();
+        assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+}
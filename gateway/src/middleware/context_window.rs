@@ -0,0 +1,207 @@
+//! Context Window Guard — pre-flight check for prompts that won't fit.
+//!
+//! Per-token `context_window_action` decides what happens when the estimated
+//! prompt plus the requested completion exceeds the model's context window
+//! (see [`crate::models::tokenizer`]):
+//! - `reject`: fail with `context_length_exceeded` before calling upstream
+//! - `trim`: drop the oldest conversation turns until the request fits,
+//!   keeping system messages and the latest message
+//!
+//! Unset (the default) skips the check entirely.
+
+use serde_json::Value;
+
+use crate::models::tokenizer::{
+    estimate_message_tokens, estimate_prompt_tokens, requested_output_tokens,
+};
+
+/// Accepted values for `tokens.context_window_action`.
+pub const ACTIONS: [&str; 2] = ["reject", "trim"];
+
+/// What to do with a request that would overflow the context window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowAction {
+    Reject,
+    Trim,
+}
+
+impl OverflowAction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "reject" => Some(Self::Reject),
+            "trim" => Some(Self::Trim),
+            _ => None,
+        }
+    }
+}
+
+/// Result of [`ContextWindowGuard::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardOutcome {
+    /// The request fits; `estimated_tokens` includes the completion reserve.
+    Fits { estimated_tokens: u32 },
+    /// Oldest turns were removed so the request fits.
+    Trimmed {
+        estimated_before: u32,
+        estimated_tokens: u32,
+        messages_removed: usize,
+    },
+    /// The request doesn't fit (and couldn't be trimmed to fit).
+    Overflow { estimated_tokens: u32 },
+}
+
+/// Checks a chat request against one model's context window.
+pub struct ContextWindowGuard {
+    pub context_window: u32,
+    pub action: OverflowAction,
+}
+
+impl ContextWindowGuard {
+    /// Check `body` and, for [`OverflowAction::Trim`], remove the oldest
+    /// non-system messages until it fits. A tool result is never left
+    /// without the assistant turn that called it.
+    pub fn apply(&self, body: &mut Value) -> GuardOutcome {
+        let reserve = requested_output_tokens(body);
+        let estimated_before = estimate_prompt_tokens(body).saturating_add(reserve);
+        if estimated_before <= self.context_window {
+            return GuardOutcome::Fits {
+                estimated_tokens: estimated_before,
+            };
+        }
+        if self.action == OverflowAction::Reject {
+            return GuardOutcome::Overflow {
+                estimated_tokens: estimated_before,
+            };
+        }
+
+        let Some(messages) = body.get_mut("messages").and_then(Value::as_array_mut) else {
+            return GuardOutcome::Overflow {
+                estimated_tokens: estimated_before,
+            };
+        };
+        let mut estimated = estimated_before;
+        let mut removed = 0;
+        while estimated > self.context_window {
+            // Oldest message that is neither a system prompt nor the latest turn.
+            let last = messages.len().saturating_sub(1);
+            let Some(idx) = messages[..last].iter().position(|m| !is_system(m)) else {
+                break;
+            };
+            estimated -= estimate_message_tokens(&messages.remove(idx));
+            removed += 1;
+            // Drop tool results orphaned by removing their assistant turn.
+            while idx < messages.len().saturating_sub(1) && is_tool_result(&messages[idx]) {
+                estimated -= estimate_message_tokens(&messages.remove(idx));
+                removed += 1;
+            }
+        }
+
+        if estimated > self.context_window {
+            GuardOutcome::Overflow {
+                estimated_tokens: estimated,
+            }
+        } else {
+            GuardOutcome::Trimmed {
+                estimated_before,
+                estimated_tokens: estimated,
+                messages_removed: removed,
+            }
+        }
+    }
+}
+
+fn is_system(message: &Value) -> bool {
+    matches!(
+        message.get("role").and_then(Value::as_str),
+        Some("system" | "developer")
+    )
+}
+
+fn is_tool_result(message: &Value) -> bool {
+    message.get("role").and_then(Value::as_str) == Some("tool")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn msg(role: &str, chars: usize) -> Value {
+        json!({"role": role, "content": "x".repeat(chars)})
+    }
+
+    #[test]
+    fn test_fits_and_reject_leave_body_untouched() {
+        let mut body = json!({"messages": [msg("user", 400)], "max_tokens": 50});
+        let guard = ContextWindowGuard {
+            context_window: 1_000,
+            action: OverflowAction::Reject,
+        };
+        // 100 + 4 framing + 3 priming + 50 reserve
+        assert_eq!(
+            guard.apply(&mut body),
+            GuardOutcome::Fits {
+                estimated_tokens: 157
+            }
+        );
+
+        let guard = ContextWindowGuard {
+            context_window: 120,
+            action: OverflowAction::Reject,
+        };
+        assert!(matches!(
+            guard.apply(&mut body),
+            GuardOutcome::Overflow { .. }
+        ));
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_trim_drops_oldest_turns_and_orphaned_tool_results() {
+        let mut body = json!({"messages": [
+            msg("system", 40),
+            {"role": "assistant", "content": null, "tool_calls": [{"id": "c1", "function": {"name": "f", "arguments": "{}"}}]},
+            msg("tool", 400),
+            msg("user", 400),
+            msg("assistant", 400),
+            msg("user", 40),
+        ]});
+        let guard = ContextWindowGuard {
+            context_window: 150,
+            action: OverflowAction::Trim,
+        };
+        let outcome = guard.apply(&mut body);
+        let roles: Vec<&str> = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["system", "assistant", "user"]);
+        match outcome {
+            GuardOutcome::Trimmed {
+                estimated_tokens,
+                messages_removed,
+                ..
+            } => {
+                assert!(estimated_tokens <= 150);
+                assert_eq!(messages_removed, 3);
+            }
+            other => panic!("expected Trimmed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_trim_overflows_when_latest_message_alone_is_too_big() {
+        let mut body = json!({"messages": [msg("user", 40), msg("user", 4_000)]});
+        let guard = ContextWindowGuard {
+            context_window: 500,
+            action: OverflowAction::Trim,
+        };
+        assert!(matches!(
+            guard.apply(&mut body),
+            GuardOutcome::Overflow { .. }
+        ));
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde_json::Value;
 
 use super::operators::glob_match;
@@ -13,6 +15,7 @@ pub(super) fn action_name(action: &Action) -> &'static str {
         Action::Redact { .. } => "redact",
         Action::Transform { .. } => "transform",
         Action::Override { .. } => "override",
+        Action::InjectResponseField { .. } => "inject_response_field",
         Action::Log { .. } => "log",
         Action::Tag { .. } => "tag",
         Action::Webhook { .. } => "webhook",
@@ -20,9 +23,12 @@ pub(super) fn action_name(action: &Action) -> &'static str {
         Action::Split { .. } => "split",
         Action::DynamicRoute { .. } => "dynamic_route",
         Action::ValidateSchema { .. } => "validate_schema",
+        Action::CoerceResponseSchema { .. } => "coerce_response_schema",
         Action::ConditionalRoute { .. } => "conditional_route",
         Action::ExternalGuardrail { .. } => "external_guardrail",
         Action::ToolScope { .. } => "tool_scope",
+        Action::RequireProperties { .. } => "require_properties",
+        Action::KeywordBlock { .. } => "keyword_block",
     }
 }
 
@@ -125,4 +131,44 @@ pub fn evaluate_tool_scope(
     Ok(())
 }
 
+// ── Required Request Metadata ────────────────────────────────
+
+/// Check `X-Properties` against a `require_properties` action.
+///
+/// Returns the offending keys (missing, or with a value outside the allowed
+/// set), in the order they were declared. Empty means the request passes.
+/// Non-string values are compared by their JSON text (e.g. `42`, `true`).
+pub fn evaluate_required_properties(
+    properties: Option<&Value>,
+    required_keys: &[String],
+    allowed_values: Option<&HashMap<String, Vec<String>>>,
+) -> Vec<String> {
+    let props = properties.and_then(|v| v.as_object());
+    let lookup = |key: &str| props.and_then(|p| p.get(key)).filter(|v| !v.is_null());
+    let mut offending: Vec<String> = Vec::new();
+
+    for key in required_keys {
+        if lookup(key).is_none() {
+            offending.push(key.clone());
+        }
+    }
+
+    if let Some(allowed) = allowed_values {
+        let mut keys: Vec<&String> = allowed.keys().collect();
+        keys.sort();
+        for key in keys {
+            let Some(value) = lookup(key) else { continue };
+            let text = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if !allowed[key].contains(&text) && !offending.contains(key) {
+                offending.push(key.clone());
+            }
+        }
+    }
+
+    offending
+}
+
 // ── Tests ────────────────────────────────────────────────────
@@ -5,17 +5,25 @@ mod operators;
 #[cfg(test)]
 mod tests;
 
-use crate::models::policy::{EvalOutcome, Phase, Policy, PolicyMode, TriggeredAction};
+use crate::models::policy::{Action, EvalOutcome, Phase, Policy, PolicyMode, TriggeredAction};
 
 use super::fields::RequestContext;
 
 use self::actions::action_name;
-pub use self::actions::{evaluate_tool_scope, extract_tool_names};
+pub use self::actions::{evaluate_required_properties, evaluate_tool_scope, extract_tool_names};
 pub use self::evaluate::evaluate_condition;
 pub(crate) use self::operators::glob_match;
 
 /// Evaluate all policies against a request context.
 ///
+/// Conflict resolution is deterministic:
+/// - policies run in ascending `priority`; ties keep slice (attachment) order
+/// - within a policy, rules and actions keep their declared order, so when two
+///   policies override the same body field the later one wins
+/// - terminal `Deny` actions are moved ahead of everything else (deny-wins), so
+///   a deny is never preceded by side effects such as rate-limit increments,
+///   webhooks or HITL waits from other matching rules
+///
 /// Returns an `EvalOutcome` containing:
 /// - `actions`         — blocking rules to execute before responding
 /// - `async_triggered` — async rules (rule.async_check=true) to run after responding
@@ -27,7 +35,10 @@ pub fn evaluate_policies(
 ) -> EvalOutcome {
     let mut outcome = EvalOutcome::default();
 
-    for policy in policies {
+    let mut ordered: Vec<&Policy> = policies.iter().collect();
+    ordered.sort_by_key(|p| p.priority); // stable: ties keep attachment order
+
+    for policy in ordered {
         // Skip policies not matching the current phase
         if policy.phase != *phase {
             continue;
@@ -75,6 +86,14 @@ pub fn evaluate_policies(
         }
     }
 
+    // Deny-wins: stable partition keeps relative order on both sides.
+    let (mut denies, rest): (Vec<_>, Vec<_>) = outcome
+        .actions
+        .into_iter()
+        .partition(|ta| matches!(ta.action, Action::Deny { .. }));
+    denies.extend(rest);
+    outcome.actions = denies;
+
     outcome
 }
 
@@ -62,14 +62,18 @@ pub(super) fn check_in(actual: &Value, expected: &Value) -> bool {
     }
 }
 
-/// Glob pattern matching (supports `*` and `?`).
+/// Glob pattern matching (supports `*` and `?`). Array values (wildcard
+/// extraction, `request.tool_names`) match if any element does.
 pub(super) fn check_glob(actual: &Value, pattern: &Value) -> bool {
-    let actual_str = value_as_str(actual);
-    let pattern_str = value_as_str(pattern);
-    match (actual_str, pattern_str) {
-        (Some(a), Some(p)) => glob_match(&p, &a),
-        _ => false,
+    let Some(p) = value_as_str(pattern) else {
+        return false;
+    };
+    if let Value::Array(arr) = actual {
+        return arr
+            .iter()
+            .any(|elem| value_as_str(elem).is_some_and(|a| glob_match(&p, &a)));
     }
+    value_as_str(actual).is_some_and(|a| glob_match(&p, &a))
 }
 
 /// Simple glob matching: `*` matches any sequence, `?` matches one char.
@@ -20,6 +20,7 @@ fn make_ctx<'a>(
         headers,
         body,
         body_size: body.map(|b| b.to_string().len()).unwrap_or(0),
+        estimated_cost_usd: None,
         agent_name: Some("test-agent"),
         token_id: "tok_123",
         token_name: "My Token",
@@ -816,6 +817,7 @@ fn test_evaluate_full_policy_triggers_action() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "high-value-hitl".to_string(),
+        priority: 0,
         phase: Phase::Pre,
         mode: PolicyMode::Enforce,
         rules: vec![Rule {
@@ -863,6 +865,7 @@ fn test_evaluate_policy_no_match() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "high-value-only".to_string(),
+        priority: 0,
         phase: Phase::Pre,
         mode: PolicyMode::Enforce,
         rules: vec![Rule {
@@ -895,6 +898,7 @@ fn test_shadow_mode_logs_but_doesnt_enforce() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "shadow-test".to_string(),
+        priority: 0,
         phase: Phase::Pre,
         mode: PolicyMode::Shadow,
         rules: vec![Rule {
@@ -927,6 +931,7 @@ fn test_phase_filtering() {
     let pre_policy = Policy {
         id: Uuid::new_v4(),
         name: "pre-only".to_string(),
+        priority: 0,
         phase: Phase::Pre,
         mode: PolicyMode::Enforce,
         rules: vec![Rule {
@@ -943,6 +948,7 @@ fn test_phase_filtering() {
     let post_policy = Policy {
         id: Uuid::new_v4(),
         name: "post-only".to_string(),
+        priority: 0,
         phase: Phase::Post,
         mode: PolicyMode::Enforce,
         rules: vec![Rule {
@@ -1000,6 +1006,7 @@ fn test_multiple_policies_multiple_actions() {
         Policy {
             id: Uuid::new_v4(),
             name: "always-log".to_string(),
+            priority: 0,
             phase: Phase::Pre,
             mode: PolicyMode::Enforce,
             rules: vec![Rule {
@@ -1015,6 +1022,7 @@ fn test_multiple_policies_multiple_actions() {
         Policy {
             id: Uuid::new_v4(),
             name: "gpt4-rate-limit".to_string(),
+            priority: 0,
             phase: Phase::Pre,
             mode: PolicyMode::Enforce,
             rules: vec![Rule {
@@ -1048,6 +1056,7 @@ fn test_multi_rule_policy_partial_match() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "multi-rule".to_string(),
+        priority: 0,
         phase: Phase::Pre,
         mode: PolicyMode::Enforce,
         rules: vec![
@@ -1187,6 +1196,7 @@ fn test_async_check_routes_to_async_triggered() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "async-content-filter".to_string(),
+        priority: 0,
         mode: PolicyMode::Enforce,
         phase: Phase::Pre,
         retry: None,
@@ -1241,6 +1251,7 @@ fn test_blocking_check_routes_to_actions_not_async() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "blocking-deny".to_string(),
+        priority: 0,
         mode: PolicyMode::Enforce,
         phase: Phase::Pre,
         retry: None,
@@ -1274,6 +1285,7 @@ fn test_mixed_blocking_and_async_rules() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "mixed-policy".to_string(),
+        priority: 0,
         mode: PolicyMode::Enforce,
         phase: Phase::Pre,
         retry: None,
@@ -1321,6 +1333,7 @@ fn test_async_check_shadow_mode_still_logs_not_async_triggered() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "shadow-policy".to_string(),
+        priority: 0,
         mode: PolicyMode::Shadow,
         phase: Phase::Pre,
         retry: None,
@@ -1352,6 +1365,7 @@ fn test_async_check_wrong_phase_skipped() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "pre-only-policy".to_string(),
+        priority: 0,
         mode: PolicyMode::Enforce,
         phase: Phase::Pre,
         retry: None,
@@ -1382,6 +1396,7 @@ fn test_async_check_condition_not_matched_skipped() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "conditional-async".to_string(),
+        priority: 0,
         mode: PolicyMode::Enforce,
         phase: Phase::Pre,
         retry: None,
@@ -1415,6 +1430,7 @@ fn test_async_check_multiple_actions_per_rule() {
     let policy = Policy {
         id: Uuid::new_v4(),
         name: "multi-action-async".to_string(),
+        priority: 0,
         mode: PolicyMode::Enforce,
         phase: Phase::Pre,
         retry: None,
@@ -1457,3 +1473,265 @@ fn test_async_check_true_from_json() {
     let rule: Rule = serde_json::from_str(json).unwrap();
     assert!(rule.async_check, "async_check should be true");
 }
+
+// ── Conflict Resolution ──────────────────────────────────
+
+fn always_policy(name: &str, priority: i32, then: Vec<Action>) -> Policy {
+    Policy {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        priority,
+        phase: Phase::Pre,
+        mode: PolicyMode::Enforce,
+        rules: vec![Rule {
+            when: Condition::Always { always: true },
+            then,
+            async_check: false,
+        }],
+        retry: None,
+    }
+}
+
+fn override_model(model: &str) -> Action {
+    let mut fields = HashMap::new();
+    fields.insert("model".to_string(), json!(model));
+    Action::Override {
+        set_body_fields: fields,
+    }
+}
+
+/// Apply overrides in outcome order, the same way the proxy handler does.
+fn apply_overrides(outcome: &EvalOutcome, body: &mut Value) {
+    for ta in &outcome.actions {
+        if let Action::Override { set_body_fields } = &ta.action {
+            let obj = body.as_object_mut().unwrap();
+            for (k, v) in set_body_fields {
+                obj.insert(k.clone(), v.clone());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_conflicting_overrides_higher_priority_wins() {
+    let method = Method::POST;
+    let uri: Uri = "/v1/chat".parse().unwrap();
+    let headers = HeaderMap::new();
+    let ctx = make_ctx(&method, "/v1/chat", &uri, &headers, None);
+
+    // Attached high-priority first: priority, not slice order, decides
+    let policies = vec![
+        always_policy("force-gpt4o", 10, vec![override_model("gpt-4o")]),
+        always_policy("force-mini", 0, vec![override_model("gpt-4o-mini")]),
+    ];
+
+    let outcome = evaluate_policies(&policies, &ctx, &Phase::Pre);
+    let names: Vec<&str> = outcome
+        .actions
+        .iter()
+        .map(|a| a.policy_name.as_str())
+        .collect();
+    assert_eq!(names, vec!["force-mini", "force-gpt4o"]);
+
+    let mut body = json!({"model": "gpt-3.5-turbo"});
+    apply_overrides(&outcome, &mut body);
+    assert_eq!(body["model"], "gpt-4o");
+}
+
+#[test]
+fn test_conflicting_overrides_equal_priority_last_attached_wins() {
+    let method = Method::POST;
+    let uri: Uri = "/v1/chat".parse().unwrap();
+    let headers = HeaderMap::new();
+    let ctx = make_ctx(&method, "/v1/chat", &uri, &headers, None);
+
+    let policies = vec![
+        always_policy("first", 0, vec![override_model("a")]),
+        always_policy("second", 0, vec![override_model("b")]),
+    ];
+
+    // Repeated evaluation is stable
+    for _ in 0..5 {
+        let outcome = evaluate_policies(&policies, &ctx, &Phase::Pre);
+        let mut body = json!({});
+        apply_overrides(&outcome, &mut body);
+        assert_eq!(body["model"], "b");
+    }
+}
+
+#[test]
+fn test_deny_wins_over_allow() {
+    let method = Method::POST;
+    let uri: Uri = "/v1/chat".parse().unwrap();
+    let headers = HeaderMap::new();
+    let ctx = make_ctx(&method, "/v1/chat", &uri, &headers, None);
+
+    // The allow policy has the higher priority and runs later, but deny is terminal
+    let policies = vec![
+        always_policy(
+            "deny-all",
+            0,
+            vec![Action::Deny {
+                status: 403,
+                message: "blocked".to_string(),
+            }],
+        ),
+        always_policy("allow-all", 100, vec![Action::Allow]),
+    ];
+
+    let outcome = evaluate_policies(&policies, &ctx, &Phase::Pre);
+    assert_eq!(outcome.actions.len(), 2);
+    assert!(matches!(outcome.actions[0].action, Action::Deny { .. }));
+    assert_eq!(outcome.actions[0].policy_name, "deny-all");
+}
+
+#[test]
+fn test_deny_runs_before_rate_limit_side_effects() {
+    let method = Method::POST;
+    let uri: Uri = "/v1/chat".parse().unwrap();
+    let headers = HeaderMap::new();
+    let ctx = make_ctx(&method, "/v1/chat", &uri, &headers, None);
+
+    let policies = vec![
+        always_policy(
+            "limit",
+            0,
+            vec![Action::RateLimit {
+                window: "1m".to_string(),
+                max_requests: 10,
+                key: crate::models::policy::RateLimitKey::PerToken,
+            }],
+        ),
+        always_policy(
+            "deny",
+            5,
+            vec![Action::Deny {
+                status: 403,
+                message: "blocked".to_string(),
+            }],
+        ),
+    ];
+
+    let outcome = evaluate_policies(&policies, &ctx, &Phase::Pre);
+    assert!(matches!(outcome.actions[0].action, Action::Deny { .. }));
+    assert!(matches!(
+        outcome.actions[1].action,
+        Action::RateLimit { .. }
+    ));
+}
+
+#[test]
+fn test_policy_priority_defaults_to_zero() {
+    let policy: Policy = serde_json::from_value(json!({
+        "id": "00000000-0000-0000-0000-000000000001",
+        "name": "p",
+        "rules": []
+    }))
+    .unwrap();
+    assert_eq!(policy.priority, 0);
+}
+
+fn approval_policy(when: Condition) -> Policy {
+    Policy {
+        id: Uuid::new_v4(),
+        name: "risky-ops-hitl".to_string(),
+        priority: 0,
+        phase: Phase::Pre,
+        mode: PolicyMode::Enforce,
+        rules: vec![Rule {
+            when,
+            then: vec![Action::RequireApproval {
+                timeout: "10m".to_string(),
+                fallback: "deny".to_string(),
+                notify: None,
+            }],
+            async_check: false,
+        }],
+        retry: None,
+    }
+}
+
+#[test]
+fn test_require_approval_on_tool_name_glob() {
+    let policy = approval_policy(Condition::Check {
+        field: "request.tool_names".to_string(),
+        op: Operator::Glob,
+        value: json!("delete_*"),
+    });
+    let method = Method::POST;
+    let uri: Uri = "/v1/chat/completions".parse().unwrap();
+    let headers = HeaderMap::new();
+
+    let risky = json!({"tools": [
+        {"type": "function", "function": {"name": "search_docs"}},
+        {"type": "function", "function": {"name": "delete_account"}}
+    ]});
+    let ctx = make_ctx(
+        &method,
+        "/v1/chat/completions",
+        &uri,
+        &headers,
+        Some(&risky),
+    );
+    let outcome = evaluate_policies(std::slice::from_ref(&policy), &ctx, &Phase::Pre);
+    assert_eq!(outcome.actions.len(), 1);
+    assert!(matches!(
+        outcome.actions[0].action,
+        Action::RequireApproval { .. }
+    ));
+
+    let safe = json!({"tools": [{"type": "function", "function": {"name": "search_docs"}}]});
+    let ctx = make_ctx(&method, "/v1/chat/completions", &uri, &headers, Some(&safe));
+    assert!(evaluate_policies(&[policy], &ctx, &Phase::Pre)
+        .actions
+        .is_empty());
+}
+
+#[test]
+fn test_require_approval_on_tool_call_in_conversation() {
+    let policy = approval_policy(Condition::Check {
+        field: "request.tool_calls".to_string(),
+        op: Operator::Glob,
+        value: json!("delete_*"),
+    });
+    let method = Method::POST;
+    let uri: Uri = "/v1/messages".parse().unwrap();
+    let headers = HeaderMap::new();
+    let body = json!({"messages": [
+        {"role": "user", "content": "clean up"},
+        {"role": "assistant", "content": [{"type": "tool_use", "id": "t1", "name": "delete_rows", "input": {}}]}
+    ]});
+    let ctx = make_ctx(&method, "/v1/messages", &uri, &headers, Some(&body));
+    let outcome = evaluate_policies(&[policy], &ctx, &Phase::Pre);
+    assert!(matches!(
+        outcome.actions[0].action,
+        Action::RequireApproval { .. }
+    ));
+}
+
+#[test]
+fn test_require_approval_on_estimated_cost() {
+    let policy = approval_policy(Condition::Check {
+        field: "request.estimated_cost_usd".to_string(),
+        op: Operator::Gt,
+        value: json!(1.0),
+    });
+    let method = Method::POST;
+    let uri: Uri = "/v1/chat/completions".parse().unwrap();
+    let headers = HeaderMap::new();
+    let mut ctx = make_ctx(&method, "/v1/chat/completions", &uri, &headers, None);
+
+    ctx.estimated_cost_usd = Some(0.02);
+    assert!(
+        evaluate_policies(std::slice::from_ref(&policy), &ctx, &Phase::Pre)
+            .actions
+            .is_empty()
+    );
+    ctx.estimated_cost_usd = Some(2.5);
+    assert_eq!(
+        evaluate_policies(&[policy], &ctx, &Phase::Pre)
+            .actions
+            .len(),
+        1
+    );
+}
@@ -12,6 +12,9 @@ pub struct RequestContext<'a> {
     pub headers: &'a HeaderMap,
     pub body: Option<&'a Value>,
     pub body_size: usize,
+    /// Pre-flight cost estimate for this request (USD), see
+    /// [`crate::models::cost::estimate_request_tokens`].
+    pub estimated_cost_usd: Option<f64>,
 
     // ── Identity ──
     pub agent_name: Option<&'a str>,
@@ -33,6 +36,8 @@ pub struct RequestContext<'a> {
 ///
 /// Supported prefixes:
 /// - `request.method`, `request.path`, `request.body_size`
+/// - `request.tool_names` (declared tools), `request.tool_calls` (tool calls in the conversation)
+/// - `request.estimated_cost_usd`
 /// - `request.body.<json_path>` (dot-notation into JSON body)
 /// - `request.headers.<header_name>`
 /// - `request.query.<param_name>`
@@ -62,6 +67,20 @@ fn resolve_request(path: &str, ctx: &RequestContext<'_>) -> Option<Value> {
         "method" => return Some(Value::String(ctx.method.to_string())),
         "path" => return Some(Value::String(ctx.path.to_string())),
         "body_size" => return Some(Value::Number(ctx.body_size.into())),
+        "tool_names" => {
+            let names = super::engine::extract_tool_names(ctx.body);
+            return Some(Value::Array(names.into_iter().map(Value::String).collect()));
+        }
+        "tool_calls" => {
+            let names = extract_tool_call_names(ctx.body);
+            return Some(Value::Array(names.into_iter().map(Value::String).collect()));
+        }
+        "estimated_cost_usd" => {
+            return ctx
+                .estimated_cost_usd
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number);
+        }
         _ => {}
     }
 
@@ -95,6 +114,39 @@ fn resolve_request(path: &str, ctx: &RequestContext<'_>) -> Option<Value> {
     None
 }
 
+/// Names of tool calls already present in the conversation: OpenAI
+/// `messages[].tool_calls[].function.name` and Anthropic `tool_use` content blocks.
+fn extract_tool_call_names(body: Option<&Value>) -> Vec<String> {
+    let Some(Value::Array(messages)) = body.and_then(|b| b.get("messages")) else {
+        return vec![];
+    };
+    let mut names: Vec<String> = Vec::new();
+    let mut push = |name: &str| {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    };
+    for msg in messages {
+        if let Some(Value::Array(calls)) = msg.get("tool_calls") {
+            for call in calls {
+                if let Some(name) = call.pointer("/function/name").and_then(|v| v.as_str()) {
+                    push(name);
+                }
+            }
+        }
+        if let Some(Value::Array(blocks)) = msg.get("content") {
+            for block in blocks {
+                if block.get("type").and_then(|t| t.as_str()) == Some("tool_use") {
+                    if let Some(name) = block.get("name").and_then(|v| v.as_str()) {
+                        push(name);
+                    }
+                }
+            }
+        }
+    }
+    names
+}
+
 fn resolve_response(path: &str, ctx: &RequestContext<'_>) -> Option<Value> {
     if path == "status" {
         return ctx.response_status.map(|s| Value::Number(s.into()));
@@ -268,6 +320,7 @@ mod tests {
             headers,
             body,
             body_size: body.map(|b| b.to_string().len()).unwrap_or(0),
+            estimated_cost_usd: None,
             agent_name: Some("test-agent"),
             token_id: "tok_abc123",
             token_name: "My Token",
@@ -629,6 +682,46 @@ mod tests {
         assert_eq!(resolve_field("usage.unknown_counter", &ctx), None);
     }
 
+    // ── resolve_field: request tool/cost signals ─────────────
+
+    #[test]
+    fn test_resolve_tool_names_and_calls() {
+        let method = Method::POST;
+        let uri: Uri = "/v1/chat/completions".parse().unwrap();
+        let headers = HeaderMap::new();
+        let body = json!({
+            "tools": [{"type": "function", "function": {"name": "delete_user"}}],
+            "messages": [
+                {"role": "assistant", "tool_calls": [{"function": {"name": "list_users"}}]},
+                {"role": "assistant", "content": [{"type": "tool_use", "name": "drop_table"}]}
+            ]
+        });
+        let ctx = make_ctx(&method, "/v1/chat/completions", &uri, &headers, Some(&body));
+
+        assert_eq!(
+            resolve_field("request.tool_names", &ctx),
+            Some(json!(["delete_user"]))
+        );
+        assert_eq!(
+            resolve_field("request.tool_calls", &ctx),
+            Some(json!(["list_users", "drop_table"]))
+        );
+    }
+
+    #[test]
+    fn test_resolve_estimated_cost() {
+        let method = Method::POST;
+        let uri: Uri = "/v1/chat/completions".parse().unwrap();
+        let headers = HeaderMap::new();
+        let mut ctx = make_ctx(&method, "/v1/chat/completions", &uri, &headers, None);
+        assert_eq!(resolve_field("request.estimated_cost_usd", &ctx), None);
+        ctx.estimated_cost_usd = Some(0.25);
+        assert_eq!(
+            resolve_field("request.estimated_cost_usd", &ctx),
+            Some(json!(0.25))
+        );
+    }
+
     // ── resolve_field: response.* ────────────────────────────
 
     #[test]
@@ -0,0 +1,317 @@
+//! Content-category tagging — observe-only request classification.
+//!
+//! Backed by the `content_category_rules` system setting (`PUT /settings`),
+//! a JSON object mapping a category name to the patterns that put a request
+//! in it:
+//!
+//! ```json
+//! {
+//!   "code": ["builtin:source_code"],
+//!   "pii": ["builtin:ssn", "builtin:email", "builtin:credit_card"],
+//!   "medical": ["builtin:medical", "(?i)\\bradiology\\b"]
+//! }
+//! ```
+//!
+//! `builtin:<name>` refers to one of the guardrail pattern sets (the
+//! `ContentFilter` categories plus `source_code` and `medical`) or a built-in
+//! redaction pattern (`ssn`, `email`, ...); anything else is a regex.
+//! Matching categories are recorded in the audit entry's `custom_properties`
+//! under `content_categories`. Nothing is blocked. With no rules configured
+//! (the default) the classifier doesn't run.
+//!
+//! Reloaded after every settings update and every 60s by a background job
+//! in `main.rs`, like the other settings-backed tables.
+
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use regex::RegexSet;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use super::extract_text_content;
+use super::patterns::*;
+use crate::middleware::redact;
+use crate::store::postgres::PgStore;
+
+/// `system_settings` key holding the category rules.
+pub const SETTING_KEY: &str = "content_category_rules";
+
+/// `custom_properties` key the matched categories are written to.
+pub const PROPERTY_KEY: &str = "content_categories";
+
+/// Upper bound on categories and on patterns per category.
+pub const MAX_CATEGORIES: usize = 100;
+pub const MAX_PATTERNS_PER_CATEGORY: usize = 100;
+
+/// Only the start of very large prompts is scanned, keeping tagging cheap.
+const MAX_SCAN_BYTES: usize = 256 * 1024;
+
+/// Compiled size limit, as for policy-authored regexes.
+const REGEX_SIZE_LIMIT: usize = 1_000_000;
+
+/// Guardrail pattern sets addressable as `builtin:<name>`.
+fn builtin_set(name: &str) -> Option<&'static Lazy<RegexSet>> {
+    Some(match name {
+        "jailbreak" => &JAILBREAK_SET,
+        "harmful" => &HARMFUL_SET,
+        "code_injection" => &CODE_INJECTION_SET,
+        "profanity" => &PROFANITY_SET,
+        "bias" => &BIAS_SET,
+        "sensitive_topics" => &SENSITIVE_TOPIC_SET,
+        "gibberish" => &GIBBERISH_SET,
+        "contact_info" => &CONTACT_INFO_SET,
+        "ip_leakage" => &IP_LEAKAGE_SET,
+        "source_code" => &SOURCE_CODE_SET,
+        "medical" => &MEDICAL_SET,
+        _ => return None,
+    })
+}
+
+struct CategoryRule {
+    category: String,
+    builtins: Vec<&'static Lazy<RegexSet>>,
+    /// Regexes and built-in redaction patterns, compiled together.
+    custom: Option<RegexSet>,
+}
+
+impl CategoryRule {
+    fn is_match(&self, text: &str) -> bool {
+        self.builtins.iter().any(|set| set.is_match(text))
+            || self.custom.as_ref().is_some_and(|set| set.is_match(text))
+    }
+}
+
+/// A compiled set of category rules.
+#[derive(Default)]
+pub struct CategoryRules {
+    rules: Vec<CategoryRule>,
+}
+
+impl CategoryRules {
+    /// Validate and compile a `content_category_rules` setting value.
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let obj = value
+            .as_object()
+            .ok_or("must be an object of category -> list of patterns")?;
+        if obj.len() > MAX_CATEGORIES {
+            return Err(format!("too many categories (max {})", MAX_CATEGORIES));
+        }
+        let mut rules = Vec::with_capacity(obj.len());
+        for (category, patterns) in obj {
+            if category.trim().is_empty() {
+                return Err("category names must be non-empty".into());
+            }
+            let patterns = patterns
+                .as_array()
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| format!("'{}' must be a non-empty list of patterns", category))?;
+            if patterns.len() > MAX_PATTERNS_PER_CATEGORY {
+                return Err(format!(
+                    "'{}' has too many patterns (max {})",
+                    category, MAX_PATTERNS_PER_CATEGORY
+                ));
+            }
+
+            let mut builtins = Vec::new();
+            let mut custom: Vec<String> = Vec::new();
+            for pattern in patterns {
+                let pattern = pattern
+                    .as_str()
+                    .ok_or_else(|| format!("patterns for '{}' must be strings", category))?;
+                match pattern.strip_prefix("builtin:") {
+                    Some(name) => {
+                        if let Some(set) = builtin_set(name) {
+                            builtins.push(set);
+                        } else if redact::is_builtin_pattern(name) {
+                            let compiled = redact::compile_pii_patterns(&[name.to_string()]);
+                            custom.extend(compiled.iter().map(|p| p.regex.as_str().to_string()));
+                        } else {
+                            return Err(format!("unknown built-in pattern '{}'", name));
+                        }
+                    }
+                    None => custom.push(pattern.to_string()),
+                }
+            }
+            let custom = if custom.is_empty() {
+                None
+            } else {
+                Some(
+                    regex::RegexSetBuilder::new(&custom)
+                        .size_limit(REGEX_SIZE_LIMIT)
+                        .build()
+                        .map_err(|e| format!("invalid pattern for '{}': {}", category, e))?,
+                )
+            };
+            rules.push(CategoryRule {
+                category: category.clone(),
+                builtins,
+                custom,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Categories whose patterns match the request's text content, sorted.
+    pub fn classify(&self, body: &Value) -> Vec<String> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        let text = extract_text_content(body);
+        if text.is_empty() {
+            return Vec::new();
+        }
+        let mut end = text.len().min(MAX_SCAN_BYTES);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let text = &text[..end];
+        let mut categories: Vec<String> = self
+            .rules
+            .iter()
+            .filter(|r| r.is_match(text))
+            .map(|r| r.category.clone())
+            .collect();
+        categories.sort();
+        categories
+    }
+}
+
+/// Record matched categories in a request's `custom_properties`. A
+/// client-sent `content_categories` is replaced (or dropped when nothing
+/// matched), so the key only ever holds the classifier's result. Properties
+/// that aren't a JSON object are left untouched.
+pub fn tag_properties(properties: Option<Value>, categories: Vec<String>) -> Option<Value> {
+    match properties {
+        None if categories.is_empty() => None,
+        None => Some(serde_json::json!({ PROPERTY_KEY: categories })),
+        Some(Value::Object(mut map)) => {
+            if categories.is_empty() {
+                map.remove(PROPERTY_KEY);
+            } else {
+                map.insert(PROPERTY_KEY.to_string(), Value::from(categories));
+            }
+            Some(Value::Object(map))
+        }
+        Some(other) => Some(other),
+    }
+}
+
+/// Shared, cheaply-cloneable category rules.
+#[derive(Clone, Default)]
+pub struct ContentCategoryTable(Arc<RwLock<Arc<CategoryRules>>>);
+
+impl ContentCategoryTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the whole rule set.
+    pub async fn replace(&self, rules: CategoryRules) {
+        *self.0.write().await = Arc::new(rules);
+    }
+
+    /// Reload the rules from `system_settings`. An absent setting clears
+    /// them; an unreadable one keeps the previous rules.
+    pub async fn reload(&self, db: &PgStore) {
+        match db.get_system_setting::<Value>(SETTING_KEY).await {
+            Ok(value) => match value.as_ref().map(CategoryRules::parse).transpose() {
+                Ok(rules) => self.replace(rules.unwrap_or_default()).await,
+                Err(e) => tracing::error!("content_category: invalid {}: {}", SETTING_KEY, e),
+            },
+            Err(e) => tracing::error!("content_category: reload failed: {}", e),
+        }
+    }
+
+    /// The current rules; cheap to hold across a request.
+    pub async fn rules(&self) -> Arc<CategoryRules> {
+        self.0.read().await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chat(text: &str) -> Value {
+        json!({"model": "gpt-4o", "messages": [{"role": "user", "content": text}]})
+    }
+
+    #[test]
+    fn test_content_categories_classify() {
+        let rules = CategoryRules::parse(&json!({
+            "code": ["builtin:source_code"],
+            "pii": ["builtin:ssn", "builtin:email"],
+            "medical": ["builtin:medical"],
+            "billing": ["(?i)\\binvoice\\b"],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            rules.classify(&chat("fix this:\n```\nfn main() {}\n```")),
+            vec!["code"]
+        );
+        assert_eq!(
+            rules.classify(&chat(
+                "Patient diagnosed with hypertension, SSN 123-45-6789"
+            )),
+            vec!["medical", "pii"]
+        );
+        assert_eq!(
+            rules.classify(&chat("Where is my INVOICE?")),
+            vec!["billing"]
+        );
+        assert!(rules
+            .classify(&chat("What's the capital of France?"))
+            .is_empty());
+        assert!(CategoryRules::default().classify(&chat("```")).is_empty());
+    }
+
+    #[test]
+    fn test_content_categories_parse_errors() {
+        for bad in [
+            json!(["code"]),
+            json!({"code": []}),
+            json!({"code": "builtin:source_code"}),
+            json!({"code": ["builtin:nope"]}),
+            json!({"code": ["("]}),
+            json!({" ": ["x"]}),
+        ] {
+            assert!(CategoryRules::parse(&bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_content_categories_tag_properties() {
+        let cats = vec!["code".to_string()];
+        assert_eq!(
+            tag_properties(None, cats.clone()),
+            Some(json!({"content_categories": ["code"]}))
+        );
+        assert_eq!(
+            tag_properties(Some(json!({"team": "ml"})), cats.clone()),
+            Some(json!({"team": "ml", "content_categories": ["code"]}))
+        );
+        assert_eq!(
+            tag_properties(Some(json!("x")), cats.clone()),
+            Some(json!("x"))
+        );
+        assert_eq!(tag_properties(None, vec![]), None);
+
+        // A client can't supply its own tags.
+        let spoofed = json!({"team": "ml", "content_categories": ["none"]});
+        assert_eq!(
+            tag_properties(Some(spoofed.clone()), cats),
+            Some(json!({"team": "ml", "content_categories": ["code"]}))
+        );
+        assert_eq!(
+            tag_properties(Some(spoofed), vec![]),
+            Some(json!({"team": "ml"}))
+        );
+    }
+}
@@ -0,0 +1,381 @@
+//! Best-effort response coercion — implements `Action::CoerceResponseSchema`.
+//!
+//! Walks the response alongside the schema, fixing what can be fixed without
+//! guessing: lossless type conversions, `default` filling, empty values for
+//! missing required properties, and dropping properties the schema forbids.
+//! Keywords it doesn't understand are left alone; the result is then checked
+//! with the full validator, so a response either comes out valid or the
+//! coercion fails.
+
+use serde_json::{Map, Value};
+
+use super::schema::{compiled_schema, extract_json_from_markdown, validate_schema};
+
+/// How deep nested schemas are followed.
+const MAX_DEPTH: usize = 32;
+
+/// A successfully coerced response.
+#[derive(Debug)]
+pub struct CoercionResult {
+    /// The full response body, with the coerced value written back.
+    pub body: Value,
+    /// One entry per change, e.g. `/score: string -> number`. Empty when the
+    /// response already conformed.
+    pub changes: Vec<String>,
+}
+
+/// Coerce an LLM response toward `schema`.
+///
+/// Like `validate_schema`, works on the JSON in `choices[0].message.content`
+/// (bare or in a ```json fence) when present, otherwise on the whole body.
+/// Coerced message content is written back as compact JSON. Returns the
+/// remaining validation errors when the response can't be made to conform.
+pub fn coerce_to_schema(body: &Value, schema: &Value) -> Result<CoercionResult, Vec<String>> {
+    // Inline local `$ref`s so the walk below follows them.
+    let compiled =
+        compiled_schema(schema).map_err(|e| vec![format!("Invalid JSON Schema: {}", e)])?;
+    let resolved = &compiled.resolved;
+
+    let content = body
+        .pointer("/choices/0/message/content")
+        .and_then(|v| v.as_str());
+    let mut candidate = match content {
+        Some(raw) => serde_json::from_str::<Value>(raw)
+            .ok()
+            .or_else(|| extract_json_from_markdown(raw).and_then(|j| serde_json::from_str(&j).ok()))
+            .unwrap_or_else(|| Value::String(raw.to_owned())),
+        None => body.clone(),
+    };
+
+    let mut changes = Vec::new();
+    coerce_value(&mut candidate, resolved, "", 0, &mut changes);
+
+    let check = validate_schema(&candidate, schema);
+    if !check.valid {
+        return Err(check.errors);
+    }
+
+    let mut out = body.clone();
+    if changes.is_empty() {
+        return Ok(CoercionResult { body: out, changes });
+    }
+    match content {
+        Some(_) => {
+            let serialized = match candidate {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            if let Some(slot) = out.pointer_mut("/choices/0/message/content") {
+                *slot = Value::String(serialized);
+            }
+        }
+        None => out = candidate,
+    }
+    Ok(CoercionResult { body: out, changes })
+}
+
+fn coerce_value(
+    value: &mut Value,
+    schema: &Value,
+    path: &str,
+    depth: usize,
+    changes: &mut Vec<String>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let at = if path.is_empty() { "/" } else { path };
+
+    let types = schema_types(schema);
+    if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+        if let Some((converted, to)) = types
+            .iter()
+            .find_map(|t| convert(value, t).map(|v| (v, *t)))
+        {
+            changes.push(format!("{}: {} -> {}", at, type_name(value), to));
+            *value = converted;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            if let Some(s) = value.as_str() {
+                let folded = allowed
+                    .iter()
+                    .find(|a| a.as_str().is_some_and(|a| a.eq_ignore_ascii_case(s.trim())));
+                if let Some(m) = folded {
+                    changes.push(format!("{}: {:?} -> {}", at, s, m));
+                    *value = m.clone();
+                }
+            }
+        }
+    }
+
+    match value {
+        Value::Object(obj) => coerce_object(obj, schema, path, depth, changes),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items").filter(|s| s.is_object()) {
+                for (i, item) in items.iter_mut().enumerate() {
+                    coerce_value(
+                        item,
+                        item_schema,
+                        &format!("{}/{}", path, i),
+                        depth + 1,
+                        changes,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn coerce_object(
+    obj: &mut Map<String, Value>,
+    schema: &Map<String, Value>,
+    path: &str,
+    depth: usize,
+    changes: &mut Vec<String>,
+) {
+    let empty = Map::new();
+    let props = schema
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .unwrap_or(&empty);
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|k| k.as_str()).collect())
+        .unwrap_or_default();
+
+    for (key, prop_schema) in props {
+        let child = format!("{}/{}", path, key);
+        match obj.get_mut(key) {
+            Some(v) => coerce_value(v, prop_schema, &child, depth + 1, changes),
+            None => {
+                if let Some(default) = prop_schema.get("default") {
+                    changes.push(format!("{}: filled with default", child));
+                    obj.insert(key.clone(), default.clone());
+                } else if required.contains(&key.as_str()) {
+                    if let Some(filler) = empty_value(prop_schema, depth + 1) {
+                        changes.push(format!("{}: filled with {}", child, filler));
+                        obj.insert(key.clone(), filler);
+                    }
+                }
+            }
+        }
+    }
+
+    if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+        let extra: Vec<String> = obj
+            .keys()
+            .filter(|k| !props.contains_key(*k))
+            .cloned()
+            .collect();
+        for key in extra {
+            changes.push(format!("{}/{}: removed", path, key));
+            obj.remove(&key);
+        }
+    }
+}
+
+fn schema_types(schema: &Map<String, Value>) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Lossless conversion of `value` to `ty`, if there is one.
+fn convert(value: &Value, ty: &str) -> Option<Value> {
+    match (ty, value) {
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        ("number", Value::String(s)) => {
+            let f = s.trim().parse::<f64>().ok().filter(|f| f.is_finite())?;
+            serde_json::Number::from_f64(f).map(Value::Number)
+        }
+        ("integer", Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+        ("boolean", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("array", v) if !v.is_null() => Some(Value::Array(vec![v.clone()])),
+        _ => None,
+    }
+}
+
+/// Placeholder for a missing required property: its `default`, else an empty
+/// value of its first type (objects get their own required fields filled).
+fn empty_value(schema: &Value, depth: usize) -> Option<Value> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    if let Some(default) = schema.get("default") {
+        return Some(default.clone());
+    }
+    let obj = schema.as_object()?;
+    let types = schema_types(obj);
+    if types.contains(&"null") {
+        return Some(Value::Null);
+    }
+    match *types.first()? {
+        "string" => Some(Value::String(String::new())),
+        "number" | "integer" => Some(Value::from(0)),
+        "boolean" => Some(Value::Bool(false)),
+        "array" => Some(Value::Array(Vec::new())),
+        "object" => {
+            let mut filled = Map::new();
+            let mut ignored = Vec::new();
+            coerce_object(&mut filled, obj, "", depth, &mut ignored);
+            Some(Value::Object(filled))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["answer", "score", "tags", "meta"],
+            "additionalProperties": false,
+            "properties": {
+                "answer": {"type": "string"},
+                "score": {"type": "number"},
+                "count": {"type": "integer"},
+                "ok": {"type": "boolean"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "level": {"type": "string", "enum": ["low", "high"], "default": "low"},
+                "meta": {
+                    "type": "object",
+                    "required": ["source"],
+                    "properties": {"source": {"type": "string"}}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_coerce_types_defaults_and_extras() {
+        let body = json!({
+            "answer": 42,
+            "score": "0.9",
+            "count": "3",
+            "ok": "TRUE",
+            "tags": "solo",
+            "level": "High",
+            "debug": true
+        });
+        let result = coerce_to_schema(&body, &schema()).unwrap();
+        assert_eq!(
+            result.body,
+            json!({
+                "answer": "42",
+                "score": 0.9,
+                "count": 3,
+                "ok": true,
+                "tags": ["solo"],
+                "level": "high",
+                "meta": {"source": ""}
+            })
+        );
+        assert!(result
+            .changes
+            .contains(&"/score: string -> number".to_string()));
+        assert!(result.changes.contains(&"/debug: removed".to_string()));
+        assert!(result
+            .changes
+            .iter()
+            .any(|c| c.starts_with("/meta: filled")));
+
+        // Conforming responses come back untouched.
+        let clean = result.body.clone();
+        let again = coerce_to_schema(&clean, &schema()).unwrap();
+        assert!(again.changes.is_empty());
+        assert_eq!(again.body, clean);
+    }
+
+    #[test]
+    fn test_coerce_chat_content_and_failures() {
+        let body = json!({
+            "choices": [{"message": {
+                "role": "assistant",
+                "content": "```json\n{\"answer\": \"yes\", \"score\": \"1\", \"tags\": [], \"meta\": {\"source\": \"kb\"}}\n```"
+            }}]
+        });
+        let result = coerce_to_schema(&body, &schema()).unwrap();
+        let content = result.body["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap();
+        let parsed: Value = serde_json::from_str(content).unwrap();
+        assert_eq!(parsed["score"], json!(1.0));
+        assert_eq!(parsed["level"], json!("low"));
+        assert_eq!(result.body["choices"][0]["message"]["role"], "assistant");
+
+        // Not convertible, or not JSON at all: coercion fails.
+        assert!(coerce_to_schema(
+            &json!({"answer": "a", "score": "high", "tags": [], "meta": {"source": "x"}}),
+            &schema()
+        )
+        .is_err());
+        let prose = json!({"choices": [{"message": {"content": "I can't answer that."}}]});
+        assert!(coerce_to_schema(&prose, &schema()).is_err());
+        assert!(coerce_to_schema(&json!({}), &json!({"type": 5})).is_err());
+    }
+
+    #[test]
+    fn test_coerce_follows_local_refs() {
+        let schema = json!({
+            "type": "object",
+            "required": ["billing"],
+            "properties": {"billing": {"$ref": "#/$defs/Address"}},
+            "$defs": {"Address": {
+                "type": "object",
+                "required": ["zip"],
+                "properties": {"zip": {"type": "string"}}
+            }}
+        });
+        let result = coerce_to_schema(&json!({"billing": {"zip": 94107}}), &schema).unwrap();
+        assert_eq!(result.body, json!({"billing": {"zip": "94107"}}));
+
+        let external = json!({"$ref": "https://example.com/address.json"});
+        let errors = coerce_to_schema(&json!({}), &external).unwrap_err();
+        assert!(errors[0].contains("external $ref not supported"));
+    }
+}
@@ -0,0 +1,137 @@
+//! Keyword deny-list — implements `Action::KeywordBlock`.
+//!
+//! Plain term matching over the request's text content, with no scoring:
+//! a request is blocked as soon as any term is found. `substring` mode
+//! matches anywhere ("acme" blocks "acmeware"); `word` mode only where the
+//! term is not joined to a surrounding letter, digit or underscore.
+
+use serde_json::Value;
+
+use super::extract_text_content;
+use crate::models::policy::{Action, KeywordMatchMode};
+
+/// First configured term found in the request, in the order the terms were
+/// listed. `None` if the request is clean or the action isn't `KeywordBlock`.
+pub fn find_blocked_term(body: &Value, action: &Action) -> Option<String> {
+    let Action::KeywordBlock {
+        terms,
+        case_insensitive,
+        match_mode,
+        ..
+    } = action
+    else {
+        return None;
+    };
+    let text = extract_text_content(body);
+    if text.is_empty() {
+        return None;
+    }
+    let text = if *case_insensitive {
+        text.to_lowercase()
+    } else {
+        text
+    };
+    terms
+        .iter()
+        .find(|term| {
+            let trimmed = term.trim();
+            if trimmed.is_empty() {
+                return false;
+            }
+            let needle = if *case_insensitive {
+                trimmed.to_lowercase()
+            } else {
+                trimmed.to_string()
+            };
+            contains_term(&text, &needle, *match_mode)
+        })
+        .cloned()
+}
+
+fn contains_term(text: &str, term: &str, mode: KeywordMatchMode) -> bool {
+    match mode {
+        KeywordMatchMode::Substring => text.contains(term),
+        KeywordMatchMode::Word => text.match_indices(term).any(|(start, _)| {
+            let end = start + term.len();
+            let before = text[..start].chars().next_back();
+            let after = text[end..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        }),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn action(terms: &[&str], case_insensitive: bool, match_mode: KeywordMatchMode) -> Action {
+        Action::KeywordBlock {
+            terms: terms.iter().map(|t| t.to_string()).collect(),
+            case_insensitive,
+            match_mode,
+            message: None,
+        }
+    }
+
+    fn chat(text: &str) -> Value {
+        json!({"model": "gpt-4o", "messages": [{"role": "user", "content": text}]})
+    }
+
+    #[test]
+    fn test_keyword_block_substring_vs_word() {
+        let substring = action(&["acme"], true, KeywordMatchMode::Substring);
+        let word = action(&["acme"], true, KeywordMatchMode::Word);
+
+        for text in [
+            "Compare us to Acme.",
+            "acme",
+            "(ACME)",
+            "acme_corp? no: acme-corp",
+        ] {
+            assert_eq!(
+                find_blocked_term(&chat(text), &substring),
+                Some("acme".into())
+            );
+            assert_eq!(
+                find_blocked_term(&chat(text), &word),
+                Some("acme".into()),
+                "{text}"
+            );
+        }
+        for text in ["acmeware is great", "the acme_corp account", "acme2"] {
+            assert_eq!(
+                find_blocked_term(&chat(text), &substring),
+                Some("acme".into())
+            );
+            assert_eq!(find_blocked_term(&chat(text), &word), None, "{text}");
+        }
+        // A later occurrence at a boundary still counts.
+        assert!(find_blocked_term(&chat("acmeware, then acme"), &word).is_some());
+        // Multi-word terms need a boundary at both ends.
+        let phrase = action(&["project falcon"], true, KeywordMatchMode::Word);
+        assert!(find_blocked_term(&chat("About Project Falcon."), &phrase).is_some());
+        assert!(find_blocked_term(&chat("project falconry"), &phrase).is_none());
+    }
+
+    #[test]
+    fn test_keyword_block_case_and_content_scope() {
+        let sensitive = action(&["Falcon"], false, KeywordMatchMode::Substring);
+        assert!(find_blocked_term(&chat("falcon"), &sensitive).is_none());
+        assert!(find_blocked_term(&chat("Falcon"), &sensitive).is_some());
+
+        // Only text content is scanned, not the model name or other fields.
+        let model = action(&["gpt-4o"], true, KeywordMatchMode::Substring);
+        assert!(find_blocked_term(&chat("hello"), &model).is_none());
+        let input = json!({"model": "text-embedding-3-small", "input": "GPT-4o rocks"});
+        assert!(find_blocked_term(&input, &model).is_some());
+
+        // Blank terms never match.
+        let blank = action(&["", "  "], true, KeywordMatchMode::Substring);
+        assert!(find_blocked_term(&chat("anything"), &blank).is_none());
+    }
+}
@@ -0,0 +1,253 @@
+//! Policy pattern linting.
+//!
+//! Walks a policy's rules, compiles every policy-authored regex exactly the way
+//! the runtime does (`regex` crate, 1MB compiled size limit) and runs it against
+//! a fixed set of adversarial inputs under a time budget.
+//!
+//! The `regex` crate uses finite automata and guarantees linear-time matching,
+//! so classic catastrophic backtracking cannot occur at request time. Patterns
+//! that rely on backtracking-only features (look-around, backreferences) fail
+//! to compile and are silently skipped by the engine — linting surfaces them
+//! before the policy is saved instead.
+
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::middleware::redact::{is_builtin_pattern, CUSTOM_REGEX_SIZE_LIMIT};
+use crate::models::policy::{Action, Condition, Operator, Rule, TransformOp};
+
+/// Compiled size limit — must match the runtime compile sites.
+const REGEX_SIZE_LIMIT: usize = CUSTOM_REGEX_SIZE_LIMIT;
+
+/// Length of each generated adversarial input.
+const ADVERSARIAL_LEN: usize = 20_000;
+
+/// Default per-pattern evaluation budget.
+pub const DEFAULT_LINT_BUDGET: Duration = Duration::from_millis(50);
+
+/// Inputs that trigger pathological behaviour in backtracking engines
+/// (nested quantifiers, overlapping alternations, failing suffixes).
+static ADVERSARIAL_INPUTS: Lazy<Vec<String>> = Lazy::new(|| {
+    vec![
+        "a".repeat(ADVERSARIAL_LEN),
+        format!("{}!", "a".repeat(ADVERSARIAL_LEN)),
+        format!("{}!", "ab".repeat(ADVERSARIAL_LEN / 2)),
+        format!("{}x", " ".repeat(ADVERSARIAL_LEN)),
+        format!("{}a", "0".repeat(ADVERSARIAL_LEN)),
+        format!("{}@", "a.".repeat(ADVERSARIAL_LEN / 2)),
+        format!(
+            "{}{}",
+            "(".repeat(ADVERSARIAL_LEN / 2),
+            ")".repeat(ADVERSARIAL_LEN / 2)
+        ),
+    ]
+});
+
+// ── Public Types ──────────────────────────────────────────────
+
+/// Outcome of linting a single pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintStatus {
+    /// Compiles and evaluates within budget.
+    Ok,
+    /// Not a valid regular expression.
+    Invalid,
+    /// Uses look-around or backreferences, which require a backtracking engine.
+    UnsupportedFeature,
+    /// Exceeds the compiled size limit.
+    TooComplex,
+    /// Evaluation against adversarial inputs exceeded the time budget.
+    Slow,
+}
+
+/// Lint result for one pattern found in a policy.
+#[derive(Debug, Clone, Serialize)]
+pub struct PatternLint {
+    /// Where the pattern lives, e.g. `rules[0].then[1].custom_patterns[2]`.
+    pub location: String,
+    pub pattern: String,
+    pub status: LintStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Total time spent matching the adversarial inputs (0 if not compiled).
+    pub elapsed_us: u64,
+}
+
+impl PatternLint {
+    pub fn is_ok(&self) -> bool {
+        self.status == LintStatus::Ok
+    }
+}
+
+// ── Linting ──────────────────────────────────────────────────
+
+/// Lint every regex pattern referenced by `rules`.
+pub fn lint_rules(rules: &[Rule], budget: Duration) -> Vec<PatternLint> {
+    collect_patterns(rules)
+        .into_iter()
+        .map(|(location, pattern)| lint_pattern(location, pattern, budget))
+        .collect()
+}
+
+/// Compile and stress a single pattern.
+pub fn lint_pattern(location: String, pattern: String, budget: Duration) -> PatternLint {
+    let re = match regex::RegexBuilder::new(&pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+    {
+        Ok(re) => re,
+        Err(e) => {
+            let status = classify_compile_error(&e);
+            return PatternLint {
+                location,
+                pattern,
+                status,
+                error: Some(e.to_string()),
+                elapsed_us: 0,
+            };
+        }
+    };
+
+    let start = Instant::now();
+    for input in ADVERSARIAL_INPUTS.iter() {
+        // find_iter forces a full scan, unlike is_match which may stop early.
+        let _ = re.find_iter(input).count();
+        if start.elapsed() > budget {
+            break;
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let (status, error) = if elapsed > budget {
+        (
+            LintStatus::Slow,
+            Some(format!(
+                "evaluation took {}ms, budget is {}ms — potential ReDoS risk",
+                elapsed.as_millis(),
+                budget.as_millis()
+            )),
+        )
+    } else {
+        (LintStatus::Ok, None)
+    };
+
+    PatternLint {
+        location,
+        pattern,
+        status,
+        error,
+        elapsed_us: elapsed.as_micros() as u64,
+    }
+}
+
+fn classify_compile_error(e: &regex::Error) -> LintStatus {
+    match e {
+        regex::Error::CompiledTooBig(_) => LintStatus::TooComplex,
+        regex::Error::Syntax(msg) => {
+            let msg = msg.to_lowercase();
+            if msg.contains("look-around")
+                || msg.contains("look-ahead")
+                || msg.contains("look-behind")
+                || msg.contains("backreference")
+            {
+                LintStatus::UnsupportedFeature
+            } else {
+                LintStatus::Invalid
+            }
+        }
+        _ => LintStatus::Invalid,
+    }
+}
+
+// ── Pattern Collection ───────────────────────────────────────
+
+/// Gather `(location, pattern)` pairs for every regex the engine would compile.
+fn collect_patterns(rules: &[Rule]) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for (ri, rule) in rules.iter().enumerate() {
+        collect_condition(&rule.when, &format!("rules[{}].when", ri), &mut out);
+        for (ai, action) in rule.then.iter().enumerate() {
+            let loc = format!("rules[{}].then[{}]", ri, ai);
+            collect_action(action, &loc, &mut out);
+        }
+    }
+    out
+}
+
+fn collect_condition(cond: &Condition, loc: &str, out: &mut Vec<(String, String)>) {
+    match cond {
+        Condition::All { all } => {
+            for (i, c) in all.iter().enumerate() {
+                collect_condition(c, &format!("{}.all[{}]", loc, i), out);
+            }
+        }
+        Condition::Any { any } => {
+            for (i, c) in any.iter().enumerate() {
+                collect_condition(c, &format!("{}.any[{}]", loc, i), out);
+            }
+        }
+        Condition::Not { not } => collect_condition(not, &format!("{}.not", loc), out),
+        Condition::Check {
+            op: Operator::Regex,
+            value,
+            ..
+        } => {
+            if let Some(p) = value.as_str() {
+                out.push((loc.to_string(), p.to_string()));
+            }
+        }
+        Condition::Check { .. } | Condition::Always { .. } => {}
+    }
+}
+
+fn collect_action(action: &Action, loc: &str, out: &mut Vec<(String, String)>) {
+    match action {
+        Action::ContentFilter {
+            custom_patterns, ..
+        } => {
+            for (i, p) in custom_patterns.iter().enumerate() {
+                out.push((format!("{}.custom_patterns[{}]", loc, i), p.clone()));
+            }
+        }
+        Action::Redact {
+            patterns,
+            custom_regexes,
+            ..
+        } => {
+            for (i, p) in patterns.iter().enumerate() {
+                if !is_builtin_pattern(p) {
+                    out.push((format!("{}.patterns[{}]", loc, i), p.clone()));
+                }
+            }
+            for (i, c) in custom_regexes.iter().enumerate() {
+                out.push((
+                    format!("{}.custom_regexes[{}].pattern", loc, i),
+                    c.pattern.clone(),
+                ));
+            }
+        }
+        Action::Transform { operations } => {
+            for (i, op) in operations.iter().enumerate() {
+                if let TransformOp::RegexReplace { pattern, .. } = op {
+                    out.push((
+                        format!("{}.operations[{}].pattern", loc, i),
+                        pattern.clone(),
+                    ));
+                }
+            }
+        }
+        Action::ConditionalRoute { branches, .. } => {
+            for (i, branch) in branches.iter().enumerate() {
+                if branch.condition.op == "regex" {
+                    if let Some(p) = branch.condition.value.as_str() {
+                        out.push((format!("{}.branches[{}].condition", loc, i), p.to_string()));
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
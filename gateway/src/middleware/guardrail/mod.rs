@@ -11,6 +11,10 @@
 //! - **Custom patterns**: policy authors can supply additional regex strings.
 //! - **Risk scoring**: 0.0–1.0 composite score; threshold configurable per policy.
 
+pub mod category;
+mod coerce;
+pub mod keyword;
+mod lint;
 mod patterns;
 mod schema;
 
@@ -22,6 +26,8 @@ use serde_json::Value;
 use self::patterns::*;
 use crate::models::policy::Action;
 
+pub use self::coerce::coerce_to_schema;
+pub use self::lint::{lint_rules, PatternLint, DEFAULT_LINT_BUDGET};
 pub use self::schema::validate_schema;
 
 // ── Public Types ──────────────────────────────────────────────
@@ -232,3 +232,33 @@ static IP_LEAKAGE_PATTERNS: &[&str] = &[
 
 pub(super) static IP_LEAKAGE_SET: Lazy<RegexSet> =
     Lazy::new(|| RegexSet::new(IP_LEAKAGE_PATTERNS).expect("invalid IP leakage regex patterns"));
+
+// ── Content-Category Patterns (tagging only, never block) ────
+
+/// Source code in a prompt: fenced blocks and common declaration syntax.
+static SOURCE_CODE_PATTERNS: &[&str] = &[
+    r"```",
+    r"\b(def|fn|func)\s+\w+\s*\(",
+    r"\bfunction\s+\w*\s*\([^)]*\)\s*\{",
+    r"\b(public|private|protected)\s+(static\s+)?[\w<>\[\]]+\s+\w+\s*\(",
+    r"\bclass\s+\w+\s*(\(|:|\{|extends|implements)",
+    r"#include\s*<\w+(\.h)?>",
+    r"(?m)^\s*(import|from)\s+[\w.]+(\s+import\s+[\w*, ]+)?\s*;?\s*$",
+    r"\b(const|let|var)\s+\w+\s*=\s*[^=]",
+    r"(?i)\bSELECT\s+[\w*, .]+\s+FROM\s+\w+",
+];
+
+pub(super) static SOURCE_CODE_SET: Lazy<RegexSet> =
+    Lazy::new(|| RegexSet::new(SOURCE_CODE_PATTERNS).expect("invalid source code regex patterns"));
+
+/// Medical and health information: diagnoses, treatment, clinical records.
+static MEDICAL_PATTERNS: &[&str] = &[
+    r"(?i)\b(diagnos(is|ed|es)|prognosis|symptoms?|comorbidit(y|ies))\b",
+    r"(?i)\b(prescri(bed|ption)|dosage|\d+\s?mg\b|medication)\b",
+    r"(?i)\b(patient|clinical|medical)\s+(record|history|chart|notes?)\b",
+    r"(?i)\b(icd-?10|cpt\s+code|hipaa|phi)\b",
+    r"(?i)\b(cancer|diabetes|hypertension|hiv|depression|asthma|chemotherapy)\b",
+];
+
+pub(super) static MEDICAL_SET: Lazy<RegexSet> =
+    Lazy::new(|| RegexSet::new(MEDICAL_PATTERNS).expect("invalid medical regex patterns"));
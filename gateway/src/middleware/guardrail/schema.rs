@@ -1,5 +1,10 @@
 // ── JSON Schema Validation ────────────────────────────────────
 
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+
 /// Result of a JSON Schema validation check.
 pub struct SchemaValidationResult {
     pub valid: bool,
@@ -9,6 +14,30 @@ pub struct SchemaValidationResult {
     /// we extracted JSON from a markdown code block).
     #[allow(dead_code)]
     pub validated_value: Option<serde_json::Value>,
+    /// The schema itself is unusable (doesn't compile, or has an external
+    /// or dangling `$ref`). `errors` holds the reason.
+    pub schema_error: bool,
+}
+
+impl SchemaValidationResult {
+    /// Whether a `validate_schema` rule with the given `not` flag is
+    /// violated. A broken schema always violates the rule — in `not` mode it
+    /// would otherwise let every response through.
+    pub fn violated(&self, not: bool) -> bool {
+        if self.schema_error {
+            return true;
+        }
+        self.valid == not
+    }
+}
+
+fn invalid_schema(reason: String) -> SchemaValidationResult {
+    SchemaValidationResult {
+        valid: false,
+        errors: vec![format!("Invalid JSON Schema: {}", reason)],
+        validated_value: None,
+        schema_error: true,
+    }
 }
 
 /// Validate an LLM response body against a JSON Schema.
@@ -23,16 +52,10 @@ pub fn validate_schema(
     response_body: &serde_json::Value,
     schema: &serde_json::Value,
 ) -> SchemaValidationResult {
-    // 1. Compile the schema
-    let compiled = match jsonschema::JSONSchema::compile(schema) {
+    // 1. Inline local `$ref`s and compile the schema (cached per schema)
+    let compiled = match compiled_schema(schema) {
         Ok(c) => c,
-        Err(e) => {
-            return SchemaValidationResult {
-                valid: false,
-                errors: vec![format!("Invalid JSON Schema: {}", e)],
-                validated_value: None,
-            };
-        }
+        Err(e) => return invalid_schema(e),
     };
 
     // 2. Extract the candidate value to validate
@@ -40,7 +63,7 @@ pub fn validate_schema(
     let candidate = extract_content_for_validation(response_body);
 
     // 3. Validate — eagerly collect errors so we don't need to keep `compiled` borrowed
-    let errors: Vec<String> = match compiled.validate(&candidate) {
+    let errors: Vec<String> = match compiled.validator.validate(&candidate) {
         Ok(()) => vec![],
         Err(errs) => errs
             .map(|e| format!("{} (at {})", e, e.instance_path))
@@ -52,9 +75,155 @@ pub fn validate_schema(
         valid,
         errors,
         validated_value: Some(candidate),
+        schema_error: false,
+    }
+}
+
+/// A schema with its local `$ref`s inlined, plus the compiled validator.
+pub(super) struct CompiledSchema {
+    pub(super) resolved: serde_json::Value,
+    validator: jsonschema::JSONSchema,
+}
+
+/// Compiled schemas keyed by their JSON text. Policies are evaluated on every
+/// response, so inlining and compiling once per distinct schema keeps
+/// post-flight cheap. Broken schemas are cached too.
+static COMPILED_SCHEMAS: Lazy<DashMap<String, Result<Arc<CompiledSchema>, String>>> =
+    Lazy::new(DashMap::new);
+
+/// Entries kept before the cache is flushed; schemas come from policies, so
+/// this is only reached if policies churn.
+const MAX_CACHED_SCHEMAS: usize = 512;
+
+/// Inline and compile `schema`, reusing an earlier result for the same schema.
+pub(super) fn compiled_schema(schema: &serde_json::Value) -> Result<Arc<CompiledSchema>, String> {
+    let key = schema.to_string();
+    if let Some(hit) = COMPILED_SCHEMAS.get(&key) {
+        return hit.clone();
+    }
+    let compiled = resolve_local_refs(schema).and_then(|resolved| {
+        let validator = jsonschema::JSONSchema::compile(&resolved).map_err(|e| e.to_string())?;
+        Ok(Arc::new(CompiledSchema {
+            resolved,
+            validator,
+        }))
+    });
+    if COMPILED_SCHEMAS.len() >= MAX_CACHED_SCHEMAS {
+        COMPILED_SCHEMAS.clear();
+    }
+    COMPILED_SCHEMAS.insert(key, compiled.clone());
+    compiled
+}
+
+/// Keywords whose values are instance data, not subschemas: a `$ref` key
+/// inside them is not a reference.
+const DATA_KEYWORDS: [&str; 4] = ["const", "enum", "default", "examples"];
+
+/// How many `$ref`s deep inlining goes before leaving the rest to the
+/// validator.
+const MAX_REF_DEPTH: usize = 32;
+
+/// Upper bound on the size of a schema once its `$ref`s are inlined. Depth
+/// alone doesn't bound it: a def that refs the next one twice doubles at
+/// every level.
+const MAX_RESOLVED_NODES: usize = 10_000;
+
+/// Inline local `$ref` pointers (`#/$defs/...`, `#/definitions/...`, or any
+/// other JSON pointer into the same document) so every consumer — the
+/// validator and the coercion walker alike — sees the referenced subschema.
+///
+/// Recursive references are left in place for the validator to follow.
+/// External references (anything not starting with `#`) are rejected: the
+/// gateway never fetches schemas, and letting them through would make the
+/// rule fail open.
+pub(super) fn resolve_local_refs(schema: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut stack = vec!["#".to_owned()];
+    let mut nodes = 0;
+    resolve_node(schema, schema, &mut stack, &mut nodes)
+}
+
+fn resolve_node(
+    node: &serde_json::Value,
+    root: &serde_json::Value,
+    stack: &mut Vec<String>,
+    nodes: &mut usize,
+) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    *nodes += 1;
+    if *nodes > MAX_RESOLVED_NODES {
+        return Err(format!(
+            "schema exceeds {} nodes once $refs are inlined",
+            MAX_RESOLVED_NODES
+        ));
+    }
+
+    match node {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| resolve_node(item, root, stack, nodes))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Value::Object(obj) => {
+            let mut out = serde_json::Map::with_capacity(obj.len());
+            for (key, value) in obj {
+                if key != "$ref" {
+                    let value = if DATA_KEYWORDS.contains(&key.as_str()) {
+                        value.clone()
+                    } else {
+                        resolve_node(value, root, stack, nodes)?
+                    };
+                    out.insert(key.clone(), value);
+                }
+            }
+            let Some(reference) = obj.get("$ref") else {
+                return Ok(Value::Object(out));
+            };
+            let Some(reference) = reference.as_str() else {
+                return Err("$ref must be a string".to_owned());
+            };
+            let Some(pointer) = reference.strip_prefix('#') else {
+                return Err(format!("external $ref not supported: {}", reference));
+            };
+            let target = root
+                .pointer(pointer)
+                .ok_or_else(|| format!("unresolvable $ref: {}", reference))?;
+
+            if stack.iter().any(|r| r == reference) || stack.len() > MAX_REF_DEPTH {
+                // Recursive: keep the reference (its target is checked when
+                // first inlined).
+                out.insert("$ref".to_owned(), Value::String(reference.to_owned()));
+                return Ok(Value::Object(out));
+            }
+            stack.push(reference.to_owned());
+            let resolved = resolve_node(target, root, stack, nodes);
+            stack.pop();
+            let resolved = resolved?;
+
+            if out.is_empty() {
+                Ok(resolved)
+            } else {
+                // Keywords next to `$ref` apply alongside it.
+                out.insert("allOf".to_owned(), merge_all_of(out.get("allOf"), resolved));
+                Ok(Value::Object(out))
+            }
+        }
+        other => Ok(other.clone()),
     }
 }
 
+fn merge_all_of(
+    existing: Option<&serde_json::Value>,
+    resolved: serde_json::Value,
+) -> serde_json::Value {
+    let mut all_of = existing
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    all_of.push(resolved);
+    serde_json::Value::Array(all_of)
+}
+
 /// Extract the value to validate from an LLM response.
 /// Tries `choices[0].message.content` first (OpenAI format), then uses
 /// the full response body. If the content is a JSON-wrapped markdown block,
@@ -88,7 +257,7 @@ fn extract_content_for_validation(body: &serde_json::Value) -> serde_json::Value
 /// ```json
 /// { ... }
 /// ```
-fn extract_json_from_markdown(text: &str) -> Option<String> {
+pub(super) fn extract_json_from_markdown(text: &str) -> Option<String> {
     // Find opening fence
     let start = text.find("```json").or_else(|| text.find("```JSON"))?;
     let after_fence = &text[start + 7..]; // skip "```json"
@@ -181,4 +350,138 @@ mod schema_tests {
         let result = validate_schema(&response, &schema);
         assert!(!result.valid);
     }
+
+    fn address_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["name", "shipping", "billing"],
+            "properties": {
+                "name": { "type": "string" },
+                "shipping": { "$ref": "#/$defs/Address" },
+                "billing": { "$ref": "#/$defs/Address" }
+            },
+            "$defs": {
+                "Address": {
+                    "type": "object",
+                    "required": ["street", "city"],
+                    "properties": {
+                        "street": { "type": "string" },
+                        "city": { "type": "string" }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_defs_ref_conforming_response_passes() {
+        let content = json!({
+            "name": "Ada",
+            "shipping": { "street": "1 Main St", "city": "London" },
+            "billing": { "street": "2 High St", "city": "Leeds" }
+        });
+        let response = json!({
+            "choices": [{ "message": { "role": "assistant", "content": content.to_string() } }]
+        });
+        let result = validate_schema(&response, &address_schema());
+        assert!(result.valid, "errors: {:?}", result.errors);
+        assert!(!result.violated(false));
+        assert!(result.violated(true));
+    }
+
+    #[test]
+    fn test_defs_ref_non_conforming_response_fails() {
+        let response = json!({
+            "name": "Ada",
+            "shipping": { "street": "1 Main St", "city": "London" },
+            "billing": { "street": 42 }
+        });
+        let result = validate_schema(&response, &address_schema());
+        assert!(!result.valid);
+        assert!(!result.schema_error);
+        assert!(result.violated(false));
+        assert!(!result.violated(true));
+        let errors = result.errors.join("; ");
+        assert!(errors.contains("/billing"), "errors: {}", errors);
+    }
+
+    #[test]
+    fn test_definitions_ref_and_recursive_ref() {
+        let schema = json!({
+            "$ref": "#/definitions/Node",
+            "definitions": {
+                "Node": {
+                    "type": "object",
+                    "required": ["value"],
+                    "properties": {
+                        "value": { "type": "integer" },
+                        "children": { "type": "array", "items": { "$ref": "#/definitions/Node" } }
+                    }
+                }
+            }
+        });
+        let tree = json!({ "value": 1, "children": [{ "value": 2, "children": [] }] });
+        assert!(validate_schema(&tree, &schema).valid);
+        let bad = json!({ "value": 1, "children": [{ "value": "two" }] });
+        assert!(!validate_schema(&bad, &schema).valid);
+    }
+
+    #[test]
+    fn test_external_ref_is_a_schema_error() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "address": { "$ref": "https://example.com/address.json" } }
+        });
+        let result = validate_schema(&json!({ "address": {} }), &schema);
+        assert!(!result.valid);
+        assert!(result.schema_error);
+        assert_eq!(
+            result.errors,
+            vec!["Invalid JSON Schema: external $ref not supported: https://example.com/address.json"]
+        );
+        // Fails closed in `not` mode too.
+        assert!(result.violated(true));
+
+        let dangling = json!({ "$ref": "#/$defs/Missing" });
+        let result = validate_schema(&json!({}), &dangling);
+        assert!(result.schema_error);
+        assert!(result.errors[0].contains("unresolvable $ref: #/$defs/Missing"));
+    }
+
+    #[test]
+    fn test_ref_inside_const_is_data() {
+        let schema = json!({ "const": { "$ref": "https://example.com/x" } });
+        let result = validate_schema(&json!({ "$ref": "https://example.com/x" }), &schema);
+        assert!(result.valid, "errors: {:?}", result.errors);
+    }
+
+    #[test]
+    fn test_doubling_refs_are_rejected_not_expanded() {
+        // Each def refs the next twice: inlining would produce 2^40 nodes.
+        let mut defs = serde_json::Map::new();
+        for i in 0..40 {
+            defs.insert(
+                format!("D{}", i),
+                json!({ "type": "array", "items": [
+                    { "$ref": format!("#/$defs/D{}", i + 1) },
+                    { "$ref": format!("#/$defs/D{}", i + 1) }
+                ] }),
+            );
+        }
+        defs.insert("D40".to_owned(), json!({ "type": "string" }));
+        let schema = json!({ "$ref": "#/$defs/D0", "$defs": defs });
+
+        let result = validate_schema(&json!([]), &schema);
+        assert!(result.schema_error);
+        assert!(result.errors[0].contains("nodes once $refs are inlined"));
+        assert!(result.violated(true));
+    }
+
+    #[test]
+    fn test_compiled_schema_is_reused() {
+        let schema = address_schema();
+        let first = compiled_schema(&schema).unwrap();
+        let second = compiled_schema(&schema).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
 }
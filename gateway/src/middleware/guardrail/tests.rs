@@ -538,3 +538,58 @@ fn test_content_length_limit() {
         .iter()
         .any(|p| p.starts_with("content_too_long")));
 }
+
+// ── Pattern Lint ─────────────────────────────────────────────
+
+use super::lint::LintStatus;
+
+fn lint_json(rules: Value) -> Vec<PatternLint> {
+    let rules: Vec<crate::models::policy::Rule> = serde_json::from_value(rules).unwrap();
+    lint_rules(&rules, std::time::Duration::from_secs(5))
+}
+
+#[test]
+fn test_lint_collects_patterns_from_all_locations() {
+    let results = lint_json(json!([{
+        "when": {"field": "request.body.model", "op": "regex", "value": "^gpt-4"},
+        "then": [
+            {"action": "content_filter", "custom_patterns": ["(a+)+$"]},
+            {"action": "redact", "patterns": ["ssn", "\\d{4}-\\d{4}"]},
+            {"action": "transform", "operations": [
+                {"type": "regex_replace", "pattern": "foo\\s+bar", "replacement": "x"}
+            ]}
+        ]
+    }]));
+    let locations: Vec<&str> = results.iter().map(|r| r.location.as_str()).collect();
+    assert_eq!(
+        locations,
+        vec![
+            "rules[0].when",
+            "rules[0].then[0].custom_patterns[0]",
+            "rules[0].then[1].patterns[1]",
+            "rules[0].then[2].operations[0].pattern",
+        ],
+        "built-in redact names must be skipped"
+    );
+    // The regex crate is linear-time, so the classic ReDoS pattern is fine here.
+    assert!(results.iter().all(|r| r.is_ok()), "{:?}", results);
+}
+
+#[test]
+fn test_lint_flags_invalid_and_backtracking_patterns() {
+    let results = lint_json(json!([{
+        "then": {"action": "content_filter", "custom_patterns": ["(unclosed", "foo(?=bar)", "(a)\\1"]}
+    }]));
+    assert_eq!(results[0].status, LintStatus::Invalid);
+    assert_eq!(results[1].status, LintStatus::UnsupportedFeature);
+    assert_eq!(results[2].status, LintStatus::UnsupportedFeature);
+    assert!(results.iter().all(|r| r.error.is_some()));
+}
+
+#[test]
+fn test_lint_flags_oversized_pattern() {
+    let results = lint_json(json!([{
+        "then": {"action": "content_filter", "custom_patterns": ["\\w{1000}\\w{1000}\\w{1000}"]}
+    }]));
+    assert_eq!(results[0].status, LintStatus::TooComplex);
+}
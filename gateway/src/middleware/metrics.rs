@@ -1,14 +1,18 @@
 //! Prometheus metrics recorder for TrueFlow Gateway.
 //!
 //! Exposes a standard `/metrics` endpoint that Prometheus can scrape.
-//! Metrics are updated on every proxied request via `record()`.
+//! Metrics are updated on every proxied request via `record()`, i.e. wherever
+//! an audit entry is emitted.
+//!
+//! Label cardinality is kept bounded: metrics are labelled by project and
+//! provider (and model, behind a cardinality guard), never by token ID.
 
-use crate::models::audit::AuditEntry;
+use crate::models::audit::{AuditEntry, PolicyResult};
 use dashmap::DashSet;
 use once_cell::sync::Lazy;
 use prometheus::{
-    opts, register_counter_vec, register_histogram_vec, CounterVec, Encoder, HistogramVec,
-    TextEncoder,
+    histogram_opts, opts, register_counter_vec, register_gauge, register_gauge_vec,
+    register_histogram_vec, CounterVec, Encoder, Gauge, GaugeVec, HistogramVec, TextEncoder,
 };
 use rust_decimal::prelude::ToPrimitive;
 
@@ -18,8 +22,141 @@ const MAX_CARDINALITY: usize = 10_000;
 /// Tracks unique model names seen so far (cardinality guard).
 static SEEN_MODELS: Lazy<DashSet<String>> = Lazy::new(DashSet::new);
 
+/// Current effective limit of tokens using adaptive (AIMD) rate limiting,
+/// by project and provider. With several adaptive tokens on one project and
+/// provider, the gauge holds the most recently updated limit.
+static ADAPTIVE_RATE_LIMIT: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        opts!(
+            "trueflow_adaptive_rate_limit",
+            "Current adaptive request limit per window"
+        ),
+        &["project", "provider"]
+    )
+    .expect("failed to register trueflow_adaptive_rate_limit")
+});
+
+/// Publish an adaptive token's current rate limit.
+pub fn set_adaptive_rate_limit(project: &str, provider: &str, limit: u64) {
+    ADAPTIVE_RATE_LIMIT
+        .with_label_values(&[project, provider])
+        .set(limit as f64);
+}
+
+/// Streaming responses currently open on this replica.
+static ACTIVE_STREAMS: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(opts!(
+        "trueflow_active_streams",
+        "Streaming responses currently in progress"
+    ))
+    .expect("failed to register trueflow_active_streams")
+});
+
+/// Publish the number of in-progress streaming responses.
+pub fn set_active_streams(count: u64) {
+    ACTIVE_STREAMS.set(count as f64);
+}
+
+/// Upstream call latency, from sending the request to receiving response
+/// headers (retries included). Excludes policy evaluation and HITL waits.
+static UPSTREAM_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        histogram_opts!(
+            "trueflow_upstream_latency_seconds",
+            "Upstream call latency in seconds",
+            vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0]
+        ),
+        &["project", "provider"]
+    )
+    .expect("failed to register trueflow_upstream_latency_seconds")
+});
+
+/// Observe one upstream call's latency.
+pub fn observe_upstream_latency(project: &str, provider: &str, secs: f64) {
+    UPSTREAM_LATENCY
+        .with_label_values(&[project, provider])
+        .observe(secs);
+}
+
+/// Response cache lookups by outcome (`hit` or `miss`).
+static CACHE_LOOKUPS: Lazy<CounterVec> = Lazy::new(|| {
+    register_counter_vec!(
+        opts!(
+            "trueflow_cache_lookups_total",
+            "Response cache lookups by result"
+        ),
+        &["project", "result"]
+    )
+    .expect("failed to register trueflow_cache_lookups_total")
+});
+
+/// Count a response cache lookup for a cacheable request.
+pub fn record_cache_lookup(project: &str, hit: bool) {
+    CACHE_LOOKUPS
+        .with_label_values(&[project, if hit { "hit" } else { "miss" }])
+        .inc();
+}
+
+/// Requests on this replica currently waiting for a HITL decision.
+static HITL_PENDING: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        opts!(
+            "trueflow_hitl_pending",
+            "Requests waiting for a human approval decision"
+        ),
+        &["project"]
+    )
+    .expect("failed to register trueflow_hitl_pending")
+});
+
+/// Counts a request in `trueflow_hitl_pending` for as long as it is held.
+pub struct HitlPendingGuard {
+    project: String,
+}
+
+impl HitlPendingGuard {
+    pub fn new(project: &str) -> Self {
+        HITL_PENDING.with_label_values(&[project]).inc();
+        Self {
+            project: project.to_string(),
+        }
+    }
+}
+
+impl Drop for HitlPendingGuard {
+    fn drop(&mut self) {
+        HITL_PENDING.with_label_values(&[&self.project]).dec();
+    }
+}
+
+/// Upstream circuits currently open on this replica.
+static CIRCUITS_OPEN: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        opts!(
+            "trueflow_circuit_breakers_open",
+            "Upstream circuit breakers currently open (including half-open)"
+        ),
+        &["provider"]
+    )
+    .expect("failed to register trueflow_circuit_breakers_open")
+});
+
+/// Track a circuit breaker opening (`healthy = false`) or closing.
+pub fn record_circuit_transition(provider: &str, healthy: bool) {
+    let gauge = CIRCUITS_OPEN.with_label_values(&[provider]);
+    if healthy {
+        gauge.dec();
+    } else {
+        gauge.inc();
+    }
+}
+
+/// Registered once; every [`PrometheusRecorder::new`] shares these metrics.
+static RECORDER: Lazy<PrometheusRecorder> = Lazy::new(PrometheusRecorder::register);
+
 /// Prometheus metrics recorder.
 /// All metrics are registered in the global default registry.
+#[derive(Clone)]
 pub struct PrometheusRecorder {
     // Counters
     requests_total: CounterVec,
@@ -33,6 +170,7 @@ pub struct PrometheusRecorder {
 
     // Gauges
     cache_hit_total: CounterVec,
+    policy_denials_total: CounterVec,
 }
 
 impl Default for PrometheusRecorder {
@@ -42,14 +180,26 @@ impl Default for PrometheusRecorder {
 }
 
 impl PrometheusRecorder {
-    /// Create and register all metrics in the global Prometheus registry.
+    /// Handle to the recorder's metrics, registering them in the global
+    /// Prometheus registry on first use.
     pub fn new() -> Self {
+        RECORDER.clone()
+    }
+
+    fn register() -> Self {
         let requests_total = register_counter_vec!(
             opts!(
                 "trueflow_requests_total",
                 "Total number of proxied requests"
             ),
-            &["model", "status_code", "cache_hit", "is_streaming"]
+            &[
+                "project",
+                "provider",
+                "model",
+                "status_code",
+                "cache_hit",
+                "is_streaming"
+            ]
         )
         .expect("failed to register trueflow_requests_total");
 
@@ -98,6 +248,15 @@ impl PrometheusRecorder {
         )
         .expect("failed to register trueflow_cache_hits_total");
 
+        let policy_denials_total = register_counter_vec!(
+            opts!(
+                "trueflow_policy_denials_total",
+                "Requests denied, by policy name"
+            ),
+            &["project", "policy"]
+        )
+        .expect("failed to register trueflow_policy_denials_total");
+
         Self {
             requests_total,
             tokens_total,
@@ -106,6 +265,7 @@ impl PrometheusRecorder {
             request_duration_seconds,
             ttft_seconds,
             cache_hit_total,
+            policy_denials_total,
         }
     }
 
@@ -129,12 +289,21 @@ impl PrometheusRecorder {
             .unwrap_or_else(|| "0".to_string());
         let cache_hit = if entry.cache_hit { "true" } else { "false" };
         let is_streaming = if entry.is_streaming { "true" } else { "false" };
+        let project = entry.project_id.to_string();
+        let provider = entry.provider.as_deref().unwrap_or("unknown");
 
         // Request counter
         self.requests_total
-            .with_label_values(&[model, &status, cache_hit, is_streaming])
+            .with_label_values(&[&project, provider, model, &status, cache_hit, is_streaming])
             .inc();
 
+        // Policy denials (built-in checks such as spend caps use fixed names)
+        if let PolicyResult::Deny { policy, .. } = &entry.policy_result {
+            self.policy_denials_total
+                .with_label_values(&[&project, policy])
+                .inc();
+        }
+
         // Latency histogram
         let duration_secs = entry.response_latency_ms as f64 / 1000.0;
         self.request_duration_seconds
@@ -186,6 +355,18 @@ impl PrometheusRecorder {
     }
 }
 
+/// GET /metrics — Prometheus text exposition format.
+/// Unauthenticated (standard for Prometheus scrape targets).
+pub async fn metrics_handler() -> axum::response::Response<axum::body::Body> {
+    let body = encode_metrics();
+    axum::response::Response::builder()
+        .header("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
+        .body(axum::body::Body::from(body))
+        .unwrap_or_else(|_| {
+            axum::response::Response::new(axum::body::Body::from("# error encoding metrics\n"))
+        })
+}
+
 /// Encode all registered metrics as Prometheus text format.
 /// Called by the `/metrics` HTTP handler.
 pub fn encode_metrics() -> String {
@@ -215,4 +396,64 @@ mod tests {
     fn test_cardinality_guard_threshold() {
         assert_eq!(MAX_CARDINALITY, 10_000);
     }
+
+    #[test]
+    fn test_recorder_can_be_created_twice() {
+        let _a = PrometheusRecorder::new();
+        let _b = PrometheusRecorder::new();
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_exposes_expected_metrics() {
+        use tower::ServiceExt;
+
+        let recorder = PrometheusRecorder::new();
+        let mut entry = crate::middleware::audit::tests::test_audit_entry(PolicyResult::Deny {
+            policy: "block-pii".to_string(),
+            reason: "ssn".to_string(),
+        });
+        entry.provider = Some("openai".to_string());
+        entry.upstream_status = Some(403);
+        recorder.record(&entry);
+        let project = entry.project_id.to_string();
+        observe_upstream_latency(&project, "openai", 0.3);
+        record_cache_lookup(&project, false);
+        record_circuit_transition("anthropic", false);
+        let hitl = HitlPendingGuard::new(&project);
+
+        let app = axum::Router::new().route("/metrics", axum::routing::get(metrics_handler));
+        let resp = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/metrics")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        for name in [
+            "trueflow_requests_total",
+            "trueflow_upstream_latency_seconds_bucket",
+            "trueflow_cache_lookups_total",
+            "trueflow_policy_denials_total",
+            "trueflow_hitl_pending",
+            "trueflow_circuit_breakers_open",
+        ] {
+            assert!(text.contains(name), "missing {name} in /metrics output");
+        }
+        assert!(text.contains(&format!(
+            "trueflow_policy_denials_total{{policy=\"block-pii\",project=\"{project}\"}} 1"
+        )));
+        assert!(!text.contains("token_id=\"test-token\""));
+        drop(hitl);
+    }
 }
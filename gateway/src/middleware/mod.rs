@@ -1,5 +1,10 @@
+pub mod access_log;
 pub mod anomaly;
 pub mod audit;
+pub mod audit_sink;
+pub mod capability;
+pub mod compression;
+pub mod context_window;
 pub mod datadog;
 pub mod engine;
 pub mod external_guardrail;
@@ -10,6 +15,7 @@ pub mod langfuse;
 pub mod mcp;
 pub mod metrics;
 pub mod model_access;
+pub mod model_rate_limit;
 pub mod observer;
 pub mod oidc;
 pub mod pii;
@@ -17,6 +23,7 @@ pub mod pii_vault;
 pub mod policy;
 pub mod rbac;
 pub mod redact;
+pub mod replay;
 pub mod sanitize;
 pub mod spend;
 pub mod teams;
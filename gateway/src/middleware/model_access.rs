@@ -12,6 +12,9 @@
 //!
 //! If `allowed_models` is NULL/empty AND `allowed_model_group_ids` is NULL/empty,
 //! all models are allowed (backwards compatible with existing tokens).
+//!
+//! Credentials carry their own `allowed_models` list, checked independently of
+//! the token so a shared billing credential can't serve models outside its budget.
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -41,22 +44,7 @@ pub fn check_model_access(
         return Ok(());
     }
 
-    // Collect all allowed patterns from both sources
-    let mut patterns: Vec<String> = Vec::new();
-
-    // 1. Direct allowed_models from token
-    if let Some(models_json) = allowed_models {
-        if let Some(arr) = models_json.as_array() {
-            for v in arr {
-                if let Some(s) = v.as_str() {
-                    patterns.push(s.to_string());
-                }
-            }
-        }
-    }
-
-    // 2. Models from resolved groups
-    patterns.extend_from_slice(resolved_group_models);
+    let patterns = allowed_model_patterns(allowed_models, resolved_group_models);
 
     // If no patterns configured at all, allow everything (backwards compatible)
     if patterns.is_empty() {
@@ -77,6 +65,48 @@ pub fn check_model_access(
     ))
 }
 
+/// Every pattern a token may call: its direct `allowed_models` followed by
+/// the models of its resolved groups. Empty means unrestricted.
+pub fn allowed_model_patterns(
+    allowed_models: Option<&serde_json::Value>,
+    resolved_group_models: &[String],
+) -> Vec<String> {
+    let mut patterns: Vec<String> = allowed_models
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    patterns.extend_from_slice(resolved_group_models);
+    patterns
+}
+
+/// Check whether the credential resolved for a request may serve the model.
+///
+/// `allowed` is the credential's `allowed_models` patterns; `None` or empty
+/// leaves the credential unrestricted.
+pub fn check_credential_model_access(
+    requested_model: &str,
+    credential_name: &str,
+    allowed: Option<&[String]>,
+) -> Result<(), String> {
+    let patterns = match allowed {
+        Some(p) if !p.is_empty() && !requested_model.is_empty() => p,
+        _ => return Ok(()),
+    };
+    if patterns.iter().any(|p| model_matches(requested_model, p)) {
+        return Ok(());
+    }
+    Err(format!(
+        "Model '{}' is not permitted for credential '{}'. Allowed: [{}]",
+        requested_model,
+        credential_name,
+        patterns.join(", ")
+    ))
+}
+
 /// Check if a model name matches a pattern.
 ///
 /// Supports:
@@ -291,6 +321,17 @@ mod tests {
         assert!(check_model_access("", Some(&allowed), &[]).is_ok());
     }
 
+    #[test]
+    fn test_allowed_model_patterns_merges_sources() {
+        let allowed = serde_json::json!(["gpt-4*", 7]);
+        let group = vec!["claude-3-haiku*".to_string()];
+        assert_eq!(
+            allowed_model_patterns(Some(&allowed), &group),
+            vec!["gpt-4*", "claude-3-haiku*"]
+        );
+        assert!(allowed_model_patterns(None, &[]).is_empty());
+    }
+
     #[test]
     fn test_denied_model_error_message() {
         let allowed = serde_json::json!(["gpt-4o"]);
@@ -299,4 +340,26 @@ mod tests {
         assert!(err.contains("not allowed"));
         assert!(err.contains("gpt-4o"));
     }
+
+    // ── check_credential_model_access ──────────────────────────
+
+    #[test]
+    fn test_credential_unrestricted_when_unset_or_empty() {
+        assert!(check_credential_model_access("gpt-4o", "shared", None).is_ok());
+        assert!(check_credential_model_access("gpt-4o", "shared", Some(&[])).is_ok());
+    }
+
+    #[test]
+    fn test_credential_allowed_models_globs() {
+        let allowed = vec!["gpt-4o-mini".to_string(), "text-embedding-*".to_string()];
+        assert!(check_credential_model_access("gpt-4o-mini", "team-a", Some(&allowed)).is_ok());
+        assert!(
+            check_credential_model_access("text-embedding-3-small", "team-a", Some(&allowed))
+                .is_ok()
+        );
+
+        let err = check_credential_model_access("gpt-4o", "team-a", Some(&allowed)).unwrap_err();
+        assert!(err.contains("gpt-4o"));
+        assert!(err.contains("credential 'team-a'"));
+    }
 }
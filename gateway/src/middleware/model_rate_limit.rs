@@ -0,0 +1,140 @@
+//! Provider-account rate limits, shared by every token on a credential.
+//!
+//! Per-token limits can't stop several tokens that share one provider key from
+//! jointly exceeding the provider's per-model RPM/TPM quota. A credential's
+//! `model_rate_limits` holds those quotas; they are counted per
+//! `(credential, model)` in Redis, so they hold across tokens and replicas.
+
+use serde::{Deserialize, Serialize};
+
+use super::model_access::model_matches;
+
+/// Sliding window for both limits. Provider quotas are per minute.
+pub const MODEL_RATE_LIMIT_WINDOW_SECS: u64 = 60;
+
+/// Longest a request may wait for a slot.
+pub const MAX_QUEUE_MS: u64 = 30_000;
+
+/// One entry of a credential's `model_rate_limits`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelRateLimit {
+    /// Model pattern, using the same globs as `allowed_models`.
+    pub model: String,
+    /// Requests per minute across all tokens on the credential.
+    #[serde(default)]
+    pub rpm: Option<u64>,
+    /// Tokens per minute (estimated prompt plus reserved output).
+    #[serde(default)]
+    pub tpm: Option<u64>,
+    /// How long a request waits for capacity before it is rejected.
+    /// 0 rejects immediately.
+    #[serde(default)]
+    pub queue_ms: u64,
+}
+
+/// Which of a [`ModelRateLimit`]'s counters was full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelLimitKind {
+    Rpm,
+    Tpm,
+}
+
+impl ModelLimitKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModelLimitKind::Rpm => "rpm",
+            ModelLimitKind::Tpm => "tpm",
+        }
+    }
+}
+
+/// Parse and validate a `model_rate_limits` value. Every entry needs a
+/// pattern and at least one positive limit.
+pub fn parse_limits(value: &serde_json::Value) -> Result<Vec<ModelRateLimit>, String> {
+    let limits: Vec<ModelRateLimit> =
+        serde_json::from_value(value.clone()).map_err(|e| e.to_string())?;
+    for limit in &limits {
+        if limit.model.trim().is_empty() {
+            return Err("model pattern must not be empty".into());
+        }
+        if limit.rpm.is_none() && limit.tpm.is_none() {
+            return Err(format!("'{}' sets neither rpm nor tpm", limit.model));
+        }
+        if limit.rpm == Some(0) || limit.tpm == Some(0) {
+            return Err(format!("'{}' limits must be positive", limit.model));
+        }
+        if limit.queue_ms > MAX_QUEUE_MS {
+            return Err(format!(
+                "'{}' queue_ms exceeds {}",
+                limit.model, MAX_QUEUE_MS
+            ));
+        }
+    }
+    Ok(limits)
+}
+
+/// The first limit whose pattern matches `model`.
+pub fn limit_for<'a>(limits: &'a [ModelRateLimit], model: &str) -> Option<&'a ModelRateLimit> {
+    if model.is_empty() {
+        return None;
+    }
+    limits.iter().find(|l| model_matches(model, &l.model))
+}
+
+/// Redis key of one counter. Keyed on the concrete model, not the pattern,
+/// since provider quotas are per model.
+pub fn window_key(credential_id: &str, model: &str, kind: ModelLimitKind) -> String {
+    format!(
+        "rl:cred:{}:{}:{}",
+        credential_id,
+        model.to_lowercase(),
+        kind.as_str()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_limits_validation() {
+        let limits = parse_limits(&json!([
+            {"model": "gpt-4o*", "rpm": 500, "tpm": 30000},
+            {"model": "*", "rpm": 100, "queue_ms": 2000}
+        ]))
+        .unwrap();
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits[1].queue_ms, 2000);
+
+        assert!(parse_limits(&json!([{"model": "gpt-4o"}])).is_err());
+        assert!(parse_limits(&json!([{"model": "gpt-4o", "rpm": 0}])).is_err());
+        assert!(parse_limits(&json!([{"model": " ", "rpm": 1}])).is_err());
+        assert!(parse_limits(&json!([{"model": "x", "rpm": 1, "queue_ms": 60000}])).is_err());
+        assert!(parse_limits(&json!([{"model": "x", "rpm": 1, "burst": 2}])).is_err());
+    }
+
+    #[test]
+    fn test_limit_for_first_match_and_key_per_model() {
+        let limits = parse_limits(&json!([
+            {"model": "gpt-4o-mini", "rpm": 1000},
+            {"model": "gpt-4o*", "rpm": 500}
+        ]))
+        .unwrap();
+        assert_eq!(limit_for(&limits, "gpt-4o-mini").unwrap().rpm, Some(1000));
+        assert_eq!(limit_for(&limits, "GPT-4o").unwrap().rpm, Some(500));
+        assert!(limit_for(&limits, "claude-3-haiku").is_none());
+        assert!(limit_for(&limits, "").is_none());
+
+        // Two models under one pattern get separate counters
+        assert_ne!(
+            window_key("c1", "gpt-4o", ModelLimitKind::Rpm),
+            window_key("c1", "gpt-4o-2024-08-06", ModelLimitKind::Rpm)
+        );
+        assert_eq!(
+            window_key("c1", "GPT-4o", ModelLimitKind::Tpm),
+            "rl:cred:c1:gpt-4o:tpm"
+        );
+    }
+}
@@ -150,6 +150,44 @@ pub async fn discover(issuer_url: &str) -> anyhow::Result<OidcDiscovery> {
     Ok(discovery)
 }
 
+// ── Provider Validation ──────────────────────────────────────
+
+/// Roles a provider may grant by default (see the role mapping in `api::admin_auth`).
+pub const PROVIDER_ROLES: &[&str] = &["superadmin", "admin", "member", "readonly", "viewer"];
+
+/// Issuers must be absolute HTTPS URLs: the issuer is matched against the
+/// JWT `iss` claim and used for discovery, so plain HTTP would let anyone on
+/// the path substitute keys.
+pub fn validate_issuer_url(issuer_url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(issuer_url).map_err(|e| format!("invalid issuer URL: {}", e))?;
+    if url.scheme() != "https" {
+        return Err("issuer URL must use https".to_string());
+    }
+    if url.host_str().is_none_or(|h| h.is_empty()) {
+        return Err("issuer URL must have a host".to_string());
+    }
+    Ok(())
+}
+
+/// Check that a provider's keys can be fetched: `jwks_uri` if set, else the
+/// one discovered from the issuer. Bypasses the JWKS cache. Returns the
+/// number of keys.
+pub async fn check_jwks(issuer_url: &str, jwks_uri: Option<&str>) -> anyhow::Result<usize> {
+    let jwks_uri = match jwks_uri {
+        Some(uri) => uri.to_string(),
+        None => discover(issuer_url).await?.jwks_uri,
+    };
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let resp = client.get(&jwks_uri).send().await?.error_for_status()?;
+    let jwks: Jwks = resp.json().await?;
+    if jwks.keys.is_empty() {
+        anyhow::bail!("JWKS at {} has no keys", jwks_uri);
+    }
+    Ok(jwks.keys.len())
+}
+
 // ── JWT Validation ───────────────────────────────────────────
 
 /// Decode a JWT token (header only — for kid extraction).
@@ -536,4 +574,13 @@ mod tests {
         let result = decode_claims("not-a-jwt");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_validate_issuer_url() {
+        assert!(validate_issuer_url("https://corp.okta.com").is_ok());
+        assert!(validate_issuer_url("https://login.microsoftonline.com/tenant/v2.0").is_ok());
+        assert!(validate_issuer_url("http://corp.okta.com").is_err());
+        assert!(validate_issuer_url("corp.okta.com").is_err());
+        assert!(validate_issuer_url("").is_err());
+    }
 }
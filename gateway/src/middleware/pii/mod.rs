@@ -128,11 +128,7 @@ pub fn extract_text_from_value(v: &Value) -> String {
 
 fn collect_strings(v: &Value, parts: &mut Vec<String>) {
     match v {
-        Value::String(s) => {
-            if !s.is_empty() {
-                parts.push(s.clone());
-            }
-        }
+        Value::String(s) if !s.is_empty() => parts.push(s.clone()),
         Value::Array(arr) => {
             for item in arr {
                 collect_strings(item, parts);
@@ -169,11 +169,7 @@ fn collect_pii_matches(
 /// Replace all occurrences of `target` with `replacement` in all string values.
 fn replace_in_value(v: &mut Value, target: &str, replacement: &str) {
     match v {
-        Value::String(s) => {
-            if s.contains(target) {
-                *s = s.replace(target, replacement);
-            }
-        }
+        Value::String(s) if s.contains(target) => *s = s.replace(target, replacement),
         Value::Array(arr) => {
             for item in arr {
                 replace_in_value(item, target, replacement);
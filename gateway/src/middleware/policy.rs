@@ -6,6 +6,11 @@
 
 // use crate::cache::TieredCache;
 // use crate::errors::AppError;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
 use crate::models::policy::{EvalOutcome, Phase, Policy};
 
 use super::engine;
@@ -49,6 +54,108 @@ pub fn parse_window_secs(window: &str) -> Option<u64> {
     }
 }
 
+// ── Evaluation Budget ────────────────────────────────────────
+
+/// Time spent on one step of pre-flight evaluation.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionTiming {
+    pub policy: String,
+    pub action: &'static str,
+    pub elapsed_ms: u64,
+}
+
+/// Wall-clock budget for a request's pre-flight policy evaluation
+/// (`TRUEFLOW_POLICY_EVAL_BUDGET_MS`).
+///
+/// Each evaluated step is timed so an overrun can name the slow policy.
+/// Local work that can be moved off the request task (content filters,
+/// redaction) is bounded with [`EvalBudget::run`]; synchronous condition
+/// matching can only be measured, and the overrun is caught after it returns.
+pub struct EvalBudget {
+    budget: Option<Duration>,
+    started: Instant,
+    timings: Vec<ActionTiming>,
+    timed_out: bool,
+}
+
+impl EvalBudget {
+    /// `budget_ms = 0` disables the budget (timings are still recorded).
+    pub fn new(budget_ms: u64) -> Self {
+        Self {
+            budget: (budget_ms > 0).then(|| Duration::from_millis(budget_ms)),
+            started: Instant::now(),
+            timings: Vec::new(),
+            timed_out: false,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.budget.is_some()
+    }
+
+    pub fn budget_ms(&self) -> u64 {
+        self.budget.map_or(0, |b| b.as_millis() as u64)
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    pub fn record(&mut self, policy: &str, action: &'static str, elapsed: Duration) {
+        let elapsed_ms = elapsed.as_millis() as u64;
+        tracing::debug!(policy, action, elapsed_ms, "pre-flight policy step");
+        self.timings.push(ActionTiming {
+            policy: policy.to_string(),
+            action,
+            elapsed_ms,
+        });
+    }
+
+    /// Exclude a deliberate wait (e.g. a `throttle` action) from the budget.
+    pub fn credit(&mut self, wait: Duration) {
+        self.started += wait;
+    }
+
+    /// True once the budget is spent, or a bounded step was cut off.
+    pub fn exhausted(&self) -> bool {
+        self.timed_out
+            || self
+                .budget
+                .is_some_and(|budget| self.started.elapsed() >= budget)
+    }
+
+    /// Await `fut` for at most the remaining budget. Returns `None` (and marks
+    /// the budget exhausted) if it didn't finish in time.
+    pub async fn run<F: Future>(&mut self, fut: F) -> Option<F::Output> {
+        let Some(budget) = self.budget else {
+            return Some(fut.await);
+        };
+        let remaining = budget.saturating_sub(self.started.elapsed());
+        match tokio::time::timeout(remaining, fut).await {
+            Ok(out) => Some(out),
+            Err(_) => {
+                self.timed_out = true;
+                None
+            }
+        }
+    }
+
+    /// The step that took longest — the likely culprit of an overrun.
+    pub fn slowest(&self) -> Option<&ActionTiming> {
+        self.timings.iter().max_by_key(|t| t.elapsed_ms)
+    }
+
+    /// Summary for the audit log: budget, total and per-step durations.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "budget_ms": self.budget_ms(),
+            "elapsed_ms": self.elapsed_ms(),
+            "slowest": self.slowest(),
+            "steps": self.timings,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,4 +169,23 @@ mod tests {
         assert_eq!(parse_window_secs(""), None);
         assert_eq!(parse_window_secs("abc"), None);
     }
+
+    #[tokio::test]
+    async fn test_eval_budget_bounds_slow_step() {
+        let mut budget = EvalBudget::new(20);
+        assert!(!budget.exhausted());
+        budget.record("fast", "log", Duration::from_millis(1));
+
+        let slow = budget.run(tokio::time::sleep(Duration::from_secs(5))).await;
+        assert!(slow.is_none());
+        assert!(budget.exhausted());
+        budget.record("slow-regex", "content_filter", Duration::from_millis(20));
+        assert_eq!(budget.slowest().unwrap().policy, "slow-regex");
+        assert_eq!(budget.to_json()["steps"].as_array().unwrap().len(), 2);
+
+        // Disabled budget never times out.
+        let mut unlimited = EvalBudget::new(0);
+        assert_eq!(unlimited.run(async { 7 }).await, Some(7));
+        assert!(!unlimited.exhausted());
+    }
 }
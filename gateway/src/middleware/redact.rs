@@ -4,11 +4,14 @@
 //! `Action::Transform` (header/body mutations) for the condition→action engine.
 
 #![allow(dead_code)]
+use std::collections::HashMap;
+use std::sync::RwLock;
+
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde_json::Value;
 
-use crate::models::policy::{Action, RedactDirection, RedactOnMatch, TransformOp};
+use crate::models::policy::{Action, NamedRegex, RedactDirection, RedactOnMatch, TransformOp};
 
 // ── Built-in PII patterns ────────────────────────────────────
 
@@ -154,19 +157,21 @@ pub struct RedactResult {
 /// Apply policy-driven redaction to a JSON body.
 ///
 /// Supports two modes:
-/// - **Pattern-based**: Named patterns (`ssn`, `email`) or custom regex strings.
+/// - **Pattern-based**: Named patterns (`ssn`, `email`), raw regex strings, or
+///   named `custom_regexes`.
 /// - **Field-based**: Blanks specific JSON keys listed in `fields`.
 ///
 /// Returns a `RedactResult` describing what matched and whether the request should be blocked.
 pub fn apply_redact(body: &mut Value, action: &Action, is_request: bool) -> RedactResult {
-    let (direction, patterns, fields, on_match) = match action {
+    let (direction, patterns, custom_regexes, fields, on_match) = match action {
         Action::Redact {
             direction,
             patterns,
+            custom_regexes,
             fields,
             on_match,
             ..
-        } => (direction, patterns, fields, on_match),
+        } => (direction, patterns, custom_regexes, fields, on_match),
         _ => return RedactResult::default(),
     };
 
@@ -184,8 +189,8 @@ pub fn apply_redact(body: &mut Value, action: &Action, is_request: bool) -> Reda
     let mut matched = Vec::new();
 
     // 1. Pattern-based redaction (walk all string values)
-    if !patterns.is_empty() {
-        let compiled = compile_patterns(patterns);
+    if !patterns.is_empty() || !custom_regexes.is_empty() {
+        let compiled = compile_patterns(patterns, custom_regexes);
         redact_value(body, &compiled, &mut matched);
     }
 
@@ -202,26 +207,95 @@ pub fn apply_redact(body: &mut Value, action: &Action, is_request: bool) -> Reda
     }
 }
 
-/// Compile pattern names into (regex, replacement, name) tuples.
+/// Whether `name` refers to one of the built-in redaction patterns
+/// (e.g. `ssn`, `email`) rather than a raw regex string.
+pub fn is_builtin_pattern(name: &str) -> bool {
+    BUILTIN_PATTERNS.iter().any(|b| b.name == name)
+}
+
+// ── Custom regexes ───────────────────────────────────────────
+
+/// Compiled-size limit for policy-authored regexes. The `regex` crate runs in
+/// linear time, so this bounds the remaining risk: automata that blow up memory.
+pub const CUSTOM_REGEX_SIZE_LIMIT: usize = 1_000_000;
+/// Longest accepted policy-authored pattern source.
+pub const MAX_CUSTOM_REGEX_LEN: usize = 1_024;
+/// Distinct patterns kept in the compile cache; beyond it, patterns are compiled per use.
+const CUSTOM_REGEX_CACHE_MAX: usize = 1_024;
+
+/// Compiled policy-authored patterns keyed by source, so a pattern is compiled
+/// once rather than per request. Rejected patterns are cached as `None`.
+static CUSTOM_REGEX_CACHE: Lazy<RwLock<HashMap<String, Option<Regex>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Compile a policy-authored pattern, rejecting ones that are empty, too long,
+/// fail to compile, or exceed [`CUSTOM_REGEX_SIZE_LIMIT`].
+pub fn validate_custom_regex(pattern: &str) -> Result<Regex, String> {
+    if pattern.is_empty() {
+        return Err("pattern is empty".to_string());
+    }
+    if pattern.len() > MAX_CUSTOM_REGEX_LEN {
+        return Err(format!(
+            "pattern is {} bytes (max {})",
+            pattern.len(),
+            MAX_CUSTOM_REGEX_LEN
+        ));
+    }
+    regex::RegexBuilder::new(pattern)
+        .size_limit(CUSTOM_REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Cached [`validate_custom_regex`]; `None` if the pattern is rejected.
+fn custom_regex(pattern: &str) -> Option<Regex> {
+    if let Some(cached) = CUSTOM_REGEX_CACHE
+        .read()
+        .ok()
+        .and_then(|cache| cache.get(pattern).cloned())
+    {
+        return cached;
+    }
+    let compiled = match validate_custom_regex(pattern) {
+        Ok(re) => Some(re),
+        Err(e) => {
+            tracing::warn!(pattern, error = %e, "redact: rejected custom regex");
+            None
+        }
+    };
+    if let Ok(mut cache) = CUSTOM_REGEX_CACHE.write() {
+        if cache.len() < CUSTOM_REGEX_CACHE_MAX {
+            cache.insert(pattern.to_string(), compiled.clone());
+        }
+    }
+    compiled
+}
+
+/// Compile pattern names and named custom regexes into (regex, replacement, name) tuples.
 /// If a pattern name matches a built-in, use that; otherwise treat it as raw regex.
-fn compile_patterns(patterns: &[String]) -> Vec<(Regex, String, String)> {
-    patterns
-        .iter()
-        .filter_map(|p| {
-            // Check built-in patterns first
-            if let Some(builtin) = BUILTIN_PATTERNS.iter().find(|b| b.name == p) {
-                // Clone the inner Regex from the Lazy
-                let re: &Regex = builtin.regex;
-                return Some((re.clone(), builtin.replacement.to_string(), p.clone()));
-            }
-            // Try compiling as custom regex with size limit to prevent ReDoS
-            regex::RegexBuilder::new(p)
-                .size_limit(1_000_000) // 1MB limit on compiled regex size
-                .build()
-                .ok()
-                .map(|re| (re, format!("[REDACTED_{}]", p.to_uppercase()), p.clone()))
+fn compile_patterns(
+    patterns: &[String],
+    custom_regexes: &[NamedRegex],
+) -> Vec<(Regex, String, String)> {
+    let named = patterns.iter().filter_map(|p| {
+        // Check built-in patterns first
+        if let Some(builtin) = BUILTIN_PATTERNS.iter().find(|b| b.name == p) {
+            // Clone the inner Regex from the Lazy
+            let re: &Regex = builtin.regex;
+            return Some((re.clone(), builtin.replacement.to_string(), p.clone()));
+        }
+        custom_regex(p).map(|re| (re, format!("[REDACTED_{}]", p.to_uppercase()), p.clone()))
+    });
+    let custom = custom_regexes.iter().filter_map(|c| {
+        custom_regex(&c.pattern).map(|re| {
+            (
+                re,
+                format!("[REDACTED_{}]", c.name.to_uppercase()),
+                c.name.clone(),
+            )
         })
-        .collect()
+    });
+    named.chain(custom).collect()
 }
 
 /// Recursively walk a JSON value and apply pattern-based redaction to strings.
@@ -281,27 +355,15 @@ fn redact_fields(v: &mut Value, fields: &[String], matched: &mut Vec<String>) {
 pub fn compile_pii_patterns(
     pattern_names: &[String],
 ) -> Vec<crate::middleware::pii_vault::PiiPattern> {
-    pattern_names
-        .iter()
-        .filter_map(|p| {
-            // Check built-in patterns first
-            if let Some(builtin) = BUILTIN_PATTERNS.iter().find(|b| b.name == p) {
-                let re: &Regex = builtin.regex;
-                return Some(crate::middleware::pii_vault::PiiPattern {
-                    name: p.clone(),
-                    regex: re.clone(),
-                });
-            }
-            // Try compiling as custom regex
-            regex::RegexBuilder::new(p)
-                .size_limit(1_000_000)
-                .build()
-                .ok()
-                .map(|re| crate::middleware::pii_vault::PiiPattern {
-                    name: p.clone(),
-                    regex: re,
-                })
-        })
+    into_pii_patterns(compile_patterns(pattern_names, &[]))
+}
+
+fn into_pii_patterns(
+    compiled: Vec<(Regex, String, String)>,
+) -> Vec<crate::middleware::pii_vault::PiiPattern> {
+    compiled
+        .into_iter()
+        .map(|(regex, _, name)| crate::middleware::pii_vault::PiiPattern { name, regex })
         .collect()
 }
 
@@ -318,14 +380,14 @@ pub async fn apply_redact_tokenize(
     pool: &sqlx::PgPool,
     vault: &crate::vault::builtin::VaultCrypto,
 ) -> RedactResult {
-    let (direction, patterns, fields, _) = match action {
+    let (direction, patterns, custom_regexes, fields) = match action {
         Action::Redact {
             direction,
             patterns,
+            custom_regexes,
             fields,
-            on_match: _,
             ..
-        } => (direction, patterns, fields, ()),
+        } => (direction, patterns, custom_regexes, fields),
         _ => return RedactResult::default(),
     };
 
@@ -343,8 +405,8 @@ pub async fn apply_redact_tokenize(
     let mut matched = Vec::new();
 
     // 1. Pattern-based tokenization (async — stores tokens in PG)
-    if !patterns.is_empty() {
-        let pii_patterns = compile_pii_patterns(patterns);
+    if !patterns.is_empty() || !custom_regexes.is_empty() {
+        let pii_patterns = into_pii_patterns(compile_patterns(patterns, custom_regexes));
         let tok_result = crate::middleware::pii_vault::tokenize_in_value(
             body,
             &pii_patterns,
@@ -457,6 +519,10 @@ pub fn apply_transform(body: &mut Value, header_mutations: &mut HeaderMutations,
             tracing::info!(path = %path, "transform: remove body field");
             remove_body_field_by_path(body, path);
         }
+        TransformOp::RenameField { from, to } => {
+            tracing::info!(from = %from, to = %to, "transform: rename body field");
+            rename_body_field(body, from, to);
+        }
         TransformOp::AddToMessageList {
             role,
             content,
@@ -638,6 +704,68 @@ fn remove_body_field_by_path(body: &mut Value, path: &str) {
     }
 }
 
+/// Split a body path into segments: a JSON pointer (`/a/b`, with `~1` and
+/// `~0` escapes) or a dot-path (`a.b`).
+fn path_segments(path: &str) -> Vec<String> {
+    match path.strip_prefix('/') {
+        Some(pointer) => pointer
+            .split('/')
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+        None => path.split('.').map(str::to_owned).collect(),
+    }
+}
+
+/// The object holding the last segment of `segments`, following array
+/// indices along the way. With `create`, missing intermediate objects are
+/// added.
+fn parent_object<'a>(
+    body: &'a mut Value,
+    segments: &[String],
+    create: bool,
+) -> Option<&'a mut serde_json::Map<String, Value>> {
+    let (_, parents) = segments.split_last()?;
+    let mut node = body;
+    for segment in parents {
+        if create && node.is_object() && node.get(segment.as_str()).is_none() {
+            node[segment.as_str()] = Value::Object(Default::default());
+        }
+        node = match node {
+            Value::Object(obj) => obj.get_mut(segment)?,
+            Value::Array(items) => items.get_mut(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    node.as_object_mut()
+}
+
+/// Move the value at `from` to `to` (see `TransformOp::RenameField`).
+fn rename_body_field(body: &mut Value, from: &str, to: &str) {
+    let from = path_segments(from);
+    let to = path_segments(to);
+    if from == to || from.iter().chain(&to).any(|s| s.is_empty()) {
+        return;
+    }
+    let Some(value) =
+        parent_object(body, &from, false).and_then(|obj| obj.remove(&from[from.len() - 1]))
+    else {
+        return;
+    };
+    // Creating objects only happens past the last existing node, so a blocked
+    // destination (e.g. through a string) fails before anything is added.
+    match parent_object(body, &to, true) {
+        Some(obj) => {
+            obj.insert(to[to.len() - 1].clone(), value);
+        }
+        None => {
+            tracing::warn!("transform: rename destination unreachable, body unchanged");
+            if let Some(obj) = parent_object(body, &from, false) {
+                obj.insert(from[from.len() - 1].clone(), value);
+            }
+        }
+    }
+}
+
 /// Inject a synthetic message into the `messages` array.
 /// `position`:
 ///   - `"first"` — insert at index 0
@@ -680,6 +808,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({"user": {"email": "alice@example.com", "name": "Alice"}});
         let result = apply_redact(&mut body, &action, true);
@@ -697,6 +826,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({"data": "My SSN is 123-45-6789"});
         let result = apply_redact(&mut body, &action, true);
@@ -713,6 +843,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({
             "from": "user@test.com",
@@ -733,6 +864,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({"passport": "AB123456"});
         let result = apply_redact(&mut body, &action, true);
@@ -749,6 +881,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({
             "users": [
@@ -773,6 +906,7 @@ mod tests {
             fields: vec!["password".to_string(), "secret".to_string()],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({
             "user": "alice",
@@ -795,6 +929,7 @@ mod tests {
             fields: vec!["token".to_string()],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({
             "auth": {"token": "xyz"},
@@ -816,6 +951,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({"email": "a@b.com"});
 
@@ -838,6 +974,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({"data": "SSN: 123-45-6789"});
 
@@ -858,6 +995,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body_req = json!({"email": "a@b.com"});
         let mut body_resp = json!({"email": "c@d.com"});
@@ -981,6 +1119,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({"email": "a@b.com"});
         let result = apply_redact(&mut body, &action, true);
@@ -1008,6 +1147,7 @@ mod tests {
             fields: vec![],
             on_match: RedactOnMatch::Redact,
             nlp_backend: None,
+            custom_regexes: vec![],
         };
         let mut body = json!({"contact": "Call me at 555-123-4567"});
         let result = apply_redact(&mut body, &action, true);
@@ -1285,6 +1425,81 @@ mod tests {
         assert_eq!(body["model"], "gpt-4", "Other fields must be preserved");
     }
 
+    // ── Transform: RenameField ─────────────────────────────────
+
+    fn rename(body: &mut Value, from: &str, to: &str) {
+        let mut mutations = HeaderMutations::default();
+        apply_transform(
+            body,
+            &mut mutations,
+            &TransformOp::RenameField {
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_transform_rename_field_flat_and_nested() {
+        let mut body = json!({"model": "gpt-4o", "system_prompt": "Be terse."});
+        rename(&mut body, "system_prompt", "system");
+        assert_eq!(body, json!({"model": "gpt-4o", "system": "Be terse."}));
+
+        // Dot-path into a new object, and JSON pointer through an array.
+        let mut body = json!({"metadata": {"user": {"id": "u1"}}, "messages": [{"role": "user", "name": "ada"}]});
+        rename(&mut body, "metadata.user.id", "user.id");
+        rename(&mut body, "/messages/0/name", "/messages/0/meta~1author");
+        assert_eq!(
+            body,
+            json!({
+                "metadata": {"user": {}},
+                "user": {"id": "u1"},
+                "messages": [{"role": "user", "meta/author": "ada"}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_transform_rename_field_missing_source_is_noop() {
+        let original = json!({"model": "gpt-4o", "metadata": {"a": 1}});
+        let mut body = original.clone();
+        rename(&mut body, "system_prompt", "system");
+        rename(&mut body, "metadata.b", "b");
+        rename(&mut body, "/messages/3/content", "content");
+        assert_eq!(body, original);
+    }
+
+    #[test]
+    fn test_transform_rename_field_overwrites_destination() {
+        let mut body = json!({"system_prompt": "new", "system": "old"});
+        rename(&mut body, "system_prompt", "system");
+        assert_eq!(body, json!({"system": "new"}));
+
+        // A destination through a scalar can't be reached: nothing moves.
+        let original = json!({"system_prompt": "new", "system": "old"});
+        let mut body = original.clone();
+        rename(&mut body, "system_prompt", "system.text");
+        assert_eq!(body, original);
+    }
+
+    #[test]
+    fn test_transform_rename_field_on_response_body() {
+        let mut body = json!({
+            "id": "chatcmpl-1",
+            "choices": [{"message": {"role": "assistant", "content": "hi"}}],
+            "usage": {"total_tokens": 3}
+        });
+        rename(&mut body, "usage", "x_usage");
+        rename(
+            &mut body,
+            "/choices/0/message/content",
+            "/choices/0/message/text",
+        );
+        assert_eq!(body["x_usage"]["total_tokens"], 3);
+        assert!(body.get("usage").is_none());
+        assert_eq!(body["choices"][0]["message"]["text"], "hi");
+    }
+
     // ── Issue 9: Multi-Provider System Prompt ──
 
     #[test]
@@ -1331,4 +1546,85 @@ mod tests {
         append_system_prompt(&mut body, "You are helpful.");
         assert_eq!(body["system"], "You are helpful.");
     }
+
+    // ── Custom regexes ───────────────────────────────────────
+
+    fn uk_nino_action(on_match: RedactOnMatch) -> Action {
+        serde_json::from_value(json!({
+            "action": "redact",
+            "direction": "request",
+            "patterns": ["email"],
+            "custom_regexes": [
+                {"name": "uk_nino", "pattern": r"\b[A-CEGHJ-PR-TW-Z]{2}\d{6}[A-D]\b"}
+            ],
+            "on_match": on_match,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_custom_regex_uk_nino_redact_mode() {
+        let action = uk_nino_action(RedactOnMatch::Redact);
+        let mut body = json!({"messages": [
+            {"role": "user", "content": "My NI number is JG103759A, email me at a@b.com"}
+        ]});
+        let result = apply_redact(&mut body, &action, true);
+
+        assert_eq!(result.matched_types, vec!["email", "uk_nino"]);
+        assert!(!result.should_block);
+        assert_eq!(
+            body["messages"][0]["content"],
+            "My NI number is [REDACTED_UK_NINO], email me at [REDACTED_EMAIL]"
+        );
+
+        // Not a NINO: the prefix letters D, F, I, Q, U, V are never issued first.
+        let mut clean = json!({"content": "order DQ123456C shipped"});
+        assert!(apply_redact(&mut clean, &action, true)
+            .matched_types
+            .is_empty());
+    }
+
+    #[test]
+    fn test_custom_regex_uk_nino_block_mode() {
+        let action = uk_nino_action(RedactOnMatch::Block);
+        let mut body = json!({"content": "NINO: AB123456D"});
+        let result = apply_redact(&mut body, &action, true);
+
+        assert!(result.should_block);
+        assert_eq!(result.matched_types, vec!["uk_nino"]);
+    }
+
+    #[test]
+    fn test_custom_regex_rejects_invalid_and_oversized() {
+        assert!(validate_custom_regex(r"\b[A-Z]{2}\d{6}\b").is_ok());
+        assert!(validate_custom_regex("(unclosed").is_err());
+        assert!(validate_custom_regex("").is_err());
+        assert!(validate_custom_regex(&"a".repeat(MAX_CUSTOM_REGEX_LEN + 1)).is_err());
+        // Short source, but the compiled automaton exceeds the size limit.
+        assert!(validate_custom_regex(r"\w{1000}").is_err());
+
+        // Rejected entries are skipped; the rest of the action still applies.
+        let action: Action = serde_json::from_value(json!({
+            "action": "redact",
+            "custom_regexes": [
+                {"name": "broken", "pattern": "(unclosed"},
+                {"name": "huge", "pattern": r"\w{1000}"},
+                {"name": "ticket", "pattern": r"TCK-\d+"}
+            ],
+        }))
+        .unwrap();
+        let mut body = json!({"content": "see TCK-42"});
+        let result = apply_redact(&mut body, &action, true);
+        assert_eq!(result.matched_types, vec!["ticket"]);
+        assert_eq!(body["content"], "see [REDACTED_TICKET]");
+    }
+
+    #[test]
+    fn test_custom_regex_compiled_once() {
+        let pattern = r"cache-probe-\d{3}";
+        let first = custom_regex(pattern).unwrap();
+        let second = custom_regex(pattern).unwrap();
+        assert_eq!(first.as_str(), second.as_str());
+        assert!(CUSTOM_REGEX_CACHE.read().unwrap().contains_key(pattern));
+    }
 }
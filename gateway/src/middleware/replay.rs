@@ -0,0 +1,235 @@
+//! Nonce-based duplicate-request rejection.
+//!
+//! Tokens with `replay_window_secs` require every proxied request to carry a
+//! unique `X-TrueFlow-Nonce` and its send time as `X-TrueFlow-Timestamp`
+//! (unix seconds). A request is rejected when the timestamp is further than
+//! the window from the gateway clock, or when its nonce was already used with
+//! the same token. Nonces are claimed in Redis with `SET NX`, so a replay is
+//! caught by any replica. The `X-AILink-*` spellings are accepted as aliases.
+//!
+//! The nonce is not signed or bound to the request's method, path or body.
+//! This stops a captured request from being re-sent verbatim, but anyone
+//! holding the token can mint a fresh nonce and timestamp for any request.
+
+use std::future::Future;
+
+use axum::http::HeaderMap;
+
+/// `X-AILink-Nonce` is the legacy spelling.
+const NONCE_HEADERS: &[&str] = &["x-trueflow-nonce", "x-ailink-nonce"];
+/// `X-AILink-Timestamp` is the legacy spelling.
+const TIMESTAMP_HEADERS: &[&str] = &["x-trueflow-timestamp", "x-ailink-timestamp"];
+
+/// Longest nonce accepted; keeps Redis keys bounded.
+pub const MAX_NONCE_LEN: usize = 128;
+
+/// Largest `replay_window_secs` a token may configure (24 hours).
+pub const MAX_REPLAY_WINDOW_SECS: i32 = 86_400;
+
+/// Why a request failed replay protection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayRejection {
+    /// Nonce or timestamp header absent.
+    MissingHeaders,
+    /// Nonce empty, too long or not printable ASCII, or timestamp not an integer.
+    InvalidHeaders,
+    /// Timestamp outside the window around the gateway clock.
+    StaleTimestamp,
+    /// Nonce already used with this token inside the window.
+    NonceReused,
+}
+
+impl ReplayRejection {
+    /// Error code returned to the client.
+    pub fn code(self) -> &'static str {
+        match self {
+            ReplayRejection::MissingHeaders => "replay_headers_missing",
+            ReplayRejection::InvalidHeaders => "replay_headers_invalid",
+            ReplayRejection::StaleTimestamp => "stale_timestamp",
+            ReplayRejection::NonceReused => "nonce_reused",
+        }
+    }
+}
+
+/// Failure of [`verify`].
+#[derive(Debug)]
+pub enum ReplayError {
+    Rejected(ReplayRejection),
+    /// The nonce store could not be reached; callers fail closed.
+    Store(anyhow::Error),
+}
+
+fn header<'a>(headers: &'a HeaderMap, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|n| headers.get(*n))
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+}
+
+/// Validate the nonce and timestamp headers against `now_secs`, returning
+/// the nonce to claim.
+pub fn check_headers(
+    headers: &HeaderMap,
+    window_secs: u64,
+    now_secs: i64,
+) -> Result<String, ReplayRejection> {
+    let (Some(nonce), Some(timestamp)) = (
+        header(headers, NONCE_HEADERS),
+        header(headers, TIMESTAMP_HEADERS),
+    ) else {
+        return Err(ReplayRejection::MissingHeaders);
+    };
+    if nonce.is_empty()
+        || nonce.len() > MAX_NONCE_LEN
+        || !nonce.bytes().all(|b| b.is_ascii_graphic())
+    {
+        return Err(ReplayRejection::InvalidHeaders);
+    }
+    let timestamp: i64 = timestamp
+        .parse()
+        .map_err(|_| ReplayRejection::InvalidHeaders)?;
+    if now_secs.abs_diff(timestamp) > window_secs {
+        return Err(ReplayRejection::StaleTimestamp);
+    }
+    Ok(nonce.to_string())
+}
+
+/// Redis key recording a used nonce for one token.
+pub fn nonce_key(token_id: &str, nonce: &str) -> String {
+    format!("nonce:{}:{}", token_id, nonce)
+}
+
+/// How long a claimed nonce is remembered. A timestamp is accepted up to
+/// `window_secs` either side of the gateway clock, so a nonce must outlive
+/// two windows before the timestamp check alone rejects its replay.
+pub fn nonce_ttl_secs(window_secs: u64) -> u64 {
+    window_secs.saturating_mul(2)
+}
+
+/// Check the headers, then claim the nonce with `claim(key, ttl_secs)`,
+/// which returns `true` when the key was newly set.
+pub async fn verify<F, Fut>(
+    headers: &HeaderMap,
+    token_id: &str,
+    window_secs: u64,
+    now_secs: i64,
+    claim: F,
+) -> Result<(), ReplayError>
+where
+    F: FnOnce(String, u64) -> Fut,
+    Fut: Future<Output = anyhow::Result<bool>>,
+{
+    let nonce = check_headers(headers, window_secs, now_secs).map_err(ReplayError::Rejected)?;
+    let fresh = claim(nonce_key(token_id, &nonce), nonce_ttl_secs(window_secs))
+        .await
+        .map_err(ReplayError::Store)?;
+    if fresh {
+        Ok(())
+    } else {
+        Err(ReplayError::Rejected(ReplayRejection::NonceReused))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    const NOW: i64 = 1_760_000_000;
+
+    fn signed(nonce: &str, timestamp: i64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-trueflow-nonce", HeaderValue::from_str(nonce).unwrap());
+        headers.insert(
+            "x-trueflow-timestamp",
+            HeaderValue::from_str(&timestamp.to_string()).unwrap(),
+        );
+        headers
+    }
+
+    async fn verify_with(
+        seen: &Mutex<HashSet<String>>,
+        headers: &HeaderMap,
+        now: i64,
+    ) -> Result<(), ReplayRejection> {
+        verify(headers, "tf_v1_tok", 300, now, |key, _ttl| async move {
+            Ok(seen.lock().unwrap().insert(key))
+        })
+        .await
+        .map_err(|e| match e {
+            ReplayError::Rejected(r) => r,
+            ReplayError::Store(e) => panic!("in-memory claim failed: {e}"),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_replayed_nonce_rejected() {
+        let seen = Mutex::new(HashSet::new());
+        let headers = signed("n-1", NOW);
+        assert_eq!(verify_with(&seen, &headers, NOW).await, Ok(()));
+        assert_eq!(
+            verify_with(&seen, &headers, NOW + 5).await,
+            Err(ReplayRejection::NonceReused)
+        );
+        // A fresh nonce on the same token still passes.
+        assert_eq!(verify_with(&seen, &signed("n-2", NOW), NOW).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_stale_timestamp_rejected() {
+        let seen = Mutex::new(HashSet::new());
+        assert_eq!(
+            verify_with(&seen, &signed("old", NOW - 301), NOW).await,
+            Err(ReplayRejection::StaleTimestamp)
+        );
+        assert_eq!(
+            verify_with(&seen, &signed("future", NOW + 301), NOW).await,
+            Err(ReplayRejection::StaleTimestamp)
+        );
+        assert_eq!(
+            verify_with(&seen, &signed("edge", NOW - 300), NOW).await,
+            Ok(())
+        );
+        // Stale requests never claim their nonce.
+        assert_eq!(seen.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_missing_and_invalid_headers() {
+        assert_eq!(
+            check_headers(&HeaderMap::new(), 300, NOW),
+            Err(ReplayRejection::MissingHeaders)
+        );
+        let mut legacy = HeaderMap::new();
+        legacy.insert("x-ailink-nonce", HeaderValue::from_static("abc"));
+        legacy.insert(
+            "x-ailink-timestamp",
+            HeaderValue::from_str(&NOW.to_string()).unwrap(),
+        );
+        assert_eq!(check_headers(&legacy, 300, NOW), Ok("abc".to_string()));
+
+        let long = "a".repeat(MAX_NONCE_LEN + 1);
+        assert_eq!(
+            check_headers(&signed(&long, NOW), 300, NOW),
+            Err(ReplayRejection::InvalidHeaders)
+        );
+        let mut bad_ts = signed("abc", NOW);
+        bad_ts.insert(
+            "x-trueflow-timestamp",
+            HeaderValue::from_static("yesterday"),
+        );
+        assert_eq!(
+            check_headers(&bad_ts, 300, NOW),
+            Err(ReplayRejection::InvalidHeaders)
+        );
+    }
+
+    #[test]
+    fn test_nonce_outlives_both_sides_of_window() {
+        assert_eq!(nonce_ttl_secs(300), 600);
+        assert_eq!(nonce_key("tok", "n"), "nonce:tok:n");
+    }
+}
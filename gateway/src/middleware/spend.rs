@@ -18,6 +18,22 @@ pub struct SpendCap {
     pub lifetime_limit_usd: Option<f64>,
 }
 
+/// Remaining-budget percentage that triggers a token's
+/// `budget_pressure_model_map` when no threshold is set.
+pub const DEFAULT_BUDGET_PRESSURE_PCT: i32 = 20;
+
+/// Accepted values for `tokens.enforcement_order`. `policies_first` (the
+/// default) checks spend caps after the policy loop and rate limits;
+/// `budget_first` checks them before, so an over-budget token is rejected
+/// without touching request or rate-limit counters.
+pub const ENFORCEMENT_ORDERS: [&str; 2] = ["policies_first", "budget_first"];
+
+/// True when a token's `enforcement_order` puts spend caps ahead of the
+/// policy loop. Unset or unrecognised values keep the default order.
+pub fn is_budget_first(enforcement_order: Option<&str>) -> bool {
+    enforcement_order == Some("budget_first")
+}
+
 /// Current spend status for a token (for the API/dashboard).
 #[derive(Debug, Serialize)]
 pub struct SpendStatus {
@@ -29,8 +45,54 @@ pub struct SpendStatus {
     pub current_lifetime_usd: f64,
 }
 
+impl SpendStatus {
+    /// Share of budget left (0.0–1.0) on the tightest configured cap, or
+    /// `None` when the token has no caps.
+    pub fn remaining_fraction(&self) -> Option<f64> {
+        [
+            (self.daily_limit_usd, self.current_daily_usd),
+            (self.monthly_limit_usd, self.current_monthly_usd),
+            (self.lifetime_limit_usd, self.current_lifetime_usd),
+        ]
+        .into_iter()
+        .filter_map(|(limit, current)| limit.filter(|l| *l > 0.0).map(|l| (l - current) / l))
+        .map(|f| f.clamp(0.0, 1.0))
+        .reduce(f64::min)
+    }
+}
+
 // ── Enforcement ───────────────────────────────────────────────
 
+/// A token spend cap that blocked a request. Returned inside the
+/// `anyhow::Error` from [`check_spend_cap`] so callers can report which cap
+/// was hit and when it resets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpendCapExceeded {
+    /// `daily`, `monthly` or `lifetime`.
+    pub period: &'static str,
+    pub limit_usd: f64,
+    pub current_usd: f64,
+}
+
+impl SpendCapExceeded {
+    /// When the cap's counter resets; `None` for lifetime caps.
+    pub fn resets_at(&self) -> Option<chrono::DateTime<Utc>> {
+        (self.period != "lifetime").then(|| next_reset_at(self.period))
+    }
+}
+
+impl std::fmt::Display for SpendCapExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} spend cap of ${:.2} exceeded (current: ${:.4})",
+            self.period, self.limit_usd, self.current_usd
+        )
+    }
+}
+
+impl std::error::Error for SpendCapExceeded {}
+
 /// Check if the token has exceeded its spend cap.
 ///
 /// Reads the current daily and monthly spend from Redis and compares
@@ -69,11 +131,12 @@ pub async fn check_spend_cap(cache: &TieredCache, db: &sqlx::PgPool, token_id: &
             .and_then(|s| s.parse::<f64>().ok())
             .unwrap_or(0.0);
         if current >= daily_limit {
-            anyhow::bail!(
-                "daily spend cap of ${:.2} exceeded (current: ${:.4})",
-                daily_limit,
-                current
-            );
+            return Err(SpendCapExceeded {
+                period: "daily",
+                limit_usd: daily_limit,
+                current_usd: current,
+            }
+            .into());
         }
     }
 
@@ -87,11 +150,12 @@ pub async fn check_spend_cap(cache: &TieredCache, db: &sqlx::PgPool, token_id: &
             .and_then(|s| s.parse::<f64>().ok())
             .unwrap_or(0.0);
         if current >= monthly_limit {
-            anyhow::bail!(
-                "monthly spend cap of ${:.2} exceeded (current: ${:.4})",
-                monthly_limit,
-                current
-            );
+            return Err(SpendCapExceeded {
+                period: "monthly",
+                limit_usd: monthly_limit,
+                current_usd: current,
+            }
+            .into());
         }
     }
 
@@ -105,11 +169,12 @@ pub async fn check_spend_cap(cache: &TieredCache, db: &sqlx::PgPool, token_id: &
             .and_then(|s| s.parse::<f64>().ok())
             .unwrap_or(0.0);
         if current >= lifetime_limit {
-            anyhow::bail!(
-                "lifetime spend cap of ${:.2} exceeded (current: ${:.4})",
-                lifetime_limit,
-                current
-            );
+            return Err(SpendCapExceeded {
+                period: "lifetime",
+                limit_usd: lifetime_limit,
+                current_usd: current,
+            }
+            .into());
         }
     }
 
@@ -497,6 +562,21 @@ pub async fn get_spend_status(
     token_id: &str,
 ) -> Result<SpendStatus> {
     let caps = load_spend_caps(db, token_id).await?;
+    Ok(spend_status_for(cache, token_id, caps).await)
+}
+
+/// Like `get_spend_status`, but reads caps through the 60s Redis cache.
+/// Used on the proxy hot path.
+pub async fn get_spend_status_cached(
+    cache: &TieredCache,
+    db: &sqlx::PgPool,
+    token_id: &str,
+) -> Result<SpendStatus> {
+    let caps = load_spend_caps_cached(cache, db, token_id).await?;
+    Ok(spend_status_for(cache, token_id, caps).await)
+}
+
+async fn spend_status_for(cache: &TieredCache, token_id: &str, caps: SpendCap) -> SpendStatus {
     let mut conn = cache.redis();
     let now = Utc::now();
 
@@ -524,14 +604,156 @@ pub async fn get_spend_status(
         .and_then(|s| s.parse::<f64>().ok())
         .unwrap_or(0.0);
 
-    Ok(SpendStatus {
+    SpendStatus {
         daily_limit_usd: caps.daily_limit_usd,
         monthly_limit_usd: caps.monthly_limit_usd,
         lifetime_limit_usd: caps.lifetime_limit_usd,
         current_daily_usd: daily_spend,
         current_monthly_usd: monthly_spend,
         current_lifetime_usd: lifetime_spend,
-    })
+    }
+}
+
+// ── Simulation ────────────────────────────────────────────────
+
+/// Days of recent history used for "typical daily spend".
+const TYPICAL_SPEND_DAYS: i64 = 7;
+
+/// One window (day, month, or the whole lookback for lifetime) in which a
+/// proposed cap would have been hit.
+#[derive(Debug, Serialize)]
+pub struct CapBreach {
+    /// Window label, e.g. `2026-03-14`, `2026-03` or `lifetime`.
+    pub window: String,
+    /// Start of the hour in which cumulative spend reached the cap.
+    pub breached_at: chrono::DateTime<Utc>,
+    /// Total spend observed in the window (uncapped).
+    pub window_spend_usd: f64,
+}
+
+/// Simulation result for one proposed cap.
+#[derive(Debug, Serialize)]
+pub struct CapSimulation {
+    pub period: &'static str,
+    pub limit_usd: f64,
+    pub breach_count: usize,
+    pub breaches: Vec<CapBreach>,
+}
+
+/// Result of replaying historical spend against proposed caps.
+#[derive(Debug, Serialize)]
+pub struct SpendSimulation {
+    pub lookback_days: i64,
+    pub from: chrono::DateTime<Utc>,
+    pub to: chrono::DateTime<Utc>,
+    pub total_spend_usd: f64,
+    /// Median daily spend over the last 7 days of the lookback, idle days included.
+    pub typical_daily_spend_usd: f64,
+    pub caps: Vec<CapSimulation>,
+    pub warnings: Vec<String>,
+}
+
+/// Window a cost at `at` is counted in for `period` — the same UTC day /
+/// month boundaries as the Redis spend counters.
+fn period_window(period: &str, at: chrono::DateTime<Utc>) -> String {
+    match period {
+        "daily" => at.format("%Y-%m-%d").to_string(),
+        "monthly" => at.format("%Y-%m").to_string(),
+        _ => "lifetime".to_string(),
+    }
+}
+
+/// Replay hourly spend (oldest first) against proposed caps. A breach is
+/// counted once per window, at the hour cumulative spend reached the limit
+/// (enforcement rejects at `current >= limit`). Lifetime spend is counted
+/// from the start of the lookback.
+pub fn simulate_spend_caps(
+    caps: &SpendCap,
+    hourly: &[(chrono::DateTime<Utc>, f64)],
+    lookback_days: i64,
+    now: chrono::DateTime<Utc>,
+) -> SpendSimulation {
+    let proposed = [
+        ("daily", caps.daily_limit_usd),
+        ("monthly", caps.monthly_limit_usd),
+        ("lifetime", caps.lifetime_limit_usd),
+    ];
+    let mut results = Vec::new();
+    for (period, limit) in proposed {
+        let Some(limit) = limit else { continue };
+        let mut breaches: Vec<CapBreach> = Vec::new();
+        let mut window = String::new();
+        let mut total = 0.0;
+        for &(at, cost) in hourly {
+            let w = period_window(period, at);
+            if w != window {
+                window = w;
+                total = 0.0;
+            }
+            let before = total;
+            total += cost;
+            match breaches.last_mut() {
+                Some(b) if b.window == window => b.window_spend_usd = total,
+                _ if before < limit && total >= limit => breaches.push(CapBreach {
+                    window: window.clone(),
+                    breached_at: at,
+                    window_spend_usd: total,
+                }),
+                _ => {}
+            }
+        }
+        results.push(CapSimulation {
+            period,
+            limit_usd: limit,
+            breach_count: breaches.len(),
+            breaches,
+        });
+    }
+
+    // Daily totals for the most recent days, idle days as zero.
+    let today = now.date_naive();
+    let recent_days = TYPICAL_SPEND_DAYS.min(lookback_days.max(1));
+    let mut daily: Vec<f64> = (0..recent_days)
+        .map(|ago| {
+            let day = today - chrono::Duration::days(ago);
+            hourly
+                .iter()
+                .filter(|(at, _)| at.date_naive() == day)
+                .map(|(_, cost)| cost)
+                .sum()
+        })
+        .collect();
+    daily.sort_by(|a, b| a.total_cmp(b));
+    let typical = match daily.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => daily[n / 2],
+        n => (daily[n / 2 - 1] + daily[n / 2]) / 2.0,
+    };
+
+    let mut warnings = Vec::new();
+    if let Some(limit) = caps.daily_limit_usd.filter(|l| *l < typical) {
+        warnings.push(format!(
+            "proposed daily cap ${:.2} is below typical daily spend ${:.2}",
+            limit, typical
+        ));
+    }
+    if let Some(limit) = caps.monthly_limit_usd.filter(|l| *l < typical * 30.0) {
+        warnings.push(format!(
+            "proposed monthly cap ${:.2} is below typical daily spend x 30 (${:.2})",
+            limit,
+            typical * 30.0
+        ));
+    }
+
+    SpendSimulation {
+        lookback_days,
+        from: now - chrono::Duration::days(lookback_days),
+        to: now,
+        total_spend_usd: hourly.iter().map(|(_, cost)| cost).sum(),
+        typical_daily_spend_usd: typical,
+        caps: results,
+        warnings,
+    }
 }
 
 // ── Helpers ───────────────────────────────────────────────────
@@ -585,6 +807,58 @@ async fn update_db_spend(
 mod tests {
     use super::*;
 
+    // ── Enforcement order ─────────────────────────────────────
+
+    #[test]
+    fn test_enforcement_order_defaults_to_policies_first() {
+        assert!(!is_budget_first(None));
+        assert!(!is_budget_first(Some("policies_first")));
+        assert_eq!(ENFORCEMENT_ORDERS[0], "policies_first");
+    }
+
+    #[test]
+    fn test_enforcement_order_budget_first() {
+        assert!(is_budget_first(Some("budget_first")));
+        assert!(ENFORCEMENT_ORDERS.contains(&"budget_first"));
+    }
+
+    #[test]
+    fn test_enforcement_order_unknown_value_keeps_default() {
+        assert!(!is_budget_first(Some("BUDGET_FIRST")));
+        assert!(!is_budget_first(Some("")));
+    }
+
+    // ── SpendStatus::remaining_fraction ───────────────────────
+
+    fn status(daily: Option<(f64, f64)>, monthly: Option<(f64, f64)>) -> SpendStatus {
+        SpendStatus {
+            daily_limit_usd: daily.map(|d| d.0),
+            monthly_limit_usd: monthly.map(|m| m.0),
+            lifetime_limit_usd: None,
+            current_daily_usd: daily.map_or(0.0, |d| d.1),
+            current_monthly_usd: monthly.map_or(0.0, |m| m.1),
+            current_lifetime_usd: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_remaining_fraction_uses_tightest_cap() {
+        // Daily has 50% left, monthly only 10% — monthly wins.
+        let s = status(Some((10.0, 5.0)), Some((100.0, 90.0)));
+        assert!((s.remaining_fraction().unwrap() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_remaining_fraction_none_without_caps() {
+        assert_eq!(status(None, None).remaining_fraction(), None);
+    }
+
+    #[test]
+    fn test_remaining_fraction_clamps_overspend() {
+        let s = status(Some((10.0, 12.5)), None);
+        assert_eq!(s.remaining_fraction(), Some(0.0));
+    }
+
     // ── next_reset_at: real boundary tests ────────────────────
 
     #[test]
@@ -842,4 +1116,49 @@ mod tests {
         assert!(result > 0.0, "Should allow: 9.0 + 0.99 = 9.99 < 10.0");
         assert!((counter - 9.99).abs() < f64::EPSILON);
     }
+
+    // ── simulate_spend_caps ───────────────────────────────────
+
+    #[test]
+    fn test_simulate_counts_one_breach_per_window() {
+        use chrono::TimeZone;
+        let at = |d: u32, h: u32| Utc.with_ymd_and_hms(2026, 3, d, h, 0, 0).unwrap();
+        let hourly = vec![
+            (at(1, 9), 4.0),
+            (at(1, 10), 7.0), // day 1 reaches 10 here
+            (at(1, 11), 3.0),
+            (at(2, 9), 6.0),
+            (at(3, 9), 9.0),
+            (at(3, 12), 1.0), // exactly at the cap counts as hit
+        ];
+        let caps = SpendCap {
+            daily_limit_usd: Some(10.0),
+            monthly_limit_usd: Some(100.0),
+            lifetime_limit_usd: None,
+        };
+        let sim = simulate_spend_caps(&caps, &hourly, 30, at(3, 23));
+
+        assert_eq!(sim.caps.len(), 2);
+        let daily = &sim.caps[0];
+        assert_eq!(daily.breach_count, 2);
+        assert_eq!(daily.breaches[0].window, "2026-03-01");
+        assert_eq!(daily.breaches[0].breached_at, at(1, 10));
+        assert!((daily.breaches[0].window_spend_usd - 14.0).abs() < 1e-9);
+        assert_eq!(daily.breaches[1].breached_at, at(3, 12));
+        assert_eq!(sim.caps[1].breach_count, 0);
+        assert!((sim.total_spend_usd - 30.0).abs() < 1e-9);
+
+        // Last 7 days: 14, 6, 10 and four idle days → median 0.
+        assert_eq!(sim.typical_daily_spend_usd, 0.0);
+        assert!(sim.warnings.is_empty());
+
+        let tight = SpendCap {
+            daily_limit_usd: Some(5.0),
+            ..Default::default()
+        };
+        let sim = simulate_spend_caps(&tight, &hourly, 3, at(3, 23));
+        assert_eq!(sim.typical_daily_spend_usd, 10.0);
+        assert_eq!(sim.caps[0].breach_count, 3);
+        assert_eq!(sim.warnings.len(), 1);
+    }
 }
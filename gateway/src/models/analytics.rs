@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -66,3 +66,436 @@ pub struct ExperimentSummary {
     pub avg_tokens: f64,
     pub error_count: i64,
 }
+
+// ── Experiment variant comparison ─────────────────────────────
+
+/// |z| at or above this is significant at the 95% level (two-sided).
+pub const SIGNIFICANCE_Z: f64 = 1.96;
+
+/// Both sides need this many samples before a difference is called
+/// significant; the normal approximation is unreliable below it.
+pub const MIN_SIGNIFICANCE_SAMPLES: i64 = 30;
+
+/// Per-variant sample statistics for one experiment, from `audit_logs`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct VariantStats {
+    pub variant_name: String,
+    pub requests: i64,
+    pub error_count: i64,
+    pub cost_samples: i64,
+    pub mean_cost_usd: f64,
+    pub cost_stddev: f64,
+    pub latency_samples: i64,
+    pub mean_latency_ms: f64,
+    pub latency_stddev: f64,
+    /// Requests that reported `X-TrueFlow-Feedback-Score`.
+    pub quality_samples: i64,
+    pub mean_quality: Option<f64>,
+    pub quality_stddev: f64,
+}
+
+impl VariantStats {
+    pub fn error_rate(&self) -> f64 {
+        if self.requests > 0 {
+            self.error_count as f64 / self.requests as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// One metric of a variant compared against the baseline variant.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MetricComparison {
+    /// Variant minus baseline.
+    pub delta: f64,
+    /// `delta` relative to the baseline; `None` when the baseline is 0.
+    pub delta_pct: Option<f64>,
+    pub z_score: f64,
+    pub significant: bool,
+}
+
+impl MetricComparison {
+    fn new(variant: f64, baseline: f64, z_score: f64, enough_samples: bool) -> Self {
+        let delta = variant - baseline;
+        Self {
+            delta,
+            delta_pct: (baseline != 0.0).then(|| delta / baseline * 100.0),
+            z_score,
+            significant: enough_samples && z_score.abs() >= SIGNIFICANCE_Z,
+        }
+    }
+
+    /// Welch-style z for a difference in means.
+    fn means(v: (f64, f64, i64), b: (f64, f64, i64)) -> Self {
+        let ((vm, vs, vn), (bm, bs, bn)) = (v, b);
+        let se = if vn > 0 && bn > 0 {
+            (vs * vs / vn as f64 + bs * bs / bn as f64).sqrt()
+        } else {
+            0.0
+        };
+        let z = if se > 0.0 { (vm - bm) / se } else { 0.0 };
+        Self::new(vm, bm, z, vn.min(bn) >= MIN_SIGNIFICANCE_SAMPLES)
+    }
+
+    /// Pooled two-proportion z test.
+    fn proportions(v: (i64, i64), b: (i64, i64)) -> Self {
+        let ((vx, vn), (bx, bn)) = (v, b);
+        let rate = |x: i64, n: i64| if n > 0 { x as f64 / n as f64 } else { 0.0 };
+        let (vp, bp) = (rate(vx, vn), rate(bx, bn));
+        let pooled = rate(vx + bx, vn + bn);
+        let se = if vn > 0 && bn > 0 {
+            (pooled * (1.0 - pooled) * (1.0 / vn as f64 + 1.0 / bn as f64)).sqrt()
+        } else {
+            0.0
+        };
+        let z = if se > 0.0 { (vp - bp) / se } else { 0.0 };
+        Self::new(vp, bp, z, vn.min(bn) >= MIN_SIGNIFICANCE_SAMPLES)
+    }
+}
+
+/// A variant's metrics against the baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantDeltas {
+    pub cost_usd: MetricComparison,
+    pub latency_ms: MetricComparison,
+    pub error_rate: MetricComparison,
+    /// `None` unless both variants have feedback scores.
+    pub quality: Option<MetricComparison>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantComparison {
+    #[serde(flatten)]
+    pub stats: VariantStats,
+    pub error_rate: f64,
+    /// `None` for the baseline itself.
+    pub vs_baseline: Option<VariantDeltas>,
+}
+
+/// Response of `GET /analytics/experiments/:name/compare`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentComparison {
+    pub experiment: String,
+    pub hours: i32,
+    /// Variant the others are compared against: `control` or `baseline` if
+    /// present, otherwise the variant with the most requests.
+    pub baseline: String,
+    pub significance_z: f64,
+    pub min_samples: i64,
+    pub variants: Vec<VariantComparison>,
+}
+
+/// Compare each variant against the baseline variant. `None` when there
+/// is no data.
+pub fn compare_variants(
+    experiment: &str,
+    hours: i32,
+    stats: Vec<VariantStats>,
+) -> Option<ExperimentComparison> {
+    let baseline = stats
+        .iter()
+        .find(|s| s.variant_name == "control" || s.variant_name == "baseline")
+        .or_else(|| stats.iter().max_by_key(|s| s.requests))?
+        .clone();
+
+    let variants = stats
+        .into_iter()
+        .map(|s| {
+            let vs_baseline = (s.variant_name != baseline.variant_name).then(|| VariantDeltas {
+                cost_usd: MetricComparison::means(
+                    (s.mean_cost_usd, s.cost_stddev, s.cost_samples),
+                    (
+                        baseline.mean_cost_usd,
+                        baseline.cost_stddev,
+                        baseline.cost_samples,
+                    ),
+                ),
+                latency_ms: MetricComparison::means(
+                    (s.mean_latency_ms, s.latency_stddev, s.latency_samples),
+                    (
+                        baseline.mean_latency_ms,
+                        baseline.latency_stddev,
+                        baseline.latency_samples,
+                    ),
+                ),
+                error_rate: MetricComparison::proportions(
+                    (s.error_count, s.requests),
+                    (baseline.error_count, baseline.requests),
+                ),
+                quality: s.mean_quality.zip(baseline.mean_quality).map(|(vq, bq)| {
+                    MetricComparison::means(
+                        (vq, s.quality_stddev, s.quality_samples),
+                        (bq, baseline.quality_stddev, baseline.quality_samples),
+                    )
+                }),
+            });
+            VariantComparison {
+                error_rate: s.error_rate(),
+                stats: s,
+                vs_baseline,
+            }
+        })
+        .collect();
+
+    Some(ExperimentComparison {
+        experiment: experiment.to_string(),
+        hours,
+        baseline: baseline.variant_name,
+        significance_z: SIGNIFICANCE_Z,
+        min_samples: MIN_SIGNIFICANCE_SAMPLES,
+        variants,
+    })
+}
+
+// ── Token spend forecast ──────────────────────────────────────
+
+/// Default trailing window for the burn rate.
+pub const DEFAULT_FORECAST_DAYS: u32 = 7;
+
+/// Longest accepted trailing window.
+pub const MAX_FORECAST_DAYS: u32 = 90;
+
+/// A token's spend on one UTC day, from `audit_logs`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DailyCost {
+    pub day: NaiveDate,
+    pub cost_usd: f64,
+}
+
+/// Projected month-end spend for a token at its recent burn rate.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SpendForecast {
+    pub token_id: String,
+    /// Trailing window the daily average is taken over.
+    pub days: u32,
+    /// First and last day of the current (calendar, UTC) billing month.
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    /// Days left in the month after today.
+    pub days_remaining: u32,
+    pub month_to_date_usd: f64,
+    /// `None` when the token has no spend in the window.
+    pub daily_average_usd: Option<f64>,
+    /// Month-to-date plus the daily average for each remaining day.
+    pub projected_month_end_usd: Option<f64>,
+    pub monthly_limit_usd: Option<f64>,
+    /// Projected spend over the monthly cap (0 if under it); `None` without
+    /// a cap or a projection.
+    pub projected_overage_usd: Option<f64>,
+}
+
+/// Project month-end spend from daily costs (any order, days without spend
+/// omitted).
+///
+/// The daily average covers the `days` complete days before `today`. Days
+/// before `created` (the token's creation day) aren't counted, so a token
+/// created mid-window isn't averaged down by days it didn't exist, while
+/// idle days since then still count.
+pub fn forecast_spend(
+    token_id: &str,
+    daily: &[DailyCost],
+    days: u32,
+    today: NaiveDate,
+    created: NaiveDate,
+    monthly_limit_usd: Option<f64>,
+) -> SpendForecast {
+    let period_start = today.with_day(1).unwrap_or(today);
+    let next_month = period_start
+        .checked_add_months(Months::new(1))
+        .unwrap_or(period_start);
+    let period_end = next_month.pred_opt().unwrap_or(today);
+    let days_remaining = (period_end - today).num_days().max(0) as u32;
+
+    let month_to_date_usd: f64 = daily
+        .iter()
+        .filter(|d| d.day >= period_start && d.day <= today)
+        .map(|d| d.cost_usd)
+        .sum();
+
+    let window_start = today - chrono::Duration::days(days as i64);
+    let window: Vec<&DailyCost> = daily
+        .iter()
+        .filter(|d| d.day >= window_start && d.day < today && d.cost_usd > 0.0)
+        .collect();
+    let active_days = (today - window_start.max(created)).num_days().max(1) as f64;
+    let daily_average_usd =
+        (!window.is_empty()).then(|| window.iter().map(|d| d.cost_usd).sum::<f64>() / active_days);
+
+    let projected_month_end_usd =
+        daily_average_usd.map(|avg| month_to_date_usd + avg * days_remaining as f64);
+    let projected_overage_usd = projected_month_end_usd
+        .zip(monthly_limit_usd)
+        .map(|(projected, limit)| (projected - limit).max(0.0));
+
+    SpendForecast {
+        token_id: token_id.to_string(),
+        days,
+        period_start,
+        period_end,
+        days_remaining,
+        month_to_date_usd,
+        daily_average_usd,
+        projected_month_end_usd,
+        monthly_limit_usd,
+        projected_overage_usd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str, n: i64, errors: i64, cost: f64, latency: f64) -> VariantStats {
+        VariantStats {
+            variant_name: name.into(),
+            requests: n,
+            error_count: errors,
+            cost_samples: n,
+            mean_cost_usd: cost,
+            cost_stddev: 0.002,
+            latency_samples: n,
+            mean_latency_ms: latency,
+            latency_stddev: 200.0,
+            quality_samples: 0,
+            mean_quality: None,
+            quality_stddev: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_compare_variants_flags_significant_differences() {
+        let mut treatment = variant("treatment", 500, 40, 0.010, 905.0);
+        treatment.quality_samples = 100;
+        treatment.mean_quality = Some(0.82);
+        treatment.quality_stddev = 0.1;
+        let mut control = variant("control", 400, 8, 0.020, 900.0);
+        control.quality_samples = 80;
+        control.mean_quality = Some(0.80);
+        control.quality_stddev = 0.1;
+
+        let cmp = compare_variants("exp", 720, vec![control, treatment]).unwrap();
+        assert_eq!(cmp.baseline, "control");
+        assert!(cmp.variants[0].vs_baseline.is_none());
+
+        let d = cmp.variants[1].vs_baseline.as_ref().unwrap();
+        // Half the cost: clearly significant.
+        assert!(d.cost_usd.significant);
+        assert_eq!(d.cost_usd.delta_pct.map(|p| p.round()), Some(-50.0));
+        // 5ms on a 200ms spread: noise.
+        assert!(!d.latency_ms.significant);
+        // 8% vs 2% errors over hundreds of requests.
+        assert!(d.error_rate.significant);
+        assert!(d.error_rate.z_score > 0.0);
+        let quality = d.quality.as_ref().unwrap();
+        assert!(!quality.significant);
+    }
+
+    #[test]
+    fn test_compare_variants_needs_samples_and_picks_busiest_baseline() {
+        let cmp = compare_variants(
+            "exp",
+            24,
+            vec![
+                variant("a", 10, 0, 0.01, 100.0),
+                variant("b", 12, 6, 0.05, 900.0),
+            ],
+        )
+        .unwrap();
+        assert_eq!(cmp.baseline, "b");
+        let d = cmp.variants[0].vs_baseline.as_ref().unwrap();
+        assert!(!d.cost_usd.significant && !d.error_rate.significant);
+        assert!(d.quality.is_none());
+
+        assert!(compare_variants("exp", 24, vec![]).is_none());
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 4, d).unwrap()
+    }
+
+    fn cost(d: u32, usd: f64) -> DailyCost {
+        DailyCost {
+            day: day(d),
+            cost_usd: usd,
+        }
+    }
+
+    #[test]
+    fn test_forecast_projects_trailing_average_to_month_end() {
+        // April has 30 days; today is the 10th (partial, $1.50 so far).
+        let daily = vec![
+            cost(1, 1.0),
+            cost(2, 1.0),
+            cost(3, 2.0),
+            cost(4, 2.0),
+            cost(5, 3.0),
+            cost(6, 3.0),
+            cost(7, 4.0),
+            cost(8, 4.0),
+            cost(9, 6.0),
+            cost(10, 1.5),
+        ];
+        let f = forecast_spend("tf_v1_a", &daily, 7, day(10), day(1), Some(80.0));
+
+        assert_eq!((f.period_start, f.period_end), (day(1), day(30)));
+        assert_eq!(f.days_remaining, 20);
+        assert!((f.month_to_date_usd - 27.5).abs() < 1e-9);
+        // Days 3..=9: 2+2+3+3+4+4+6 = 24 over 7 days.
+        let avg = f.daily_average_usd.unwrap();
+        assert!((avg - 24.0 / 7.0).abs() < 1e-9);
+        let projected = f.projected_month_end_usd.unwrap();
+        assert!((projected - (27.5 + 20.0 * 24.0 / 7.0)).abs() < 1e-9);
+        assert!((f.projected_overage_usd.unwrap() - (projected - 80.0)).abs() < 1e-9);
+
+        // Under the cap: no overage.
+        let f = forecast_spend("tf_v1_a", &daily, 7, day(10), day(1), Some(500.0));
+        assert_eq!(f.projected_overage_usd, Some(0.0));
+        // No cap: no overage figure.
+        let f = forecast_spend("tf_v1_a", &daily, 7, day(10), day(1), None);
+        assert_eq!(f.projected_overage_usd, None);
+    }
+
+    #[test]
+    fn test_forecast_sparse_and_empty_history() {
+        // No spend at all: nulls, not NaN.
+        let f = forecast_spend("tf_v1_new", &[], 7, day(10), day(10), Some(50.0));
+        assert_eq!(f.month_to_date_usd, 0.0);
+        assert_eq!(f.daily_average_usd, None);
+        assert_eq!(f.projected_month_end_usd, None);
+        assert_eq!(f.projected_overage_usd, None);
+
+        // Only today's (partial) spend: nothing to average yet.
+        let f = forecast_spend("tf_v1_new", &[cost(10, 5.0)], 7, day(10), day(10), None);
+        assert_eq!(f.month_to_date_usd, 5.0);
+        assert_eq!(f.daily_average_usd, None);
+
+        // Created two days ago: averaged over 2 days, not 7.
+        let f = forecast_spend(
+            "tf_v1_new",
+            &[cost(8, 3.0), cost(9, 1.0)],
+            7,
+            day(10),
+            day(8),
+            None,
+        );
+        assert_eq!(f.daily_average_usd, Some(2.0));
+        assert_eq!(f.projected_month_end_usd, Some(4.0 + 2.0 * 20.0));
+
+        // An old token with sparse spend: idle days in the window count.
+        let f = forecast_spend(
+            "tf_v1_a",
+            &[cost(8, 3.0), cost(9, 4.0)],
+            7,
+            day(10),
+            day(1),
+            None,
+        );
+        assert_eq!(f.daily_average_usd, Some(1.0));
+
+        // Last day of the month: nothing left to project.
+        let f = forecast_spend("tf_v1_a", &[cost(29, 3.0)], 7, day(30), day(1), None);
+        assert_eq!(f.days_remaining, 0);
+        assert_eq!(f.projected_month_end_usd, Some(3.0));
+    }
+}
@@ -24,4 +24,6 @@ pub enum ApprovalStatus {
     Approved,
     Rejected,
     Expired,
+    /// The waiting client disconnected before a decision.
+    Abandoned,
 }
@@ -59,8 +59,11 @@ pub struct AuditEntry {
     pub finish_reason: Option<String>,
     /// Session ID for grouping conversations (from X-Session-ID)
     pub session_id: Option<String>,
-    /// Parent span ID for nested calls (from X-Parent-Span-ID)
+    /// Parent span ID for nested calls (from `traceparent` or X-Parent-Span-ID)
     pub parent_span_id: Option<String>,
+    /// W3C trace ID from the client's `traceparent` header
+    #[serde(default)]
+    pub trace_id: Option<String>,
     /// Classified error type (rate_limit, context_too_long, etc.)
     pub error_type: Option<String>,
     /// Whether this was a streaming (SSE) response
@@ -69,6 +72,94 @@ pub struct AuditEntry {
     pub ttft_ms: Option<u64>,
     /// Whether this response was served from cache
     pub cache_hit: bool,
+    /// Provider response could not be translated to OpenAI format and the
+    /// raw provider body was returned instead.
+    #[serde(default)]
+    pub translation_fallback: bool,
+    /// Effective provider used for translation and pricing (e.g. `anthropic`).
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// `provider` came from the token's `provider_hint` rather than detection.
+    #[serde(default)]
+    pub provider_hinted: bool,
+    /// `X-Properties` keys missing or outside the allowed set (RequireProperties action).
+    #[serde(default)]
+    pub missing_properties: Option<Vec<String>>,
+    /// Token param_defaults keys filled into the request body because the client omitted them.
+    #[serde(default)]
+    pub param_defaults_applied: Option<Vec<String>>,
+    /// Request body fields removed by the token's strip_body_fields list.
+    #[serde(default)]
+    pub body_fields_stripped: Option<Vec<String>>,
+    /// Originally requested model when budget pressure swapped in a cheaper one.
+    #[serde(default)]
+    pub model_downgraded_from: Option<String>,
+    /// Deprecated model the client sent when `deprecated_model_map` rewrote it.
+    #[serde(default)]
+    pub model_remapped_from: Option<String>,
+    /// Upstream URL that replaced the token's upstream via test_upstream_override.
+    #[serde(default)]
+    pub test_upstream_override: Option<String>,
+    /// Estimated prompt + max_tokens from the context-window pre-flight check.
+    #[serde(default)]
+    pub context_estimated_tokens: Option<u32>,
+    /// Context window the request was checked against.
+    #[serde(default)]
+    pub context_window_tokens: Option<u32>,
+    /// Oldest messages removed to fit the context window (trim action).
+    #[serde(default)]
+    pub context_messages_trimmed: Option<u32>,
+    /// Client-reported quality score from `X-TrueFlow-Feedback-Score`.
+    #[serde(default)]
+    pub feedback_score: Option<f64>,
+    /// Characters of content received before a streaming response was cut off
+    /// mid-stream (set together with `error_type: "stream_interrupted"`).
+    #[serde(default)]
+    pub partial_content_len: Option<u32>,
+    /// Pre-flight policy timings (budget, total and per-action ms), recorded
+    /// when evaluation overran `TRUEFLOW_POLICY_EVAL_BUDGET_MS`.
+    #[serde(default)]
+    pub policy_eval_timings: Option<serde_json::Value>,
+    /// Token `migration` split: `target` when the request went to the migration
+    /// target credential/upstream, `current` when it stayed; None without a migration.
+    #[serde(default)]
+    pub migration_path: Option<String>,
+    /// Changes a `coerce_response_schema` policy made to the response, e.g.
+    /// `/score: string -> number`.
+    #[serde(default)]
+    pub schema_coercions: Option<Vec<String>>,
+    /// Output-token ceiling applied to the request: `{"ceiling", "clamped": {field: requested}}`
+    /// or `{"ceiling", "injected": field}` when the client sent no limit.
+    #[serde(default)]
+    pub max_tokens_clamp: Option<serde_json::Value>,
+    /// A stale cached response was served because the upstream failed
+    /// (token `serve_stale_on_error`).
+    #[serde(default)]
+    pub stale_cache_served: bool,
+    /// Pre-flight worst-case cost estimate (prompt plus reserved output),
+    /// recorded for tokens with `max_cost_per_request_usd`.
+    #[serde(default)]
+    pub request_cost_estimate_usd: Option<rust_decimal::Decimal>,
+    /// The request's actual cost exceeded the token's `max_cost_per_request_usd`.
+    /// Flagged after the fact, e.g. when no output limit bounded the estimate.
+    #[serde(default)]
+    pub request_cost_cap_exceeded: bool,
+    /// Stored credential the upstream rejected with 401/403 (invalid,
+    /// expired or revoked key).
+    #[serde(default)]
+    pub rejected_credential_id: Option<Uuid>,
+    /// `response_format: json_object` was emulated with a system instruction
+    /// and JSON extraction because the provider has no native JSON mode.
+    #[serde(default)]
+    pub json_mode_emulated: bool,
+    /// Features and image/audio parts removed by the token's `capability_action:
+    /// strip`: `{"features": [...], "parts": [{message, part, kind, source}]}`.
+    #[serde(default)]
+    pub capability_stripped: Option<serde_json::Value>,
+    /// The streamed response carried no usage, so `prompt_tokens`,
+    /// `completion_tokens` and the cost are estimates.
+    #[serde(default)]
+    pub tokens_estimated: bool,
     // ── A/B Experiment Tracking (Split action) ───────────────────
     /// Experiment name from the Split policy action (for grouping in analytics).
     pub experiment_name: Option<String>,
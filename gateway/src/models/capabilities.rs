@@ -0,0 +1,273 @@
+//! Per-model capability table: which request features a model accepts.
+//!
+//! Used by the token `capability_action` pre-flight check (see
+//! [`crate::middleware::capability`]) to catch `tools`, image inputs or
+//! `response_format` and image or audio inputs sent to a model that would
+//! reject them with a 400.
+//!
+//! Capabilities come from a built-in table, overridable through the
+//! `model_capabilities` system setting (`PUT /settings`). The setting maps a
+//! provider name (`openai`, `anthropic`, `ollama`, ..., or `*` for any) to
+//! model names or prefixes, each with the capabilities that differ from the
+//! defaults (everything supported, no context limit):
+//!
+//! ```json
+//! {"*": {"my-finetune": {"supports_tools": false, "max_context": 32768}},
+//!  "ollama": {"*": {"supports_tools": false}, "llama3.1": {"supports_tools": true}}}
+//! ```
+//!
+//! The longest matching prefix wins (`*` matches every model of a provider),
+//! provider-specific entries beat `*` entries of the same length, and any
+//! override beats the built-ins. Reloaded with the other settings caches
+//! every 60s by a background job in `main.rs`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::store::postgres::PgStore;
+
+/// `system_settings` key holding capability overrides.
+pub const SETTING_KEY: &str = "model_capabilities";
+
+/// Upper bound on override entries across all providers.
+pub const MAX_ENTRIES: usize = 1_000;
+
+/// Provider key (and model key) matching anything.
+const WILDCARD: &str = "*";
+
+/// What a model accepts. Unset fields in an override mean "supported".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelCapabilities {
+    #[serde(default = "supported")]
+    pub supports_tools: bool,
+    #[serde(default = "supported")]
+    pub supports_vision: bool,
+    #[serde(default = "supported")]
+    pub supports_json_mode: bool,
+    /// Audio input parts (`input_audio`).
+    #[serde(default = "supported")]
+    pub supports_audio: bool,
+    /// Context window in tokens; takes precedence over `model_context_windows`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_context: Option<u32>,
+}
+
+fn supported() -> bool {
+    true
+}
+
+impl Default for ModelCapabilities {
+    fn default() -> Self {
+        FULL
+    }
+}
+
+const FULL: ModelCapabilities = caps(true, true, true, true);
+
+/// Everything but audio input — most current chat models.
+const NO_AUDIO: ModelCapabilities = caps(true, true, true, false);
+
+const fn caps(tools: bool, vision: bool, json_mode: bool, audio: bool) -> ModelCapabilities {
+    ModelCapabilities {
+        supports_tools: tools,
+        supports_vision: vision,
+        supports_json_mode: json_mode,
+        supports_audio: audio,
+        max_context: None,
+    }
+}
+
+/// Built-in capabilities by model-name prefix (longest prefix wins). Only
+/// models with known gaps need an entry; more specific prefixes re-enable
+/// features for newer models under the same family name.
+const DEFAULT_CAPABILITIES: &[(&str, ModelCapabilities)] = &[
+    ("gpt-4.1", NO_AUDIO),
+    ("gpt-4o", NO_AUDIO),
+    ("gpt-4o-audio", FULL),
+    ("gpt-4o-mini-audio", FULL),
+    ("gpt-4-turbo", NO_AUDIO),
+    ("gpt-4", caps(true, false, false, false)),
+    ("gpt-3.5-turbo", caps(true, false, true, false)),
+    ("o1-mini", caps(false, false, false, false)),
+    ("o1-preview", caps(false, false, false, false)),
+    ("o1", NO_AUDIO),
+    ("o3-mini", caps(true, false, true, false)),
+    ("o3", NO_AUDIO),
+    ("o4-mini", NO_AUDIO),
+    // Anthropic has no response_format; see `json_mode_fallback`.
+    ("claude", caps(true, true, false, false)),
+    ("claude-2", caps(false, false, false, false)),
+    ("claude-instant", caps(false, false, false, false)),
+    ("deepseek-reasoner", caps(false, false, false, false)),
+];
+
+/// One `model_capabilities` override.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityOverride {
+    /// `None` for the `*` provider.
+    pub provider: Option<String>,
+    /// Model name or prefix; empty for the `*` model.
+    pub prefix: String,
+    pub capabilities: ModelCapabilities,
+}
+
+/// Shared, cheaply-cloneable capability table (overrides + built-ins).
+#[derive(Clone)]
+pub struct CapabilityTable(Arc<RwLock<Vec<CapabilityOverride>>>);
+
+impl Default for CapabilityTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CapabilityTable {
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(Vec::new())))
+    }
+
+    /// Replace the override table.
+    pub async fn replace(&self, overrides: Vec<CapabilityOverride>) {
+        *self.0.write().await = overrides;
+    }
+
+    /// Reload overrides from `system_settings`. An absent setting clears
+    /// them; an unreadable one keeps the previous overrides.
+    pub async fn reload(&self, db: &PgStore) {
+        match db.get_system_setting::<Value>(SETTING_KEY).await {
+            Ok(value) => match value.as_ref().map(parse_table).transpose() {
+                Ok(overrides) => self.replace(overrides.unwrap_or_default()).await,
+                Err(e) => tracing::error!("capabilities: invalid {}: {}", SETTING_KEY, e),
+            },
+            Err(e) => tracing::error!("capabilities: reload failed: {}", e),
+        }
+    }
+
+    /// Capabilities of `model` served by `provider`: the best matching
+    /// override, else the built-in table. `None` for unknown models.
+    pub async fn lookup(&self, provider: &str, model: &str) -> Option<ModelCapabilities> {
+        let overrides = self.0.read().await;
+        overrides
+            .iter()
+            .filter(|o| o.provider.as_deref().is_none_or(|p| p == provider))
+            .filter(|o| model.starts_with(o.prefix.as_str()))
+            .max_by_key(|o| (o.prefix.len(), o.provider.is_some()))
+            .map(|o| o.capabilities)
+            .or_else(|| {
+                DEFAULT_CAPABILITIES
+                    .iter()
+                    .filter(|(prefix, _)| model.starts_with(prefix))
+                    .max_by_key(|(prefix, _)| prefix.len())
+                    .map(|(_, caps)| *caps)
+            })
+    }
+}
+
+/// Validate a `model_capabilities` setting value: an object of provider
+/// (or `*`) to an object of model name/prefix (or `*`) to capabilities.
+pub fn parse_table(value: &Value) -> Result<Vec<CapabilityOverride>, String> {
+    let providers = value
+        .as_object()
+        .ok_or("must be an object of provider -> model -> capabilities")?;
+    let mut overrides = Vec::new();
+    for (provider, models) in providers {
+        if provider.trim().is_empty() {
+            return Err("provider names must be non-empty (use \"*\" for any)".into());
+        }
+        let models = models
+            .as_object()
+            .ok_or_else(|| format!("'{}' must be an object of model -> capabilities", provider))?;
+        for (model, capabilities) in models {
+            if model.trim().is_empty() {
+                return Err("model names must be non-empty (use \"*\" for any)".into());
+            }
+            let capabilities: ModelCapabilities = serde_json::from_value(capabilities.clone())
+                .map_err(|e| format!("capabilities for '{}': {}", model, e))?;
+            if capabilities.max_context == Some(0) {
+                return Err(format!("max_context for '{}' must be positive", model));
+            }
+            overrides.push(CapabilityOverride {
+                provider: (provider != WILDCARD).then(|| provider.clone()),
+                prefix: if model == WILDCARD {
+                    String::new()
+                } else {
+                    model.clone()
+                },
+                capabilities,
+            });
+        }
+    }
+    if overrides.len() > MAX_ENTRIES {
+        return Err(format!("too many entries (max {})", MAX_ENTRIES));
+    }
+    Ok(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_builtin_lookup_uses_longest_prefix() {
+        let table = CapabilityTable::new();
+        assert_eq!(table.lookup("openai", "gpt-4o-mini").await, Some(NO_AUDIO));
+        let audio = table
+            .lookup("openai", "gpt-4o-audio-preview")
+            .await
+            .unwrap();
+        assert!(audio.supports_audio);
+        let gpt4 = table.lookup("openai", "gpt-4-0613").await.unwrap();
+        assert!(gpt4.supports_tools && !gpt4.supports_vision && !gpt4.supports_json_mode);
+        let o1_mini = table.lookup("openai", "o1-mini-2024-09-12").await.unwrap();
+        assert!(!o1_mini.supports_tools);
+        let sonnet = table
+            .lookup("anthropic", "claude-3-5-sonnet-latest")
+            .await
+            .unwrap();
+        assert!(sonnet.supports_vision && !sonnet.supports_json_mode);
+        assert_eq!(table.lookup("openai", "my-finetune").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_overrides_are_provider_scoped() {
+        let table = CapabilityTable::new();
+        let overrides = parse_table(&json!({
+            "*": {"my-finetune": {"supports_tools": false, "max_context": 32768}},
+            "ollama": {"*": {"supports_tools": false}, "llama3.1": {}},
+        }))
+        .unwrap();
+        table.replace(overrides).await;
+
+        let finetune = table.lookup("openai", "my-finetune-v2").await.unwrap();
+        assert!(!finetune.supports_tools && finetune.supports_vision);
+        assert_eq!(finetune.max_context, Some(32_768));
+
+        let ollama_default = table.lookup("ollama", "mistral").await.unwrap();
+        assert!(!ollama_default.supports_tools);
+        let ollama_llama = table.lookup("ollama", "llama3.1:8b").await.unwrap();
+        assert!(ollama_llama.supports_tools);
+        // A provider wildcard doesn't leak to other providers, and beats the
+        // built-ins for that provider only.
+        assert_eq!(table.lookup("groq", "mistral").await, None);
+        let ollama_gpt4 = table.lookup("ollama", "gpt-4").await.unwrap();
+        assert!(!ollama_gpt4.supports_tools);
+        let openai_gpt4 = table.lookup("openai", "gpt-4").await.unwrap();
+        assert!(openai_gpt4.supports_tools && !openai_gpt4.supports_vision);
+    }
+
+    #[test]
+    fn test_parse_table_rejects_bad_values() {
+        assert!(parse_table(&json!({"*": {"gpt-4o": {"supports_vision": true}}})).is_ok());
+        assert!(parse_table(&json!({"*": {"gpt-4o": {"supports_audio": true}}})).is_ok());
+        assert!(parse_table(&json!({"gpt-4o": true})).is_err());
+        assert!(parse_table(&json!({"*": {"gpt-4o": {"supports_video": false}}})).is_err());
+        assert!(parse_table(&json!({"*": {"gpt-4o": {"supports_tools": "no"}}})).is_err());
+        assert!(parse_table(&json!({"*": {"gpt-4o": {"max_context": 0}}})).is_err());
+        assert!(parse_table(&json!({"*": {" ": {}}})).is_err());
+        assert!(parse_table(&json!({"": {"gpt-4o": {}}})).is_err());
+    }
+}
@@ -26,6 +26,30 @@ pub fn extract_usage(_upstream_url: &str, body: &[u8]) -> anyhow::Result<Option<
         if input > 0 || output > 0 {
             return Ok(Some((input, output)));
         }
+
+        // Embeddings APIs that only report a total (e.g. Voyage): all input.
+        let total = usage
+            .get("total_tokens")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+        if total > 0 {
+            return Ok(Some((total, 0)));
+        }
+    }
+
+    // Cohere (embed, rerank): meta.billed_units
+    if let Some(billed) = json.pointer("/meta/billed_units") {
+        let input = billed
+            .get("input_tokens")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+        let output = billed
+            .get("output_tokens")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+        if input > 0 || output > 0 {
+            return Ok(Some((input, output)));
+        }
     }
 
     // Gemini: usageMetadata
@@ -65,6 +89,29 @@ pub fn extract_model(body: &[u8]) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// Map a token's upstream URL to the provider key used by the pricing table.
+pub fn provider_from_upstream_url(upstream_url: &str) -> &'static str {
+    if upstream_url.contains("anthropic") && !upstream_url.contains("bedrock") {
+        "anthropic"
+    } else if upstream_url.contains("generativelanguage") || upstream_url.contains("googleapis") {
+        "google"
+    } else if upstream_url.contains("mistral") {
+        "mistral"
+    } else if upstream_url.contains("bedrock") {
+        "bedrock"
+    } else if upstream_url.contains("groq") {
+        "groq"
+    } else if upstream_url.contains("cohere") {
+        "cohere"
+    } else if upstream_url.contains("together") {
+        "together"
+    } else if upstream_url.contains("localhost:11434") || upstream_url.contains("ollama") {
+        "ollama"
+    } else {
+        "openai"
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ModelPricing {
     pub input_cost_per_m: Decimal,
@@ -344,6 +391,141 @@ pub async fn calculate_cost_with_cache(
     input_cost + output_cost
 }
 
+/// An image generation request, priced per image rather than per token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRequest {
+    pub model: String,
+    pub size: String,
+    pub quality: String,
+    pub n: u32,
+}
+
+impl ImageRequest {
+    /// Parse a `/images/generations` request body, with OpenAI's defaults
+    /// for omitted params. `None` for other endpoints.
+    pub fn from_request(path: &str, body: Option<&Value>) -> Option<Self> {
+        if !path.trim_end_matches('/').ends_with("/images/generations") {
+            return None;
+        }
+        let field = |key: &str, default: &str| {
+            body.and_then(|b| b.get(key))
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        let n = body
+            .and_then(|b| b.get("n"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .clamp(1, u32::MAX as u64) as u32;
+        Some(Self {
+            model: field("model", "dall-e-2"),
+            size: field("size", "1024x1024"),
+            // dall-e-3 calls it `standard`, gpt-image-1 `auto`.
+            quality: field("quality", "standard"),
+            n,
+        })
+    }
+
+    /// `model/quality/size`, matched against `model_pattern` for per-image
+    /// prices, e.g. `dall-e-3/hd/1024x1792`.
+    pub fn pricing_key(&self) -> String {
+        format!("{}/{}/{}", self.model, self.quality, self.size)
+    }
+}
+
+/// Hardcoded per-image prices (USD), keyed like [`ImageRequest::pricing_key`].
+/// Longest matching pattern wins. Prices as of 2025-02.
+const IMAGE_PRICES_FALLBACK: &[(&str, &str)] = &[
+    ("dall-e-3/standard/1024x1024", "0.040"),
+    ("dall-e-3/standard/", "0.080"),
+    ("dall-e-3/hd/1024x1024", "0.080"),
+    ("dall-e-3/hd/", "0.120"),
+    ("dall-e-2/", "0.020"),
+    ("dall-e-2/standard/512x512", "0.018"),
+    ("dall-e-2/standard/256x256", "0.016"),
+];
+
+fn image_price_fallback(key: &str) -> Option<Decimal> {
+    IMAGE_PRICES_FALLBACK
+        .iter()
+        .filter(|(pattern, _)| key.contains(pattern))
+        .max_by_key(|(pattern, _)| pattern.len())
+        .map(|(_, price)| Decimal::from_str(price).unwrap())
+}
+
+/// Cost of an image generation request: the per-image price times `n`.
+/// `None` when no price is known for the model, size and quality.
+pub async fn calculate_image_cost_with_cache(
+    pricing: &crate::models::pricing_cache::PricingCache,
+    provider: &str,
+    request: &ImageRequest,
+) -> Option<Decimal> {
+    let key = request.pricing_key();
+    let per_image = match pricing.lookup_image(provider, &key).await {
+        Some(price) => price,
+        None => image_price_fallback(&key)?,
+    };
+    Some(per_image * Decimal::from(request.n))
+}
+
+/// Rough pre-flight token estimate for a request, used by the
+/// `request.estimated_cost_usd` policy field before any usage is known.
+///
+/// Input is ~4 bytes per token of the raw body (an overestimate for JSON,
+/// which errs on the side of gating). Output is the client's declared
+/// completion limit, or 0 when it sets none.
+pub fn estimate_request_tokens(body_size: usize, body: Option<&Value>) -> (u32, u32) {
+    let input = (body_size / 4).min(u32::MAX as usize) as u32;
+    let output = body
+        .and_then(|b| {
+            ["max_tokens", "max_completion_tokens", "max_output_tokens"]
+                .iter()
+                .find_map(|k| b.get(*k).and_then(|v| v.as_u64()))
+        })
+        .map(|n| n.min(u32::MAX as u64) as u32)
+        .unwrap_or(0);
+    (input, output)
+}
+
+/// Worst-case pre-flight cost of a request, for the per-token
+/// `max_cost_per_request_usd` ceiling: the estimated prompt plus the full
+/// output the client reserved. The flag is `false` when the request sets no
+/// output limit, in which case only the prompt is priced.
+pub async fn estimate_request_cost(
+    pricing: &crate::models::pricing_cache::PricingCache,
+    provider: &str,
+    model: &str,
+    body: &Value,
+) -> (Decimal, bool) {
+    let prompt = crate::models::tokenizer::estimate_prompt_tokens(body);
+    let output = crate::models::tokenizer::requested_output_tokens(body);
+    let cost = calculate_cost_with_cache(pricing, provider, model, prompt, output).await;
+    (cost, output > 0)
+}
+
+/// Estimate per-tool-call tokens and attribute output-token cost to each call.
+///
+/// Token counts are approximations (see [`crate::models::llm::estimate_tool_call_tokens`]);
+/// cost uses the model's output price since tool calls are generated tokens.
+pub async fn attribute_tool_call_costs(
+    pricing: &crate::models::pricing_cache::PricingCache,
+    provider: &str,
+    model: &str,
+    tool_calls: &mut [crate::models::llm::ToolCallInfo],
+    completion_tokens: Option<u32>,
+) {
+    if tool_calls.is_empty() {
+        return;
+    }
+    crate::models::llm::attribute_tool_call_tokens(tool_calls, completion_tokens);
+    for tc in tool_calls.iter_mut() {
+        let tokens = tc.estimated_tokens.unwrap_or(0);
+        let cost = calculate_cost_with_cache(pricing, provider, model, 0, tokens).await;
+        tc.estimated_cost_usd = rust_decimal::prelude::ToPrimitive::to_f64(&cost);
+    }
+}
+
 /// Synchronous version kept for backwards compatibility with non-async call sites.
 /// Uses only the hardcoded fallback table.
 #[allow(dead_code)]
@@ -366,6 +548,34 @@ pub fn calculate_cost(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_estimate_request_tokens() {
+        let body = serde_json::json!({"model": "gpt-4o", "max_tokens": 512});
+        assert_eq!(estimate_request_tokens(400, Some(&body)), (100, 512));
+        let body = serde_json::json!({"max_completion_tokens": 64});
+        assert_eq!(estimate_request_tokens(3, Some(&body)), (0, 64));
+        assert_eq!(estimate_request_tokens(40, None), (10, 0));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_request_cost_prices_reserved_output() {
+        let pricing = crate::models::pricing_cache::PricingCache::new();
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "hi"}],
+            "max_tokens": 100_000,
+        });
+        let (cost, bounded) = estimate_request_cost(&pricing, "openai", "gpt-4o", &body).await;
+        assert!(bounded);
+        // 100k output tokens at $10/M dominate the tiny prompt.
+        assert!(cost > Decimal::ONE && cost < Decimal::from_str("1.01").unwrap());
+
+        let body = serde_json::json!({"messages": [{"role": "user", "content": "hi"}]});
+        let (cost, bounded) = estimate_request_cost(&pricing, "openai", "gpt-4o", &body).await;
+        assert!(!bounded);
+        assert!(cost > Decimal::ZERO && cost < Decimal::from_str("0.001").unwrap());
+    }
+
     // ── Pricing match-order tests (BUG-3 regression) ──────────
 
     #[test]
@@ -492,6 +702,72 @@ mod tests {
         assert_eq!(result, Some((300, 120)));
     }
 
+    #[test]
+    fn test_extract_usage_embeddings() {
+        // OpenAI embeddings: prompt tokens only, billed at the input rate.
+        let body = r#"{"object":"list","data":[{"object":"embedding","index":0,"embedding":[0.1,0.2]}],"model":"text-embedding-3-small","usage":{"prompt_tokens":8,"total_tokens":8}}"#;
+        let (input, output) = extract_usage("https://api.openai.com", body.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!((input, output), (8, 0));
+        assert_eq!(
+            calculate_cost("openai", "text-embedding-3-small", input, output),
+            Decimal::from_str("0.00000016").unwrap()
+        );
+
+        let total_only = r#"{"data":[],"usage":{"total_tokens":12}}"#;
+        let result = extract_usage("https://api.voyageai.com", total_only.as_bytes()).unwrap();
+        assert_eq!(result, Some((12, 0)));
+
+        let cohere = r#"{"embeddings":[[0.1]],"meta":{"billed_units":{"input_tokens":5}}}"#;
+        let result = extract_usage("https://api.cohere.com", cohere.as_bytes()).unwrap();
+        assert_eq!(result, Some((5, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_image_request_priced_per_image() {
+        let pricing = crate::models::pricing_cache::PricingCache::new();
+        let body = serde_json::json!({
+            "model": "dall-e-3",
+            "prompt": "a lighthouse at dusk",
+            "size": "1792x1024",
+            "quality": "hd",
+            "n": 2
+        });
+        let request = ImageRequest::from_request("/v1/images/generations", Some(&body)).unwrap();
+        assert_eq!(request.pricing_key(), "dall-e-3/hd/1792x1024");
+        let cost = calculate_image_cost_with_cache(&pricing, "openai", &request).await;
+        assert_eq!(cost, Some(Decimal::from_str("0.240").unwrap()));
+
+        // OpenAI defaults: dall-e-2, 1024x1024, one image.
+        let defaults = ImageRequest::from_request("/v1/images/generations", None).unwrap();
+        let cost = calculate_image_cost_with_cache(&pricing, "openai", &defaults).await;
+        assert_eq!(cost, Some(Decimal::from_str("0.020").unwrap()));
+
+        // A DB price beats the built-in table; unknown models stay unpriced.
+        pricing
+            .reload(vec![crate::models::pricing_cache::PricingEntry {
+                provider: "openai".to_string(),
+                model_pattern: "dall-e-3/hd".to_string(),
+                input_per_m: Decimal::ZERO,
+                output_per_m: Decimal::ZERO,
+                image_per_unit: Some(Decimal::from_str("0.100").unwrap()),
+            }])
+            .await;
+        let cost = calculate_image_cost_with_cache(&pricing, "openai", &request).await;
+        assert_eq!(cost, Some(Decimal::from_str("0.200").unwrap()));
+        let other = ImageRequest {
+            model: "my-diffusion".to_string(),
+            ..defaults
+        };
+        assert_eq!(
+            calculate_image_cost_with_cache(&pricing, "openai", &other).await,
+            None
+        );
+
+        assert!(ImageRequest::from_request("/v1/chat/completions", Some(&body)).is_none());
+    }
+
     #[test]
     fn test_extract_usage_no_usage() {
         let body = r#"{"choices":[{"message":{"content":"hello"}}]}"#;
@@ -1,16 +1,95 @@
 //! In-memory latency cache backed by `audit_logs`.
 //!
-//! Stores the p50 response latency per model, refreshed every 5 minutes
-//! by a background job in `main.rs`. Used by the smart router's
-//! `lowest_latency` strategy to rank candidates.
+//! Stores the p50/p95/p99 response latency per model (and per model/upstream
+//! pair), refreshed every 5 minutes by a background job in `main.rs`. Used by
+//! the smart router's `lowest_latency` strategy to rank candidates and exposed
+//! at `GET /api/v1/analytics/latency/by-model`.
+//!
+//! Each refresh is persisted to `model_latency_snapshots`, and the cache is
+//! seeded from that table at startup so routing has data from the first
+//! request instead of waiting for the first audit-log aggregation.
 
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Distributions built from fewer samples than this are flagged `low_sample`.
+pub const MIN_SAMPLES: i64 = 20;
+
+/// Observed latency distribution for one model, optionally on one upstream.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyDistribution {
+    pub model: String,
+    /// `None` for the all-upstreams rollup that routing uses.
+    pub upstream_url: Option<String>,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub sample_count: i64,
+    /// Fewer than [`MIN_SAMPLES`] samples; percentiles are unreliable.
+    pub low_sample: bool,
+}
+
+/// Point-in-time view of the cache, as served by the analytics endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencySnapshot {
+    /// When the distributions were computed. `None` before the first load.
+    pub captured_at: Option<DateTime<Utc>>,
+    /// `"audit_logs"` after a live refresh, `"snapshot"` when seeded at
+    /// startup, `"none"` before either.
+    pub source: &'static str,
+    pub min_samples: i64,
+    pub distributions: Vec<LatencyDistribution>,
+}
+
+struct Inner {
+    /// Rollup distribution per model, keyed by model name.
+    by_model: HashMap<String, LatencyDistribution>,
+    /// Per model/upstream distributions.
+    by_upstream: Vec<LatencyDistribution>,
+    captured_at: Option<DateTime<Utc>>,
+    source: &'static str,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            by_model: HashMap::new(),
+            by_upstream: Vec::new(),
+            captured_at: None,
+            source: "none",
+        }
+    }
+}
+
+impl Inner {
+    fn from_rows(rows: Vec<LatencyRow>, captured_at: DateTime<Utc>, source: &'static str) -> Self {
+        let mut inner = Inner {
+            captured_at: Some(captured_at),
+            source,
+            ..Default::default()
+        };
+        for row in rows {
+            let dist = row.into_distribution();
+            if dist.upstream_url.is_none() {
+                inner.by_model.insert(dist.model.clone(), dist);
+            } else {
+                inner.by_upstream.push(dist);
+            }
+        }
+        inner
+    }
+
+    fn is_empty(&self) -> bool {
+        self.by_model.is_empty() && self.by_upstream.is_empty()
+    }
+}
+
 /// Shared, cheaply-cloneable latency cache.
 #[derive(Clone)]
-pub struct LatencyCache(Arc<RwLock<HashMap<String, f64>>>);
+pub struct LatencyCache(Arc<RwLock<Inner>>);
 
 impl Default for LatencyCache {
     fn default() -> Self {
@@ -20,18 +99,25 @@ impl Default for LatencyCache {
 
 impl LatencyCache {
     pub fn new() -> Self {
-        Self(Arc::new(RwLock::new(HashMap::new())))
+        Self(Arc::new(RwLock::new(Inner::default())))
     }
 
     /// Reload latency data from `audit_logs` for the last 24 hours.
-    /// Groups by the `model` field in the response body and computes p50.
+    /// Groups by the `model` field in the response body and computes
+    /// percentiles per model and per model/upstream.
+    ///
+    /// An empty result keeps the current data, so a warm-started cache isn't
+    /// wiped before any traffic has been logged.
     pub async fn reload(&self, pool: &sqlx::PgPool) {
-        match fetch_latency_p50(pool).await {
-            Ok(map) => {
-                *self.0.write().await = map;
-                tracing::debug!("latency_cache: reloaded {} model entries", {
-                    self.0.read().await.len()
-                });
+        match fetch_latency_distributions(pool).await {
+            Ok(rows) if rows.is_empty() => {
+                tracing::debug!("latency_cache: no recent samples, keeping current data");
+            }
+            Ok(rows) => {
+                let inner = Inner::from_rows(rows, Utc::now(), "audit_logs");
+                let models = inner.by_model.len();
+                *self.0.write().await = inner;
+                tracing::debug!("latency_cache: reloaded {} model entries", models);
             }
             Err(e) => {
                 tracing::error!("latency_cache: reload failed: {}", e);
@@ -39,45 +125,267 @@ impl LatencyCache {
         }
     }
 
+    /// Seed the cache from the last persisted snapshot. No-op if the cache
+    /// already holds data or no snapshot exists.
+    pub async fn load_snapshot(&self, pool: &sqlx::PgPool) {
+        let rows = match sqlx::query_as::<_, SnapshotRow>(
+            r#"
+            SELECT model, NULLIF(upstream_url, '') AS upstream_url,
+                   p50_ms, p95_ms, p99_ms, sample_count, captured_at
+            FROM model_latency_snapshots
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::error!("latency_cache: snapshot load failed: {}", e);
+                return;
+            }
+        };
+        let Some(captured_at) = rows.iter().map(|r| r.captured_at).max() else {
+            return;
+        };
+
+        let mut guard = self.0.write().await;
+        if guard.is_empty() {
+            let rows = rows.into_iter().map(|r| r.row).collect();
+            *guard = Inner::from_rows(rows, captured_at, "snapshot");
+            tracing::info!(
+                "latency_cache: warm-started {} model entries from snapshot",
+                guard.by_model.len()
+            );
+        }
+    }
+
+    /// Persist the current distributions, replacing the previous snapshot.
+    pub async fn save_snapshot(&self, pool: &sqlx::PgPool) -> anyhow::Result<()> {
+        let (mut models, mut upstreams, mut p50s, mut p95s, mut p99s, mut counts) =
+            (vec![], vec![], vec![], vec![], vec![], vec![]);
+        {
+            let guard = self.0.read().await;
+            if guard.source != "audit_logs" {
+                // Nothing new since the last snapshot.
+                return Ok(());
+            }
+            for d in guard.by_model.values().chain(guard.by_upstream.iter()) {
+                models.push(d.model.clone());
+                upstreams.push(d.upstream_url.clone().unwrap_or_default());
+                p50s.push(d.p50_ms);
+                p95s.push(d.p95_ms);
+                p99s.push(d.p99_ms);
+                counts.push(d.sample_count);
+            }
+        }
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM model_latency_snapshots")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO model_latency_snapshots
+                (model, upstream_url, p50_ms, p95_ms, p99_ms, sample_count)
+            SELECT * FROM UNNEST($1::text[], $2::text[], $3::float8[], $4::float8[], $5::float8[], $6::int8[])
+            "#,
+        )
+        .bind(&models)
+        .bind(&upstreams)
+        .bind(&p50s)
+        .bind(&p95s)
+        .bind(&p99s)
+        .bind(&counts)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
     /// Get the p50 latency in ms for a model. Returns `None` if unknown.
     pub async fn get_p50(&self, model: &str) -> Option<f64> {
-        self.0.read().await.get(model).copied()
+        self.0.read().await.by_model.get(model).map(|d| d.p50_ms)
     }
 
-    /// Return all entries (for diagnostics).
-    pub async fn all(&self) -> HashMap<String, f64> {
-        self.0.read().await.clone()
+    /// Rolling p50 per load-balancer upstream, across all models, in the
+    /// order given. Audit logs record the full request URL, so every
+    /// distribution under an upstream's base URL counts toward it, weighted by
+    /// sample count. `None` for an upstream with no history.
+    pub async fn upstream_p50s(&self, upstream_urls: &[&str]) -> Vec<Option<f64>> {
+        let guard = self.0.read().await;
+        upstream_urls
+            .iter()
+            .map(|base| {
+                let base = base.trim_end_matches('/');
+                let (weighted, samples) = guard
+                    .by_upstream
+                    .iter()
+                    .filter(|d| {
+                        d.upstream_url.as_deref().is_some_and(|u| {
+                            u.strip_prefix(base)
+                                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+                        })
+                    })
+                    .fold((0.0, 0i64), |(w, n), d| {
+                        (w + d.p50_ms * d.sample_count as f64, n + d.sample_count)
+                    });
+                (samples > 0).then(|| weighted / samples as f64)
+            })
+            .collect()
+    }
+
+    /// All distributions, rollup first within each model, sorted by model.
+    pub async fn snapshot(&self) -> LatencySnapshot {
+        let guard = self.0.read().await;
+        let mut distributions: Vec<LatencyDistribution> = guard
+            .by_model
+            .values()
+            .chain(guard.by_upstream.iter())
+            .cloned()
+            .collect();
+        distributions.sort_by(|a, b| {
+            a.model
+                .cmp(&b.model)
+                .then_with(|| a.upstream_url.cmp(&b.upstream_url))
+        });
+        LatencySnapshot {
+            captured_at: guard.captured_at,
+            source: guard.source,
+            min_samples: MIN_SAMPLES,
+            distributions,
+        }
     }
 }
 
-/// Query audit_logs for p50 latency per model over the last 24 hours.
-async fn fetch_latency_p50(pool: &sqlx::PgPool) -> anyhow::Result<HashMap<String, f64>> {
-    #[derive(sqlx::FromRow)]
-    struct LatencyRow {
-        model: String,
-        p50: f64,
+#[derive(sqlx::FromRow)]
+struct LatencyRow {
+    model: String,
+    upstream_url: Option<String>,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    sample_count: i64,
+}
+
+impl LatencyRow {
+    fn into_distribution(self) -> LatencyDistribution {
+        LatencyDistribution {
+            low_sample: self.sample_count < MIN_SAMPLES,
+            model: self.model,
+            upstream_url: self.upstream_url,
+            p50_ms: self.p50_ms,
+            p95_ms: self.p95_ms,
+            p99_ms: self.p99_ms,
+            sample_count: self.sample_count,
+        }
     }
+}
+
+#[derive(sqlx::FromRow)]
+struct SnapshotRow {
+    #[sqlx(flatten)]
+    row: LatencyRow,
+    captured_at: DateTime<Utc>,
+}
 
+/// Query audit_logs for latency percentiles over the last 24 hours, per
+/// model/upstream and rolled up per model (`upstream_url` NULL).
+async fn fetch_latency_distributions(pool: &sqlx::PgPool) -> anyhow::Result<Vec<LatencyRow>> {
     let rows = sqlx::query_as::<_, LatencyRow>(
         r#"
         SELECT
             COALESCE(response_model, model, 'unknown') AS model,
-            PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY response_latency_ms)::float8 AS p50
+            CASE WHEN GROUPING(COALESCE(upstream_url, 'unknown')) = 1 THEN NULL
+                 ELSE COALESCE(upstream_url, 'unknown') END AS upstream_url,
+            PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY response_latency_ms)::float8 AS p50_ms,
+            PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY response_latency_ms)::float8 AS p95_ms,
+            PERCENTILE_CONT(0.99) WITHIN GROUP (ORDER BY response_latency_ms)::float8 AS p99_ms,
+            COUNT(*)::int8 AS sample_count
         FROM audit_logs
         WHERE
             created_at >= NOW() - INTERVAL '24 hours'
             AND response_latency_ms IS NOT NULL
             AND response_latency_ms > 0
-        GROUP BY COALESCE(response_model, model, 'unknown')
+        GROUP BY GROUPING SETS (
+            (COALESCE(response_model, model, 'unknown'), COALESCE(upstream_url, 'unknown')),
+            (COALESCE(response_model, model, 'unknown'))
+        )
         "#,
     )
     .fetch_all(pool)
     .await?;
 
-    let mut map = HashMap::with_capacity(rows.len());
-    for row in rows {
-        map.insert(row.model, row.p50);
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(model: &str, upstream: Option<&str>, p50: f64, samples: i64) -> LatencyRow {
+        LatencyRow {
+            model: model.into(),
+            upstream_url: upstream.map(Into::into),
+            p50_ms: p50,
+            p95_ms: p50 * 2.0,
+            p99_ms: p50 * 3.0,
+            sample_count: samples,
+        }
     }
 
-    Ok(map)
+    #[tokio::test]
+    async fn test_rollup_feeds_routing_and_thin_data_is_flagged() {
+        let cache = LatencyCache::new();
+        *cache.0.write().await = Inner::from_rows(
+            vec![
+                row("gpt-4o", Some("https://b.example"), 900.0, 5),
+                row("gpt-4o", None, 400.0, 105),
+                row("gpt-4o", Some("https://a.example"), 380.0, 100),
+            ],
+            Utc::now(),
+            "audit_logs",
+        );
+
+        assert_eq!(cache.get_p50("gpt-4o").await, Some(400.0));
+        assert_eq!(cache.get_p50("claude-3-5-sonnet").await, None);
+
+        let snap = cache.snapshot().await;
+        let upstreams: Vec<_> = snap
+            .distributions
+            .iter()
+            .map(|d| d.upstream_url.as_deref())
+            .collect();
+        assert_eq!(
+            upstreams,
+            [None, Some("https://a.example"), Some("https://b.example")]
+        );
+        assert!(!snap.distributions[0].low_sample);
+        assert!(snap.distributions[2].low_sample);
+        assert_eq!(snap.distributions[0].p95_ms, 800.0);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_p50s_pools_models_under_base_url() {
+        let cache = LatencyCache::new();
+        *cache.0.write().await = Inner::from_rows(
+            vec![
+                row("gpt-4o", Some("https://a.example/v1/chat"), 300.0, 30),
+                row("gpt-4o-mini", Some("https://a.example/v1/chat"), 100.0, 10),
+                row(
+                    "gpt-4o",
+                    Some("https://a.example.evil/v1/chat"),
+                    9_000.0,
+                    50,
+                ),
+                row("gpt-4o", None, 400.0, 90),
+            ],
+            Utc::now(),
+            "audit_logs",
+        );
+
+        let p50s = cache
+            .upstream_p50s(&["https://a.example/", "https://b.example"])
+            .await;
+        assert_eq!(p50s, vec![Some(250.0), None]);
+    }
 }
@@ -15,6 +15,39 @@ pub struct ToolCallInfo {
     pub arguments: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub call_id: Option<String>,
+    /// Approximate completion tokens spent emitting this call (name + arguments).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_tokens: Option<u32>,
+    /// Output-token cost attributed to this call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// Fixed per-call framing overhead (id, type, JSON punctuation) in tokens.
+const TOOL_CALL_OVERHEAD_TOKENS: u32 = 8;
+
+/// Rough token estimate for a single tool call (~4 chars per token).
+pub fn estimate_tool_call_tokens(tc: &ToolCallInfo) -> u32 {
+    let chars = tc.name.len() + tc.arguments.as_deref().map_or(0, str::len);
+    (chars as u32).div_ceil(4) + TOOL_CALL_OVERHEAD_TOKENS
+}
+
+/// Fill `estimated_tokens` on each tool call.
+///
+/// When the response reports `completion_tokens`, estimates are scaled down so
+/// their sum never exceeds the actual completion token count.
+pub fn attribute_tool_call_tokens(tool_calls: &mut [ToolCallInfo], completion_tokens: Option<u32>) {
+    let estimates: Vec<u32> = tool_calls.iter().map(estimate_tool_call_tokens).collect();
+    let total: u64 = estimates.iter().map(|&t| t as u64).sum();
+    let cap = completion_tokens.map(u64::from).unwrap_or(u64::MAX);
+    for (tc, est) in tool_calls.iter_mut().zip(estimates) {
+        let tokens = if total > cap && total > 0 {
+            (est as u64 * cap / total) as u32
+        } else {
+            est
+        };
+        tc.estimated_tokens = Some(tokens);
+    }
 }
 
 /// Extract tool calls from a complete (non-streaming) LLM response body.
@@ -74,6 +107,8 @@ fn extract_openai_tool_calls(json: &Value) -> Vec<ToolCallInfo> {
                             name,
                             arguments,
                             call_id,
+                            estimated_tokens: None,
+                            estimated_cost_usd: None,
                         });
                     }
                 }
@@ -105,6 +140,8 @@ fn extract_anthropic_tool_calls(json: &Value) -> Vec<ToolCallInfo> {
                     name,
                     arguments,
                     call_id,
+                    estimated_tokens: None,
+                    estimated_cost_usd: None,
                 });
             }
         }
@@ -136,6 +173,8 @@ fn extract_gemini_tool_calls(json: &Value) -> Vec<ToolCallInfo> {
                             name,
                             arguments,
                             call_id: None,
+                            estimated_tokens: None,
+                            estimated_cost_usd: None,
                         });
                     }
                 }
@@ -184,6 +223,35 @@ pub fn extract_finish_reason_from_value(json: &Value) -> Option<String> {
     None
 }
 
+/// Map a provider's native finish reason onto the OpenAI vocabulary
+/// (`stop`, `length`, `tool_calls`, `content_filter`), mirroring the
+/// non-streaming translators in `model_router`. OpenAI values pass through;
+/// unrecognised values fall back to `stop`, as the translators do.
+pub fn normalize_finish_reason(raw: &str) -> &'static str {
+    match raw.to_ascii_lowercase().as_str() {
+        // OpenAI (and OpenAI-compatible providers)
+        "stop" => "stop",
+        "length" => "length",
+        "tool_calls" | "function_call" => "tool_calls",
+        "content_filter" => "content_filter",
+        // Anthropic / Bedrock Converse
+        "end_turn" | "stop_sequence" | "pause_turn" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "refusal" | "content_filtered" | "guardrail_intervened" => "content_filter",
+        // Gemini (uppercase on the wire, lowercased above)
+        "safety" | "recitation" | "blocklist" | "prohibited_content" | "spii" | "image_safety" => {
+            "content_filter"
+        }
+        // Cohere / Mistral
+        "complete" => "stop",
+        "tool_call" => "tool_calls",
+        "error_toxic" => "content_filter",
+        "model_length" => "length",
+        _ => "stop",
+    }
+}
+
 // ── Error Classification ────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -306,6 +374,39 @@ fn classify_error_inner(status: u16, body_str: &str, json: Option<&Value>) -> Op
     Some(result.to_string())
 }
 
+/// Phrases in a 403 body that point at the credential itself rather than at
+/// the request (region blocks, moderation, per-model access).
+const CREDENTIAL_ERROR_MARKERS: &[&str] = &[
+    "api key",
+    "api_key",
+    "apikey",
+    "access key",
+    "authentication",
+    "unauthenticated",
+    "credential",
+    "revoked",
+    "invalid_token",
+    "expiredtoken",
+    "security token",
+    "signature",
+];
+
+/// Whether an upstream 401/403 means the credential is unusable: invalid,
+/// expired, revoked or badly signed. A 401 always does. Providers also answer
+/// 403 for region blocks, moderation and model access the client can change,
+/// so a 403 only counts when its error names the key.
+pub fn is_credential_rejection(status: u16, body: &[u8]) -> bool {
+    match status {
+        401 => true,
+        403 => {
+            let head = &body[..body.len().min(4096)];
+            let lower = String::from_utf8_lossy(head).to_lowercase();
+            CREDENTIAL_ERROR_MARKERS.iter().any(|m| lower.contains(m))
+        }
+        _ => false,
+    }
+}
+
 // ── Streaming Helpers ───────────────────────────────────────────
 
 /// Check if the request body has `stream: true`.
@@ -325,6 +426,42 @@ pub fn is_streaming_request(body: &[u8]) -> bool {
 mod tests {
     use super::*;
 
+    // ── Credential Rejection ────────────────────────────────────
+
+    #[test]
+    fn test_credential_rejection_401_always() {
+        assert!(is_credential_rejection(401, b""));
+        assert!(is_credential_rejection(
+            401,
+            br#"{"error":{"message":"rate limited"}}"#
+        ));
+        assert!(!is_credential_rejection(
+            400,
+            br#"{"error":{"code":"invalid_api_key"}}"#
+        ));
+    }
+
+    #[test]
+    fn test_credential_rejection_403_naming_the_key() {
+        let anthropic = br#"{"type":"error","error":{"type":"permission_error","message":"Your API key does not have permission to use the specified resource."}}"#;
+        assert!(is_credential_rejection(403, anthropic));
+        let bedrock = br#"{"__type":"UnrecognizedClientException","message":"The security token included in the request is invalid."}"#;
+        assert!(is_credential_rejection(403, bedrock));
+        let azure = br#"{"error":{"code":"AuthenticationTypeDisabled","message":"Key based authentication is disabled for this resource."}}"#;
+        assert!(is_credential_rejection(403, azure));
+    }
+
+    #[test]
+    fn test_credential_rejection_403_about_the_request() {
+        let region = br#"{"error":{"code":"unsupported_country_region_territory","message":"Country, region, or territory not supported","type":"request_forbidden"}}"#;
+        assert!(!is_credential_rejection(403, region));
+        let model_access = br#"{"__type":"AccessDeniedException","message":"You don't have access to the model with the specified model ID."}"#;
+        assert!(!is_credential_rejection(403, model_access));
+        let moderation = br#"{"error":{"message":"Your request was flagged by our moderation system.","type":"invalid_request_error"}}"#;
+        assert!(!is_credential_rejection(403, moderation));
+        assert!(!is_credential_rejection(403, b""));
+    }
+
     // ── Tool Call Extraction (bytes API) ────────────────────────
 
     #[test]
@@ -607,6 +744,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normalize_finish_reason() {
+        for (raw, want) in [
+            ("stop", "stop"),
+            ("length", "length"),
+            ("tool_calls", "tool_calls"),
+            ("content_filter", "content_filter"),
+            ("end_turn", "stop"),
+            ("stop_sequence", "stop"),
+            ("max_tokens", "length"),
+            ("tool_use", "tool_calls"),
+            ("refusal", "content_filter"),
+            ("STOP", "stop"),
+            ("MAX_TOKENS", "length"),
+            ("SAFETY", "content_filter"),
+            ("RECITATION", "content_filter"),
+            ("guardrail_intervened", "content_filter"),
+            ("COMPLETE", "stop"),
+            ("something_new", "stop"),
+        ] {
+            assert_eq!(normalize_finish_reason(raw), want, "raw={}", raw);
+        }
+    }
+
     #[test]
     fn test_finish_reason_from_value() {
         let json = serde_json::json!({"choices":[{"finish_reason":"stop"}]});
@@ -837,6 +998,8 @@ mod tests {
             name: "get_weather".into(),
             arguments: Some("{\"city\":\"NYC\"}".into()),
             call_id: Some("call_123".into()),
+            estimated_tokens: None,
+            estimated_cost_usd: None,
         };
         let json = serde_json::to_value(&tc).unwrap();
         assert_eq!(json["name"], "get_weather");
@@ -850,6 +1013,8 @@ mod tests {
             name: "search".into(),
             arguments: None,
             call_id: None,
+            estimated_tokens: None,
+            estimated_cost_usd: None,
         };
         let json = serde_json::to_value(&tc).unwrap();
         assert_eq!(json["name"], "search");
@@ -857,6 +1022,63 @@ mod tests {
         assert!(json.get("call_id").is_none());
     }
 
+    #[test]
+    fn test_estimate_tool_call_tokens() {
+        let tc = ToolCallInfo {
+            name: "get_weather".into(),                   // 11 chars
+            arguments: Some("{\"city\":\"NYC\"}".into()), // 14 chars
+            call_id: None,
+            estimated_tokens: None,
+            estimated_cost_usd: None,
+        };
+        // ceil(25 / 4) + overhead
+        assert_eq!(
+            estimate_tool_call_tokens(&tc),
+            7 + TOOL_CALL_OVERHEAD_TOKENS
+        );
+    }
+
+    #[test]
+    fn test_attribute_tool_call_tokens_capped_by_completion() {
+        let make = |name: &str, args: &str| ToolCallInfo {
+            name: name.into(),
+            arguments: Some(args.into()),
+            call_id: None,
+            estimated_tokens: None,
+            estimated_cost_usd: None,
+        };
+        let mut calls = vec![
+            make("search", &"x".repeat(400)),
+            make("read", &"y".repeat(400)),
+        ];
+        attribute_tool_call_tokens(&mut calls, None);
+        let uncapped: Vec<u32> = calls.iter().map(|c| c.estimated_tokens.unwrap()).collect();
+        assert!(uncapped.iter().all(|&t| t > 100));
+
+        // Parallel tool calls can't claim more tokens than the response generated
+        attribute_tool_call_tokens(&mut calls, Some(50));
+        let total: u32 = calls.iter().map(|c| c.estimated_tokens.unwrap()).sum();
+        assert!(total <= 50, "attributed {} > 50 completion tokens", total);
+        assert!(calls.iter().all(|c| c.estimated_tokens.unwrap() > 0));
+    }
+
+    #[test]
+    fn test_tool_call_info_estimates_serialized() {
+        let mut calls = vec![ToolCallInfo {
+            name: "search".into(),
+            arguments: None,
+            call_id: None,
+            estimated_tokens: None,
+            estimated_cost_usd: None,
+        }];
+        attribute_tool_call_tokens(&mut calls, Some(100));
+        let json = serde_json::to_value(&calls[0]).unwrap();
+        assert!(json["estimated_tokens"].as_u64().unwrap() > 0);
+        // Older audit rows without the field still deserialize
+        let old: ToolCallInfo = serde_json::from_value(serde_json::json!({"name": "x"})).unwrap();
+        assert!(old.estimated_tokens.is_none());
+    }
+
     // ── LlmErrorType Display ────────────────────────────────────
 
     #[test]
@@ -1,11 +1,15 @@
 pub mod analytics;
 pub mod approval;
 pub mod audit;
+pub mod capabilities;
 pub mod cost;
 pub mod latency_cache;
 pub mod llm;
+pub mod model_remap;
 pub mod notification;
 pub mod policy;
 pub mod pricing_cache;
+pub mod pricing_import;
 pub mod service;
 pub mod token;
+pub mod tokenizer;
@@ -0,0 +1,117 @@
+//! In-memory deprecated-model remap table.
+//!
+//! Backed by the `deprecated_model_map` system setting (`PUT /settings`),
+//! e.g. `{"gpt-4-0314": "gpt-4o"}`. The proxy consults it before model
+//! access checks and translation, so a deprecated name is routed, checked and
+//! priced exactly like its replacement. Reloaded after every settings update
+//! and every 60s by a background job in `main.rs`, so all replicas converge.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::store::postgres::PgStore;
+
+/// `system_settings` key holding the remap table.
+pub const SETTING_KEY: &str = "deprecated_model_map";
+
+/// Upper bound on entries; deprecation tables are a few dozen models.
+pub const MAX_ENTRIES: usize = 1_000;
+
+/// Shared, cheaply-cloneable remap table.
+#[derive(Clone)]
+pub struct ModelRemapCache(Arc<RwLock<HashMap<String, String>>>);
+
+impl Default for ModelRemapCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelRemapCache {
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(HashMap::new())))
+    }
+
+    /// Replace the whole table.
+    pub async fn replace(&self, map: HashMap<String, String>) {
+        *self.0.write().await = map;
+    }
+
+    /// Reload the table from `system_settings`. An absent setting clears it;
+    /// an unreadable one keeps the previous table.
+    pub async fn reload(&self, db: &PgStore) {
+        match db
+            .get_system_setting::<serde_json::Value>(SETTING_KEY)
+            .await
+        {
+            Ok(value) => match value.as_ref().map(parse_map).transpose() {
+                Ok(map) => self.replace(map.unwrap_or_default()).await,
+                Err(e) => tracing::error!("model_remap: invalid {}: {}", SETTING_KEY, e),
+            },
+            Err(e) => tracing::error!("model_remap: reload failed: {}", e),
+        }
+    }
+
+    /// Replacement for a deprecated model, if one is configured.
+    pub async fn lookup(&self, model: &str) -> Option<String> {
+        self.0.read().await.get(model).cloned()
+    }
+}
+
+/// Validate a `deprecated_model_map` setting value: a JSON object mapping
+/// deprecated model names to non-empty replacement names.
+pub fn parse_map(value: &serde_json::Value) -> Result<HashMap<String, String>, String> {
+    let obj = value
+        .as_object()
+        .ok_or("must be an object of deprecated model -> replacement")?;
+    if obj.len() > MAX_ENTRIES {
+        return Err(format!("too many entries (max {})", MAX_ENTRIES));
+    }
+    let mut map = HashMap::with_capacity(obj.len());
+    for (from, to) in obj {
+        let to = to
+            .as_str()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| format!("replacement for '{}' must be a non-empty string", from))?;
+        if to == from {
+            return Err(format!("'{}' is mapped to itself", from));
+        }
+        map.insert(from.clone(), to.to_string());
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_map_accepts_string_replacements() {
+        let map = parse_map(&json!({"gpt-4-0314": "gpt-4o", "text-davinci-003": " gpt-4o-mini "}))
+            .unwrap();
+        assert_eq!(map["gpt-4-0314"], "gpt-4o");
+        assert_eq!(map["text-davinci-003"], "gpt-4o-mini");
+    }
+
+    #[test]
+    fn test_parse_map_rejects_bad_values() {
+        assert!(parse_map(&json!(["gpt-4-0314"])).is_err());
+        assert!(parse_map(&json!({"gpt-4-0314": 4})).is_err());
+        assert!(parse_map(&json!({"gpt-4-0314": ""})).is_err());
+        assert!(parse_map(&json!({"gpt-4o": "gpt-4o"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_after_replace() {
+        let cache = ModelRemapCache::new();
+        assert!(cache.lookup("gpt-4-0314").await.is_none());
+        cache
+            .replace(HashMap::from([("gpt-4-0314".into(), "gpt-4o".into())]))
+            .await;
+        assert_eq!(cache.lookup("gpt-4-0314").await.as_deref(), Some("gpt-4o"));
+        assert!(cache.lookup("gpt-4o").await.is_none());
+    }
+}
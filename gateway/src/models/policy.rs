@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -12,6 +14,10 @@ use uuid::Uuid;
 pub struct Policy {
     pub id: Uuid,
     pub name: String,
+    /// Evaluation order among a token's policies: lower runs first, ties keep
+    /// attachment order. Later body overrides win, so higher priority wins.
+    #[serde(default)]
+    pub priority: i32,
     /// Evaluation phase: "pre" (before upstream) or "post" (after upstream).
     #[serde(default = "default_phase")]
     pub phase: Phase,
@@ -55,8 +61,10 @@ pub struct RetryConfig {
     pub max_backoff_ms: u64,
     #[serde(default = "default_jitter")]
     pub jitter_ms: u64,
-    #[serde(default = "default_retry_status_codes")]
-    pub status_codes: Vec<u16>,
+    /// Upstream status codes that trigger a retry. Anything else is returned
+    /// as-is. Accepts the older `status_codes` key.
+    #[serde(default = "default_retry_status_codes", alias = "status_codes")]
+    pub retry_on_status: Vec<u16>,
     /// Maximum total time (in milliseconds) for all retry attempts combined.
     /// When set, the retry loop aborts once the deadline is exceeded, even if
     /// max_retries has not been reached. None = no deadline (existing behaviour).
@@ -71,7 +79,7 @@ impl Default for RetryConfig {
             base_backoff_ms: default_base_backoff(),
             max_backoff_ms: default_max_backoff(),
             jitter_ms: default_jitter(),
-            status_codes: default_retry_status_codes(),
+            retry_on_status: default_retry_status_codes(),
             max_total_timeout_ms: None,
         }
     }
@@ -89,8 +97,15 @@ fn default_max_backoff() -> u64 {
 fn default_jitter() -> u64 {
     200
 }
+impl RetryConfig {
+    /// Whether an upstream response with this status should be retried.
+    pub fn retries_status(&self, status: u16) -> bool {
+        self.retry_on_status.contains(&status)
+    }
+}
+
 fn default_retry_status_codes() -> Vec<u16> {
-    vec![429, 500, 502, 503, 504]
+    vec![429, 502, 503, 504]
 }
 
 // ── Rule ─────────────────────────────────────────────────────
@@ -227,6 +242,10 @@ pub enum Action {
         /// When absent, regex-only redaction is used (fully backward compatible).
         #[serde(default)]
         nlp_backend: Option<NlpBackendConfig>,
+        /// Project-specific PII patterns (e.g. UK NINO), matched alongside
+        /// `patterns` in every mode. Matches are reported under the entry's name.
+        #[serde(default)]
+        custom_regexes: Vec<NamedRegex>,
     },
     /// Transform the request (set headers, append system prompt, etc.)
     Transform { operations: Vec<TransformOp> },
@@ -234,6 +253,27 @@ pub enum Action {
     Override {
         set_body_fields: std::collections::HashMap<String, serde_json::Value>,
     },
+    /// Set a field in the response JSON (post-flight only). The response-side
+    /// analog of `override`, for normalizing provider extensions, e.g. moving
+    /// a RAG upstream's top-level `citations` into a standard `x_sources`.
+    ///
+    /// ```json
+    /// { "action": "inject_response_field", "path": "x_sources", "from": "citations", "remove_source": true }
+    /// ```
+    InjectResponseField {
+        /// Dot-separated target path. Missing objects are created, numeric
+        /// segments index arrays and `*` targets every element.
+        path: String,
+        /// Literal value to set (used when `from` is absent).
+        #[serde(default)]
+        value: Option<serde_json::Value>,
+        /// Response path to copy the value from instead of `value`.
+        #[serde(default)]
+        from: Option<String>,
+        /// Delete `from` after copying it.
+        #[serde(default)]
+        remove_source: bool,
+    },
     /// Log a message without blocking.
     Log {
         #[serde(default = "default_log_level")]
@@ -307,7 +347,8 @@ pub enum Action {
     ///
     /// Example: send 30% of traffic to GPT-4 and 70% to Claude.
     /// Variant selection is deterministic per request_id (same caller always
-    /// gets the same variant within a request).
+    /// gets the same variant within a request). A variant may also set
+    /// `upstream_url` and `credential_id` to split between providers.
     ///
     /// ```json
     /// {
@@ -344,6 +385,10 @@ pub enum Action {
     ///   ]
     /// }
     /// ```
+    ///
+    /// With `"strategy": "by_prompt_complexity"`, each pool target carries a
+    /// `tier` (`simple`, `moderate` or `complex`) and the optional `complexity`
+    /// block tunes how prompts are classified.
     DynamicRoute {
         /// How to rank and select from the pool.
         strategy: RoutingStrategy,
@@ -352,6 +397,9 @@ pub enum Action {
         /// Used when all pool targets are unhealthy.
         #[serde(default)]
         fallback: Option<RouteTarget>,
+        /// Classification settings for the `by_prompt_complexity` strategy.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        complexity: Option<ComplexityConfig>,
     },
 
     /// Validate the LLM response against a JSON Schema.
@@ -385,6 +433,34 @@ pub enum Action {
         message: Option<String>,
     },
 
+    /// Coerce the response toward a JSON Schema instead of rejecting it.
+    ///
+    /// Post-flight only. Mismatched scalars are converted where lossless
+    /// (`"0.9"` → `0.9`, `1` → `"1"`, `"true"` → `true`, a scalar → a
+    /// one-element array), missing properties with a `default` are filled, and
+    /// missing required properties without one get an empty value of their
+    /// type. Properties rejected by `additionalProperties: false` are dropped.
+    /// The response is denied only if it still doesn't validate afterwards.
+    /// Use `validate_schema` for strict checking.
+    ///
+    /// ```json
+    /// {
+    ///   "action": "coerce_response_schema",
+    ///   "schema": {
+    ///     "type": "object",
+    ///     "required": ["answer", "confidence"],
+    ///     "properties": {
+    ///       "answer": {"type": "string"},
+    ///       "confidence": {"type": "number", "default": 0}
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    CoerceResponseSchema {
+        /// The JSON Schema to coerce toward.
+        schema: serde_json::Value,
+    },
+
     /// Condition-based routing — select an upstream target based on request properties.
     ///
     /// Evaluates branches in order; the first matching branch wins. Falls back to
@@ -472,6 +548,114 @@ pub enum Action {
         #[serde(default = "default_tool_deny_message")]
         deny_message: String,
     },
+
+    /// Require request metadata — enforce keys in the `X-Properties` header.
+    ///
+    /// Evaluated pre-flight against the parsed `X-Properties` JSON object. The
+    /// request is denied if any `required_keys` entry is missing, or if a key
+    /// listed in `allowed_values` carries a value outside its allowed set.
+    ///
+    /// ```json
+    /// {
+    ///   "action": "require_properties",
+    ///   "required_keys": ["cost_center", "environment"],
+    ///   "allowed_values": { "environment": ["dev", "staging", "prod"] }
+    /// }
+    /// ```
+    RequireProperties {
+        /// Keys that must be present in `X-Properties`.
+        #[serde(default)]
+        required_keys: Vec<String>,
+        /// Per-key allowlist of values. Keys not listed accept any value.
+        #[serde(default)]
+        allowed_values: Option<HashMap<String, Vec<String>>>,
+    },
+
+    /// Keyword deny-list — block requests that mention any listed term.
+    ///
+    /// Evaluated pre-flight against the request's text content (messages,
+    /// `input`, `prompt`). Cheaper and more predictable than `content_filter`
+    /// for exact terms such as competitor names or banned phrases.
+    ///
+    /// ```json
+    /// {
+    ///   "action": "keyword_block",
+    ///   "terms": ["acme corp", "project falcon"],
+    ///   "match_mode": "word",
+    ///   "message": "Request mentions a restricted term"
+    /// }
+    /// ```
+    KeywordBlock {
+        /// Terms that block the request when found.
+        terms: Vec<String>,
+        #[serde(default = "default_true")]
+        case_insensitive: bool,
+        /// `substring` matches anywhere; `word` only at word boundaries.
+        #[serde(default)]
+        match_mode: KeywordMatchMode,
+        /// Replaces the default reason. When set, the matched term is not
+        /// revealed to the caller (it is still logged).
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+/// How `keyword_block` terms are matched.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeywordMatchMode {
+    #[default]
+    Substring,
+    Word,
+}
+
+impl Action {
+    /// Actions that block, limit or scrub a request. An exhausted policy
+    /// evaluation budget never skips these: they still run, and a `redact`,
+    /// `content_filter` or deny-mode `external_guardrail` cut off by the
+    /// budget denies the request.
+    pub fn is_security_control(&self) -> bool {
+        matches!(
+            self,
+            Action::Deny { .. }
+                | Action::RequireApproval { .. }
+                | Action::RateLimit { .. }
+                | Action::Redact { .. }
+                | Action::ContentFilter { .. }
+                | Action::ExternalGuardrail { .. }
+                | Action::ToolScope { .. }
+                | Action::RequireProperties { .. }
+                | Action::KeywordBlock { .. }
+        )
+    }
+
+    /// The `action` tag this variant is (de)serialized with.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Action::Allow => "allow",
+            Action::Deny { .. } => "deny",
+            Action::RequireApproval { .. } => "require_approval",
+            Action::RateLimit { .. } => "rate_limit",
+            Action::Throttle { .. } => "throttle",
+            Action::Redact { .. } => "redact",
+            Action::Transform { .. } => "transform",
+            Action::Override { .. } => "override",
+            Action::InjectResponseField { .. } => "inject_response_field",
+            Action::Log { .. } => "log",
+            Action::Tag { .. } => "tag",
+            Action::Webhook { .. } => "webhook",
+            Action::ContentFilter { .. } => "content_filter",
+            Action::Split { .. } => "split",
+            Action::DynamicRoute { .. } => "dynamic_route",
+            Action::ValidateSchema { .. } => "validate_schema",
+            Action::CoerceResponseSchema { .. } => "coerce_response_schema",
+            Action::ConditionalRoute { .. } => "conditional_route",
+            Action::ExternalGuardrail { .. } => "external_guardrail",
+            Action::ToolScope { .. } => "tool_scope",
+            Action::RequireProperties { .. } => "require_properties",
+            Action::KeywordBlock { .. } => "keyword_block",
+        }
+    }
 }
 
 /// Which external guardrail vendor to call.
@@ -521,6 +705,15 @@ pub struct RouteCondition {
 
 // ── Action Sub-types ─────────────────────────────────────────
 
+/// A named custom regex for `Action::Redact`. Patterns that fail to compile
+/// or exceed the compiled size limit are rejected and never matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedRegex {
+    /// Reported in `matched_types`; the replacement is `[REDACTED_<NAME>]`.
+    pub name: String,
+    pub pattern: String,
+}
+
 /// A single weighted variant in a Split action.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SplitVariant {
@@ -528,10 +721,17 @@ pub struct SplitVariant {
     pub weight: u32,
     /// Body fields to override when this variant is selected.
     /// Typically `{"model": "claude-3-5-sonnet-20241022"}` to redirect to a different model.
+    #[serde(default)]
     pub set_body_fields: std::collections::HashMap<String, serde_json::Value>,
     /// Optional variant label shown in experiment analytics (e.g., "control", "experiment").
     #[serde(default)]
     pub name: Option<String>,
+    /// Upstream to send this variant's requests to, replacing the token's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_url: Option<String>,
+    /// Credential to inject for this variant, replacing the token's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<Uuid>,
 }
 
 /// Routing strategy for `DynamicRoute` actions.
@@ -548,6 +748,73 @@ pub enum RoutingStrategy {
     LeastBusy,
     /// Randomly select from the pool, weighted by each target's weight field.
     WeightedRandom,
+    /// Classify the prompt as simple/moderate/complex and pick a target of that tier.
+    ByPromptComplexity,
+}
+
+/// Prompt classification settings for `RoutingStrategy::ByPromptComplexity`.
+///
+/// The built-in heuristic looks at prompt length, message count and whether the
+/// prompt contains code. When `classifier_url` is set, the gateway POSTs the
+/// request body there first and expects `{"tier": "simple" | "moderate" | "complex"}`;
+/// a timeout, error or unknown tier falls back to `default_tier`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ComplexityConfig {
+    /// Prompts at or below this many characters (with no code and few messages) are `simple`.
+    #[serde(default = "default_simple_max_chars")]
+    pub simple_max_chars: usize,
+    /// Prompts at or above this many characters are `complex`.
+    #[serde(default = "default_complex_min_chars")]
+    pub complex_min_chars: usize,
+    /// Conversations with at least this many messages are `complex`.
+    #[serde(default = "default_complex_min_messages")]
+    pub complex_min_messages: usize,
+    /// Treat prompts containing code (fenced blocks or common syntax) as `complex`.
+    #[serde(default = "default_true")]
+    pub code_is_complex: bool,
+    /// Optional external classifier endpoint.
+    #[serde(default)]
+    pub classifier_url: Option<String>,
+    /// Hard timeout for the external classifier (default: 200ms, max: 2000ms).
+    #[serde(default = "default_classifier_timeout_ms")]
+    pub classifier_timeout_ms: u64,
+    /// Tier used when the external classifier fails (default: "moderate").
+    #[serde(default = "default_complexity_tier")]
+    pub default_tier: String,
+}
+
+impl Default for ComplexityConfig {
+    fn default() -> Self {
+        Self {
+            simple_max_chars: default_simple_max_chars(),
+            complex_min_chars: default_complex_min_chars(),
+            complex_min_messages: default_complex_min_messages(),
+            code_is_complex: true,
+            classifier_url: None,
+            classifier_timeout_ms: default_classifier_timeout_ms(),
+            default_tier: default_complexity_tier(),
+        }
+    }
+}
+
+fn default_simple_max_chars() -> usize {
+    500
+}
+
+fn default_complex_min_chars() -> usize {
+    4000
+}
+
+fn default_complex_min_messages() -> usize {
+    12
+}
+
+fn default_classifier_timeout_ms() -> u64 {
+    200
+}
+
+fn default_complexity_tier() -> String {
+    "moderate".to_string()
 }
 
 /// A single entry in a `DynamicRoute` pool.
@@ -560,6 +827,9 @@ pub struct RouteTarget {
     /// Optional credential override for this target.
     #[serde(default)]
     pub credential_id: Option<Uuid>,
+    /// Complexity tier served by this target (`by_prompt_complexity` strategy only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -663,6 +933,11 @@ pub enum TransformOp {
     },
     /// Remove a JSON field by dot-path from the body.
     RemoveBodyField { path: String },
+    /// Move a JSON field to another path, e.g. `system_prompt` → `system`.
+    /// Paths are dot-paths or JSON pointers (`/metadata/source`). Intermediate
+    /// objects are created; an existing destination is overwritten. The body is
+    /// left unchanged if the source is absent or the destination unreachable.
+    RenameField { from: String, to: String },
     /// Inject a synthetic message into `messages` array (request-side only).
     /// Equivalent to Portkey's `addStringBeforeInput`/`addStringAfterInput` in structured form.
     AddToMessageList {
@@ -1203,7 +1478,19 @@ mod tests {
         assert_eq!(retry.max_retries, 5);
         assert_eq!(retry.base_backoff_ms, 100);
         assert_eq!(retry.max_backoff_ms, 10000); // default
-        assert_eq!(retry.status_codes, vec![429, 503]);
+        assert_eq!(retry.retry_on_status, vec![429, 503]);
+    }
+
+    #[test]
+    fn test_retry_on_status_defaults_and_override() {
+        let retry: RetryConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(retry.retry_on_status, vec![429, 502, 503, 504]);
+        assert!(!retry.retries_status(500));
+
+        let retry: RetryConfig =
+            serde_json::from_str(r#"{"retry_on_status": [429, 529]}"#).unwrap();
+        assert!(retry.retries_status(529));
+        assert!(!retry.retries_status(503));
     }
 
     // ── Full Scenario: Stripe HITL policy ────────────────────
@@ -1475,4 +1762,62 @@ mod tests {
         // Just verify it doesn't panic — the actual name is tested in engine::tests
         let _ = format!("{:?}", action);
     }
+
+    #[test]
+    fn test_deserialize_require_properties() {
+        let json = r#"{
+            "action": "require_properties",
+            "required_keys": ["cost_center", "environment"],
+            "allowed_values": { "environment": ["dev", "prod"] }
+        }"#;
+        let action: Action = serde_json::from_str(json).unwrap();
+        match action {
+            Action::RequireProperties {
+                required_keys,
+                allowed_values,
+            } => {
+                assert_eq!(required_keys, vec!["cost_center", "environment"]);
+                assert_eq!(allowed_values.unwrap()["environment"], vec!["dev", "prod"]);
+            }
+            _ => panic!("Expected RequireProperties"),
+        }
+    }
+
+    #[test]
+    fn test_action_kind_matches_serde_tag() {
+        for json in [
+            r#"{"action": "allow"}"#,
+            r#"{"action": "throttle", "delay_ms": 10}"#,
+            r#"{"action": "tag", "key": "k", "value": "v"}"#,
+            r#"{"action": "require_properties", "required_keys": ["team"]}"#,
+            r#"{"action": "tool_scope", "blocked_tools": ["rm"]}"#,
+        ] {
+            let action: Action = serde_json::from_str(json).unwrap();
+            let tagged = serde_json::to_value(&action).unwrap();
+            assert_eq!(tagged["action"], action.kind(), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_security_controls_survive_budget_exhaustion() {
+        for (json, security) in [
+            (r#"{"action": "deny", "message": "no"}"#, true),
+            (
+                r#"{"action": "rate_limit", "window": "1m", "max_requests": 5}"#,
+                true,
+            ),
+            (r#"{"action": "redact", "patterns": ["email"]}"#, true),
+            (r#"{"action": "keyword_block", "terms": ["secret"]}"#, true),
+            (
+                r#"{"action": "external_guardrail", "vendor": "llama_guard", "endpoint": "http://guard:11434"}"#,
+                true,
+            ),
+            (r#"{"action": "tag", "key": "k", "value": "v"}"#, false),
+            (r#"{"action": "throttle", "delay_ms": 10}"#, false),
+            (r#"{"action": "log", "level": "info"}"#, false),
+        ] {
+            let action: Action = serde_json::from_str(json).unwrap();
+            assert_eq!(action.is_security_control(), security, "{json}");
+        }
+    }
 }
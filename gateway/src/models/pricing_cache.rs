@@ -14,6 +14,9 @@ pub struct PricingEntry {
     pub model_pattern: String,
     pub input_per_m: Decimal,
     pub output_per_m: Decimal,
+    /// USD per generated image, for image endpoints (see
+    /// [`crate::models::cost::ImageRequest::pricing_key`]).
+    pub image_per_unit: Option<Decimal>,
 }
 
 /// Shared, cheaply-cloneable pricing cache.
@@ -50,6 +53,18 @@ impl PricingCache {
         None
     }
 
+    /// Per-image price for an image pricing key (`model/quality/size`): the
+    /// longest `model_pattern` contained in `key` that has an image price.
+    pub async fn lookup_image(&self, provider: &str, key: &str) -> Option<Decimal> {
+        let entries = self.0.read().await;
+        entries
+            .iter()
+            .filter(|e| e.provider == provider && key.contains(e.model_pattern.as_str()))
+            .filter_map(|e| e.image_per_unit.map(|price| (e.model_pattern.len(), price)))
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, price)| price)
+    }
+
     /// Return all entries (for the list API endpoint).
     pub async fn all(&self) -> Vec<PricingEntry> {
         self.0.read().await.clone()
@@ -0,0 +1,284 @@
+//! Bulk pricing import.
+//!
+//! Parses a pricing sheet (CSV or JSON) into validated rows for
+//! `PgStore::import_model_pricing`. Shared by `POST /api/v1/pricing/import`
+//! and `trueflow pricing import`.
+//!
+//! CSV needs a header row naming the four columns, in any order:
+//!
+//! ```text
+//! provider,model_pattern,input_per_m,output_per_m
+//! openai,^gpt-4o$,2.50,10.00
+//! anthropic,"^claude-3-5-haiku.*",0.80,4.00
+//! ```
+//!
+//! JSON is an array of objects with the same keys; prices may be numbers or
+//! strings.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Upper bound on rows per import; pricing tables are a few hundred rows.
+pub const MAX_IMPORT_ROWS: usize = 5_000;
+
+const COLUMNS: [&str; 4] = ["provider", "model_pattern", "input_per_m", "output_per_m"];
+
+/// A row that passed validation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PricingImportRow {
+    pub provider: String,
+    pub model_pattern: String,
+    pub input_per_m: Decimal,
+    pub output_per_m: Decimal,
+}
+
+/// Outcome for one input row (1-based, header excluded).
+#[derive(Debug, Clone, Serialize)]
+pub struct PricingImportResult {
+    pub row: usize,
+    pub provider: String,
+    pub model_pattern: String,
+    /// "valid" (dry run), "created", "updated" or "error".
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Raw, unvalidated fields of one row.
+#[derive(Debug, Default)]
+struct RawRow {
+    provider: String,
+    model_pattern: String,
+    input_per_m: String,
+    output_per_m: String,
+}
+
+/// Parse and validate a pricing sheet.
+///
+/// Returns the valid rows alongside a result for every input row. A
+/// whole-document problem (bad header, malformed JSON) is an `Err`.
+pub fn parse(
+    text: &str,
+    is_csv: bool,
+) -> Result<(Vec<PricingImportRow>, Vec<PricingImportResult>), String> {
+    let raw = if is_csv {
+        parse_csv(text)?
+    } else {
+        parse_json(text)?
+    };
+    if raw.is_empty() {
+        return Err("no pricing rows found".into());
+    }
+    if raw.len() > MAX_IMPORT_ROWS {
+        return Err(format!("too many rows (max {})", MAX_IMPORT_ROWS));
+    }
+
+    let mut seen = HashSet::new();
+    let mut valid = Vec::new();
+    let mut results = Vec::with_capacity(raw.len());
+    for (i, r) in raw.into_iter().enumerate() {
+        let provider = r.provider.trim().to_lowercase();
+        let model_pattern = r.model_pattern.trim().to_string();
+        let checked = validate_row(&provider, &model_pattern, &r.input_per_m, &r.output_per_m)
+            .and_then(|(input, output)| {
+                if seen.insert((provider.clone(), model_pattern.clone())) {
+                    Ok((input, output))
+                } else {
+                    Err("duplicate provider/model_pattern in import".to_string())
+                }
+            });
+        let error = match checked {
+            Ok((input_per_m, output_per_m)) => {
+                valid.push(PricingImportRow {
+                    provider: provider.clone(),
+                    model_pattern: model_pattern.clone(),
+                    input_per_m,
+                    output_per_m,
+                });
+                None
+            }
+            Err(e) => Some(e),
+        };
+        results.push(PricingImportResult {
+            row: i + 1,
+            provider,
+            model_pattern,
+            status: if error.is_some() { "error" } else { "valid" },
+            error,
+        });
+    }
+    Ok((valid, results))
+}
+
+fn validate_row(
+    provider: &str,
+    model_pattern: &str,
+    input: &str,
+    output: &str,
+) -> Result<(Decimal, Decimal), String> {
+    if provider.is_empty() {
+        return Err("provider is required".into());
+    }
+    if model_pattern.is_empty() {
+        return Err("model_pattern is required".into());
+    }
+    // Same ReDoS guard as PUT /pricing: patterns are compiled on cost lookups.
+    if regex::RegexBuilder::new(model_pattern)
+        .size_limit(1_000_000)
+        .build()
+        .is_err()
+    {
+        return Err("model_pattern is not a valid regex".into());
+    }
+    Ok((
+        parse_price("input_per_m", input)?,
+        parse_price("output_per_m", output)?,
+    ))
+}
+
+fn parse_price(field: &str, value: &str) -> Result<Decimal, String> {
+    let value = value.trim().trim_start_matches('$');
+    let price =
+        Decimal::from_str(value).map_err(|_| format!("{} is not a decimal: {:?}", field, value))?;
+    if price.is_sign_negative() {
+        return Err(format!("{} must not be negative", field));
+    }
+    Ok(price)
+}
+
+fn parse_json(text: &str) -> Result<Vec<RawRow>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+    let arr = value
+        .as_array()
+        .ok_or("JSON import must be an array of pricing objects")?;
+    Ok(arr
+        .iter()
+        .map(|item| {
+            let field = |k: &str| match item.get(k) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(serde_json::Value::Number(n)) => n.to_string(),
+                _ => String::new(),
+            };
+            RawRow {
+                provider: field("provider"),
+                model_pattern: field("model_pattern"),
+                input_per_m: field("input_per_m"),
+                output_per_m: field("output_per_m"),
+            }
+        })
+        .collect())
+}
+
+fn parse_csv(text: &str) -> Result<Vec<RawRow>, String> {
+    let mut lines = text
+        .lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'));
+    let header = split_csv_line(lines.next().ok_or("empty CSV")?)?;
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let mut idx = [0usize; 4];
+    for (slot, col) in idx.iter_mut().zip(COLUMNS) {
+        *slot = header
+            .iter()
+            .position(|h| h == col)
+            .ok_or_else(|| format!("CSV header is missing column '{}'", col))?;
+    }
+
+    lines
+        .map(|line| {
+            let cells = split_csv_line(line)?;
+            let cell = |i: usize| cells.get(i).cloned().unwrap_or_default();
+            Ok(RawRow {
+                provider: cell(idx[0]),
+                model_pattern: cell(idx[1]),
+                input_per_m: cell(idx[2]),
+                output_per_m: cell(idx[3]),
+            })
+        })
+        .collect()
+}
+
+/// Split one CSV record. Supports double-quoted fields (so regex patterns
+/// like `{1,3}` survive) with `""` as an escaped quote.
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut cells = Vec::new();
+    let mut cur = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cur.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => cells.push(std::mem::take(&mut cur)),
+            _ => cur.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("unterminated quote in CSV line: {}", line));
+    }
+    cells.push(cur);
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_with_quoted_pattern() {
+        let csv = "model_pattern,provider,input_per_m,output_per_m\n\
+                   ^gpt-4o$,openai,2.50,10.00\n\
+                   \"^claude-3-[0-9]{1,2}.*\",Anthropic,$3,15\n";
+        let (rows, results) = parse(csv, true).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].provider, "anthropic");
+        assert_eq!(rows[1].model_pattern, "^claude-3-[0-9]{1,2}.*");
+        assert_eq!(rows[1].input_per_m, Decimal::from(3));
+        assert!(results.iter().all(|r| r.status == "valid"));
+    }
+
+    #[test]
+    fn test_parse_reports_per_row_errors() {
+        let csv = "provider,model_pattern,input_per_m,output_per_m\n\
+                   openai,gpt-4o,2.5,10\n\
+                   openai,gpt-4o,2.6,10\n\
+                   openai,gpt-4o-mini,abc,0.6\n\
+                   openai,(unclosed,1,1\n\
+                   openai,o1,-1,1\n";
+        let (rows, results) = parse(csv, true).unwrap();
+        assert_eq!(rows.len(), 1);
+        let errors: Vec<(usize, &str)> = results
+            .iter()
+            .filter_map(|r| r.error.as_deref().map(|e| (r.row, e)))
+            .collect();
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[0].0, 2);
+        assert!(errors[0].1.contains("duplicate"));
+        assert!(errors[1].1.contains("not a decimal"));
+        assert!(errors[2].1.contains("regex"));
+        assert!(errors[3].1.contains("negative"));
+    }
+
+    #[test]
+    fn test_parse_json_accepts_numbers_and_strings() {
+        let json = r#"[
+            {"provider": "openai", "model_pattern": "gpt-4o", "input_per_m": 2.5, "output_per_m": "10.00"}
+        ]"#;
+        let (rows, _) = parse(json, false).unwrap();
+        assert_eq!(rows[0].input_per_m, Decimal::from_str("2.5").unwrap());
+        assert_eq!(rows[0].output_per_m, Decimal::from_str("10.00").unwrap());
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_header_column() {
+        let err = parse("provider,model_pattern,input_per_m\nopenai,x,1\n", true).unwrap_err();
+        assert!(err.contains("output_per_m"));
+    }
+}
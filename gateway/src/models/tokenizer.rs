@@ -0,0 +1,256 @@
+//! Pre-flight prompt token estimation and per-model context windows.
+//!
+//! Estimates are heuristic (~4 characters per token plus per-message
+//! framing) — close enough to catch requests that will clearly overflow a
+//! model's context window without a tokenizer dependency per provider.
+//!
+//! Context windows come from a built-in table, overridable through the
+//! `model_context_windows` system setting (`PUT /settings`), e.g.
+//! `{"gpt-4o": 128000, "my-finetune": 32768}`. Keys match a model exactly or
+//! as a prefix; the longest match wins. Reloaded with the other settings
+//! caches every 60s by a background job in `main.rs`.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::store::postgres::PgStore;
+
+/// `system_settings` key holding context-window overrides.
+pub const SETTING_KEY: &str = "model_context_windows";
+
+/// Upper bound on override entries.
+pub const MAX_ENTRIES: usize = 1_000;
+
+/// Framing tokens per chat message (role, separators).
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Tokens that prime the assistant reply.
+const REPLY_PRIMING_TOKENS: u32 = 3;
+
+/// Built-in context windows by model-name prefix (longest prefix wins, see
+/// [`prefix_matches`]).
+const DEFAULT_CONTEXT_WINDOWS: &[(&str, u32)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1-mini", 128_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    ("claude", 200_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini-1.5-flash", 1_048_576),
+    ("gemini-2.0", 1_048_576),
+    ("gemini-2.5", 1_048_576),
+    ("mistral-large", 128_000),
+    ("llama-3.1", 128_000),
+    ("llama-3.3", 128_000),
+];
+
+/// Rough token count for a piece of text (~4 characters per token).
+pub fn estimate_text_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
+
+/// Estimated tokens for one chat message: text content (string or parts),
+/// tool calls and tool results, plus framing.
+pub fn estimate_message_tokens(message: &Value) -> u32 {
+    let content = match message.get("content") {
+        Some(Value::String(s)) => estimate_text_tokens(s),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .map(|p| match p.get("text").and_then(Value::as_str) {
+                Some(text) => estimate_text_tokens(text),
+                // Images, tool_use/tool_result blocks etc: count their JSON.
+                None => estimate_text_tokens(&p.to_string()),
+            })
+            .sum(),
+        _ => 0,
+    };
+    let tool_calls = message
+        .get("tool_calls")
+        .map_or(0, |tc| estimate_text_tokens(&tc.to_string()));
+    content + tool_calls + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Estimated prompt tokens for a chat request body: messages, an Anthropic
+/// style top-level `system`, and tool definitions.
+pub fn estimate_prompt_tokens(body: &Value) -> u32 {
+    let messages: u32 = body
+        .get("messages")
+        .and_then(Value::as_array)
+        .map_or(0, |m| m.iter().map(estimate_message_tokens).sum());
+    let system = match body.get("system") {
+        Some(Value::String(s)) => estimate_text_tokens(s),
+        Some(other @ Value::Array(_)) => estimate_text_tokens(&other.to_string()),
+        _ => 0,
+    };
+    let tools = body
+        .get("tools")
+        .map_or(0, |t| estimate_text_tokens(&t.to_string()));
+    messages + system + tools + REPLY_PRIMING_TOKENS
+}
+
+/// Completion tokens the client reserved (`max_tokens` and aliases), 0 if none.
+pub fn requested_output_tokens(body: &Value) -> u32 {
+    ["max_tokens", "max_completion_tokens", "max_output_tokens"]
+        .iter()
+        .find_map(|k| body.get(*k).and_then(Value::as_u64))
+        .map_or(0, |n| n.min(u32::MAX as u64) as u32)
+}
+
+/// Whether `prefix` names `model` or a variant of it: the prefix must end
+/// where a name segment ends, so `gpt-4` covers `gpt-4-0613` but not `gpt-4o`
+/// or `gpt-4.5-preview`.
+fn prefix_matches(prefix: &str, model: &str) -> bool {
+    model
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', ':', '@', '/']))
+}
+
+/// Longest matching prefix in a model → window table, with its length.
+fn longest_prefix<'a>(
+    entries: impl Iterator<Item = (&'a str, u32)>,
+    model: &str,
+) -> Option<(usize, u32)> {
+    entries
+        .filter(|(prefix, _)| prefix_matches(prefix, model))
+        .map(|(prefix, window)| (prefix.len(), window))
+        .max_by_key(|(len, _)| *len)
+}
+
+/// Shared, cheaply-cloneable context-window table (overrides + built-ins).
+#[derive(Clone)]
+pub struct ContextWindowTable(Arc<RwLock<HashMap<String, u32>>>);
+
+impl Default for ContextWindowTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextWindowTable {
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(HashMap::new())))
+    }
+
+    /// Replace the override table.
+    pub async fn replace(&self, map: HashMap<String, u32>) {
+        *self.0.write().await = map;
+    }
+
+    /// Reload overrides from `system_settings`. An absent setting clears
+    /// them; an unreadable one keeps the previous overrides.
+    pub async fn reload(&self, db: &PgStore) {
+        match db.get_system_setting::<Value>(SETTING_KEY).await {
+            Ok(value) => match value.as_ref().map(parse_table).transpose() {
+                Ok(map) => self.replace(map.unwrap_or_default()).await,
+                Err(e) => tracing::error!("context_window: invalid {}: {}", SETTING_KEY, e),
+            },
+            Err(e) => tracing::error!("context_window: reload failed: {}", e),
+        }
+    }
+
+    /// Context window for `model`: the longest matching prefix across the
+    /// overrides and the built-in table, an override winning a tie. `None`
+    /// for unknown models.
+    pub async fn lookup(&self, model: &str) -> Option<u32> {
+        let overrides = self.0.read().await;
+        let custom = longest_prefix(overrides.iter().map(|(k, v)| (k.as_str(), *v)), model);
+        let builtin = longest_prefix(DEFAULT_CONTEXT_WINDOWS.iter().copied(), model);
+        match (custom, builtin) {
+            (Some((len, window)), Some((builtin_len, _))) if len >= builtin_len => Some(window),
+            (_, Some((_, window))) | (Some((_, window)), None) => Some(window),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Validate a `model_context_windows` setting value: a JSON object mapping
+/// model names (or prefixes) to positive token counts.
+pub fn parse_table(value: &Value) -> Result<HashMap<String, u32>, String> {
+    let obj = value
+        .as_object()
+        .ok_or("must be an object of model -> context window tokens")?;
+    if obj.len() > MAX_ENTRIES {
+        return Err(format!("too many entries (max {})", MAX_ENTRIES));
+    }
+    let mut map = HashMap::with_capacity(obj.len());
+    for (model, window) in obj {
+        if model.trim().is_empty() {
+            return Err("model names must be non-empty".into());
+        }
+        let window = window
+            .as_u64()
+            .filter(|w| (1..=u32::MAX as u64).contains(w))
+            .ok_or_else(|| format!("context window for '{}' must be a positive integer", model))?;
+        map.insert(model.clone(), window as u32);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_estimate_prompt_tokens_counts_parts_and_tools() {
+        let body = json!({
+            "messages": [
+                {"role": "system", "content": "abcdefgh"},
+                {"role": "user", "content": [{"type": "text", "text": "abcd"}]},
+            ],
+            "max_tokens": 256,
+        });
+        // (2 + 4) + (1 + 4) + 3 priming
+        assert_eq!(estimate_prompt_tokens(&body), 14);
+        assert_eq!(requested_output_tokens(&body), 256);
+
+        let with_tools = json!({"messages": [], "tools": [{"name": "x"}]});
+        assert!(estimate_prompt_tokens(&with_tools) > REPLY_PRIMING_TOKENS);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_prefers_overrides_and_longest_prefix() {
+        let table = ContextWindowTable::new();
+        assert_eq!(table.lookup("gpt-4o-mini-2024-07-18").await, Some(128_000));
+        assert_eq!(table.lookup("gpt-4-0613").await, Some(8_192));
+        assert_eq!(
+            table.lookup("claude-3-5-sonnet-latest").await,
+            Some(200_000)
+        );
+        assert_eq!(table.lookup("my-finetune").await, None);
+        // Prefixes end at a name segment: gpt-4 doesn't cover gpt-4.5.
+        assert_eq!(table.lookup("gpt-4.5-preview").await, None);
+        assert_eq!(table.lookup("gemini-2.5-pro").await, Some(1_048_576));
+
+        table
+            .replace(HashMap::from([
+                ("gpt-4o".into(), 64_000),
+                ("my-finetune".into(), 32_768),
+                ("gpt-4".into(), 16_384),
+            ]))
+            .await;
+        assert_eq!(table.lookup("gpt-4o-2024-08-06").await, Some(64_000));
+        assert_eq!(table.lookup("my-finetune").await, Some(32_768));
+        assert_eq!(table.lookup("gpt-4-0613").await, Some(16_384));
+        // A short override doesn't shadow a longer built-in entry.
+        assert_eq!(table.lookup("gpt-4-turbo").await, Some(128_000));
+        assert_eq!(table.lookup("gpt-4o-mini").await, Some(64_000));
+    }
+
+    #[test]
+    fn test_parse_table_rejects_bad_values() {
+        assert!(parse_table(&json!({"gpt-4o": 128000})).is_ok());
+        assert!(parse_table(&json!(["gpt-4o"])).is_err());
+        assert!(parse_table(&json!({"gpt-4o": 0})).is_err());
+        assert!(parse_table(&json!({"gpt-4o": "128k"})).is_err());
+        assert!(parse_table(&json!({" ": 1000})).is_err());
+    }
+}
@@ -53,6 +53,30 @@ impl SlackNotifier {
         tracing::info!("Sent Slack notification for approval {}", approval_id);
         Ok(())
     }
+
+    /// Send a plain operator alert (no-op when no webhook is configured).
+    pub async fn send_alert(&self, text: &str) -> anyhow::Result<()> {
+        let Some(url) = &self.webhook_url else {
+            return Ok(());
+        };
+
+        let resp = self
+            .client
+            .post(url)
+            .json(&SlackMessage {
+                text: text.to_string(),
+            })
+            .send()
+            .await
+            .context("failed to send slack alert")?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            anyhow::bail!("slack returned error: status={}, body={}", status, body);
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize)]
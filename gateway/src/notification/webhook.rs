@@ -7,6 +7,21 @@ use tracing::{debug, info, warn};
 
 // ── Webhook Event Types ───────────────────────────────────────
 
+/// Every `event_type` the gateway emits. Webhook subscriptions are validated
+/// against this list.
+pub const EVENT_TYPES: &[&str] = &[
+    "policy_violation",
+    "rate_limit_exceeded",
+    "spend_cap_exceeded",
+    "approval_requested",
+    "credential_decryption_failed",
+    "credential_auth_failed",
+    "anomaly_detected",
+    "budget_warning",
+    "budget_cap_exceeded",
+    "token_rotated",
+];
+
 /// A structured event payload sent to webhook endpoints.
 #[derive(Debug, Clone, Serialize)]
 pub struct WebhookEvent {
@@ -110,6 +125,75 @@ impl WebhookEvent {
         }
     }
 
+    /// A stored credential could not be decrypted — master key misconfiguration
+    /// or ciphertext corruption. Operator action is required.
+    pub fn credential_decryption_failed(
+        token_id: &str,
+        token_name: &str,
+        project_id: &str,
+        credential_id: &str,
+    ) -> Self {
+        Self {
+            event_type: "credential_decryption_failed".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            token_id: token_id.to_string(),
+            token_name: token_name.to_string(),
+            project_id: project_id.to_string(),
+            details: serde_json::json!({
+                "credential_id": credential_id,
+                "severity": "critical",
+                "hint": "Verify TRUEFLOW_MASTER_KEY matches the key the credential was stored with, or re-create the credential.",
+            }),
+        }
+    }
+
+    /// The upstream answered 401/403 to a request signed with a stored
+    /// credential — the key is likely invalid, expired or revoked.
+    pub fn credential_auth_failed(
+        token_id: &str,
+        token_name: &str,
+        project_id: &str,
+        credential_id: &str,
+        upstream_status: u16,
+    ) -> Self {
+        Self {
+            event_type: "credential_auth_failed".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            token_id: token_id.to_string(),
+            token_name: token_name.to_string(),
+            project_id: project_id.to_string(),
+            details: serde_json::json!({
+                "credential_id": credential_id,
+                "upstream_status": upstream_status,
+                "severity": "critical",
+                "hint": "Rotate the credential's API key with the provider and update it in the vault.",
+            }),
+        }
+    }
+
+    /// A token was rotated: `token_id` is the replacement, `rotated_from` the
+    /// old ID, which stays usable until `old_token_expires_at` when a grace
+    /// window was given.
+    pub fn token_rotated(
+        new_token_id: &str,
+        token_name: &str,
+        project_id: &str,
+        old_token_id: &str,
+        old_token_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        Self {
+            event_type: "token_rotated".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            token_id: new_token_id.to_string(),
+            token_name: token_name.to_string(),
+            project_id: project_id.to_string(),
+            details: serde_json::json!({
+                "rotated_from": old_token_id,
+                "old_token_expires_at": old_token_expires_at.map(|t| t.to_rfc3339()),
+            }),
+        }
+    }
+
     /// Anomaly detection alert — triggered when request velocity exceeds baseline.
     pub fn anomaly_detected(
         token_id: &str,
@@ -135,6 +219,23 @@ impl WebhookEvent {
     }
 }
 
+// ── Webhook Targets ──────────────────────────────────────────
+
+/// A project webhook loaded from the `webhooks` table.
+#[derive(Debug, Clone)]
+pub struct WebhookTarget {
+    pub url: String,
+    pub signing_secret: Option<String>,
+    /// Subscribed event types. Empty means every event.
+    pub events: Vec<String>,
+}
+
+impl WebhookTarget {
+    pub fn subscribes_to(&self, event_type: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event_type)
+    }
+}
+
 // ── HMAC Signing ─────────────────────────────────────────────
 
 /// Compute HMAC-SHA256 of `payload` using `secret`.
@@ -293,19 +394,28 @@ impl WebhookNotifier {
         });
     }
 
-    /// Dispatch a signed event to per-webhook DB records (URL + optional signing secret).
-    pub async fn dispatch_signed(&self, targets: &[(String, Option<String>)], event: WebhookEvent) {
+    /// Dispatch a signed event to project webhooks, skipping any whose
+    /// subscription does not include the event's type.
+    pub async fn dispatch_signed(&self, targets: &[WebhookTarget], event: WebhookEvent) {
+        let targets: Vec<WebhookTarget> = targets
+            .iter()
+            .filter(|t| t.subscribes_to(&event.event_type))
+            .cloned()
+            .collect();
         if targets.is_empty() {
-            debug!("dispatch_signed: no webhook targets, skipping");
+            debug!(event_type = %event.event_type, "dispatch_signed: no subscribed webhook targets, skipping");
             return;
         }
 
         let notifier = self.clone();
-        let targets = targets.to_vec();
 
         tokio::spawn(async move {
-            for (url, secret) in &targets {
-                if let Err(e) = notifier.send_signed(url, &event, secret.as_deref()).await {
+            for target in &targets {
+                let url = target.url.as_str();
+                if let Err(e) = notifier
+                    .send_signed(url, &event, target.signing_secret.as_deref())
+                    .await
+                {
                     warn!(url, error = %e, "signed webhook dispatch ultimately failed");
                 }
             }
@@ -344,6 +454,16 @@ mod tests {
         assert_eq!(event.details["window_secs"], 60);
     }
 
+    #[test]
+    fn test_token_rotated_event_type() {
+        let event = WebhookEvent::token_rotated("tok2", "my-token", "proj1", "tok1", None);
+        assert_eq!(event.event_type, "token_rotated");
+        assert_eq!(event.token_id, "tok2");
+        assert_eq!(event.details["rotated_from"], "tok1");
+        assert!(event.details["old_token_expires_at"].is_null());
+        assert!(EVENT_TYPES.contains(&event.event_type.as_str()));
+    }
+
     #[test]
     fn test_spend_cap_event_type() {
         let event =
@@ -352,6 +472,24 @@ mod tests {
         assert_eq!(event.details["reason"], "daily cap exceeded");
     }
 
+    #[test]
+    fn test_credential_decryption_failed_event_type() {
+        let event =
+            WebhookEvent::credential_decryption_failed("tok1", "my-token", "proj1", "cred1");
+        assert_eq!(event.event_type, "credential_decryption_failed");
+        assert_eq!(event.details["credential_id"], "cred1");
+        assert_eq!(event.details["severity"], "critical");
+    }
+
+    #[test]
+    fn test_credential_auth_failed_event_type() {
+        let event = WebhookEvent::credential_auth_failed("tok1", "my-token", "proj1", "cred1", 401);
+        assert_eq!(event.event_type, "credential_auth_failed");
+        assert_eq!(event.details["credential_id"], "cred1");
+        assert_eq!(event.details["upstream_status"], 401);
+        assert!(EVENT_TYPES.contains(&"credential_auth_failed"));
+    }
+
     #[test]
     fn test_event_serializes_to_json() {
         let event = WebhookEvent::policy_violation("t", "n", "p", "pol", "reason");
@@ -360,6 +498,24 @@ mod tests {
         assert!(json.contains("timestamp"));
     }
 
+    #[test]
+    fn test_target_subscription_filter() {
+        let all = WebhookTarget {
+            url: "https://example.com/hook".into(),
+            signing_secret: None,
+            events: vec![],
+        };
+        assert!(all.subscribes_to("policy_violation"));
+        assert!(all.subscribes_to("spend_cap_exceeded"));
+
+        let caps_only = WebhookTarget {
+            events: vec!["spend_cap_exceeded".into()],
+            ..all
+        };
+        assert!(caps_only.subscribes_to("spend_cap_exceeded"));
+        assert!(!caps_only.subscribes_to("policy_violation"));
+    }
+
     #[test]
     fn test_hmac_signature_deterministic() {
         let sig1 = hmac_sha256_hex("secret123", b"payload");
@@ -0,0 +1,240 @@
+//! Adaptive (AIMD) rate limiting driven by upstream latency.
+//!
+//! A token with `adaptive_rate_limit` configured gets an effective request
+//! limit that starts at `max_requests`. Every upstream response is fed back:
+//! a slow response (above the latency threshold) or a 429/5xx multiplies the
+//! limit by `decrease_factor`, a healthy one adds `increase_step`, bounded by
+//! `[min_requests, max_requests]`. Pushing less traffic at a struggling
+//! upstream lets it recover instead of piling on — the circuit breaker only
+//! reacts once requests are already failing.
+//!
+//! The controller state is per gateway replica; the request counter it is
+//! compared against is the shared sliding window in Redis.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Minimum spacing between multiplicative decreases, so a burst of slow
+/// responses to requests that were already in flight counts as one signal.
+const DECREASE_COOLDOWN: Duration = Duration::from_secs(2);
+
+/// Per-token AIMD settings, stored in `tokens.adaptive_rate_limit`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdaptiveRateLimitConfig {
+    /// Requests per window while the upstream is healthy (the ceiling).
+    pub max_requests: u64,
+    /// Floor the limit never drops below (default: 1).
+    #[serde(default = "default_min_requests")]
+    pub min_requests: u64,
+    /// Sliding window in seconds (default: 60).
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// Upstream latency (ms) above which a response counts as congestion.
+    /// When unset, `baseline_multiplier` × the model's p50 from the latency
+    /// cache is used; with neither, only 429/5xx responses shrink the limit.
+    #[serde(default)]
+    pub latency_threshold_ms: Option<u64>,
+    /// Multiplier over the model's p50 when `latency_threshold_ms` is unset (default: 2.0).
+    #[serde(default = "default_baseline_multiplier")]
+    pub baseline_multiplier: f64,
+    /// Added to the limit after each healthy response (default: 1).
+    #[serde(default = "default_increase_step")]
+    pub increase_step: u64,
+    /// Multiplied into the limit on congestion, in (0, 1) (default: 0.5).
+    #[serde(default = "default_decrease_factor")]
+    pub decrease_factor: f64,
+}
+
+fn default_min_requests() -> u64 {
+    1
+}
+
+fn default_window_secs() -> u64 {
+    60
+}
+
+fn default_baseline_multiplier() -> f64 {
+    2.0
+}
+
+fn default_increase_step() -> u64 {
+    1
+}
+
+fn default_decrease_factor() -> f64 {
+    0.5
+}
+
+impl AdaptiveRateLimitConfig {
+    /// Parse and validate a stored or submitted config.
+    pub fn from_value(value: &serde_json::Value) -> Result<Self, String> {
+        let cfg: Self = serde_json::from_value(value.clone()).map_err(|e| e.to_string())?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_requests == 0 {
+            return Err("max_requests must be positive".into());
+        }
+        if self.min_requests == 0 || self.min_requests > self.max_requests {
+            return Err("min_requests must be between 1 and max_requests".into());
+        }
+        if self.window_secs == 0 {
+            return Err("window_secs must be positive".into());
+        }
+        if self.decrease_factor <= 0.0 || self.decrease_factor >= 1.0 {
+            return Err("decrease_factor must be between 0 and 1".into());
+        }
+        if self.baseline_multiplier < 1.0 {
+            return Err("baseline_multiplier must be at least 1.0".into());
+        }
+        Ok(())
+    }
+
+    /// Latency threshold for this request: the explicit one, or the model's
+    /// p50 scaled by `baseline_multiplier`.
+    pub fn threshold_ms(&self, model_p50_ms: Option<f64>) -> Option<f64> {
+        self.latency_threshold_ms
+            .map(|t| t as f64)
+            .or_else(|| model_p50_ms.map(|p50| p50 * self.baseline_multiplier))
+    }
+}
+
+struct LimiterState {
+    limit: f64,
+    last_decrease: Option<Instant>,
+}
+
+/// Per-token AIMD controllers. Cheap to clone; clones share state.
+#[derive(Clone, Default)]
+pub struct AdaptiveLimiter {
+    tokens: Arc<DashMap<String, LimiterState>>,
+}
+
+impl AdaptiveLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current effective limit for a token, clamped to the config's bounds
+    /// (so lowering `max_requests` takes effect immediately).
+    pub fn current_limit(&self, token_id: &str, cfg: &AdaptiveRateLimitConfig) -> u64 {
+        self.tokens
+            .get(token_id)
+            .map(|s| clamp(s.limit, cfg))
+            .unwrap_or(cfg.max_requests)
+    }
+
+    /// Feed one upstream outcome into the controller and return the new limit.
+    pub fn observe(
+        &self,
+        token_id: &str,
+        cfg: &AdaptiveRateLimitConfig,
+        latency_ms: u64,
+        threshold_ms: Option<f64>,
+        upstream_overloaded: bool,
+    ) -> u64 {
+        let congested = upstream_overloaded || threshold_ms.is_some_and(|t| latency_ms as f64 > t);
+        let now = Instant::now();
+        let mut state = self
+            .tokens
+            .entry(token_id.to_string())
+            .or_insert_with(|| LimiterState {
+                limit: cfg.max_requests as f64,
+                last_decrease: None,
+            });
+
+        if congested {
+            let cooled = state
+                .last_decrease
+                .is_none_or(|at| now.duration_since(at) >= DECREASE_COOLDOWN);
+            if cooled {
+                state.limit = clamp(state.limit, cfg) as f64 * cfg.decrease_factor;
+                state.last_decrease = Some(now);
+            }
+        } else {
+            state.limit = clamp(state.limit, cfg) as f64 + cfg.increase_step as f64;
+        }
+        state.limit = state
+            .limit
+            .clamp(cfg.min_requests as f64, cfg.max_requests as f64);
+        clamp(state.limit, cfg)
+    }
+}
+
+fn clamp(limit: f64, cfg: &AdaptiveRateLimitConfig) -> u64 {
+    (limit.floor() as u64).clamp(cfg.min_requests, cfg.max_requests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> AdaptiveRateLimitConfig {
+        AdaptiveRateLimitConfig::from_value(&serde_json::json!({
+            "max_requests": 100,
+            "min_requests": 10,
+            "latency_threshold_ms": 1000
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_adaptive_limit_aimd() {
+        let limiter = AdaptiveLimiter::new();
+        let cfg = cfg();
+        let threshold = cfg.threshold_ms(None);
+        assert_eq!(limiter.current_limit("tok", &cfg), 100);
+
+        // Slow response halves the limit.
+        assert_eq!(limiter.observe("tok", &cfg, 2500, threshold, false), 50);
+        // A second slow response inside the cooldown doesn't halve again.
+        assert_eq!(limiter.observe("tok", &cfg, 2500, threshold, false), 50);
+        // Healthy responses recover additively.
+        assert_eq!(limiter.observe("tok", &cfg, 200, threshold, false), 51);
+        assert_eq!(limiter.current_limit("tok", &cfg), 51);
+
+        // Never below the floor.
+        for _ in 0..10 {
+            limiter.tokens.get_mut("tok").unwrap().last_decrease = None;
+            limiter.observe("tok", &cfg, 200, threshold, true);
+        }
+        assert_eq!(limiter.current_limit("tok", &cfg), 10);
+
+        // Never above the ceiling.
+        for _ in 0..200 {
+            limiter.observe("tok", &cfg, 200, threshold, false);
+        }
+        assert_eq!(limiter.current_limit("tok", &cfg), 100);
+    }
+
+    #[test]
+    fn test_adaptive_limit_threshold_from_baseline() {
+        let cfg =
+            AdaptiveRateLimitConfig::from_value(&serde_json::json!({"max_requests": 20})).unwrap();
+        assert_eq!(cfg.threshold_ms(Some(400.0)), Some(800.0));
+        assert_eq!(cfg.threshold_ms(None), None);
+
+        // No threshold and a healthy status: the limit only grows.
+        let limiter = AdaptiveLimiter::new();
+        assert_eq!(limiter.observe("tok", &cfg, 60_000, None, false), 20);
+        assert_eq!(limiter.observe("tok", &cfg, 100, None, true), 10);
+    }
+
+    #[test]
+    fn test_adaptive_limit_config_validation() {
+        for bad in [
+            serde_json::json!({"max_requests": 0}),
+            serde_json::json!({"max_requests": 10, "min_requests": 20}),
+            serde_json::json!({"max_requests": 10, "decrease_factor": 1.0}),
+            serde_json::json!({"max_requests": 10, "window_secs": 0}),
+            serde_json::json!({"max_requests": 10, "baseline_multiplier": 0.5}),
+            serde_json::json!({"min_requests": 1}),
+        ] {
+            assert!(AdaptiveRateLimitConfig::from_value(&bad).is_err(), "{bad}");
+        }
+    }
+}
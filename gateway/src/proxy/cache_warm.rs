@@ -0,0 +1,299 @@
+//! Response cache warming.
+//!
+//! With `TRUEFLOW_CACHE_WARM_THRESHOLD` set, every cache miss on a cacheable
+//! request is counted per cache key in a Redis sorted set bucketed by the
+//! cache TTL, and the request is kept (for two TTLs after its last miss) so
+//! it can be replayed.
+//! Keys missed at least `threshold` times over the current and previous
+//! bucket are "hot".
+//!
+//! Hot keys can be re-primed from `POST /api/v1/system/cache-warm`, or
+//! automatically by `jobs::cache_warmer` when `TRUEFLOW_CACHE_AUTO_WARM` is
+//! set. Warming replays the request through `proxy_handler` under the owning
+//! token, so policies, spend tracking, the audit log and the normal cache
+//! key/TTL all apply, and a warmed request is billed like any other.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, Method, Uri};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use super::response_cache::{self, DEFAULT_CACHE_TTL_SECS};
+use crate::cache::TieredCache;
+use crate::AppState;
+
+/// Requests larger than this are counted but not stored for replay.
+const MAX_STORED_BODY_BYTES: usize = 64 * 1024;
+
+/// Agent name warmed requests carry in the audit log.
+pub const WARMER_AGENT_NAME: &str = "trueflow-cache-warmer";
+
+/// A missed request, kept for replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmRequest {
+    pub token_id: String,
+    pub path: String,
+    pub body: serde_json::Value,
+}
+
+/// A hot cache key and its misses over the current and previous bucket.
+#[derive(Debug, Clone, Serialize)]
+pub struct HotKey {
+    pub cache_key: String,
+    pub misses: u64,
+}
+
+/// What warming one request did.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum WarmOutcome {
+    /// A fresh response was fetched and cached.
+    Warmed { cache_key: String },
+    /// The response was already cached; nothing was sent upstream.
+    AlreadyCached { cache_key: String },
+    /// The request can't be cached (streaming, high temperature, no model...).
+    Skipped { reason: String },
+    /// The upstream call or a policy failed; nothing was cached.
+    Failed { status: u16, reason: String },
+}
+
+fn bucket(now_secs: u64) -> u64 {
+    now_secs / DEFAULT_CACHE_TTL_SECS
+}
+
+fn miss_set_key(bucket: u64) -> String {
+    format!("cache_miss:hot:{}", bucket)
+}
+
+fn request_key(cache_key: &str) -> String {
+    format!("cache_miss:req:{}", cache_key)
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Count a cache miss and keep the request for replay. `body` is the
+/// client's body before policy transforms, since the replay is transformed
+/// again. Best-effort: Redis errors are logged and ignored.
+pub async fn record_miss(
+    cache: &TieredCache,
+    cache_key: &str,
+    token_id: &str,
+    path: &str,
+    body: &serde_json::Value,
+) {
+    let retention = DEFAULT_CACHE_TTL_SECS * 2;
+    let set_key = miss_set_key(bucket(now_secs()));
+    let mut conn = cache.redis();
+    let mut pipe = redis::pipe();
+    pipe.zincr(&set_key, cache_key, 1u64)
+        .ignore()
+        .expire(&set_key, retention as i64)
+        .ignore();
+    let stored = WarmRequest {
+        token_id: token_id.to_string(),
+        path: path.to_string(),
+        body: body.clone(),
+    };
+    match serde_json::to_string(&stored) {
+        Ok(json) if json.len() <= MAX_STORED_BODY_BYTES => {
+            pipe.cmd("SET")
+                .arg(request_key(cache_key))
+                .arg(json)
+                .arg("EX")
+                .arg(retention)
+                .ignore();
+        }
+        _ => {}
+    }
+    if let Err(e) = pipe.query_async::<_, ()>(&mut conn).await {
+        tracing::debug!(error = %e, "cache_warm: failed to record miss");
+    }
+}
+
+/// Keys with at least `threshold` misses over the current and previous
+/// bucket, most-missed first.
+pub async fn hot_keys(
+    cache: &TieredCache,
+    threshold: u64,
+    limit: usize,
+) -> anyhow::Result<Vec<HotKey>> {
+    let current = bucket(now_secs());
+    let mut conn = cache.redis();
+    let mut counts: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
+    for b in [current.saturating_sub(1), current] {
+        let entries: Vec<(String, f64)> = conn.zrange_withscores(miss_set_key(b), 0, -1).await?;
+        for (key, score) in entries {
+            *counts.entry(key).or_default() += score as u64;
+        }
+    }
+    let mut hot: Vec<HotKey> = counts
+        .into_iter()
+        .filter(|(_, misses)| *misses >= threshold.max(1))
+        .map(|(cache_key, misses)| HotKey { cache_key, misses })
+        .collect();
+    hot.sort_by(|a, b| {
+        b.misses
+            .cmp(&a.misses)
+            .then_with(|| a.cache_key.cmp(&b.cache_key))
+    });
+    hot.truncate(limit);
+    Ok(hot)
+}
+
+/// The request stored for a hot key, if it hasn't expired.
+pub async fn stored_request(cache: &TieredCache, cache_key: &str) -> Option<WarmRequest> {
+    let mut conn = cache.redis();
+    let raw: Option<String> = conn.get(request_key(cache_key)).await.ok()?;
+    serde_json::from_str(&raw?).ok()
+}
+
+/// Replay one request through the proxy to populate the cache. Requests
+/// already cached are not sent upstream.
+pub async fn warm_request(state: &Arc<AppState>, request: &WarmRequest) -> WarmOutcome {
+    let path = if request.path.starts_with('/') {
+        request.path.clone()
+    } else {
+        format!("/{}", request.path)
+    };
+    let Ok(uri) = path.parse::<Uri>() else {
+        return WarmOutcome::Skipped {
+            reason: format!("invalid path '{}'", request.path),
+        };
+    };
+    let Ok(auth) = HeaderValue::from_str(&format!("Bearer {}", request.token_id)) else {
+        return WarmOutcome::Skipped {
+            reason: "invalid token id".to_string(),
+        };
+    };
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", auth);
+    headers.insert("content-type", HeaderValue::from_static("application/json"));
+    headers.insert(
+        "x-trueflow-agent-name",
+        HeaderValue::from_static(WARMER_AGENT_NAME),
+    );
+
+    if response_cache::should_skip_cache(&headers, Some(&request.body), None) {
+        return WarmOutcome::Skipped {
+            reason: "request is not cacheable (streaming or temperature > 0.1)".to_string(),
+        };
+    }
+    // The key must match the one the proxy computes, ignored paths included.
+    let token = match state
+        .cache
+        .get_token_cached(&state.db, &request.token_id)
+        .await
+    {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            return WarmOutcome::Skipped {
+                reason: "token not found".to_string(),
+            }
+        }
+        Err(e) => {
+            return WarmOutcome::Skipped {
+                reason: e.to_string(),
+            }
+        }
+    };
+    let Some(cache_key) = response_cache::compute_cache_key_ignoring(
+        &request.token_id,
+        &request.body,
+        token.cache_key_ignore_paths.as_deref().unwrap_or_default(),
+    ) else {
+        return WarmOutcome::Skipped {
+            reason: "request body has no model".to_string(),
+        };
+    };
+    if response_cache::get_cached(&state.cache, &cache_key)
+        .await
+        .is_some()
+    {
+        return WarmOutcome::AlreadyCached { cache_key };
+    }
+
+    let body = match serde_json::to_vec(&request.body) {
+        Ok(b) => Bytes::from(b),
+        Err(e) => {
+            return WarmOutcome::Skipped {
+                reason: e.to_string(),
+            }
+        }
+    };
+    match super::handler::proxy_handler(
+        State(state.clone()),
+        None,
+        Method::POST,
+        uri,
+        headers,
+        body,
+    )
+    .await
+    {
+        Ok(resp) if resp.status().is_success() => WarmOutcome::Warmed { cache_key },
+        Ok(resp) => WarmOutcome::Failed {
+            status: resp.status().as_u16(),
+            reason: "upstream returned an error".to_string(),
+        },
+        Err(e) => {
+            let reason = e.to_string();
+            let resp = axum::response::IntoResponse::into_response(e);
+            WarmOutcome::Failed {
+                status: resp.status().as_u16(),
+                reason,
+            }
+        }
+    }
+}
+
+/// Re-prime the current hot keys that aren't cached. Returns one outcome per
+/// key whose request is still stored.
+pub async fn warm_hot_keys(
+    state: &Arc<AppState>,
+    threshold: u64,
+    limit: usize,
+) -> anyhow::Result<Vec<(HotKey, WarmOutcome)>> {
+    let mut results = Vec::new();
+    for hot in hot_keys(&state.cache, threshold, limit).await? {
+        let Some(request) = stored_request(&state.cache, &hot.cache_key).await else {
+            continue;
+        };
+        let outcome = warm_request(state, &request).await;
+        results.push((hot, outcome));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_warm_buckets_follow_ttl() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(DEFAULT_CACHE_TTL_SECS - 1), 0);
+        assert_eq!(bucket(DEFAULT_CACHE_TTL_SECS), 1);
+        assert_eq!(miss_set_key(7), "cache_miss:hot:7");
+        assert_eq!(request_key("llm_cache:abc"), "cache_miss:req:llm_cache:abc");
+    }
+
+    #[test]
+    fn test_cache_warm_outcome_shape() {
+        let v = serde_json::to_value(WarmOutcome::AlreadyCached {
+            cache_key: "llm_cache:x".into(),
+        })
+        .unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({"outcome": "already_cached", "cache_key": "llm_cache:x"})
+        );
+    }
+}
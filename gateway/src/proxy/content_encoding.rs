@@ -0,0 +1,141 @@
+//! Decoding of compressed upstream response bodies.
+//!
+//! The upstream client doesn't negotiate or decode compression, but some
+//! upstreams (Bedrock among them) compress anyway. Post-flight policies,
+//! response sanitization and usage extraction all parse the body as JSON, so
+//! the buffered (non-streaming) path decodes it first and forwards the
+//! decoded bytes without `Content-Encoding`.
+
+use std::io::Read;
+
+/// Upper bound on a decoded body, so a small compressed payload can't expand
+/// without limit in memory.
+pub const MAX_DECODED_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Decode `body` per its `Content-Encoding` header value. Stacked encodings
+/// (`gzip, br`) are undone in reverse order.
+///
+/// `Ok(None)` when there's nothing to decode: `identity`, or an encoding we
+/// don't support — such bodies are passed through untouched. `Err` if the
+/// body doesn't decode or exceeds [`MAX_DECODED_BYTES`].
+pub fn decode(content_encoding: &str, body: &[u8]) -> std::io::Result<Option<Vec<u8>>> {
+    let codings: Vec<String> = content_encoding
+        .split(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+        .filter(|c| !c.is_empty() && c != "identity")
+        .collect();
+    if codings.is_empty() || !codings.iter().all(|c| is_supported(c)) {
+        return Ok(None);
+    }
+    let mut decoded = body.to_vec();
+    for coding in codings.iter().rev() {
+        decoded = decode_one(coding, &decoded)?;
+    }
+    Ok(Some(decoded))
+}
+
+fn is_supported(coding: &str) -> bool {
+    matches!(coding, "gzip" | "x-gzip" | "deflate" | "br")
+}
+
+fn decode_one(coding: &str, body: &[u8]) -> std::io::Result<Vec<u8>> {
+    match coding {
+        "gzip" | "x-gzip" => read_limited(flate2::read::MultiGzDecoder::new(body)),
+        // `deflate` is zlib-wrapped per RFC 9110, but some servers send a raw
+        // deflate stream.
+        "deflate" => read_limited(flate2::read::ZlibDecoder::new(body))
+            .or_else(|_| read_limited(flate2::read::DeflateDecoder::new(body))),
+        "br" => read_limited(brotli::Decompressor::new(body, 4096)),
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unsupported content-encoding '{}'", other),
+        )),
+    }
+}
+
+fn read_limited(reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    reader.take(MAX_DECODED_BYTES + 1).read_to_end(&mut out)?;
+    if out.len() as u64 > MAX_DECODED_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("decoded body exceeds {} bytes", MAX_DECODED_BYTES),
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const PII_BODY: &str = r#"{"choices":[{"message":{"role":"assistant","content":"Reach Jane at jane.doe@example.com"}}]}"#;
+
+    fn gzip(body: &[u8]) -> Vec<u8> {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(body).unwrap();
+        enc.finish().unwrap()
+    }
+
+    #[test]
+    fn test_gzip_json_with_pii_is_redacted_after_decoding() {
+        let compressed = gzip(PII_BODY.as_bytes());
+
+        // On the compressed bytes the sanitizer sees nothing to redact.
+        let blind = crate::middleware::sanitize::sanitize_response(&compressed, "application/json");
+        assert!(blind.redacted_types.is_empty());
+
+        let decoded = decode("gzip", &compressed).unwrap().unwrap();
+        assert_eq!(decoded, PII_BODY.as_bytes());
+        let result = crate::middleware::sanitize::sanitize_response(&decoded, "application/json");
+        assert!(result.redacted_types.iter().any(|t| t == "email"));
+        let body = String::from_utf8(result.body).unwrap();
+        assert!(!body.contains("jane.doe@example.com"));
+    }
+
+    #[test]
+    fn test_decode_deflate_and_brotli() {
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(PII_BODY.as_bytes()).unwrap();
+        let zlib = zlib.finish().unwrap();
+        assert_eq!(
+            decode("deflate", &zlib).unwrap().unwrap(),
+            PII_BODY.as_bytes()
+        );
+
+        let mut raw =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        raw.write_all(PII_BODY.as_bytes()).unwrap();
+        let raw = raw.finish().unwrap();
+        assert_eq!(
+            decode("deflate", &raw).unwrap().unwrap(),
+            PII_BODY.as_bytes()
+        );
+
+        let mut br = Vec::new();
+        brotli::CompressorWriter::new(&mut br, 4096, 5, 22)
+            .write_all(PII_BODY.as_bytes())
+            .unwrap();
+        assert_eq!(decode("br", &br).unwrap().unwrap(), PII_BODY.as_bytes());
+
+        // Stacked: gzip applied first, then br.
+        let mut stacked = Vec::new();
+        brotli::CompressorWriter::new(&mut stacked, 4096, 5, 22)
+            .write_all(&gzip(PII_BODY.as_bytes()))
+            .unwrap();
+        assert_eq!(
+            decode("gzip, br", &stacked).unwrap().unwrap(),
+            PII_BODY.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_unknown_or_identity_encoding_passes_through() {
+        assert!(decode("identity", b"{}").unwrap().is_none());
+        assert!(decode("zstd", b"\x28\xb5\x2f\xfd").unwrap().is_none());
+        // One unknown coding in a stack leaves the whole body alone.
+        assert!(decode("gzip, compress", b"...").unwrap().is_none());
+        assert!(decode("gzip", b"not gzip").is_err());
+    }
+}
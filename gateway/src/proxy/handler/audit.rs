@@ -41,10 +41,36 @@ pub(crate) struct AuditBuilder {
     pub(super) finish_reason: Option<String>,
     pub(super) session_id: Option<String>,
     pub(super) parent_span_id: Option<String>,
+    pub(super) trace_id: Option<String>,
     pub(super) error_type: Option<String>,
     pub(super) is_streaming: bool,
     pub(super) ttft_ms: Option<u64>,
     pub(super) cache_hit: bool,
+    pub(super) translation_fallback: bool,
+    pub(super) provider: Option<String>,
+    pub(super) provider_hinted: bool,
+    pub(super) missing_properties: Option<Vec<String>>,
+    pub(super) param_defaults_applied: Option<Vec<String>>,
+    pub(super) body_fields_stripped: Option<Vec<String>>,
+    pub(super) model_downgraded_from: Option<String>,
+    pub(super) model_remapped_from: Option<String>,
+    pub(super) test_upstream_override: Option<String>,
+    pub(super) context_estimated_tokens: Option<u32>,
+    pub(super) context_window_tokens: Option<u32>,
+    pub(super) context_messages_trimmed: Option<u32>,
+    pub(super) feedback_score: Option<f64>,
+    pub(super) partial_content_len: Option<u32>,
+    pub(super) policy_eval_timings: Option<serde_json::Value>,
+    pub(super) migration_path: Option<String>,
+    pub(super) schema_coercions: Option<Vec<String>>,
+    pub(super) max_tokens_clamp: Option<serde_json::Value>,
+    pub(super) stale_cache_served: bool,
+    pub(super) request_cost_estimate_usd: Option<rust_decimal::Decimal>,
+    pub(super) request_cost_cap_exceeded: bool,
+    pub(super) rejected_credential_id: Option<Uuid>,
+    pub(super) json_mode_emulated: bool,
+    pub(super) capability_stripped: Option<serde_json::Value>,
+    pub(super) tokens_estimated: bool,
     // A/B experiment tracking
     pub(super) experiment_name: Option<String>,
     pub(super) variant_name: Option<String>,
@@ -54,7 +80,25 @@ pub(crate) struct AuditBuilder {
 
 impl AuditBuilder {
     pub(super) fn emit(self, state: &AppState) {
-        let entry = crate::models::audit::AuditEntry {
+        let entry = self.into_entry();
+        if state.config.access_log_json {
+            crate::middleware::access_log::emit(&entry);
+        }
+
+        // ── Observability Export ──────────────────────────────────────
+        // Fan out to Prometheus, Langfuse, and DataDog (non-blocking).
+        state.observer.record(&entry);
+
+        crate::middleware::audit::dispatch(
+            state.db.pool().clone(),
+            state.payload_store.clone(),
+            &state.audit_sinks,
+            entry,
+        );
+    }
+
+    fn into_entry(self) -> crate::models::audit::AuditEntry {
+        crate::models::audit::AuditEntry {
             request_id: self.req_id.unwrap_or_else(Uuid::new_v4),
             project_id: self.project_id.unwrap_or_default(),
             token_id: self.token_id,
@@ -93,27 +137,52 @@ impl AuditBuilder {
             finish_reason: self.finish_reason,
             session_id: self.session_id,
             parent_span_id: self.parent_span_id,
+            trace_id: self.trace_id,
             error_type: self.error_type,
             is_streaming: self.is_streaming,
             ttft_ms: self.ttft_ms,
             cache_hit: self.cache_hit,
+            translation_fallback: self.translation_fallback,
+            provider: self.provider,
+            provider_hinted: self.provider_hinted,
+            missing_properties: self.missing_properties,
+            param_defaults_applied: self.param_defaults_applied,
+            body_fields_stripped: self.body_fields_stripped,
+            model_downgraded_from: self.model_downgraded_from,
+            model_remapped_from: self.model_remapped_from,
+            test_upstream_override: self.test_upstream_override,
+            context_estimated_tokens: self.context_estimated_tokens,
+            context_window_tokens: self.context_window_tokens,
+            context_messages_trimmed: self.context_messages_trimmed,
+            feedback_score: self.feedback_score,
+            partial_content_len: self.partial_content_len,
+            policy_eval_timings: self.policy_eval_timings,
+            migration_path: self.migration_path,
+            schema_coercions: self.schema_coercions,
+            max_tokens_clamp: self.max_tokens_clamp,
+            stale_cache_served: self.stale_cache_served,
+            request_cost_estimate_usd: self.request_cost_estimate_usd,
+            request_cost_cap_exceeded: self.request_cost_cap_exceeded,
+            rejected_credential_id: self.rejected_credential_id,
+            json_mode_emulated: self.json_mode_emulated,
+            capability_stripped: self.capability_stripped,
+            tokens_estimated: self.tokens_estimated,
             experiment_name: self.experiment_name,
             variant_name: self.variant_name,
             custom_properties: self.custom_properties,
             payload_url: None, // set by audit middleware after potential offload
-        };
-        // ── Observability Export ──────────────────────────────────────
-        // Fan out to Prometheus, Langfuse, and DataDog (non-blocking).
-        state.observer.record(&entry);
-
-        crate::middleware::audit::log_async(
-            state.db.pool().clone(),
-            state.payload_store.clone(),
-            entry,
-        );
+        }
     }
 }
 
+/// The client's trace context: the `traceparent` trace id, and the parent
+/// span from `traceparent` or else `X-Parent-Span-Id`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TraceContext {
+    pub trace_id: Option<String>,
+    pub parent_span_id: Option<String>,
+}
+
 /// Helper to create a pre-populated AuditBuilder from shared request context.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn base_audit(
@@ -132,7 +201,7 @@ pub(crate) fn base_audit(
     tenant_id: Option<String>,
     external_request_id: Option<String>,
     session_id: Option<String>,
-    parent_span_id: Option<String>,
+    trace: TraceContext,
     custom_properties: Option<serde_json::Value>,
 ) -> AuditBuilder {
     AuditBuilder {
@@ -151,8 +220,40 @@ pub(crate) fn base_audit(
         tenant_id,
         external_request_id,
         session_id,
-        parent_span_id,
+        parent_span_id: trace.parent_span_id,
+        trace_id: trace.trace_id,
         custom_properties,
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_records_split_variant() {
+        let variants: Vec<crate::models::policy::SplitVariant> =
+            serde_json::from_value(serde_json::json!([
+                {"weight": 1, "name": "control"},
+                {"weight": 1, "name": "llama", "upstream_url": "http://llama.internal/v1"}
+            ]))
+            .unwrap();
+        let req_id = Uuid::from_bytes([1; 16]);
+        let assignment = crate::proxy::split::assign(&variants, req_id).unwrap();
+        let mut upstream = None;
+        assignment.apply(None, &mut upstream, &mut None);
+
+        let entry = AuditBuilder {
+            req_id: Some(req_id),
+            upstream_url: upstream.clone().unwrap_or_default(),
+            experiment_name: Some("gpt-vs-llama".to_string()),
+            variant_name: assignment.variant.name.clone(),
+            ..Default::default()
+        }
+        .into_entry();
+        assert_eq!(entry.upstream_url, "http://llama.internal/v1");
+        assert_eq!(entry.experiment_name.as_deref(), Some("gpt-vs-llama"));
+        assert_eq!(entry.variant_name.as_deref(), Some("llama"));
+    }
+}
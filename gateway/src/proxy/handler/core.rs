@@ -15,24 +15,28 @@ use crate::middleware::pii::PiiDetector as _;
 use crate::models::cost::{self, extract_model, extract_usage};
 use crate::models::policy::{Action, RedactDirection, RedactOnMatch, TriggeredAction};
 use crate::proxy;
-use crate::vault::SecretStore;
 use crate::AppState;
 
 use super::audit::base_audit;
-use super::headers::{headers_to_json, headers_to_json_reqwest};
-use super::security::is_safe_webhook_url;
+use super::headers::{forward_trace_headers, headers_to_json, headers_to_json_reqwest};
+use super::hitl;
+use super::security::{active_test_upstream_override, is_safe_webhook_url};
 
 /// The main handler for all proxied requests.
-#[tracing::instrument(skip(state, headers, body), fields(req_id = %uuid::Uuid::new_v4()))]
+#[tracing::instrument(skip(state, request_id_ext, headers, body))]
 pub async fn proxy_handler(
     State(state): State<Arc<AppState>>,
+    request_id_ext: Option<axum::Extension<crate::RequestId>>,
     method: Method,
     uri: Uri,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Response, AppError> {
     let start = Instant::now();
-    let request_id = Uuid::new_v4();
+    // Reuse the middleware's ID so the audit row, X-Request-Id and log span agree
+    let request_id = request_id_ext
+        .map(|axum::Extension(crate::RequestId(id))| id)
+        .unwrap_or_else(Uuid::new_v4);
 
     // Copy agent name header before consuming request
     let agent_name = headers
@@ -110,6 +114,15 @@ pub async fn proxy_handler(
         .and_then(|v| v.to_str().ok())
         .map(String::from);
 
+    // Client-reported quality score, compared across experiment variants.
+    // `X-AILink-Feedback-Score` is accepted as a legacy alias.
+    let feedback_score: Option<f64> = headers
+        .get("x-trueflow-feedback-score")
+        .or_else(|| headers.get("x-ailink-feedback-score"))
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite());
+
     // ── Phase 6: Custom properties ────────────────────────────
     // X-Properties: {"env":"prod","run_id":"agent-run-42","customer":"acme"}
     // Arbitrary JSON key-values attached to every audit log for this request.
@@ -122,29 +135,27 @@ pub async fn proxy_handler(
     // W3C Trace Context: parse `traceparent` header if present.
     // Format: 00-{trace_id}-{parent_id}-{flags}
     // We prefer traceparent over x-parent-span-id when both are present.
-    let (w3c_trace_id, w3c_parent_id) = headers
-        .get("traceparent")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|tp| {
-            let parts: Vec<&str> = tp.splitn(4, '-').collect();
-            if parts.len() == 4 {
-                Some((parts[1].to_string(), parts[2].to_string()))
-            } else {
-                tracing::debug!(traceparent = %tp, "malformed traceparent header, ignoring");
-                None
-            }
-        })
-        .map(|(t, p)| (Some(t), Some(p)))
-        .unwrap_or((None, None));
+    let traceparent = headers.get("traceparent").and_then(|v| v.to_str().ok());
+    let (w3c_trace_id, w3c_parent_id) = match traceparent.map(super::headers::parse_traceparent) {
+        Some(Some((t, p))) => (Some(t), Some(p)),
+        Some(None) => {
+            tracing::debug!(traceparent = ?traceparent, "malformed traceparent header, ignoring");
+            (None, None)
+        }
+        None => (None, None),
+    };
 
-    let parent_span_id = w3c_parent_id.or_else(|| {
-        headers
-            .get("x-parent-span-id")
-            .and_then(|v| v.to_str().ok())
-            .map(String::from)
-    });
+    let trace_context = super::audit::TraceContext {
+        trace_id: w3c_trace_id.clone(),
+        parent_span_id: w3c_parent_id.or_else(|| {
+            headers
+                .get("x-parent-span-id")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        }),
+    };
 
-    if let (Some(ref tid), Some(ref pid)) = (&w3c_trace_id, &parent_span_id) {
+    if let (Some(ref tid), Some(ref pid)) = (&w3c_trace_id, &trace_context.parent_span_id) {
         tracing::debug!(
             trace_id = %tid,
             parent_span_id = %pid,
@@ -163,20 +174,38 @@ pub async fn proxy_handler(
         .unwrap_or("application/json")
         .to_string();
 
+    // Rejections before the token is fully checked have no audit entry, so
+    // they get their access log line here.
+    let log_rejection = |token: Option<&crate::store::postgres::TokenRow>, error: &str| {
+        if state.config.access_log_json {
+            middleware::access_log::emit_rejection(&middleware::access_log::Rejection {
+                request_id,
+                token_id: token.map(|t| t.id.as_str()),
+                project_id: token.map(|t| t.project_id),
+                method: method.as_str(),
+                path: uri.path(),
+                latency_ms: start.elapsed().as_millis() as u64,
+                error,
+            });
+        }
+    };
+
     // -- 1. Extract virtual token --
-    let token_str = extract_bearer_token(&headers)?;
+    let token_str =
+        extract_bearer_token(&headers).inspect_err(|_| log_rejection(None, "token_not_found"))?;
 
     // -- 2. Resolve token --
     let token = state
-        .db
-        .get_token(&token_str)
+        .cache
+        .get_token_cached(&state.db, &token_str)
         .await
         .map_err(AppError::Internal)?
-        .ok_or(AppError::TokenNotFound)?;
+        .filter(|t| t.is_active)
+        .ok_or(AppError::TokenNotFound)
+        .inspect_err(|_| log_rejection(None, "token_not_found"))?;
 
-    if !token.is_active {
-        return Err(AppError::TokenNotFound);
-    }
+    // Estimate-only: evaluate without calling the upstream or consuming limits.
+    let estimate_only = super::estimate::is_estimate_only(&headers);
 
     // Check token expiration
     if let Some(exp) = token.expires_at {
@@ -186,10 +215,48 @@ pub async fn proxy_handler(
                 expired_at = %exp,
                 "proxy: token rejected — expired"
             );
+            log_rejection(Some(&token), "token_not_found");
             return Err(AppError::TokenNotFound);
         }
     }
 
+    // -- 2.0a Replay protection: unique nonce + fresh timestamp --
+    // Fails closed: without Redis a replay can't be ruled out.
+    if let Some(window) = token.replay_window_secs.filter(|w| *w > 0) {
+        let window_secs = window as u64;
+        let verified = middleware::replay::verify(
+            &headers,
+            &token.id,
+            window_secs,
+            chrono::Utc::now().timestamp(),
+            |key, ttl| {
+                let cache = state.cache.clone();
+                async move { cache.set_nx(&key, ttl).await }
+            },
+        )
+        .await;
+        match verified {
+            Ok(()) => {}
+            Err(middleware::replay::ReplayError::Rejected(reason)) => {
+                tracing::warn!(
+                    token_id = %token.id,
+                    reason = reason.code(),
+                    "proxy: request rejected by replay protection"
+                );
+                log_rejection(Some(&token), reason.code());
+                return Err(AppError::ReplayRejected {
+                    reason,
+                    window_secs,
+                });
+            }
+            Err(middleware::replay::ReplayError::Store(e)) => {
+                tracing::error!(token_id = %token.id, error = %e, "replay protection: nonce store unavailable");
+                log_rejection(Some(&token), "replay_store_unavailable");
+                return Err(AppError::Internal(e));
+            }
+        }
+    }
+
     // -- 2.1 Parse per-token circuit breaker configuration --
     let cb_config: crate::proxy::loadbalancer::CircuitBreakerConfig = token
         .circuit_breaker
@@ -206,13 +273,154 @@ pub async fn proxy_handler(
         .await
         .map_err(AppError::Internal)?;
 
+    // -- 3.0 Admission control: every upstream's circuit is open --
+    // Fail fast before policy evaluation, credential decryption and usage
+    // counters. Skipped for service-registry paths and tokens with routing
+    // policies, which may send the request somewhere else.
+    if cb_config.fail_fast
+        && !path.starts_with("/v1/proxy/services/")
+        && !policies.iter().any(|p| {
+            p.rules.iter().flat_map(|r| &r.then).any(|a| {
+                matches!(
+                    a,
+                    Action::DynamicRoute { .. }
+                        | Action::ConditionalRoute { .. }
+                        | Action::Split { .. }
+                )
+            })
+        })
+        && state.lb.all_circuits_open(&token.id, &cb_config)
+    {
+        tracing::warn!(token_id = %token.id, "admission: all upstream circuits open, rejecting early");
+        let mut audit = base_audit(
+            request_id,
+            token.project_id,
+            &token.id,
+            agent_name,
+            method.as_str(),
+            &path,
+            &token.upstream_url,
+            &policies,
+            false,
+            None,
+            None,
+            user_id,
+            tenant_id,
+            external_request_id,
+            session_id,
+            trace_context,
+            custom_properties,
+        );
+        audit.upstream_status = Some(503);
+        audit.error_type = Some("circuit_breaker_open".to_string());
+        audit.response_latency_ms = start.elapsed().as_millis() as u64;
+        audit.emit(&state);
+        return Err(AppError::AllUpstreamsExhausted {
+            details: Some(serde_json::json!({
+                "reason": "circuit_breaker_open",
+                "admission": "early",
+                "cooldown_secs": cb_config.recovery_cooldown_secs,
+            })),
+        });
+    }
+
     // -- 3.1 Parse request body as JSON (for body inspection) --
-    let mut parsed_body: Option<serde_json::Value> = if !body.is_empty() {
+    // Reject pathologically deep or wide bodies before the parser allocates
+    // them and policy evaluation walks them. A non-JSON body (multipart
+    // upload, raw audio) over the limits is forwarded unparsed instead.
+    let within_json_limits = match super::limits::check_json_limits(
+        &body,
+        state.config.max_json_depth,
+        state.config.max_json_array_len,
+    ) {
+        Ok(()) => true,
+        Err(_) if !middleware::compression::is_json_content_type(&original_content_type) => false,
+        Err((limit, max)) => {
+            tracing::warn!(
+                token_id = %token.id,
+                limit = limit.as_str(),
+                max,
+                "request body exceeds JSON structure limit"
+            );
+            let mut audit = base_audit(
+                request_id,
+                token.project_id,
+                &token.id,
+                agent_name,
+                method.as_str(),
+                &path,
+                &token.upstream_url,
+                &policies,
+                false,
+                None,
+                None,
+                user_id,
+                tenant_id,
+                external_request_id,
+                session_id,
+                trace_context,
+                custom_properties,
+            );
+            audit.upstream_status = Some(400);
+            audit.error_type = Some("payload_too_complex".to_string());
+            audit.response_latency_ms = start.elapsed().as_millis() as u64;
+            audit.emit(&state);
+            return Err(AppError::PayloadTooComplex {
+                limit: limit.as_str(),
+                max,
+            });
+        }
+    };
+    let mut parsed_body: Option<serde_json::Value> = if within_json_limits && !body.is_empty() {
         serde_json::from_slice(&body).ok()
     } else {
         None
     };
 
+    // -- 3.1a Content-category tagging (content_category_rules setting) --
+    // Observability only: matched categories go into the audit entry's
+    // custom_properties; nothing is blocked. Policies (`RequireProperties`)
+    // see only what the client sent.
+    let client_properties = custom_properties.clone();
+    let rules = state.content_categories.rules().await;
+    let custom_properties = match parsed_body.as_ref() {
+        Some(body_val) if !rules.is_empty() => {
+            let categories = rules.classify(body_val);
+            middleware::guardrail::category::tag_properties(custom_properties, categories)
+        }
+        _ => custom_properties,
+    };
+
+    // -- 3.1b Spend caps (budget_first) --
+    // Token spend cap + project hard cap. `enforcement_order: budget_first`
+    // runs them here, so an over-budget token fails before policy evaluation
+    // touches request or rate-limit counters; otherwise they run at 3.5.
+    let budget_first = middleware::spend::is_budget_first(token.enforcement_order.as_deref());
+    let deny_audit = || {
+        base_audit(
+            request_id,
+            token.project_id,
+            &token.id,
+            agent_name.clone(),
+            method.as_str(),
+            &path,
+            &token.upstream_url,
+            &policies,
+            false,
+            None,
+            None,
+            user_id.clone(),
+            tenant_id.clone(),
+            external_request_id.clone(),
+            session_id.clone(),
+            trace_context.clone(),
+            custom_properties.clone(),
+        )
+    };
+    if budget_first && !estimate_only {
+        enforce_budget_caps(&state, &token, start, &deny_audit).await?;
+    }
+
     // -- 3.2 Evaluate PRE-FLIGHT policies --
     // Load usage counters from Redis for condition evaluation
     let usage_counters = {
@@ -232,24 +440,30 @@ pub async fn proxy_handler(
         // 2. Incr Requests (Daily + Hourly)
         // We use a pipeline to minimize RTT.
         let mut pipe = redis::pipe();
-        pipe.get(&spend_daily_key)
-            .get(&spend_monthly_key)
-            .incr(&req_daily_key, 1)
-            .expire(&req_daily_key, 90000)
-            .ignore() // Daily + buffer
-            .incr(&req_hourly_key, 1)
-            .expire(&req_hourly_key, 4000)
-            .ignore(); // Hourly + buffer
+        pipe.get(&spend_daily_key).get(&spend_monthly_key);
+        if estimate_only {
+            // Read-only: conditions see the counts as if this request were counted.
+            pipe.get(&req_daily_key).get(&req_hourly_key);
+        } else {
+            pipe.incr(&req_daily_key, 1)
+                .expire(&req_daily_key, 90000)
+                .ignore() // Daily + buffer
+                .incr(&req_hourly_key, 1)
+                .expire(&req_hourly_key, 4000)
+                .ignore(); // Hourly + buffer
+        }
 
         let (spend_daily, spend_monthly, req_daily, req_hourly): (
             Option<f64>,
             Option<f64>,
-            u64,
-            u64,
+            Option<u64>,
+            Option<u64>,
         ) = pipe
             .query_async(&mut conn)
             .await
-            .unwrap_or((None, None, 0, 0));
+            .unwrap_or((None, None, None, None));
+        let req_daily = req_daily.unwrap_or(0) + estimate_only as u64;
+        let req_hourly = req_hourly.unwrap_or(0) + estimate_only as u64;
 
         if let Some(v) = spend_daily {
             counters.insert("spend_today_usd".to_string(), v);
@@ -279,6 +493,30 @@ pub async fn proxy_handler(
             .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
     };
 
+    // Pre-flight cost estimate for `request.estimated_cost_usd` conditions
+    // (e.g. gate RequireApproval on expensive requests).
+    let request_cost_estimate = {
+        let model = parsed_body
+            .as_ref()
+            .and_then(|b| b.get("model"))
+            .and_then(|m| m.as_str())
+            .unwrap_or("");
+        let provider = proxy::model_router::detect_provider(model, &token.upstream_url);
+        let (input, output) = cost::estimate_request_tokens(body.len(), parsed_body.as_ref());
+        let estimate = cost::calculate_cost_with_cache(
+            &state.pricing,
+            provider.pricing_name(),
+            model,
+            input,
+            output,
+        )
+        .await;
+        rust_decimal::prelude::ToPrimitive::to_f64(&estimate)
+    };
+
+    // Wall-clock budget for pre-flight evaluation (TRUEFLOW_POLICY_EVAL_BUDGET_MS).
+    let mut policy_budget = middleware::policy::EvalBudget::new(state.config.policy_eval_budget_ms);
+
     // Scope the RequestContext borrow so we can mutate parsed_body after evaluation
     let (outcome_actions, shadow_violations, pre_async_triggered) = {
         let ctx = RequestContext {
@@ -288,6 +526,7 @@ pub async fn proxy_handler(
             headers: &headers,
             body: parsed_body.as_ref(),
             body_size: body.len(),
+            estimated_cost_usd: request_cost_estimate,
             agent_name: agent_name.as_deref(),
             token_id: &token.id,
             token_name: &token.name,
@@ -299,7 +538,9 @@ pub async fn proxy_handler(
             usage: usage_counters.clone(),
         };
 
+        let match_started = Instant::now();
         let outcome = middleware::policy::evaluate_pre_flight(&policies, &ctx);
+        policy_budget.record("*", "conditions", match_started.elapsed());
         (
             outcome.actions,
             outcome.shadow_violations,
@@ -341,6 +582,7 @@ pub async fn proxy_handler(
                     fields: vec![],
                     on_match: RedactOnMatch::Redact,
                     nlp_backend: None,
+                    custom_regexes: vec![],
                 }),
                 "pii_block" => Some(Action::Redact {
                     direction: RedactDirection::Request,
@@ -351,6 +593,7 @@ pub async fn proxy_handler(
                     fields: vec![],
                     on_match: RedactOnMatch::Block,
                     nlp_backend: None,
+                    custom_regexes: vec![],
                 }),
                 "prompt_injection" => Some(Action::ContentFilter {
                     block_jailbreak: true,
@@ -392,6 +635,32 @@ pub async fn proxy_handler(
         outcome_actions = header_actions;
     }
 
+    // -- 3.2a Estimate-only response --
+    if estimate_only {
+        let estimate = super::estimate::estimate(
+            &state,
+            &token,
+            &outcome_actions,
+            parsed_body.as_ref(),
+            client_properties.as_ref(),
+            super::estimate::RateLimitSubject {
+                agent_name: agent_name.as_deref(),
+                client_ip: client_ip_str.as_deref(),
+                user_id: user_id.as_deref(),
+            },
+        )
+        .await?;
+        tracing::debug!(
+            token_id = %token.id,
+            estimated_cost_usd = estimate.estimated_cost_usd,
+            would_be_denied = estimate.would_be_denied,
+            "estimate-only request answered without upstream call"
+        );
+        return Ok(axum::response::IntoResponse::into_response(axum::Json(
+            estimate,
+        )));
+    }
+
     let mut shadow_violations = shadow_violations;
 
     // -- 3.3 Execute enforced actions --
@@ -407,10 +676,21 @@ pub async fn proxy_handler(
     let mut variant_name: Option<String> = None;
     // DynamicRoute tracking — set by DynamicRoute action
     let mut dynamic_upstream_override: Option<String> = None;
+    // Split variant credential — replaces the routed credential below
+    let mut split_credential_override: Option<Uuid> = None;
     let mut dynamic_route_strategy: Option<String> = None;
     let mut dynamic_route_reason: Option<String> = None;
 
+    // Set when the budget cut off a `redact`, `content_filter` or deny-mode
+    // `external_guardrail`: the request then can't be forwarded as if it had
+    // been checked.
+    let mut security_step_abandoned = false;
+
     for triggered in &outcome_actions {
+        if policy_budget.exhausted() && !triggered.action.is_security_control() {
+            continue;
+        }
+        let step_started = Instant::now();
         match &triggered.action {
             // ── Allow ──
             Action::Allow => {
@@ -434,7 +714,7 @@ pub async fn proxy_handler(
                     tenant_id.clone(),
                     external_request_id.clone(),
                     session_id.clone(),
-                    parent_span_id.clone(),
+                    trace_context.clone(),
                     custom_properties.clone(),
                 );
                 audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
@@ -461,8 +741,7 @@ pub async fn proxy_handler(
                     &triggered.policy_name,
                     message,
                 );
-                let webhook_urls = state.config.webhook_urls.clone();
-                state.webhook.dispatch(&webhook_urls, webhook_event).await;
+                dispatch_webhook_event(&state, token.project_id, webhook_event);
                 tokio::spawn(async move {
                     let _ = state_clone
                         .db
@@ -489,36 +768,15 @@ pub async fn proxy_handler(
                 key,
             } => {
                 let window_secs = middleware::policy::parse_window_secs(window).unwrap_or(60);
-                // SEC: Include policy_id + window in key so each rate_limit policy
-                // gets its own independent counter. Without this, two policies on the
-                // same token share one counter and the stricter window resets the
-                // lenient one — a CLASS A bypass.
-                let policy_prefix = format!("rl:{}:{}s", triggered.policy_id, window_secs);
-                let rl_key = match key {
-                    crate::models::policy::RateLimitKey::PerToken => {
-                        format!("{}:tok:{}", policy_prefix, token.id)
-                    }
-                    crate::models::policy::RateLimitKey::PerAgent => {
-                        format!(
-                            "{}:agent:{}",
-                            policy_prefix,
-                            agent_name.as_deref().unwrap_or("unknown")
-                        )
-                    }
-                    crate::models::policy::RateLimitKey::PerIp => format!(
-                        "{}:ip:{}",
-                        policy_prefix,
-                        client_ip_str.as_deref().unwrap_or("unknown")
-                    ),
-                    crate::models::policy::RateLimitKey::PerUser => format!(
-                        "{}:user:{}",
-                        policy_prefix,
-                        user_id.as_deref().unwrap_or(&token.id)
-                    ),
-                    crate::models::policy::RateLimitKey::Global => {
-                        format!("{}:global", policy_prefix)
-                    }
-                };
+                let rl_key = policy_rate_limit_key(
+                    triggered.policy_id,
+                    window_secs,
+                    key,
+                    &token.id,
+                    agent_name.as_deref(),
+                    client_ip_str.as_deref(),
+                    user_id.as_deref(),
+                );
                 // Use sliding window to prevent 2x burst at window boundaries
                 let count = state
                     .cache
@@ -543,7 +801,7 @@ pub async fn proxy_handler(
                         tenant_id.clone(),
                         external_request_id.clone(),
                         session_id.clone(),
-                        parent_span_id.clone(),
+                        trace_context.clone(),
                         custom_properties.clone(),
                     );
                     audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
@@ -575,8 +833,7 @@ pub async fn proxy_handler(
                             *max_requests,
                             window_secs,
                         );
-                    let webhook_urls = state.config.webhook_urls.clone();
-                    state.webhook.dispatch(&webhook_urls, webhook_event).await;
+                    dispatch_webhook_event(&state, token.project_id, webhook_event);
                     tokio::spawn(async move {
                         let _ = state_clone
                             .db
@@ -590,7 +847,10 @@ pub async fn proxy_handler(
                             .await;
                     });
 
-                    return Err(AppError::RateLimitExceeded { retry_after_secs: window_secs });
+                    return Err(AppError::RateLimitExceeded {
+                        retry_after_secs: window_secs,
+                        limit: Some(*max_requests),
+                    });
                 }
                 policy_rate_limited = true;
             }
@@ -618,51 +878,30 @@ pub async fn proxy_handler(
             } => {
                 if variants.is_empty() {
                     tracing::warn!(policy = %triggered.policy_name, "Split action has no variants, skipping");
+                } else if let Some(assignment) = proxy::split::assign(variants, request_id) {
+                    // Apply the chosen variant's body fields and upstream/credential overrides
+                    assignment.apply(
+                        parsed_body.as_mut(),
+                        &mut dynamic_upstream_override,
+                        &mut split_credential_override,
+                    );
+                    let chosen = assignment.variant;
+                    // Track for audit log
+                    experiment_name = experiment.clone();
+                    variant_name = chosen.name.clone();
+                    tracing::info!(
+                        policy = %triggered.policy_name,
+                        experiment = ?experiment,
+                        variant = ?chosen.name,
+                        weight = chosen.weight,
+                        total_weight = assignment.total_weight,
+                        bucket = assignment.bucket,
+                        fields = ?chosen.set_body_fields.keys().collect::<Vec<_>>(),
+                        upstream = ?chosen.upstream_url,
+                        "A/B split: assigned variant"
+                    );
                 } else {
-                    let total_weight: u32 = variants.iter().map(|v| v.weight).sum();
-                    if total_weight == 0 {
-                        tracing::warn!(policy = %triggered.policy_name, "Split action has zero total weight, skipping");
-                    } else {
-                        // Deterministic variant selection: derive a stable bucket from request_id bytes.
-                        // XOR-fold the UUID bytes into a u32 so the same request_id always picks
-                        // the same variant, ensuring stable assignment within a request.
-                        let req_bytes = request_id.as_bytes();
-                        let bucket_seed = req_bytes[0..4]
-                            .iter()
-                            .enumerate()
-                            .fold(0u32, |acc, (i, &b)| acc ^ ((b as u32) << (i * 8)));
-                        let bucket = bucket_seed % total_weight;
-                        let mut cumulative: u32 = 0;
-                        let mut chosen = &variants[0];
-                        for variant in variants {
-                            cumulative += variant.weight;
-                            if bucket < cumulative {
-                                chosen = variant;
-                                break;
-                            }
-                        }
-                        // Apply the chosen variant's body field overrides
-                        if let Some(ref mut body_val) = parsed_body {
-                            if let Some(obj) = body_val.as_object_mut() {
-                                for (k, v) in &chosen.set_body_fields {
-                                    obj.insert(k.clone(), v.clone());
-                                }
-                            }
-                        }
-                        // Track for audit log
-                        experiment_name = experiment.clone();
-                        variant_name = chosen.name.clone();
-                        tracing::info!(
-                            policy = %triggered.policy_name,
-                            experiment = ?experiment,
-                            variant = ?chosen.name,
-                            weight = chosen.weight,
-                            total_weight,
-                            bucket,
-                            fields = ?chosen.set_body_fields.keys().collect::<Vec<_>>(),
-                            "A/B split: assigned variant"
-                        );
-                    }
+                    tracing::warn!(policy = %triggered.policy_name, "Split action has zero total weight, skipping");
                 }
             }
 
@@ -671,6 +910,7 @@ pub async fn proxy_handler(
                 strategy,
                 pool,
                 fallback,
+                complexity,
             } => {
                 let cb_cooldown = {
                     // Read CB cooldown from token's circuit breaker config (default 30s)
@@ -686,6 +926,8 @@ pub async fn proxy_handler(
                     strategy,
                     pool,
                     fallback.as_ref(),
+                    parsed_body.as_ref(),
+                    complexity.as_ref(),
                     &state.pricing,
                     &state.latency,
                     &state.lb,
@@ -723,6 +965,8 @@ pub async fn proxy_handler(
             Action::Throttle { delay_ms } => {
                 tracing::info!(delay_ms = delay_ms, policy = %triggered.policy_name, "throttling request");
                 tokio::time::sleep(Duration::from_millis(*delay_ms)).await;
+                // A deliberate delay isn't slow evaluation.
+                policy_budget.credit(Duration::from_millis(*delay_ms));
             }
 
             // ── HITL (handled below after all other pre-flight checks) ──
@@ -787,7 +1031,28 @@ pub async fn proxy_handler(
             // ── Content Filter (Prompt Guardrails) ──
             Action::ContentFilter { .. } => {
                 if let Some(ref body_val) = parsed_body {
-                    let result = middleware::guardrail::check_content(body_val, &triggered.action);
+                    let result = if policy_budget.is_enabled() {
+                        // On a blocking thread so a pathological pattern can be
+                        // abandoned at the budget instead of stalling the request.
+                        let (body_copy, action) = (body_val.clone(), triggered.action.clone());
+                        let task = tokio::task::spawn_blocking(move || {
+                            middleware::guardrail::check_content(&body_copy, &action)
+                        });
+                        let Some(joined) = policy_budget.run(task).await else {
+                            policy_budget.record(
+                                &triggered.policy_name,
+                                triggered.action.kind(),
+                                step_started.elapsed(),
+                            );
+                            security_step_abandoned = true;
+                            continue;
+                        };
+                        joined.map_err(|e| {
+                            AppError::Internal(anyhow::anyhow!("content filter task failed: {}", e))
+                        })?
+                    } else {
+                        middleware::guardrail::check_content(body_val, &triggered.action)
+                    };
                     if result.blocked {
                         let reason = result
                             .reason
@@ -828,14 +1093,27 @@ pub async fn proxy_handler(
             Action::Redact { nlp_backend, .. } => {
                 if let Some(ref mut body_val) = parsed_body {
                     let action_clone = triggered.action.clone();
-                    let mut body_owned = std::mem::take(body_val);
-                    let (returned_body, result) = tokio::task::spawn_blocking(move || {
+                    // Under a budget the task may be abandoned, so it works on a copy.
+                    let mut body_owned = if policy_budget.is_enabled() {
+                        body_val.clone()
+                    } else {
+                        std::mem::take(body_val)
+                    };
+                    let task = tokio::task::spawn_blocking(move || {
                         let r =
                             middleware::redact::apply_redact(&mut body_owned, &action_clone, true);
                         (body_owned, r)
-                    })
-                    .await
-                    .map_err(|e| {
+                    });
+                    let Some(joined) = policy_budget.run(task).await else {
+                        policy_budget.record(
+                            &triggered.policy_name,
+                            triggered.action.kind(),
+                            step_started.elapsed(),
+                        );
+                        security_step_abandoned = true;
+                        continue;
+                    };
+                    let (returned_body, result) = joined.map_err(|e| {
                         AppError::Internal(anyhow::anyhow!("redact task failed: {}", e))
                     })?;
                     *body_val = returned_body;
@@ -953,11 +1231,19 @@ pub async fn proxy_handler(
                 );
             }
 
-            // ValidateSchema only applies post-flight (response phase) — skip in pre-flight
-            Action::ValidateSchema { .. } => {
+            // InjectResponseField only applies post-flight (response phase) — skip in pre-flight
+            Action::InjectResponseField { .. } => {
+                tracing::debug!(
+                    policy = %triggered.policy_name,
+                    "InjectResponseField is a response-phase action, skipping in pre-flight"
+                );
+            }
+
+            // ValidateSchema / CoerceResponseSchema only apply post-flight — skip in pre-flight
+            Action::ValidateSchema { .. } | Action::CoerceResponseSchema { .. } => {
                 tracing::debug!(
                     policy = %triggered.policy_name,
-                    "ValidateSchema is a response-phase action, skipping in pre-flight"
+                    "schema action is response-phase only, skipping in pre-flight"
                 );
             }
 
@@ -976,15 +1262,29 @@ pub async fn proxy_handler(
                 // check_with_timeout wraps the vendor call in a tokio::time::timeout (default 5s,
                 // configurable via TRUEFLOW_GUARDRAIL_TIMEOUT_SECS). On expiry it returns Err(...)
                 // which falls through to the fail-open branch below — capping worst-case latency.
-                match middleware::external_guardrail::check_with_timeout(
-                    vendor,
-                    endpoint,
-                    api_key_env.as_deref(),
-                    *threshold,
-                    &text,
-                )
-                .await
-                {
+                let Some(check) = policy_budget
+                    .run(middleware::external_guardrail::check_with_timeout(
+                        vendor,
+                        endpoint,
+                        api_key_env.as_deref(),
+                        *threshold,
+                        &text,
+                    ))
+                    .await
+                else {
+                    // Cut off by the budget, not a vendor failure: a deny-mode
+                    // guardrail that never answered must not fail open.
+                    policy_budget.record(
+                        &triggered.policy_name,
+                        triggered.action.kind(),
+                        step_started.elapsed(),
+                    );
+                    if on_fail != "log" {
+                        security_step_abandoned = true;
+                    }
+                    continue;
+                };
+                match check {
                     Ok(result) if result.blocked => {
                         tracing::warn!(
                             policy = %triggered.policy_name,
@@ -1010,7 +1310,7 @@ pub async fn proxy_handler(
                                 tenant_id.clone(),
                                 external_request_id.clone(),
                                 session_id.clone(),
-                                parent_span_id.clone(),
+                                trace_context.clone(),
                                 custom_properties.clone(),
                             );
                             audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
@@ -1076,7 +1376,7 @@ pub async fn proxy_handler(
                             tenant_id.clone(),
                             external_request_id.clone(),
                             session_id.clone(),
-                            parent_span_id.clone(),
+                            trace_context.clone(),
                             custom_properties.clone(),
                         );
                         audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
@@ -1092,8 +1392,155 @@ pub async fn proxy_handler(
                     }
                 }
             }
+
+            // ── RequireProperties: enforce X-Properties metadata schema ──
+            Action::RequireProperties {
+                required_keys,
+                allowed_values,
+            } => {
+                let offending = middleware::engine::evaluate_required_properties(
+                    client_properties.as_ref(),
+                    required_keys,
+                    allowed_values.as_ref(),
+                );
+                if !offending.is_empty() {
+                    let reason = format!(
+                        "X-Properties missing or invalid keys: {}",
+                        offending.join(", ")
+                    );
+                    tracing::warn!(
+                        policy = %triggered.policy_name,
+                        keys = ?offending,
+                        "RequireProperties: request metadata rejected"
+                    );
+                    let mut audit = base_audit(
+                        request_id,
+                        token.project_id,
+                        &token.id,
+                        agent_name,
+                        method.as_str(),
+                        &path,
+                        &token.upstream_url,
+                        &policies,
+                        false,
+                        None,
+                        None,
+                        user_id.clone(),
+                        tenant_id.clone(),
+                        external_request_id.clone(),
+                        session_id.clone(),
+                        trace_context.clone(),
+                        custom_properties.clone(),
+                    );
+                    audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                        policy: triggered.policy_name.clone(),
+                        reason: reason.clone(),
+                    });
+                    audit.missing_properties = Some(offending);
+                    audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                    audit.emit(&state);
+                    return Err(AppError::PolicyDenied {
+                        policy: triggered.policy_name.clone(),
+                        reason,
+                    });
+                }
+            }
+
+            // ── KeywordBlock: exact-term deny-list ──
+            Action::KeywordBlock { message, .. } => {
+                if let Some(term) = parsed_body.as_ref().and_then(|b| {
+                    middleware::guardrail::keyword::find_blocked_term(b, &triggered.action)
+                }) {
+                    tracing::warn!(
+                        policy = %triggered.policy_name,
+                        term = %term,
+                        "keyword block: request denied"
+                    );
+                    // A custom message hides which term matched.
+                    let (reason, matched) = match message {
+                        Some(msg) => (msg.clone(), Vec::new()),
+                        None => (
+                            format!("request contains blocked term '{}'", term),
+                            vec![term],
+                        ),
+                    };
+                    return Err(AppError::ContentBlocked {
+                        reason: reason.clone(),
+                        details: Some(serde_json::json!({
+                            "policy": triggered.policy_name,
+                            "reason": reason,
+                            "matched_patterns": matched,
+                        })),
+                    });
+                }
+            }
+        }
+        policy_budget.record(
+            &triggered.policy_name,
+            triggered.action.kind(),
+            step_started.elapsed(),
+        );
+    }
+
+    // -- 3.3a Policy evaluation budget --
+    // Fail-open forwards the request with the remaining non-security actions
+    // skipped; fail-closed denies it, as does a redaction, content filter or
+    // deny-mode external guardrail the budget cut off. Either way the audit names the slow step.
+    let mut policy_eval_timings: Option<serde_json::Value> = None;
+    if policy_budget.exhausted() {
+        let slowest = policy_budget.slowest().cloned();
+        tracing::warn!(
+            budget_ms = policy_budget.budget_ms(),
+            elapsed_ms = policy_budget.elapsed_ms(),
+            slowest_policy = ?slowest.as_ref().map(|t| &t.policy),
+            slowest_action = ?slowest.as_ref().map(|t| t.action),
+            fail_closed = state.config.policy_eval_fail_closed,
+            security_step_abandoned,
+            "pre-flight policy evaluation exceeded its budget"
+        );
+        if state.config.policy_eval_fail_closed || security_step_abandoned {
+            let reason = format!(
+                "policy evaluation exceeded its {}ms budget",
+                policy_budget.budget_ms()
+            );
+            let mut audit = base_audit(
+                request_id,
+                token.project_id,
+                &token.id,
+                agent_name,
+                method.as_str(),
+                &path,
+                &token.upstream_url,
+                &policies,
+                false,
+                None,
+                None,
+                user_id.clone(),
+                tenant_id.clone(),
+                external_request_id.clone(),
+                session_id.clone(),
+                trace_context.clone(),
+                custom_properties.clone(),
+            );
+            audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                policy: slowest
+                    .as_ref()
+                    .map_or_else(|| "*".to_string(), |t| t.policy.clone()),
+                reason,
+            });
+            audit.error_type = Some("policy_evaluation_timeout".to_string());
+            audit.policy_eval_timings = Some(policy_budget.to_json());
+            audit.response_latency_ms = start.elapsed().as_millis() as u64;
+            audit.emit(&state);
+            return Err(AppError::PolicyEvalTimeout {
+                policy: slowest.map(|t| t.policy),
+                budget_ms: policy_budget.budget_ms(),
+                elapsed_ms: policy_budget.elapsed_ms(),
+            });
         }
+        policy_eval_timings = Some(policy_budget.to_json());
     }
+
     if !policy_rate_limited && state.config.default_rate_limit > 0 {
         let rl_key = format!("rl:default:tok:{}", token.id);
         // Use sliding window to prevent 2x burst at window boundaries
@@ -1127,7 +1574,7 @@ pub async fn proxy_handler(
                 tenant_id.clone(),
                 external_request_id.clone(),
                 session_id.clone(),
-                parent_span_id.clone(),
+                trace_context.clone(),
                 custom_properties.clone(),
             );
             audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
@@ -1144,96 +1591,63 @@ pub async fn proxy_handler(
                 Some(shadow_violations)
             };
             audit.emit(&state);
-            return Err(AppError::RateLimitExceeded { retry_after_secs: state.config.default_rate_limit_window });
+            return Err(AppError::RateLimitExceeded {
+                retry_after_secs: state.config.default_rate_limit_window,
+                limit: Some(state.config.default_rate_limit),
+            });
         }
     }
 
-    // -- 3.5 Check Spend Cap --
-    if let Err(e) =
-        middleware::spend::check_spend_cap(&state.cache, state.db.pool(), &token.id).await
-    {
-        let mut audit = base_audit(
-            request_id,
-            token.project_id,
-            &token.id,
-            agent_name,
-            method.as_str(),
-            &path,
-            &token.upstream_url,
-            &policies,
-            false,
-            None,
-            None,
-            user_id.clone(),
-            tenant_id.clone(),
-            external_request_id.clone(),
-            session_id.clone(),
-            parent_span_id.clone(),
-            custom_properties.clone(),
-        );
-        audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
-            policy: "SpendCap".to_string(),
-            reason: e.to_string(),
-        });
-        audit.response_latency_ms = start.elapsed().as_millis() as u64;
-        audit.emit(&state);
-
-        // Webhook dispatch
-        let webhook_event = crate::notification::webhook::WebhookEvent::spend_cap_exceeded(
-            &token.id,
-            &token.name,
-            &token.project_id.to_string(),
-            &e.to_string(),
-        );
-        let webhook_urls = state.config.webhook_urls.clone();
-        state.webhook.dispatch(&webhook_urls, webhook_event).await;
+    // -- 3.4b Adaptive (AIMD) rate limit --
+    // Opt-in per token: the effective limit shrinks while the upstream is slow
+    // and recovers as it speeds up (see `proxy::adaptive_limit`).
+    let adaptive_cfg = token.adaptive_rate_limit.as_ref().and_then(|v| {
+        proxy::adaptive_limit::AdaptiveRateLimitConfig::from_value(v)
+            .map_err(|e| {
+                tracing::warn!(token_id = %token.id, error = %e, "invalid adaptive_rate_limit config, ignoring");
+            })
+            .ok()
+    });
+    let mut adaptive_limit = None;
+    if let Some(ref cfg) = adaptive_cfg {
+        let limit = state.adaptive_limits.current_limit(&token.id, cfg);
+        adaptive_limit = Some(limit);
+        let rl_key = format!("rl:adaptive:tok:{}", token.id);
+        let count = state
+            .cache
+            .increment_sliding_window(&rl_key, cfg.window_secs)
+            .await
+            .map_err(AppError::Internal)?;
 
-        return Err(AppError::SpendCapReached {
-            message: format!(
-                "Spend cap reached (USD): {}. Check your limits at the TrueFlow dashboard.",
-                e
-            ),
-        });
+        if count > limit {
+            tracing::warn!(
+                token_id = %token.id,
+                count = count,
+                limit = limit,
+                max_requests = cfg.max_requests,
+                window_secs = cfg.window_secs,
+                "adaptive rate limit exceeded"
+            );
+            let mut audit = deny_audit();
+            audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                policy: "AdaptiveRateLimit".to_string(),
+                reason: format!(
+                    "adaptive rate limit of {} req/{}s exceeded (ceiling {})",
+                    limit, cfg.window_secs, cfg.max_requests
+                ),
+            });
+            audit.response_latency_ms = start.elapsed().as_millis() as u64;
+            audit.emit(&state);
+            return Err(AppError::RateLimitExceeded {
+                retry_after_secs: cfg.window_secs,
+                limit: Some(limit),
+            });
+        }
     }
 
-    // -- 3.5b Project-Level Hard Cap --
-    // Uses a 60s Redis cache to avoid a DB round-trip on every request.
-    if crate::jobs::budget_checker::is_project_over_hard_cap_cached(
-        state.db.pool(),
-        &state.cache,
-        token.project_id,
-    )
-    .await
-    {
-        let mut audit = base_audit(
-            request_id,
-            token.project_id,
-            &token.id,
-            agent_name,
-            method.as_str(),
-            &path,
-            &token.upstream_url,
-            &policies,
-            false,
-            None,
-            None,
-            user_id.clone(),
-            tenant_id.clone(),
-            external_request_id.clone(),
-            session_id.clone(),
-            parent_span_id.clone(),
-            custom_properties.clone(),
-        );
-        audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
-            policy: "ProjectBudgetCap".to_string(),
-            reason: "Project hard spend cap exceeded".to_string(),
-        });
-        audit.response_latency_ms = start.elapsed().as_millis() as u64;
-        audit.emit(&state);
-
-        return Err(AppError::SpendCapReached {
-            message: "Project spending limit reached. Contact your administrator or review limits at the TrueFlow dashboard.".to_string(),
-        });
+    // -- 3.5 Spend caps (policies_first, the default order) --
+    if !budget_first {
+        enforce_budget_caps(&state, &token, start, &deny_audit).await?;
     }
 
     // -- 3.6 Session Lifecycle Guard --
@@ -1277,6 +1691,8 @@ pub async fn proxy_handler(
                                 "Session '{}' has exceeded its spend cap ({} USD)",
                                 sid, cap
                             ),
+                            cap: Some("session".to_string()),
+                            resets_at: None,
                         });
                     }
                 }
@@ -1322,8 +1738,7 @@ pub async fn proxy_handler(
                     result.baseline_mean,
                     result.threshold,
                 );
-                let webhook_urls = state.config.webhook_urls.clone();
-                state.webhook.dispatch(&webhook_urls, webhook_event).await;
+                dispatch_webhook_event(&state, token.project_id, webhook_event);
                 // NOTE: anomaly detection is informational — we do NOT block the request.
                 // Use rate limiting policies for enforcement.
             }
@@ -1334,6 +1749,18 @@ pub async fn proxy_handler(
         }
     }
 
+    // End-to-end budget (token.request_budget_secs) covers the HITL wait and the
+    // upstream call, so clients can bound worst-case latency.
+    let request_budget = token
+        .request_budget_secs
+        .filter(|b| *b > 0)
+        .map(|b| Duration::from_secs(b as u64));
+    let budget_remaining = || request_budget.map(|b| b.saturating_sub(start.elapsed()));
+    let budget_exceeded = || AppError::RequestBudgetExceeded {
+        budget_secs: request_budget.map(|b| b.as_secs()).unwrap_or_default(),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    };
+
     // -- 3.8 Handle HITL --
     if hitl_required {
         let hitl_start = Instant::now();
@@ -1411,6 +1838,8 @@ pub async fn proxy_handler(
             )
             .await
             .map_err(AppError::Internal)?;
+        let _hitl_pending =
+            middleware::metrics::HitlPendingGuard::new(&token.project_id.to_string());
 
         // Phase 5: Emit notifications
         // 1. Dashboard Notification
@@ -1443,13 +1872,7 @@ pub async fn proxy_handler(
             &token.upstream_url,
             parsed_body.clone(),
         );
-        let webhook_urls = state.config.webhook_urls.clone();
-        let webhook_notifier = state.webhook.clone();
-        tokio::spawn(async move {
-            webhook_notifier
-                .dispatch(&webhook_urls, webhook_event)
-                .await;
-        });
+        dispatch_webhook_event(&state, token.project_id, webhook_event);
 
         // 3. Send Slack notification (async)
         let notifier = state.notifier.clone();
@@ -1467,94 +1890,120 @@ pub async fn proxy_handler(
 
         let timeout_secs = middleware::policy::parse_window_secs(&hitl_timeout_str).unwrap_or(1800); // default 30m
 
+        // Never wait past the token's end-to-end budget.
+        let wait_duration = match budget_remaining() {
+            Some(remaining) => Duration::from_secs(timeout_secs).min(remaining),
+            None => Duration::from_secs(timeout_secs),
+        };
+
         // ── HITL: Dedicated-connection BLPOP for instant approval delivery ──
-        // We create a dedicated Redis connection (not the shared ConnectionManager)
-        // so BLPOP blocks only this connection without affecting the connection pool.
         // This gives us sub-millisecond notification latency vs the old 500ms LPOP polling.
+        // If the client disconnects mid-wait the whole wait is dropped: the
+        // dedicated connection closes and the approval is marked abandoned.
         let hitl_key = format!("hitl:decision:{}", approval_id);
-        let mut decision_opt: Option<String> = None;
-
-        // Try dedicated BLPOP first, fall back to LPOP polling if connection fails
-        let blpop_timeout = timeout_secs.min(1800) as f64; // Redis BLPOP timeout in seconds
-        let blpop_result: Result<Option<String>, ()> = async {
-            // Open a fresh, dedicated connection for the blocking call
-            let redis_url =
-                std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".into());
-            let client = redis::Client::open(redis_url.as_str()).map_err(|e| {
-                tracing::warn!("HITL: failed to create dedicated Redis client: {}", e);
-            })?;
-            let mut conn = client
-                .get_multiplexed_async_connection()
-                .await
-                .map_err(|e| {
-                    tracing::warn!("HITL: failed to open dedicated Redis connection: {}", e);
-                })?;
-
-            // BLPOP blocks until a value is pushed or timeout expires.
-            // Returns Option<(key, value)> tuple.
-            let result: redis::RedisResult<Option<(String, String)>> = redis::cmd("BLPOP")
-                .arg(&hitl_key)
-                .arg(blpop_timeout)
-                .query_async(&mut conn)
-                .await;
-
-            match result {
-                Ok(Some((_key, value))) => Ok(Some(value)),
-                Ok(None) => Ok(None), // timeout expired
-                Err(e) => {
-                    tracing::warn!("HITL BLPOP failed: {}", e);
-                    Err(())
-                }
-            }
-        }
-        .await;
-
-        match blpop_result {
-            Ok(Some(value)) => {
-                decision_opt = Some(value);
-            }
-            Ok(None) => {
-                // BLPOP timed out — no decision received
+        let on_abandon = {
+            let state = state.clone();
+            let project_id = token.project_id;
+            let mut audit = base_audit(
+                request_id,
+                token.project_id,
+                &token.id,
+                agent_name.clone(),
+                method.as_str(),
+                &path,
+                &token.upstream_url,
+                &policies,
+                true,
+                Some("abandoned".to_string()),
+                None,
+                user_id.clone(),
+                tenant_id.clone(),
+                external_request_id.clone(),
+                session_id.clone(),
+                trace_context.clone(),
+                custom_properties.clone(),
+            );
+            audit.policy_result = Some(crate::models::audit::PolicyResult::HitlTimeout);
+            audit.error_type = Some("client_disconnected".to_string());
+            move || {
+                tracing::info!(
+                    approval_id = %approval_id,
+                    hitl_wait_ms = hitl_start.elapsed().as_millis() as u64,
+                    "HITL: client disconnected during approval wait — abandoning"
+                );
+                audit.hitl_latency_ms = Some(hitl_start.elapsed().as_millis() as i32);
+                audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                audit.emit(&state);
+                tokio::spawn(async move {
+                    if let Err(e) = state
+                        .db
+                        .abandon_approval_request(approval_id, project_id)
+                        .await
+                    {
+                        tracing::error!(
+                            approval_id = %approval_id,
+                            "HITL: marking approval abandoned failed: {}",
+                            e
+                        );
+                    }
+                });
             }
-            Err(()) => {
-                // Redis failed — fall back to LPOP polling loop
-                tracing::info!("HITL: falling back to LPOP polling");
-                let mut redis_conn = state.cache.redis();
-                let start_wait = std::time::Instant::now();
-                let timeout_duration = std::time::Duration::from_secs(timeout_secs as u64);
-
-                while start_wait.elapsed() < timeout_duration {
-                    let lpop_result: redis::RedisResult<Option<String>> =
-                        redis::AsyncCommands::lpop(&mut redis_conn, &hitl_key, None).await;
+        };
 
-                    match lpop_result {
-                        Ok(Some(value)) => {
-                            decision_opt = Some(value);
-                            break;
-                        }
-                        Ok(None) => {
-                            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                        }
-                        Err(e) => {
-                            tracing::warn!("HITL LPOP fallback also failed: {}", e);
-                            break;
+        let decision = hitl::abandon_on_drop(
+            async {
+                // Try dedicated BLPOP first, fall back to LPOP polling if connection fails.
+                // Redis BLPOP timeout in seconds.
+                let blpop_timeout = wait_duration.as_secs_f64().min(1800.0);
+                let mut decision_opt: Option<String> = None;
+                match hitl::blpop_decision(&hitl_key, blpop_timeout).await {
+                    Ok(Some(value)) => {
+                        decision_opt = Some(value);
+                    }
+                    Ok(None) => {
+                        // BLPOP timed out — no decision received
+                    }
+                    Err(()) => {
+                        // Redis failed — fall back to LPOP polling loop
+                        tracing::info!("HITL: falling back to LPOP polling");
+                        let mut redis_conn = state.cache.redis();
+                        let start_wait = std::time::Instant::now();
+                        let timeout_duration = wait_duration;
+
+                        while start_wait.elapsed() < timeout_duration {
+                            let lpop_result: redis::RedisResult<Option<String>> =
+                                redis::AsyncCommands::lpop(&mut redis_conn, &hitl_key, None).await;
+
+                            match lpop_result {
+                                Ok(Some(value)) => {
+                                    decision_opt = Some(value);
+                                    break;
+                                }
+                                Ok(None) => {
+                                    tokio::time::sleep(Duration::from_millis(500)).await;
+                                }
+                                Err(e) => {
+                                    tracing::warn!("HITL LPOP fallback also failed: {}", e);
+                                    break;
+                                }
+                            }
                         }
                     }
                 }
-            }
-        }
 
-        let decision = match decision_opt {
-            Some(value) => value,
-            None => {
-                // All Redis paths exhausted — final DB check
-                state
-                    .db
-                    .get_approval_status(approval_id, token.project_id)
-                    .await
-                    .map_err(AppError::Internal)?
-            }
-        };
+                match decision_opt {
+                    Some(value) => Ok(value),
+                    // All Redis paths exhausted — final DB check
+                    None => state
+                        .db
+                        .get_approval_status(approval_id, token.project_id)
+                        .await
+                        .map_err(AppError::Internal),
+                }
+            },
+            on_abandon,
+        )
+        .await?;
 
         match decision.as_str() {
             "approved" => {
@@ -1586,7 +2035,7 @@ pub async fn proxy_handler(
                             tenant_id.clone(),
                             external_request_id.clone(),
                             session_id.clone(),
-                            parent_span_id.clone(),
+                            trace_context.clone(),
                             custom_properties.clone(),
                         );
                         audit.policy_result =
@@ -1611,6 +2060,39 @@ pub async fn proxy_handler(
                         return Err(AppError::Internal(e));
                     }
                 }
+                if budget_remaining().is_some_and(|r| r.is_zero()) {
+                    tracing::warn!(
+                        token_id = %token.id,
+                        approval_id = %approval_id,
+                        hitl_wait_ms = hitl_start.elapsed().as_millis() as u64,
+                        elapsed_ms = start.elapsed().as_millis() as u64,
+                        "HITL approved after the request budget was spent — not forwarding"
+                    );
+                    let mut audit = base_audit(
+                        request_id,
+                        token.project_id,
+                        &token.id,
+                        agent_name,
+                        method.as_str(),
+                        &path,
+                        &token.upstream_url,
+                        &policies,
+                        true,
+                        Some("approved_budget_exceeded".to_string()),
+                        Some(hitl_start.elapsed().as_millis() as i32),
+                        user_id.clone(),
+                        tenant_id.clone(),
+                        external_request_id.clone(),
+                        session_id.clone(),
+                        trace_context.clone(),
+                        custom_properties.clone(),
+                    );
+                    audit.policy_result = Some(crate::models::audit::PolicyResult::HitlTimeout);
+                    audit.error_type = Some("request_budget_exceeded".to_string());
+                    audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                    audit.emit(&state);
+                    return Err(budget_exceeded());
+                }
                 hitl_decision = Some("approved".to_string());
             }
             "rejected" => {
@@ -1631,7 +2113,7 @@ pub async fn proxy_handler(
                     tenant_id.clone(),
                     external_request_id.clone(),
                     session_id.clone(),
-                    parent_span_id.clone(),
+                    trace_context.clone(),
                     custom_properties.clone(),
                 );
                 audit.policy_result = Some(crate::models::audit::PolicyResult::HitlRejected);
@@ -1645,7 +2127,14 @@ pub async fn proxy_handler(
                 return Err(AppError::ApprovalRejected);
             }
             _ => {
-                hitl_decision = Some("expired".to_string());
+                // The wait may have been cut short by the request budget rather
+                // than the policy's approval timeout.
+                let budget_spent = budget_remaining().is_some_and(|r| r.is_zero());
+                hitl_decision = Some(if budget_spent {
+                    "budget_exceeded".to_string()
+                } else {
+                    "expired".to_string()
+                });
                 let mut audit = base_audit(
                     request_id,
                     token.project_id,
@@ -1662,10 +2151,13 @@ pub async fn proxy_handler(
                     tenant_id.clone(),
                     external_request_id.clone(),
                     session_id.clone(),
-                    parent_span_id.clone(),
+                    trace_context.clone(),
                     custom_properties.clone(),
                 );
                 audit.policy_result = Some(crate::models::audit::PolicyResult::HitlTimeout);
+                if budget_spent {
+                    audit.error_type = Some("request_budget_exceeded".to_string());
+                }
                 audit.response_latency_ms = start.elapsed().as_millis() as u64;
                 audit.shadow_violations = if shadow_violations.is_empty() {
                     None
@@ -1673,6 +2165,9 @@ pub async fn proxy_handler(
                     Some(shadow_violations.clone())
                 };
                 audit.emit(&state);
+                if budget_spent {
+                    return Err(budget_exceeded());
+                }
                 return Err(AppError::ApprovalTimeout);
             }
         }
@@ -1681,6 +2176,20 @@ pub async fn proxy_handler(
     }
 
     // -- 4. Resolve credential + upstream URL --
+    let migration_cfg = token.migration.as_ref().and_then(|v| {
+        proxy::migration::MigrationConfig::from_value(v)
+            .map_err(|e| {
+                tracing::warn!(token_id = %token.id, error = %e, "invalid migration config, ignoring");
+            })
+            .ok()
+    });
+    let mut migration_path_taken: Option<proxy::migration::MigrationPath> = None;
+    // Set when the LB had no available upstream (all open, or half-open with
+    // every probe slot taken); the CB pre-check then refuses the fallback.
+    let mut lb_exhausted = false;
+    // Half-open probe slot taken by the LB pick. Released on drop, so every
+    // return before the upstream call gives it back; see the send below.
+    let mut probe_slot: Option<proxy::loadbalancer::ProbeSlot> = None;
     // Service Registry: if path starts with /v1/proxy/services/{name}/...,
     // dynamically resolve the service and use its credential + base_url.
     let service_prefix = "/v1/proxy/services/";
@@ -1719,27 +2228,137 @@ pub async fn proxy_handler(
             });
         }
 
+        // Least-spend balances the credentials that will actually be charged,
+        // so targets without their own credential count as the token default.
+        let least_spend = cb_config.strategy == proxy::loadbalancer::LbStrategy::WeightedLeastSpend;
+        if least_spend {
+            for target in lb_upstreams.iter_mut() {
+                target.credential_id = target.credential_id.or(token.credential_id);
+            }
+        }
+
         // DEBUG LOGGING
         tracing::info!(token_id = %token.id, upstream_count = lb_upstreams.len(), "Calling LB select");
 
         // Always route through LB to ensure health tracking
-        if let Some(idx) = state.lb.select(&token.id, &lb_upstreams, &cb_config) {
+        let lb_p50s = if cb_config.strategy == proxy::loadbalancer::LbStrategy::WeightedLeastLatency
+        {
+            let urls: Vec<&str> = lb_upstreams.iter().map(|u| u.url.as_str()).collect();
+            state.latency.upstream_p50s(&urls).await
+        } else {
+            Vec::new()
+        };
+        let (effective_cred_id, effective_url) = if let Some((idx, slot)) = state
+            .lb
+            .select_with_probe(&token.id, &lb_upstreams, &cb_config, &lb_p50s)
+        {
+            probe_slot = slot;
             let target = &lb_upstreams[idx];
             tracing::info!(token_id = %token.id, selected_url = %target.url, "LB selected target");
+            if least_spend && dynamic_route_reason.is_none() {
+                dynamic_route_reason = Some(state.lb.spend_route_reason(&lb_upstreams, idx));
+            }
             // Use target-specific credential if set, otherwise token default
-            let effective_cred_id = target.credential_id.or(token.credential_id);
-            (effective_cred_id, target.url.clone(), path.clone())
+            (
+                target.credential_id.or(token.credential_id),
+                target.url.clone(),
+            )
         } else {
             // All upstreams unhealthy — fall back to primary as last resort
             tracing::error!("all upstreams unhealthy, falling back to primary");
-            (
-                token.credential_id,
-                token.upstream_url.clone(),
-                path.clone(),
-            )
+            lb_exhausted = true;
+            (token.credential_id, token.upstream_url.clone())
+        };
+
+        // -- 4.0a Gradual migration: send a stable share to the target --
+        match migration_cfg {
+            Some(ref cfg) => {
+                let sticky_key = session_id
+                    .clone()
+                    .or_else(|| user_id.clone())
+                    .unwrap_or_else(|| request_id.to_string());
+                let migration_path = cfg.route(&token.id, &sticky_key);
+                migration_path_taken = Some(migration_path);
+                if migration_path == proxy::migration::MigrationPath::Target {
+                    tracing::debug!(
+                        token_id = %token.id,
+                        percentage = cfg.percentage,
+                        "migration: request routed to target"
+                    );
+                    (
+                        cfg.target_credential_id.or(effective_cred_id),
+                        cfg.target_upstream_url.clone().unwrap_or(effective_url),
+                        path.clone(),
+                    )
+                } else {
+                    (effective_cred_id, effective_url, path.clone())
+                }
+            }
+            None => (effective_cred_id, effective_url, path.clone()),
+        }
+    };
+    // Policies are validated on write, but a credential can move or the rules
+    // predate the check: never inject one from outside the token's project.
+    let split_credential_override = match split_credential_override {
+        Some(cred_id) => match state
+            .db
+            .credential_in_project(cred_id, token.project_id)
+            .await
+        {
+            Ok(true) => Some(cred_id),
+            Ok(false) => {
+                tracing::warn!(
+                    token_id = %token.id,
+                    credential_id = %cred_id,
+                    "split variant credential is not in the token's project, ignoring it"
+                );
+                None
+            }
+            Err(e) => {
+                tracing::error!(credential_id = %cred_id, "split credential lookup failed, ignoring it: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    let effective_credential_id = split_credential_override.or(effective_credential_id);
+
+    // Integration-test override: point the token at a mock server without a
+    // header on every request. Data-driven counterpart to the `test-hooks`
+    // feature; ignored unless TRUEFLOW_ALLOW_TEST_OVERRIDES is set.
+    let test_upstream_override = active_test_upstream_override(
+        token.test_upstream_override.as_deref(),
+        state.config.allow_test_overrides,
+        path.starts_with(service_prefix),
+    );
+    let effective_upstream_url = match test_upstream_override {
+        Some(ref override_url) => {
+            tracing::warn!(
+                token_id = %token.id,
+                upstream = %override_url,
+                "test_upstream_override active — routing to test upstream"
+            );
+            // Takes precedence over DynamicRoute/ConditionalRoute targets too.
+            dynamic_upstream_override = None;
+            override_url.clone()
         }
+        None => effective_upstream_url,
     };
 
+    // A token-level provider_hint overrides name-based detection — needed for
+    // fine-tunes (`ft:gpt-4o:...`) and self-hosted aliases that don't match the
+    // built-in prefixes. It also drives which pricing table is used.
+    let hinted_provider = token.provider_hint.as_deref().and_then(|hint| {
+        let provider = proxy::model_router::Provider::from_name(hint);
+        if provider.is_none() {
+            tracing::warn!(token_id = %token.id, hint = %hint, "Ignoring unrecognised provider_hint");
+        }
+        provider
+    });
+    let pricing_provider = hinted_provider
+        .map(|p| p.pricing_name())
+        .unwrap_or_else(|| cost::provider_from_upstream_url(&token.upstream_url));
+
     // -- 4.1 Credential injection vs passthrough --
     // If credential_id is Some, decrypt from vault and inject.
     // If None, operate in passthrough mode: forward X-Real-Authorization from the agent.
@@ -1751,10 +2370,21 @@ pub async fn proxy_handler(
 
     let injected_cred = if let Some(cred_id) = effective_credential_id {
         let (real_key, _provider, injection_mode, injection_header) = state
-            .vault
+            .secrets
             .retrieve(&cred_id.to_string())
             .await
-            .map_err(AppError::Internal)?;
+            .map_err(|e| {
+                if e.downcast_ref::<crate::vault::builtin::DecryptionError>()
+                    .is_some()
+                {
+                    notify_credential_decryption_failure(&state, &token, cred_id);
+                    AppError::CredentialDecryptionFailed {
+                        credential_id: cred_id.to_string(),
+                    }
+                } else {
+                    AppError::Internal(e)
+                }
+            })?;
         Some(InjectedCredential {
             key: real_key,
             mode: injection_mode,
@@ -1767,7 +2397,226 @@ pub async fn proxy_handler(
     // -- 5. Build upstream request --
     let upstream_url = proxy::transform::rewrite_url(&effective_upstream_url, &effective_path);
 
+    // ── Universal Model Router: translate request for non-OpenAI providers ──
+    let mut detected_model = parsed_body
+        .as_ref()
+        .and_then(|b| b.get("model"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    // ── Deprecated model remap (deprecated_model_map setting) ──
+    // Before access checks and translation, so the replacement is what gets
+    // authorized, routed and priced.
+    let mut model_remapped_from: Option<String> = None;
+    if let Some(target) = state.model_remap.lookup(&detected_model).await {
+        if let Some(body) = parsed_body.as_mut().and_then(|b| b.as_object_mut()) {
+            body.insert(
+                "model".to_string(),
+                serde_json::Value::from(target.as_str()),
+            );
+            tracing::info!(
+                token_id = %token.id,
+                from = %detected_model,
+                to = %target,
+                "remapped deprecated model"
+            );
+            model_remapped_from = Some(std::mem::replace(&mut detected_model, target));
+        }
+    }
+
+    // ── Budget pressure: swap in a cheaper model near the spend cap ──
+    // Runs before the access checks, provider detection and translation so
+    // the substitute is authorized, routed and priced exactly like a
+    // client-requested model.
+    let mut model_downgraded_from: Option<String> = None;
+    if let Some(target) = token
+        .budget_pressure_model_map
+        .as_ref()
+        .and_then(|map| map.get(&detected_model))
+        .and_then(|v| v.as_str())
+    {
+        let threshold = token
+            .budget_pressure_threshold_pct
+            .unwrap_or(middleware::spend::DEFAULT_BUDGET_PRESSURE_PCT)
+            as f64
+            / 100.0;
+        match middleware::spend::get_spend_status_cached(&state.cache, state.db.pool(), &token.id)
+            .await
+        {
+            Ok(status) if status.remaining_fraction().is_some_and(|f| f <= threshold) => {
+                if let Some(body) = parsed_body.as_mut().and_then(|b| b.as_object_mut()) {
+                    body.insert("model".to_string(), serde_json::Value::from(target));
+                    tracing::info!(
+                        token_id = %token.id,
+                        from = %detected_model,
+                        to = %target,
+                        "budget pressure: downgraded model"
+                    );
+                    model_downgraded_from =
+                        Some(std::mem::replace(&mut detected_model, target.to_string()));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::debug!(token_id = %token.id, error = %e, "budget pressure check failed (non-critical, proceeding)");
+            }
+        }
+    }
+
+    // ── Model Access Control (RBAC Depth) ──
+    // Check if this token is allowed to use the requested model.
+    if !detected_model.is_empty() {
+        let group_models = if let Some(ref group_ids) = token.allowed_model_group_ids {
+            if !group_ids.is_empty() {
+                middleware::model_access::resolve_group_models(
+                    state.db.pool(),
+                    group_ids,
+                    token.project_id,
+                )
+                .await
+            } else {
+                Vec::new()
+            }
+        } else {
+            Vec::new()
+        };
+
+        if let Err(reason) = middleware::model_access::check_model_access(
+            &detected_model,
+            token.allowed_models.as_ref(),
+            &group_models,
+        ) {
+            tracing::warn!(
+                token_id = %token.id,
+                model = %detected_model,
+                "Model access denied: {}",
+                reason
+            );
+            let mut audit = base_audit(
+                request_id,
+                token.project_id,
+                &token.id,
+                agent_name,
+                method.as_str(),
+                &path,
+                &upstream_url,
+                &policies,
+                hitl_required,
+                hitl_decision,
+                hitl_latency_ms,
+                user_id,
+                tenant_id,
+                external_request_id,
+                session_id,
+                trace_context,
+                custom_properties,
+            );
+            audit.upstream_status = Some(403);
+            audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                policy: "ModelAllowlist".to_string(),
+                reason,
+            });
+            audit.response_latency_ms = start.elapsed().as_millis() as u64;
+            audit.emit(&state);
+            return Err(AppError::ModelNotAllowed {
+                model: detected_model.clone(),
+                allowed: middleware::model_access::allowed_model_patterns(
+                    token.allowed_models.as_ref(),
+                    &group_models,
+                ),
+            });
+        }
+    }
+
+    // ── Credential-Level Model Restriction ──
+    // Binds to the billing credential, so it applies whichever token resolved it.
+    let mut credential_model_limits = None;
+    if let (Some(cred_id), false) = (effective_credential_id, detected_model.is_empty()) {
+        // Fails closed: a restriction that can't be read isn't skipped.
+        let restriction = state
+            .cache
+            .get_credential_model_restriction_cached(&state.db, cred_id)
+            .await
+            .map_err(|e| {
+                tracing::error!(credential_id = %cred_id, "credential model lookup failed: {}", e);
+                AppError::Internal(e)
+            })?;
+        if let Some((cred_name, allowed, model_limits)) = restriction {
+            if let Err(reason) = middleware::model_access::check_credential_model_access(
+                &detected_model,
+                &cred_name,
+                allowed.as_deref(),
+            ) {
+                tracing::warn!(
+                    token_id = %token.id,
+                    credential_id = %cred_id,
+                    model = %detected_model,
+                    "Credential model restriction: {}",
+                    reason
+                );
+                let mut audit = base_audit(
+                    request_id,
+                    token.project_id,
+                    &token.id,
+                    agent_name,
+                    method.as_str(),
+                    &path,
+                    &upstream_url,
+                    &policies,
+                    hitl_required,
+                    hitl_decision,
+                    hitl_latency_ms,
+                    user_id,
+                    tenant_id,
+                    external_request_id,
+                    session_id,
+                    trace_context,
+                    custom_properties,
+                );
+                audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                    policy: "CredentialModelRestriction".to_string(),
+                    reason: reason.clone(),
+                });
+                audit.upstream_status = Some(403);
+                audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                audit.emit(&state);
+                return Err(AppError::Forbidden(reason));
+            }
+            credential_model_limits = Some((cred_id, cred_name, model_limits));
+        }
+    }
+
+    // ── Team-Level Enforcement (Budget + Model Access + Tags) ──
+    let resolved_team = if let Some(team_id) = token.team_id {
+        middleware::teams::get_team(state.db.pool(), team_id).await
+    } else {
+        None
+    };
+
+    if let Some(ref team) = resolved_team {
+        // Check team budget
+        if let Err(reason) = middleware::teams::check_team_budget(state.db.pool(), team).await {
+            tracing::warn!(token_id = %token.id, team = %team.name, "Team budget exceeded: {}", reason);
+            return Err(AppError::SpendCapReached {
+                message: reason,
+                cap: Some("team".to_string()),
+                resets_at: None,
+            });
+        }
+
+        // Check team-level model restrictions
+        if !detected_model.is_empty() {
+            if let Err(reason) = middleware::teams::check_team_model_access(&detected_model, team) {
+                tracing::warn!(token_id = %token.id, team = %team.name, model = %detected_model, "Team model access denied: {}", reason);
+                return Err(AppError::Forbidden(reason));
+            }
+        }
+    }
+
     // ── Response Cache: check for cache hit BEFORE upstream call ──
+    // After the remap, downgrade and model access checks: the key covers the
+    // model actually served, and a HIT can't bypass an allowlist.
     let token_scopes: Vec<String> = token
         .scopes
         .as_array()
@@ -1784,15 +2633,21 @@ pub async fn proxy_handler(
     ) || is_streaming_req
         || method != Method::POST;
     let cache_key = if !skip_cache {
-        parsed_body
-            .as_ref()
-            .and_then(|b| proxy::response_cache::compute_cache_key(&token.id, b))
+        parsed_body.as_ref().and_then(|b| {
+            proxy::response_cache::compute_cache_key_ignoring(
+                &token.id,
+                b,
+                token.cache_key_ignore_paths.as_deref().unwrap_or_default(),
+            )
+        })
     } else {
         None
     };
 
     if let Some(ref key) = cache_key {
-        if let Some(cached) = proxy::response_cache::get_cached(&state.cache, key).await {
+        let cached = proxy::response_cache::get_cached(&state.cache, key).await;
+        middleware::metrics::record_cache_lookup(&token.project_id.to_string(), cached.is_some());
+        if let Some(cached) = cached {
             tracing::info!(cache_key = %key, "response cache HIT");
 
             // BILLING: Record spend for cached responses
@@ -1800,36 +2655,9 @@ pub async fn proxy_handler(
                 (cached.prompt_tokens, cached.completion_tokens)
             {
                 if let Some(ref cached_model) = cached.model {
-                    // Detect provider from upstream URL
-                    let provider = if token.upstream_url.contains("anthropic")
-                        && !token.upstream_url.contains("bedrock")
-                    {
-                        "anthropic"
-                    } else if token.upstream_url.contains("generativelanguage")
-                        || token.upstream_url.contains("googleapis")
-                    {
-                        "google"
-                    } else if token.upstream_url.contains("mistral") {
-                        "mistral"
-                    } else if token.upstream_url.contains("bedrock") {
-                        "bedrock"
-                    } else if token.upstream_url.contains("groq") {
-                        "groq"
-                    } else if token.upstream_url.contains("cohere") {
-                        "cohere"
-                    } else if token.upstream_url.contains("together") {
-                        "together"
-                    } else if token.upstream_url.contains("localhost:11434")
-                        || token.upstream_url.contains("ollama")
-                    {
-                        "ollama"
-                    } else {
-                        "openai"
-                    };
-
                     let final_cost = cost::calculate_cost_with_cache(
                         &state.pricing,
-                        provider,
+                        pricing_provider,
                         cached_model,
                         prompt_tokens,
                         completion_tokens,
@@ -1852,6 +2680,7 @@ pub async fn proxy_handler(
                 }
             }
 
+            let freshness = cached.freshness(chrono::Utc::now().timestamp());
             let mut audit = base_audit(
                 request_id,
                 token.project_id,
@@ -1868,7 +2697,7 @@ pub async fn proxy_handler(
                 tenant_id,
                 external_request_id,
                 session_id,
-                parent_span_id,
+                trace_context,
                 custom_properties.clone(),
             );
             audit.policy_result = Some(crate::models::audit::PolicyResult::Allow);
@@ -1887,53 +2716,378 @@ pub async fn proxy_handler(
 
             let axum_status =
                 StatusCode::from_u16(cached.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-            return Response::builder()
+            let mut response = Response::builder()
                 .status(axum_status)
+                .header("x-trueflow-cache", "HIT");
+            if let Some((age, ttl)) = freshness {
+                response = response
+                    .header("x-trueflow-cache-age", age)
+                    .header("x-trueflow-cache-ttl", ttl);
+            }
+            return response
                 .header("content-type", cached.content_type)
-                .header("x-trueflow-cache", "HIT")
                 .body(Body::from(cached.body))
                 .map_err(|e| {
                     AppError::Internal(anyhow::anyhow!("cached response build failed: {}", e))
                 });
         }
+
+        // Count the miss for cache warming (warmer replays aren't counted).
+        if state.config.cache_warm_threshold > 0
+            && agent_name.as_deref() != Some(proxy::cache_warm::WARMER_AGENT_NAME)
+        {
+            // Keep the client's body, not the policy-transformed one: the
+            // replay runs through the same policies again.
+            if let Ok(body_val) = serde_json::from_slice::<serde_json::Value>(&body) {
+                let (state_ref, key, token_id, path) =
+                    (state.clone(), key.clone(), token.id.clone(), path.clone());
+                tokio::spawn(async move {
+                    proxy::cache_warm::record_miss(
+                        &state_ref.cache,
+                        &key,
+                        &token_id,
+                        &path,
+                        &body_val,
+                    )
+                    .await;
+                });
+            }
+        }
+    }
+
+    // ── Credential-Level Model Rate Limit ──
+    // Provider-account RPM/TPM shared by every token on the credential.
+    // Runs after the cache lookup: a HIT never reaches the provider.
+    if let Some((cred_id, cred_name, model_limits)) = credential_model_limits {
+        let model_limit = model_limits.as_ref().and_then(|v| {
+            middleware::model_rate_limit::parse_limits(v)
+                .map_err(|e| {
+                    tracing::warn!(credential_id = %cred_id, error = %e, "invalid model_rate_limits, ignoring");
+                })
+                .ok()
+        });
+        if let Some(limit) = model_limit
+            .as_deref()
+            .and_then(|l| middleware::model_rate_limit::limit_for(l, &detected_model))
+        {
+            let request_tokens = match (limit.tpm, parsed_body.as_ref()) {
+                (Some(_), Some(body)) => {
+                    crate::models::tokenizer::estimate_prompt_tokens(body) as u64
+                        + crate::models::tokenizer::requested_output_tokens(body) as u64
+                }
+                _ => 0,
+            };
+            if let Some((kind, max)) = acquire_credential_model_limit(
+                &state.cache,
+                &cred_id.to_string(),
+                &detected_model,
+                limit,
+                request_tokens,
+            )
+            .await
+            .map_err(AppError::Internal)?
+            {
+                tracing::warn!(
+                    token_id = %token.id,
+                    credential_id = %cred_id,
+                    model = %detected_model,
+                    limit = max,
+                    kind = kind.as_str(),
+                    "credential model rate limit exceeded"
+                );
+                let mut audit = base_audit(
+                    request_id,
+                    token.project_id,
+                    &token.id,
+                    agent_name,
+                    method.as_str(),
+                    &path,
+                    &upstream_url,
+                    &policies,
+                    hitl_required,
+                    hitl_decision,
+                    hitl_latency_ms,
+                    user_id,
+                    tenant_id,
+                    external_request_id,
+                    session_id,
+                    trace_context,
+                    custom_properties,
+                );
+                audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                    policy: "CredentialModelRateLimit".to_string(),
+                    reason: format!(
+                        "credential '{}' {} limit of {}/min for {} exceeded",
+                        cred_name,
+                        kind.as_str(),
+                        max,
+                        detected_model
+                    ),
+                });
+                audit.upstream_status = Some(429);
+                audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                audit.emit(&state);
+                return Err(AppError::RateLimitExceeded {
+                    retry_after_secs: middleware::model_rate_limit::MODEL_RATE_LIMIT_WINDOW_SECS,
+                    limit: Some(max),
+                });
+            }
+        }
+    }
+
+    let detected_provider = if let Some(hinted) = hinted_provider {
+        hinted
+    } else if !detected_model.is_empty() {
+        proxy::model_router::detect_provider(&detected_model, &effective_upstream_url)
+    } else {
+        proxy::model_router::Provider::Unknown
+    };
+
+    // Per-token body field stripping: drop client-internal keys upstreams reject.
+    // Runs before defaults and translation so neither sees the stripped fields.
+    let body_fields_stripped = match (parsed_body.as_mut(), token.strip_body_fields.as_deref()) {
+        (Some(body_val), Some(fields)) if !fields.is_empty() => {
+            let stripped = proxy::transform::strip_body_fields(body_val, fields);
+            if !stripped.is_empty() {
+                tracing::debug!(token_id = %token.id, fields = ?stripped, "stripped request body fields");
+            }
+            stripped
+        }
+        _ => Vec::new(),
+    };
+
+    // Per-token parameter defaults: fill gaps only, before translation so the
+    // provider translator maps them like any client-supplied field.
+    let param_defaults_applied = match (parsed_body.as_mut(), token.param_defaults.as_ref()) {
+        (Some(body_val), Some(defaults)) => {
+            let applied = proxy::transform::apply_param_defaults(body_val, defaults);
+            if !applied.is_empty() {
+                tracing::debug!(token_id = %token.id, keys = ?applied, "applied token param defaults");
+            }
+            applied
+        }
+        _ => Vec::new(),
+    };
+
+    // Per-token output-token ceiling: clamp the client's limit (or inject one)
+    // before translation, so Anthropic/Gemini/Bedrock carry the clamped value.
+    let max_tokens_clamp = match (
+        parsed_body.as_mut(),
+        token.max_output_tokens_ceiling.filter(|c| *c > 0),
+    ) {
+        (Some(body_val), Some(ceiling)) => {
+            let field = proxy::transform::output_limit_field(detected_provider, uri.path());
+            let clamp = proxy::transform::clamp_output_tokens(body_val, ceiling as u64, field);
+            if let Some(ref record) = clamp {
+                tracing::debug!(token_id = %token.id, clamp = %record, "enforced max output tokens ceiling");
+            }
+            clamp
+        }
+        _ => None,
+    };
+
+    // JSON-mode fallback: providers without a native `response_format` get a
+    // system instruction instead; the response is cleaned up post-flight.
+    let json_mode_emulated = match parsed_body.as_mut() {
+        Some(body_val)
+            if token.json_mode_fallback
+                && !proxy::json_mode::supports_native_json_mode(detected_provider) =>
+        {
+            let injected = proxy::json_mode::inject_instruction(body_val);
+            if injected {
+                tracing::debug!(token_id = %token.id, provider = ?detected_provider, "emulating JSON mode");
+            }
+            injected
+        }
+        _ => false,
+    };
+
+    // Model capability pre-flight: tools, images, audio or JSON mode sent to a
+    // model without them would come back as an opaque upstream 400. Runs after
+    // JSON-mode emulation, which already removed an emulated response_format.
+    let model_capabilities = state
+        .capabilities
+        .lookup(detected_provider.as_str(), &detected_model)
+        .await;
+    let mut capability_stripped = String::new();
+    let mut capability_stripped_audit: Option<serde_json::Value> = None;
+    let capability_action = token
+        .capability_action
+        .as_deref()
+        .and_then(middleware::capability::CapabilityAction::from_name);
+    if let (Some(action), Some(caps), Some(body_val)) = (
+        capability_action,
+        model_capabilities.as_ref(),
+        parsed_body.as_mut(),
+    ) {
+        let unsupported = middleware::capability::unsupported_features(body_val, caps);
+        if !unsupported.is_empty() {
+            let features = middleware::capability::feature_list(&unsupported);
+            match action {
+                middleware::capability::CapabilityAction::Strip => {
+                    let parts = middleware::capability::strip_features(body_val, &unsupported);
+                    tracing::warn!(
+                        token_id = %token.id,
+                        model = %detected_model,
+                        features = %features,
+                        parts = parts.len(),
+                        "model capability: stripped unsupported features"
+                    );
+                    capability_stripped_audit =
+                        Some(middleware::capability::audit_record(&unsupported, &parts));
+                    capability_stripped = features;
+                }
+                middleware::capability::CapabilityAction::Reject => {
+                    tracing::warn!(
+                        token_id = %token.id,
+                        model = %detected_model,
+                        features = %features,
+                        "model capability: request rejected before upstream"
+                    );
+                    let mut audit = base_audit(
+                        request_id,
+                        token.project_id,
+                        &token.id,
+                        agent_name,
+                        method.as_str(),
+                        &path,
+                        &upstream_url,
+                        &policies,
+                        hitl_required,
+                        hitl_decision,
+                        hitl_latency_ms,
+                        user_id,
+                        tenant_id,
+                        external_request_id,
+                        session_id,
+                        trace_context,
+                        custom_properties,
+                    );
+                    audit.model = Some(detected_model.clone());
+                    audit.upstream_status = Some(400);
+                    audit.error_type = Some("unsupported_model_feature".to_string());
+                    audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                    audit.emit(&state);
+                    return Err(AppError::UnsupportedModelFeature {
+                        model: detected_model,
+                        features: unsupported.iter().map(|f| f.as_str().to_string()).collect(),
+                    });
+                }
+            }
+        }
+    }
+
+    // Context-window pre-flight: reject or trim prompts that clearly won't fit
+    // the model, instead of spending an upstream round trip on a 400. Runs
+    // after param_defaults so a defaulted max_tokens counts against the window.
+    // (estimated tokens, context window, messages trimmed)
+    let mut context_check: Option<(u32, u32, Option<u32>)> = None;
+    let overflow_action = token
+        .context_window_action
+        .as_deref()
+        .and_then(middleware::context_window::OverflowAction::from_name);
+    if let (Some(action), Some(body_val)) = (overflow_action, parsed_body.as_mut()) {
+        let window = match model_capabilities.and_then(|c| c.max_context) {
+            Some(window) => Some(window),
+            None => state.context_windows.lookup(&detected_model).await,
+        };
+        if let Some(window) = window {
+            let guard = middleware::context_window::ContextWindowGuard {
+                context_window: window,
+                action,
+            };
+            match guard.apply(body_val) {
+                middleware::context_window::GuardOutcome::Fits { estimated_tokens } => {
+                    context_check = Some((estimated_tokens, window, None));
+                }
+                middleware::context_window::GuardOutcome::Trimmed {
+                    estimated_before,
+                    estimated_tokens,
+                    messages_removed,
+                } => {
+                    tracing::info!(
+                        token_id = %token.id,
+                        model = %detected_model,
+                        estimated_before,
+                        estimated_tokens,
+                        context_window = window,
+                        messages_removed,
+                        "context window: trimmed oldest messages"
+                    );
+                    context_check = Some((estimated_tokens, window, Some(messages_removed as u32)));
+                }
+                middleware::context_window::GuardOutcome::Overflow { estimated_tokens } => {
+                    tracing::warn!(
+                        token_id = %token.id,
+                        model = %detected_model,
+                        estimated_tokens,
+                        context_window = window,
+                        "context window: request rejected before upstream"
+                    );
+                    let mut audit = base_audit(
+                        request_id,
+                        token.project_id,
+                        &token.id,
+                        agent_name,
+                        method.as_str(),
+                        &path,
+                        &upstream_url,
+                        &policies,
+                        hitl_required,
+                        hitl_decision,
+                        hitl_latency_ms,
+                        user_id,
+                        tenant_id,
+                        external_request_id,
+                        session_id,
+                        trace_context,
+                        custom_properties,
+                    );
+                    audit.model = Some(detected_model.clone());
+                    audit.upstream_status = Some(400);
+                    audit.error_type = Some("context_length_exceeded".to_string());
+                    audit.context_estimated_tokens = Some(estimated_tokens);
+                    audit.context_window_tokens = Some(window);
+                    audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                    audit.emit(&state);
+                    return Err(AppError::ContextWindowExceeded {
+                        model: detected_model,
+                        estimated_tokens,
+                        context_window: window,
+                    });
+                }
+            }
+        }
     }
 
-    // ── Universal Model Router: translate request for non-OpenAI providers ──
-    let detected_model = parsed_body
-        .as_ref()
-        .and_then(|b| b.get("model"))
-        .and_then(|m| m.as_str())
-        .unwrap_or("")
-        .to_string();
-
-    // ── Model Access Control (RBAC Depth) ──
-    // Check if this token is allowed to use the requested model.
-    if !detected_model.is_empty() {
-        let group_models = if let Some(ref group_ids) = token.allowed_model_group_ids {
-            if !group_ids.is_empty() {
-                middleware::model_access::resolve_group_models(
-                    state.db.pool(),
-                    group_ids,
-                    token.project_id,
-                )
-                .await
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        };
-
-        if let Err(reason) = middleware::model_access::check_model_access(
+    // Per-request cost ceiling: price the prompt plus the output the client
+    // reserved and reject before the upstream call if that could exceed it.
+    // Without an output limit only the prompt is priced here, and the actual
+    // cost is checked again once usage is known.
+    let request_cost_cap = token.max_cost_per_request_usd;
+    let mut cost_ceiling_estimate = None;
+    if let (Some(cap), Some(body_val)) = (request_cost_cap, parsed_body.as_ref()) {
+        let (estimate, bounded) = cost::estimate_request_cost(
+            &state.pricing,
+            pricing_provider,
             &detected_model,
-            token.allowed_models.as_ref(),
-            &group_models,
-        ) {
+            body_val,
+        )
+        .await;
+        cost_ceiling_estimate = Some(estimate);
+        if estimate > cap {
             tracing::warn!(
                 token_id = %token.id,
                 model = %detected_model,
-                "Model access denied: {}",
-                reason
+                estimate = %estimate,
+                cap = %cap,
+                bounded,
+                "request cost ceiling exceeded before upstream"
+            );
+            let reason = format!(
+                "estimated cost ${} exceeds the per-request limit of ${}",
+                estimate.round_dp(6),
+                cap
             );
             let mut audit = base_audit(
                 request_id,
@@ -1951,51 +3105,50 @@ pub async fn proxy_handler(
                 tenant_id,
                 external_request_id,
                 session_id,
-                parent_span_id,
+                trace_context,
                 custom_properties,
             );
-            audit.upstream_status = Some(403);
+            audit.model = Some(detected_model.clone());
+            audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                policy: "RequestCostCap".to_string(),
+                reason: reason.clone(),
+            });
+            audit.upstream_status = Some(402);
+            audit.request_cost_estimate_usd = Some(estimate);
             audit.response_latency_ms = start.elapsed().as_millis() as u64;
             audit.emit(&state);
-            return Err(AppError::Forbidden(reason));
-        }
-    }
-
-    // ── Team-Level Enforcement (Budget + Model Access + Tags) ──
-    let resolved_team = if let Some(team_id) = token.team_id {
-        middleware::teams::get_team(state.db.pool(), team_id).await
-    } else {
-        None
-    };
-
-    if let Some(ref team) = resolved_team {
-        // Check team budget
-        if let Err(reason) = middleware::teams::check_team_budget(state.db.pool(), team).await {
-            tracing::warn!(token_id = %token.id, team = %team.name, "Team budget exceeded: {}", reason);
-            return Err(AppError::SpendCapReached { message: reason });
-        }
-
-        // Check team-level model restrictions
-        if !detected_model.is_empty() {
-            if let Err(reason) = middleware::teams::check_team_model_access(&detected_model, team) {
-                tracing::warn!(token_id = %token.id, team = %team.name, model = %detected_model, "Team model access denied: {}", reason);
-                return Err(AppError::Forbidden(reason));
-            }
+            return Err(AppError::SpendCapReached {
+                message: format!(
+                    "Request blocked: {}. Lower max_tokens or shorten the prompt.",
+                    reason
+                ),
+                cap: Some("request".to_string()),
+                resets_at: None,
+            });
         }
     }
 
-    let detected_provider = if !detected_model.is_empty() {
-        proxy::model_router::detect_provider(&detected_model, &effective_upstream_url)
-    } else {
-        proxy::model_router::Provider::Unknown
-    };
-
     // Translate request body if needed (OpenAI → Anthropic/Gemini)
     let router_translated = if let Some(ref body_val) = parsed_body {
         proxy::model_router::translate_request(detected_provider, body_val)
     } else {
         None
     };
+    // Fields the translator couldn't map for this provider, reported back
+    // to the client rather than silently dropped.
+    let dropped_fields = match (&router_translated, &parsed_body) {
+        (Some(_), Some(body_val)) => {
+            proxy::model_router::dropped_fields(detected_provider, body_val).join(",")
+        }
+        _ => String::new(),
+    };
+    if !dropped_fields.is_empty() {
+        tracing::debug!(
+            provider = ?detected_provider,
+            fields = %dropped_fields,
+            "request fields dropped in provider translation"
+        );
+    }
 
     // Rewrite upstream URL for the target provider.
     // Gemini uses different endpoints for streaming vs non-streaming.
@@ -2243,6 +3396,11 @@ pub async fn proxy_handler(
         }
     }
 
+    // Client correlation headers on the token's allowlist
+    if let Some(ref allowlist) = token.forward_trace_headers {
+        forward_trace_headers(allowlist, &headers, &mut upstream_headers);
+    }
+
     // Forward standard safe headers (required by strict APIs like GitHub)
     // BUT skip if a transform explicitly removed User-Agent
     let ua_removed = header_mutations
@@ -2326,14 +3484,15 @@ pub async fn proxy_handler(
 
     // ── Circuit Breaker pre-check ────────────────────────────────────────────
     // For single-upstream tokens, fail fast with 503 when the circuit is OPEN.
-    // This prevents flooding a known-broken upstream with requests.
+    // This prevents flooding a known-broken upstream with requests. A
+    // half-open upstream whose probe slots are all taken counts as open.
     if cb_config.enabled {
         let cb_state = state.lb.get_circuit_state(
             &token.id,
             &final_upstream_url,
             cb_config.recovery_cooldown_secs,
         );
-        if cb_state == "open" {
+        if cb_state == "open" || (cb_state == "half_open" && lb_exhausted) {
             state.lb.decrement_in_flight(&final_upstream_url);
             return Err(AppError::AllUpstreamsExhausted {
                 details: Some(serde_json::json!({
@@ -2422,10 +3581,73 @@ pub async fn proxy_handler(
     // For streaming requests, use forward_raw (no retry, returns raw response for piping)
     let safety_secs =
         65 + (retry_config.max_retries as u64 * (retry_config.max_backoff_ms / 1000 + 65));
+    let safety_timeout = match budget_remaining() {
+        Some(remaining) => Duration::from_secs(safety_secs).min(remaining),
+        None => Duration::from_secs(safety_secs),
+    };
+    // Budget-limited timeouts surface as request_budget_exceeded, not a generic 504.
+    let budget_limited =
+        request_budget.is_some() && safety_timeout < Duration::from_secs(safety_secs);
+    // -- Concurrent stream cap --
+    // The permit is held until the stream bridge finishes (see the streaming
+    // fast path below), or dropped here on any non-streaming outcome.
+    let stream_permit = if is_streaming_req {
+        let token_cap = token.max_concurrent_streams.map(|c| c.max(0) as u64);
+        let global_cap = (state.config.max_concurrent_streams > 0)
+            .then_some(state.config.max_concurrent_streams);
+        match state
+            .streams
+            .acquire(
+                &token.id,
+                token_cap,
+                global_cap,
+                Duration::from_millis(state.config.stream_queue_timeout_ms),
+            )
+            .await
+        {
+            Ok(permit) => Some(permit),
+            Err(hit) => {
+                tracing::warn!(
+                    token_id = %token.id,
+                    scope = hit.scope.as_str(),
+                    limit = hit.limit,
+                    "concurrent stream limit reached"
+                );
+                let mut audit = deny_audit();
+                audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+                    policy: "StreamConcurrency".to_string(),
+                    reason: format!(
+                        "{} limit of {} concurrent streams reached",
+                        hit.scope.as_str(),
+                        hit.limit
+                    ),
+                });
+                audit.upstream_status = Some(429);
+                audit.response_latency_ms = start.elapsed().as_millis() as u64;
+                audit.emit(&state);
+                return Err(AppError::StreamLimitExceeded {
+                    scope: hit.scope.as_str(),
+                    limit: hit.limit,
+                });
+            }
+        }
+    } else {
+        None
+    };
+
+    // The probe slot is only kept when the request goes to the probed
+    // upstream; a migration, routing or test override sent it elsewhere.
+    if let Some(slot) = probe_slot.take() {
+        if final_upstream_url.starts_with(slot.url()) {
+            slot.forwarded();
+        }
+    }
+
+    let upstream_call_start = Instant::now();
     let upstream_resp = if is_streaming_req {
         // Streaming: no retry, direct connection
         match tokio::time::timeout(
-            Duration::from_secs(safety_secs),
+            safety_timeout,
             state.upstream_client.forward_raw(
                 reqwest_method,
                 &final_upstream_url,
@@ -2436,6 +3658,11 @@ pub async fn proxy_handler(
         .await
         {
             Ok(Ok(res)) => {
+                state.lb.history().record(
+                    &final_upstream_url,
+                    upstream_call_start.elapsed().as_millis() as u64,
+                    !res.status().is_server_error(),
+                );
                 // FIX 4A-1: Only mark healthy if upstream DID NOT return 5xx.
                 // 5xx = upstream is broken → open the circuit.
                 // 4xx = upstream is alive (client error) → keep circuit closed.
@@ -2451,6 +3678,11 @@ pub async fn proxy_handler(
             }
             Ok(Err(e)) => {
                 tracing::error!("Upstream streaming request failed: {}", e);
+                state.lb.history().record(
+                    &final_upstream_url,
+                    upstream_call_start.elapsed().as_millis() as u64,
+                    false,
+                );
                 state
                     .lb
                     .mark_failed(&token.id, &final_upstream_url, &cb_config);
@@ -2471,7 +3703,7 @@ pub async fn proxy_handler(
                     tenant_id.clone(),
                     external_request_id.clone(),
                     session_id.clone(),
-                    parent_span_id.clone(),
+                    trace_context.clone(),
                     custom_properties.clone(),
                 );
                 audit.upstream_status = Some(502);
@@ -2482,9 +3714,17 @@ pub async fn proxy_handler(
             }
             Err(_) => {
                 tracing::error!("Upstream streaming request timed out (safety net)");
-                state
-                    .lb
-                    .mark_failed(&token.id, &final_upstream_url, &cb_config);
+                // A spent request budget says nothing about upstream health.
+                if !budget_limited {
+                    state
+                        .lb
+                        .mark_failed(&token.id, &final_upstream_url, &cb_config);
+                    state.lb.history().record(
+                        &final_upstream_url,
+                        upstream_call_start.elapsed().as_millis() as u64,
+                        false,
+                    );
+                }
                 state.lb.decrement_in_flight(&final_upstream_url);
                 let mut audit = base_audit(
                     request_id,
@@ -2502,13 +3742,19 @@ pub async fn proxy_handler(
                     tenant_id,
                     external_request_id,
                     session_id,
-                    parent_span_id,
+                    trace_context,
                     custom_properties.clone(),
                 );
                 audit.upstream_status = Some(504);
                 audit.response_latency_ms = start.elapsed().as_millis() as u64;
                 audit.is_streaming = true;
+                if budget_limited {
+                    audit.error_type = Some("request_budget_exceeded".to_string());
+                }
                 audit.emit(&state);
+                if budget_limited {
+                    return Err(budget_exceeded());
+                }
                 return Err(AppError::Upstream(
                     "Upstream streaming request timed out".to_string(),
                 ));
@@ -2516,7 +3762,7 @@ pub async fn proxy_handler(
         }
     } else {
         match tokio::time::timeout(
-            Duration::from_secs(safety_secs),
+            safety_timeout,
             state.upstream_client.forward(
                 reqwest_method,
                 &final_upstream_url,
@@ -2528,6 +3774,11 @@ pub async fn proxy_handler(
         .await
         {
             Ok(Ok(res)) => {
+                state.lb.history().record(
+                    &final_upstream_url,
+                    upstream_call_start.elapsed().as_millis() as u64,
+                    !res.status().is_server_error(),
+                );
                 // FIX 4A-1: Only mark healthy if upstream DID NOT return 5xx.
                 // 5xx = upstream is broken → open the circuit.
                 // 4xx = upstream is alive (client error) → keep circuit closed.
@@ -2543,6 +3794,11 @@ pub async fn proxy_handler(
             }
             Ok(Err(e)) => {
                 tracing::error!("Upstream request failed: {}", e);
+                state.lb.history().record(
+                    &final_upstream_url,
+                    upstream_call_start.elapsed().as_millis() as u64,
+                    false,
+                );
                 // Loadbalancer: mark upstream as failed
                 state
                     .lb
@@ -2564,7 +3820,7 @@ pub async fn proxy_handler(
                     tenant_id.clone(),
                     external_request_id.clone(),
                     session_id.clone(),
-                    parent_span_id.clone(),
+                    trace_context.clone(),
                     custom_properties.clone(),
                 );
                 audit.policy_result = Some(if hitl_required {
@@ -2580,15 +3836,27 @@ pub async fn proxy_handler(
                     Some(shadow_violations)
                 };
                 audit.is_streaming = is_streaming_req;
+                if let Some(stale) =
+                    stale_fallback(&state, token.serve_stale_on_error, cache_key.as_deref()).await
+                {
+                    return serve_stale(&state, audit, stale, start);
+                }
                 audit.emit(&state);
                 return Err(e);
             }
             Err(_) => {
                 tracing::error!("Upstream request timed out (safety net)");
-                // Loadbalancer: mark upstream as failed
-                state
-                    .lb
-                    .mark_failed(&token.id, &final_upstream_url, &cb_config);
+                // Loadbalancer: mark upstream as failed (unless our own budget cut it short)
+                if !budget_limited {
+                    state
+                        .lb
+                        .mark_failed(&token.id, &final_upstream_url, &cb_config);
+                    state.lb.history().record(
+                        &final_upstream_url,
+                        upstream_call_start.elapsed().as_millis() as u64,
+                        false,
+                    );
+                }
                 state.lb.decrement_in_flight(&final_upstream_url);
                 let mut audit = base_audit(
                     request_id,
@@ -2606,7 +3874,7 @@ pub async fn proxy_handler(
                     tenant_id,
                     external_request_id,
                     session_id,
-                    parent_span_id,
+                    trace_context,
                     custom_properties.clone(),
                 );
                 audit.policy_result = Some(if hitl_required {
@@ -2622,14 +3890,146 @@ pub async fn proxy_handler(
                     Some(shadow_violations)
                 };
                 audit.is_streaming = is_streaming_req;
+                if budget_limited {
+                    audit.error_type = Some("request_budget_exceeded".to_string());
+                } else if let Some(stale) =
+                    stale_fallback(&state, token.serve_stale_on_error, cache_key.as_deref()).await
+                {
+                    return serve_stale(&state, audit, stale, start);
+                }
                 audit.emit(&state);
+                if budget_limited {
+                    return Err(budget_exceeded());
+                }
                 return Err(AppError::Upstream("Upstream request timed out".to_string()));
             }
         }
     };
 
+    middleware::metrics::observe_upstream_latency(
+        &token.project_id.to_string(),
+        detected_provider.as_str(),
+        upstream_call_start.elapsed().as_secs_f64(),
+    );
+
     let status = upstream_resp.status();
-    let resp_headers = upstream_resp.headers().clone();
+    let mut resp_headers = upstream_resp.headers().clone();
+
+    // serve_stale_on_error: a 5xx after retries is an outage for this request.
+    if status.is_server_error() {
+        if let Some(stale) =
+            stale_fallback(&state, token.serve_stale_on_error, cache_key.as_deref()).await
+        {
+            let mut audit = base_audit(
+                request_id,
+                token.project_id,
+                &token.id,
+                agent_name,
+                method.as_str(),
+                &path,
+                &upstream_url,
+                &policies,
+                hitl_required,
+                hitl_decision,
+                hitl_latency_ms,
+                user_id,
+                tenant_id,
+                external_request_id,
+                session_id,
+                trace_context,
+                custom_properties.clone(),
+            );
+            audit.policy_result = Some(if hitl_required {
+                crate::models::audit::PolicyResult::HitlApproved
+            } else {
+                crate::models::audit::PolicyResult::Allow
+            });
+            audit.upstream_status = Some(status.as_u16());
+            audit.shadow_violations = if shadow_violations.is_empty() {
+                None
+            } else {
+                Some(shadow_violations)
+            };
+            return serve_stale(&state, audit, stale, start);
+        }
+    }
+
+    // A 401/403 to a request signed with a stored credential means the key
+    // itself is bad, which the client can't fix: alert the operators, and
+    // with TRUEFLOW_MASK_UPSTREAM_AUTH_ERRORS hide the provider's error.
+    // Providers also send 403 for region blocks, moderation and model access,
+    // so a 403 body is read first and only counts when it names the key.
+    let (rejected_credential_id, upstream_resp) = match effective_credential_id {
+        Some(cred_id) if status.as_u16() == 401 => (Some(cred_id), upstream_resp),
+        Some(cred_id) if status.as_u16() == 403 => {
+            let (body, upstream_resp) = buffer_upstream_response(upstream_resp).await?;
+            let rejected = crate::models::llm::is_credential_rejection(403, &body);
+            (rejected.then_some(cred_id), upstream_resp)
+        }
+        _ => (None, upstream_resp),
+    };
+    if let Some(cred_id) = rejected_credential_id {
+        notify_credential_auth_failure(&state, &token, cred_id, status.as_u16());
+        if state.config.mask_upstream_auth_errors {
+            let mut audit = base_audit(
+                request_id,
+                token.project_id,
+                &token.id,
+                agent_name,
+                method.as_str(),
+                &path,
+                &upstream_url,
+                &policies,
+                hitl_required,
+                hitl_decision,
+                hitl_latency_ms,
+                user_id,
+                tenant_id,
+                external_request_id,
+                session_id,
+                trace_context,
+                custom_properties.clone(),
+            );
+            audit.policy_result = Some(if hitl_required {
+                crate::models::audit::PolicyResult::HitlApproved
+            } else {
+                crate::models::audit::PolicyResult::Allow
+            });
+            audit.upstream_status = Some(status.as_u16());
+            audit.response_latency_ms = start.elapsed().as_millis() as u64;
+            audit.error_type = Some("credential_auth_failed".to_string());
+            audit.rejected_credential_id = Some(cred_id);
+            audit.is_streaming = is_streaming_req;
+            audit.shadow_violations = if shadow_violations.is_empty() {
+                None
+            } else {
+                Some(shadow_violations)
+            };
+            audit.emit(&state);
+            return Err(AppError::CredentialAuthFailed {
+                credential_id: cred_id.to_string(),
+                upstream_status: status.as_u16(),
+            });
+        }
+    }
+
+    // Feed the upstream outcome into the token's adaptive rate limiter.
+    if let Some(ref cfg) = adaptive_cfg {
+        let model_p50 = state.latency.get_p50(&detected_model).await;
+        let limit = state.adaptive_limits.observe(
+            &token.id,
+            cfg,
+            upstream_call_start.elapsed().as_millis() as u64,
+            cfg.threshold_ms(model_p50),
+            status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+        );
+        middleware::metrics::set_adaptive_rate_limit(
+            &token.project_id.to_string(),
+            detected_provider.as_str(),
+            limit,
+        );
+        adaptive_limit = Some(limit);
+    }
 
     // ── STREAMING FAST PATH: zero-copy SSE passthrough ──────────────────────
     // For successful streaming responses, pipe bytes directly to the client.
@@ -2639,19 +4039,59 @@ pub async fn proxy_handler(
         // - Bedrock: binary event stream → OpenAI SSE (dedicated decoder)
         // - Anthropic: Anthropic SSE → OpenAI SSE (per-chunk translation)
         // - Gemini: Gemini SSE → OpenAI SSE (per-chunk translation)
+        // - Cohere: native v2 SSE → OpenAI SSE (per-chunk translation)
         // - All others: OpenAI-compatible, passthrough SSE unchanged
+        let stream_flush =
+            proxy::stream_bridge::StreamFlushConfig::from_token_value(token.stream_flush.as_ref());
+        let ttft_comment = token.stream_ttft_comment;
+        let output_format = proxy::stream_bridge::StreamOutputFormat::from_token_value(
+            token.stream_output_format.as_deref(),
+        );
         let (stream_body, result_slot, stream_notify) = match detected_provider {
             proxy::model_router::Provider::Bedrock => proxy::stream_bridge::tee_bedrock_stream(
                 upstream_resp,
                 start,
                 detected_model.clone(),
+                stream_flush,
+                ttft_comment,
+                output_format,
             ),
             proxy::model_router::Provider::Anthropic => {
+                let mut anthropic_state = proxy::model_router::AnthropicStreamState::default();
+                proxy::stream_bridge::tee_translating_sse_stream(
+                    upstream_resp,
+                    start,
+                    detected_model.clone(),
+                    move |chunk: &[u8], model: &str| {
+                        proxy::model_router::translate_anthropic_sse_chunk(
+                            chunk,
+                            model,
+                            &mut anthropic_state,
+                        )
+                    },
+                    stream_flush,
+                    ttft_comment,
+                    output_format,
+                )
+            }
+            // Native v2 events are translated; compatibility-endpoint OpenAI
+            // SSE passes through the same translator unchanged.
+            proxy::model_router::Provider::Cohere => {
+                let mut cohere_state = proxy::model_router::CohereStreamState::default();
                 proxy::stream_bridge::tee_translating_sse_stream(
                     upstream_resp,
                     start,
                     detected_model.clone(),
-                    proxy::model_router::translate_anthropic_sse_to_openai,
+                    move |chunk: &[u8], model: &str| {
+                        proxy::model_router::translate_cohere_sse_chunk(
+                            chunk,
+                            model,
+                            &mut cohere_state,
+                        )
+                    },
+                    stream_flush,
+                    ttft_comment,
+                    output_format,
                 )
             }
             proxy::model_router::Provider::Gemini => {
@@ -2660,15 +4100,24 @@ pub async fn proxy_handler(
                     start,
                     detected_model.clone(),
                     proxy::model_router::translate_gemini_sse_to_openai,
+                    stream_flush,
+                    ttft_comment,
+                    output_format,
                 )
             }
-            _ => proxy::stream_bridge::tee_sse_stream(upstream_resp, start),
+            _ => proxy::stream_bridge::tee_sse_stream(
+                upstream_resp,
+                start,
+                stream_flush,
+                ttft_comment,
+                output_format,
+            ),
         };
 
         // Build the SSE response immediately — this starts streaming to the client
         let mut sse_response = axum::response::Response::builder()
             .status(StatusCode::OK)
-            .header("content-type", "text/event-stream")
+            .header("content-type", output_format.content_type())
             .header("cache-control", "no-cache")
             .header("x-accel-buffering", "no") // Disable nginx buffering
             .body(stream_body)
@@ -2683,19 +4132,58 @@ pub async fn proxy_handler(
             ) {
                 continue;
             }
+            // Reframed streams keep their own content type.
+            if name_str == "content-type"
+                && output_format != proxy::stream_bridge::StreamOutputFormat::OpenAiSse
+            {
+                continue;
+            }
             if let Ok(name) = axum::http::HeaderName::from_bytes(name_str.as_bytes()) {
                 if let Ok(val) = axum::http::HeaderValue::from_bytes(value.as_bytes()) {
                     sse_response.headers_mut().insert(name, val);
                 }
             }
         }
+        if let Some(ref from) = model_downgraded_from {
+            if let Ok(hv) = axum::http::HeaderValue::from_str(from) {
+                sse_response
+                    .headers_mut()
+                    .insert("x-trueflow-model-downgraded", hv);
+            }
+        }
+        if let Some(ref from) = model_remapped_from {
+            if let Ok(hv) = axum::http::HeaderValue::from_str(from) {
+                sse_response
+                    .headers_mut()
+                    .insert("x-trueflow-model-remapped", hv);
+            }
+        }
+        if !dropped_fields.is_empty() {
+            if let Ok(hv) = axum::http::HeaderValue::from_str(&dropped_fields) {
+                sse_response
+                    .headers_mut()
+                    .insert("x-trueflow-dropped-fields", hv);
+            }
+        }
+        if !capability_stripped.is_empty() {
+            if let Ok(hv) = axum::http::HeaderValue::from_str(&capability_stripped) {
+                sse_response
+                    .headers_mut()
+                    .insert("x-trueflow-capability-stripped", hv);
+            }
+        }
+        if let Some(limit) = adaptive_limit {
+            sse_response.headers_mut().insert(
+                "x-trueflow-adaptive-limit",
+                axum::http::HeaderValue::from(limit),
+            );
+        }
 
         // Spawn background task: wait for stream to finish, then audit + cost
         let state_bg = state.clone();
         // Extract needed token fields (TokenRow doesn't implement Clone)
         let token_bg_id = token.id.clone();
         let token_bg_project_id = token.project_id;
-        let token_bg_upstream_url = token.upstream_url.clone();
         let policies_bg = policies.clone();
         let shadow_violations_bg = shadow_violations.clone();
         let upstream_url_bg = upstream_url.clone();
@@ -2705,7 +4193,19 @@ pub async fn proxy_handler(
         let ext_req_id_bg = external_request_id.clone();
         let session_id_bg = session_id.clone();
         let session_id_for_spend = session_id.clone();
-        let parent_span_id_bg = parent_span_id.clone();
+        let trace_context_bg = trace_context.clone();
+        let model_downgraded_from_bg = model_downgraded_from.clone();
+        let model_remapped_from_bg = model_remapped_from.clone();
+        let migration_path_bg = migration_path_taken;
+        let max_tokens_clamp_bg = max_tokens_clamp.clone();
+        let capability_stripped_bg = capability_stripped_audit.clone();
+        let policy_eval_timings_bg = policy_eval_timings.clone();
+        let experiment_name_bg = experiment_name.clone();
+        let variant_name_bg = variant_name.clone();
+        let test_upstream_override_bg = test_upstream_override.clone();
+        let context_check_bg = context_check;
+        // Only estimated from if the stream carries no usage chunk.
+        let parsed_body_bg = parsed_body;
 
         tokio::spawn(async move {
             // Wait up to 5 minutes for the stream to complete
@@ -2715,12 +4215,27 @@ pub async fn proxy_handler(
                 Duration::from_secs(300),
             )
             .await;
+            drop(stream_permit);
 
+            let mut tokens_estimated = false;
             let (prompt_tokens, completion_tokens, model_name, finish_reason, tool_calls, ttft_ms) =
                 if let Some(ref r) = sr {
+                    let (prompt, completion, exact) = r.usage_or_estimate(|| {
+                        parsed_body_bg
+                            .as_ref()
+                            .map_or(0, crate::models::tokenizer::estimate_prompt_tokens)
+                    });
+                    tokens_estimated = !exact;
+                    if !exact {
+                        tracing::debug!(
+                            prompt,
+                            completion,
+                            "Streaming: no usage chunk from upstream, using estimated token counts"
+                        );
+                    }
                     (
-                        r.prompt_tokens,
-                        r.completion_tokens,
+                        Some(prompt),
+                        Some(completion),
                         r.model.clone(),
                         r.finish_reason.clone(),
                         r.tool_calls.clone(),
@@ -2734,38 +4249,21 @@ pub async fn proxy_handler(
             let mut estimated_cost_usd: Option<rust_decimal::Decimal> = None;
             if let (Some(inp), Some(out)) = (prompt_tokens, completion_tokens) {
                 // GAP-1 FIX: detect all supported providers, not just openai/anthropic
-                let provider = if token_bg_upstream_url.contains("anthropic")
-                    && !token_bg_upstream_url.contains("bedrock")
-                {
-                    "anthropic"
-                } else if token_bg_upstream_url.contains("generativelanguage")
-                    || token_bg_upstream_url.contains("googleapis")
-                {
-                    "google"
-                } else if token_bg_upstream_url.contains("mistral") {
-                    "mistral"
-                } else if token_bg_upstream_url.contains("bedrock") {
-                    "bedrock"
-                } else if token_bg_upstream_url.contains("groq") {
-                    "groq"
-                } else if token_bg_upstream_url.contains("cohere") {
-                    "cohere"
-                } else if token_bg_upstream_url.contains("together") {
-                    "together"
-                } else if token_bg_upstream_url.contains("localhost:11434")
-                    || token_bg_upstream_url.contains("ollama")
-                {
-                    "ollama"
-                } else {
-                    "openai"
-                };
                 let model = model_name.as_deref().unwrap_or("unknown");
-                let final_cost =
-                    cost::calculate_cost_with_cache(&state_bg.pricing, provider, model, inp, out)
-                        .await;
+                let final_cost = cost::calculate_cost_with_cache(
+                    &state_bg.pricing,
+                    pricing_provider,
+                    model,
+                    inp,
+                    out,
+                )
+                .await;
                 if !final_cost.is_zero() {
                     estimated_cost_usd = Some(final_cost);
                     let cost_f64 = final_cost.to_f64().unwrap_or(0.0);
+                    if let Some(cred_id) = effective_credential_id {
+                        state_bg.lb.record_credential_spend(cred_id, cost_f64);
+                    }
                     if let Err(e) = middleware::spend::check_and_increment_spend(
                         &state_bg.cache,
                         state_bg.db.pool(),
@@ -2779,6 +4277,17 @@ pub async fn proxy_handler(
                 }
             }
 
+            // Per-tool-call token/cost attribution for tool analytics
+            let mut tool_calls = tool_calls;
+            cost::attribute_tool_call_costs(
+                &state_bg.pricing,
+                pricing_provider,
+                model_name.as_deref().unwrap_or("unknown"),
+                &mut tool_calls,
+                completion_tokens,
+            )
+            .await;
+
             // Sanitize accumulated content for audit log
             let full_content = sr.as_ref().map(|r| r.content.clone()).unwrap_or_default();
             let sanitized_content = middleware::sanitize::sanitize_stream_content(&full_content);
@@ -2800,7 +4309,7 @@ pub async fn proxy_handler(
                 tenant_id_bg,
                 ext_req_id_bg,
                 session_id_bg,
-                parent_span_id_bg,
+                trace_context_bg,
                 custom_properties.clone(),
             );
             audit.policy_result = Some(if hitl_required {
@@ -2811,8 +4320,27 @@ pub async fn proxy_handler(
             audit.upstream_status = Some(200);
             audit.response_latency_ms = start.elapsed().as_millis() as u64;
             audit.is_streaming = true;
+            audit.provider = Some(detected_provider.as_str().to_string());
+            audit.provider_hinted = hinted_provider.is_some();
+            audit.model_downgraded_from = model_downgraded_from_bg;
+            audit.model_remapped_from = model_remapped_from_bg;
+            audit.migration_path = migration_path_bg.map(|p| p.as_str().to_string());
+            audit.max_tokens_clamp = max_tokens_clamp_bg;
+            audit.capability_stripped = capability_stripped_bg;
+            audit.json_mode_emulated = json_mode_emulated;
+            audit.policy_eval_timings = policy_eval_timings_bg;
+            audit.experiment_name = experiment_name_bg;
+            audit.variant_name = variant_name_bg;
+            audit.feedback_score = feedback_score;
+            audit.test_upstream_override = test_upstream_override_bg;
+            if let Some((estimated, window, trimmed)) = context_check_bg {
+                audit.context_estimated_tokens = Some(estimated);
+                audit.context_window_tokens = Some(window);
+                audit.context_messages_trimmed = trimmed;
+            }
             audit.prompt_tokens = prompt_tokens;
             audit.completion_tokens = completion_tokens;
+            audit.tokens_estimated = tokens_estimated;
             audit.model = model_name;
             audit.finish_reason = finish_reason;
             // Serialize tool calls to JSON Value for audit storage
@@ -2825,6 +4353,15 @@ pub async fn proxy_handler(
             audit.tool_call_count = tool_calls.len() as u16;
             audit.ttft_ms = ttft_ms;
             audit.estimated_cost_usd = estimated_cost_usd;
+            audit.request_cost_estimate_usd = cost_ceiling_estimate;
+            audit.request_cost_cap_exceeded =
+                request_cost_over_cap(&token_bg_id, estimated_cost_usd, request_cost_cap);
+            // Headers (and a 200) already went out; the client saw the
+            // synthetic error event appended by the stream bridge.
+            if let Some(r) = sr.as_ref().filter(|r| r.stream_error.is_some()) {
+                audit.error_type = Some("stream_interrupted".to_string());
+                audit.partial_content_len = Some(r.content.chars().count() as u32);
+            }
             audit.fields_redacted = if sanitized_content.redacted_types.is_empty() {
                 None
             } else {
@@ -2864,16 +4401,47 @@ pub async fn proxy_handler(
     // -- 5.5 Post-flight policy evaluation --
     let mut resp_body_vec = resp_body.to_vec();
 
+    // Policies and sanitization need the plain body; it's forwarded decoded.
+    if let Some(encoding) = resp_headers
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+    {
+        match proxy::content_encoding::decode(&encoding, &resp_body_vec) {
+            Ok(Some(decoded)) => {
+                resp_body_vec = decoded;
+                resp_headers.remove(reqwest::header::CONTENT_ENCODING);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(
+                content_encoding = %encoding,
+                "upstream response body could not be decoded, passing through: {}",
+                e
+            ),
+        }
+    }
+
     // ── Universal Model Router: translate JSON responses ──
     // BUG-01 FIX: Removed dead `is_streaming_req` branch — streaming + success
     // requests are handled by the fast path (line 1948) and never reach here.
+    // Set when translation would have produced an empty completion; the raw
+    // provider body is returned instead and the audit entry is flagged.
+    let mut translation_fallback = false;
     if status.is_success() {
         // Non-streaming JSON response: translate to OpenAI format
         if let Ok(parsed) = serde_json::from_slice::<serde_json::Value>(&resp_body_vec) {
-            if let Some(translated) =
-                proxy::model_router::translate_response(detected_provider, &parsed, &detected_model)
-            {
-                resp_body_vec = serde_json::to_vec(&translated).unwrap_or(resp_body_vec);
+            match proxy::model_router::translate_response_checked(
+                detected_provider,
+                &parsed,
+                &detected_model,
+            ) {
+                proxy::model_router::ResponseTranslation::Translated(translated) => {
+                    resp_body_vec = serde_json::to_vec(&translated).unwrap_or(resp_body_vec);
+                }
+                proxy::model_router::ResponseTranslation::Fallback => {
+                    translation_fallback = true;
+                }
+                proxy::model_router::ResponseTranslation::Passthrough => {}
             }
         }
     } else {
@@ -3013,14 +4581,19 @@ pub async fn proxy_handler(
             // Translate response if non-OpenAI provider
             resp_body_vec =
                 if let Ok(parsed) = serde_json::from_slice::<serde_json::Value>(&loop_body) {
-                    if let Some(translated) = proxy::model_router::translate_response(
+                    match proxy::model_router::translate_response_checked(
                         detected_provider,
                         &parsed,
                         &detected_model,
                     ) {
-                        serde_json::to_vec(&translated).unwrap_or(loop_body)
-                    } else {
-                        loop_body
+                        proxy::model_router::ResponseTranslation::Translated(translated) => {
+                            serde_json::to_vec(&translated).unwrap_or(loop_body)
+                        }
+                        proxy::model_router::ResponseTranslation::Fallback => {
+                            translation_fallback = true;
+                            loop_body
+                        }
+                        proxy::model_router::ResponseTranslation::Passthrough => loop_body,
                     }
                 } else {
                     loop_body
@@ -3035,19 +4608,7 @@ pub async fn proxy_handler(
                 mcp_cumulative_completion_tokens += iter_completion;
 
                 // Convert Provider enum to string for cost calculation
-                let provider_str = match detected_provider {
-                    proxy::model_router::Provider::Anthropic => "anthropic",
-                    proxy::model_router::Provider::Gemini => "google",
-                    proxy::model_router::Provider::Mistral => "mistral",
-                    proxy::model_router::Provider::Bedrock => "bedrock",
-                    proxy::model_router::Provider::Groq => "groq",
-                    proxy::model_router::Provider::Cohere => "cohere",
-                    proxy::model_router::Provider::TogetherAI => "together",
-                    proxy::model_router::Provider::Ollama => "ollama",
-                    proxy::model_router::Provider::AzureOpenAI => "openai",
-                    proxy::model_router::Provider::OpenAI => "openai",
-                    proxy::model_router::Provider::Unknown => "openai",
-                };
+                let provider_str = detected_provider.pricing_name();
 
                 // Calculate and immediately bill for this iteration's cost
                 let iter_cost = cost::calculate_cost_with_cache(
@@ -3102,6 +4663,25 @@ pub async fn proxy_handler(
         }
     }
 
+    // Emulated JSON mode: hand the client clean JSON content, as a native
+    // json_object response would be.
+    if json_mode_emulated && status.is_success() {
+        if let Ok(mut parsed) = serde_json::from_slice::<serde_json::Value>(&resp_body_vec) {
+            let (cleaned, failed) = proxy::json_mode::clean_response(&mut parsed);
+            if failed > 0 {
+                tracing::warn!(
+                    token_id = %token.id,
+                    provider = ?detected_provider,
+                    choices = failed,
+                    "emulated JSON mode: response contained no JSON"
+                );
+            }
+            if cleaned > 0 {
+                resp_body_vec = serde_json::to_vec(&parsed).unwrap_or(resp_body_vec);
+            }
+        }
+    }
+
     let parsed_resp_body: Option<serde_json::Value> = serde_json::from_slice(&resp_body_vec).ok();
 
     // Convert reqwest headers to axum headers for RequestContext
@@ -3159,63 +4739,54 @@ pub async fn proxy_handler(
 
     // FIX: Skip billing if upstream returned 4xx or 5xx error
     if estimated_cost_usd.is_none() && status.is_success() {
-        match extract_usage(&token.upstream_url, &sanitized_body) {
+        let final_cost = match extract_usage(&token.upstream_url, &sanitized_body) {
             Ok(Some((input, output))) => {
                 audit_prompt_tokens = Some(input);
                 audit_completion_tokens = Some(output);
                 let model = extract_model(&sanitized_body).unwrap_or("unknown".to_string());
                 audit_model = Some(model.clone());
-                let provider = if token.upstream_url.contains("anthropic")
-                    && !token.upstream_url.contains("bedrock")
-                {
-                    "anthropic"
-                } else if token.upstream_url.contains("generativelanguage")
-                    || token.upstream_url.contains("googleapis")
-                {
-                    "google"
-                } else if token.upstream_url.contains("mistral") {
-                    "mistral"
-                } else if token.upstream_url.contains("bedrock") {
-                    "bedrock"
-                } else if token.upstream_url.contains("groq") {
-                    "groq"
-                } else if token.upstream_url.contains("cohere") {
-                    "cohere"
-                } else if token.upstream_url.contains("together") {
-                    "together"
-                } else if token.upstream_url.contains("localhost:11434")
-                    || token.upstream_url.contains("ollama")
-                {
-                    "ollama"
-                } else {
-                    "openai"
-                };
-                let final_cost = cost::calculate_cost_with_cache(
-                    &state.pricing,
-                    provider,
-                    &model,
-                    input,
-                    output,
-                )
-                .await;
-
-                if !final_cost.is_zero() {
-                    estimated_cost_usd = Some(final_cost);
-                    let cost_f64 = final_cost.to_f64().unwrap_or(0.0);
-                    if let Err(e) = middleware::spend::check_and_increment_spend(
-                        &state.cache,
-                        state.db.pool(),
-                        &token.id,
-                        cost_f64,
+                Some(
+                    cost::calculate_cost_with_cache(
+                        &state.pricing,
+                        pricing_provider,
+                        &model,
+                        input,
+                        output,
                     )
-                    .await
-                    {
-                        tracing::error!("Spend cap exceeded or tracking failed: {}", e);
-                    }
+                    .await,
+                )
+            }
+            // Image endpoints report no usage; they're billed per image.
+            Ok(None) => match cost::ImageRequest::from_request(&path, parsed_body.as_ref()) {
+                Some(image) => {
+                    audit_model = Some(image.model.clone());
+                    cost::calculate_image_cost_with_cache(&state.pricing, pricing_provider, &image)
+                        .await
                 }
+                None => None,
+            },
+            Err(e) => {
+                tracing::warn!("Failed to extract usage: {}", e);
+                None
+            }
+        };
+
+        if let Some(final_cost) = final_cost.filter(|c| !c.is_zero()) {
+            estimated_cost_usd = Some(final_cost);
+            let cost_f64 = final_cost.to_f64().unwrap_or(0.0);
+            if let Some(cred_id) = effective_credential_id {
+                state.lb.record_credential_spend(cred_id, cost_f64);
+            }
+            if let Err(e) = middleware::spend::check_and_increment_spend(
+                &state.cache,
+                state.db.pool(),
+                &token.id,
+                cost_f64,
+            )
+            .await
+            {
+                tracing::error!("Spend cap exceeded or tracking failed: {}", e);
             }
-            Ok(None) => {}
-            Err(e) => tracing::warn!("Failed to extract usage: {}", e),
         }
     } else if estimated_cost_usd.is_none() && !status.is_success() {
         tracing::debug!(
@@ -3224,6 +4795,7 @@ pub async fn proxy_handler(
         );
     }
 
+    let mut schema_coercions: Vec<String> = Vec::new();
     {
         let project_id_str = token.project_id.to_string();
         let post_ctx = RequestContext {
@@ -3233,6 +4805,7 @@ pub async fn proxy_handler(
             headers: &headers,
             body: parsed_body.as_ref(),
             body_size: body.len(),
+            estimated_cost_usd: request_cost_estimate,
             agent_name: agent_name.as_deref(),
             token_id: &token.id,
             token_name: &token.name,
@@ -3441,6 +5014,38 @@ pub async fn proxy_handler(
                     }
                 }
 
+                // ── InjectResponseField (post-flight, response-side) ──
+                // Parse the current body so earlier post-flight edits are kept.
+                Action::InjectResponseField {
+                    path,
+                    value,
+                    from,
+                    remove_source,
+                } => {
+                    if let Ok(mut resp_json) =
+                        serde_json::from_slice::<serde_json::Value>(&resp_body_vec)
+                    {
+                        let set = proxy::transform::inject_response_field(
+                            &mut resp_json,
+                            path,
+                            value.as_ref(),
+                            from.as_deref(),
+                            *remove_source,
+                        );
+                        if set > 0 {
+                            if let Ok(new_body) = serde_json::to_vec(&resp_json) {
+                                resp_body_vec = new_body;
+                            }
+                        }
+                        tracing::debug!(
+                            policy = %triggered.policy_name,
+                            path = %path,
+                            set,
+                            "applied post-flight response field injection"
+                        );
+                    }
+                }
+
                 // ConditionalRoute is request-phase only — skip post-flight
                 Action::ConditionalRoute { .. } => {
                     tracing::debug!(
@@ -3458,9 +5063,9 @@ pub async fn proxy_handler(
                     if let Some(ref resp_json) = parsed_resp_body {
                         let result = middleware::guardrail::validate_schema(resp_json, schema);
                         // `not` mode: invert – pass only if validation FAILS
-                        let should_deny = if *not { result.valid } else { !result.valid };
+                        let should_deny = result.violated(*not);
                         if should_deny {
-                            let default_msg = if *not {
+                            let default_msg = if *not && !result.schema_error {
                                 "Response matches a forbidden schema pattern".to_string()
                             } else {
                                 format!(
@@ -3489,6 +5094,43 @@ pub async fn proxy_handler(
                     }
                 }
 
+                // ── CoerceResponseSchema (post-flight, response-side) ──
+                // Parse the current body so earlier post-flight edits are kept.
+                Action::CoerceResponseSchema { schema } => {
+                    if let Ok(resp_json) =
+                        serde_json::from_slice::<serde_json::Value>(&resp_body_vec)
+                    {
+                        match middleware::guardrail::coerce_to_schema(&resp_json, schema) {
+                            Ok(result) if !result.changes.is_empty() => {
+                                tracing::info!(
+                                    policy = %triggered.policy_name,
+                                    changes = ?result.changes,
+                                    "coerced response toward schema"
+                                );
+                                if let Ok(new_body) = serde_json::to_vec(&result.body) {
+                                    resp_body_vec = new_body;
+                                }
+                                schema_coercions.extend(result.changes);
+                            }
+                            Ok(_) => {}
+                            Err(errors) => {
+                                tracing::warn!(
+                                    policy = %triggered.policy_name,
+                                    errors = ?errors,
+                                    "schema coercion failed, blocking response"
+                                );
+                                return Err(AppError::PolicyDenied {
+                                    policy: triggered.policy_name.clone(),
+                                    reason: format!(
+                                        "Response could not be coerced to the required schema: {}",
+                                        errors.join("; ")
+                                    ),
+                                });
+                            }
+                        }
+                    }
+                }
+
                 Action::ExternalGuardrail {
                     vendor,
                     endpoint,
@@ -3580,10 +5222,14 @@ pub async fn proxy_handler(
                 (req, resp, None, None)
             }
             2 => {
-                // Level 2: Full debug — store raw bodies + headers (auto-expires in 24h)
-                let req = parsed_body
-                    .as_ref()
-                    .map(|v| serde_json::to_string(v).unwrap_or_default());
+                // Level 2: Full debug — store raw bodies + headers (auto-expires in 24h).
+                // The request body is the client's, before policies rewrote
+                // it, so audit replay re-runs what was actually sent. Binary
+                // bodies (audio, image and file uploads) aren't stored.
+                let req = std::str::from_utf8(&body)
+                    .ok()
+                    .filter(|b| !b.is_empty())
+                    .map(str::to_string);
                 let resp = parsed_resp_body
                     .as_ref()
                     .map(|v| serde_json::to_string(v).unwrap_or_default());
@@ -3595,10 +5241,18 @@ pub async fn proxy_handler(
         };
 
     // ── Phase 5: LLM Observability extraction ─────────────────
-    let llm_tool_calls = parsed_resp_body
+    let mut llm_tool_calls = parsed_resp_body
         .as_ref()
         .map(crate::models::llm::extract_tool_calls_from_value)
         .unwrap_or_default();
+    cost::attribute_tool_call_costs(
+        &state.pricing,
+        pricing_provider,
+        audit_model.as_deref().unwrap_or(&detected_model),
+        &mut llm_tool_calls,
+        audit_completion_tokens,
+    )
+    .await;
     let llm_finish_reason = parsed_resp_body
         .as_ref()
         .and_then(crate::models::llm::extract_finish_reason_from_value);
@@ -3635,7 +5289,7 @@ pub async fn proxy_handler(
         tenant_id,
         external_request_id,
         session_id,
-        parent_span_id,
+        trace_context,
         custom_properties.clone(),
     );
     audit.policy_result = Some(if hitl_required {
@@ -3657,6 +5311,10 @@ pub async fn proxy_handler(
         Some(shadow_violations)
     };
     audit.estimated_cost_usd = estimated_cost_usd;
+    audit.request_cost_estimate_usd = cost_ceiling_estimate;
+    audit.request_cost_cap_exceeded =
+        request_cost_over_cap(&token.id, estimated_cost_usd, request_cost_cap);
+    audit.rejected_credential_id = rejected_credential_id;
     // Phase 4
     audit.log_level = log_level;
     audit.request_body = logged_req_body;
@@ -3675,29 +5333,73 @@ pub async fn proxy_handler(
     audit.error_type = llm_error_type;
     audit.is_streaming = is_streaming_req;
     audit.cache_hit = false; // not a cache hit — we went to upstream
+    audit.translation_fallback = translation_fallback;
+    audit.provider = Some(detected_provider.as_str().to_string());
+    audit.provider_hinted = hinted_provider.is_some();
     audit.experiment_name = experiment_name;
     audit.variant_name = variant_name;
+    audit.feedback_score = feedback_score;
+    audit.param_defaults_applied = if param_defaults_applied.is_empty() {
+        None
+    } else {
+        Some(param_defaults_applied)
+    };
+    audit.model_downgraded_from = model_downgraded_from.clone();
+    audit.model_remapped_from = model_remapped_from.clone();
+    audit.migration_path = migration_path_taken.map(|p| p.as_str().to_string());
+    audit.schema_coercions = (!schema_coercions.is_empty()).then_some(schema_coercions);
+    audit.max_tokens_clamp = max_tokens_clamp;
+    audit.capability_stripped = capability_stripped_audit;
+    audit.json_mode_emulated = json_mode_emulated;
+    audit.policy_eval_timings = policy_eval_timings;
+    audit.test_upstream_override = test_upstream_override.clone();
+    if let Some((estimated, window, trimmed)) = context_check {
+        audit.context_estimated_tokens = Some(estimated);
+        audit.context_window_tokens = Some(window);
+        audit.context_messages_trimmed = trimmed;
+    }
+    audit.body_fields_stripped = if body_fields_stripped.is_empty() {
+        None
+    } else {
+        Some(body_fields_stripped)
+    };
     let session_id_for_spend = audit.session_id.clone();
     audit.emit(&state);
 
     // -- Session spend increment (non-streaming) --
-    // session_id was consumed by audit builder above, so we use the clone
+    // session_id was consumed by audit builder above, so we use the clone.
+    // Tokens that opted into the session cost header wait for the update so the
+    // header reflects this request; everyone else updates in the background.
+    let mut session_cost_total: Option<rust_decimal::Decimal> = None;
     if let Some(ref sid) = session_id_for_spend {
         let cost = estimated_cost_usd.unwrap_or_default();
         let tokens =
             audit_prompt_tokens.unwrap_or(0) as i64 + audit_completion_tokens.unwrap_or(0) as i64;
-        let state_for_session = state.clone();
-        let sid_owned = sid.clone();
-        let project_id = token.project_id;
-        tokio::spawn(async move {
-            if let Err(e) = state_for_session
+        if token.session_cost_header {
+            match state
                 .db
-                .increment_session_spend(&sid_owned, project_id, cost, tokens)
+                .increment_session_spend(sid, token.project_id, cost, tokens)
                 .await
             {
-                tracing::warn!(session_id = %sid_owned, error = %e, "Failed to increment session spend");
+                Ok(total) => session_cost_total = total,
+                Err(e) => {
+                    tracing::warn!(session_id = %sid, error = %e, "Failed to increment session spend")
+                }
             }
-        });
+        } else {
+            let state_for_session = state.clone();
+            let sid_owned = sid.clone();
+            let project_id = token.project_id;
+            tokio::spawn(async move {
+                if let Err(e) = state_for_session
+                    .db
+                    .increment_session_spend(&sid_owned, project_id, cost, tokens)
+                    .await
+                {
+                    tracing::warn!(session_id = %sid_owned, error = %e, "Failed to increment session spend");
+                }
+            });
+        }
     }
 
     // ── Response Cache: store successful, non-streaming responses ──
@@ -3710,9 +5412,12 @@ pub async fn proxy_handler(
                 model: audit_model_for_cache,
                 prompt_tokens: audit_prompt_tokens,
                 completion_tokens: audit_completion_tokens,
+                stored_at: Some(chrono::Utc::now().timestamp()),
+                ttl_secs: Some(proxy::response_cache::DEFAULT_CACHE_TTL_SECS),
             };
             let state_ref = state.clone();
             let key = key.clone();
+            let keep_stale = token.serve_stale_on_error;
             tokio::spawn(async move {
                 proxy::response_cache::set_cached(
                     &state_ref.cache,
@@ -3721,6 +5426,15 @@ pub async fn proxy_handler(
                     proxy::response_cache::DEFAULT_CACHE_TTL_SECS,
                 )
                 .await;
+                if keep_stale {
+                    proxy::response_cache::set_stale(
+                        &state_ref.cache,
+                        &key,
+                        &cached,
+                        state_ref.config.cache_stale_grace_secs,
+                    )
+                    .await;
+                }
             });
         }
     }
@@ -3779,6 +5493,38 @@ pub async fn proxy_handler(
             response = response.header("x-trueflow-route-reason", hv);
         }
     }
+    if let Some(ref from) = model_downgraded_from {
+        if let Ok(hv) = axum::http::HeaderValue::from_str(from) {
+            response = response.header("x-trueflow-model-downgraded", hv);
+        }
+    }
+    if let Some(ref from) = model_remapped_from {
+        if let Ok(hv) = axum::http::HeaderValue::from_str(from) {
+            response = response.header("x-trueflow-model-remapped", hv);
+        }
+    }
+    if !dropped_fields.is_empty() {
+        if let Ok(hv) = axum::http::HeaderValue::from_str(&dropped_fields) {
+            response = response.header("x-trueflow-dropped-fields", hv);
+        }
+    }
+    if !capability_stripped.is_empty() {
+        if let Ok(hv) = axum::http::HeaderValue::from_str(&capability_stripped) {
+            response = response.header("x-trueflow-capability-stripped", hv);
+        }
+    }
+    if let Some(limit) = adaptive_limit {
+        response = response.header("x-trueflow-adaptive-limit", limit);
+    }
+    // A cacheable request that reached the upstream missed the cache.
+    if cache_key.is_some() {
+        response = response.header("x-trueflow-cache", "MISS");
+    }
+    if let Some(total) = session_cost_total {
+        if let Ok(hv) = axum::http::HeaderValue::from_str(&total.round_dp(6).to_string()) {
+            response = response.header("x-trueflow-session-cost-usd", hv);
+        }
+    }
     // Attach request ID to every response for support correlation
     let req_id_str = format!("req_{}", request_id.simple());
     if let Ok(req_id_hv) = axum::http::HeaderValue::from_str(&req_id_str) {
@@ -3823,13 +5569,33 @@ pub async fn proxy_handler(
     // Spawn background evaluation for rules that opted into async_check=true.
     // The response is committed before these are evaluated — violations are
     // logged and trigger audit/webhook events but cannot block the response.
-    if !pre_async_triggered.is_empty() {
-        let _state_async = state.clone();
+    // Concurrency is capped by `state.async_guardrail_permits`; when every
+    // permit is taken the evaluation is dropped rather than queued so a burst
+    // cannot pile up unbounded vendor/webhook calls.
+    let async_permit = if pre_async_triggered.is_empty() {
+        None
+    } else {
+        match state.async_guardrail_permits.clone().try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                tracing::warn!(
+                    token_id = %token.id,
+                    rules = pre_async_triggered.len(),
+                    limit = state.config.async_guardrail_concurrency,
+                    "async guardrail: concurrency limit reached, skipping evaluation"
+                );
+                None
+            }
+        }
+    };
+    if let Some(permit) = async_permit {
         let token_id_async = token.id.clone();
         let async_triggered = pre_async_triggered;
         // Snapshot the sanitized body for async guardrail content checks
         let async_body_snapshot = parsed_body.clone();
         tokio::spawn(async move {
+            // Held until every rule has been evaluated.
+            let _permit = permit;
             for triggered in &async_triggered {
                 tracing::info!(
                     token_id = %token_id_async,
@@ -3871,7 +5637,7 @@ pub async fn proxy_handler(
                         if let Some(ref body) = async_body_snapshot {
                             let result =
                                 crate::middleware::guardrail::validate_schema(body, schema);
-                            let violated = if *not { result.valid } else { !result.valid };
+                            let violated = result.violated(*not);
                             if violated {
                                 tracing::warn!(
                                     token_id = %token_id_async,
@@ -3921,6 +5687,391 @@ pub async fn proxy_handler(
         .map_err(|e| AppError::Internal(anyhow::anyhow!("response build failed: {}", e)))
 }
 
+/// Post-flight check of the per-request cost ceiling. The pre-flight estimate
+/// can't bound requests without an output limit, so an overrun is flagged on
+/// the audit entry once the actual cost is known.
+fn request_cost_over_cap(
+    token_id: &str,
+    cost: Option<rust_decimal::Decimal>,
+    cap: Option<rust_decimal::Decimal>,
+) -> bool {
+    match (cost, cap) {
+        (Some(cost), Some(cap)) if cost > cap => {
+            tracing::warn!(
+                token_id = %token_id,
+                cost = %cost,
+                cap = %cap,
+                "request cost exceeded the per-request limit"
+            );
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The last cached response for a failed request, when the token opted into
+/// `serve_stale_on_error` and the request was cacheable.
+async fn stale_fallback(
+    state: &Arc<AppState>,
+    serve_stale_on_error: bool,
+    cache_key: Option<&str>,
+) -> Option<proxy::response_cache::CachedResponse> {
+    if !serve_stale_on_error {
+        return None;
+    }
+    proxy::response_cache::get_stale(&state.cache, cache_key?).await
+}
+
+/// Answer with a stale cached response after an upstream failure. The audit
+/// keeps the upstream's failure status and flags the stale hit. Not billed:
+/// nothing new was generated.
+fn serve_stale(
+    state: &Arc<AppState>,
+    mut audit: super::audit::AuditBuilder,
+    stale: proxy::response_cache::CachedResponse,
+    start: Instant,
+) -> Result<Response, AppError> {
+    tracing::warn!(
+        token_id = %audit.token_id,
+        upstream_status = ?audit.upstream_status,
+        "upstream failed, serving stale cached response"
+    );
+    let freshness = stale.freshness(chrono::Utc::now().timestamp());
+    audit.response_latency_ms = start.elapsed().as_millis() as u64;
+    audit.cache_hit = true;
+    audit.stale_cache_served = true;
+    audit.model = stale.model;
+    audit.prompt_tokens = stale.prompt_tokens;
+    audit.completion_tokens = stale.completion_tokens;
+    audit.emit(state);
+
+    let status = StatusCode::from_u16(stale.status).unwrap_or(StatusCode::OK);
+    let mut response = Response::builder()
+        .status(status)
+        .header("content-type", stale.content_type)
+        .header("x-trueflow-cache", "STALE")
+        .header("x-trueflow-stale", "true");
+    if let Some((age, _)) = freshness {
+        response = response.header("x-trueflow-cache-age", age);
+    }
+    response
+        .body(Body::from(stale.body))
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("stale response build failed: {}", e)))
+}
+
+/// Reject the request if the token's spend cap or its project's hard cap
+/// is exhausted, emitting the deny audit entry (and spend-cap webhook).
+async fn enforce_budget_caps(
+    state: &Arc<AppState>,
+    token: &crate::store::postgres::TokenRow,
+    start: Instant,
+    deny_audit: &impl Fn() -> super::audit::AuditBuilder,
+) -> Result<(), AppError> {
+    if let Err(e) =
+        middleware::spend::check_spend_cap(&state.cache, state.db.pool(), &token.id).await
+    {
+        let mut audit = deny_audit();
+        audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+            policy: "SpendCap".to_string(),
+            reason: e.to_string(),
+        });
+        audit.response_latency_ms = start.elapsed().as_millis() as u64;
+        audit.emit(state);
+
+        // Webhook dispatch
+        let webhook_event = crate::notification::webhook::WebhookEvent::spend_cap_exceeded(
+            &token.id,
+            &token.name,
+            &token.project_id.to_string(),
+            &e.to_string(),
+        );
+        dispatch_webhook_event(state, token.project_id, webhook_event);
+
+        let exceeded = e.downcast_ref::<middleware::spend::SpendCapExceeded>();
+        return Err(AppError::SpendCapReached {
+            message: format!(
+                "Spend cap reached (USD): {}. Check your limits at the TrueFlow dashboard.",
+                e
+            ),
+            cap: exceeded.map(|c| c.period.to_string()),
+            resets_at: exceeded.and_then(|c| c.resets_at()),
+        });
+    }
+
+    // Project-level hard cap. Uses a 60s Redis cache to avoid a DB
+    // round-trip on every request.
+    if crate::jobs::budget_checker::is_project_over_hard_cap_cached(
+        state.db.pool(),
+        &state.cache,
+        token.project_id,
+    )
+    .await
+    {
+        let mut audit = deny_audit();
+        audit.policy_result = Some(crate::models::audit::PolicyResult::Deny {
+            policy: "ProjectBudgetCap".to_string(),
+            reason: "Project hard spend cap exceeded".to_string(),
+        });
+        audit.response_latency_ms = start.elapsed().as_millis() as u64;
+        audit.emit(state);
+
+        return Err(AppError::SpendCapReached {
+            message: "Project spending limit reached. Contact your administrator or review limits at the TrueFlow dashboard.".to_string(),
+            cap: Some("project".to_string()),
+            resets_at: None,
+        });
+    }
+
+    Ok(())
+}
+
+/// Deliver an event to the global `TRUEFLOW_WEBHOOK_URLS` and to every project
+/// webhook subscribed to its type. Runs in the background.
+fn dispatch_webhook_event(
+    state: &Arc<AppState>,
+    project_id: Uuid,
+    event: crate::notification::webhook::WebhookEvent,
+) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        state
+            .webhook
+            .dispatch(&state.config.webhook_urls, event.clone())
+            .await;
+        match state.db.list_webhook_targets(project_id).await {
+            Ok(targets) => state.webhook.dispatch_signed(&targets, event).await,
+            Err(e) => {
+                tracing::warn!(project_id = %project_id, error = %e, "failed to load project webhooks")
+            }
+        }
+    });
+}
+
+/// Minimum interval between operator alerts for the same credential.
+const CREDENTIAL_ALERT_INTERVAL: Duration = Duration::from_secs(300);
+
+static DECRYPTION_ALERTS: once_cell::sync::Lazy<dashmap::DashMap<Uuid, Instant>> =
+    once_cell::sync::Lazy::new(dashmap::DashMap::new);
+
+/// Alert operators (webhook + Slack) that a credential failed to decrypt.
+///
+/// A wrong master key fails every request, so alerts are throttled per
+/// credential to avoid flooding the configured channels.
+fn notify_credential_decryption_failure(
+    state: &Arc<AppState>,
+    token: &crate::store::postgres::TokenRow,
+    cred_id: Uuid,
+) {
+    let now = Instant::now();
+    let mut should_alert = false;
+    DECRYPTION_ALERTS
+        .entry(cred_id)
+        .and_modify(|last| {
+            if now.duration_since(*last) >= CREDENTIAL_ALERT_INTERVAL {
+                *last = now;
+                should_alert = true;
+            }
+        })
+        .or_insert_with(|| {
+            should_alert = true;
+            now
+        });
+    if !should_alert {
+        return;
+    }
+
+    let event = crate::notification::webhook::WebhookEvent::credential_decryption_failed(
+        &token.id,
+        &token.name,
+        &token.project_id.to_string(),
+        &cred_id.to_string(),
+    );
+    dispatch_webhook_event(state, token.project_id, event);
+    let slack = state.notifier.clone();
+    let text = format!(
+        "🔐 *Credential decryption failed* 🔐\n\nCredential `{}` (token `{}`) could not be decrypted. \
+         TRUEFLOW_MASTER_KEY may be wrong or the stored ciphertext is corrupted.",
+        cred_id, token.id
+    );
+    tokio::spawn(async move {
+        if let Err(e) = slack.send_alert(&text).await {
+            tracing::error!("Failed to send credential decryption alert: {}", e);
+        }
+    });
+}
+
+/// Read an upstream response's body so it can be inspected, returning the
+/// decoded body and an equivalent response to carry on with.
+async fn buffer_upstream_response(
+    resp: reqwest::Response,
+) -> Result<(Vec<u8>, reqwest::Response), AppError> {
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let raw = resp
+        .bytes()
+        .await
+        .map_err(|e| AppError::Upstream(format!("upstream body read failed: {}", e)))?;
+    let body = headers
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|encoding| {
+            proxy::content_encoding::decode(encoding, &raw)
+                .ok()
+                .flatten()
+        })
+        .unwrap_or_else(|| raw.to_vec());
+    let mut rebuilt = axum::http::Response::new(raw);
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok((body, reqwest::Response::from(rebuilt)))
+}
+
+static CREDENTIAL_AUTH_ALERTS: once_cell::sync::Lazy<dashmap::DashMap<Uuid, Instant>> =
+    once_cell::sync::Lazy::new(dashmap::DashMap::new);
+
+/// Alert operators (webhook + Slack) that the upstream rejected a stored
+/// credential. Throttled per credential like decryption failures, since a
+/// revoked key fails every request that uses it.
+fn notify_credential_auth_failure(
+    state: &Arc<AppState>,
+    token: &crate::store::postgres::TokenRow,
+    cred_id: Uuid,
+    upstream_status: u16,
+) {
+    let now = Instant::now();
+    let mut should_alert = false;
+    CREDENTIAL_AUTH_ALERTS
+        .entry(cred_id)
+        .and_modify(|last| {
+            if now.duration_since(*last) >= CREDENTIAL_ALERT_INTERVAL {
+                *last = now;
+                should_alert = true;
+            }
+        })
+        .or_insert_with(|| {
+            should_alert = true;
+            now
+        });
+    if !should_alert {
+        return;
+    }
+
+    let event = crate::notification::webhook::WebhookEvent::credential_auth_failed(
+        &token.id,
+        &token.name,
+        &token.project_id.to_string(),
+        &cred_id.to_string(),
+        upstream_status,
+    );
+    dispatch_webhook_event(state, token.project_id, event);
+    let slack = state.notifier.clone();
+    let text = format!(
+        "🔑 *Upstream rejected credential* 🔑\n\nThe provider answered {} to credential `{}` (token `{}`). \
+         The key may be invalid, expired or revoked — rotate it.",
+        upstream_status, cred_id, token.id
+    );
+    tokio::spawn(async move {
+        if let Err(e) = slack.send_alert(&text).await {
+            tracing::error!("Failed to send credential auth failure alert: {}", e);
+        }
+    });
+}
+
+/// Count a request against its credential's shared per-model limit, first
+/// waiting up to the limit's `queue_ms` while either window is full. Returns
+/// the exhausted counter and its limit when the request must be rejected.
+async fn acquire_credential_model_limit(
+    cache: &crate::cache::TieredCache,
+    credential_id: &str,
+    model: &str,
+    limit: &middleware::model_rate_limit::ModelRateLimit,
+    request_tokens: u64,
+) -> anyhow::Result<Option<(middleware::model_rate_limit::ModelLimitKind, u64)>> {
+    use crate::cache::WindowAcquire;
+    use middleware::model_rate_limit::{window_key, ModelLimitKind, MODEL_RATE_LIMIT_WINDOW_SECS};
+
+    let window = MODEL_RATE_LIMIT_WINDOW_SECS;
+    let rpm_key = window_key(credential_id, model, ModelLimitKind::Rpm);
+    let tpm_key = window_key(credential_id, model, ModelLimitKind::Tpm);
+
+    if limit.queue_ms > 0 {
+        let deadline = Instant::now() + Duration::from_millis(limit.queue_ms);
+        loop {
+            let rpm_full = match limit.rpm {
+                Some(rpm) => cache.count_sliding_window(&rpm_key, window).await? >= rpm,
+                None => false,
+            };
+            let tpm_full = match limit.tpm {
+                Some(tpm) => {
+                    cache.sum_weighted_sliding_window(&tpm_key, window).await? + request_tokens
+                        > tpm
+                }
+                None => false,
+            };
+            let now = Instant::now();
+            if !(rpm_full || tpm_full) || now >= deadline {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(250).min(deadline - now)).await;
+        }
+    }
+
+    // Checked and recorded atomically, so the result is authoritative when
+    // requests race, and a rejected request uses up neither window.
+    let outcome = cache
+        .acquire_sliding_windows(
+            &rpm_key,
+            limit.rpm,
+            &tpm_key,
+            limit.tpm,
+            request_tokens,
+            window,
+        )
+        .await?;
+    Ok(match outcome {
+        WindowAcquire::Acquired => None,
+        WindowAcquire::CountFull => limit.rpm.map(|rpm| (ModelLimitKind::Rpm, rpm)),
+        WindowAcquire::WeightFull => limit.tpm.map(|tpm| (ModelLimitKind::Tpm, tpm)),
+    })
+}
+
+/// Redis key for a `RateLimit` policy action's sliding window.
+///
+/// SEC: Include policy_id + window in key so each rate_limit policy
+/// gets its own independent counter. Without this, two policies on the
+/// same token share one counter and the stricter window resets the
+/// lenient one — a CLASS A bypass.
+pub(super) fn policy_rate_limit_key(
+    policy_id: Uuid,
+    window_secs: u64,
+    key: &crate::models::policy::RateLimitKey,
+    token_id: &str,
+    agent_name: Option<&str>,
+    client_ip: Option<&str>,
+    user_id: Option<&str>,
+) -> String {
+    let policy_prefix = format!("rl:{}:{}s", policy_id, window_secs);
+    match key {
+        crate::models::policy::RateLimitKey::PerToken => {
+            format!("{}:tok:{}", policy_prefix, token_id)
+        }
+        crate::models::policy::RateLimitKey::PerAgent => {
+            format!(
+                "{}:agent:{}",
+                policy_prefix,
+                agent_name.unwrap_or("unknown")
+            )
+        }
+        crate::models::policy::RateLimitKey::PerIp => {
+            format!("{}:ip:{}", policy_prefix, client_ip.unwrap_or("unknown"))
+        }
+        crate::models::policy::RateLimitKey::PerUser => {
+            format!("{}:user:{}", policy_prefix, user_id.unwrap_or(token_id))
+        }
+        crate::models::policy::RateLimitKey::Global => format!("{}:global", policy_prefix),
+    }
+}
+
 fn extract_bearer_token(headers: &HeaderMap) -> Result<String, AppError> {
     let auth = headers
         .get("authorization")
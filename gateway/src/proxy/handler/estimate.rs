@@ -0,0 +1,493 @@
+//! Estimate-only requests (`X-TrueFlow-Estimate-Only: true`).
+//!
+//! Agent frameworks want to show "this will cost ~$X" before confirming a
+//! call. An estimate-only request is authenticated and matched against the
+//! token's pre-flight policies like any other, then answered with the
+//! estimated prompt tokens and cost and whether the gateway would deny it —
+//! the upstream is never called.
+//!
+//! Nothing is consumed: rate-limit windows, request counters and spend caps
+//! are read, not incremented, and no audit row is written. Actions with side
+//! effects (approvals, webhooks, external guardrails, NLP redaction, routing)
+//! are not run; a `RequireApproval` match is reported as `requires_approval`.
+
+use axum::http::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::AppError;
+use crate::middleware;
+use crate::models::policy::{Action, TriggeredAction};
+use crate::models::{cost, tokenizer};
+use crate::proxy;
+use crate::store::postgres::TokenRow;
+use crate::AppState;
+
+/// Request headers that turn a proxy request into an estimate.
+/// `X-AILink-Estimate-Only` is the legacy spelling.
+const ESTIMATE_ONLY_HEADERS: &[&str] = &["x-trueflow-estimate-only", "x-ailink-estimate-only"];
+
+pub(crate) fn is_estimate_only(headers: &HeaderMap) -> bool {
+    ESTIMATE_ONLY_HEADERS.iter().any(|h| {
+        headers
+            .get(*h)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("true") || v.trim() == "1")
+    })
+}
+
+/// Response body of an estimate-only request.
+#[derive(Debug, Serialize)]
+pub(crate) struct CostEstimate {
+    pub estimated_prompt_tokens: u32,
+    /// Completion tokens the client reserved (`max_tokens` and aliases), 0 if none.
+    pub estimated_completion_tokens: u32,
+    pub estimated_cost_usd: f64,
+    pub model: String,
+    pub provider: String,
+    pub would_be_denied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denial_reason: Option<String>,
+    /// Policy (or built-in check such as `SpendCap`) that would deny the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denied_by: Option<String>,
+    /// A `RequireApproval` policy matched; the real request would wait for HITL.
+    pub requires_approval: bool,
+}
+
+/// Who the request is attributed to, for per-agent/IP/user rate-limit keys.
+pub(crate) struct RateLimitSubject<'a> {
+    pub agent_name: Option<&'a str>,
+    pub client_ip: Option<&'a str>,
+    pub user_id: Option<&'a str>,
+}
+
+/// Outcome of the side-effect-free policy actions.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct LocalOutcome {
+    /// `(policy, reason)` of the first action that would reject the request.
+    pub denial: Option<(String, String)>,
+    pub requires_approval: bool,
+}
+
+/// Evaluate the actions that only inspect the request: `Deny`, `ContentFilter`,
+/// block-mode `Redact` (regex patterns only), `ToolScope`, `RequireProperties`
+/// and `KeywordBlock`. Stops at the first denial, as the proxy does.
+pub(crate) fn evaluate_local_actions(
+    actions: &[TriggeredAction],
+    body: Option<&Value>,
+    custom_properties: Option<&Value>,
+) -> LocalOutcome {
+    let mut outcome = LocalOutcome::default();
+    for triggered in actions {
+        let reason = match &triggered.action {
+            Action::Deny { message, .. } => Some(message.clone()),
+            Action::RequireApproval { .. } => {
+                outcome.requires_approval = true;
+                None
+            }
+            Action::ContentFilter { .. } => body
+                .map(|b| middleware::guardrail::check_content(b, &triggered.action))
+                .filter(|r| r.blocked)
+                .map(|r| {
+                    r.reason
+                        .unwrap_or_else(|| "Content filter blocked request".to_string())
+                }),
+            Action::Redact { .. } => body.and_then(|b| {
+                let mut copy = b.clone();
+                let result = middleware::redact::apply_redact(&mut copy, &triggered.action, true);
+                result.should_block.then(|| {
+                    format!(
+                        "Request contains PII that violates policy '{}'",
+                        triggered.policy_name
+                    )
+                })
+            }),
+            Action::ToolScope {
+                allowed_tools,
+                blocked_tools,
+                deny_message,
+            } => {
+                let tool_names = middleware::engine::extract_tool_names(body);
+                if tool_names.is_empty() {
+                    None
+                } else {
+                    middleware::engine::evaluate_tool_scope(
+                        &tool_names,
+                        allowed_tools,
+                        blocked_tools,
+                        deny_message,
+                    )
+                    .err()
+                }
+            }
+            Action::RequireProperties {
+                required_keys,
+                allowed_values,
+            } => {
+                let offending = middleware::engine::evaluate_required_properties(
+                    custom_properties,
+                    required_keys,
+                    allowed_values.as_ref(),
+                );
+                (!offending.is_empty()).then(|| {
+                    format!(
+                        "X-Properties missing or invalid keys: {}",
+                        offending.join(", ")
+                    )
+                })
+            }
+            Action::KeywordBlock { message, .. } => body
+                .and_then(|b| {
+                    middleware::guardrail::keyword::find_blocked_term(b, &triggered.action)
+                })
+                .map(|term| {
+                    message
+                        .clone()
+                        .unwrap_or_else(|| format!("request contains blocked term '{}'", term))
+                }),
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            outcome.denial = Some((triggered.policy_name.clone(), reason));
+            return outcome;
+        }
+    }
+    outcome
+}
+
+/// Build the estimate: token count and cost, then the policy outcome —
+/// local actions, rate limits (peeked), spend caps, then the model allowlist
+/// and context-window checks, in that order.
+pub(crate) async fn estimate(
+    state: &AppState,
+    token: &TokenRow,
+    actions: &[TriggeredAction],
+    body: Option<&Value>,
+    custom_properties: Option<&Value>,
+    subject: RateLimitSubject<'_>,
+) -> Result<CostEstimate, AppError> {
+    let model = body
+        .and_then(|b| b.get("model"))
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_string();
+    let detected_provider = proxy::model_router::detect_provider(&model, &token.upstream_url);
+    let provider = detected_provider.pricing_name();
+    let prompt_tokens = body.map_or(0, tokenizer::estimate_prompt_tokens);
+    let completion_tokens = body.map_or(0, tokenizer::requested_output_tokens);
+    let cost = cost::calculate_cost_with_cache(
+        &state.pricing,
+        provider,
+        &model,
+        prompt_tokens,
+        completion_tokens,
+    )
+    .await;
+
+    let local = evaluate_local_actions(actions, body, custom_properties);
+    let mut denial = local.denial;
+    if denial.is_none() {
+        denial = peek_rate_limits(state, token, actions, &subject).await?;
+    }
+    if denial.is_none() {
+        denial = peek_spend_caps(state, token).await;
+    }
+    if denial.is_none() {
+        denial = check_model(state, token, &model, detected_provider.as_str(), body).await;
+    }
+
+    let (denied_by, denial_reason) = denial.unzip();
+    Ok(CostEstimate {
+        estimated_prompt_tokens: prompt_tokens,
+        estimated_completion_tokens: completion_tokens,
+        estimated_cost_usd: rust_decimal::prelude::ToPrimitive::to_f64(&cost).unwrap_or(0.0),
+        model,
+        provider: provider.to_string(),
+        would_be_denied: denied_by.is_some(),
+        denial_reason,
+        denied_by,
+        requires_approval: local.requires_approval,
+    })
+}
+
+/// Would this request push a rate-limit window over its limit? Counts the
+/// windows without adding an entry.
+async fn peek_rate_limits(
+    state: &AppState,
+    token: &TokenRow,
+    actions: &[TriggeredAction],
+    subject: &RateLimitSubject<'_>,
+) -> Result<Option<(String, String)>, AppError> {
+    let mut policy_rate_limited = false;
+    for triggered in actions {
+        let Action::RateLimit {
+            window,
+            max_requests,
+            key,
+        } = &triggered.action
+        else {
+            continue;
+        };
+        policy_rate_limited = true;
+        let window_secs = middleware::policy::parse_window_secs(window).unwrap_or(60);
+        let rl_key = super::core::policy_rate_limit_key(
+            triggered.policy_id,
+            window_secs,
+            key,
+            &token.id,
+            subject.agent_name,
+            subject.client_ip,
+            subject.user_id,
+        );
+        let count = state
+            .cache
+            .count_sliding_window(&rl_key, window_secs)
+            .await
+            .map_err(AppError::Internal)?;
+        if count + 1 > *max_requests {
+            return Ok(Some((
+                triggered.policy_name.clone(),
+                "rate limit exceeded".to_string(),
+            )));
+        }
+    }
+
+    if !policy_rate_limited && state.config.default_rate_limit > 0 {
+        let count = state
+            .cache
+            .count_sliding_window(
+                &format!("rl:default:tok:{}", token.id),
+                state.config.default_rate_limit_window,
+            )
+            .await
+            .map_err(AppError::Internal)?;
+        if count + 1 > state.config.default_rate_limit {
+            return Ok(Some((
+                "DefaultRateLimit".to_string(),
+                "rate limit exceeded".to_string(),
+            )));
+        }
+    }
+
+    let adaptive_cfg = token
+        .adaptive_rate_limit
+        .as_ref()
+        .and_then(|v| proxy::adaptive_limit::AdaptiveRateLimitConfig::from_value(v).ok());
+    if let Some(cfg) = adaptive_cfg {
+        let limit = state.adaptive_limits.current_limit(&token.id, &cfg);
+        let count = state
+            .cache
+            .count_sliding_window(&format!("rl:adaptive:tok:{}", token.id), cfg.window_secs)
+            .await
+            .map_err(AppError::Internal)?;
+        if count + 1 > limit {
+            return Ok(Some((
+                "AdaptiveRateLimit".to_string(),
+                format!(
+                    "adaptive rate limit of {} req/{}s exceeded",
+                    limit, cfg.window_secs
+                ),
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// The model pre-flight checks: the token's allowed models and model groups,
+/// then its `context_window_action`. Under `trim`, only a request that
+/// trimming can't fit is a denial.
+async fn check_model(
+    state: &AppState,
+    token: &TokenRow,
+    model: &str,
+    provider: &str,
+    body: Option<&Value>,
+) -> Option<(String, String)> {
+    if model.is_empty() {
+        return None;
+    }
+    let group_models = match token.allowed_model_group_ids.as_deref() {
+        Some(ids) if !ids.is_empty() => {
+            middleware::model_access::resolve_group_models(state.db.pool(), ids, token.project_id)
+                .await
+        }
+        _ => Vec::new(),
+    };
+    if let Err(reason) = middleware::model_access::check_model_access(
+        model,
+        token.allowed_models.as_ref(),
+        &group_models,
+    ) {
+        return Some(("ModelAllowlist".to_string(), reason));
+    }
+
+    let action = token
+        .context_window_action
+        .as_deref()
+        .and_then(middleware::context_window::OverflowAction::from_name)?;
+    let body = body?;
+    let context_window = match state
+        .capabilities
+        .lookup(provider, model)
+        .await
+        .and_then(|c| c.max_context)
+    {
+        Some(window) => window,
+        None => state.context_windows.lookup(model).await?,
+    };
+    context_window_denial(model, context_window, action, body)
+}
+
+/// Run the context-window guard on a copy of the body: `Some` when the request
+/// wouldn't fit (after trimming, under `trim`).
+fn context_window_denial(
+    model: &str,
+    context_window: u32,
+    action: middleware::context_window::OverflowAction,
+    body: &Value,
+) -> Option<(String, String)> {
+    let guard = middleware::context_window::ContextWindowGuard {
+        context_window,
+        action,
+    };
+    match guard.apply(&mut body.clone()) {
+        middleware::context_window::GuardOutcome::Overflow { estimated_tokens } => Some((
+            "ContextWindow".to_string(),
+            AppError::ContextWindowExceeded {
+                model: model.to_string(),
+                estimated_tokens,
+                context_window,
+            }
+            .to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// Token and project spend caps. Both checks only read the counters.
+async fn peek_spend_caps(state: &AppState, token: &TokenRow) -> Option<(String, String)> {
+    if let Err(e) =
+        middleware::spend::check_spend_cap(&state.cache, state.db.pool(), &token.id).await
+    {
+        return Some(("SpendCap".to_string(), e.to_string()));
+    }
+    if crate::jobs::budget_checker::is_project_over_hard_cap_cached(
+        state.db.pool(),
+        &state.cache,
+        token.project_id,
+    )
+    .await
+    {
+        return Some((
+            "ProjectBudgetCap".to_string(),
+            "Project hard spend cap exceeded".to_string(),
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use uuid::Uuid;
+
+    fn triggered(name: &str, action: Value) -> TriggeredAction {
+        TriggeredAction {
+            policy_id: Uuid::nil(),
+            policy_name: name.to_string(),
+            rule_index: 0,
+            action: serde_json::from_value(action).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_estimate_only_header() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_estimate_only(&headers));
+        headers.insert("x-ailink-estimate-only", HeaderValue::from_static("TRUE"));
+        assert!(is_estimate_only(&headers));
+        headers.clear();
+        headers.insert(
+            "x-trueflow-estimate-only",
+            HeaderValue::from_static("false"),
+        );
+        assert!(!is_estimate_only(&headers));
+    }
+
+    #[test]
+    fn test_estimate_local_actions() {
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "my ssn is 123-45-6789"}]
+        });
+        let approval = triggered("review", serde_json::json!({"action": "require_approval"}));
+        let block_pii = triggered(
+            "no-pii",
+            serde_json::json!({"action": "redact", "patterns": ["ssn"], "on_match": "block"}),
+        );
+        let deny = triggered(
+            "closed",
+            serde_json::json!({"action": "deny", "message": "maintenance"}),
+        );
+
+        let outcome = evaluate_local_actions(
+            &[approval.clone(), block_pii.clone(), deny.clone()],
+            Some(&body),
+            None,
+        );
+        assert!(outcome.requires_approval);
+        let (policy, reason) = outcome.denial.unwrap();
+        assert_eq!(policy, "no-pii");
+        assert!(reason.contains("PII"));
+
+        // Clean body: the block-mode redact passes and the deny is reported.
+        let clean = serde_json::json!({"messages": [{"role": "user", "content": "hi"}]});
+        let outcome = evaluate_local_actions(&[block_pii, deny], Some(&clean), None);
+        assert_eq!(
+            outcome.denial,
+            Some(("closed".to_string(), "maintenance".to_string()))
+        );
+        assert!(!outcome.requires_approval);
+
+        // Rate limits are peeked separately, never counted here.
+        let rl = triggered(
+            "rl",
+            serde_json::json!({"action": "rate_limit", "window": "1m", "max_requests": 0}),
+        );
+        assert_eq!(
+            evaluate_local_actions(&[rl], Some(&clean), None),
+            LocalOutcome::default()
+        );
+    }
+
+    #[test]
+    fn test_estimate_context_window_denial() {
+        use middleware::context_window::OverflowAction;
+        let long = "x".repeat(4_000);
+        let body = serde_json::json!({
+            "model": "gpt-4",
+            "messages": [
+                {"role": "user", "content": long},
+                {"role": "assistant", "content": "ok"},
+                {"role": "user", "content": "hi"},
+            ]
+        });
+        let (policy, reason) =
+            context_window_denial("gpt-4", 500, OverflowAction::Reject, &body).unwrap();
+        assert_eq!(policy, "ContextWindow");
+        assert!(reason.contains("500"), "{reason}");
+        // Trimming the old turn makes it fit, so it isn't a denial; the
+        // caller's body is left as sent.
+        assert_eq!(
+            context_window_denial("gpt-4", 500, OverflowAction::Trim, &body),
+            None
+        );
+        assert_eq!(body["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            context_window_denial("gpt-4", 100_000, OverflowAction::Reject, &body),
+            None
+        );
+    }
+}
@@ -19,6 +19,91 @@ pub(crate) fn is_sensitive_header(name: &str) -> bool {
         .any(|h| name.eq_ignore_ascii_case(h))
 }
 
+/// Upper bound on a token's `forward_trace_headers` allowlist.
+pub(crate) const MAX_FORWARD_TRACE_HEADERS: usize = 20;
+
+/// Gateway-internal header namespaces, never forwarded from the client.
+const INTERNAL_HEADER_PREFIXES: &[&str] = &["x-trueflow-", "x-ailink-"];
+
+/// Headers the gateway sets itself or that describe the client connection.
+/// `traceparent`/`tracestate` are already propagated as W3C context.
+const GATEWAY_MANAGED_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "content-length",
+    "content-type",
+    "content-encoding",
+    "user-agent",
+    "traceparent",
+    "tracestate",
+];
+
+/// Whether `name` may appear in a `forward_trace_headers` allowlist: a valid
+/// header name that isn't credential-bearing, gateway-managed, or in the
+/// internal `X-TrueFlow-*` / `X-AILink-*` namespace.
+pub(crate) fn is_forwardable_trace_header(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    axum::http::HeaderName::from_bytes(name.as_bytes()).is_ok()
+        && !is_sensitive_header(&lower)
+        && !GATEWAY_MANAGED_HEADERS.contains(&lower.as_str())
+        && !INTERNAL_HEADER_PREFIXES
+            .iter()
+            .any(|p| lower.starts_with(p))
+}
+
+/// Parse a W3C `traceparent` header (`{version}-{trace_id}-{parent_id}-{flags}`)
+/// into its lowercase trace id and parent span id. `None` unless both are
+/// well-formed, non-zero hex ids.
+pub(crate) fn parse_traceparent(value: &str) -> Option<(String, String)> {
+    let mut parts = value.trim().split('-');
+    let (version, trace_id, parent_id, flags) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let is_id = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit()) && s.bytes().any(|b| b != b'0')
+    };
+    let is_byte = |s: &str| s.len() == 2 && s.bytes().all(|b| b.is_ascii_hexdigit());
+    (is_byte(version)
+        && version != "ff"
+        && is_byte(flags)
+        && is_id(trace_id, 32)
+        && is_id(parent_id, 16))
+    .then(|| {
+        (
+            trace_id.to_ascii_lowercase(),
+            parent_id.to_ascii_lowercase(),
+        )
+    })
+}
+
+/// Copy allowlisted correlation headers from the client request to the
+/// upstream request. Headers already set (by credentials or transform
+/// policies) are left alone; names that fail the allowlist rules are skipped.
+pub(crate) fn forward_trace_headers(
+    allowlist: &[String],
+    client: &HeaderMap,
+    upstream: &mut reqwest::header::HeaderMap,
+) {
+    for name in allowlist.iter().filter(|n| is_forwardable_trace_header(n)) {
+        let Ok(header_name) = reqwest::header::HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        if upstream.contains_key(&header_name) {
+            continue;
+        }
+        for value in client.get_all(name.as_str()) {
+            if let Ok(v) = reqwest::header::HeaderValue::from_bytes(value.as_bytes()) {
+                upstream.append(header_name.clone(), v);
+            }
+        }
+    }
+}
+
 /// Convert axum HeaderMap to JSON object for Level 2 logging.
 pub(crate) fn headers_to_json(headers: &HeaderMap) -> serde_json::Value {
     let mut map = serde_json::Map::new();
@@ -81,4 +166,65 @@ mod tests {
         assert!(!is_sensitive_header("user-agent"));
         assert!(!is_sensitive_header("x-session-id"));
     }
+
+    // ── forward_trace_headers ──────────────────────────────────────────
+
+    #[test]
+    fn test_forwardable_trace_header_rules() {
+        assert!(is_forwardable_trace_header("X-Correlation-Id"));
+        assert!(is_forwardable_trace_header("x-trace-id"));
+        assert!(!is_forwardable_trace_header("X-TrueFlow-Agent-Name"));
+        assert!(!is_forwardable_trace_header("X-AILink-Internal"));
+        assert!(!is_forwardable_trace_header("Authorization"));
+        assert!(!is_forwardable_trace_header("host"));
+        assert!(!is_forwardable_trace_header("traceparent"));
+        assert!(!is_forwardable_trace_header("bad header"));
+        assert!(!is_forwardable_trace_header(""));
+    }
+
+    #[test]
+    fn test_parse_traceparent() {
+        assert_eq!(
+            parse_traceparent("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01"),
+            Some((
+                "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+                "00f067aa0ba902b7".to_string()
+            ))
+        );
+        for bad in [
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-not-hex-01",
+        ] {
+            assert_eq!(parse_traceparent(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_forward_trace_headers_copies_only_allowlisted() {
+        let mut client = HeaderMap::new();
+        client.insert("x-correlation-id", "corr-1".parse().unwrap());
+        client.insert("x-trace-id", "t-1".parse().unwrap());
+        client.insert("x-other", "nope".parse().unwrap());
+        client.insert("x-trueflow-test", "internal".parse().unwrap());
+
+        let mut upstream = reqwest::header::HeaderMap::new();
+        upstream.insert("x-trace-id", "set-by-policy".parse().unwrap());
+        let allowlist = vec![
+            "X-Correlation-Id".to_string(),
+            "x-trace-id".to_string(),
+            "x-trueflow-test".to_string(),
+            "x-missing".to_string(),
+        ];
+        forward_trace_headers(&allowlist, &client, &mut upstream);
+
+        assert_eq!(upstream["x-correlation-id"], "corr-1");
+        assert_eq!(upstream["x-trace-id"], "set-by-policy");
+        assert!(!upstream.contains_key("x-other"));
+        assert!(!upstream.contains_key("x-trueflow-test"));
+        assert!(!upstream.contains_key("x-missing"));
+    }
 }
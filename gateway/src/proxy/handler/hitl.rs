@@ -0,0 +1,130 @@
+//! HITL approval wait: delivery of the decision and cleanup when the caller
+//! hangs up mid-wait.
+//!
+//! When a client disconnects, hyper drops the request future, so the wait is
+//! cancelled at whatever `.await` it is parked on. Everything it owns — the
+//! dedicated BLPOP connection in particular — is dropped with it, closing
+//! the socket and releasing the server-side block. [`abandon_on_drop`] turns
+//! that drop into an explicit outcome so the approval doesn't sit `pending`
+//! until it expires.
+
+use std::future::Future;
+
+/// Runs its callback when dropped, unless disarmed first.
+struct AbandonGuard(Option<Box<dyn FnOnce() + Send>>);
+
+impl AbandonGuard {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for AbandonGuard {
+    fn drop(&mut self) {
+        if let Some(on_abandon) = self.0.take() {
+            on_abandon();
+        }
+    }
+}
+
+/// Await `wait`; if the returned future is dropped before `wait` finishes
+/// (the client went away), run `on_abandon` instead. `on_abandon` runs
+/// synchronously inside `Drop`, so it should only spawn follow-up work.
+pub(super) async fn abandon_on_drop<F>(
+    wait: F,
+    on_abandon: impl FnOnce() + Send + 'static,
+) -> F::Output
+where
+    F: Future,
+{
+    let guard = AbandonGuard(Some(Box::new(on_abandon)));
+    let output = wait.await;
+    guard.disarm();
+    output
+}
+
+/// Block on `hitl:decision:{id}` over a dedicated Redis connection (not the
+/// shared ConnectionManager), so BLPOP blocks only this connection.
+/// `Ok(None)` on timeout, `Err` if Redis is unavailable.
+pub(super) async fn blpop_decision(
+    hitl_key: &str,
+    timeout_secs: f64,
+) -> Result<Option<String>, ()> {
+    // BLPOP treats 0 as "block forever" — an exhausted budget must not wait at all.
+    if timeout_secs <= 0.0 {
+        return Ok(None);
+    }
+    let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".into());
+    let client = redis::Client::open(redis_url.as_str()).map_err(|e| {
+        tracing::warn!("HITL: failed to create dedicated Redis client: {}", e);
+    })?;
+    let mut conn = client
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| {
+            tracing::warn!("HITL: failed to open dedicated Redis connection: {}", e);
+        })?;
+
+    // BLPOP blocks until a value is pushed or timeout expires.
+    // Returns Option<(key, value)> tuple.
+    let result: redis::RedisResult<Option<(String, String)>> = redis::cmd("BLPOP")
+        .arg(hitl_key)
+        .arg(timeout_secs)
+        .query_async(&mut conn)
+        .await;
+
+    match result {
+        Ok(Some((_key, value))) => Ok(Some(value)),
+        Ok(None) => Ok(None), // timeout expired
+        Err(e) => {
+            tracing::warn!("HITL BLPOP failed: {}", e);
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_dropped_wait_releases_connection_and_abandons() {
+        // Stands in for the dedicated BLPOP connection held during the wait.
+        let conn = Arc::new(());
+        let abandoned = Arc::new(AtomicBool::new(false));
+
+        let held = conn.clone();
+        let flag = abandoned.clone();
+        let request = tokio::spawn(abandon_on_drop(
+            async move {
+                let _conn = held;
+                std::future::pending::<Option<String>>().await
+            },
+            move || flag.store(true, Ordering::SeqCst),
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(Arc::strong_count(&conn), 2, "connection held mid-wait");
+        assert!(!abandoned.load(Ordering::SeqCst));
+
+        // The client hangs up: hyper drops the request future.
+        request.abort();
+        assert!(request.await.unwrap_err().is_cancelled());
+        assert_eq!(Arc::strong_count(&conn), 1, "connection released");
+        assert!(abandoned.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_completed_wait_is_not_abandoned() {
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let flag = abandoned.clone();
+        let decision = abandon_on_drop(async { Some("approved".to_string()) }, move || {
+            flag.store(true, Ordering::SeqCst)
+        })
+        .await;
+        assert_eq!(decision.as_deref(), Some("approved"));
+        assert!(!abandoned.load(Ordering::SeqCst));
+    }
+}
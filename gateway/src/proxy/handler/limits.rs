@@ -0,0 +1,112 @@
+//! Structural limits on request bodies, checked before JSON parsing.
+//!
+//! `serde_json::from_slice` into a `Value` allocates every node up front, and
+//! policy evaluation then walks the whole tree. A body that fits the size limit
+//! can still be a million-element array or nested thousands of levels deep.
+//! `check_json_limits` scans the raw bytes once, without allocating per node,
+//! so such bodies are rejected before the parser commits to them.
+
+/// Which structural limit a body exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JsonLimit {
+    Depth,
+    ArrayLength,
+}
+
+impl JsonLimit {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            JsonLimit::Depth => "depth",
+            JsonLimit::ArrayLength => "array_length",
+        }
+    }
+}
+
+/// Scan a JSON body and return the first limit it exceeds.
+///
+/// `max_depth` bounds object/array nesting; `max_array_len` bounds the element
+/// count of any single array (including `messages`). Either may be 0 to disable
+/// it. Malformed JSON is not an error here — the parser reports that later.
+pub(crate) fn check_json_limits(
+    body: &[u8],
+    max_depth: usize,
+    max_array_len: usize,
+) -> Result<(), (JsonLimit, usize)> {
+    // One entry per open container: `Some(commas)` for arrays, `None` for objects.
+    // The stack never grows past `max_depth`, so memory stays bounded.
+    let mut stack: Vec<Option<usize>> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &b in body {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                if max_depth > 0 && stack.len() >= max_depth {
+                    return Err((JsonLimit::Depth, max_depth));
+                }
+                stack.push((b == b'[').then_some(0));
+            }
+            b'}' | b']' => {
+                stack.pop();
+            }
+            b',' => {
+                if let Some(Some(commas)) = stack.last_mut() {
+                    *commas += 1;
+                    // n commas separate n + 1 elements.
+                    if max_array_len > 0 && *commas >= max_array_len {
+                        return Err((JsonLimit::ArrayLength, max_array_len));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_limits_depth() {
+        let ok = br#"{"messages":[{"role":"user","content":[{"type":"text","text":"hi"}]}]}"#;
+        assert_eq!(check_json_limits(ok, 5, 0), Ok(()));
+        assert_eq!(check_json_limits(ok, 4, 0), Err((JsonLimit::Depth, 4)));
+
+        let deep = "[".repeat(10_000);
+        assert_eq!(
+            check_json_limits(deep.as_bytes(), 64, 0),
+            Err((JsonLimit::Depth, 64))
+        );
+        // Brackets inside strings don't count.
+        let quoted = br#"{"text":"[[[[[[[[{{{{{{\"]]]"}"#;
+        assert_eq!(check_json_limits(quoted, 1, 0), Ok(()));
+        assert_eq!(check_json_limits(deep.as_bytes(), 0, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_json_limits_array_length() {
+        let three = br#"{"messages":[1,2,3],"stop":["a,b,c,d,e"]}"#;
+        assert_eq!(check_json_limits(three, 0, 3), Ok(()));
+        assert_eq!(
+            check_json_limits(three, 0, 2),
+            Err((JsonLimit::ArrayLength, 2))
+        );
+
+        // Object members are not array elements.
+        let wide = br#"{"a":1,"b":2,"c":3,"d":4}"#;
+        assert_eq!(check_json_limits(wide, 0, 2), Ok(()));
+    }
+}
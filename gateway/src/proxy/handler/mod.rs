@@ -1,7 +1,11 @@
 mod audit;
 mod core;
+mod estimate;
 mod headers;
+mod hitl;
+mod limits;
 mod security;
 
 pub use self::core::proxy_handler;
+pub(crate) use self::headers::{is_forwardable_trace_header, MAX_FORWARD_TRACE_HEADERS};
 pub(crate) use self::security::is_safe_webhook_url;
@@ -28,13 +28,28 @@ pub(crate) fn is_public_ip(ip: std::net::IpAddr) -> bool {
                 && (v6.segments()[0] & 0xfe00) != 0xfc00
                 // Link-local fe80::/10
                 && (v6.segments()[0] & 0xffc0) != 0xfe80
-                // IPv4-mapped ::ffff:x.x.x.x — validate the embedded v4
-                && v6
-                    .to_ipv4_mapped().is_none_or(|v4| is_public_ip(std::net::IpAddr::V4(v4)))
+                // IPv4-mapped / -compatible / NAT64 — validate the embedded v4
+                && embedded_ipv4(v6).is_none_or(|v4| is_public_ip(std::net::IpAddr::V4(v4)))
         }
     }
 }
 
+/// Extract an IPv4 address tunnelled inside an IPv6 literal, so it can be run
+/// through the V4 checks: IPv4-mapped `::ffff:a.b.c.d`, deprecated
+/// IPv4-compatible `::a.b.c.d`, and the NAT64 well-known prefix
+/// `64:ff9b::a.b.c.d` (RFC 6052), which a NAT64 gateway forwards to the v4 host.
+fn embedded_ipv4(v6: std::net::Ipv6Addr) -> Option<std::net::Ipv4Addr> {
+    if let Some(v4) = v6.to_ipv4() {
+        return Some(v4);
+    }
+    let s = v6.segments();
+    if s[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let o = v6.octets();
+        return Some(std::net::Ipv4Addr::new(o[12], o[13], o[14], o[15]));
+    }
+    None
+}
+
 /// SEC: SSRF protection for policy-defined webhook URLs.
 ///
 /// Two-stage check:
@@ -122,11 +137,52 @@ pub(crate) async fn is_safe_webhook_url(url_str: &str) -> bool {
     }
 }
 
+/// The token's `test_upstream_override`, if it applies to this request.
+///
+/// Only honored when the gateway runs with TRUEFLOW_ALLOW_TEST_OVERRIDES, and
+/// never for service-proxy paths, whose upstream comes from the service row.
+pub(crate) fn active_test_upstream_override(
+    override_url: Option<&str>,
+    allow_test_overrides: bool,
+    is_service_path: bool,
+) -> Option<String> {
+    override_url
+        .filter(|_| allow_test_overrides && !is_service_path)
+        .map(str::to_string)
+}
+
 // ── Unit Tests ──────────────────────────────────────────────────────────
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // ── active_test_upstream_override ──────────────────────────────────
+
+    #[test]
+    fn test_upstream_override_ignored_unless_allowed() {
+        assert_eq!(
+            active_test_upstream_override(Some("http://mock:9000"), false, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_upstream_override_applies_when_allowed() {
+        assert_eq!(
+            active_test_upstream_override(Some("http://mock:9000"), true, false).as_deref(),
+            Some("http://mock:9000")
+        );
+        assert_eq!(active_test_upstream_override(None, true, false), None);
+    }
+
+    #[test]
+    fn test_upstream_override_skips_service_paths() {
+        assert_eq!(
+            active_test_upstream_override(Some("http://mock:9000"), true, true),
+            None
+        );
+    }
+
     // ── SSRF: is_safe_webhook_url ──────────────────────────────────────
     // NOTE: is_safe_webhook_url is async (DNS-resolving). Literal-IP tests complete
     // instantly (no DNS); domain tests resolve via the system resolver.
@@ -152,6 +208,48 @@ mod tests {
         assert!(!is_safe_webhook_url("http://[::ffff:10.0.0.1]/hook").await);
     }
 
+    #[tokio::test]
+    async fn test_ssrf_blocks_ipv6_reserved_ranges() {
+        // Unique-local fc00::/7 — both halves
+        assert!(!is_safe_webhook_url("http://[fc00::1]/hook").await);
+        assert!(!is_safe_webhook_url("http://[fdff:ffff::1]/hook").await);
+        // Link-local fe80::/10 — upper edge
+        assert!(!is_safe_webhook_url("http://[febf::1]/hook").await);
+        // Unspecified
+        assert!(!is_safe_webhook_url("http://[::]/hook").await);
+        // AWS IMDS IPv6 endpoint
+        assert!(!is_safe_webhook_url("http://[fd00:ec2::254]/latest/meta-data/").await);
+    }
+
+    #[tokio::test]
+    async fn test_ssrf_blocks_metadata_embedded_in_ipv6() {
+        // IPv4-mapped, both dotted and hex notation
+        assert!(!is_safe_webhook_url("http://[::ffff:169.254.169.254]/latest/meta-data/").await);
+        assert!(!is_safe_webhook_url("http://[::ffff:a9fe:a9fe]/latest/meta-data/").await);
+        assert!(!is_safe_webhook_url("http://[::ffff:100.100.100.200]/").await);
+        // Deprecated IPv4-compatible form
+        assert!(!is_safe_webhook_url("http://[::169.254.169.254]/").await);
+        assert!(!is_safe_webhook_url("http://[::192.168.0.1]/").await);
+        // NAT64 well-known prefix
+        assert!(!is_safe_webhook_url("http://[64:ff9b::169.254.169.254]/").await);
+        assert!(!is_safe_webhook_url("http://[64:ff9b::10.0.0.1]/").await);
+    }
+
+    #[test]
+    fn test_is_public_ip_ipv6_embedded_public_v4_allowed() {
+        assert!(is_public_ip("::ffff:203.0.113.1".parse().unwrap()));
+        assert!(is_public_ip("64:ff9b::203.0.113.1".parse().unwrap()));
+        assert!(is_public_ip("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_ssrf_resolves_hostnames_before_checking() {
+        // Not on the literal blocklist, so this exercises the DNS path:
+        // resolves to loopback (or fails to resolve) — either way blocked.
+        assert!(!is_safe_webhook_url("http://localhost./hook").await);
+        assert!(!is_safe_webhook_url("http://does-not-exist.invalid/hook").await);
+    }
+
     #[tokio::test]
     async fn test_ssrf_blocks_localhost() {
         assert!(!is_safe_webhook_url("http://localhost/hook").await);
@@ -0,0 +1,151 @@
+//! In-memory collector for upstream health history.
+//!
+//! The request path records each upstream call's latency and outcome into a
+//! per-URL bucket; the load balancer records circuit open/close transitions.
+//! `jobs::upstream_health` drains both into Postgres once a minute, so the hot
+//! path never touches the database.
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+
+use crate::store::postgres::{NewUpstreamHealthSample, NewUpstreamTransition};
+
+/// Latency samples kept per upstream per flush window (reservoir sampled).
+const LATENCY_RESERVOIR: usize = 128;
+/// Transitions buffered between flushes before the oldest are dropped.
+const MAX_PENDING_TRANSITIONS: usize = 10_000;
+
+struct Bucket {
+    started_at: DateTime<Utc>,
+    requests: u32,
+    failures: u32,
+    latencies_ms: Vec<i32>,
+}
+
+impl Bucket {
+    fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            requests: 0,
+            failures: 0,
+            latencies_ms: Vec::with_capacity(LATENCY_RESERVOIR),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HealthHistory {
+    buckets: DashMap<String, Bucket>,
+    transitions: Mutex<Vec<NewUpstreamTransition>>,
+}
+
+impl HealthHistory {
+    /// Record one upstream call. Latency is time to response headers.
+    pub fn record(&self, url: &str, latency_ms: u64, success: bool) {
+        let mut bucket = self
+            .buckets
+            .entry(url.to_string())
+            .or_insert_with(Bucket::new);
+        bucket.requests += 1;
+        if !success {
+            bucket.failures += 1;
+        }
+        let latency = latency_ms.min(i32::MAX as u64) as i32;
+        if bucket.latencies_ms.len() < LATENCY_RESERVOIR {
+            bucket.latencies_ms.push(latency);
+        } else {
+            // Reservoir sampling keeps an unbiased sample of the whole window.
+            let slot = rand::thread_rng().gen_range(0..bucket.requests as usize);
+            if slot < LATENCY_RESERVOIR {
+                bucket.latencies_ms[slot] = latency;
+            }
+        }
+    }
+
+    /// Record a circuit-breaker state change.
+    pub fn record_transition(&self, token_id: &str, url: &str, healthy: bool) {
+        crate::middleware::metrics::record_circuit_transition(
+            super::model_router::detect_provider("", url).as_str(),
+            healthy,
+        );
+        let mut pending = self.transitions.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.len() >= MAX_PENDING_TRANSITIONS {
+            pending.remove(0);
+        }
+        pending.push(NewUpstreamTransition {
+            upstream_url: url.to_string(),
+            token_id: token_id.to_string(),
+            healthy,
+            occurred_at: Utc::now(),
+        });
+    }
+
+    /// Take everything collected since the last drain.
+    pub fn drain(&self) -> (Vec<NewUpstreamHealthSample>, Vec<NewUpstreamTransition>) {
+        let urls: Vec<String> = self.buckets.iter().map(|e| e.key().clone()).collect();
+        let samples = urls
+            .into_iter()
+            .filter_map(|url| self.buckets.remove(&url))
+            .map(|(url, b)| NewUpstreamHealthSample {
+                upstream_url: url,
+                bucket_start: b.started_at,
+                requests: b.requests as i32,
+                failures: b.failures as i32,
+                latencies_ms: b.latencies_ms,
+            })
+            .collect();
+        let transitions =
+            std::mem::take(&mut *self.transitions.lock().unwrap_or_else(|e| e.into_inner()));
+        (samples, transitions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_aggregates_per_upstream() {
+        let history = HealthHistory::default();
+        history.record("https://a", 100, true);
+        history.record("https://a", 300, false);
+        history.record("https://b", 50, true);
+
+        let (mut samples, transitions) = history.drain();
+        samples.sort_by(|x, y| x.upstream_url.cmp(&y.upstream_url));
+        assert!(transitions.is_empty());
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].requests, 2);
+        assert_eq!(samples[0].failures, 1);
+        assert_eq!(samples[0].latencies_ms, vec![100, 300]);
+        assert_eq!(samples[1].requests, 1);
+
+        // Drained buckets start fresh
+        assert!(history.drain().0.is_empty());
+    }
+
+    #[test]
+    fn test_latency_reservoir_is_bounded() {
+        let history = HealthHistory::default();
+        for i in 0..10_000 {
+            history.record("https://a", i, true);
+        }
+        let (samples, _) = history.drain();
+        assert_eq!(samples[0].requests, 10_000);
+        assert_eq!(samples[0].latencies_ms.len(), LATENCY_RESERVOIR);
+    }
+
+    #[test]
+    fn test_transitions_are_drained_in_order() {
+        let history = HealthHistory::default();
+        history.record_transition("tok", "https://a", false);
+        history.record_transition("tok", "https://a", true);
+        let (_, transitions) = history.drain();
+        let states: Vec<bool> = transitions.iter().map(|t| t.healthy).collect();
+        assert_eq!(states, vec![false, true]);
+        assert!(history.drain().1.is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! JSON-mode emulation for providers without a native `response_format`.
+//!
+//! OpenAI-compatible providers and Gemini honour
+//! `response_format: {"type": "json_object"}`; Anthropic and Bedrock have no
+//! equivalent and the translators drop the field, so the model may answer in
+//! prose. For tokens with `json_mode_fallback`, the gateway instead adds a
+//! system instruction asking for a bare JSON object and, on non-streaming
+//! responses, extracts the JSON from each choice's content (stripping
+//! markdown fences and surrounding prose) so the client gets clean JSON.
+
+use serde_json::{json, Value};
+
+use super::model_router::Provider;
+
+/// System instruction appended for emulated JSON mode.
+pub const JSON_MODE_INSTRUCTION: &str = "Respond only with a single valid JSON object. \
+Do not wrap it in markdown code fences and do not add any text before or after it.";
+
+/// Providers whose request format has a native JSON mode the gateway can
+/// forward `response_format` to.
+pub fn supports_native_json_mode(provider: Provider) -> bool {
+    !matches!(provider, Provider::Anthropic | Provider::Bedrock)
+}
+
+/// Whether the client asked for `response_format: {"type": "json_object"}`.
+pub fn requests_json_object(body: &Value) -> bool {
+    body.pointer("/response_format/type")
+        .and_then(|t| t.as_str())
+        == Some("json_object")
+}
+
+/// Emulate JSON mode on an OpenAI-format request body: drop
+/// `response_format` and add [`JSON_MODE_INSTRUCTION`] as a system message
+/// after any leading system messages. Returns `false` (body untouched) when
+/// the body doesn't request `json_object` or has no `messages` array.
+pub fn inject_instruction(body: &mut Value) -> bool {
+    if !requests_json_object(body) {
+        return false;
+    }
+    let Some(obj) = body.as_object_mut() else {
+        return false;
+    };
+    let Some(messages) = obj.get_mut("messages").and_then(|m| m.as_array_mut()) else {
+        return false;
+    };
+    let at = messages
+        .iter()
+        .take_while(|m| m.get("role").and_then(|r| r.as_str()) == Some("system"))
+        .count();
+    messages.insert(
+        at,
+        json!({ "role": "system", "content": JSON_MODE_INSTRUCTION }),
+    );
+    obj.remove("response_format");
+    true
+}
+
+/// Extract a JSON object or array from model output: the whole text, the
+/// body of a markdown code fence (with or without a language tag), or the
+/// outermost `{...}` / `[...]` span. Returns the compact serialization.
+pub fn extract_json(text: &str) -> Option<String> {
+    let trimmed = text.trim();
+    let candidates = [
+        Some(trimmed),
+        fenced_block(trimmed),
+        outer_span(trimmed, '{', '}'),
+        outer_span(trimmed, '[', ']'),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .filter_map(|c| serde_json::from_str::<Value>(c).ok())
+        .find(|v| v.is_object() || v.is_array())
+        .map(|v| v.to_string())
+}
+
+/// Body of the first markdown code fence, without the language tag.
+fn fenced_block(text: &str) -> Option<&str> {
+    let start = text.find("```")? + 3;
+    let rest = &text[start..];
+    // Skip the info string (e.g. `json`) up to the end of the fence line.
+    let body_start = rest.find('\n').map_or(0, |i| i + 1);
+    let body = &rest[body_start..];
+    let end = body.find("```")?;
+    Some(body[..end].trim())
+}
+
+fn outer_span(text: &str, open: char, close: char) -> Option<&str> {
+    let start = text.find(open)?;
+    let end = text.rfind(close)?;
+    (end > start).then(|| &text[start..=end])
+}
+
+/// Replace each choice's `message.content` in an OpenAI-format response with
+/// the JSON extracted from it. Returns `(cleaned, failed)` counts; content
+/// with no extractable JSON is left unchanged.
+pub fn clean_response(body: &mut Value) -> (usize, usize) {
+    let Some(choices) = body.get_mut("choices").and_then(|c| c.as_array_mut()) else {
+        return (0, 0);
+    };
+    let (mut cleaned, mut failed) = (0, 0);
+    for choice in choices {
+        let Some(content) = choice.pointer_mut("/message/content") else {
+            continue;
+        };
+        let Some(text) = content.as_str() else {
+            continue;
+        };
+        match extract_json(text) {
+            Some(json_text) => {
+                if json_text != text {
+                    *content = Value::String(json_text);
+                }
+                cleaned += 1;
+            }
+            None => failed += 1,
+        }
+    }
+    (cleaned, failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_anthropic_and_bedrock_need_emulation() {
+        assert!(!supports_native_json_mode(Provider::Anthropic));
+        assert!(!supports_native_json_mode(Provider::Bedrock));
+        assert!(supports_native_json_mode(Provider::OpenAI));
+        assert!(supports_native_json_mode(Provider::Gemini));
+        assert!(supports_native_json_mode(Provider::Groq));
+    }
+
+    #[test]
+    fn test_inject_instruction_after_client_system_messages() {
+        let mut body = json!({
+            "model": "claude-3-5-sonnet",
+            "response_format": {"type": "json_object"},
+            "messages": [
+                {"role": "system", "content": "You are terse."},
+                {"role": "user", "content": "List three colors."}
+            ]
+        });
+        assert!(inject_instruction(&mut body));
+        assert!(body.get("response_format").is_none());
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["content"], "You are terse.");
+        assert_eq!(messages[1]["role"], "system");
+        assert_eq!(messages[1]["content"], JSON_MODE_INSTRUCTION);
+        assert_eq!(messages[2]["role"], "user");
+    }
+
+    #[test]
+    fn test_inject_instruction_ignores_other_formats() {
+        let mut body = json!({
+            "response_format": {"type": "text"},
+            "messages": [{"role": "user", "content": "hi"}]
+        });
+        let before = body.clone();
+        assert!(!inject_instruction(&mut body));
+        assert_eq!(body, before);
+
+        let mut no_format = json!({"messages": [{"role": "user", "content": "hi"}]});
+        assert!(!inject_instruction(&mut no_format));
+    }
+
+    #[test]
+    fn test_extract_json_strips_fences_and_prose() {
+        assert_eq!(extract_json(r#"{"a": 1}"#).as_deref(), Some(r#"{"a":1}"#));
+        assert_eq!(
+            extract_json("```json\n{\"a\": 1}\n```").as_deref(),
+            Some(r#"{"a":1}"#)
+        );
+        assert_eq!(extract_json("```\n[1, 2]\n```").as_deref(), Some("[1,2]"));
+        assert_eq!(
+            extract_json("Sure! Here it is: {\"a\": {\"b\": true}} Hope that helps.").as_deref(),
+            Some(r#"{"a":{"b":true}}"#)
+        );
+        assert_eq!(extract_json("no json here"), None);
+        assert_eq!(extract_json("42"), None);
+    }
+
+    #[test]
+    fn test_clean_response_rewrites_choice_content() {
+        let mut body = json!({
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "```json\n{\"ok\": true}\n```"}},
+                {"index": 1, "message": {"role": "assistant", "content": "I can't do that."}}
+            ]
+        });
+        assert_eq!(clean_response(&mut body), (1, 1));
+        assert_eq!(body["choices"][0]["message"]["content"], r#"{"ok":true}"#);
+        assert_eq!(body["choices"][1]["message"]["content"], "I can't do that.");
+    }
+}
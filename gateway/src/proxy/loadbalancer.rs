@@ -5,9 +5,18 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// How long a claimed half-open probe slot is held before it is presumed
+/// abandoned (e.g. a forwarded probe whose outcome was never reported).
+/// Keeps a lost probe from pinning the circuit half-open forever.
+const HALF_OPEN_PROBE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Half-life of the per-credential spend used by
+/// [`LbStrategy::WeightedLeastSpend`]: spend from an hour ago counts half.
+const CREDENTIAL_SPEND_HALF_LIFE: Duration = Duration::from_secs(3600);
+
 /// An upstream target parsed from the token's `upstreams` JSONB array.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpstreamTarget {
@@ -53,6 +62,31 @@ pub struct CircuitBreakerConfig {
     /// Prevents tripping on 1-of-1 failures. Default: 10 when rate mode is enabled.
     #[serde(default)]
     pub min_sample_size: Option<u32>,
+    /// Reject with 503 right after token lookup when every upstream's circuit is
+    /// open, skipping policy evaluation, credential decryption and usage counters.
+    /// Set false to run the full pipeline for audit completeness.
+    #[serde(default = "default_cb_enabled")]
+    pub fail_fast: bool,
+    /// How a target is picked among the healthy upstreams of a priority tier.
+    #[serde(default)]
+    pub strategy: LbStrategy,
+}
+
+/// Upstream selection mode within a priority tier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[allow(clippy::enum_variant_names)] // names mirror the `strategy` config values
+pub enum LbStrategy {
+    /// Weighted round-robin on the configured weights.
+    #[default]
+    WeightedRoundRobin,
+    /// Weighted random on `weight / (p50_ms + 1)`, so traffic drifts toward
+    /// the upstream that has been fastest. No latency history means pure weight.
+    WeightedLeastLatency,
+    /// Weighted random on `weight / (spend + tier mean spend)`, where spend is
+    /// each credential's recent, decaying USD spend, so traffic drifts toward
+    /// the account that has been used least. No spend history means pure weight.
+    WeightedLeastSpend,
 }
 
 fn default_cb_enabled() -> bool {
@@ -77,6 +111,8 @@ impl Default for CircuitBreakerConfig {
             half_open_max_requests: default_half_open_max(),
             failure_rate_threshold: None,
             min_sample_size: None,
+            fail_fast: true,
+            strategy: LbStrategy::default(),
         }
     }
 }
@@ -88,9 +124,11 @@ struct UpstreamHealth {
     is_healthy: bool,
     failure_count: u32,
     last_failure: Option<Instant>,
-    /// Number of requests allowed through in the current half-open window.
+    /// Probe requests currently in flight during the half-open window.
     /// Reset when the circuit closes (mark_healthy) or re-opens (mark_failed).
-    half_open_attempts: u32,
+    probes_in_flight: u32,
+    /// When the first probe of the current half-open window was admitted.
+    probes_started: Option<Instant>,
     /// Rolling window for rate-based circuit breaking.
     /// Stores recent request outcomes: true = success, false = failure.
     /// Bounded to max(min_sample_size, 100) entries.
@@ -101,7 +139,8 @@ struct UpstreamHealth {
 /// Uses weighted round-robin within priority tiers and automatic failover.
 pub struct LoadBalancer {
     /// Per-token health status: token_id → Vec<UpstreamHealth>
-    health: DashMap<String, Vec<UpstreamHealth>>,
+    /// Shared with outstanding [`ProbeSlot`]s so a dropped slot can give itself back.
+    health: Arc<DashMap<String, Vec<UpstreamHealth>>>,
     /// Per-token round-robin counter
     counters: DashMap<String, Arc<AtomicU64>>,
     /// In-flight request count per upstream URL (for least-busy routing)
@@ -109,6 +148,75 @@ pub struct LoadBalancer {
     /// Optional Redis connection for distributed circuit-breaker state.
     /// When set, failure counts are shared across all gateway instances.
     redis: Option<ConnectionManager>,
+    /// Latency/outcome samples and circuit transitions awaiting persistence.
+    history: super::health_history::HealthHistory,
+    /// Recent spend per credential: credential_id → (USD, last update).
+    /// Decays with [`CREDENTIAL_SPEND_HALF_LIFE`]; local to this instance.
+    credential_spend: DashMap<Uuid, (f64, Instant)>,
+}
+
+impl UpstreamHealth {
+    /// Probes still counted against `half_open_max_requests`. Slots held past
+    /// [`HALF_OPEN_PROBE_TIMEOUT`] are treated as released.
+    fn live_probes(&self) -> u32 {
+        match self.probes_started {
+            Some(started) if started.elapsed() < HALF_OPEN_PROBE_TIMEOUT => self.probes_in_flight,
+            _ => 0,
+        }
+    }
+
+    fn reset_probes(&mut self) {
+        self.probes_in_flight = 0;
+        self.probes_started = None;
+    }
+}
+
+/// A half-open probe slot taken by [`LoadBalancer::select_with_probe`].
+///
+/// Dropping it gives the slot back, so a request denied or answered before
+/// it reaches the upstream doesn't hold the circuit half-open until
+/// [`HALF_OPEN_PROBE_TIMEOUT`]. Once the request is sent, call
+/// [`forwarded`](Self::forwarded): the probe's outcome (`mark_healthy` /
+/// `mark_failed`) settles the slot from then on.
+#[must_use]
+pub struct ProbeSlot {
+    health: Arc<DashMap<String, Vec<UpstreamHealth>>>,
+    token_id: String,
+    url: String,
+    /// Start of the half-open window the slot was taken in. A slot from an
+    /// earlier window (the circuit re-opened since) has nothing to give back.
+    window: Instant,
+    armed: bool,
+}
+
+impl ProbeSlot {
+    /// Upstream URL the slot was taken on.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The probe reached the upstream: keep the slot for its outcome.
+    pub fn forwarded(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for ProbeSlot {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        if let Some(mut healths) = self.health.get_mut(&self.token_id) {
+            if let Some(h) = healths.iter_mut().find(|h| h.url == self.url) {
+                if h.probes_started == Some(self.window) && h.probes_in_flight > 0 {
+                    h.probes_in_flight -= 1;
+                    if h.probes_in_flight == 0 {
+                        h.probes_started = None;
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl LoadBalancer {
@@ -116,23 +224,32 @@ impl LoadBalancer {
     /// Used in tests and single-instance deployments.
     pub fn new() -> Self {
         Self {
-            health: DashMap::new(),
+            health: Default::default(),
             counters: DashMap::new(),
             in_flight: DashMap::new(),
             redis: None,
+            history: Default::default(),
+            credential_spend: DashMap::new(),
         }
     }
 
     /// Create a new LoadBalancer with Redis-backed distributed circuit breaking.
     pub fn new_with_redis(redis: ConnectionManager) -> Self {
         Self {
-            health: DashMap::new(),
+            health: Default::default(),
             counters: DashMap::new(),
             in_flight: DashMap::new(),
             redis: Some(redis),
+            history: Default::default(),
+            credential_spend: DashMap::new(),
         }
     }
 
+    /// Upstream health history collector (drained by `jobs::upstream_health`).
+    pub fn history(&self) -> &super::health_history::HealthHistory {
+        &self.history
+    }
+
     /// Compute a short, stable Redis key for a (token, upstream_url) pair.
     fn cb_redis_key(token_id: &str, url: &str) -> String {
         let mut hasher = Sha256::new();
@@ -150,6 +267,36 @@ impl LoadBalancer {
         upstreams: &[UpstreamTarget],
         config: &CircuitBreakerConfig,
     ) -> Option<usize> {
+        self.select_with_latency(token_id, upstreams, config, &[])
+    }
+
+    /// [`select`](Self::select) with the rolling p50 latency of each upstream
+    /// (same order as `upstreams`), used by [`LbStrategy::WeightedLeastLatency`].
+    /// Missing entries count as no history.
+    pub fn select_with_latency(
+        &self,
+        token_id: &str,
+        upstreams: &[UpstreamTarget],
+        config: &CircuitBreakerConfig,
+        p50_ms: &[Option<f64>],
+    ) -> Option<usize> {
+        let (idx, slot) = self.select_with_probe(token_id, upstreams, config, p50_ms)?;
+        if let Some(slot) = slot {
+            slot.forwarded();
+        }
+        Some(idx)
+    }
+
+    /// [`select_with_latency`](Self::select_with_latency), also returning the
+    /// half-open probe slot the pick took, if any. Drop the slot when the
+    /// request won't reach the upstream; see [`ProbeSlot`].
+    pub fn select_with_probe(
+        &self,
+        token_id: &str,
+        upstreams: &[UpstreamTarget],
+        config: &CircuitBreakerConfig,
+        p50_ms: &[Option<f64>],
+    ) -> Option<(usize, Option<ProbeSlot>)> {
         tracing::info!(
             token_id = token_id,
             upstream_count = upstreams.len(),
@@ -163,33 +310,68 @@ impl LoadBalancer {
                 return None;
             }
             if upstreams.len() == 1 {
-                return Some(0);
+                return Some((0, None));
             }
             let counter = self
                 .counters
                 .entry(token_id.to_string())
                 .or_insert_with(|| Arc::new(AtomicU64::new(0)));
             let idx = counter.fetch_add(1, Ordering::Relaxed) as usize % upstreams.len();
-            return Some(idx);
+            return Some((idx, None));
         }
         if upstreams.is_empty() {
             return None;
         }
-        if upstreams.len() == 1 {
-            // Still track health for single-upstream tokens so get_all_status() works
-            self.ensure_health(token_id, upstreams);
-            return Some(0);
-        }
-
-        // Pass cooldown parameter into health checks
-        let cooldown = config.recovery_cooldown_secs;
 
         // Ensure health entries exist
         self.ensure_health(token_id, upstreams);
 
-        // Get health snapshot
-        let health = self.health.get(token_id);
-        let health_vec = health.as_ref().map(|h| h.value());
+        // Hold the write guard across selection and probe admission so two
+        // concurrent requests can't both take the last half-open probe slot.
+        let mut health = self.health.get_mut(token_id)?;
+        let idx = self.pick_upstream(token_id, upstreams, &health, config, p50_ms)?;
+
+        // A selected upstream that is still unhealthy is half-open: this
+        // request is one of its recovery probes.
+        let mut slot = None;
+        if let Some(h) = health.iter_mut().find(|h| h.url == upstreams[idx].url) {
+            if !h.is_healthy {
+                if h.live_probes() == 0 {
+                    h.probes_started = Some(Instant::now());
+                    h.probes_in_flight = 0;
+                }
+                h.probes_in_flight += 1;
+                slot = h.probes_started.map(|window| ProbeSlot {
+                    health: self.health.clone(),
+                    token_id: token_id.to_string(),
+                    url: h.url.clone(),
+                    window,
+                    armed: true,
+                });
+                tracing::debug!(
+                    token_id = token_id,
+                    url = %h.url,
+                    probes_in_flight = h.probes_in_flight,
+                    max = config.half_open_max_requests,
+                    "circuit breaker HALF-OPEN: admitted probe request"
+                );
+            }
+        }
+        Some((idx, slot))
+    }
+
+    /// Weighted tier selection over the upstreams that are currently
+    /// available (closed, or half-open with a free probe slot).
+    fn pick_upstream(
+        &self,
+        token_id: &str,
+        upstreams: &[UpstreamTarget],
+        health_vec: &[UpstreamHealth],
+        config: &CircuitBreakerConfig,
+        p50_ms: &[Option<f64>],
+    ) -> Option<usize> {
+        // Pass cooldown parameter into health checks
+        let cooldown = config.recovery_cooldown_secs;
 
         // Find the highest priority tier (lowest number) that has healthy upstreams
         let mut priorities: Vec<u32> = upstreams.iter().map(|u| u.priority).collect();
@@ -200,11 +382,10 @@ impl LoadBalancer {
             let candidates: Vec<(usize, &UpstreamTarget)> = upstreams
                 .iter()
                 .enumerate()
-                .filter(|(i, u)| {
+                .filter(|(_, u)| {
                     u.priority == priority
-                        && self.is_healthy_at(
+                        && Self::is_healthy_at(
                             health_vec,
-                            *i,
                             &u.url,
                             cooldown,
                             config.half_open_max_requests,
@@ -216,6 +397,31 @@ impl LoadBalancer {
                 continue; // all upstreams at this priority are unhealthy, try next tier
             }
 
+            if config.strategy == LbStrategy::WeightedLeastSpend {
+                let spends: Vec<f64> = candidates
+                    .iter()
+                    .map(|(_, u)| u.credential_id.map_or(0.0, |c| self.credential_spend(c)))
+                    .collect();
+                let mean = spends.iter().sum::<f64>() / spends.len() as f64;
+                let scores: Vec<f64> = candidates
+                    .iter()
+                    .zip(&spends)
+                    .map(|((_, u), spend)| spend_score(u.weight, *spend, mean))
+                    .collect();
+                return pick_weighted(&scores, rand::random::<f64>()).map(|pos| candidates[pos].0);
+            }
+
+            if config.strategy == LbStrategy::WeightedLeastLatency {
+                let scores: Vec<f64> = candidates
+                    .iter()
+                    .map(|(i, u)| {
+                        let p50 = p50_ms.get(*i).copied().flatten().unwrap_or(0.0);
+                        latency_score(u.weight, p50)
+                    })
+                    .collect();
+                return pick_weighted(&scores, rand::random::<f64>()).map(|pos| candidates[pos].0);
+            }
+
             // Weighted round-robin among candidates
             let counter = self
                 .counters
@@ -242,14 +448,8 @@ impl LoadBalancer {
             return candidates.first().map(|(i, _)| *i);
         }
 
-        // All tiers exhausted — try recovery on highest priority
-        // Return the first upstream that has cooled down
-        for (i, upstream) in upstreams.iter().enumerate() {
-            if self.check_recovery(token_id, &upstream.url, cooldown) {
-                return Some(i);
-            }
-        }
-
+        // All tiers exhausted: every upstream is open or its probe slots are
+        // taken. The caller treats this as "no upstream available".
         None
     }
 
@@ -284,7 +484,7 @@ impl LoadBalancer {
                         let rate = failures as f64 / h.outcome_window.len() as f64;
                         if rate >= rate_threshold && h.is_healthy {
                             h.is_healthy = false;
-                            h.half_open_attempts = 0;
+                            self.history.record_transition(token_id, url, false);
                             tracing::warn!(
                                 token_id = token_id,
                                 url = url,
@@ -297,8 +497,10 @@ impl LoadBalancer {
                     }
                 } else if h.failure_count >= config.failure_threshold {
                     // Count-based tripping (original logic)
+                    if h.is_healthy {
+                        self.history.record_transition(token_id, url, false);
+                    }
                     h.is_healthy = false;
-                    h.half_open_attempts = 0;
                     tracing::warn!(
                         token_id = token_id,
                         url = url,
@@ -307,6 +509,12 @@ impl LoadBalancer {
                         "circuit breaker OPENED: upstream marked unhealthy"
                     );
                 }
+
+                // Opening the circuit, or a failed half-open probe, starts a
+                // fresh cooldown with a fresh set of probe slots.
+                if !h.is_healthy {
+                    h.reset_probes();
+                }
             }
         }
 
@@ -399,11 +607,12 @@ impl LoadBalancer {
                         url = url,
                         "circuit breaker CLOSED: upstream recovered"
                     );
+                    self.history.record_transition(token_id, url, true);
                 }
                 h.is_healthy = true;
                 h.failure_count = 0;
                 h.last_failure = None;
-                h.half_open_attempts = 0;
+                h.reset_probes();
                 h.outcome_window.clear();
             }
         }
@@ -446,57 +655,57 @@ impl LoadBalancer {
                     is_healthy: true,
                     failure_count: 0,
                     last_failure: None,
-                    half_open_attempts: 0,
+                    probes_in_flight: 0,
+                    probes_started: None,
                     outcome_window: std::collections::VecDeque::new(),
                 })
                 .collect()
         });
     }
 
-    /// Check if an upstream at a given index is considered healthy.
+    /// Check if an upstream is available for selection.
     /// `half_open_max` limits the number of probe requests allowed through
-    /// during the half-open recovery window.
+    /// during the half-open recovery window; once those slots are taken the
+    /// upstream is treated as still open until a probe reports back.
     fn is_healthy_at(
-        &self,
-        health_vec: Option<&Vec<UpstreamHealth>>,
-        idx: usize,
+        health_vec: &[UpstreamHealth],
         url: &str,
         cooldown_secs: u64,
         half_open_max: u32,
     ) -> bool {
-        if let Some(healths) = health_vec {
-            if let Some(h) = healths.iter().find(|h| h.url == url) {
-                if h.is_healthy {
-                    return true;
-                }
-                // Check if cooldown has passed (half-open state)
-                if let Some(last) = h.last_failure {
-                    if last.elapsed().as_secs() >= cooldown_secs {
-                        // B9-1 FIX: enforce half_open_max_requests limit
-                        if h.half_open_attempts < half_open_max {
-                            return true; // allow probe (half-open, under limit)
-                        }
-                        return false; // half-open limit reached
-                    }
-                }
-                return false;
+        let Some(h) = health_vec.iter().find(|h| h.url == url) else {
+            // No health data — assume healthy
+            return true;
+        };
+        if h.is_healthy {
+            return true;
+        }
+        // Check if cooldown has passed (half-open state)
+        match h.last_failure {
+            Some(last) if last.elapsed().as_secs() >= cooldown_secs => {
+                h.live_probes() < half_open_max
             }
+            _ => false,
         }
-        // No health data — assume healthy
-        let _ = idx;
-        true
     }
 
-    /// Check if an unhealthy upstream has cooled down enough for a recovery attempt.
-    fn check_recovery(&self, token_id: &str, url: &str, cooldown_secs: u64) -> bool {
-        if let Some(healths) = self.health.get(token_id) {
-            if let Some(h) = healths.iter().find(|h| h.url == url) {
-                if let Some(last) = h.last_failure {
-                    return last.elapsed().as_secs() >= cooldown_secs;
-                }
-            }
+    /// True when the token has health data and every tracked upstream's circuit
+    /// is open (failed and still inside the cooldown). Read-only: unlike
+    /// [`LoadBalancer::select`] it touches no counters or half-open state, so it
+    /// is safe as an early admission check.
+    pub fn all_circuits_open(&self, token_id: &str, config: &CircuitBreakerConfig) -> bool {
+        if !config.enabled {
+            return false;
         }
-        true
+        let Some(healths) = self.health.get(token_id) else {
+            return false;
+        };
+        !healths.is_empty()
+            && healths.iter().all(|h| {
+                !h.is_healthy
+                    && h.last_failure
+                        .is_some_and(|t| t.elapsed().as_secs() < config.recovery_cooldown_secs)
+            })
     }
 
     /// Get the circuit breaker state for a specific upstream.
@@ -521,18 +730,6 @@ impl LoadBalancer {
 
     // ── In-Flight Request Tracking (for LeastBusy routing) ───────
 
-    /// Increment the half-open attempt counter for an upstream.
-    /// Called by the handler when a request is routed to a half-open upstream.
-    pub fn increment_half_open(&self, token_id: &str, url: &str) {
-        if let Some(mut healths) = self.health.get_mut(token_id) {
-            if let Some(h) = healths.iter_mut().find(|h| h.url == url) {
-                if !h.is_healthy {
-                    h.half_open_attempts += 1;
-                }
-            }
-        }
-    }
-
     /// Increment the in-flight counter for an upstream URL.
     /// Call at the start of a proxy request.
     pub fn increment_in_flight(&self, url: &str) {
@@ -557,6 +754,68 @@ impl LoadBalancer {
         }
     }
 
+    // ── Credential Spend (for LeastSpend routing) ────────────────
+
+    /// Add a request's cost to its credential's recent spend.
+    pub fn record_credential_spend(&self, credential_id: Uuid, usd: f64) {
+        if usd <= 0.0 {
+            return;
+        }
+        let now = Instant::now();
+        self.credential_spend
+            .entry(credential_id)
+            .and_modify(|(spend, updated)| {
+                *spend = decay_spend(*spend, now.duration_since(*updated)) + usd;
+                *updated = now;
+            })
+            .or_insert((usd, now));
+    }
+
+    /// Recent, decayed USD spend of a credential (0 when untracked).
+    pub fn credential_spend(&self, credential_id: Uuid) -> f64 {
+        self.credential_spend
+            .get(&credential_id)
+            .map(|e| decay_spend(e.0, e.1.elapsed()))
+            .unwrap_or(0.0)
+    }
+
+    /// Why [`LbStrategy::WeightedLeastSpend`] picked `upstreams[idx]`, for the
+    /// `X-TrueFlow-Route-Reason` header: whether its credential's recent spend
+    /// is below the mean of its priority tier. The header goes to clients, so
+    /// the dollar amounts are only logged.
+    pub fn spend_route_reason(&self, upstreams: &[UpstreamTarget], idx: usize) -> String {
+        let chosen = &upstreams[idx];
+        let tier: Vec<f64> = upstreams
+            .iter()
+            .filter(|u| u.priority == chosen.priority)
+            .map(|u| u.credential_id.map_or(0.0, |c| self.credential_spend(c)))
+            .collect();
+        let mean = tier.iter().sum::<f64>() / tier.len() as f64;
+        let spend = chosen
+            .credential_id
+            .map_or(0.0, |c| self.credential_spend(c));
+        let credential = chosen
+            .credential_id
+            .map_or_else(|| "none".to_string(), |c| c.to_string());
+        tracing::debug!(
+            credential = %credential,
+            recent_spend_usd = spend,
+            tier_mean_usd = mean,
+            "least-spend route selected"
+        );
+        let relative = if spend < mean {
+            "below"
+        } else if spend > mean {
+            "above"
+        } else {
+            "at"
+        };
+        format!(
+            "least-spend: credential {} recent spend {} tier mean",
+            credential, relative
+        )
+    }
+
     /// Get the current in-flight count for an upstream URL.
     pub fn get_in_flight(&self, url: &str) -> u64 {
         self.in_flight
@@ -566,6 +825,45 @@ impl LoadBalancer {
     }
 }
 
+/// Selection score for [`LbStrategy::WeightedLeastLatency`].
+fn latency_score(weight: u32, p50_ms: f64) -> f64 {
+    weight as f64 / (p50_ms.max(0.0) + 1.0)
+}
+
+/// Selection score for [`LbStrategy::WeightedLeastSpend`]. Normalizing by the
+/// tier mean keeps the skew independent of absolute spend; with no spend at
+/// all every upstream scores its plain `weight`.
+fn spend_score(weight: u32, spend_usd: f64, mean_usd: f64) -> f64 {
+    let denom = spend_usd.max(0.0) + mean_usd.max(0.0);
+    if denom <= 0.0 {
+        return weight as f64;
+    }
+    weight as f64 * mean_usd / denom
+}
+
+/// `usd` after `elapsed` of exponential decay at [`CREDENTIAL_SPEND_HALF_LIFE`].
+fn decay_spend(usd: f64, elapsed: Duration) -> f64 {
+    usd * 0.5f64.powf(elapsed.as_secs_f64() / CREDENTIAL_SPEND_HALF_LIFE.as_secs_f64())
+}
+
+/// Position picked by a uniform `roll` in `[0, 1)` over `scores`. Falls back
+/// to the first entry when every score is zero.
+fn pick_weighted(scores: &[f64], roll: f64) -> Option<usize> {
+    let total: f64 = scores.iter().sum();
+    if scores.is_empty() || total <= 0.0 {
+        return (!scores.is_empty()).then_some(0);
+    }
+    let target = roll * total;
+    let mut cumulative = 0.0;
+    for (pos, score) in scores.iter().enumerate() {
+        cumulative += score;
+        if target < cumulative {
+            return Some(pos);
+        }
+    }
+    Some(scores.len() - 1)
+}
+
 /// Parse upstreams from token JSONB. Returns empty vec if null or invalid.
 pub fn parse_upstreams(upstreams_json: Option<&serde_json::Value>) -> Vec<UpstreamTarget> {
     match upstreams_json {
@@ -654,6 +952,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_least_latency_skews_toward_faster_upstream() {
+        let lb = LoadBalancer::new();
+        let upstreams = make_upstreams(3);
+        let config = CircuitBreakerConfig {
+            strategy: LbStrategy::WeightedLeastLatency,
+            ..Default::default()
+        };
+        let p50 = [Some(100.0), Some(1_000.0), Some(1_000.0)];
+
+        let mut counts = [0usize; 3];
+        for _ in 0..5_000 {
+            let idx = lb
+                .select_with_latency("tok-lat", &upstreams, &config, &p50)
+                .unwrap();
+            counts[idx] += 1;
+        }
+        // Expected share of the fast upstream is ~83%.
+        assert!(counts[0] > 3_700, "counts = {counts:?}");
+        assert!(counts[1] > 0 && counts[2] > 0, "counts = {counts:?}");
+
+        // An open circuit is still excluded, however fast it was.
+        for _ in 0..3 {
+            lb.mark_failed("tok-lat", &upstreams[0].url, &config);
+        }
+        for _ in 0..100 {
+            let idx = lb
+                .select_with_latency("tok-lat", &upstreams, &config, &p50)
+                .unwrap();
+            assert_ne!(idx, 0);
+        }
+    }
+
+    #[test]
+    fn test_least_latency_without_history_is_pure_weight() {
+        assert_eq!(latency_score(70, 0.0), 70.0);
+        assert_eq!(pick_weighted(&[70.0, 30.0], 0.69), Some(0));
+        assert_eq!(pick_weighted(&[70.0, 30.0], 0.71), Some(1));
+        assert_eq!(pick_weighted(&[0.0, 0.0], 0.5), Some(0));
+        assert_eq!(pick_weighted(&[], 0.5), None);
+
+        let config: CircuitBreakerConfig =
+            serde_json::from_value(serde_json::json!({"strategy": "weighted-least-latency"}))
+                .unwrap();
+        assert_eq!(config.strategy, LbStrategy::WeightedLeastLatency);
+        let legacy: CircuitBreakerConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(legacy.strategy, LbStrategy::WeightedRoundRobin);
+    }
+
+    #[test]
+    fn test_least_spend_skews_toward_cheaper_credential() {
+        let lb = LoadBalancer::new();
+        let mut upstreams = make_upstreams(2);
+        let (busy, idle) = (Uuid::new_v4(), Uuid::new_v4());
+        upstreams[0].credential_id = Some(busy);
+        upstreams[1].credential_id = Some(idle);
+        let config = CircuitBreakerConfig {
+            strategy: LbStrategy::WeightedLeastSpend,
+            ..Default::default()
+        };
+        lb.record_credential_spend(busy, 9.0);
+        lb.record_credential_spend(idle, 1.0);
+
+        let mut counts = [0usize; 2];
+        for _ in 0..5_000 {
+            counts[lb.select("tok-spend", &upstreams, &config).unwrap()] += 1;
+        }
+        // Scores 5/14 vs 5/6: expected share of the idle account is 70%.
+        assert!(counts[1] > 3_200, "counts = {counts:?}");
+        assert!(counts[0] > 0, "counts = {counts:?}");
+
+        let reason = lb.spend_route_reason(&upstreams, 1);
+        assert!(reason.contains(&idle.to_string()), "{reason}");
+        assert!(reason.ends_with("recent spend below tier mean"), "{reason}");
+        assert!(!reason.contains('$'), "{reason}");
+        let reason = lb.spend_route_reason(&upstreams, 0);
+        assert!(reason.ends_with("recent spend above tier mean"), "{reason}");
+    }
+
+    #[test]
+    fn test_least_spend_without_history_is_pure_weight() {
+        assert_eq!(spend_score(70, 0.0, 0.0), 70.0);
+        assert_eq!(spend_score(100, 0.0, 2.0), 100.0);
+        assert_eq!(spend_score(100, 2.0, 2.0), 50.0);
+
+        let lb = LoadBalancer::new();
+        let cred = Uuid::new_v4();
+        assert_eq!(lb.credential_spend(cred), 0.0);
+        lb.record_credential_spend(cred, 0.25);
+        lb.record_credential_spend(cred, 0.0);
+        assert!((lb.credential_spend(cred) - 0.25).abs() < 1e-6);
+        assert!((decay_spend(1.0, CREDENTIAL_SPEND_HALF_LIFE) - 0.5).abs() < 1e-9);
+
+        let config: CircuitBreakerConfig =
+            serde_json::from_value(serde_json::json!({"strategy": "weighted-least-spend"}))
+                .unwrap();
+        assert_eq!(config.strategy, LbStrategy::WeightedLeastSpend);
+    }
+
     #[test]
     fn test_round_robin_distributes() {
         let lb = LoadBalancer::new();
@@ -1012,6 +1409,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_all_circuits_open_requires_every_upstream_open() {
+        let lb = LoadBalancer::new();
+        let config = CircuitBreakerConfig::default();
+        let upstreams: Vec<UpstreamTarget> = ["https://a.com", "https://b.com"]
+            .iter()
+            .map(|url| UpstreamTarget {
+                url: url.to_string(),
+                credential_id: None,
+                weight: 100,
+                priority: 1,
+            })
+            .collect();
+
+        // No health data yet — admit.
+        assert!(!lb.all_circuits_open("tok1", &config));
+        lb.ensure_health("tok1", &upstreams);
+
+        for _ in 0..config.failure_threshold {
+            lb.mark_failed("tok1", "https://a.com", &config);
+        }
+        assert!(!lb.all_circuits_open("tok1", &config), "b is still closed");
+
+        for _ in 0..config.failure_threshold {
+            lb.mark_failed("tok1", "https://b.com", &config);
+        }
+        assert!(lb.all_circuits_open("tok1", &config));
+
+        // Cooldown elapsed (half-open) or breaker disabled — admit.
+        let cooled = CircuitBreakerConfig {
+            recovery_cooldown_secs: 0,
+            ..Default::default()
+        };
+        assert!(!lb.all_circuits_open("tok1", &cooled));
+        let disabled = CircuitBreakerConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(!lb.all_circuits_open("tok1", &disabled));
+    }
+
     #[test]
     fn test_mark_failed_noop_when_disabled() {
         let lb = LoadBalancer::new();
@@ -1253,6 +1691,146 @@ mod tests {
         );
     }
 
+    fn half_open_lb(half_open_max_requests: u32) -> (LoadBalancer, CircuitBreakerConfig) {
+        let lb = LoadBalancer::new();
+        let config = CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 1,
+            recovery_cooldown_secs: 0,
+            half_open_max_requests,
+            ..Default::default()
+        };
+        lb.ensure_health("tok1", &make_upstreams(2));
+        lb.mark_failed("tok1", "https://api0.example.com", &config);
+        (lb, config)
+    }
+
+    /// 50 simultaneous requests against a half-open upstream: only the
+    /// configured number of probes reach it, the rest route elsewhere.
+    #[test]
+    fn test_half_open_admits_only_configured_probes_under_concurrency() {
+        for max in [1u32, 3] {
+            let (lb, config) = half_open_lb(max);
+            let upstreams = make_upstreams(2);
+            let barrier = std::sync::Barrier::new(50);
+
+            let picks: Vec<Option<usize>> = std::thread::scope(|s| {
+                let handles: Vec<_> = (0..50)
+                    .map(|_| {
+                        s.spawn(|| {
+                            barrier.wait();
+                            lb.select("tok1", &upstreams, &config)
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+
+            let probes = picks.iter().filter(|p| **p == Some(0)).count();
+            assert_eq!(probes, max as usize, "half_open_max_requests = {max}");
+            assert!(
+                picks.iter().all(|p| p.is_some()),
+                "others route to the healthy upstream"
+            );
+            assert_eq!(
+                lb.get_circuit_state("tok1", &upstreams[0].url, 0),
+                "half_open"
+            );
+        }
+    }
+
+    #[test]
+    fn test_half_open_probe_outcome_releases_slot() {
+        let (lb, config) = half_open_lb(1);
+        let upstreams = make_upstreams(2);
+        let probe_url = &upstreams[0].url;
+
+        let picks: Vec<_> = (0..4)
+            .map(|_| lb.select("tok1", &upstreams, &config))
+            .collect();
+        assert_eq!(picks.iter().filter(|p| **p == Some(0)).count(), 1);
+
+        // Failed probe: circuit re-opens with fresh slots (cooldown 0 → half-open again)
+        lb.mark_failed("tok1", probe_url, &config);
+        let picks: Vec<_> = (0..4)
+            .map(|_| lb.select("tok1", &upstreams, &config))
+            .collect();
+        assert_eq!(picks.iter().filter(|p| **p == Some(0)).count(), 1);
+
+        // Successful probe closes the circuit: no more gating
+        lb.mark_healthy("tok1", probe_url);
+        assert_eq!(lb.get_circuit_state("tok1", probe_url, 0), "closed");
+        let picks: Vec<_> = (0..200)
+            .map(|_| lb.select("tok1", &upstreams, &config))
+            .collect();
+        assert_eq!(picks.iter().filter(|p| **p == Some(0)).count(), 100);
+    }
+
+    #[test]
+    fn test_half_open_unforwarded_probe_frees_slot() {
+        let (lb, config) = half_open_lb(1);
+        let upstreams = make_upstreams(2);
+        let probe_url = &upstreams[0].url;
+
+        // Selected, then denied before the upstream call: the slot comes back
+        let (idx, slot) = lb
+            .select_with_probe("tok1", &upstreams, &config, &[])
+            .unwrap();
+        assert_eq!(idx, 0);
+        assert_eq!(lb.select("tok1", &upstreams, &config), Some(1));
+        drop(slot);
+        let (idx, slot) = lb
+            .select_with_probe("tok1", &upstreams, &config, &[])
+            .unwrap();
+        assert_eq!(idx, 0);
+
+        // Forwarded: the slot stays taken until the probe reports back
+        slot.unwrap().forwarded();
+        let picks: Vec<_> = (0..4)
+            .map(|_| lb.select("tok1", &upstreams, &config))
+            .collect();
+        assert!(picks.iter().all(|p| *p == Some(1)));
+        assert_eq!(lb.get_circuit_state("tok1", probe_url, 0), "half_open");
+    }
+
+    #[test]
+    fn test_stale_probe_slot_does_not_free_newer_window() {
+        let (lb, config) = half_open_lb(1);
+        let upstreams = make_upstreams(2);
+        let probe_url = &upstreams[0].url;
+
+        let (_, stale) = lb
+            .select_with_probe("tok1", &upstreams, &config, &[])
+            .unwrap();
+        // Another probe fails in the meantime: fresh window, fresh slot taken
+        lb.mark_failed("tok1", probe_url, &config);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(lb.select("tok1", &upstreams, &config), Some(0));
+
+        drop(stale);
+        let picks: Vec<_> = (0..4)
+            .map(|_| lb.select("tok1", &upstreams, &config))
+            .collect();
+        assert!(picks.iter().all(|p| *p == Some(1)));
+    }
+
+    #[test]
+    fn test_half_open_single_upstream_exhausted_returns_none() {
+        let lb = LoadBalancer::new();
+        let config = CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 1,
+            recovery_cooldown_secs: 0,
+            ..Default::default()
+        };
+        let upstreams = make_upstreams(1);
+        lb.select("tok1", &upstreams, &config);
+        lb.mark_failed("tok1", &upstreams[0].url, &config);
+
+        assert_eq!(lb.select("tok1", &upstreams, &config), Some(0));
+        assert_eq!(lb.select("tok1", &upstreams, &config), None);
+    }
+
     // ── In-Flight Tracking (Least Busy) ────────────────────────
 
     #[test]
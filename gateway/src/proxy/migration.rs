@@ -0,0 +1,150 @@
+//! Gradual traffic migration between credentials/upstreams.
+//!
+//! A token with a `migration` config sends `percentage` of its requests to
+//! the target credential and/or upstream and the rest to its current ones, so
+//! a move between provider accounts can be ramped (1% → 10% → 100%) and
+//! watched in the audit log (`migration_path`) before the token is switched
+//! over for good.
+//!
+//! Assignment is a stable hash of the request's session (`X-Session-Id`) or
+//! user (`X-User-Id`), salted with the token id, so a conversation stays on
+//! one path while the percentage is unchanged; raising it only moves more
+//! buckets over. Requests with neither header are assigned individually.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Hash buckets; `percentage` is applied with two-decimal precision.
+const BUCKETS: u64 = 10_000;
+
+/// Per-token migration settings, stored in `tokens.migration`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationConfig {
+    /// Credential the migrated share uses. Unset keeps the current credential.
+    #[serde(default)]
+    pub target_credential_id: Option<Uuid>,
+    /// Upstream the migrated share is sent to. Unset keeps the current upstream.
+    #[serde(default)]
+    pub target_upstream_url: Option<String>,
+    /// Share of requests, 0–100, sent to the target.
+    pub percentage: f64,
+}
+
+/// Which side of a migration a request took, as recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationPath {
+    Current,
+    Target,
+}
+
+impl MigrationPath {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MigrationPath::Current => "current",
+            MigrationPath::Target => "target",
+        }
+    }
+}
+
+impl MigrationConfig {
+    /// Parse and validate a stored or submitted config.
+    pub fn from_value(value: &serde_json::Value) -> Result<Self, String> {
+        let cfg: Self = serde_json::from_value(value.clone()).map_err(|e| e.to_string())?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.target_credential_id.is_none() && self.target_upstream_url.is_none() {
+            return Err("set target_credential_id, target_upstream_url or both".into());
+        }
+        if let Some(ref url) = self.target_upstream_url {
+            let parsed = reqwest::Url::parse(url)
+                .map_err(|_| format!("target_upstream_url is not a valid URL: {}", url))?;
+            if parsed.scheme() != "http" && parsed.scheme() != "https" {
+                return Err("target_upstream_url must be http or https".into());
+            }
+        }
+        if !(0.0..=100.0).contains(&self.percentage) {
+            return Err("percentage must be between 0 and 100".into());
+        }
+        Ok(())
+    }
+
+    /// Pick the path for a request. `sticky_key` is the session or user id
+    /// when the client sent one, otherwise the request id.
+    pub fn route(&self, token_id: &str, sticky_key: &str) -> MigrationPath {
+        let threshold = (self.percentage * (BUCKETS as f64 / 100.0)).round() as u64;
+        if bucket(token_id, sticky_key) < threshold {
+            MigrationPath::Target
+        } else {
+            MigrationPath::Current
+        }
+    }
+}
+
+/// Stable bucket in `[0, BUCKETS)`: identical on every replica and release.
+fn bucket(token_id: &str, sticky_key: &str) -> u64 {
+    let digest = Sha256::new()
+        .chain_update(token_id.as_bytes())
+        .chain_update([0u8])
+        .chain_update(sticky_key.as_bytes())
+        .finalize();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(head) % BUCKETS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(percentage: f64) -> MigrationConfig {
+        MigrationConfig {
+            target_credential_id: Some(Uuid::nil()),
+            target_upstream_url: None,
+            percentage,
+        }
+    }
+
+    #[test]
+    fn test_migration_route_is_sticky_and_proportional() {
+        let keys: Vec<String> = (0..10_000).map(|i| format!("session-{}", i)).collect();
+        let share = |pct: f64| {
+            keys.iter()
+                .filter(|k| cfg(pct).route("tok", k) == MigrationPath::Target)
+                .count()
+        };
+        assert_eq!(share(0.0), 0);
+        assert_eq!(share(100.0), keys.len());
+        let quarter = share(25.0);
+        assert!((2_300..=2_700).contains(&quarter), "{quarter}");
+
+        // Same key, same path; ramping up never moves a key back.
+        for k in &keys {
+            let at_10 = cfg(10.0).route("tok", k);
+            assert_eq!(at_10, cfg(10.0).route("tok", k));
+            if at_10 == MigrationPath::Target {
+                assert_eq!(cfg(50.0).route("tok", k), MigrationPath::Target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_migration_config_validation() {
+        assert!(MigrationConfig::from_value(&serde_json::json!({
+            "target_upstream_url": "https://api.openai.com",
+            "percentage": 5
+        }))
+        .is_ok());
+        for bad in [
+            serde_json::json!({"percentage": 10}),
+            serde_json::json!({"target_upstream_url": "ftp://x", "percentage": 10}),
+            serde_json::json!({"target_upstream_url": "https://x.com", "percentage": 101}),
+            serde_json::json!({"target_credential_id": "not-a-uuid", "percentage": 10}),
+        ] {
+            assert!(MigrationConfig::from_value(&bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -1,13 +1,22 @@
+pub mod adaptive_limit;
+pub mod cache_warm;
+pub mod content_encoding;
 pub mod handler;
+pub mod health_history;
+pub mod json_mode;
 pub mod loadbalancer;
+pub mod migration;
 pub mod model_router;
 pub mod post_flight;
 pub mod realtime;
+pub mod replay;
 pub mod response_cache;
 pub mod retry;
 pub mod sigv4;
 pub mod smart_router;
+pub mod split;
 pub mod stream;
 pub mod stream_bridge;
+pub mod stream_limit;
 pub mod transform;
 pub mod upstream;
@@ -159,16 +159,101 @@ pub(crate) fn decode_bedrock_event_stream(data: &[u8]) -> Vec<(String, Value)> {
     events
 }
 
-/// Translate decoded Bedrock event stream events into OpenAI SSE format.
-/// This works on the raw binary bytes of the event stream.
-#[allow(dead_code)]
-pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str) -> Vec<u8> {
-    let events = decode_bedrock_event_stream(body);
-    let chunk_id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
-    let mut output = String::new();
+/// Upper bound on a single event stream frame. AWS caps messages at 16 MiB;
+/// anything larger means the length prefix is garbage and we'd buffer forever.
+const MAX_EVENT_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// Incremental Bedrock event stream → OpenAI SSE translator.
+///
+/// Network reads don't line up with frame boundaries, so incoming bytes are
+/// buffered and only complete frames are decoded. The `[DONE]` marker is held
+/// back after `messageStop` until the trailing `metadata` event (which carries
+/// usage) has been emitted, so downstream accumulators see usage before the
+/// stream is considered finished.
+pub(crate) struct BedrockStreamTranslator {
+    model: String,
+    chunk_id: String,
+    buffer: Vec<u8>,
+    stopped: bool,
+    done: bool,
+}
+
+impl BedrockStreamTranslator {
+    pub(crate) fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            chunk_id: format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+            buffer: Vec::new(),
+            stopped: false,
+            done: false,
+        }
+    }
+
+    /// Feed raw bytes from the upstream; returns any SSE produced by the
+    /// frames that became complete.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> String {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut output = String::new();
+        let mut consumed = 0;
+        while self.buffer.len() - consumed >= 12 {
+            let remaining = &self.buffer[consumed..];
+            let total_length =
+                u32::from_be_bytes([remaining[0], remaining[1], remaining[2], remaining[3]])
+                    as usize;
+
+            if !(16..=MAX_EVENT_FRAME_BYTES).contains(&total_length) {
+                tracing::warn!(
+                    total_length,
+                    "Bedrock event stream: invalid frame length, dropping buffered data"
+                );
+                output.push_str(&stream_error_sse(
+                    "invalid event stream frame",
+                    "invalidFrameException",
+                ));
+                consumed = self.buffer.len();
+                break;
+            }
+            if remaining.len() < total_length {
+                break; // Incomplete frame, wait for more data
+            }
+
+            for (event_type, payload) in decode_bedrock_event_stream(&remaining[..total_length]) {
+                self.translate_event(&event_type, &payload, &mut output);
+            }
+            consumed += total_length;
+        }
+
+        if consumed > 0 {
+            self.buffer.drain(..consumed);
+        }
+        output
+    }
+
+    /// Flush at end of stream: emits `[DONE]` if it hasn't been sent yet.
+    pub(crate) fn finish(&mut self) -> String {
+        if !self.buffer.is_empty() {
+            tracing::warn!(
+                bytes = self.buffer.len(),
+                "Bedrock event stream ended with a partial frame"
+            );
+            self.buffer.clear();
+        }
+        self.done_marker()
+    }
 
-    for (event_type, payload) in &events {
-        match event_type.as_str() {
+    fn done_marker(&mut self) -> String {
+        if self.done {
+            return String::new();
+        }
+        self.done = true;
+        "data: [DONE]\n\n".to_string()
+    }
+
+    fn translate_event(&mut self, event_type: &str, payload: &Value, output: &mut String) {
+        let chunk_id = self.chunk_id.as_str();
+        let model = self.model.as_str();
+        match event_type {
             "messageStart" => {
                 // Emit role chunk
                 let role = payload
@@ -176,7 +261,7 @@ pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str)
                     .and_then(|r| r.as_str())
                     .unwrap_or("assistant");
                 output.push_str(&openai_sse_chunk(
-                    &chunk_id,
+                    chunk_id,
                     model,
                     json!({"role": role, "content": ""}),
                     None,
@@ -184,29 +269,27 @@ pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str)
             }
             "contentBlockStart" => {
                 // Tool use start
-                if let Some(start) = payload.get("start") {
-                    if let Some(tool_use) = start.get("toolUse") {
-                        let index = payload
-                            .get("contentBlockIndex")
-                            .and_then(|i| i.as_u64())
-                            .unwrap_or(0);
-                        let name = tool_use.get("name").and_then(|n| n.as_str()).unwrap_or("");
-                        let tool_id = tool_use
-                            .get("toolUseId")
-                            .and_then(|id| id.as_str())
-                            .unwrap_or("");
-                        output.push_str(&openai_sse_chunk(
-                            &chunk_id,
-                            model,
-                            json!({"tool_calls": [{
-                                "index": index,
-                                "id": tool_id,
-                                "type": "function",
-                                "function": {"name": name, "arguments": ""}
-                            }]}),
-                            None,
-                        ));
-                    }
+                if let Some(tool_use) = payload.get("start").and_then(|s| s.get("toolUse")) {
+                    let index = payload
+                        .get("contentBlockIndex")
+                        .and_then(|i| i.as_u64())
+                        .unwrap_or(0);
+                    let name = tool_use.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                    let tool_id = tool_use
+                        .get("toolUseId")
+                        .and_then(|id| id.as_str())
+                        .unwrap_or("");
+                    output.push_str(&openai_sse_chunk(
+                        chunk_id,
+                        model,
+                        json!({"tool_calls": [{
+                            "index": index,
+                            "id": tool_id,
+                            "type": "function",
+                            "function": {"name": name, "arguments": ""}
+                        }]}),
+                        None,
+                    ));
                 }
             }
             "contentBlockDelta" => {
@@ -214,7 +297,7 @@ pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str)
                     // Text delta
                     if let Some(text) = delta.get("text").and_then(|t| t.as_str()) {
                         output.push_str(&openai_sse_chunk(
-                            &chunk_id,
+                            chunk_id,
                             model,
                             json!({"content": text}),
                             None,
@@ -232,7 +315,7 @@ pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str)
                             .and_then(|i| i.as_u64())
                             .unwrap_or(0);
                         output.push_str(&openai_sse_chunk(
-                            &chunk_id,
+                            chunk_id,
                             model,
                             json!({"tool_calls": [{
                                 "index": index,
@@ -252,12 +335,46 @@ pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str)
                     Some("content_filtered") => "content_filter",
                     _ => "stop",
                 };
-                output.push_str(&openai_sse_chunk(&chunk_id, model, json!({}), Some(finish)));
+                output.push_str(&openai_sse_chunk(chunk_id, model, json!({}), Some(finish)));
+                // [DONE] waits for the metadata event (usage) — see finish().
+                self.stopped = true;
             }
             "metadata" => {
-                // Usage info — emit as a usage chunk (OpenAI style)
-                // We don't emit this as a separate SSE event since usage
-                // is typically included in the final chunk
+                // Usage arrives after messageStop:
+                // {"usage": {"inputTokens": N, "outputTokens": M}}
+                // Emit an OpenAI-style usage chunk (choices: []), as with
+                // stream_options.include_usage.
+                if let Some(usage) = payload.get("usage") {
+                    let input = usage
+                        .get("inputTokens")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0);
+                    let output_t = usage
+                        .get("outputTokens")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0);
+                    if input > 0 || output_t > 0 {
+                        let usage_chunk = json!({
+                            "id": chunk_id,
+                            "object": "chat.completion.chunk",
+                            "created": chrono::Utc::now().timestamp(),
+                            "model": model,
+                            "choices": [],
+                            "usage": {
+                                "prompt_tokens": input,
+                                "completion_tokens": output_t,
+                                "total_tokens": input + output_t,
+                            }
+                        });
+                        output.push_str(&format!(
+                            "data: {}\n\n",
+                            serde_json::to_string(&usage_chunk).unwrap_or_default()
+                        ));
+                    }
+                }
+                if self.stopped {
+                    output.push_str(&self.done_marker());
+                }
             }
             // FIX: Surface Bedrock stream exceptions as SSE error events.
             // These arrive when the provider encounters errors mid-stream
@@ -272,23 +389,27 @@ pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str)
                     .get("message")
                     .and_then(|m| m.as_str())
                     .unwrap_or("unknown stream error");
-                let error_event = format!(
-                    "data: {{\"error\":{{\"message\":\"{}\",\"type\":\"{}\",\"code\":\"{}\"}}}}\n\n",
-                    message.replace('"', "'"),
-                    "stream_error",
-                    event_type,
-                );
-                output.push_str(&error_event);
+                output.push_str(&stream_error_sse(message, event_type));
             }
             _ => {}
         }
     }
+}
 
-    // Ensure [DONE] marker
-    if !output.ends_with("data: [DONE]\n\n") {
-        output.push_str("data: [DONE]\n\n");
-    }
+fn stream_error_sse(message: &str, code: &str) -> String {
+    format!(
+        "data: {{\"error\":{{\"message\":\"{}\",\"type\":\"{}\",\"code\":\"{}\"}}}}\n\n",
+        message.replace('"', "'"),
+        "stream_error",
+        code,
+    )
+}
 
+/// Translate a complete Bedrock binary event stream body into OpenAI SSE format.
+pub(crate) fn translate_bedrock_event_stream_to_openai(body: &[u8], model: &str) -> Vec<u8> {
+    let mut translator = BedrockStreamTranslator::new(model);
+    let mut output = translator.push(body);
+    output.push_str(&translator.finish());
     output.into_bytes()
 }
 
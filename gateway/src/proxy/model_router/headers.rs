@@ -19,21 +19,17 @@ pub(crate) fn inject_provider_headers(
                     .or_insert(HeaderValue::from_static("text/event-stream"));
             }
         }
-        Provider::Gemini => {
-            // Gemini SSE streaming needs Accept: text/event-stream
-            if is_streaming {
-                headers
-                    .entry(reqwest::header::ACCEPT)
-                    .or_insert(HeaderValue::from_static("text/event-stream"));
-            }
-        }
-        // Groq, Mistral, Together, Cohere — Accept: text/event-stream for streaming
-        Provider::Groq | Provider::Mistral | Provider::TogetherAI | Provider::Cohere => {
-            if is_streaming {
-                headers
-                    .entry(reqwest::header::ACCEPT)
-                    .or_insert(HeaderValue::from_static("text/event-stream"));
-            }
+        // Gemini, Groq, Mistral, Together, Cohere — Accept: text/event-stream for streaming
+        Provider::Gemini
+        | Provider::Groq
+        | Provider::Mistral
+        | Provider::TogetherAI
+        | Provider::Cohere
+            if is_streaming =>
+        {
+            headers
+                .entry(reqwest::header::ACCEPT)
+                .or_insert(HeaderValue::from_static("text/event-stream"));
         }
         Provider::Bedrock => {
             // Bedrock requires Accept for streaming: application/vnd.amazon.eventstream
@@ -10,13 +10,14 @@ mod url_rewrite;
 mod tests;
 
 // ── Public API re-exports ──────────────────────────────────────────────
-pub(crate) use self::bedrock::decode_bedrock_event_stream;
+pub(crate) use self::bedrock::BedrockStreamTranslator;
 pub(crate) use self::error::normalize_error_response;
 pub(crate) use self::headers::inject_provider_headers;
-pub(crate) use self::request::translate_request;
-pub(crate) use self::response::translate_response;
+pub(crate) use self::request::{dropped_fields, translate_request};
+pub(crate) use self::response::{translate_response_checked, ResponseTranslation};
 pub(crate) use self::streaming::{
-    openai_sse_chunk, translate_anthropic_sse_to_openai, translate_gemini_sse_to_openai,
+    translate_anthropic_sse_chunk, translate_cohere_sse_chunk, translate_gemini_sse_to_openai,
+    AnthropicStreamState, CohereStreamState,
 };
 pub(crate) use self::url_rewrite::rewrite_upstream_url;
 
@@ -43,6 +44,58 @@ pub enum Provider {
     Unknown,
 }
 
+impl Provider {
+    /// Parse a provider name as accepted by a token's `provider_hint`
+    /// (case-insensitive; a few common aliases are accepted).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "openai" => Some(Provider::OpenAI),
+            "azure" | "azure_openai" | "azureopenai" => Some(Provider::AzureOpenAI),
+            "anthropic" => Some(Provider::Anthropic),
+            "gemini" | "google" => Some(Provider::Gemini),
+            "groq" => Some(Provider::Groq),
+            "mistral" => Some(Provider::Mistral),
+            "together" | "togetherai" => Some(Provider::TogetherAI),
+            "cohere" => Some(Provider::Cohere),
+            "ollama" => Some(Provider::Ollama),
+            "bedrock" => Some(Provider::Bedrock),
+            _ => None,
+        }
+    }
+
+    /// Canonical lowercase name, as recorded in audit logs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::OpenAI => "openai",
+            Provider::AzureOpenAI => "azure_openai",
+            Provider::Anthropic => "anthropic",
+            Provider::Gemini => "gemini",
+            Provider::Groq => "groq",
+            Provider::Mistral => "mistral",
+            Provider::TogetherAI => "together",
+            Provider::Cohere => "cohere",
+            Provider::Ollama => "ollama",
+            Provider::Bedrock => "bedrock",
+            Provider::Unknown => "unknown",
+        }
+    }
+
+    /// Provider key used for pricing lookups (matches `model_pricing.provider`).
+    pub fn pricing_name(self) -> &'static str {
+        match self {
+            Provider::Anthropic => "anthropic",
+            Provider::Gemini => "google",
+            Provider::Mistral => "mistral",
+            Provider::Bedrock => "bedrock",
+            Provider::Groq => "groq",
+            Provider::Cohere => "cohere",
+            Provider::TogetherAI => "together",
+            Provider::Ollama => "ollama",
+            Provider::AzureOpenAI | Provider::OpenAI | Provider::Unknown => "openai",
+        }
+    }
+}
+
 /// Detect the provider from the model name or upstream URL.
 ///
 /// Fast path: dispatch on the first ASCII byte of the model name (zero
@@ -19,6 +19,52 @@ pub(crate) fn translate_request(provider: Provider, body: &Value) -> Option<Valu
     }
 }
 
+/// OpenAI sampling penalties. OpenAI-compatible providers (including
+/// Cohere's compatibility API) accept them as-is; translators map or drop them.
+const PENALTY_FIELDS: [&str; 2] = ["frequency_penalty", "presence_penalty"];
+
+/// Bedrock model families whose native request accepts OpenAI-style
+/// penalties through `additionalModelRequestFields`.
+fn bedrock_supports_penalties(model: &str) -> bool {
+    model.contains("cohere.command-r") || model.contains("ai21.jamba")
+}
+
+/// A penalty the client actually set (non-zero; 0 is the provider default).
+fn penalty_value(body: &Value, field: &str) -> Option<Value> {
+    body.get(field)
+        .filter(|v| v.as_f64().is_some_and(|p| p != 0.0))
+        .cloned()
+}
+
+/// Fields the client set that `provider`'s request translator cannot
+/// express and drops. Surfaced as `X-TrueFlow-Dropped-Fields` so a provider
+/// switch doesn't silently change sampling behaviour.
+pub(crate) fn dropped_fields(provider: Provider, body: &Value) -> Vec<&'static str> {
+    let supported = match provider {
+        Provider::Anthropic => false,
+        Provider::Bedrock => {
+            bedrock_supports_penalties(body.get("model").and_then(|m| m.as_str()).unwrap_or(""))
+        }
+        // Gemini maps them to generationConfig; the rest pass through untranslated.
+        _ => true,
+    };
+    if supported {
+        return Vec::new();
+    }
+    PENALTY_FIELDS
+        .into_iter()
+        .filter(|f| penalty_value(body, f).is_some())
+        .collect()
+}
+
+/// The client's output-token limit: `max_tokens`, or the newer OpenAI
+/// `max_completion_tokens` when only that is sent.
+fn output_limit(body: &Value) -> Option<&Value> {
+    body.get("max_tokens")
+        .filter(|v| !v.is_null())
+        .or_else(|| body.get("max_completion_tokens").filter(|v| !v.is_null()))
+}
+
 /// Translate a provider's native response body back to OpenAI format.
 // ═══════════════════════════════════════════════════════════════
 // OpenAI → Anthropic (Messages API)
@@ -32,10 +78,7 @@ pub(crate) fn openai_to_anthropic_request(body: &Value) -> Value {
     }
 
     // Max tokens (required by Anthropic, default 4096)
-    let max_tokens = body
-        .get("max_tokens")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(4096);
+    let max_tokens = output_limit(body).and_then(|v| v.as_u64()).unwrap_or(4096);
     result.insert("max_tokens".into(), json!(max_tokens));
 
     // Messages: extract system message as top-level param
@@ -198,6 +241,18 @@ pub(crate) fn openai_to_anthropic_request(body: &Value) -> Value {
         }
     }
 
+    // parallel_tool_calls: false → Anthropic tool_choice.disable_parallel_tool_use
+    if body.get("parallel_tool_calls").and_then(|v| v.as_bool()) == Some(false)
+        && result.contains_key("tools")
+    {
+        let choice = result
+            .entry("tool_choice")
+            .or_insert_with(|| json!({"type": "auto"}));
+        if let Some(obj) = choice.as_object_mut() {
+            obj.insert("disable_parallel_tool_use".into(), json!(true));
+        }
+    }
+
     // Stream
     if let Some(stream) = body.get("stream") {
         result.insert("stream".into(), stream.clone());
@@ -352,7 +407,7 @@ pub(crate) fn openai_to_gemini_request(body: &Value) -> Value {
     if let Some(temp) = body.get("temperature") {
         gen_config.insert("temperature".into(), temp.clone());
     }
-    if let Some(max_tokens) = body.get("max_tokens") {
+    if let Some(max_tokens) = output_limit(body) {
         gen_config.insert("maxOutputTokens".into(), max_tokens.clone());
     }
     if let Some(top_p) = body.get("top_p") {
@@ -365,6 +420,12 @@ pub(crate) fn openai_to_gemini_request(body: &Value) -> Value {
             gen_config.insert("stopSequences".into(), json!([s]));
         }
     }
+    if let Some(p) = penalty_value(body, "frequency_penalty") {
+        gen_config.insert("frequencyPenalty".into(), p);
+    }
+    if let Some(p) = penalty_value(body, "presence_penalty") {
+        gen_config.insert("presencePenalty".into(), p);
+    }
 
     // response_format → Gemini responseMimeType + responseSchema
     // OpenAI: {"type":"json_object"} | {"type":"json_schema","json_schema":{"schema":{...}}}
@@ -535,7 +596,7 @@ pub(crate) fn openai_to_bedrock_request(body: &Value) -> Value {
     if let Some(temp) = body.get("temperature") {
         inference_config.insert("temperature".into(), temp.clone());
     }
-    if let Some(max_tokens) = body.get("max_tokens") {
+    if let Some(max_tokens) = output_limit(body) {
         inference_config.insert("maxTokens".into(), max_tokens.clone());
     }
     if let Some(top_p) = body.get("top_p") {
@@ -552,6 +613,19 @@ pub(crate) fn openai_to_bedrock_request(body: &Value) -> Value {
         result.insert("inferenceConfig".into(), Value::Object(inference_config));
     }
 
+    // Penalties aren't part of the Converse inferenceConfig; models that
+    // support them take them as native fields.
+    let model = body.get("model").and_then(|m| m.as_str()).unwrap_or("");
+    if bedrock_supports_penalties(model) {
+        let extra: serde_json::Map<String, Value> = PENALTY_FIELDS
+            .iter()
+            .filter_map(|f| penalty_value(body, f).map(|p| (f.to_string(), p)))
+            .collect();
+        if !extra.is_empty() {
+            result.insert("additionalModelRequestFields".into(), Value::Object(extra));
+        }
+    }
+
     // Tools → Bedrock toolConfig
     if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
         let bedrock_tools: Vec<Value> = tools.iter().filter_map(|tool| {
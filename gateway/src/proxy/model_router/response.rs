@@ -7,18 +7,101 @@ pub(crate) fn translate_response(provider: Provider, body: &Value, model: &str)
         Provider::Anthropic => Some(anthropic_to_openai_response(body, model)),
         Provider::Gemini => Some(gemini_to_openai_response(body, model)),
         Provider::Bedrock => Some(bedrock_to_openai_response(body, model)),
+        // Cohere's compatibility endpoint answers in OpenAI format; only the
+        // native v2 API (`message` instead of `choices`) needs translating.
+        Provider::Cohere => {
+            is_cohere_v2_response(body).then(|| cohere_to_openai_response(body, model))
+        }
         // OpenAI-compatible providers — no translation needed
         Provider::OpenAI
         | Provider::AzureOpenAI
         | Provider::Groq
         | Provider::Mistral
         | Provider::TogetherAI
-        | Provider::Cohere
         | Provider::Ollama
         | Provider::Unknown => None,
     }
 }
 
+/// Outcome of [`translate_response_checked`].
+#[derive(Debug)]
+pub(crate) enum ResponseTranslation {
+    /// OpenAI-compatible provider — forward the body untouched.
+    Passthrough,
+    /// Successfully translated to OpenAI format.
+    Translated(Value),
+    /// Translation dropped the provider's content (partial or unexpected body).
+    /// The caller should return the raw provider body instead of an empty completion.
+    Fallback,
+}
+
+/// Translate a provider response, detecting translations that silently lose content.
+///
+/// The per-provider translators assume a well-formed body and default every
+/// missing field, so an unexpected shape turns into a valid-looking but empty
+/// completion. When the translated message has neither content nor tool calls
+/// while the source body either lacks the provider's top-level content field
+/// or still carries generated text, we report [`ResponseTranslation::Fallback`].
+pub(crate) fn translate_response_checked(
+    provider: Provider,
+    body: &Value,
+    model: &str,
+) -> ResponseTranslation {
+    let Some(translated) = translate_response(provider, body, model) else {
+        return ResponseTranslation::Passthrough;
+    };
+    if !translated_message_is_empty(&translated) {
+        return ResponseTranslation::Translated(translated);
+    }
+
+    let content_key = match provider {
+        Provider::Anthropic => "content",
+        Provider::Gemini => "candidates",
+        Provider::Bedrock => "output",
+        Provider::Cohere => "message",
+        _ => return ResponseTranslation::Translated(translated),
+    };
+    let unexpected_shape = body.get(content_key).is_none();
+    if unexpected_shape || contains_text(body) {
+        tracing::warn!(
+            provider = ?provider,
+            model = %model,
+            unexpected_shape,
+            "response translation produced an empty completion from a non-empty body, returning raw provider body"
+        );
+        return ResponseTranslation::Fallback;
+    }
+    // Genuinely empty completion (e.g. `"content": []`) — translate as-is.
+    ResponseTranslation::Translated(translated)
+}
+
+/// True when `choices[0].message` has no text content and no tool calls.
+fn translated_message_is_empty(translated: &Value) -> bool {
+    let Some(message) = translated.pointer("/choices/0/message") else {
+        return true;
+    };
+    let has_content = message
+        .get("content")
+        .and_then(|c| c.as_str())
+        .is_some_and(|c| !c.is_empty());
+    let has_tool_calls = message
+        .get("tool_calls")
+        .and_then(|t| t.as_array())
+        .is_some_and(|t| !t.is_empty());
+    !has_content && !has_tool_calls
+}
+
+/// Recursively check for any non-empty `"text"` string in a provider body.
+fn contains_text(v: &Value) -> bool {
+    match v {
+        Value::Object(map) => map.iter().any(|(k, val)| {
+            (k == "text" && val.as_str().is_some_and(|s| !s.is_empty())) || contains_text(val)
+        }),
+        Value::Array(arr) => arr.iter().any(contains_text),
+        _ => false,
+    }
+}
+
 /// Rewrite the upstream URL for the given provider and model.
 ///
 /// For Azure OpenAI, the URL format is:
@@ -345,3 +428,115 @@ pub(crate) fn bedrock_to_openai_response(body: &Value, model: &str) -> Value {
         }
     })
 }
+
+// ═══════════════════════════════════════════════════════════════
+// Cohere v2 → OpenAI
+// ═══════════════════════════════════════════════════════════════
+
+/// A native Cohere v2 `/v2/chat` response, as opposed to the OpenAI-format
+/// body from Cohere's compatibility endpoint.
+fn is_cohere_v2_response(body: &Value) -> bool {
+    body.get("choices").is_none() && body.get("message").is_some_and(|m| m.is_object())
+}
+
+/// Map a Cohere v2 `finish_reason` to OpenAI's.
+pub(crate) fn cohere_finish_reason(reason: &str) -> &'static str {
+    match reason {
+        "MAX_TOKENS" => "length",
+        "TOOL_CALL" => "tool_calls",
+        "ERROR_TOXIC" => "content_filter",
+        _ => "stop",
+    }
+}
+
+/// Token counts from a Cohere v2 `usage` object. `tokens` is what the model
+/// actually processed; `billed_units` is the fallback.
+pub(crate) fn cohere_usage(usage: Option<&Value>) -> (u64, u64) {
+    let Some(usage) = usage else {
+        return (0, 0);
+    };
+    let counts = usage.get("tokens").or_else(|| usage.get("billed_units"));
+    let count = |field: &str| {
+        counts
+            .and_then(|c| c.get(field))
+            .and_then(|t| t.as_f64())
+            .map(|t| t as u64)
+            .unwrap_or(0)
+    };
+    (count("input_tokens"), count("output_tokens"))
+}
+
+pub(crate) fn cohere_to_openai_response(body: &Value, model: &str) -> Value {
+    let message = body.get("message");
+
+    let content_text: String = message
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array())
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default();
+
+    // Cohere v2 tool calls are already OpenAI-shaped
+    let tool_calls: Vec<Value> = message
+        .and_then(|m| m.get("tool_calls"))
+        .and_then(|t| t.as_array())
+        .map(|calls| {
+            calls
+                .iter()
+                .map(|call| {
+                    let function = call.get("function");
+                    json!({
+                        "id": call.get("id").cloned().unwrap_or(json!("")),
+                        "type": "function",
+                        "function": {
+                            "name": function.and_then(|f| f.get("name")).cloned().unwrap_or(json!("")),
+                            "arguments": function
+                                .and_then(|f| f.get("arguments"))
+                                .and_then(|a| a.as_str())
+                                .unwrap_or(""),
+                        }
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let finish_reason = body
+        .get("finish_reason")
+        .and_then(|f| f.as_str())
+        .map(cohere_finish_reason)
+        .unwrap_or("stop");
+
+    let mut openai_message = json!({
+        "role": "assistant",
+        "content": content_text,
+    });
+    if !tool_calls.is_empty() {
+        openai_message["tool_calls"] = json!(tool_calls);
+    }
+
+    let (input_tokens, output_tokens) = cohere_usage(body.get("usage"));
+
+    json!({
+        "id": body.get("id").cloned().unwrap_or(json!("cohere_unknown")),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": openai_message,
+            "finish_reason": finish_reason
+        }],
+        "usage": {
+            "prompt_tokens": input_tokens,
+            "completion_tokens": output_tokens,
+            "total_tokens": input_tokens + output_tokens
+        }
+    })
+}
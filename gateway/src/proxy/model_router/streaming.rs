@@ -1,6 +1,7 @@
 use serde_json::{json, Value};
 
 use super::bedrock::translate_bedrock_event_stream_to_openai;
+use super::response::{cohere_finish_reason, cohere_usage};
 use super::Provider;
 
 #[allow(dead_code)]
@@ -9,18 +10,20 @@ pub(crate) fn translate_sse_body(provider: Provider, body: &[u8], model: &str) -
         Provider::Anthropic => Some(translate_anthropic_sse_to_openai(body, model)),
         Provider::Gemini => Some(translate_gemini_sse_to_openai(body, model)),
         // Bedrock uses binary event stream (application/vnd.amazon.eventstream),
-        // NOT SSE. Its streaming is handled separately in the handler via
-        // decode_bedrock_event_stream() + translate_bedrock_stream_to_openai_sse().
-        // Returning None here means the SSE passthrough path is used,
-        // but for Bedrock the handler intercepts before reaching this.
+        // NOT SSE. Live streams go through stream_bridge::tee_bedrock_stream,
+        // which drives the same BedrockStreamTranslator incrementally.
         Provider::Bedrock => Some(translate_bedrock_event_stream_to_openai(body, model)),
+        // Cohere's compatibility endpoint streams OpenAI SSE; only native v2
+        // events (typed `content-delta`, `message-end`, ...) need translating.
+        Provider::Cohere => {
+            is_cohere_v2_sse(body).then(|| translate_cohere_sse_to_openai(body, model))
+        }
         // OpenAI-compatible providers — no SSE translation needed
         Provider::OpenAI
         | Provider::AzureOpenAI
         | Provider::Groq
         | Provider::Mistral
         | Provider::TogetherAI
-        | Provider::Cohere
         | Provider::Ollama
         | Provider::Unknown => None,
     }
@@ -52,7 +55,28 @@ pub(crate) fn openai_sse_chunk(
 
 // ── Anthropic SSE → OpenAI SSE ──────────────────────────────────
 
+/// Usage carried across chunks of one live Anthropic stream.
+///
+/// `message_start` (input_tokens) and the final `message_delta`
+/// (output_tokens) almost always arrive in different TCP chunks, so the
+/// per-chunk translator needs somewhere to keep the prompt count until the
+/// usage chunk is emitted.
+#[derive(Debug, Default)]
+pub(crate) struct AnthropicStreamState {
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+}
+
 pub(crate) fn translate_anthropic_sse_to_openai(body: &[u8], model: &str) -> Vec<u8> {
+    translate_anthropic_sse_chunk(body, model, &mut AnthropicStreamState::default())
+}
+
+/// Translate one chunk of a live Anthropic stream, keeping usage in `state`.
+pub(crate) fn translate_anthropic_sse_chunk(
+    body: &[u8],
+    model: &str,
+    state: &mut AnthropicStreamState,
+) -> Vec<u8> {
     let body_str = String::from_utf8_lossy(body);
     let chunk_id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
     let mut output = String::new();
@@ -60,8 +84,10 @@ pub(crate) fn translate_anthropic_sse_to_openai(body: &[u8], model: &str) -> Vec
 
     // FIX #3: Track usage tokens from Anthropic streaming events.
     // Anthropic sends input_tokens in message_start and output_tokens in message_delta.
-    let mut input_tokens: Option<u64> = None;
-    let mut output_tokens: Option<u64> = None;
+    let AnthropicStreamState {
+        input_tokens,
+        output_tokens,
+    } = state;
 
     // Anthropic SSE has two relevant line types:
     // `event: <type>` followed by `data: <json>`
@@ -122,7 +148,7 @@ pub(crate) fn translate_anthropic_sse_to_openai(body: &[u8], model: &str) -> Vec
                 // Anthropic format: {"type":"message_start","message":{"usage":{"input_tokens":N}}}
                 if let Some(usage) = json.get("message").and_then(|m| m.get("usage")) {
                     if let Some(inp) = usage.get("input_tokens").and_then(|t| t.as_u64()) {
-                        input_tokens = Some(inp);
+                        *input_tokens = Some(inp);
                     }
                 }
             }
@@ -174,7 +200,7 @@ pub(crate) fn translate_anthropic_sse_to_openai(body: &[u8], model: &str) -> Vec
                 // Anthropic format: {"type":"message_delta","usage":{"output_tokens":N}}
                 if let Some(usage) = json.get("usage") {
                     if let Some(out) = usage.get("output_tokens").and_then(|t| t.as_u64()) {
-                        output_tokens = Some(out);
+                        *output_tokens = Some(out);
                     }
                 }
 
@@ -371,3 +397,159 @@ pub(crate) fn translate_gemini_sse_to_openai(body: &[u8], model: &str) -> Vec<u8
 
     output.into_bytes()
 }
+
+// ── Cohere v2 SSE → OpenAI SSE ──────────────────────────────────
+
+/// The OpenAI chunk id carried across chunks of one live Cohere stream.
+#[derive(Debug, Default)]
+pub(crate) struct CohereStreamState {
+    chunk_id: Option<String>,
+}
+
+/// Payload of an SSE `data:` line, if `line` is one.
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim)
+}
+
+/// A v2 event is an object with a string `type`; OpenAI chunks have none.
+fn cohere_v2_event(data: &str) -> Option<Value> {
+    let json: Value = serde_json::from_str(data).ok()?;
+    json.get("type")?.as_str()?;
+    Some(json)
+}
+
+/// True when a body contains native Cohere v2 stream events.
+fn is_cohere_v2_sse(body: &[u8]) -> bool {
+    String::from_utf8_lossy(body)
+        .lines()
+        .filter_map(|line| sse_data(line.trim()))
+        .any(|data| cohere_v2_event(data).is_some())
+}
+
+pub(crate) fn translate_cohere_sse_to_openai(body: &[u8], model: &str) -> Vec<u8> {
+    translate_cohere_sse_chunk(body, model, &mut CohereStreamState::default())
+}
+
+/// Translate one chunk of a live Cohere stream.
+///
+/// Native v2 events become OpenAI chunks: `content-delta` text becomes a
+/// `content` delta, `tool-call-start` / `tool-call-delta` become `tool_calls`
+/// deltas, and `message-end` becomes the `finish_reason` chunk with usage
+/// followed by `[DONE]`. `data:` lines that aren't v2 events (the
+/// compatibility endpoint's OpenAI SSE) are passed through unchanged.
+pub(crate) fn translate_cohere_sse_chunk(
+    body: &[u8],
+    model: &str,
+    state: &mut CohereStreamState,
+) -> Vec<u8> {
+    let body_str = String::from_utf8_lossy(body);
+    let mut output = String::new();
+
+    for line in body_str.lines() {
+        let Some(data) = sse_data(line.trim()) else {
+            // `event:` lines repeat the v2 `type`; blank lines are re-added per event
+            continue;
+        };
+        let Some(json) = cohere_v2_event(data) else {
+            output.push_str(&format!("data: {}\n\n", data));
+            continue;
+        };
+        let chunk_id = state
+            .chunk_id
+            .get_or_insert_with(|| format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()))
+            .clone();
+        let index = json.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+        let message = json.pointer("/delta/message");
+
+        match json.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+            "message-start" => {
+                output.push_str(&openai_sse_chunk(
+                    &chunk_id,
+                    model,
+                    json!({"role": "assistant", "content": ""}),
+                    None,
+                ));
+            }
+            "content-delta" => {
+                if let Some(text) = message
+                    .and_then(|m| m.pointer("/content/text"))
+                    .and_then(|t| t.as_str())
+                {
+                    output.push_str(&openai_sse_chunk(
+                        &chunk_id,
+                        model,
+                        json!({"content": text}),
+                        None,
+                    ));
+                }
+            }
+            "tool-call-start" => {
+                if let Some(call) = message.and_then(|m| m.get("tool_calls")) {
+                    let function = call.get("function");
+                    output.push_str(&openai_sse_chunk(
+                        &chunk_id,
+                        model,
+                        json!({"tool_calls": [{
+                            "index": index,
+                            "id": call.get("id").and_then(|id| id.as_str()).unwrap_or(""),
+                            "type": "function",
+                            "function": {
+                                "name": function.and_then(|f| f.get("name")).and_then(|n| n.as_str()).unwrap_or(""),
+                                "arguments": function.and_then(|f| f.get("arguments")).and_then(|a| a.as_str()).unwrap_or(""),
+                            }
+                        }]}),
+                        None,
+                    ));
+                }
+            }
+            "tool-call-delta" => {
+                if let Some(args) = message
+                    .and_then(|m| m.pointer("/tool_calls/function/arguments"))
+                    .and_then(|a| a.as_str())
+                {
+                    output.push_str(&openai_sse_chunk(
+                        &chunk_id,
+                        model,
+                        json!({"tool_calls": [{"index": index, "function": {"arguments": args}}]}),
+                        None,
+                    ));
+                }
+            }
+            "message-end" => {
+                let delta = json.get("delta");
+                let finish = delta
+                    .and_then(|d| d.get("finish_reason"))
+                    .and_then(|f| f.as_str())
+                    .map(cohere_finish_reason)
+                    .unwrap_or("stop");
+                let (prompt, completion) = cohere_usage(delta.and_then(|d| d.get("usage")));
+                let chunk = json!({
+                    "id": chunk_id,
+                    "object": "chat.completion.chunk",
+                    "created": chrono::Utc::now().timestamp(),
+                    "model": model,
+                    "choices": [{
+                        "index": 0,
+                        "delta": {},
+                        "finish_reason": finish,
+                    }],
+                    "usage": {
+                        "prompt_tokens": prompt,
+                        "completion_tokens": completion,
+                        "total_tokens": prompt + completion,
+                    }
+                });
+                output.push_str(&format!(
+                    "data: {}\n\n",
+                    serde_json::to_string(&chunk).unwrap_or_default()
+                ));
+                // Cohere v2 has no `[DONE]` sentinel of its own
+                output.push_str("data: [DONE]\n\n");
+            }
+            // content-start/-end, tool-plan-delta, tool-call-end, citations
+            _ => {}
+        }
+    }
+
+    output.into_bytes()
+}
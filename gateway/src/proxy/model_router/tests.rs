@@ -6,7 +6,7 @@ use super::response::*;
 use super::streaming::*;
 use super::url_rewrite::*;
 use super::*;
-use serde_json::json;
+use serde_json::{json, Value};
 
 // ── Provider Detection ──────────────────────────────────────
 
@@ -37,6 +37,39 @@ fn test_detect_gemini_models() {
     assert_eq!(detect_provider("gemini-pro", ""), Provider::Gemini);
 }
 
+#[test]
+fn test_provider_hint_names_round_trip() {
+    for p in [
+        Provider::OpenAI,
+        Provider::AzureOpenAI,
+        Provider::Anthropic,
+        Provider::Gemini,
+        Provider::Groq,
+        Provider::Mistral,
+        Provider::TogetherAI,
+        Provider::Cohere,
+        Provider::Ollama,
+        Provider::Bedrock,
+    ] {
+        assert_eq!(Provider::from_name(p.as_str()), Some(p));
+    }
+    assert_eq!(Provider::from_name(" Google "), Some(Provider::Gemini));
+    assert_eq!(Provider::from_name("unknown"), None);
+    assert_eq!(Provider::from_name("acme-llm"), None);
+}
+
+#[test]
+fn test_fine_tuned_model_name_not_detected_by_prefix() {
+    // ft: prefixes and self-hosted aliases fall through to URL detection,
+    // which is why tokens can pin the provider with provider_hint.
+    assert_eq!(
+        detect_provider("ft:gpt-4o:acme::abc", "https://llm.internal.acme.com"),
+        Provider::Unknown
+    );
+    assert_eq!(Provider::Gemini.pricing_name(), "google");
+    assert_eq!(Provider::AzureOpenAI.pricing_name(), "openai");
+}
+
 #[test]
 fn test_detect_from_url_fallback() {
     assert_eq!(
@@ -410,6 +443,41 @@ fn test_anthropic_tool_choice_specific_function() {
     assert_eq!(translated["tool_choice"]["name"], "get_weather");
 }
 
+#[test]
+fn test_anthropic_parallel_tool_calls_disabled() {
+    let body = json!({
+        "model": "claude-3-opus",
+        "messages": [{"role": "user", "content": "hi"}],
+        "tools": [{"type": "function", "function": {"name": "foo", "description": "", "parameters": {}}}],
+        "tool_choice": "required",
+        "parallel_tool_calls": false
+    });
+    let translated = openai_to_anthropic_request(&body);
+    assert_eq!(translated["tool_choice"]["type"], "any");
+    assert_eq!(translated["tool_choice"]["disable_parallel_tool_use"], true);
+
+    // No explicit tool_choice — defaults to auto with parallel use disabled
+    let body = json!({
+        "model": "claude-3-opus",
+        "messages": [{"role": "user", "content": "hi"}],
+        "tools": [{"type": "function", "function": {"name": "foo", "description": "", "parameters": {}}}],
+        "parallel_tool_calls": false
+    });
+    let translated = openai_to_anthropic_request(&body);
+    assert_eq!(translated["tool_choice"]["type"], "auto");
+    assert_eq!(translated["tool_choice"]["disable_parallel_tool_use"], true);
+
+    // parallel_tool_calls: true is Anthropic's default — nothing to add
+    let body = json!({
+        "model": "claude-3-opus",
+        "messages": [{"role": "user", "content": "hi"}],
+        "tools": [{"type": "function", "function": {"name": "foo", "description": "", "parameters": {}}}],
+        "parallel_tool_calls": true
+    });
+    let translated = openai_to_anthropic_request(&body);
+    assert!(translated.get("tool_choice").is_none());
+}
+
 // ── tool_choice (Gemini) ────────────────────────────────────
 
 #[test]
@@ -521,6 +589,43 @@ fn test_translate_request_gemini() {
     assert!(translate_request(Provider::Gemini, &body).is_some());
 }
 
+#[test]
+fn test_translate_request_carries_clamped_output_limit() {
+    use crate::proxy::transform::{clamp_output_tokens, output_limit_field};
+    let chat = "/v1/chat/completions";
+
+    // Clamped client value reaches each provider's native field.
+    for (provider, model) in [
+        (Provider::Anthropic, "claude-3-5-sonnet"),
+        (Provider::Gemini, "gemini-2.0-flash"),
+        (Provider::Bedrock, "anthropic.claude-3-sonnet"),
+    ] {
+        let mut body = json!({"model": model, "messages": [{"role": "user", "content": "hi"}], "max_tokens": 8000});
+        clamp_output_tokens(&mut body, 1000, output_limit_field(provider, chat)).unwrap();
+        let translated = translate_request(provider, &body).unwrap();
+        let native = match provider {
+            Provider::Anthropic => &translated["max_tokens"],
+            Provider::Gemini => &translated["generationConfig"]["maxOutputTokens"],
+            _ => &translated["inferenceConfig"]["maxTokens"],
+        };
+        assert_eq!(native, 1000, "{:?}", provider);
+    }
+
+    // Injected ceiling, and an OpenAI-style max_completion_tokens, translate too.
+    let mut body =
+        json!({"model": "gemini-2.0-flash", "messages": [{"role": "user", "content": "hi"}]});
+    clamp_output_tokens(&mut body, 300, output_limit_field(Provider::Gemini, chat)).unwrap();
+    let translated = translate_request(Provider::Gemini, &body).unwrap();
+    assert_eq!(translated["generationConfig"]["maxOutputTokens"], 300);
+
+    let body = json!({"model": "claude-3-5-sonnet", "messages": [{"role": "user", "content": "hi"}], "max_completion_tokens": 700});
+    assert_eq!(openai_to_anthropic_request(&body)["max_tokens"], 700);
+    assert_eq!(
+        openai_to_bedrock_request(&body)["inferenceConfig"]["maxTokens"],
+        700
+    );
+}
+
 // ── SSE Translation Tests ───────────────────────────────────
 
 #[test]
@@ -600,6 +705,42 @@ data: {\"type\":\"message_stop\"}\n\
     assert!(output.contains("\"finish_reason\":\"tool_calls\""));
 }
 
+#[test]
+fn test_anthropic_sse_usage_carried_across_chunks() {
+    // message_start and the final message_delta arrive in separate reads of
+    // a live stream; the prompt count must survive into the usage chunk.
+    let first = b"\
+event: message_start\n\
+data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_03\",\"usage\":{\"input_tokens\":412,\"output_tokens\":1}}}\n\
+\n\
+event: content_block_start\n\
+data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_02\",\"name\":\"search_docs\"}}\n\
+\n";
+    let second = b"\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{}\"}}\n\
+\n\
+event: message_delta\n\
+data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":57}}\n\
+\n\
+event: message_stop\n\
+data: {\"type\":\"message_stop\"}\n\
+\n";
+
+    let mut state = AnthropicStreamState::default();
+    let _ = translate_anthropic_sse_chunk(first, "claude-3-5-sonnet", &mut state);
+    let output = String::from_utf8(translate_anthropic_sse_chunk(
+        second,
+        "claude-3-5-sonnet",
+        &mut state,
+    ))
+    .unwrap();
+
+    assert!(output.contains("\"finish_reason\":\"tool_calls\""));
+    assert!(output.contains("\"prompt_tokens\":412"));
+    assert!(output.contains("\"completion_tokens\":57"));
+}
+
 #[test]
 fn test_gemini_sse_text_streaming() {
     let body = b"\
@@ -1113,6 +1254,120 @@ fn test_bedrock_stream_tool_use() {
     assert!(output.contains("\"finish_reason\":\"tool_calls\""));
 }
 
+fn sample_bedrock_stream() -> Vec<u8> {
+    let mut stream = Vec::new();
+    stream.extend(build_test_bedrock_event(
+        "messageStart",
+        json!({"role": "assistant"}),
+    ));
+    stream.extend(build_test_bedrock_event(
+        "contentBlockDelta",
+        json!({"contentBlockIndex": 0, "delta": {"text": "Hello"}}),
+    ));
+    stream.extend(build_test_bedrock_event(
+        "contentBlockDelta",
+        json!({"contentBlockIndex": 0, "delta": {"text": " world"}}),
+    ));
+    stream.extend(build_test_bedrock_event(
+        "messageStop",
+        json!({"stopReason": "end_turn"}),
+    ));
+    stream.extend(build_test_bedrock_event(
+        "metadata",
+        json!({"usage": {"inputTokens": 10, "outputTokens": 3}}),
+    ));
+    stream
+}
+
+/// Strip the random chunk `id` and `created` timestamp so outputs can be compared.
+fn sse_data_lines(sse: &str) -> Vec<Value> {
+    sse.lines()
+        .filter_map(|l| l.strip_prefix("data: "))
+        .map(|d| {
+            let mut v: Value = serde_json::from_str(d).unwrap_or_else(|_| json!(d));
+            if let Some(obj) = v.as_object_mut() {
+                obj.remove("id");
+                obj.remove("created");
+            }
+            v
+        })
+        .collect()
+}
+
+#[test]
+fn test_bedrock_translator_usage_precedes_done() {
+    let mut t = BedrockStreamTranslator::new("anthropic.claude-v2");
+    let mut out = t.push(&sample_bedrock_stream());
+    out.push_str(&t.finish());
+
+    let lines = sse_data_lines(&out);
+    let done_idx = lines.iter().position(|v| v == "[DONE]").unwrap();
+    let usage_idx = lines.iter().position(|v| v.get("usage").is_some()).unwrap();
+    let stop_idx = lines
+        .iter()
+        .position(|v| v["choices"][0]["finish_reason"] == "stop")
+        .unwrap();
+    assert!(stop_idx < usage_idx && usage_idx < done_idx);
+    assert_eq!(done_idx, lines.len() - 1, "[DONE] must be last");
+    assert_eq!(lines.iter().filter(|v| *v == "[DONE]").count(), 1);
+    assert_eq!(lines[usage_idx]["usage"]["prompt_tokens"], 10);
+    assert_eq!(lines[usage_idx]["usage"]["completion_tokens"], 3);
+}
+
+#[test]
+fn test_bedrock_translator_handles_split_frames() {
+    let stream = sample_bedrock_stream();
+    let mut whole = BedrockStreamTranslator::new("m");
+    let expected = sse_data_lines(&(whole.push(&stream) + &whole.finish()));
+
+    // Split at every offset, and also feed one byte at a time.
+    for split in 1..stream.len() {
+        let mut t = BedrockStreamTranslator::new("m");
+        let mut out = t.push(&stream[..split]);
+        out.push_str(&t.push(&stream[split..]));
+        out.push_str(&t.finish());
+        assert_eq!(sse_data_lines(&out), expected, "split at {}", split);
+    }
+
+    let mut t = BedrockStreamTranslator::new("m");
+    let mut out = String::new();
+    for b in &stream {
+        out.push_str(&t.push(std::slice::from_ref(b)));
+    }
+    out.push_str(&t.finish());
+    assert_eq!(sse_data_lines(&out), expected);
+}
+
+#[test]
+fn test_bedrock_translator_done_without_metadata() {
+    let mut t = BedrockStreamTranslator::new("m");
+    let out = t.push(&build_test_bedrock_event(
+        "messageStop",
+        json!({"stopReason": "max_tokens"}),
+    ));
+    assert!(out.contains("\"finish_reason\":\"length\""));
+    assert!(!out.contains("[DONE]"));
+    assert_eq!(t.finish(), "data: [DONE]\n\n");
+    assert_eq!(t.finish(), "", "[DONE] is emitted once");
+}
+
+#[test]
+fn test_bedrock_translator_rejects_garbage_length() {
+    let mut t = BedrockStreamTranslator::new("m");
+    // A length prefix far beyond the frame cap must not buffer forever.
+    let mut garbage = vec![0xFF, 0xFF, 0xFF, 0xFF];
+    garbage.extend_from_slice(&[0u8; 12]);
+    let out = t.push(&garbage);
+    assert!(out.contains("stream_error"));
+
+    // The translator recovers for subsequent valid frames.
+    let out = t.push(&build_test_bedrock_event(
+        "contentBlockDelta",
+        json!({"contentBlockIndex": 0, "delta": {"text": "ok"}}),
+    ));
+    assert!(out.contains("\"content\":\"ok\""));
+}
+
 #[test]
 fn test_bedrock_empty_event_stream() {
     let result = translate_sse_body(Provider::Bedrock, b"", "anthropic.claude-v2");
@@ -1208,3 +1463,265 @@ fn test_bedrock_content_type_header() {
     inject_provider_headers(Provider::Bedrock, &mut headers, false);
     assert!(headers.contains_key(reqwest::header::CONTENT_TYPE));
 }
+
+// ── Translation Fallback ────────────────────────────────────
+
+#[test]
+fn test_translate_checked_well_formed_anthropic() {
+    let body = json!({
+        "id": "msg_01",
+        "content": [{"type": "text", "text": "Hi"}],
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 1, "output_tokens": 1}
+    });
+    match translate_response_checked(Provider::Anthropic, &body, "claude-3-haiku") {
+        ResponseTranslation::Translated(t) => {
+            assert_eq!(t["choices"][0]["message"]["content"], "Hi")
+        }
+        other => panic!("expected Translated, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_translate_checked_openai_passthrough() {
+    let body = json!({"choices": [{"message": {"content": "hi"}}]});
+    assert!(matches!(
+        translate_response_checked(Provider::OpenAI, &body, "gpt-4o"),
+        ResponseTranslation::Passthrough
+    ));
+}
+
+#[test]
+fn test_translate_checked_anthropic_block_missing_type_falls_back() {
+    // Text block without "type" — translator finds no text block.
+    let body = json!({
+        "id": "msg_01",
+        "content": [{"text": "Hello there"}],
+        "usage": {"input_tokens": 3, "output_tokens": 2}
+    });
+    assert!(matches!(
+        translate_response_checked(Provider::Anthropic, &body, "claude-3-haiku"),
+        ResponseTranslation::Fallback
+    ));
+}
+
+#[test]
+fn test_translate_checked_anthropic_unexpected_shape_falls_back() {
+    let body = json!({"type": "error", "error": {"type": "overloaded_error"}});
+    assert!(matches!(
+        translate_response_checked(Provider::Anthropic, &body, "claude-3-haiku"),
+        ResponseTranslation::Fallback
+    ));
+}
+
+#[test]
+fn test_translate_checked_anthropic_genuinely_empty_is_translated() {
+    let body = json!({
+        "id": "msg_01",
+        "content": [],
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 3, "output_tokens": 0}
+    });
+    assert!(matches!(
+        translate_response_checked(Provider::Anthropic, &body, "claude-3-haiku"),
+        ResponseTranslation::Translated(_)
+    ));
+}
+
+#[test]
+fn test_translate_checked_gemini_malformed_parts_falls_back() {
+    let body = json!({
+        "candidates": [{"output": {"text": "partial answer"}}]
+    });
+    assert!(matches!(
+        translate_response_checked(Provider::Gemini, &body, "gemini-2.0-flash"),
+        ResponseTranslation::Fallback
+    ));
+}
+
+#[test]
+fn test_translate_checked_bedrock_malformed_output_falls_back() {
+    let body = json!({"output": {"text": "unexpected"}, "stopReason": "end_turn"});
+    assert!(matches!(
+        translate_response_checked(Provider::Bedrock, &body, "anthropic.claude-3"),
+        ResponseTranslation::Fallback
+    ));
+    let body = json!({"message": "Internal server error"});
+    assert!(matches!(
+        translate_response_checked(Provider::Bedrock, &body, "anthropic.claude-3"),
+        ResponseTranslation::Fallback
+    ));
+}
+
+// ── Sampling penalties ────────────────────────────────────────
+
+fn penalty_body(model: &str) -> Value {
+    json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "Hi"}],
+        "frequency_penalty": 0.5,
+        "presence_penalty": -0.25,
+    })
+}
+
+#[test]
+fn test_penalties_dropped_and_reported_for_anthropic() {
+    let body = penalty_body("claude-3-5-sonnet-20241022");
+    let result = translate_request(Provider::Anthropic, &body).unwrap();
+    assert!(result.get("frequency_penalty").is_none());
+    assert!(result.get("presence_penalty").is_none());
+    assert_eq!(
+        dropped_fields(Provider::Anthropic, &body),
+        ["frequency_penalty", "presence_penalty"]
+    );
+
+    // Zero is the default everywhere — nothing to report.
+    let zero = json!({"model": "claude-3", "messages": [], "frequency_penalty": 0.0});
+    assert!(dropped_fields(Provider::Anthropic, &zero).is_empty());
+}
+
+#[test]
+fn test_penalties_mapped_for_gemini() {
+    let body = penalty_body("gemini-2.0-flash");
+    let result = translate_request(Provider::Gemini, &body).unwrap();
+    assert_eq!(result["generationConfig"]["frequencyPenalty"], 0.5);
+    assert_eq!(result["generationConfig"]["presencePenalty"], -0.25);
+    assert!(dropped_fields(Provider::Gemini, &body).is_empty());
+}
+
+#[test]
+fn test_penalties_mapped_for_bedrock_models_that_support_them() {
+    let body = penalty_body("cohere.command-r-plus-v1:0");
+    let result = translate_request(Provider::Bedrock, &body).unwrap();
+    assert_eq!(
+        result["additionalModelRequestFields"],
+        json!({"frequency_penalty": 0.5, "presence_penalty": -0.25})
+    );
+    assert!(dropped_fields(Provider::Bedrock, &body).is_empty());
+
+    let body = penalty_body("anthropic.claude-3-5-sonnet-20241022-v2:0");
+    let result = translate_request(Provider::Bedrock, &body).unwrap();
+    assert!(result.get("additionalModelRequestFields").is_none());
+    assert_eq!(
+        dropped_fields(Provider::Bedrock, &body),
+        ["frequency_penalty", "presence_penalty"]
+    );
+}
+
+#[test]
+fn test_penalties_pass_through_for_openai_compatible_providers() {
+    let body = penalty_body("command-r-plus");
+    assert!(translate_request(Provider::Cohere, &body).is_none());
+    assert!(dropped_fields(Provider::Cohere, &body).is_empty());
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Cohere v2 Translation Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[test]
+fn test_rewrite_cohere_v2_url_kept() {
+    let url = rewrite_upstream_url(
+        Provider::Cohere,
+        "https://api.cohere.com/v2/chat",
+        "command-r-plus",
+        true,
+    );
+    assert_eq!(url, "https://api.cohere.com/v2/chat");
+}
+
+#[test]
+fn test_cohere_v2_sse_text_streaming() {
+    let body = concat!(
+        "event: message-start\n",
+        "data: {\"id\":\"c1\",\"type\":\"message-start\",\"delta\":{\"message\":{\"role\":\"assistant\"}}}\n\n",
+        "event: content-start\n",
+        "data: {\"type\":\"content-start\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"type\":\"text\",\"text\":\"\"}}}}\n\n",
+        "event: content-delta\n",
+        "data: {\"type\":\"content-delta\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"text\":\"Hel\"}}}}\n\n",
+        "event: content-delta\n",
+        "data: {\"type\":\"content-delta\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"text\":\"lo\"}}}}\n\n",
+        "event: content-end\n",
+        "data: {\"type\":\"content-end\",\"index\":0}\n\n",
+        "event: message-end\n",
+        "data: {\"type\":\"message-end\",\"delta\":{\"finish_reason\":\"MAX_TOKENS\",\"usage\":{\"billed_units\":{\"input_tokens\":5,\"output_tokens\":2},\"tokens\":{\"input_tokens\":71,\"output_tokens\":2}}}}\n\n",
+    );
+    let out = translate_sse_body(Provider::Cohere, body.as_bytes(), "command-r-plus").unwrap();
+    let lines = sse_data_lines(&String::from_utf8(out).unwrap());
+
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0]["choices"][0]["delta"]["role"], "assistant");
+    assert_eq!(lines[1]["choices"][0]["delta"]["content"], "Hel");
+    assert_eq!(lines[2]["choices"][0]["delta"]["content"], "lo");
+    assert_eq!(lines[3]["choices"][0]["finish_reason"], "length");
+    assert_eq!(lines[3]["usage"]["prompt_tokens"], 71);
+    assert_eq!(lines[3]["usage"]["completion_tokens"], 2);
+    assert_eq!(lines[4], json!("[DONE]"));
+}
+
+#[test]
+fn test_cohere_v2_sse_tool_call_deltas() {
+    let body = concat!(
+        "data: {\"type\":\"tool-plan-delta\",\"delta\":{\"message\":{\"tool_plan\":\"Look it up\"}}}\n\n",
+        "data: {\"type\":\"tool-call-start\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"\"}}}}}\n\n",
+        "data: {\"type\":\"tool-call-delta\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"function\":{\"arguments\":\"{\\\"city\\\":\"}}}}}\n\n",
+        "data: {\"type\":\"tool-call-delta\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"function\":{\"arguments\":\"\\\"Paris\\\"}\"}}}}}\n\n",
+        "data: {\"type\":\"tool-call-end\",\"index\":0}\n\n",
+        "data: {\"type\":\"message-end\",\"delta\":{\"finish_reason\":\"TOOL_CALL\"}}\n\n",
+    );
+    let mut state = CohereStreamState::default();
+    let out = translate_cohere_sse_chunk(body.as_bytes(), "command-r-plus", &mut state);
+    let lines = sse_data_lines(&String::from_utf8(out).unwrap());
+
+    let start = &lines[0]["choices"][0]["delta"]["tool_calls"][0];
+    assert_eq!(start["id"], "call_1");
+    assert_eq!(start["function"]["name"], "get_weather");
+    let args: String = lines[1..3]
+        .iter()
+        .map(|l| {
+            l["choices"][0]["delta"]["tool_calls"][0]["function"]["arguments"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(args, r#"{"city":"Paris"}"#);
+    assert_eq!(lines[3]["choices"][0]["finish_reason"], "tool_calls");
+    assert_eq!(lines[4], json!("[DONE]"));
+}
+
+#[test]
+fn test_cohere_sse_chunk_passes_openai_lines_through() {
+    let body = b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\ndata: [DONE]\n\n";
+    let out = translate_cohere_sse_chunk(body, "command-r", &mut CohereStreamState::default());
+    assert_eq!(out, body.to_vec());
+}
+
+#[test]
+fn test_cohere_v2_response_translation() {
+    let body = json!({
+        "id": "c2",
+        "finish_reason": "TOOL_CALL",
+        "message": {
+            "role": "assistant",
+            "tool_plan": "I will look up the weather.",
+            "content": [{"type": "text", "text": "Checking."}],
+            "tool_calls": [{
+                "id": "call_9",
+                "type": "function",
+                "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+            }]
+        },
+        "usage": {"tokens": {"input_tokens": 30, "output_tokens": 12}}
+    });
+    let out = translate_response(Provider::Cohere, &body, "command-r-plus").unwrap();
+    let message = &out["choices"][0]["message"];
+    assert_eq!(message["content"], "Checking.");
+    assert_eq!(message["tool_calls"][0]["id"], "call_9");
+    assert_eq!(
+        message["tool_calls"][0]["function"]["arguments"],
+        "{\"city\":\"Paris\"}"
+    );
+    assert_eq!(out["choices"][0]["finish_reason"], "tool_calls");
+    assert_eq!(out["usage"]["total_tokens"], 42);
+}
@@ -67,6 +67,8 @@ pub(crate) fn rewrite_upstream_url(
                 format!("{}/v1/chat/completions", sanitized_base)
             }
         }
+        // Cohere's native v2 chat endpoint (https://api.cohere.com/v2/chat) is used as-is
+        Provider::Cohere if sanitized_base.contains("/v2") => sanitized_base.to_string(),
         // Groq, Mistral, Together, Cohere all use standard /v1/chat/completions via their base URLs
         Provider::Groq | Provider::Mistral | Provider::TogetherAI | Provider::Cohere => {
             if sanitized_base.contains("/v1") {
@@ -29,7 +29,9 @@ pub struct PostFlightResult {
 /// - `Redact`: applies PII redaction patterns to the response JSON
 /// - `ContentFilter`: scans response for harmful content
 /// - `Transform`: applies JSONPath-based transformations
+/// - `InjectResponseField`: sets (or moves) a field in the response JSON
 /// - `ValidateSchema`: validates response against JSON schema
+/// - `CoerceResponseSchema`: coerces the response toward a JSON schema
 /// - `ExternalGuardrail`: calls external moderation APIs
 /// - `Log`, `Tag`, `Webhook`: observability actions (non-blocking)
 ///
@@ -181,6 +183,32 @@ pub async fn execute_post_flight_actions(
                 }
             }
 
+            // ── InjectResponseField (post-flight, response-side) ──
+            Action::InjectResponseField {
+                path,
+                value,
+                from,
+                remove_source,
+            } => {
+                if let Ok(mut resp_json) =
+                    serde_json::from_slice::<serde_json::Value>(resp_body_vec)
+                {
+                    let set = super::transform::inject_response_field(
+                        &mut resp_json,
+                        path,
+                        value.as_ref(),
+                        from.as_deref(),
+                        *remove_source,
+                    );
+                    if set > 0 {
+                        if let Ok(new_body) = serde_json::to_vec(&resp_json) {
+                            *resp_body_vec = new_body;
+                            body_modified = true;
+                        }
+                    }
+                }
+            }
+
             // ConditionalRoute is request-phase only — skip post-flight
             Action::ConditionalRoute { .. } => {
                 tracing::debug!(
@@ -197,9 +225,9 @@ pub async fn execute_post_flight_actions(
             } => {
                 if let Some(ref resp_json) = parsed_resp_body {
                     let result = middleware::guardrail::validate_schema(resp_json, schema);
-                    let should_deny = if *not { result.valid } else { !result.valid };
+                    let should_deny = result.violated(*not);
                     if should_deny {
-                        let default_msg = if *not {
+                        let default_msg = if *not && !result.schema_error {
                             "Response matches a forbidden schema pattern".to_string()
                         } else {
                             format!(
@@ -228,6 +256,30 @@ pub async fn execute_post_flight_actions(
                 }
             }
 
+            // ── CoerceResponseSchema (post-flight, response-side) ──
+            Action::CoerceResponseSchema { schema } => {
+                if let Ok(resp_json) = serde_json::from_slice::<serde_json::Value>(resp_body_vec) {
+                    match middleware::guardrail::coerce_to_schema(&resp_json, schema) {
+                        Ok(result) if !result.changes.is_empty() => {
+                            if let Ok(new_body) = serde_json::to_vec(&result.body) {
+                                *resp_body_vec = new_body;
+                                body_modified = true;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(errors) => {
+                            return Err(AppError::PolicyDenied {
+                                policy: triggered.policy_name.clone(),
+                                reason: format!(
+                                    "Response could not be coerced to the required schema: {}",
+                                    errors.join("; ")
+                                ),
+                            });
+                        }
+                    }
+                }
+            }
+
             Action::ExternalGuardrail {
                 vendor,
                 endpoint,
@@ -355,4 +407,30 @@ mod tests {
         assert!(result.redacted_fields.is_empty());
         assert!(!result.body_modified);
     }
+
+    #[tokio::test]
+    async fn test_post_flight_inject_response_field_moves_citations() {
+        let actions = vec![TriggeredAction {
+            policy_id: Uuid::nil(),
+            policy_name: "normalize-sources".to_string(),
+            rule_index: 0,
+            action: Action::InjectResponseField {
+                path: "x_sources".to_string(),
+                value: None,
+                from: Some("citations".to_string()),
+                remove_source: true,
+            },
+        }];
+        let parsed_body = Some(serde_json::json!({"choices": [], "citations": ["https://a"]}));
+        let mut body_vec = serde_json::to_vec(parsed_body.as_ref().unwrap()).unwrap();
+
+        let result = execute_post_flight_actions(&actions, &parsed_body, &mut body_vec, 200)
+            .await
+            .expect("InjectResponseField should succeed");
+
+        assert!(result.body_modified);
+        let body: serde_json::Value = serde_json::from_slice(&body_vec).unwrap();
+        assert_eq!(body["x_sources"], serde_json::json!(["https://a"]));
+        assert!(body.get("citations").is_none());
+    }
 }
@@ -29,7 +29,6 @@ use tokio_tungstenite::{
 };
 
 use crate::middleware;
-use crate::vault::SecretStore;
 use crate::AppState;
 
 // ── Query params ──────────────────────────────────────────────
@@ -89,7 +88,7 @@ pub async fn realtime_handler(
 
     // Resolve credential via vault
     let api_key: Option<String> = if let Some(cred_id) = token.credential_id {
-        match state.vault.retrieve(&cred_id.to_string()).await {
+        match state.secrets.retrieve(&cred_id.to_string()).await {
             Ok((plaintext, _provider, mode, header)) => {
                 // For "bearer"/"header" mode, use the key directly
                 // For "basic" mode, use as-is (caller can base64-encode if needed)
@@ -97,7 +96,18 @@ pub async fn realtime_handler(
                 Some(plaintext)
             }
             Err(e) => {
-                tracing::error!(token_id = %token_id, "realtime: credential decrypt error: {}", e);
+                if e.downcast_ref::<crate::vault::builtin::DecryptionError>()
+                    .is_some()
+                {
+                    tracing::error!(
+                        token_id = %token_id,
+                        credential_id = %cred_id,
+                        "realtime: credential could not be decrypted — check TRUEFLOW_MASTER_KEY: {}",
+                        e
+                    );
+                } else {
+                    tracing::error!(token_id = %token_id, "realtime: credential lookup error: {}", e);
+                }
                 return Err(StatusCode::INTERNAL_SERVER_ERROR);
             }
         }
@@ -0,0 +1,335 @@
+//! Audit replay (`POST /api/v1/audit/:id/replay`): re-send a request captured
+//! at `log_level` 2 through `proxy_handler`.
+//!
+//! The request is rebuilt from the audit entry: method, path, the stored body
+//! (as the client sent it, before policies rewrote it) and the logged request
+//! headers. Credential-bearing headers were redacted
+//! when they were logged, so they are dropped, and the request authenticates
+//! as the original token or an override (e.g. one pointed at a sandbox
+//! upstream). The query string isn't logged and isn't replayed.
+//!
+//! A replay is a real request: policies, spend tracking and the audit log all
+//! apply, and it gets its own audit entry.
+
+use std::future::Future;
+
+use axum::body::Bytes;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, Uri};
+use axum::response::Response;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::store::postgres::AuditLogDetailRow;
+
+/// Largest replayed response body returned to the caller.
+pub const MAX_REPLAY_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Log level at which request bodies and headers are captured.
+const BODY_LOG_LEVEL: i16 = 2;
+
+/// Placeholder written in place of credential-bearing header values.
+const REDACTED_VALUE: &str = "[REDACTED]";
+
+/// Logged headers that describe the original connection, not the request.
+const CONNECTION_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "content-length",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "x-request-id",
+];
+
+/// Why an audit entry can't be replayed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The entry was logged below level 2, so the request wasn't captured.
+    NotCaptured,
+    /// The original token is gone and no override was given.
+    NoToken,
+    /// The request had a body that wasn't text (e.g. a multipart upload), so
+    /// it wasn't stored.
+    BodyNotStored,
+    /// The stored method or path doesn't form a valid request.
+    Invalid(String),
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotCaptured => write!(
+                f,
+                "request body and headers were not captured (log_level < 2)"
+            ),
+            Self::BodyNotStored => write!(
+                f,
+                "request body was not text (e.g. a file upload) and was not stored"
+            ),
+            Self::NoToken => write!(f, "no token to replay with; pass token_id"),
+            Self::Invalid(reason) => write!(f, "stored request is invalid: {}", reason),
+        }
+    }
+}
+
+/// A request rebuilt from an audit entry.
+#[derive(Debug)]
+pub struct ReplayRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// What the replay returned.
+#[derive(Debug, Serialize)]
+pub struct ReplayOutcome {
+    /// Audit entry of the replayed request.
+    pub audit_id: Uuid,
+    pub status: u16,
+    pub headers: serde_json::Value,
+    /// The response body: JSON when it parses, otherwise a string. `null` if
+    /// it exceeded [`MAX_REPLAY_RESPONSE_BYTES`].
+    pub body: serde_json::Value,
+}
+
+/// Rebuild the request logged in `entry`, authenticated as `token_id`
+/// (default: the entry's own token).
+pub fn rebuild(
+    entry: &AuditLogDetailRow,
+    token_id: Option<&str>,
+) -> Result<ReplayRequest, ReplayError> {
+    if entry.log_level.unwrap_or(0) < BODY_LOG_LEVEL || entry.request_headers.is_none() {
+        return Err(ReplayError::NotCaptured);
+    }
+    if entry.request_body.is_none() && had_body(entry.request_headers.as_ref()) {
+        return Err(ReplayError::BodyNotStored);
+    }
+    let token_id = token_id
+        .or(entry.token_id.as_deref())
+        .ok_or(ReplayError::NoToken)?;
+    let method = Method::from_bytes(entry.method.as_bytes())
+        .map_err(|_| ReplayError::Invalid(format!("method '{}'", entry.method)))?;
+    let uri = entry
+        .path
+        .parse::<Uri>()
+        .map_err(|_| ReplayError::Invalid(format!("path '{}'", entry.path)))?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(serde_json::Value::Object(logged)) = &entry.request_headers {
+        for (name, value) in logged {
+            let Some(value) = value.as_str() else {
+                continue;
+            };
+            let lower = name.to_ascii_lowercase();
+            if value == REDACTED_VALUE || CONNECTION_HEADERS.contains(&lower.as_str()) {
+                continue;
+            }
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(lower.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+    }
+    let auth = HeaderValue::from_str(&format!("Bearer {}", token_id))
+        .map_err(|_| ReplayError::Invalid("token id".to_string()))?;
+    headers.insert("authorization", auth);
+
+    let body = entry
+        .request_body
+        .clone()
+        .map(Bytes::from)
+        .unwrap_or_default();
+    Ok(ReplayRequest {
+        method,
+        uri,
+        headers,
+        body,
+    })
+}
+
+/// Whether the logged request headers announce a body.
+fn had_body(headers: Option<&serde_json::Value>) -> bool {
+    let header = |name: &str| {
+        headers
+            .and_then(|h| h.as_object())
+            .and_then(|h| h.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)))
+            .and_then(|(_, v)| v.as_str())
+    };
+    header("transfer-encoding").is_some()
+        || header("content-length").is_some_and(|len| len.trim() != "0")
+}
+
+/// Send `request` with `send` (the proxy pipeline) and collect the response.
+pub async fn run<F, Fut>(request: ReplayRequest, audit_id: Uuid, send: F) -> ReplayOutcome
+where
+    F: FnOnce(ReplayRequest) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let response = send(request).await;
+    let status = response.status().as_u16();
+    let headers = serde_json::Value::Object(
+        response
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), serde_json::json!(v.to_str().ok()?))))
+            .collect(),
+    );
+    let body = match axum::body::to_bytes(response.into_body(), MAX_REPLAY_RESPONSE_BYTES).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+            serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned())
+        }),
+        Err(e) => {
+            tracing::warn!(%audit_id, "replay response body not returned: {}", e);
+            serde_json::Value::Null
+        }
+    };
+    ReplayOutcome {
+        audit_id,
+        status,
+        headers,
+        body,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn entry(log_level: i16, captured: bool) -> AuditLogDetailRow {
+        serde_json::from_value(json!({
+            "id": "00000000-0000-0000-0000-000000000001",
+            "created_at": "2026-10-01T12:00:00Z",
+            "token_id": "tf_v1_original",
+            "method": "POST",
+            "path": "/v1/chat/completions",
+            "upstream_url": "https://api.openai.com/v1/chat/completions",
+            "response_latency_ms": 812,
+            "policy_result": "allow",
+            "log_level": log_level,
+            "request_body": captured.then_some(
+                r#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}]}"#
+            ),
+            "request_headers": captured.then_some(json!({
+                "authorization": "[REDACTED]",
+                "content-type": "application/json",
+                "content-length": "64",
+                "host": "gateway.internal",
+                "x-trueflow-session-id": "run-42"
+            })),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_replay_refused_without_captured_bodies() {
+        assert_eq!(
+            rebuild(&entry(1, false), None).unwrap_err(),
+            ReplayError::NotCaptured
+        );
+        assert_eq!(
+            rebuild(&entry(0, false), Some("tf_v1_safe")).unwrap_err(),
+            ReplayError::NotCaptured
+        );
+        // Level 2 on the entry but the bodies row is gone (expired).
+        assert_eq!(
+            rebuild(&entry(2, false), None).unwrap_err(),
+            ReplayError::NotCaptured
+        );
+
+        let mut orphan = entry(2, true);
+        orphan.token_id = None;
+        assert_eq!(rebuild(&orphan, None).unwrap_err(), ReplayError::NoToken);
+    }
+
+    #[test]
+    fn test_rebuild_drops_redacted_and_connection_headers() {
+        let request = rebuild(&entry(2, true), None).unwrap();
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.uri.path(), "/v1/chat/completions");
+        assert_eq!(request.headers["authorization"], "Bearer tf_v1_original");
+        assert_eq!(request.headers["x-trueflow-session-id"], "run-42");
+        assert!(request.headers.get("host").is_none());
+        assert!(request.headers.get("content-length").is_none());
+
+        let request = rebuild(&entry(2, true), Some("tf_v1_safe")).unwrap();
+        assert_eq!(request.headers["authorization"], "Bearer tf_v1_safe");
+    }
+
+    #[test]
+    fn test_replay_refused_when_binary_body_was_not_stored() {
+        let mut upload = entry(2, true);
+        upload.request_body = None;
+        upload.request_headers = Some(json!({
+            "content-type": "multipart/form-data; boundary=x",
+            "content-length": "2097152"
+        }));
+        assert_eq!(
+            rebuild(&upload, None).unwrap_err(),
+            ReplayError::BodyNotStored
+        );
+
+        // A bodiless request has nothing to store.
+        let mut get = entry(2, true);
+        get.method = "GET".to_string();
+        get.path = "/v1/models".to_string();
+        get.request_body = None;
+        get.request_headers = Some(json!({ "accept": "application/json" }));
+        assert!(rebuild(&get, None).unwrap().body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_against_mock_upstream() {
+        let upstream = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer tf_v1_safe"))
+            .and(header("x-trueflow-session-id", "run-42"))
+            .and(body_json(json!({
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": "hi"}]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{"message": {"role": "assistant", "content": "hello again"}}]
+            })))
+            .expect(1)
+            .mount(&upstream)
+            .await;
+
+        let request = rebuild(&entry(2, true), Some("tf_v1_safe")).unwrap();
+        let audit_id = Uuid::new_v4();
+        let base = upstream.uri();
+        let outcome = run(request, audit_id, |req| async move {
+            // Stands in for proxy_handler forwarding to the upstream.
+            let upstream = reqwest::Client::new()
+                .request(req.method, format!("{}{}", base, req.uri))
+                .headers(req.headers)
+                .body(req.body)
+                .send()
+                .await
+                .unwrap();
+            let status = upstream.status();
+            Response::builder()
+                .status(status)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(upstream.bytes().await.unwrap()))
+                .unwrap()
+        })
+        .await;
+
+        assert_eq!(outcome.audit_id, audit_id);
+        assert_eq!(outcome.status, 200);
+        assert_eq!(outcome.headers["content-type"], "application/json");
+        assert_eq!(
+            outcome.body["choices"][0]["message"]["content"],
+            "hello again"
+        );
+    }
+}
@@ -52,11 +52,61 @@ pub struct CachedResponse {
     pub model: Option<String>,
     pub prompt_tokens: Option<u32>,
     pub completion_tokens: Option<u32>,
+    /// Unix seconds when the entry was stored. Absent on entries written
+    /// before cache-age headers existed.
+    #[serde(default)]
+    pub stored_at: Option<i64>,
+    /// TTL the entry was stored with.
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+impl CachedResponse {
+    /// Seconds since the entry was stored and seconds until it expires, as of
+    /// `now` (unix seconds). `None` for entries without a timestamp.
+    pub fn freshness(&self, now: i64) -> Option<(u64, u64)> {
+        let age = now.saturating_sub(self.stored_at?).max(0) as u64;
+        let ttl = self
+            .ttl_secs
+            .unwrap_or(DEFAULT_CACHE_TTL_SECS)
+            .saturating_sub(age);
+        Some((age, ttl))
+    }
 }
 
 /// Compute a deterministic cache key from the relevant request body fields.
 /// Returns `None` if the body doesn't contain enough info to cache (e.g., no model).
+#[cfg(test)]
 pub fn compute_cache_key(token_id: &str, body: &serde_json::Value) -> Option<String> {
+    compute_cache_key_ignoring(token_id, body, &[])
+}
+
+/// A token's `cache_key_ignore_paths` entry: a JSON pointer naming something
+/// below the body root. `/model` is refused since the key requires it.
+pub fn is_valid_ignore_path(path: &str) -> bool {
+    path.len() > 1 && path.starts_with('/') && path != "/model"
+}
+
+/// [`compute_cache_key`] over the body with `ignore_paths` (JSON pointers,
+/// e.g. `/metadata/request_id`) removed first. Two requests that differ only
+/// in ignored fields share a cache entry, so only list fields that can't
+/// change the response.
+pub fn compute_cache_key_ignoring(
+    token_id: &str,
+    body: &serde_json::Value,
+    ignore_paths: &[String],
+) -> Option<String> {
+    let stripped;
+    let body = if ignore_paths.is_empty() {
+        body
+    } else {
+        let mut copy = body.clone();
+        for path in ignore_paths {
+            remove_pointer(&mut copy, path);
+        }
+        stripped = copy;
+        &stripped
+    };
     let obj = body.as_object()?;
 
     // Must have at least a model to cache
@@ -66,11 +116,11 @@ pub fn compute_cache_key(token_id: &str, body: &serde_json::Value) -> Option<Str
     let mut canonical = serde_json::Map::new();
     for &field in CACHE_KEY_FIELDS {
         if let Some(val) = obj.get(field) {
-            canonical.insert(field.to_string(), val.clone());
+            canonical.insert(field.to_string(), sorted_keys(val));
         }
     }
 
-    // Sort keys for deterministic serialization (serde_json::Map is BTreeMap-backed)
+    // Keys are sorted explicitly so the key doesn't depend on client field order
     let canonical_json = serde_json::to_string(&serde_json::Value::Object(canonical)).ok()?;
 
     let mut hasher = Sha256::new();
@@ -82,6 +132,46 @@ pub fn compute_cache_key(token_id: &str, body: &serde_json::Value) -> Option<Str
     Some(format!("llm_cache:{}", hash))
 }
 
+/// Remove the value at a JSON pointer, if present.
+fn remove_pointer(body: &mut serde_json::Value, path: &str) {
+    let Some((parent, last)) = path.rsplit_once('/') else {
+        return;
+    };
+    let last = last.replace("~1", "/").replace("~0", "~");
+    match body.pointer_mut(parent) {
+        Some(serde_json::Value::Object(map)) => {
+            map.remove(&last);
+        }
+        Some(serde_json::Value::Array(items)) => {
+            if let Ok(i) = last.parse::<usize>() {
+                if i < items.len() {
+                    items.remove(i);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Rebuild objects with their keys in sorted order, recursively.
+fn sorted_keys(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            serde_json::Value::Object(
+                keys.into_iter()
+                    .map(|k| (k.clone(), sorted_keys(&map[k])))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(sorted_keys).collect())
+        }
+        other => other.clone(),
+    }
+}
+
 /// Attempt to retrieve a cached response.
 pub async fn get_cached(cache: &TieredCache, key: &str) -> Option<CachedResponse> {
     cache.get::<CachedResponse>(key).await
@@ -118,6 +208,31 @@ pub async fn set_cached(cache: &TieredCache, key: &str, response: &CachedRespons
     }
 }
 
+/// Key of the stale copy kept for `serve_stale_on_error` tokens.
+fn stale_key(key: &str) -> String {
+    format!("stale:{}", key)
+}
+
+/// Keep a copy of a response for `grace_secs` beyond the normal TTL, to be
+/// served if the upstream later fails. Subject to the same size limit.
+pub async fn set_stale(cache: &TieredCache, key: &str, response: &CachedResponse, grace_secs: u64) {
+    set_cached(
+        cache,
+        &stale_key(key),
+        response,
+        DEFAULT_CACHE_TTL_SECS + grace_secs,
+    )
+    .await;
+}
+
+/// The last response stored for `key`, fresh or stale.
+pub async fn get_stale(cache: &TieredCache, key: &str) -> Option<CachedResponse> {
+    match get_cached(cache, key).await {
+        Some(fresh) => Some(fresh),
+        None => cache.get::<CachedResponse>(&stale_key(key)).await,
+    }
+}
+
 /// Check if caching should be skipped for this request.
 ///
 /// Skips caching when:
@@ -239,6 +354,96 @@ mod tests {
         assert_eq!(key1, key2);
     }
 
+    #[test]
+    fn test_cache_key_ignoring_stripped_fields_shares_entry() {
+        let ignore = vec![
+            "/metadata/request_id".to_string(),
+            "/messages/0/timestamp".to_string(),
+        ];
+        let body1 = serde_json::json!({
+            "model": "gpt-4",
+            "metadata": {"request_id": "req-1", "team": "a"},
+            "messages": [{"role": "user", "content": "hello", "timestamp": 1700000000}]
+        });
+        let body2 = serde_json::json!({
+            "model": "gpt-4",
+            "metadata": {"team": "a", "request_id": "req-2"},
+            "messages": [{"timestamp": 1700000042, "content": "hello", "role": "user"}]
+        });
+        assert_ne!(
+            compute_cache_key("tok_123", &body1),
+            compute_cache_key("tok_123", &body2)
+        );
+        assert_eq!(
+            compute_cache_key_ignoring("tok_123", &body1, &ignore),
+            compute_cache_key_ignoring("tok_123", &body2, &ignore)
+        );
+
+        // A difference outside the ignored paths still changes the key
+        let mut body3 = body2.clone();
+        body3["messages"][0]["content"] = serde_json::json!("goodbye");
+        assert_ne!(
+            compute_cache_key_ignoring("tok_123", &body1, &ignore),
+            compute_cache_key_ignoring("tok_123", &body3, &ignore)
+        );
+    }
+
+    #[test]
+    fn test_ignore_path_validation() {
+        assert!(is_valid_ignore_path("/metadata/request_id"));
+        assert!(is_valid_ignore_path("/user"));
+        assert!(!is_valid_ignore_path("/"));
+        assert!(!is_valid_ignore_path("metadata"));
+        assert!(!is_valid_ignore_path("/model"));
+    }
+
+    #[test]
+    fn test_stale_key_is_separate_from_fresh_key() {
+        let body = serde_json::json!({"model": "gpt-4", "messages": []});
+        let key = compute_cache_key("tok_123", &body).unwrap();
+        assert_eq!(stale_key(&key), format!("stale:{}", key));
+        assert_ne!(stale_key(&key), key);
+    }
+
+    #[test]
+    fn test_freshness_reports_age_and_remaining_ttl() {
+        let stored_at = 1_700_000_000;
+        let entry = CachedResponse {
+            status: 200,
+            body: b"{}".to_vec(),
+            content_type: "application/json".to_string(),
+            model: Some("gpt-4o".to_string()),
+            prompt_tokens: None,
+            completion_tokens: None,
+            stored_at: Some(stored_at),
+            ttl_secs: Some(DEFAULT_CACHE_TTL_SECS),
+        };
+        // Round-trips through the stored form.
+        let entry: CachedResponse =
+            serde_json::from_slice(&serde_json::to_vec(&entry).unwrap()).unwrap();
+
+        assert_eq!(entry.freshness(stored_at), Some((0, 300)));
+        assert_eq!(entry.freshness(stored_at + 42), Some((42, 258)));
+        // Past expiry (served from the local tier just before eviction).
+        assert_eq!(entry.freshness(stored_at + 301), Some((301, 0)));
+        // Clock skew between replicas never yields a negative age.
+        assert_eq!(entry.freshness(stored_at - 5), Some((0, 300)));
+    }
+
+    #[test]
+    fn test_freshness_unknown_for_entries_without_timestamp() {
+        let legacy: CachedResponse = serde_json::from_value(serde_json::json!({
+            "status": 200,
+            "body": [],
+            "content_type": "application/json",
+            "model": null,
+            "prompt_tokens": null,
+            "completion_tokens": null
+        }))
+        .unwrap();
+        assert_eq!(legacy.freshness(1_700_000_000), None);
+    }
+
     #[test]
     fn test_cache_key_none_without_model() {
         let body = serde_json::json!({
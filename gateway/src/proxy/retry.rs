@@ -60,7 +60,7 @@ pub async fn robust_request(
                 let status = response.status();
 
                 // If success (not a retryable error code), return immediately
-                if !config.status_codes.contains(&status.as_u16()) {
+                if !config.retries_status(status.as_u16()) {
                     return Ok(response);
                 }
 
@@ -74,7 +74,8 @@ pub async fn robust_request(
                 }
 
                 // Calculate wait time
-                let wait_duration = calculate_wait_time(&response, config, attempt);
+                let wait_duration =
+                    calculate_wait_time(status, response.headers(), config, attempt);
 
                 // Check if sleeping would exceed deadline
                 if let Some(dl) = deadline {
@@ -135,30 +136,36 @@ async fn execute_attempt(builder: RequestBuilder) -> Result<Response> {
     builder.send().await.map_err(|e| e.into())
 }
 
-fn calculate_wait_time(response: &Response, config: &RetryConfig, attempt: u32) -> Duration {
-    // 1. Check Retry-After header (RFC 7231 §7.1.3)
-    if let Some(retry_after) = response.headers().get(reqwest::header::RETRY_AFTER) {
-        if let Ok(retry_after_str) = retry_after.to_str() {
-            // Try parsing as delta-seconds (e.g. "120")
-            if let Ok(seconds) = retry_after_str.parse::<u64>() {
-                return Duration::from_secs(seconds);
-            }
-            // FIX 4B-2: Try parsing as HTTP-date (IMF-fixdate per RFC 2616)
-            // e.g. "Fri, 31 Dec 1999 23:59:59 GMT"
-            if let Ok(date) = chrono::DateTime::parse_from_rfc2822(retry_after_str) {
-                let now = chrono::Utc::now();
-                let target = date.with_timezone(&chrono::Utc);
-                if target > now {
-                    let delta = (target - now).num_seconds().max(0) as u64;
-                    return Duration::from_secs(delta);
-                }
-                // Date is in the past → retry immediately (0-second wait)
-                return Duration::from_secs(0);
-            }
-        }
+/// Wait requested by a `Retry-After` header (RFC 9110 §10.2.3), either
+/// delta-seconds (`120`) or an HTTP-date (`Fri, 31 Dec 1999 23:59:59 GMT`).
+/// A date in the past means retry now. `None` when absent or unparseable.
+pub fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
     }
+    // FIX 4B-2: HTTP-date (IMF-fixdate), e.g. "Fri, 31 Dec 1999 23:59:59 GMT"
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
+}
 
-    // 2. Fallback to Exponential Backoff
+/// Sleep before the next attempt. A 429 or 503 carrying `Retry-After` waits
+/// as asked, capped at `max_backoff_ms`; anything else uses the backoff.
+fn calculate_wait_time(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+    config: &RetryConfig,
+    attempt: u32,
+) -> Duration {
+    let honors_retry_after = matches!(status.as_u16(), 429 | 503);
+    if let Some(wait) = parse_retry_after(headers).filter(|_| honors_retry_after) {
+        return wait.min(Duration::from_millis(config.max_backoff_ms));
+    }
     calculate_backoff(config, attempt)
 }
 
@@ -208,7 +215,11 @@ mod tests {
             .await;
 
         let client = Client::new();
-        let config = RetryConfig::default(); // 3 retries
+        let config = RetryConfig {
+            retry_on_status: vec![500],
+            base_backoff_ms: 10,
+            ..RetryConfig::default() // 3 retries
+        };
 
         let res = robust_request(
             &client,
@@ -224,6 +235,78 @@ mod tests {
         assert_eq!(res.status(), 200);
     }
 
+    fn retry_after(value: &str) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_retry_after_formats() {
+        assert_eq!(
+            parse_retry_after(&retry_after("120")),
+            Some(Duration::from_secs(120))
+        );
+
+        let at = (chrono::Utc::now() + chrono::Duration::seconds(30))
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        let wait = parse_retry_after(&retry_after(&at)).unwrap();
+        assert!(
+            wait > Duration::from_secs(25) && wait <= Duration::from_secs(30),
+            "{wait:?}"
+        );
+        assert_eq!(
+            parse_retry_after(&retry_after("Fri, 31 Dec 1999 23:59:59 GMT")),
+            Some(Duration::ZERO)
+        );
+
+        assert_eq!(parse_retry_after(&retry_after("soon")), None);
+        assert_eq!(parse_retry_after(&retry_after("-5")), None);
+        assert_eq!(parse_retry_after(&reqwest::header::HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_retry_after_wait_is_capped() {
+        let config = RetryConfig {
+            base_backoff_ms: 100,
+            max_backoff_ms: 5_000,
+            ..RetryConfig::default()
+        };
+        let too_many = reqwest::StatusCode::TOO_MANY_REQUESTS;
+        assert_eq!(
+            calculate_wait_time(too_many, &retry_after("2"), &config, 1),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            calculate_wait_time(too_many, &retry_after("120"), &config, 1),
+            Duration::from_millis(5_000)
+        );
+        assert_eq!(
+            calculate_wait_time(
+                reqwest::StatusCode::SERVICE_UNAVAILABLE,
+                &retry_after("3"),
+                &config,
+                1
+            ),
+            Duration::from_secs(3)
+        );
+
+        // Unparseable header, or a status that doesn't use it: plain backoff (≤ base on attempt 1).
+        assert!(
+            calculate_wait_time(too_many, &retry_after("soon"), &config, 1)
+                <= Duration::from_millis(100)
+        );
+        assert!(
+            calculate_wait_time(
+                reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+                &retry_after("3"),
+                &config,
+                1
+            ) <= Duration::from_millis(100)
+        );
+    }
+
     // ── Chaos: 429 + Retry-After Header ────────────────────────
 
     /// Upstream returns 429 with `Retry-After: 1` twice, then 200.
@@ -256,7 +339,7 @@ mod tests {
         let client = Client::new();
         let config = RetryConfig {
             max_retries: 3,
-            status_codes: vec![429, 500, 502, 503],
+            retry_on_status: vec![429, 500, 502, 503],
             base_backoff_ms: 100,
             max_backoff_ms: 5000,
             jitter_ms: 0,
@@ -305,7 +388,7 @@ mod tests {
         let client = Client::new();
         let config = RetryConfig {
             max_retries: 3,
-            status_codes: vec![429, 500, 502, 503],
+            retry_on_status: vec![429, 500, 502, 503],
             base_backoff_ms: 10,
             max_backoff_ms: 100,
             jitter_ms: 0,
@@ -341,7 +424,7 @@ mod tests {
         let client = Client::new();
         let config = RetryConfig {
             max_retries: 2,
-            status_codes: vec![429],
+            retry_on_status: vec![429],
             base_backoff_ms: 10,
             max_backoff_ms: 50,
             jitter_ms: 0,
@@ -365,4 +448,82 @@ mod tests {
             "Should return last 429 after retries exhausted"
         );
     }
+
+    // ── Configurable retry_on_status ─────────────────────────
+
+    /// 529 (Anthropic overloaded) isn't retried by default; listing it in
+    /// `retry_on_status` makes it retryable.
+    #[tokio::test]
+    async fn test_configured_non_default_status_is_retried() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(529))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new();
+        let config = RetryConfig {
+            retry_on_status: vec![429, 529],
+            base_backoff_ms: 10,
+            max_backoff_ms: 50,
+            ..RetryConfig::default()
+        };
+
+        let resp = robust_request(
+            &client,
+            Method::POST,
+            &format!("{}/v1/messages", mock_server.uri()),
+            reqwest::header::HeaderMap::new(),
+            Bytes::from("{}"),
+            &config,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(resp.status(), 200);
+    }
+
+    /// A status outside `retry_on_status` is returned after a single attempt.
+    #[tokio::test]
+    async fn test_unconfigured_status_is_not_retried() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new();
+        let config = RetryConfig {
+            base_backoff_ms: 10,
+            max_backoff_ms: 50,
+            ..RetryConfig::default()
+        };
+
+        let resp = robust_request(
+            &client,
+            Method::POST,
+            &format!("{}/v1/chat/completions", mock_server.uri()),
+            reqwest::header::HeaderMap::new(),
+            Bytes::from("{}"),
+            &config,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(resp.status(), 500);
+    }
 }
@@ -4,7 +4,7 @@
 //! Returns a `RouteDecision` describing which model/upstream won and why.
 
 use crate::models::latency_cache::LatencyCache;
-use crate::models::policy::{RouteTarget, RoutingStrategy};
+use crate::models::policy::{ComplexityConfig, RouteTarget, RoutingStrategy};
 use crate::models::pricing_cache::PricingCache;
 use crate::proxy::loadbalancer::LoadBalancer;
 use rust_decimal::prelude::ToPrimitive;
@@ -33,17 +33,29 @@ pub struct RouteDecision {
 static RR_COUNTERS: std::sync::LazyLock<dashmap::DashMap<String, Arc<AtomicU64>>> =
     std::sync::LazyLock::new(dashmap::DashMap::new);
 
+/// Shared client for the optional external prompt classifier.
+static CLASSIFIER_CLIENT: std::sync::LazyLock<reqwest::Client> =
+    std::sync::LazyLock::new(reqwest::Client::new);
+
+/// Upper bound on the external classifier timeout so a slow classifier can
+/// never add more than this to a request's latency.
+const MAX_CLASSIFIER_TIMEOUT_MS: u64 = 2000;
+
 /// Select the best route from `pool` given the strategy.
 ///
 /// Health filtering: any target whose upstream URL has an open circuit breaker
 /// for `token_id` is skipped. If all targets are unhealthy, `fallback` is used.
 /// If fallback is also None, returns None (caller should let the request proceed
 /// with the original model/upstream as a last-resort fail-open).
+///
+/// `body` and `complexity` are only consulted by `ByPromptComplexity`.
 #[allow(clippy::too_many_arguments)]
 pub async fn select_route(
     strategy: &RoutingStrategy,
     pool: &[RouteTarget],
     fallback: Option<&RouteTarget>,
+    body: Option<&serde_json::Value>,
+    complexity: Option<&ComplexityConfig>,
     pricing: &PricingCache,
     latency: &LatencyCache,
     lb: &LoadBalancer,
@@ -87,6 +99,13 @@ pub async fn select_route(
         RoutingStrategy::RoundRobin => select_round_robin(candidates, token_id),
         RoutingStrategy::LeastBusy => select_least_busy(candidates, lb),
         RoutingStrategy::WeightedRandom => select_weighted_random(candidates),
+        RoutingStrategy::ByPromptComplexity => {
+            let default_cfg = ComplexityConfig::default();
+            let cfg = complexity.unwrap_or(&default_cfg);
+            let classification =
+                classify_prompt(cfg, body.unwrap_or(&serde_json::Value::Null)).await;
+            select_by_complexity(candidates, &classification)
+        }
     }
 }
 
@@ -251,6 +270,201 @@ fn select_weighted_random(candidates: Vec<&RouteTarget>) -> Option<RouteDecision
     })
 }
 
+// ── Prompt Complexity Routing ─────────────────────────────────
+
+/// Complexity tiers, cheapest first.
+const TIERS: [&str; 3] = ["simple", "moderate", "complex"];
+
+/// How a prompt was classified for the `by_prompt_complexity` strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptClassification {
+    /// One of `simple`, `moderate`, `complex`.
+    pub tier: &'static str,
+    /// `heuristic`, `classifier`, or `default` (external classifier failed).
+    pub source: &'static str,
+    /// Characters of prompt text across all messages.
+    pub chars: usize,
+    pub messages: usize,
+    pub has_code: bool,
+}
+
+fn normalize_tier(tier: &str) -> Option<&'static str> {
+    TIERS
+        .iter()
+        .copied()
+        .find(|t| t.eq_ignore_ascii_case(tier.trim()))
+}
+
+/// Collect the prompt text and message count from an OpenAI-style body
+/// (`messages`), a legacy completion (`prompt`) or a Responses API `input`.
+fn prompt_text(body: &serde_json::Value) -> (String, usize) {
+    if let Some(messages) = body.get("messages").and_then(|m| m.as_array()) {
+        let text = messages
+            .iter()
+            .filter_map(|m| m.get("content"))
+            .map(crate::middleware::pii::extract_text_from_value)
+            .collect::<Vec<_>>()
+            .join("\n");
+        return (text, messages.len());
+    }
+    for field in ["prompt", "input"] {
+        if let Some(v) = body.get(field) {
+            let count = v.as_array().map_or(1, |a| a.len());
+            return (crate::middleware::pii::extract_text_from_value(v), count);
+        }
+    }
+    (String::new(), 0)
+}
+
+/// Cheap code detection: a fenced block, or at least two common syntax markers.
+fn looks_like_code(text: &str) -> bool {
+    if text.contains("```") {
+        return true;
+    }
+    const MARKERS: [&str; 10] = [
+        "def ",
+        "fn ",
+        "function ",
+        "class ",
+        "import ",
+        "#include",
+        "=> {",
+        "};",
+        "return ",
+        "SELECT ",
+    ];
+    MARKERS.iter().filter(|m| text.contains(*m)).count() >= 2
+}
+
+/// Classify a prompt with the built-in heuristic.
+///
+/// `complex` when the prompt is long, the conversation is long, or it contains
+/// code (if `code_is_complex`); `simple` when it is short, code-free and at most
+/// two messages; `moderate` otherwise.
+pub fn classify_prompt_heuristic(
+    cfg: &ComplexityConfig,
+    body: &serde_json::Value,
+) -> PromptClassification {
+    let (text, messages) = prompt_text(body);
+    let chars = text.chars().count();
+    let has_code = looks_like_code(&text);
+
+    let tier = if chars >= cfg.complex_min_chars
+        || messages >= cfg.complex_min_messages
+        || (has_code && cfg.code_is_complex)
+    {
+        "complex"
+    } else if chars <= cfg.simple_max_chars && messages <= 2 && !has_code {
+        "simple"
+    } else {
+        "moderate"
+    };
+
+    PromptClassification {
+        tier,
+        source: "heuristic",
+        chars,
+        messages,
+        has_code,
+    }
+}
+
+/// Classify a prompt, asking the external classifier first when configured.
+///
+/// The classifier gets the request body and must answer `{"tier": "..."}`
+/// within `classifier_timeout_ms`. Any failure falls back to `default_tier`
+/// rather than the heuristic, so a broken classifier degrades predictably.
+pub async fn classify_prompt(
+    cfg: &ComplexityConfig,
+    body: &serde_json::Value,
+) -> PromptClassification {
+    let mut classification = classify_prompt_heuristic(cfg, body);
+    let Some(url) = cfg.classifier_url.as_deref() else {
+        return classification;
+    };
+
+    let timeout =
+        std::time::Duration::from_millis(cfg.classifier_timeout_ms.min(MAX_CLASSIFIER_TIMEOUT_MS));
+    let result = CLASSIFIER_CLIENT
+        .post(url)
+        .timeout(timeout)
+        .json(body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    let tier = match result {
+        Ok(resp) => resp.json::<serde_json::Value>().await.ok().and_then(|v| {
+            v.get("tier")
+                .and_then(|t| t.as_str())
+                .and_then(normalize_tier)
+        }),
+        Err(e) => {
+            tracing::warn!(url, error = %e, "dynamic_route: prompt classifier failed");
+            None
+        }
+    };
+
+    match tier {
+        Some(tier) => {
+            classification.tier = tier;
+            classification.source = "classifier";
+        }
+        None => {
+            classification.tier = normalize_tier(&cfg.default_tier).unwrap_or("moderate");
+            classification.source = "default";
+        }
+    }
+    classification
+}
+
+/// Pick the first healthy target whose tier matches the classification.
+///
+/// When no target serves that tier, the nearest tier is used, preferring a more
+/// capable one (simple → moderate → complex; complex → moderate → simple).
+/// Pools without any tiers fall back to the first healthy target.
+fn select_by_complexity(
+    candidates: Vec<&RouteTarget>,
+    classification: &PromptClassification,
+) -> Option<RouteDecision> {
+    let order: [&str; 3] = match classification.tier {
+        "simple" => ["simple", "moderate", "complex"],
+        "complex" => ["complex", "moderate", "simple"],
+        _ => ["moderate", "complex", "simple"],
+    };
+    let target = order
+        .iter()
+        .find_map(|tier| {
+            candidates.iter().copied().find(|t| {
+                t.tier
+                    .as_deref()
+                    .and_then(normalize_tier)
+                    .is_some_and(|tt| tt == *tier)
+            })
+        })
+        .or_else(|| candidates.first().copied())?;
+
+    let served = target.tier.as_deref().unwrap_or("untiered");
+    Some(RouteDecision {
+        model: target.model.clone(),
+        upstream_url: target.upstream_url.clone(),
+        credential_id: target.credential_id,
+        strategy_used: "by_prompt_complexity".to_string(),
+        reason: format!(
+            "{} prompt via {} ({} chars, {} messages{}); served by {} tier",
+            classification.tier,
+            classification.source,
+            classification.chars,
+            classification.messages,
+            if classification.has_code {
+                ", code"
+            } else {
+                ""
+            },
+            served,
+        ),
+    })
+}
+
 // ── Conditional Routing ───────────────────────────────────────
 
 /// Evaluate an ordered list of route branches and return the first matching target.
@@ -386,9 +600,81 @@ mod tests {
             model: model.to_string(),
             upstream_url: url.to_string(),
             credential_id: None,
+            tier: None,
+        }
+    }
+
+    fn tiered(model: &str, tier: &str) -> RouteTarget {
+        RouteTarget {
+            tier: Some(tier.to_string()),
+            ..target(model, "https://api.openai.com")
         }
     }
 
+    #[test]
+    fn test_prompt_complexity_heuristic_tiers() {
+        let cfg = ComplexityConfig::default();
+
+        let short = serde_json::json!({"messages": [{"role": "user", "content": "What is 2+2?"}]});
+        assert_eq!(classify_prompt_heuristic(&cfg, &short).tier, "simple");
+
+        let medium = serde_json::json!({"messages": [
+            {"role": "system", "content": "You are helpful."},
+            {"role": "user", "content": "a".repeat(1000)}
+        ]});
+        assert_eq!(classify_prompt_heuristic(&cfg, &medium).tier, "moderate");
+
+        let code = serde_json::json!({"messages": [
+            {"role": "user", "content": [{"type": "text", "text": "Fix this:\n```rust\nfn main() {}\n```"}]}
+        ]});
+        let c = classify_prompt_heuristic(&cfg, &code);
+        assert!(c.has_code);
+        assert_eq!(c.tier, "complex");
+
+        let lenient = ComplexityConfig {
+            code_is_complex: false,
+            ..ComplexityConfig::default()
+        };
+        assert_eq!(classify_prompt_heuristic(&lenient, &code).tier, "moderate");
+
+        let long = serde_json::json!({"prompt": "x".repeat(5000)});
+        assert_eq!(classify_prompt_heuristic(&cfg, &long).tier, "complex");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_classifier_failure_uses_default_tier() {
+        let cfg = ComplexityConfig {
+            classifier_url: Some("http://127.0.0.1:1/classify".to_string()),
+            default_tier: "complex".to_string(),
+            ..ComplexityConfig::default()
+        };
+        let body = serde_json::json!({"messages": [{"role": "user", "content": "hi"}]});
+        let c = classify_prompt(&cfg, &body).await;
+        assert_eq!(c.tier, "complex");
+        assert_eq!(c.source, "default");
+    }
+
+    #[test]
+    fn test_select_by_complexity_prefers_nearest_tier() {
+        let pool = [tiered("gpt-4o-mini", "simple"), tiered("gpt-4o", "complex")];
+        let refs: Vec<&RouteTarget> = pool.iter().collect();
+        let mut c = PromptClassification {
+            tier: "simple",
+            source: "heuristic",
+            chars: 12,
+            messages: 1,
+            has_code: false,
+        };
+        let d = select_by_complexity(refs.clone(), &c).unwrap();
+        assert_eq!(d.model, "gpt-4o-mini");
+        assert_eq!(d.strategy_used, "by_prompt_complexity");
+        assert!(d.reason.starts_with("simple prompt via heuristic"));
+
+        // No moderate target: round up to the complex tier.
+        c.tier = "moderate";
+        assert_eq!(select_by_complexity(refs, &c).unwrap().model, "gpt-4o");
+    }
+
     #[test]
     fn test_round_robin_rotates() {
         let pool = [
@@ -0,0 +1,210 @@
+//! A/B traffic split (`Action::Split`): weighted variant assignment.
+//!
+//! A variant can override body fields (typically `model`), the upstream URL
+//! and the credential, so a split can compare model parameters on one
+//! provider or two different providers (e.g. 90% GPT-4o, 10% a self-hosted
+//! Llama). The chosen variant's name is recorded in the audit log.
+//!
+//! Assignment is derived from the request id, so it is stable for a given
+//! request (retries, fallbacks) without any shared state.
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::policy::SplitVariant;
+
+/// The variant a request was assigned to.
+#[derive(Debug)]
+pub struct Assignment<'a> {
+    pub variant: &'a SplitVariant,
+    pub bucket: u32,
+    pub total_weight: u32,
+}
+
+/// Pick a variant for `request_id`, weighted by `SplitVariant::weight`.
+/// `None` when there are no variants or their weights sum to zero.
+pub fn assign(variants: &[SplitVariant], request_id: Uuid) -> Option<Assignment<'_>> {
+    let total_weight: u32 = variants.iter().map(|v| v.weight).sum();
+    if total_weight == 0 {
+        return None;
+    }
+    // XOR-fold the first UUID bytes into a u32 so the same request_id always
+    // picks the same variant.
+    let bucket_seed = request_id.as_bytes()[0..4]
+        .iter()
+        .enumerate()
+        .fold(0u32, |acc, (i, &b)| acc ^ ((b as u32) << (i * 8)));
+    let bucket = bucket_seed % total_weight;
+    let mut cumulative: u32 = 0;
+    let mut chosen = &variants[0];
+    for variant in variants {
+        cumulative += variant.weight;
+        if bucket < cumulative {
+            chosen = variant;
+            break;
+        }
+    }
+    Some(Assignment {
+        variant: chosen,
+        bucket,
+        total_weight,
+    })
+}
+
+impl Assignment<'_> {
+    /// Apply the variant to the request: merge its body fields into `body`
+    /// and set the upstream/credential overrides it specifies. Overrides the
+    /// variant leaves unset are not touched.
+    pub fn apply(
+        &self,
+        body: Option<&mut Value>,
+        upstream_override: &mut Option<String>,
+        credential_override: &mut Option<Uuid>,
+    ) {
+        if let Some(obj) = body.and_then(|b| b.as_object_mut()) {
+            for (k, v) in &self.variant.set_body_fields {
+                obj.insert(k.clone(), v.clone());
+            }
+        }
+        if let Some(ref url) = self.variant.upstream_url {
+            *upstream_override = Some(url.clone());
+        }
+        if let Some(id) = self.variant.credential_id {
+            *credential_override = Some(id);
+        }
+    }
+}
+
+/// Credential IDs referenced by split variants anywhere in a policy's raw
+/// `rules` (including nested branch actions). Each must belong to the
+/// policy's project.
+pub fn variant_credential_ids(rules: &Value) -> Vec<Uuid> {
+    let mut ids = Vec::new();
+    collect_variant_credentials(rules, &mut ids);
+    ids
+}
+
+fn collect_variant_credentials(value: &Value, ids: &mut Vec<Uuid>) {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .for_each(|v| collect_variant_credentials(v, ids)),
+        Value::Object(obj) => {
+            if obj.get("action").and_then(Value::as_str) == Some("split") {
+                let variants = obj.get("variants").and_then(Value::as_array);
+                ids.extend(variants.into_iter().flatten().filter_map(|v| {
+                    v.get("credential_id")
+                        .and_then(Value::as_str)
+                        .and_then(|id| Uuid::parse_str(id).ok())
+                }));
+            }
+            obj.values()
+                .for_each(|v| collect_variant_credentials(v, ids));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn variants() -> Vec<SplitVariant> {
+        serde_json::from_value(json!([
+            {"weight": 90, "name": "gpt-4o", "set_body_fields": {"model": "gpt-4o"}},
+            {
+                "weight": 10,
+                "name": "llama",
+                "set_body_fields": {"model": "llama-3.1-70b"},
+                "upstream_url": "http://llama.internal:8000/v1",
+                "credential_id": "00000000-0000-0000-0000-0000000000aa"
+            }
+        ]))
+        .unwrap()
+    }
+
+    /// A request id whose folded bucket is `seed` (only the first 4 bytes count).
+    fn request_id(seed: u32) -> Uuid {
+        let mut bytes = [0u8; 16];
+        bytes[0..4].copy_from_slice(&seed.to_le_bytes());
+        Uuid::from_bytes(bytes)
+    }
+
+    #[test]
+    fn test_assignment_is_weighted_and_stable() {
+        let variants = variants();
+        let control = assign(&variants, request_id(89)).unwrap();
+        assert_eq!(control.variant.name.as_deref(), Some("gpt-4o"));
+        assert_eq!((control.bucket, control.total_weight), (89, 100));
+        let experiment = assign(&variants, request_id(195)).unwrap();
+        assert_eq!(experiment.variant.name.as_deref(), Some("llama"));
+        assert_eq!(experiment.bucket, 95);
+
+        let id = Uuid::new_v4();
+        let first = assign(&variants, id).unwrap().variant.name.clone();
+        for _ in 0..10 {
+            assert_eq!(assign(&variants, id).unwrap().variant.name, first);
+        }
+
+        assert!(assign(&[], id).is_none());
+        let mut zero = variants.clone();
+        zero.iter_mut().for_each(|v| v.weight = 0);
+        assert!(assign(&zero, id).is_none());
+    }
+
+    #[test]
+    fn test_apply_sets_upstream_and_credential_of_selected_variant() {
+        let variants = variants();
+        let mut body = json!({"model": "gpt-4o", "messages": []});
+        let mut upstream = None;
+        let mut credential = None;
+        assign(&variants, request_id(97)).unwrap().apply(
+            Some(&mut body),
+            &mut upstream,
+            &mut credential,
+        );
+        assert_eq!(body["model"], "llama-3.1-70b");
+        assert_eq!(upstream.as_deref(), Some("http://llama.internal:8000/v1"));
+        assert_eq!(
+            credential,
+            Some(Uuid::parse_str("00000000-0000-0000-0000-0000000000aa").unwrap())
+        );
+
+        // A body-only variant keeps the token's upstream and credential.
+        let mut body = json!({"model": "llama-3.1-70b"});
+        let mut upstream = None;
+        let mut credential = None;
+        assign(&variants, request_id(3)).unwrap().apply(
+            Some(&mut body),
+            &mut upstream,
+            &mut credential,
+        );
+        assert_eq!(body["model"], "gpt-4o");
+        assert!(upstream.is_none());
+        assert!(credential.is_none());
+    }
+
+    #[test]
+    fn test_variant_credential_ids_found_in_nested_rules() {
+        let rules = json!([
+            {"when": {"always": true}, "then": {"action": "split", "variants": variants()}},
+            {
+                "when": {"field": "model", "op": "eq", "value": "x"},
+                "then": [{"action": "deny", "message": "no"}],
+                "else": {"action": "split", "variants": [
+                    {"weight": 1, "credential_id": "00000000-0000-0000-0000-0000000000bb"},
+                    {"weight": 1}
+                ]}
+            }
+        ]);
+        assert_eq!(
+            variant_credential_ids(&rules),
+            vec![
+                Uuid::parse_str("00000000-0000-0000-0000-0000000000aa").unwrap(),
+                Uuid::parse_str("00000000-0000-0000-0000-0000000000bb").unwrap(),
+            ]
+        );
+        assert!(variant_credential_ids(&json!([{"then": {"action": "allow"}}])).is_empty());
+    }
+}
@@ -4,7 +4,7 @@
 //! passes them through to the client in real-time, and reassembles
 //! the complete response for logging, tool call extraction, and cost tracking.
 
-use crate::models::llm::ToolCallInfo;
+use crate::models::llm::{normalize_finish_reason, ToolCallInfo};
 use serde_json::Value;
 use std::time::Instant;
 
@@ -20,13 +20,40 @@ pub struct StreamResult {
     pub completion_tokens: Option<u32>,
     /// Model name
     pub model: Option<String>,
-    /// Finish reason from the final chunk
+    /// Finish reason from the final chunk, normalized to OpenAI values
+    /// (`stop`, `length`, `tool_calls`, `content_filter`) for every provider.
     pub finish_reason: Option<String>,
     /// Time to first token (TTFT) in milliseconds
     pub ttft_ms: Option<u64>,
     /// Total chunks received
     #[allow(dead_code)]
     pub chunk_count: u32,
+    /// Set when the upstream failed or closed the connection before the
+    /// stream completed; `content` then holds only the partial output.
+    pub stream_error: Option<String>,
+}
+
+impl StreamResult {
+    /// Token counts for cost and audit: the exact usage the upstream reported
+    /// (OpenAI's final `stream_options.include_usage` chunk, Anthropic
+    /// `message_delta`, Gemini `usageMetadata`) where present, otherwise an
+    /// estimate: `prompt_estimate` (only called when the prompt count is
+    /// missing) and the accumulated output.
+    ///
+    /// The flag is `true` when both counts are exact.
+    pub fn usage_or_estimate(&self, prompt_estimate: impl FnOnce() -> u32) -> (u32, u32, bool) {
+        let prompt = self.prompt_tokens.unwrap_or_else(prompt_estimate);
+        let completion = self.completion_tokens.unwrap_or_else(|| {
+            crate::models::tokenizer::estimate_text_tokens(&self.content)
+                + self
+                    .tool_calls
+                    .iter()
+                    .map(crate::models::llm::estimate_tool_call_tokens)
+                    .sum::<u32>()
+        });
+        let exact = self.prompt_tokens.is_some() && self.completion_tokens.is_some();
+        (prompt, completion, exact)
+    }
 }
 
 /// Accumulates SSE chunks from a streaming LLM response.
@@ -144,6 +171,9 @@ impl StreamAccumulator {
         // Try Anthropic streaming format
         self.process_anthropic_chunk(&json);
 
+        // Try Gemini streaming format
+        self.process_gemini_chunk(&json);
+
         // Check for usage in this chunk (OpenAI includes it in final chunk
         // when stream_options.include_usage is true)
         self.extract_chunk_usage(&json);
@@ -158,7 +188,7 @@ impl StreamAccumulator {
             for choice in choices {
                 // Extract finish_reason
                 if let Some(fr) = choice.get("finish_reason").and_then(|f| f.as_str()) {
-                    self.finish_reason = Some(fr.to_string());
+                    self.finish_reason = Some(normalize_finish_reason(fr).to_string());
                 }
 
                 let delta = match choice.get("delta") {
@@ -280,7 +310,7 @@ impl StreamAccumulator {
             "message_delta" => {
                 if let Some(delta) = json.get("delta") {
                     if let Some(sr) = delta.get("stop_reason").and_then(|s| s.as_str()) {
-                        self.finish_reason = Some(sr.to_string());
+                        self.finish_reason = Some(normalize_finish_reason(sr).to_string());
                     }
                 }
                 // Anthropic includes usage in message_delta
@@ -307,6 +337,19 @@ impl StreamAccumulator {
         }
     }
 
+    /// Process a Gemini `streamGenerateContent` chunk.
+    /// Format: {"candidates":[{"content":{...},"finishReason":"STOP"}],"usageMetadata":{...}}
+    fn process_gemini_chunk(&mut self, json: &Value) {
+        if let Some(fr) = json
+            .get("candidates")
+            .and_then(|c| c.get(0))
+            .and_then(|c| c.get("finishReason"))
+            .and_then(|f| f.as_str())
+        {
+            self.finish_reason = Some(normalize_finish_reason(fr).to_string());
+        }
+    }
+
     /// Extract usage from a chunk (OpenAI final chunk with stream_options.include_usage).
     fn extract_chunk_usage(&mut self, json: &Value) {
         if let Some(usage) = json.get("usage") {
@@ -354,6 +397,8 @@ impl StreamAccumulator {
                     Some(d.arguments)
                 },
                 call_id: d.call_id,
+                estimated_tokens: None,
+                estimated_cost_usd: None,
             })
             .collect();
 
@@ -370,6 +415,7 @@ impl StreamAccumulator {
             finish_reason: self.finish_reason,
             ttft_ms,
             chunk_count: self.chunk_count,
+            stream_error: None,
         }
     }
 }
@@ -536,7 +582,7 @@ mod tests {
         assert_eq!(result.model.as_deref(), Some("claude-3-5-sonnet-20241022"));
         assert_eq!(result.prompt_tokens, Some(25));
         assert_eq!(result.completion_tokens, Some(12));
-        assert_eq!(result.finish_reason.as_deref(), Some("end_turn"));
+        assert_eq!(result.finish_reason.as_deref(), Some("stop"));
     }
 
     #[test]
@@ -554,7 +600,65 @@ mod tests {
         assert_eq!(result.tool_calls.len(), 1);
         assert_eq!(result.tool_calls[0].name, "search");
         assert_eq!(result.tool_calls[0].call_id.as_deref(), Some("toolu_01"));
-        assert_eq!(result.finish_reason.as_deref(), Some("tool_use"));
+        assert_eq!(result.finish_reason.as_deref(), Some("tool_calls"));
+    }
+
+    #[test]
+    fn test_anthropic_message_delta_stop_reason_normalized() {
+        for (stop_reason, want) in [
+            ("end_turn", "stop"),
+            ("stop_sequence", "stop"),
+            ("max_tokens", "length"),
+            ("tool_use", "tool_calls"),
+            ("refusal", "content_filter"),
+        ] {
+            let mut acc = StreamAccumulator::new();
+            acc.push_sse_line(&format!(
+                "data: {{\"type\":\"message_delta\",\"delta\":{{\"stop_reason\":\"{}\"}},\"usage\":{{\"output_tokens\":3}}}}",
+                stop_reason
+            ));
+            let result = acc.finalize();
+            assert_eq!(
+                result.finish_reason.as_deref(),
+                Some(want),
+                "{}",
+                stop_reason
+            );
+        }
+    }
+
+    // ── Gemini Streaming ────────────────────────────────────────
+
+    #[test]
+    fn test_gemini_streaming_finish_reason_normalized() {
+        for (finish_reason, want) in [
+            ("STOP", "stop"),
+            ("MAX_TOKENS", "length"),
+            ("SAFETY", "content_filter"),
+            ("RECITATION", "content_filter"),
+        ] {
+            let mut acc = StreamAccumulator::new();
+            acc.push_sse_line(r#"data: {"candidates":[{"content":{"parts":[{"text":"Hi"}],"role":"model"}}],"usageMetadata":{"promptTokenCount":4,"candidatesTokenCount":1}}"#);
+            acc.push_sse_line(&format!(
+                r#"data: {{"candidates":[{{"content":{{"parts":[{{"text":"!"}}],"role":"model"}},"finishReason":"{}"}}],"usageMetadata":{{"promptTokenCount":4,"candidatesTokenCount":2}}}}"#,
+                finish_reason
+            ));
+            let result = acc.finalize();
+            assert_eq!(
+                result.finish_reason.as_deref(),
+                Some(want),
+                "{}",
+                finish_reason
+            );
+            assert_eq!(result.completion_tokens, Some(2));
+        }
+    }
+
+    #[test]
+    fn test_gemini_streaming_without_finish_reason() {
+        let mut acc = StreamAccumulator::new();
+        acc.push_sse_line(r#"data: {"candidates":[{"content":{"parts":[{"text":"partial"}]}}]}"#);
+        assert_eq!(acc.finalize().finish_reason, None);
     }
 
     #[test]
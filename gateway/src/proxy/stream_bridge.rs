@@ -6,16 +6,28 @@
 //! 3. Feeds each line into a [`StreamAccumulator`] in a background task
 //! 4. Resolves a [`StreamResult`] when the stream completes (for audit/cost)
 //!
+//! If the upstream fails or hangs up mid-body, the client stream ends with an
+//! OpenAI-format `data: {"error":{...}}` event plus `data: [DONE]` instead of
+//! being silently truncated, and the result records `stream_error`.
+//!
 //! Uses `tokio::sync::Notify` for instant stream-completion signaling.
+//!
+//! Client-bound bytes optionally pass through a reframing stage (see
+//! [`StreamOutputFormat`]) that turns SSE events into newline-delimited JSON,
+//! then a coalescing stage (see [`StreamFlushConfig`]) that batches tiny
+//! token-by-token chunks before writing them to the socket. Both only touch
+//! the client copy; the accumulator always sees OpenAI SSE.
 
 use std::sync::Arc;
 use std::time::Instant;
 
 use axum::body::Body;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures::StreamExt;
-use tokio::sync::{Mutex, Notify};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex, Notify};
 
+use crate::proxy::model_router::BedrockStreamTranslator;
 use crate::proxy::stream::{StreamAccumulator, StreamResult};
 
 /// Spawn a fire-and-forget task with panic logging.
@@ -36,15 +48,354 @@ macro_rules! spawn_logged {
 /// can await it after the response has been sent to the client.
 pub type StreamResultSlot = Arc<Mutex<Option<StreamResult>>>;
 
+type ChunkResult = Result<Bytes, std::io::Error>;
+
+/// Upper bound on `max_bytes` — larger buffers defeat the point of streaming.
+const MAX_FLUSH_BYTES: usize = 64 * 1024;
+/// Upper bound on `max_delay_ms` so buffered tokens never stall visibly.
+const MAX_FLUSH_DELAY_MS: u64 = 1_000;
+
+fn default_flush_delay_ms() -> u64 {
+    20
+}
+
+/// Per-token SSE write coalescing (stored in `tokens.stream_flush`).
+///
+/// Small chunks are buffered until `max_bytes` accumulate or `max_delay_ms`
+/// elapses since the first buffered byte, whichever comes first. The first
+/// chunk (TTFT) and any chunk carrying `[DONE]` are always flushed immediately.
+/// `max_bytes = 0` (the default) or `max_delay_ms = 0` disables buffering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamFlushConfig {
+    #[serde(default)]
+    pub max_bytes: usize,
+    #[serde(default = "default_flush_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl StreamFlushConfig {
+    /// Parse a token's `stream_flush` JSON, clamping to sane bounds.
+    /// Missing or malformed config falls back to immediate flushing.
+    pub fn from_token_value(value: Option<&serde_json::Value>) -> Self {
+        value
+            .and_then(|v| serde_json::from_value::<Self>(v.clone()).ok())
+            .map(|c| Self {
+                max_bytes: c.max_bytes.min(MAX_FLUSH_BYTES),
+                max_delay_ms: c.max_delay_ms.min(MAX_FLUSH_DELAY_MS),
+            })
+            .unwrap_or_default()
+    }
+
+    /// True when every chunk should be written as soon as it arrives.
+    pub fn is_immediate(&self) -> bool {
+        self.max_bytes == 0 || self.max_delay_ms == 0
+    }
+}
+
+/// Framing of the stream sent to the client (stored in
+/// `tokens.stream_output_format`).
+///
+/// `ndjson` and `jsonlines` write each SSE `data:` payload as one line of
+/// JSON and drop `[DONE]`, comments and other SSE fields; the end of the
+/// body marks the end of the stream. They differ only in `Content-Type`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamOutputFormat {
+    #[default]
+    OpenAiSse,
+    Ndjson,
+    JsonLines,
+}
+
+/// Accepted `stream_output_format` values.
+pub const STREAM_OUTPUT_FORMATS: &[&str] = &["openai_sse", "ndjson", "jsonlines"];
+
+impl StreamOutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "openai_sse" => Some(Self::OpenAiSse),
+            "ndjson" => Some(Self::Ndjson),
+            "jsonlines" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// A token's format; missing or unknown values fall back to SSE.
+    pub fn from_token_value(value: Option<&str>) -> Self {
+        value.and_then(Self::from_name).unwrap_or_default()
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::OpenAiSse => "text/event-stream",
+            Self::Ndjson => "application/x-ndjson",
+            Self::JsonLines => "application/jsonl",
+        }
+    }
+}
+
+/// Incremental SSE → JSON-lines converter. Input may split lines (and
+/// events) anywhere; output only ever contains whole lines.
+#[derive(Default)]
+struct JsonLinesReframer {
+    line_residual: String,
+    utf8_residual: Vec<u8>,
+    /// `data:` lines of the event being read, per the SSE spec joined by `\n`.
+    data: Vec<String>,
+}
+
+impl JsonLinesReframer {
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.utf8_residual.extend_from_slice(bytes);
+        let valid_up_to = match std::str::from_utf8(&self.utf8_residual) {
+            Ok(_) => self.utf8_residual.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let rest = self.utf8_residual.split_off(valid_up_to);
+        let text =
+            String::from_utf8(std::mem::replace(&mut self.utf8_residual, rest)).unwrap_or_default();
+
+        let complete = take_complete_lines(&mut self.line_residual, &text);
+        let mut out = String::new();
+        for line in complete.lines() {
+            self.push_line(line, &mut out);
+        }
+        out
+    }
+
+    fn finish(&mut self) -> String {
+        let mut out = String::new();
+        let tail = std::mem::take(&mut self.line_residual);
+        if !tail.is_empty() {
+            self.push_line(&tail, &mut out);
+        }
+        self.end_event(&mut out);
+        out
+    }
+
+    fn push_line(&mut self, line: &str, out: &mut String) {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() {
+            self.end_event(out);
+        } else if let Some(payload) = line.strip_prefix("data:") {
+            self.data
+                .push(payload.strip_prefix(' ').unwrap_or(payload).to_string());
+        }
+        // Comments (`:`), `event:`, `id:` and `retry:` have no JSON-lines form.
+    }
+
+    fn end_event(&mut self, out: &mut String) {
+        if self.data.is_empty() {
+            return;
+        }
+        let payload = self.data.join("\n");
+        self.data.clear();
+        if payload.trim() == "[DONE]" {
+            return;
+        }
+        match serde_json::from_str::<serde_json::Value>(&payload) {
+            // Re-serialized so each record is guaranteed to be one line.
+            Ok(value) => out.push_str(&value.to_string()),
+            Err(_) => out.push_str(&serde_json::Value::String(payload).to_string()),
+        }
+        out.push('\n');
+    }
+}
+
+/// Convert the tee's SSE chunks into JSON lines for the client.
+async fn reframe_chunks(mut rx: mpsc::Receiver<ChunkResult>, tx: mpsc::Sender<ChunkResult>) {
+    let mut reframer = JsonLinesReframer::default();
+    while let Some(next) = rx.recv().await {
+        match next {
+            Ok(bytes) => {
+                let out = reframer.push(&bytes);
+                if !out.is_empty() && tx.send(Ok(Bytes::from(out))).await.is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        }
+    }
+    let out = reframer.finish();
+    if !out.is_empty() {
+        let _ = tx.send(Ok(Bytes::from(out))).await;
+    }
+}
+
+/// Build the client body from the tee channel, inserting the reframing and
+/// coalescing stages when the token asks for them.
+fn client_body(
+    rx: mpsc::Receiver<ChunkResult>,
+    flush: StreamFlushConfig,
+    format: StreamOutputFormat,
+) -> Body {
+    let rx = match format {
+        StreamOutputFormat::OpenAiSse => rx,
+        StreamOutputFormat::Ndjson | StreamOutputFormat::JsonLines => {
+            let (out_tx, out_rx) = mpsc::channel::<ChunkResult>(1024);
+            spawn_logged!(reframe_chunks(rx, out_tx));
+            out_rx
+        }
+    };
+    if flush.is_immediate() {
+        return Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx));
+    }
+    let (out_tx, out_rx) = mpsc::channel::<ChunkResult>(1024);
+    spawn_logged!(coalesce_chunks(rx, out_tx, flush));
+    Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(out_rx))
+}
+
+/// Batch chunks from `rx` into `tx` according to `flush`.
+///
+/// Returns when the input closes or the client goes away. Dropping `rx` on
+/// client disconnect makes the tee task mark the client gone and keep reading
+/// upstream for billing, same as the unbuffered path.
+async fn coalesce_chunks(
+    mut rx: mpsc::Receiver<ChunkResult>,
+    tx: mpsc::Sender<ChunkResult>,
+    flush: StreamFlushConfig,
+) {
+    let max_delay = std::time::Duration::from_millis(flush.max_delay_ms);
+    let mut buf = BytesMut::new();
+    let mut deadline: Option<tokio::time::Instant> = None;
+    let mut first = true;
+
+    loop {
+        let next = match deadline {
+            Some(at) => match tokio::time::timeout_at(at, rx.recv()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    // Delay budget exhausted — write whatever we have.
+                    deadline = None;
+                    if tx.send(Ok(buf.split().freeze())).await.is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+
+        match next {
+            Some(Ok(bytes)) => {
+                let force = first || contains_done_marker(&bytes);
+                first = false;
+                buf.extend_from_slice(&bytes);
+                if force || buf.len() >= flush.max_bytes {
+                    deadline = None;
+                    if tx.send(Ok(buf.split().freeze())).await.is_err() {
+                        return;
+                    }
+                } else if deadline.is_none() {
+                    deadline = Some(tokio::time::Instant::now() + max_delay);
+                }
+            }
+            Some(Err(e)) => {
+                if !buf.is_empty() {
+                    let _ = tx.send(Ok(buf.split().freeze())).await;
+                }
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+            None => {
+                if !buf.is_empty() {
+                    let _ = tx.send(Ok(buf.split().freeze())).await;
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Prepend the `: ttft=<ms>ms` SSE comment to the first chunk sent to the
+/// client. Comment lines are ignored by SSE parsers, so clients that don't
+/// look for it are unaffected.
+fn with_ttft_comment(bytes: Bytes, pending: &mut Option<u64>) -> Bytes {
+    match pending.take() {
+        Some(ms) => {
+            let comment = format!(": ttft={}ms\n\n", ms);
+            let mut buf = BytesMut::with_capacity(comment.len() + bytes.len());
+            buf.extend_from_slice(comment.as_bytes());
+            buf.extend_from_slice(&bytes);
+            buf.freeze()
+        }
+        None => bytes,
+    }
+}
+
+/// Append `chunk` to `pending` and return everything up to and including the
+/// last newline. The trailing partial line stays in `pending` until the next
+/// read completes it, so an SSE line split across TCP chunks (typically the
+/// large final usage chunk) is parsed whole instead of being dropped.
+fn take_complete_lines(pending: &mut String, chunk: &str) -> String {
+    pending.push_str(chunk);
+    match pending.rfind('\n') {
+        Some(i) => {
+            let rest = pending.split_off(i + 1);
+            std::mem::replace(pending, rest)
+        }
+        None => String::new(),
+    }
+}
+
+fn contains_done_marker(bytes: &[u8]) -> bool {
+    bytes.windows(6).any(|w| w == b"[DONE]")
+}
+
+/// Terminal events sent when the upstream stream fails after headers went
+/// out: an OpenAI-format error event followed by `[DONE]`, so clients can
+/// tell a failed stream from a complete one instead of seeing truncation.
+fn stream_error_events(message: &str) -> Bytes {
+    let event = serde_json::json!({
+        "error": {
+            "message": message,
+            "type": "upstream_error",
+            "code": "stream_interrupted",
+        }
+    });
+    Bytes::from(format!("data: {}\n\ndata: [DONE]\n\n", event))
+}
+
+/// Finalize the accumulator once the upstream read loop ends without a
+/// `[DONE]` marker. A transport or stream error means the stream was cut off:
+/// the client (if still connected) gets the terminal error events and the
+/// result carries `stream_error`. A clean EOF is a normal end, even without a
+/// finish reason: some OpenAI-compatible servers never send one.
+async fn finish_stream(
+    acc: &mut StreamAccumulator,
+    upstream_error: Option<String>,
+    tx: &mpsc::Sender<ChunkResult>,
+    client_gone: bool,
+) -> StreamResult {
+    let mut result = std::mem::replace(acc, StreamAccumulator::new()).finish();
+    if let Some(ref message) = upstream_error {
+        tracing::warn!(
+            partial_content_len = result.content.len(),
+            "Upstream stream interrupted: {}",
+            message
+        );
+        if !client_gone {
+            let _ = tx.send(Ok(stream_error_events(message))).await;
+        }
+    }
+    result.stream_error = upstream_error;
+    result
+}
+
 /// Tee an upstream SSE response into two consumers:
 /// - An [`axum::body::Body`] that streams bytes directly to the HTTP client
 /// - A [`StreamResultSlot`] that resolves with accumulated usage/tool-call data
 ///
-/// The `start` instant is used to compute TTFT (time-to-first-token).
+/// The `start` instant is used to compute TTFT (time-to-first-token). With
+/// `ttft_comment` set, the first bytes sent to the client are preceded by a
+/// `: ttft=<ms>ms` SSE comment. `format` reframes the client copy only.
 ///
 /// # Usage
 /// ```ignore
-/// let (body, result_slot) = tee_sse_stream(upstream_resp, Instant::now());
+/// let (body, result_slot, notify) =
+///     tee_sse_stream(upstream_resp, Instant::now(), StreamFlushConfig::default(), false, StreamOutputFormat::OpenAiSse);
 /// // Send body to client immediately
 /// let response = Response::builder().body(body).unwrap();
 /// // Later (in a spawned task), read the result for audit/cost
@@ -53,6 +404,9 @@ pub type StreamResultSlot = Arc<Mutex<Option<StreamResult>>>;
 pub fn tee_sse_stream(
     upstream_resp: reqwest::Response,
     start: Instant,
+    flush: StreamFlushConfig,
+    ttft_comment: bool,
+    format: StreamOutputFormat,
 ) -> (Body, StreamResultSlot, Arc<Notify>) {
     let result_slot: StreamResultSlot = Arc::new(Mutex::new(None));
     let slot_for_bg = result_slot.clone();
@@ -62,10 +416,12 @@ pub fn tee_sse_stream(
     let accumulator = Arc::new(Mutex::new(StreamAccumulator::new_with_start(start)));
     let mut byte_stream = upstream_resp.bytes_stream();
 
-    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(1024);
+    let (tx, rx) = mpsc::channel::<ChunkResult>(1024);
 
     spawn_logged!(async move {
         let mut first = true;
+        // TTFT awaiting the opt-in `: ttft=<ms>ms` comment on the first send.
+        let mut pending_ttft: Option<u64> = None;
         // 5A-1 FIX: Track whether the client has disconnected. When true, we
         // continue reading the upstream to capture the usage/cost data from
         // the final SSE chunk, but skip sending bytes to the (gone) client.
@@ -74,6 +430,9 @@ pub fn tee_sse_stream(
         // SSE is text-based, so a multi-byte char can be sliced at a chunk boundary.
         // We hold trailing incomplete bytes and prepend them to the next chunk.
         let mut utf8_residual: Vec<u8> = Vec::new();
+        // Incomplete trailing SSE line, held back from the accumulator only.
+        let mut line_residual = String::new();
+        let mut upstream_error: Option<String> = None;
 
         while let Some(chunk_result) = byte_stream.next().await {
             match chunk_result {
@@ -83,7 +442,11 @@ pub fn tee_sse_stream(
                     // Record TTFT on the very first data chunk
                     if first {
                         first = false;
-                        acc_guard.set_ttft_ms(start.elapsed().as_millis() as u64);
+                        let ttft_ms = start.elapsed().as_millis() as u64;
+                        acc_guard.set_ttft_ms(ttft_ms);
+                        if ttft_comment {
+                            pending_ttft = Some(ttft_ms);
+                        }
                     }
 
                     // FIX #1: Avoid unnecessary allocation when no residual.
@@ -118,9 +481,10 @@ pub fn tee_sse_stream(
                     utf8_residual = leftover.to_vec();
                     let _ = &combined_owned; // keep alive until after leftover is copied
 
-                    // Feed each SSE line to the accumulator
+                    // Feed each complete SSE line to the accumulator
                     let mut done = false;
-                    for line in valid_str.lines() {
+                    let complete = take_complete_lines(&mut line_residual, valid_str);
+                    for line in complete.lines() {
                         if acc_guard.push_sse_line(line) {
                             done = true;
                         }
@@ -156,6 +520,7 @@ pub fn tee_sse_stream(
                     // 5A-1 FIX: Send to client unless they've disconnected.
                     // On disconnect, set client_gone and CONTINUE reading
                     // upstream so we capture the final usage/cost chunk.
+                    let send_bytes = with_ttft_comment(send_bytes, &mut pending_ttft);
                     if !client_gone && tx.send(Ok(send_bytes)).await.is_err() {
                         client_gone = true;
                         tracing::debug!(
@@ -171,47 +536,30 @@ pub fn tee_sse_stream(
                     }
                 }
                 Err(e) => {
-                    // Emit a structured SSE error event so SSE clients receive a
-                    // parseable error payload instead of a silent TCP reset.
-                    let sse_error = format!(
-                        "data: {{\"error\":{{\"message\":\"upstream connection lost: {}\",\"type\":\"stream_error\"}}}}\n\n",
-                        e.to_string().replace('"', "'")
-                    );
-                    let _ = tx.send(Ok(Bytes::from(sse_error))).await;
-
-                    // Populate the slot with partial results so audit logging still
-                    // captures whatever tokens/content arrived before the drop.
-                    {
-                        let mut slot_guard = slot_for_bg.lock().await;
-                        if slot_guard.is_none() {
-                            let mut acc_guard = accumulator.lock().await;
-                            let partial =
-                                std::mem::replace(&mut *acc_guard, StreamAccumulator::new());
-                            *slot_guard = Some(partial.finish());
-                        }
-                    }
-                    notify_bg.notify_waiters();
-
-                    let io_err = std::io::Error::new(std::io::ErrorKind::BrokenPipe, e.to_string());
-                    let _ = tx.send(Err(io_err)).await;
+                    // The body ends with a structured error event (below)
+                    // rather than a transport error, so the client can parse
+                    // it and audit still captures the partial content.
+                    upstream_error = Some(format!("upstream connection lost: {}", e));
                     break;
                 }
             }
         }
 
-        // EOF or client disconnect: ensure result_slot is populated if not already
+        // EOF, upstream error or client disconnect: ensure result_slot is populated
         let mut slot_guard = slot_for_bg.lock().await;
         if slot_guard.is_none() {
             let mut acc_guard = accumulator.lock().await;
-            let finished_acc = std::mem::replace(&mut *acc_guard, StreamAccumulator::new());
-            *slot_guard = Some(finished_acc.finish());
+            // Upstream closed without a trailing newline.
+            if !line_residual.is_empty() {
+                acc_guard.push_sse_line(&line_residual);
+            }
+            let result = finish_stream(&mut acc_guard, upstream_error, &tx, client_gone).await;
+            *slot_guard = Some(result);
         }
         notify_bg.notify_waiters();
     });
 
-    let mapped = tokio_stream::wrappers::ReceiverStream::new(rx);
-    let body = Body::from_stream(mapped);
-    (body, result_slot, notify)
+    (client_body(rx, flush, format), result_slot, notify)
 }
 
 /// FIX(X2/X3): Tee an SSE stream with per-chunk translation.
@@ -224,14 +572,20 @@ pub fn tee_sse_stream(
 ///
 /// The StreamAccumulator receives the **translated** SSE lines so that
 /// token/cost extraction works correctly (it expects OpenAI format).
+/// Only complete lines are translated; a partial line is held until the
+/// next read. `translate_fn` may carry state across chunks (e.g. Anthropic's
+/// `message_start` prompt token count).
 pub fn tee_translating_sse_stream<F>(
     upstream_resp: reqwest::Response,
     start: Instant,
     model: String,
-    translate_fn: F,
+    mut translate_fn: F,
+    flush: StreamFlushConfig,
+    ttft_comment: bool,
+    format: StreamOutputFormat,
 ) -> (Body, StreamResultSlot, Arc<Notify>)
 where
-    F: Fn(&[u8], &str) -> Vec<u8> + Send + 'static,
+    F: FnMut(&[u8], &str) -> Vec<u8> + Send + 'static,
 {
     let result_slot: StreamResultSlot = Arc::new(Mutex::new(None));
     let slot_for_bg = result_slot.clone();
@@ -241,13 +595,17 @@ where
     let accumulator = Arc::new(Mutex::new(StreamAccumulator::new_with_start(start)));
     let mut byte_stream = upstream_resp.bytes_stream();
 
-    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(1024);
+    let (tx, rx) = mpsc::channel::<ChunkResult>(1024);
 
     spawn_logged!(async move {
         let mut first = true;
+        // TTFT awaiting the opt-in `: ttft=<ms>ms` comment on the first send.
+        let mut pending_ttft: Option<u64> = None;
         // 5A-1 FIX: Continue reading upstream after client disconnect for billing.
         let mut client_gone = false;
         let mut utf8_residual: Vec<u8> = Vec::new();
+        let mut line_residual = String::new();
+        let mut upstream_error: Option<String> = None;
 
         while let Some(chunk_result) = byte_stream.next().await {
             match chunk_result {
@@ -257,7 +615,11 @@ where
                     // Record TTFT on first data chunk
                     if first {
                         first = false;
-                        acc_guard.set_ttft_ms(start.elapsed().as_millis() as u64);
+                        let ttft_ms = start.elapsed().as_millis() as u64;
+                        acc_guard.set_ttft_ms(ttft_ms);
+                        if ttft_comment {
+                            pending_ttft = Some(ttft_ms);
+                        }
                     }
 
                     // FIX #1: Avoid unnecessary allocation when no residual.
@@ -283,13 +645,16 @@ where
                     utf8_residual = leftover.to_vec();
                     let _ = &combined_owned; // keep alive
 
-                    if valid_bytes.is_empty() {
+                    // SAFETY: validated as UTF-8 above
+                    let valid_str = unsafe { std::str::from_utf8_unchecked(valid_bytes) };
+                    let complete = take_complete_lines(&mut line_residual, valid_str);
+                    if complete.is_empty() {
                         drop(acc_guard);
                         continue;
                     }
 
                     // Translate provider SSE → OpenAI SSE
-                    let translated = translate_fn(valid_bytes, &model);
+                    let translated = translate_fn(complete.as_bytes(), &model);
 
                     // Feed translated SSE to accumulator
                     if let Ok(translated_str) = std::str::from_utf8(&translated) {
@@ -328,6 +693,7 @@ where
                     };
 
                     // 5A-1 FIX: Send translated bytes to client unless disconnected.
+                    let send_bytes = with_ttft_comment(send_bytes, &mut pending_ttft);
                     if !client_gone && tx.send(Ok(send_bytes)).await.is_err() {
                         client_gone = true;
                         tracing::debug!("Client disconnected — continuing upstream read for billing (translated)");
@@ -340,43 +706,33 @@ where
                     }
                 }
                 Err(e) => {
-                    let sse_error = format!(
-                        "data: {{\"error\":{{\"message\":\"upstream connection lost: {}\",\"type\":\"stream_error\"}}}}\n\n",
-                        e.to_string().replace('"', "'")
-                    );
-                    let _ = tx.send(Ok(Bytes::from(sse_error))).await;
-
-                    {
-                        let mut slot_guard = slot_for_bg.lock().await;
-                        if slot_guard.is_none() {
-                            let mut acc_guard = accumulator.lock().await;
-                            let partial =
-                                std::mem::replace(&mut *acc_guard, StreamAccumulator::new());
-                            *slot_guard = Some(partial.finish());
-                        }
-                    }
-                    notify_bg.notify_waiters();
-
-                    let io_err = std::io::Error::new(std::io::ErrorKind::BrokenPipe, e.to_string());
-                    let _ = tx.send(Err(io_err)).await;
+                    upstream_error = Some(format!("upstream connection lost: {}", e));
                     break;
                 }
             }
         }
 
-        // EOF: ensure result_slot is populated
+        // EOF or upstream error: ensure result_slot is populated
         let mut slot_guard = slot_for_bg.lock().await;
         if slot_guard.is_none() {
             let mut acc_guard = accumulator.lock().await;
-            let finished_acc = std::mem::replace(&mut *acc_guard, StreamAccumulator::new());
-            *slot_guard = Some(finished_acc.finish());
+            // Upstream closed without a trailing newline.
+            if !line_residual.is_empty() {
+                let tail = translate_fn(line_residual.as_bytes(), &model);
+                for line in String::from_utf8_lossy(&tail).lines() {
+                    acc_guard.push_sse_line(line);
+                }
+                if !client_gone {
+                    let _ = tx.send(Ok(Bytes::from(tail))).await;
+                }
+            }
+            let result = finish_stream(&mut acc_guard, upstream_error, &tx, client_gone).await;
+            *slot_guard = Some(result);
         }
         notify_bg.notify_waiters();
     });
 
-    let mapped = tokio_stream::wrappers::ReceiverStream::new(rx);
-    let body = Body::from_stream(mapped);
-    (body, result_slot, notify)
+    (client_body(rx, flush, format), result_slot, notify)
 }
 
 /// Wait for the stream result to be populated, with a timeout.
@@ -417,12 +773,16 @@ pub async fn wait_for_stream_result(
 /// 1. Buffer incoming binary chunks (frames can span TCP boundaries)
 /// 2. Decode complete binary frames using `decode_bedrock_event_stream`
 /// 3. Translate each Bedrock event to an OpenAI `chat.completion.chunk` SSE line
+///    (both via `BedrockStreamTranslator`, shared with the buffered path)
 /// 4. Pipe the translated SSE text to the client
 /// 5. Feed SSE lines to StreamAccumulator for cost/audit tracking
 pub fn tee_bedrock_stream(
     upstream_resp: reqwest::Response,
     start: Instant,
     model: String,
+    flush: StreamFlushConfig,
+    ttft_comment: bool,
+    format: StreamOutputFormat,
 ) -> (Body, StreamResultSlot, Arc<Notify>) {
     let result_slot: StreamResultSlot = Arc::new(Mutex::new(None));
     let slot_for_bg = result_slot.clone();
@@ -432,16 +792,18 @@ pub fn tee_bedrock_stream(
     let accumulator = Arc::new(Mutex::new(StreamAccumulator::new_with_start(start)));
     let mut byte_stream = upstream_resp.bytes_stream();
 
-    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(1024);
+    let (tx, rx) = mpsc::channel::<ChunkResult>(1024);
 
     spawn_logged!(async move {
         let mut first = true;
+        // TTFT awaiting the opt-in `: ttft=<ms>ms` comment on the first send.
+        let mut pending_ttft: Option<u64> = None;
         // 5A-1 FIX: Continue reading upstream after client disconnect for billing.
         let mut client_gone = false;
-        // Buffer for accumulating binary data across TCP chunks.
-        // Bedrock binary frames can be split across chunk boundaries.
-        let mut binary_buffer: Vec<u8> = Vec::new();
-        let chunk_id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+        // Frames can be split across TCP reads; the translator buffers
+        // partial frames and only decodes complete ones.
+        let mut translator = BedrockStreamTranslator::new(&model);
+        let mut upstream_error: Option<String> = None;
 
         while let Some(chunk_result) = byte_stream.next().await {
             match chunk_result {
@@ -450,190 +812,14 @@ pub fn tee_bedrock_stream(
 
                     if first {
                         first = false;
-                        acc_guard.set_ttft_ms(start.elapsed().as_millis() as u64);
-                    }
-
-                    // Append new bytes to the binary buffer
-                    binary_buffer.extend_from_slice(&bytes);
-
-                    // Try to decode complete frames from the buffer
-                    let mut sse_output = String::new();
-                    let mut consumed = 0;
-
-                    while consumed + 12 <= binary_buffer.len() {
-                        let remaining = &binary_buffer[consumed..];
-                        if remaining.len() < 4 {
-                            break;
-                        }
-                        let total_length = u32::from_be_bytes([
-                            remaining[0],
-                            remaining[1],
-                            remaining[2],
-                            remaining[3],
-                        ]) as usize;
-
-                        if total_length < 16 || remaining.len() < total_length {
-                            break; // Incomplete frame, wait for more data
-                        }
-
-                        // Decode this single complete frame
-                        let frame_bytes = &remaining[..total_length];
-                        let events =
-                            crate::proxy::model_router::decode_bedrock_event_stream(frame_bytes);
-
-                        for (event_type, payload) in &events {
-                            match event_type.as_str() {
-                                "messageStart" => {
-                                    let role = payload
-                                        .get("role")
-                                        .and_then(|r| r.as_str())
-                                        .unwrap_or("assistant");
-                                    sse_output.push_str(
-                                        &crate::proxy::model_router::openai_sse_chunk(
-                                            &chunk_id,
-                                            &model,
-                                            serde_json::json!({"role": role, "content": ""}),
-                                            None,
-                                        ),
-                                    );
-                                }
-                                "contentBlockStart" => {
-                                    if let Some(start) = payload.get("start") {
-                                        if let Some(tool_use) = start.get("toolUse") {
-                                            let index = payload
-                                                .get("contentBlockIndex")
-                                                .and_then(|i| i.as_u64())
-                                                .unwrap_or(0);
-                                            let name = tool_use
-                                                .get("name")
-                                                .and_then(|n| n.as_str())
-                                                .unwrap_or("");
-                                            let tool_id = tool_use
-                                                .get("toolUseId")
-                                                .and_then(|id| id.as_str())
-                                                .unwrap_or("");
-                                            sse_output.push_str(
-                                                &crate::proxy::model_router::openai_sse_chunk(
-                                                    &chunk_id,
-                                                    &model,
-                                                    serde_json::json!({"tool_calls": [{
-                                                        "index": index,
-                                                        "id": tool_id,
-                                                        "type": "function",
-                                                        "function": {"name": name, "arguments": ""}
-                                                    }]}),
-                                                    None,
-                                                ),
-                                            );
-                                        }
-                                    }
-                                }
-                                "contentBlockDelta" => {
-                                    if let Some(delta) = payload.get("delta") {
-                                        if let Some(text) =
-                                            delta.get("text").and_then(|t| t.as_str())
-                                        {
-                                            sse_output.push_str(
-                                                &crate::proxy::model_router::openai_sse_chunk(
-                                                    &chunk_id,
-                                                    &model,
-                                                    serde_json::json!({"content": text}),
-                                                    None,
-                                                ),
-                                            );
-                                        }
-                                        if let Some(input) =
-                                            delta.get("toolUse").and_then(|tu| tu.get("input"))
-                                        {
-                                            let input_str = if input.is_string() {
-                                                input.as_str().unwrap_or("").to_string()
-                                            } else {
-                                                serde_json::to_string(input).unwrap_or_default()
-                                            };
-                                            let index = payload
-                                                .get("contentBlockIndex")
-                                                .and_then(|i| i.as_u64())
-                                                .unwrap_or(0);
-                                            sse_output.push_str(
-                                                &crate::proxy::model_router::openai_sse_chunk(
-                                                    &chunk_id,
-                                                    &model,
-                                                    serde_json::json!({"tool_calls": [{
-                                                        "index": index,
-                                                        "function": {"arguments": input_str}
-                                                    }]}),
-                                                    None,
-                                                ),
-                                            );
-                                        }
-                                    }
-                                }
-                                "messageStop" => {
-                                    let finish =
-                                        match payload.get("stopReason").and_then(|s| s.as_str()) {
-                                            Some("end_turn") => "stop",
-                                            Some("tool_use") => "tool_calls",
-                                            Some("max_tokens") => "length",
-                                            Some("stop_sequence") => "stop",
-                                            Some("content_filtered") => "content_filter",
-                                            _ => "stop",
-                                        };
-                                    sse_output.push_str(
-                                        &crate::proxy::model_router::openai_sse_chunk(
-                                            &chunk_id,
-                                            &model,
-                                            serde_json::json!({}),
-                                            Some(finish),
-                                        ),
-                                    );
-                                    sse_output.push_str("data: [DONE]\n\n");
-                                }
-                                "metadata" => {
-                                    // Bedrock sends usage in the metadata event:
-                                    // {"usage": {"inputTokens": N, "outputTokens": M}}
-                                    // Emit as an OpenAI-format usage chunk so the
-                                    // StreamAccumulator captures it for cost tracking.
-                                    if let Some(usage) = payload.get("usage") {
-                                        let input = usage
-                                            .get("inputTokens")
-                                            .and_then(|v| v.as_u64())
-                                            .unwrap_or(0);
-                                        let output_t = usage
-                                            .get("outputTokens")
-                                            .and_then(|v| v.as_u64())
-                                            .unwrap_or(0);
-                                        if input > 0 || output_t > 0 {
-                                            let usage_chunk = serde_json::json!({
-                                                "id": chunk_id,
-                                                "object": "chat.completion.chunk",
-                                                "created": chrono::Utc::now().timestamp(),
-                                                "model": model,
-                                                "choices": [],
-                                                "usage": {
-                                                    "prompt_tokens": input,
-                                                    "completion_tokens": output_t,
-                                                    "total_tokens": input + output_t,
-                                                }
-                                            });
-                                            sse_output.push_str(&format!(
-                                                "data: {}\n\n",
-                                                serde_json::to_string(&usage_chunk)
-                                                    .unwrap_or_default()
-                                            ));
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            }
+                        let ttft_ms = start.elapsed().as_millis() as u64;
+                        acc_guard.set_ttft_ms(ttft_ms);
+                        if ttft_comment {
+                            pending_ttft = Some(ttft_ms);
                         }
-
-                        consumed += total_length;
                     }
 
-                    // Remove consumed bytes from buffer
-                    if consumed > 0 {
-                        binary_buffer.drain(..consumed);
-                    }
+                    let sse_output = translator.push(&bytes);
 
                     // Feed translated SSE lines to accumulator
                     if !sse_output.is_empty() {
@@ -665,6 +851,7 @@ pub fn tee_bedrock_stream(
                         };
 
                         // 5A-1 FIX: Send translated SSE to client unless disconnected.
+                        let send_bytes = with_ttft_comment(send_bytes, &mut pending_ttft);
                         if !client_gone && tx.send(Ok(send_bytes)).await.is_err() {
                             client_gone = true;
                             tracing::debug!("Client disconnected — continuing upstream read for billing (bedrock)");
@@ -680,41 +867,416 @@ pub fn tee_bedrock_stream(
                     }
                 }
                 Err(e) => {
-                    let sse_error = format!(
-                        "data: {{\"error\":{{\"message\":\"Bedrock stream error: {}\",\"type\":\"stream_error\"}}}}\n\n",
-                        e.to_string().replace('"', "'")
-                    );
-                    let _ = tx.send(Ok(Bytes::from(sse_error))).await;
-
-                    {
-                        let mut slot_guard = slot_for_bg.lock().await;
-                        if slot_guard.is_none() {
-                            let mut acc_guard = accumulator.lock().await;
-                            let partial =
-                                std::mem::replace(&mut *acc_guard, StreamAccumulator::new());
-                            *slot_guard = Some(partial.finish());
-                        }
-                    }
-                    notify_bg.notify_waiters();
-
-                    let io_err = std::io::Error::new(std::io::ErrorKind::BrokenPipe, e.to_string());
-                    let _ = tx.send(Err(io_err)).await;
+                    upstream_error = Some(format!("Bedrock stream error: {}", e));
                     break;
                 }
             }
         }
 
-        // EOF: ensure result_slot is populated
+        // EOF or upstream error: ensure result_slot is populated. A failed
+        // stream gets the error events (which carry their own [DONE]); one
+        // that ended without the trailing metadata event still needs [DONE].
+        // No-op if [DONE] was already emitted.
         let mut slot_guard = slot_for_bg.lock().await;
         if slot_guard.is_none() {
             let mut acc_guard = accumulator.lock().await;
-            let finished = std::mem::replace(&mut *acc_guard, StreamAccumulator::new());
-            *slot_guard = Some(finished.finish());
+            let result = finish_stream(&mut acc_guard, upstream_error, &tx, client_gone).await;
+            if result.stream_error.is_none() && !client_gone {
+                let tail = translator.finish();
+                if !tail.is_empty() {
+                    let _ = tx.send(Ok(Bytes::from(tail))).await;
+                }
+            }
+            *slot_guard = Some(result);
         }
         notify_bg.notify_waiters();
     });
 
-    let mapped = tokio_stream::wrappers::ReceiverStream::new(rx);
-    let body = Body::from_stream(mapped);
-    (body, result_slot, notify)
+    (client_body(rx, flush, format), result_slot, notify)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(max_bytes: usize, max_delay_ms: u64) -> StreamFlushConfig {
+        StreamFlushConfig {
+            max_bytes,
+            max_delay_ms,
+        }
+    }
+
+    async fn run_coalescer(flush: StreamFlushConfig, chunks: Vec<&'static str>) -> Vec<String> {
+        let (in_tx, in_rx) = mpsc::channel::<ChunkResult>(64);
+        let (out_tx, mut out_rx) = mpsc::channel::<ChunkResult>(64);
+        for c in chunks {
+            in_tx
+                .send(Ok(Bytes::from_static(c.as_bytes())))
+                .await
+                .unwrap();
+        }
+        drop(in_tx);
+        coalesce_chunks(in_rx, out_tx, flush).await;
+        let mut out = Vec::new();
+        while let Some(Ok(b)) = out_rx.recv().await {
+            out.push(String::from_utf8(b.to_vec()).unwrap());
+        }
+        out
+    }
+
+    #[test]
+    fn test_flush_config_defaults_to_immediate() {
+        assert!(StreamFlushConfig::default().is_immediate());
+        assert!(StreamFlushConfig::from_token_value(None).is_immediate());
+        let bad = serde_json::json!({"max_bytes": "lots"});
+        assert!(StreamFlushConfig::from_token_value(Some(&bad)).is_immediate());
+    }
+
+    #[test]
+    fn test_flush_config_parses_and_clamps() {
+        let v = serde_json::json!({"max_bytes": 10_000_000, "max_delay_ms": 60_000});
+        let c = StreamFlushConfig::from_token_value(Some(&v));
+        assert_eq!(c, cfg(MAX_FLUSH_BYTES, MAX_FLUSH_DELAY_MS));
+
+        let v = serde_json::json!({"max_bytes": 512});
+        let c = StreamFlushConfig::from_token_value(Some(&v));
+        assert_eq!(c, cfg(512, 20));
+        assert!(!c.is_immediate());
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_first_chunk_flushed_alone() {
+        let out = run_coalescer(
+            cfg(1024, 1_000),
+            vec!["data: a\n\n", "data: b\n\n", "data: c\n\n"],
+        )
+        .await;
+        assert_eq!(out, vec!["data: a\n\n", "data: b\n\ndata: c\n\n"]);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_flushes_on_size_threshold() {
+        let out = run_coalescer(cfg(8, 1_000), vec!["first", "1234", "5678", "9"]).await;
+        assert_eq!(out, vec!["first", "12345678", "9"]);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_flushes_on_done() {
+        let out = run_coalescer(
+            cfg(1024, 1_000),
+            vec!["data: a\n\n", "data: b\n\n", "data: [DONE]\n\n", "trailing"],
+        )
+        .await;
+        assert_eq!(
+            out,
+            vec!["data: a\n\n", "data: b\n\ndata: [DONE]\n\n", "trailing"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_flushes_after_delay() {
+        let (in_tx, in_rx) = mpsc::channel::<ChunkResult>(8);
+        let (out_tx, mut out_rx) = mpsc::channel::<ChunkResult>(8);
+        tokio::spawn(coalesce_chunks(in_rx, out_tx, cfg(1024, 10)));
+
+        in_tx.send(Ok(Bytes::from_static(b"first"))).await.unwrap();
+        in_tx.send(Ok(Bytes::from_static(b"tok"))).await.unwrap();
+        assert_eq!(&out_rx.recv().await.unwrap().unwrap()[..], b"first");
+        // Upstream stalls — buffered bytes must still go out within the delay.
+        let next = tokio::time::timeout(std::time::Duration::from_secs(2), out_rx.recv())
+            .await
+            .expect("buffered chunk not flushed on delay")
+            .unwrap()
+            .unwrap();
+        assert_eq!(&next[..], b"tok");
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_propagates_error_after_buffer() {
+        let (in_tx, in_rx) = mpsc::channel::<ChunkResult>(8);
+        let (out_tx, mut out_rx) = mpsc::channel::<ChunkResult>(8);
+        in_tx.send(Ok(Bytes::from_static(b"first"))).await.unwrap();
+        in_tx
+            .send(Ok(Bytes::from_static(b"partial")))
+            .await
+            .unwrap();
+        in_tx
+            .send(Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "gone",
+            )))
+            .await
+            .unwrap();
+        drop(in_tx);
+        coalesce_chunks(in_rx, out_tx, cfg(1024, 1_000)).await;
+
+        assert_eq!(&out_rx.recv().await.unwrap().unwrap()[..], b"first");
+        assert_eq!(&out_rx.recv().await.unwrap().unwrap()[..], b"partial");
+        assert!(out_rx.recv().await.unwrap().is_err());
+    }
+
+    #[test]
+    fn test_ttft_comment_prefixes_first_chunk_only() {
+        let mut pending = Some(142);
+        let first = with_ttft_comment(Bytes::from_static(b"data: {}\n\n"), &mut pending);
+        assert_eq!(&first[..], b": ttft=142ms\n\ndata: {}\n\n");
+        let second = with_ttft_comment(Bytes::from_static(b"data: [DONE]\n\n"), &mut pending);
+        assert_eq!(&second[..], b"data: [DONE]\n\n");
+    }
+
+    #[test]
+    fn test_ttft_comment_disabled_passes_through() {
+        let mut pending = None;
+        let out = with_ttft_comment(Bytes::from_static(b"data: {}\n\n"), &mut pending);
+        assert_eq!(&out[..], b"data: {}\n\n");
+    }
+
+    /// A streaming agent turn that is nothing but tool-call deltas, with the
+    /// usage chunk OpenAI sends after the finish chunk.
+    const TOOL_CALL_ONLY_STREAM: &str = concat!(
+        "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o-2024-08-06\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":null,\"tool_calls\":[{\"index\":0,\"id\":\"call_abc\",\"type\":\"function\",\"function\":{\"name\":\"search_docs\",\"arguments\":\"\"}}]},\"finish_reason\":null}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"query\\\":\"}}]},\"finish_reason\":null}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"rate limits\\\"}\"}}]},\"finish_reason\":null}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}],\"usage\":null}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"choices\":[],\"usage\":{\"prompt_tokens\":1200,\"completion_tokens\":85,\"total_tokens\":1285}}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    /// Build an upstream response whose body yields `chunks` and then, if
+    /// `fail` is set, a transport error.
+    fn upstream_response(chunks: Vec<&'static str>, fail: bool) -> reqwest::Response {
+        let mut items: Vec<Result<Bytes, std::io::Error>> = chunks
+            .into_iter()
+            .map(|c| Ok(Bytes::from_static(c.as_bytes())))
+            .collect();
+        if fail {
+            items.push(Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "connection reset by peer",
+            )));
+        }
+        let body = reqwest::Body::wrap_stream(futures::stream::iter(items));
+        reqwest::Response::from(axum::http::Response::new(body))
+    }
+
+    async fn run_tee(resp: reqwest::Response) -> (String, StreamResult) {
+        run_tee_as(resp, StreamOutputFormat::OpenAiSse).await
+    }
+
+    async fn run_tee_as(
+        resp: reqwest::Response,
+        format: StreamOutputFormat,
+    ) -> (String, StreamResult) {
+        let (body, slot, notify) = tee_sse_stream(
+            resp,
+            Instant::now(),
+            StreamFlushConfig::default(),
+            false,
+            format,
+        );
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .expect("client body must end cleanly, not with a transport error");
+        let result = wait_for_stream_result(&slot, &notify, std::time::Duration::from_secs(5))
+            .await
+            .unwrap();
+        (String::from_utf8(bytes.to_vec()).unwrap(), result)
+    }
+
+    const PARTIAL_CHUNKS: [&str; 2] = [
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+    ];
+
+    #[tokio::test]
+    async fn test_upstream_disconnect_mid_stream_appends_error_event() {
+        let (body, result) = run_tee(upstream_response(PARTIAL_CHUNKS.to_vec(), true)).await;
+
+        assert!(body.starts_with(PARTIAL_CHUNKS[0]));
+        let events: Vec<&str> = body
+            .split("\n\n")
+            .filter_map(|e| e.strip_prefix("data: "))
+            .collect();
+        assert_eq!(events.len(), 4, "{body}");
+        assert_eq!(events[3], "[DONE]");
+        let err: serde_json::Value = serde_json::from_str(events[2]).unwrap();
+        assert_eq!(err["error"]["type"], "upstream_error");
+        assert_eq!(err["error"]["code"], "stream_interrupted");
+        assert!(err["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("upstream connection lost"));
+
+        assert_eq!(result.content, "Hello");
+        assert!(result.stream_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clean_eof_without_finish_reason_is_not_an_error() {
+        // Upstream closed cleanly before any finish_reason or [DONE]: passed
+        // through as-is, since some servers never send a finish reason.
+        let (body, result) = run_tee(upstream_response(PARTIAL_CHUNKS.to_vec(), false)).await;
+        assert_eq!(body, PARTIAL_CHUNKS.concat());
+        assert_eq!(result.content, "Hello");
+        assert!(result.finish_reason.is_none());
+        assert!(result.stream_error.is_none());
+
+        let (body, result) = run_tee(upstream_response(vec![TOOL_CALL_ONLY_STREAM], false)).await;
+        assert_eq!(body, TOOL_CALL_ONLY_STREAM);
+        assert!(result.stream_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_include_usage_chunk_gives_exact_counts() {
+        let (_, result) = run_tee(upstream_response(vec![TOOL_CALL_ONLY_STREAM], false)).await;
+        assert_eq!(result.prompt_tokens, Some(1200));
+        assert_eq!(result.completion_tokens, Some(85));
+        // The reported usage wins over any estimate.
+        assert_eq!(
+            result.usage_or_estimate(|| unreachable!()),
+            (1200, 85, true)
+        );
+
+        // Client didn't opt in: no usage chunk, so fall back to estimates.
+        let mut chunks = PARTIAL_CHUNKS.to_vec();
+        chunks.push(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}],\"usage\":null}\n\ndata: [DONE]\n\n",
+        );
+        let (_, result) = run_tee(upstream_response(chunks, false)).await;
+        assert_eq!(result.prompt_tokens, None);
+        assert_eq!(result.completion_tokens, None);
+        assert_eq!(result.usage_or_estimate(|| 9), (9, 2, false));
+    }
+
+    #[test]
+    fn test_take_complete_lines_holds_partial_line() {
+        let mut pending = String::new();
+        assert_eq!(take_complete_lines(&mut pending, "data: {\"a\""), "");
+        assert_eq!(
+            take_complete_lines(&mut pending, ":1}\n\ndata: [DO"),
+            "data: {\"a\":1}\n\n"
+        );
+        assert_eq!(pending, "data: [DO");
+        assert_eq!(take_complete_lines(&mut pending, "NE]\n"), "data: [DONE]\n");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_tool_call_only_stream_is_costed_across_chunk_splits() {
+        // Split at every size that lands mid-line, including inside the
+        // final usage chunk.
+        for split in [1, 7, 64, 333] {
+            let mut acc = StreamAccumulator::new_with_start(Instant::now());
+            let mut pending = String::new();
+            let mut done = false;
+            let bytes = TOOL_CALL_ONLY_STREAM.as_bytes();
+            for chunk in bytes.chunks(split) {
+                let chunk = std::str::from_utf8(chunk).unwrap();
+                for line in take_complete_lines(&mut pending, chunk).lines() {
+                    done |= acc.push_sse_line(line);
+                }
+            }
+            assert!(done, "split={split}");
+            let result = acc.finish();
+
+            assert!(result.content.is_empty());
+            assert_eq!(result.tool_calls.len(), 1);
+            assert_eq!(result.tool_calls[0].name, "search_docs");
+            assert_eq!(result.finish_reason.as_deref(), Some("tool_calls"));
+            assert_eq!(result.prompt_tokens, Some(1200), "split={split}");
+            assert_eq!(result.completion_tokens, Some(85), "split={split}");
+
+            // gpt-4o: $2.50/$10.00 per 1M → 0.003 + 0.00085
+            let cost = crate::models::cost::calculate_cost(
+                "openai",
+                "gpt-4o-2024-08-06",
+                result.prompt_tokens.unwrap(),
+                result.completion_tokens.unwrap(),
+            );
+            assert_eq!(cost, rust_decimal::Decimal::new(385, 5), "split={split}");
+        }
+    }
+
+    /// Every line of a JSON-lines body is a standalone JSON value.
+    fn json_lines(body: &str) -> Vec<serde_json::Value> {
+        assert!(
+            body.ends_with('\n'),
+            "body must end with a newline: {body:?}"
+        );
+        body.lines()
+            .map(|l| serde_json::from_str(l).unwrap_or_else(|e| panic!("{l:?}: {e}")))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_output_format_ndjson_and_jsonlines_framing() {
+        // Events split mid-line and mid-event, with a comment and [DONE].
+        let chunks = vec![
+            ": keep-alive\n\ndata: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel",
+            "\"}}]}\n\ndata: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\\nthere\"},\"finish_reason\":\"stop\"}]}\n",
+            "\ndata: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2}}\r\n\r\ndata: [DONE]\n\n",
+        ];
+        for format in [StreamOutputFormat::Ndjson, StreamOutputFormat::JsonLines] {
+            let (body, result) = run_tee_as(upstream_response(chunks.clone(), false), format).await;
+            let lines = json_lines(&body);
+            assert_eq!(lines.len(), 3, "{body}");
+            assert_eq!(lines[0]["choices"][0]["delta"]["content"], "Hel");
+            assert_eq!(lines[1]["choices"][0]["delta"]["content"], "lo\nthere");
+            assert_eq!(lines[2]["usage"]["prompt_tokens"], 5);
+            assert!(!body.contains("[DONE]") && !body.contains("data:"));
+
+            // Accounting still sees the SSE stream.
+            assert_eq!(result.content, "Hello\nthere");
+            assert_eq!(result.prompt_tokens, Some(5));
+            assert_eq!(result.completion_tokens, Some(2));
+        }
+
+        // openai_sse is a byte-for-byte passthrough.
+        let (body, _) = run_tee_as(
+            upstream_response(chunks.clone(), false),
+            StreamOutputFormat::OpenAiSse,
+        )
+        .await;
+        assert_eq!(body, chunks.concat());
+    }
+
+    #[tokio::test]
+    async fn test_output_format_jsonlines_ends_with_error_line_on_disconnect() {
+        let (body, result) = run_tee_as(
+            upstream_response(PARTIAL_CHUNKS.to_vec(), true),
+            StreamOutputFormat::Ndjson,
+        )
+        .await;
+        let lines = json_lines(&body);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2]["error"]["code"], "stream_interrupted");
+        assert!(result.stream_error.is_some());
+    }
+
+    #[test]
+    fn test_output_format_names_and_content_types() {
+        for name in STREAM_OUTPUT_FORMATS {
+            assert!(StreamOutputFormat::from_name(name).is_some(), "{name}");
+        }
+        assert_eq!(
+            StreamOutputFormat::from_token_value(None),
+            StreamOutputFormat::OpenAiSse
+        );
+        assert_eq!(
+            StreamOutputFormat::from_token_value(Some("xml")),
+            StreamOutputFormat::OpenAiSse
+        );
+        assert_eq!(
+            StreamOutputFormat::OpenAiSse.content_type(),
+            "text/event-stream"
+        );
+        assert_eq!(
+            StreamOutputFormat::Ndjson.content_type(),
+            "application/x-ndjson"
+        );
+        assert_eq!(
+            StreamOutputFormat::JsonLines.content_type(),
+            "application/jsonl"
+        );
+    }
 }
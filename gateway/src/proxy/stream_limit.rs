@@ -0,0 +1,211 @@
+//! Caps on concurrent streaming responses.
+//!
+//! A streaming response holds an upstream connection and a client connection
+//! open for as long as the model keeps generating, so a burst of streams can
+//! exhaust sockets and memory long before any request-rate limit trips. Each
+//! stream takes a [`StreamPermit`] before the upstream call; the permit is
+//! held by the background task that waits for the stream bridge to finish and
+//! is released when it drops. A global cap (`TRUEFLOW_MAX_CONCURRENT_STREAMS`)
+//! and a per-token cap (`max_concurrent_streams`) are checked together.
+//!
+//! Counts are per gateway replica.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+/// Which cap rejected a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamLimitScope {
+    Global,
+    Token,
+}
+
+impl StreamLimitScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamLimitScope::Global => "global",
+            StreamLimitScope::Token => "token",
+        }
+    }
+}
+
+/// A cap that was full: its scope and its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamLimitHit {
+    pub scope: StreamLimitScope,
+    pub limit: u64,
+}
+
+#[derive(Default)]
+struct Counts {
+    global: u64,
+    per_token: HashMap<String, u64>,
+}
+
+#[derive(Default)]
+struct Inner {
+    counts: Mutex<Counts>,
+    released: Notify,
+}
+
+/// Active streaming responses, globally and per token.
+#[derive(Clone, Default)]
+pub struct StreamLimiter(Arc<Inner>);
+
+/// One active stream. Dropping it frees the slot.
+pub struct StreamPermit {
+    inner: Arc<Inner>,
+    token_id: String,
+}
+
+impl StreamLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a slot if neither cap is full. `None` leaves that cap unenforced.
+    pub fn try_acquire(
+        &self,
+        token_id: &str,
+        token_cap: Option<u64>,
+        global_cap: Option<u64>,
+    ) -> Result<StreamPermit, StreamLimitHit> {
+        let mut counts = self.0.counts.lock().unwrap();
+        if let Some(limit) = global_cap {
+            if counts.global >= limit {
+                return Err(StreamLimitHit {
+                    scope: StreamLimitScope::Global,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = token_cap {
+            if counts.per_token.get(token_id).copied().unwrap_or(0) >= limit {
+                return Err(StreamLimitHit {
+                    scope: StreamLimitScope::Token,
+                    limit,
+                });
+            }
+        }
+        counts.global += 1;
+        *counts.per_token.entry(token_id.to_string()).or_insert(0) += 1;
+        crate::middleware::metrics::set_active_streams(counts.global);
+        Ok(StreamPermit {
+            inner: self.0.clone(),
+            token_id: token_id.to_string(),
+        })
+    }
+
+    /// [`try_acquire`](Self::try_acquire), waiting up to `queue` for a slot
+    /// to free up. A zero `queue` doesn't wait.
+    pub async fn acquire(
+        &self,
+        token_id: &str,
+        token_cap: Option<u64>,
+        global_cap: Option<u64>,
+        queue: Duration,
+    ) -> Result<StreamPermit, StreamLimitHit> {
+        let deadline = tokio::time::Instant::now() + queue;
+        loop {
+            // Registered before the check so a release in between isn't missed.
+            let released = self.0.released.notified();
+            let hit = match self.try_acquire(token_id, token_cap, global_cap) {
+                Ok(permit) => return Ok(permit),
+                Err(hit) => hit,
+            };
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                return Err(hit);
+            }
+        }
+    }
+
+    /// Streams currently active across all tokens.
+    pub fn active(&self) -> u64 {
+        self.0.counts.lock().unwrap().global
+    }
+
+    /// Streams currently active per token.
+    pub fn active_by_token(&self) -> HashMap<String, u64> {
+        self.0.counts.lock().unwrap().per_token.clone()
+    }
+}
+
+impl Drop for StreamPermit {
+    fn drop(&mut self) {
+        let mut counts = self.inner.counts.lock().unwrap();
+        counts.global = counts.global.saturating_sub(1);
+        if let Some(n) = counts.per_token.get_mut(&self.token_id) {
+            *n -= 1;
+            if *n == 0 {
+                counts.per_token.remove(&self.token_id);
+            }
+        }
+        crate::middleware::metrics::set_active_streams(counts.global);
+        drop(counts);
+        self.inner.released.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caps_apply_and_permits_release() {
+        let limiter = StreamLimiter::new();
+        let a1 = limiter.try_acquire("a", Some(2), Some(3)).unwrap();
+        let _a2 = limiter.try_acquire("a", Some(2), Some(3)).unwrap();
+        assert_eq!(
+            limiter.try_acquire("a", Some(2), Some(3)).err(),
+            Some(StreamLimitHit {
+                scope: StreamLimitScope::Token,
+                limit: 2
+            })
+        );
+
+        let _b1 = limiter.try_acquire("b", None, Some(3)).unwrap();
+        assert_eq!(
+            limiter
+                .try_acquire("b", None, Some(3))
+                .err()
+                .map(|h| h.scope),
+            Some(StreamLimitScope::Global)
+        );
+        assert_eq!(limiter.active(), 3);
+
+        drop(a1);
+        assert_eq!(limiter.active(), 2);
+        assert_eq!(limiter.active_by_token().get("a"), Some(&1));
+        assert!(limiter.try_acquire("a", Some(2), Some(3)).is_ok());
+        assert!(limiter.try_acquire("c", None, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_gets_released_slot() {
+        let limiter = StreamLimiter::new();
+        let held = limiter.try_acquire("a", Some(1), None).unwrap();
+
+        let err = limiter
+            .acquire("a", Some(1), None, Duration::ZERO)
+            .await
+            .err();
+        assert_eq!(err.map(|h| h.scope), Some(StreamLimitScope::Token));
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                limiter
+                    .acquire("a", Some(1), None, Duration::from_secs(5))
+                    .await
+                    .is_ok()
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(held);
+        assert!(waiter.await.unwrap());
+        assert!(limiter.active_by_token().is_empty());
+    }
+}
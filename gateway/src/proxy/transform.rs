@@ -4,3 +4,419 @@
 pub fn rewrite_url(upstream_base: &str, original_path: &str) -> String {
     format!("{}{}", upstream_base.trim_end_matches('/'), original_path)
 }
+
+/// Fill a token's `param_defaults` into a request body for keys the client
+/// omitted (or sent as `null`). Client-supplied values are never replaced.
+/// Returns the keys that were filled, sorted, for the audit log.
+pub fn apply_param_defaults(
+    body: &mut serde_json::Value,
+    defaults: &serde_json::Value,
+) -> Vec<String> {
+    let (Some(obj), Some(defaults)) = (body.as_object_mut(), defaults.as_object()) else {
+        return Vec::new();
+    };
+    let mut applied = Vec::new();
+    for (key, value) in defaults {
+        if obj.get(key).is_none_or(|v| v.is_null()) {
+            obj.insert(key.clone(), value.clone());
+            applied.push(key.clone());
+        }
+    }
+    applied.sort();
+    applied
+}
+
+/// Remove a token's `strip_body_fields` from the top level of a request body.
+/// A body left empty stays `{}` rather than being dropped, so the upstream
+/// still receives valid JSON. Returns the fields actually removed.
+pub fn strip_body_fields(body: &mut serde_json::Value, fields: &[String]) -> Vec<String> {
+    let Some(obj) = body.as_object_mut() else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter(|f| obj.remove(f.as_str()).is_some())
+        .cloned()
+        .collect()
+}
+
+/// Body keys a token's `param_defaults` may not set: they decide routing,
+/// translation or response framing and must come from the client.
+pub const PARAM_DEFAULTS_RESERVED: &[&str] = &["model", "messages", "stream", "input", "prompt"];
+
+/// Request fields that cap output tokens, across the Chat Completions
+/// (`max_tokens`, `max_completion_tokens`) and Responses (`max_output_tokens`) APIs.
+pub const OUTPUT_LIMIT_FIELDS: &[&str] =
+    &["max_tokens", "max_completion_tokens", "max_output_tokens"];
+
+/// The field a token's output-token ceiling is injected as when the client
+/// sent none: `max_output_tokens` on the Responses API, `max_completion_tokens`
+/// for OpenAI (its reasoning models reject `max_tokens`), else `max_tokens`,
+/// which the provider translators map to each native field.
+pub fn output_limit_field(provider: super::model_router::Provider, path: &str) -> &'static str {
+    use super::model_router::Provider;
+    if path.trim_end_matches('/').ends_with("/responses") {
+        "max_output_tokens"
+    } else if matches!(provider, Provider::OpenAI | Provider::AzureOpenAI) {
+        "max_completion_tokens"
+    } else {
+        "max_tokens"
+    }
+}
+
+/// Enforce a token's `max_output_tokens_ceiling`. Output-limit fields above
+/// the ceiling are lowered to it; if the client sent none, `inject_field` is
+/// set to the ceiling. Returns the audit record, or `None` when the request
+/// was already within bounds.
+pub fn clamp_output_tokens(
+    body: &mut serde_json::Value,
+    ceiling: u64,
+    inject_field: &str,
+) -> Option<serde_json::Value> {
+    let obj = body.as_object_mut()?;
+    let mut clamped = serde_json::Map::new();
+    let mut present = false;
+    for &field in OUTPUT_LIMIT_FIELDS {
+        let Some(value) = obj.get_mut(field).filter(|v| !v.is_null()) else {
+            continue;
+        };
+        present = true;
+        if value.as_u64().is_some_and(|r| r <= ceiling) {
+            continue;
+        }
+        // Anything else is rewritten: an upstream may accept "100000" or
+        // 1e6, and one that ignores a malformed limit applies its own default.
+        // Numbers within the ceiling in another form become integers; the
+        // rest become the ceiling.
+        let within = match &*value {
+            serde_json::Value::Number(n) => n.as_f64(),
+            serde_json::Value::String(s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+        .filter(|r| r.is_finite() && *r >= 0.0 && *r <= ceiling as f64);
+        let requested = std::mem::replace(
+            value,
+            serde_json::json!(within.map_or(ceiling, |r| r as u64)),
+        );
+        clamped.insert(field.to_string(), requested);
+    }
+    if !present {
+        obj.insert(inject_field.to_string(), serde_json::json!(ceiling));
+        return Some(serde_json::json!({ "ceiling": ceiling, "injected": inject_field }));
+    }
+    (!clamped.is_empty()).then(|| serde_json::json!({ "ceiling": ceiling, "clamped": clamped }))
+}
+
+/// Set a field in a response body for `Action::InjectResponseField`.
+///
+/// The value is `from` (another response path, copied or moved when
+/// `remove_source` is set) if given, else the literal `value`. Paths are
+/// dot-separated; missing objects are created, numeric segments index arrays
+/// (the index equal to the length appends) and `*` targets every element.
+/// Returns how many locations were set.
+pub fn inject_response_field(
+    body: &mut serde_json::Value,
+    path: &str,
+    value: Option<&serde_json::Value>,
+    from: Option<&str>,
+    remove_source: bool,
+) -> usize {
+    let value = match from {
+        Some(src) => match get_path(body, src) {
+            Some(v) => v.clone(),
+            None => return 0,
+        },
+        None => match value {
+            Some(v) => v.clone(),
+            None => return 0,
+        },
+    };
+    let segs: Vec<&str> = path.split('.').filter(|s| !s.is_empty()).collect();
+    if segs.is_empty() {
+        return 0;
+    }
+    let set = set_path(body, &segs, &value);
+    // The source is only dropped once the value landed somewhere, and never
+    // when the target is the source itself or lies inside it.
+    if let (Some(src), true) = (from, remove_source && set > 0) {
+        let inside = path
+            .strip_prefix(src)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'));
+        if !inside {
+            remove_path(body, src);
+        }
+    }
+    set
+}
+
+fn get_path<'a>(body: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .filter(|s| !s.is_empty())
+        .try_fold(body, |cur, seg| match cur {
+            serde_json::Value::Array(arr) => seg.parse::<usize>().ok().and_then(|i| arr.get(i)),
+            _ => cur.get(seg),
+        })
+}
+
+fn remove_path(body: &mut serde_json::Value, path: &str) {
+    let Some((parent, leaf)) = path.rsplit_once('.') else {
+        if let Some(obj) = body.as_object_mut() {
+            obj.remove(path);
+        }
+        return;
+    };
+    let parent = parent.split('.').try_fold(body, |cur, seg| match cur {
+        serde_json::Value::Array(arr) => seg.parse::<usize>().ok().and_then(|i| arr.get_mut(i)),
+        _ => cur.get_mut(seg),
+    });
+    match parent {
+        Some(serde_json::Value::Object(obj)) => {
+            obj.remove(leaf);
+        }
+        Some(serde_json::Value::Array(arr)) => {
+            if let Ok(i) = leaf.parse::<usize>() {
+                if i < arr.len() {
+                    arr.remove(i);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn set_path(target: &mut serde_json::Value, segs: &[&str], value: &serde_json::Value) -> usize {
+    use serde_json::Value;
+
+    let Some((&seg, rest)) = segs.split_first() else {
+        *target = value.clone();
+        return 1;
+    };
+    // Only create containers where nothing exists yet; never clobber scalars.
+    if target.is_null() {
+        *target = if seg == "*" || seg.parse::<usize>().is_ok() {
+            Value::Array(Vec::new())
+        } else {
+            Value::Object(Default::default())
+        };
+    }
+    match target {
+        Value::Array(arr) if seg == "*" => arr.iter_mut().map(|el| set_path(el, rest, value)).sum(),
+        Value::Array(arr) => match seg.parse::<usize>() {
+            Ok(i) if i < arr.len() => set_path(&mut arr[i], rest, value),
+            Ok(i) if i == arr.len() => {
+                arr.push(Value::Null);
+                set_path(&mut arr[i], rest, value)
+            }
+            _ => 0,
+        },
+        Value::Object(obj) => set_path(obj.entry(seg).or_insert(Value::Null), rest, value),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_param_defaults_fill_only_absent_fields() {
+        let mut body = json!({
+            "model": "gpt-4o",
+            "temperature": 0.9,
+            "top_p": null,
+            "messages": []
+        });
+        let defaults = json!({"temperature": 0.2, "top_p": 0.5, "max_tokens": 1024});
+
+        let applied = apply_param_defaults(&mut body, &defaults);
+
+        assert_eq!(applied, vec!["max_tokens", "top_p"]);
+        assert_eq!(body["temperature"], 0.9, "client value must win");
+        assert_eq!(body["top_p"], 0.5);
+        assert_eq!(body["max_tokens"], 1024);
+    }
+
+    #[test]
+    fn test_strip_body_fields_removes_only_present_keys() {
+        let mut body = json!({"model": "gpt-4o", "x_internal_trace": "abc", "messages": []});
+        let fields = vec!["x_internal_trace".to_string(), "vendor_junk".to_string()];
+
+        let stripped = strip_body_fields(&mut body, &fields);
+
+        assert_eq!(stripped, vec!["x_internal_trace"]);
+        assert_eq!(body, json!({"model": "gpt-4o", "messages": []}));
+    }
+
+    #[test]
+    fn test_strip_body_fields_leaves_empty_object() {
+        let mut body = json!({"x_internal_trace": 1});
+        strip_body_fields(&mut body, &["x_internal_trace".to_string()]);
+        assert_eq!(body, json!({}));
+
+        let mut body = json!("not an object");
+        assert!(strip_body_fields(&mut body, &["x".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_clamp_output_tokens_lowers_and_injects() {
+        let mut body = json!({"model": "gpt-4o", "max_tokens": 8000, "max_completion_tokens": 100});
+        let record = clamp_output_tokens(&mut body, 1024, "max_tokens").unwrap();
+        assert_eq!(body["max_tokens"], 1024);
+        assert_eq!(
+            body["max_completion_tokens"], 100,
+            "values under the ceiling are kept"
+        );
+        assert_eq!(
+            record,
+            json!({"ceiling": 1024, "clamped": {"max_tokens": 8000}})
+        );
+
+        let mut body = json!({"model": "gpt-4o", "max_tokens": 512});
+        assert!(clamp_output_tokens(&mut body, 1024, "max_tokens").is_none());
+        assert_eq!(body["max_tokens"], 512);
+
+        let mut body = json!({"model": "gpt-4o", "max_tokens": null});
+        let record = clamp_output_tokens(&mut body, 256, "max_completion_tokens").unwrap();
+        assert_eq!(body["max_completion_tokens"], 256);
+        assert_eq!(
+            record,
+            json!({"ceiling": 256, "injected": "max_completion_tokens"})
+        );
+    }
+
+    #[test]
+    fn test_clamp_output_tokens_coerces_non_integer_limits() {
+        let mut body = json!({"max_tokens": "100000", "max_completion_tokens": 1e6});
+        let record = clamp_output_tokens(&mut body, 1024, "max_tokens").unwrap();
+        assert_eq!(body["max_tokens"], 1024);
+        assert_eq!(body["max_completion_tokens"], 1024);
+        assert_eq!(
+            record["clamped"],
+            json!({"max_tokens": "100000", "max_completion_tokens": 1e6})
+        );
+
+        let mut body = json!({"max_tokens": 100000.0, "max_output_tokens": "lots"});
+        clamp_output_tokens(&mut body, 1024, "max_tokens").unwrap();
+        assert_eq!(body["max_tokens"], 1024);
+        assert_eq!(body["max_output_tokens"], 1024);
+
+        // In range but not an integer: normalised, not raised to the ceiling.
+        let mut body = json!({"max_tokens": "512", "max_completion_tokens": 200.0});
+        let record = clamp_output_tokens(&mut body, 1024, "max_tokens").unwrap();
+        assert_eq!(body["max_tokens"], 512);
+        assert_eq!(body["max_completion_tokens"], 200);
+        assert!(body["max_tokens"].is_u64());
+        assert_eq!(record["clamped"]["max_tokens"], "512");
+
+        let mut body = json!({"max_tokens": -1});
+        clamp_output_tokens(&mut body, 1024, "max_tokens").unwrap();
+        assert_eq!(body["max_tokens"], 1024);
+    }
+
+    #[test]
+    fn test_output_limit_field_by_provider_and_api() {
+        use crate::proxy::model_router::Provider;
+        assert_eq!(
+            output_limit_field(Provider::OpenAI, "/v1/chat/completions"),
+            "max_completion_tokens"
+        );
+        assert_eq!(
+            output_limit_field(Provider::OpenAI, "/v1/responses"),
+            "max_output_tokens"
+        );
+        assert_eq!(
+            output_limit_field(Provider::Anthropic, "/v1/chat/completions"),
+            "max_tokens"
+        );
+        assert_eq!(
+            output_limit_field(Provider::Groq, "/openai/v1/chat/completions"),
+            "max_tokens"
+        );
+    }
+
+    #[test]
+    fn test_param_defaults_ignore_non_object_inputs() {
+        let mut body = json!([1, 2]);
+        assert!(apply_param_defaults(&mut body, &json!({"temperature": 0.2})).is_empty());
+
+        let mut body = json!({"model": "gpt-4o"});
+        assert!(apply_param_defaults(&mut body, &json!("bogus")).is_empty());
+        assert_eq!(body, json!({"model": "gpt-4o"}));
+    }
+
+    #[test]
+    fn test_inject_response_field_moves_source() {
+        let mut body = json!({
+            "choices": [{"message": {"content": "hi"}}],
+            "citations": ["https://a", "https://b"]
+        });
+        let n = inject_response_field(&mut body, "x_sources", None, Some("citations"), true);
+        assert_eq!(n, 1);
+        assert_eq!(body["x_sources"], json!(["https://a", "https://b"]));
+        assert!(body.get("citations").is_none());
+    }
+
+    #[test]
+    fn test_inject_response_field_creates_path_and_fans_out() {
+        let mut body = json!({
+            "choices": [{"message": {"content": "a"}}, {"message": {"content": "b"}}]
+        });
+        let n = inject_response_field(
+            &mut body,
+            "choices.*.message.x_meta.source",
+            Some(&json!("kb")),
+            None,
+            false,
+        );
+        assert_eq!(n, 2);
+        assert_eq!(body["choices"][1]["message"]["x_meta"]["source"], "kb");
+
+        // Index equal to the length appends; past the end is ignored.
+        let mut body = json!({"x_sources": ["a"]});
+        assert_eq!(
+            inject_response_field(&mut body, "x_sources.1", Some(&json!("b")), None, false),
+            1
+        );
+        assert_eq!(
+            inject_response_field(&mut body, "x_sources.5", Some(&json!("z")), None, false),
+            0
+        );
+        assert_eq!(body["x_sources"], json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_inject_response_field_missing_source_or_scalar_is_noop() {
+        let mut body = json!({"id": "chatcmpl-1", "usage": 3});
+        let before = body.clone();
+        assert_eq!(
+            inject_response_field(&mut body, "x_sources", None, Some("citations"), true),
+            0
+        );
+        assert_eq!(
+            inject_response_field(&mut body, "usage.extra", Some(&json!(1)), None, false),
+            0
+        );
+        assert_eq!(body, before);
+    }
+
+    #[test]
+    fn test_inject_response_field_keeps_source_when_nothing_set() {
+        // The target can't be created under a scalar: the source stays.
+        let mut body = json!({"citations": ["https://a"], "usage": 3});
+        let before = body.clone();
+        assert_eq!(
+            inject_response_field(&mut body, "usage.sources", None, Some("citations"), true),
+            0
+        );
+        assert_eq!(body, before);
+
+        // Moving a field into itself leaves it in place.
+        let mut body = json!({"citations": ["https://a"]});
+        assert_eq!(
+            inject_response_field(&mut body, "citations.0", None, Some("citations"), true),
+            1
+        );
+        assert_eq!(body["citations"][0], json!(["https://a"]));
+    }
+}
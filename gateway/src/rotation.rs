@@ -2,6 +2,7 @@
 use crate::cache::TieredCache;
 use crate::store::postgres::PgStore;
 use crate::vault::builtin::VaultCrypto;
+use crate::vault::cache::SecretCache;
 use sqlx::Row;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -24,6 +25,7 @@ pub struct RotationScheduler {
     db: PgStore,
     vault: VaultCrypto,
     cache: TieredCache,
+    secret_cache: SecretCache,
     interval_secs: u64,
 }
 
@@ -47,11 +49,18 @@ impl RotationScheduler {
     ///
     /// `check_interval_secs`: how often (seconds) the scheduler checks for due credentials.
     /// Default: 3600 (1 hour). Set via `TRUEFLOW_ROTATION_CHECK_INTERVAL` env var.
-    pub fn new(db: PgStore, vault: VaultCrypto, cache: TieredCache, interval_secs: u64) -> Self {
+    pub fn new(
+        db: PgStore,
+        vault: VaultCrypto,
+        cache: TieredCache,
+        secret_cache: SecretCache,
+        interval_secs: u64,
+    ) -> Self {
         Self {
             db,
             vault,
             cache,
+            secret_cache,
             interval_secs,
         }
     }
@@ -227,6 +236,7 @@ impl RotationScheduler {
         // Step 4: Invalidate credential cache
         let cache_key = format!("credential:{}", cred.id);
         self.cache.invalidate_local(&cache_key);
+        self.secret_cache.invalidate(&cred.id.to_string());
 
         // Step 5: Log the rotation
         self.log_rotation(
@@ -180,7 +180,7 @@ pub struct PayloadBodies {
 }
 
 /// Parse a `PAYLOAD_STORE_URL` and return an `(ObjectStore impl, prefix)` pair.
-fn build_object_store(url: &str) -> Result<(Box<dyn ObjectStore>, String)> {
+pub(crate) fn build_object_store(url: &str) -> Result<(Box<dyn ObjectStore>, String)> {
     if url.starts_with("file://") {
         // Local filesystem — great for development/testing
         let path = url.trim_start_matches("file://");
@@ -1,5 +1,6 @@
 use super::types::{
     SpendByDimension, TokenLatencyStat, TokenStatusStat, TokenSummary, TokenVolumeStat,
+    ToolUsageStat,
 };
 use super::PgStore;
 use uuid::Uuid;
@@ -23,7 +24,7 @@ impl PgStore {
             "#,
         )
         .bind(project_id)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -44,7 +45,7 @@ impl PgStore {
             "#,
         )
         .bind(project_id)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -67,7 +68,7 @@ impl PgStore {
             "#
         )
         .bind(project_id)
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await?;
         Ok(row)
     }
@@ -93,7 +94,7 @@ impl PgStore {
         )
         .bind(token_id)
         .bind(project_id)
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await?;
 
         // Hourly buckets for sparkline
@@ -109,7 +110,7 @@ impl PgStore {
         )
         .bind(token_id)
         .bind(project_id)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
 
         Ok(crate::models::analytics::TokenUsageStats {
@@ -142,7 +143,7 @@ impl PgStore {
         )
         .bind(project_id)
         .bind(hours.to_string())
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await?;
         Ok(row)
     }
@@ -172,7 +173,7 @@ impl PgStore {
         .bind(project_id)
         .bind(hours.to_string())
         .bind(bucket)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -203,7 +204,7 @@ impl PgStore {
         )
         .bind(project_id)
         .bind(hours.to_string())
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -232,7 +233,68 @@ impl PgStore {
         )
         .bind(project_id)
         .bind(hours.to_string())
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
+        .await?;
+        Ok(rows)
+    }
+
+    /// Hourly spend for one token over the last `days` days, oldest first.
+    /// Feeds spend-cap simulation; empty hours are omitted.
+    pub async fn get_token_hourly_spend(
+        &self,
+        token_id: &str,
+        days: i32,
+    ) -> anyhow::Result<Vec<(chrono::DateTime<chrono::Utc>, f64)>> {
+        let rows = sqlx::query_as::<_, (chrono::DateTime<chrono::Utc>, f64)>(
+            r#"
+            SELECT
+                date_trunc('hour', created_at)          AS bucket,
+                COALESCE(SUM(estimated_cost_usd), 0)::float8 AS cost_usd
+            FROM audit_logs
+            WHERE token_id = $1
+              AND created_at > now() - ($2 || ' days')::interval
+              AND estimated_cost_usd IS NOT NULL
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )
+        .bind(token_id)
+        .bind(days.to_string())
+        .fetch_all(self.read_pool())
+        .await?;
+        Ok(rows)
+    }
+
+    /// Tool invocation counts and attributed cost over a time window.
+    /// Expands the `tool_calls` JSONB array; per-call token/cost fields are
+    /// estimates written by the proxy at audit time (0 for older rows).
+    pub async fn get_tool_usage(
+        &self,
+        project_id: Uuid,
+        hours: i32,
+    ) -> anyhow::Result<Vec<ToolUsageStat>> {
+        let rows = sqlx::query_as::<_, ToolUsageStat>(
+            r#"
+            SELECT
+                COALESCE(tc->>'name', 'unknown')        AS tool_name,
+                COUNT(*)::bigint                        AS call_count,
+                COUNT(DISTINCT a.id)::bigint            AS request_count,
+                COALESCE(SUM((tc->>'estimated_tokens')::bigint), 0)::bigint AS estimated_tokens,
+                COALESCE(SUM((tc->>'estimated_cost_usd')::float8), 0)::float8 AS estimated_cost_usd
+            FROM audit_logs a
+            CROSS JOIN LATERAL jsonb_array_elements(a.tool_calls) AS tc
+            WHERE a.project_id = $1
+              AND a.created_at > now() - ($2 || ' hours')::interval
+              AND a.tool_calls IS NOT NULL
+              AND jsonb_typeof(a.tool_calls) = 'array'
+            GROUP BY 1
+            ORDER BY call_count DESC
+            LIMIT 500
+            "#,
+        )
+        .bind(project_id)
+        .bind(hours.to_string())
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -264,7 +326,7 @@ impl PgStore {
         .bind(project_id)
         .bind(hours.to_string())
         .bind(tag_key)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -290,7 +352,7 @@ impl PgStore {
              ORDER BY 2 DESC"#,
             project_id
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
 
         Ok(rows)
@@ -314,7 +376,7 @@ impl PgStore {
             project_id,
             token_id
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -337,7 +399,7 @@ impl PgStore {
             project_id,
             token_id
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
@@ -358,7 +420,7 @@ impl PgStore {
             project_id,
             token_id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await?;
 
         Ok(TokenLatencyStat {
@@ -376,20 +438,89 @@ impl PgStore {
         // For baseline (null variants), we group them together under an empty string or 'baseline'
         let rows = sqlx::query_as::<_, crate::models::analytics::ExperimentSummary>(
             r#"SELECT 
-                experiment_name as "experiment_name!",
-                COALESCE(variant_name, 'baseline') as "variant_name!",
-                COUNT(*) as "total_requests!",
-                COALESCE(AVG(response_latency_ms)::float8, 0.0) as "avg_latency_ms!",
-                COALESCE(SUM(cost_usd)::float8, 0.0) as "total_cost_usd!",
-                COALESCE(AVG(prompt_tokens + completion_tokens)::float8, 0.0) as "avg_tokens!",
-                COUNT(*) FILTER (WHERE upstream_status >= 400) as "error_count!"
+                experiment_name,
+                COALESCE(variant_name, 'baseline') as variant_name,
+                COUNT(*) as total_requests,
+                COALESCE(AVG(response_latency_ms)::float8, 0.0) as avg_latency_ms,
+                COALESCE(SUM(estimated_cost_usd)::float8, 0.0) as total_cost_usd,
+                COALESCE(AVG(prompt_tokens + completion_tokens)::float8, 0.0) as avg_tokens,
+                COUNT(*) FILTER (WHERE upstream_status >= 400) as error_count
              FROM audit_logs
              WHERE project_id = $1 AND experiment_name IS NOT NULL
              GROUP BY experiment_name, variant_name
              ORDER BY experiment_name, variant_name"#,
         )
         .bind(project_id)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
+        .await?;
+        Ok(rows)
+    }
+
+    /// Per-variant means, spreads and error counts for one experiment over
+    /// the last `hours`, for variant comparison. Unassigned requests are
+    /// grouped as `baseline`.
+    pub async fn get_experiment_variant_stats(
+        &self,
+        project_id: Uuid,
+        experiment_name: &str,
+        hours: i32,
+    ) -> anyhow::Result<Vec<crate::models::analytics::VariantStats>> {
+        let rows = sqlx::query_as::<_, crate::models::analytics::VariantStats>(
+            r#"
+            SELECT
+                COALESCE(variant_name, 'baseline')                         AS variant_name,
+                COUNT(*)::int8                                             AS requests,
+                COUNT(*) FILTER (WHERE upstream_status >= 400)::int8       AS error_count,
+                COUNT(estimated_cost_usd)::int8                            AS cost_samples,
+                COALESCE(AVG(estimated_cost_usd), 0)::float8               AS mean_cost_usd,
+                COALESCE(STDDEV_SAMP(estimated_cost_usd), 0)::float8       AS cost_stddev,
+                COUNT(response_latency_ms)::int8                           AS latency_samples,
+                COALESCE(AVG(response_latency_ms), 0)::float8              AS mean_latency_ms,
+                COALESCE(STDDEV_SAMP(response_latency_ms), 0)::float8      AS latency_stddev,
+                COUNT(feedback_score)::int8                                AS quality_samples,
+                AVG(feedback_score)::float8                                AS mean_quality,
+                COALESCE(STDDEV_SAMP(feedback_score), 0)::float8           AS quality_stddev
+            FROM audit_logs
+            WHERE project_id = $1
+              AND experiment_name = $2
+              AND created_at > now() - ($3 || ' hours')::interval
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )
+        .bind(project_id)
+        .bind(experiment_name)
+        .bind(hours.to_string())
+        .fetch_all(self.read_pool())
+        .await?;
+        Ok(rows)
+    }
+
+    /// One token's spend per UTC day from `since` on, for the spend forecast.
+    /// Days without spend are omitted.
+    pub async fn get_token_daily_costs(
+        &self,
+        project_id: Uuid,
+        token_id: &str,
+        since: chrono::NaiveDate,
+    ) -> anyhow::Result<Vec<crate::models::analytics::DailyCost>> {
+        let rows = sqlx::query_as::<_, crate::models::analytics::DailyCost>(
+            r#"
+            SELECT
+                (created_at AT TIME ZONE 'UTC')::date                AS day,
+                COALESCE(SUM(estimated_cost_usd), 0)::float8         AS cost_usd
+            FROM audit_logs
+            WHERE project_id = $1
+              AND token_id = $2
+              AND created_at >= ($3::date)::timestamp AT TIME ZONE 'UTC'
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+        )
+        .bind(project_id)
+        .bind(token_id)
+        .bind(since)
+        .fetch_all(self.read_pool())
         .await?;
         Ok(rows)
     }
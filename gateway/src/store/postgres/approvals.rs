@@ -15,7 +15,7 @@ impl PgStore {
         let id_opt = sqlx::query_scalar::<_, Uuid>(
             r#"INSERT INTO approval_requests (token_id, project_id, idempotency_key, request_summary, expires_at)
                VALUES ($1, $2, $3, $4, $5)
-               ON CONFLICT (token_id, idempotency_key) DO NOTHING
+               ON CONFLICT (token_id, idempotency_key) WHERE status <> 'abandoned' DO NOTHING
                RETURNING id"#
         )
         .bind(token_id)
@@ -35,7 +35,7 @@ impl PgStore {
         } else {
             // Conflict -> fetch existing
             let existing_id = sqlx::query_scalar::<_, Uuid>(
-                "SELECT id FROM approval_requests WHERE token_id = $1 AND idempotency_key = $2",
+                "SELECT id FROM approval_requests WHERE token_id = $1 AND idempotency_key = $2 AND status <> 'abandoned'",
             )
             .bind(token_id)
             .bind(&idempotency_key)
@@ -110,6 +110,23 @@ impl PgStore {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Mark a still-pending approval abandoned (its caller disconnected).
+    /// The row is kept for audit; its idempotency key becomes free for a retry.
+    pub async fn abandon_approval_request(
+        &self,
+        request_id: Uuid,
+        project_id: Uuid,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE approval_requests SET status = 'abandoned' WHERE id = $1 AND project_id = $2 AND status = 'pending'",
+        )
+        .bind(request_id)
+        .bind(project_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     // -- Additional Approval Operations --
 
     /// List ALL approval requests for a project (pending + historical) for the dashboard.
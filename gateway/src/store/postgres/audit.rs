@@ -40,7 +40,7 @@ impl PgStore {
         .bind(project_id)
         .bind(limit)
         .bind(offset)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
 
         Ok(rows)
@@ -108,7 +108,7 @@ impl PgStore {
         )
         .bind(session_id)
         .bind(project_id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(self.read_pool())
         .await?;
 
         let Some(agg) = summary else {
@@ -137,7 +137,7 @@ impl PgStore {
         )
         .bind(session_id)
         .bind(project_id)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
 
         Ok(Some(SessionSummaryRow {
@@ -185,7 +185,7 @@ impl PgStore {
         .bind(project_id)
         .bind(limit)
         .bind(offset)
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
         .await?;
 
         Ok(rows
@@ -1,12 +1,12 @@
-use super::types::{CredentialMeta, NewCredential};
+use super::types::{CredentialMeta, CredentialModelRestriction, NewCredential};
 use super::PgStore;
 use uuid::Uuid;
 
 impl PgStore {
     pub async fn insert_credential(&self, cred: &NewCredential) -> anyhow::Result<Uuid> {
         let id = sqlx::query_scalar::<_, Uuid>(
-            r#"INSERT INTO credentials (project_id, name, provider, encrypted_dek, dek_nonce, encrypted_secret, secret_nonce, injection_mode, injection_header)
-               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            r#"INSERT INTO credentials (project_id, name, provider, encrypted_dek, dek_nonce, encrypted_secret, secret_nonce, injection_mode, injection_header, allowed_models, model_rate_limits)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
                RETURNING id"#
         )
         .bind(cred.project_id)
@@ -18,6 +18,8 @@ impl PgStore {
         .bind(&cred.secret_nonce)
         .bind(&cred.injection_mode)
         .bind(&cred.injection_header)
+        .bind(&cred.allowed_models)
+        .bind(&cred.model_rate_limits)
         .fetch_one(&self.pool)
         .await?;
 
@@ -26,7 +28,7 @@ impl PgStore {
 
     pub async fn list_credentials(&self, project_id: Uuid) -> anyhow::Result<Vec<CredentialMeta>> {
         let rows = sqlx::query_as::<_, CredentialMeta>(
-            "SELECT id, name, provider, version, is_active, created_at FROM credentials WHERE project_id = $1 ORDER BY created_at DESC"
+            "SELECT id, name, provider, version, is_active, created_at, allowed_models, model_rate_limits FROM credentials WHERE project_id = $1 ORDER BY created_at DESC"
         )
         .bind(project_id)
         .fetch_all(&self.pool)
@@ -35,6 +37,69 @@ impl PgStore {
         Ok(rows)
     }
 
+    /// Whether credential `id` belongs to `project_id`.
+    pub async fn credential_in_project(&self, id: Uuid, project_id: Uuid) -> anyhow::Result<bool> {
+        let found = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM credentials WHERE id = $1 AND project_id = $2)",
+        )
+        .bind(id)
+        .bind(project_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(found)
+    }
+
+    /// Credential IDs by name, for configs that reference credentials by
+    /// name. The newest credential of a name wins, and an active one wins
+    /// over an inactive one.
+    pub async fn credential_ids_by_name(
+        &self,
+        project_id: Uuid,
+    ) -> anyhow::Result<std::collections::HashMap<String, Uuid>> {
+        let mut credentials = std::collections::HashMap::new();
+        let mut listed = self.list_credentials(project_id).await?;
+        listed.sort_by_key(|c| !c.is_active);
+        for c in listed {
+            credentials.entry(c.name).or_insert(c.id);
+        }
+        Ok(credentials)
+    }
+
+    /// Name, `allowed_models` and `model_rate_limits` of an active credential,
+    /// for per-request model enforcement. `None` if the credential is missing
+    /// or inactive.
+    pub async fn get_credential_model_restriction(
+        &self,
+        id: Uuid,
+    ) -> anyhow::Result<Option<CredentialModelRestriction>> {
+        let row = sqlx::query_as::<_, CredentialModelRestriction>(
+            "SELECT name, allowed_models, model_rate_limits FROM credentials WHERE id = $1 AND is_active = true",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Replace a credential's `model_rate_limits` (`None` clears them).
+    /// Scoped to project_id for tenant isolation.
+    pub async fn update_credential_model_rate_limits(
+        &self,
+        id: Uuid,
+        project_id: Uuid,
+        limits: Option<&serde_json::Value>,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE credentials SET model_rate_limits = $3 WHERE id = $1 AND project_id = $2 AND is_active = true",
+        )
+        .bind(id)
+        .bind(project_id)
+        .bind(limits)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Soft-delete a credential by setting is_active = false.
     /// Scoped to project_id for tenant isolation.
     pub async fn delete_credential(&self, id: Uuid, project_id: Uuid) -> anyhow::Result<bool> {
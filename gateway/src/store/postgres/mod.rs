@@ -14,19 +14,36 @@ mod sessions;
 mod settings;
 mod tokens;
 pub mod types;
+mod upstream_health;
 mod usage;
+mod webhooks;
 
 #[cfg(test)]
 mod tests;
 
+pub use self::projects::merge_default_policy_ids;
 pub use self::types::*;
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
 #[derive(Clone)]
 pub struct PgStore {
     pool: PgPool,
+    /// Optional standby for analytics and audit-list reads (`DATABASE_READ_URL`).
+    read_replica: Option<ReadReplica>,
+}
+
+#[derive(Clone)]
+struct ReadReplica {
+    pool: PgPool,
+    /// Cleared when a health probe fails; reads fall back to the primary
+    /// until the next successful probe.
+    healthy: Arc<AtomicBool>,
 }
 
 impl PgStore {
@@ -39,13 +56,81 @@ impl PgStore {
             .max_connections(max_conns)
             .connect(database_url)
             .await?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            read_replica: None,
+        })
+    }
+
+    /// Attach a read replica for reporting queries. The pool connects lazily,
+    /// so an unreachable replica never blocks startup; it is probed once here
+    /// and then by [`PgStore::check_read_replica`].
+    pub async fn with_read_replica(mut self, read_url: &str) -> Self {
+        let max_conns: u32 = std::env::var("DATABASE_READ_MAX_CONNECTIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+        match PgPoolOptions::new()
+            .max_connections(max_conns)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect_lazy(read_url)
+        {
+            Ok(pool) => {
+                self.read_replica = Some(ReadReplica {
+                    pool,
+                    healthy: Arc::new(AtomicBool::new(false)),
+                });
+                self.check_read_replica().await;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Invalid DATABASE_READ_URL, analytics reads use the primary: {}",
+                    e
+                );
+            }
+        }
+        self
     }
 
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
 
+    /// Pool for read-only analytics and audit-list queries: the replica when
+    /// one is configured and healthy, otherwise the primary. Writes and the
+    /// proxy's token lookups always use [`PgStore::pool`].
+    pub fn read_pool(&self) -> &PgPool {
+        match &self.read_replica {
+            Some(replica) if replica.healthy.load(Ordering::Relaxed) => &replica.pool,
+            _ => &self.pool,
+        }
+    }
+
+    /// Probe the read replica and update its health flag, logging transitions.
+    /// No-op without a replica.
+    pub async fn check_read_replica(&self) {
+        let Some(replica) = &self.read_replica else {
+            return;
+        };
+        let ok = tokio::time::timeout(
+            Duration::from_secs(5),
+            sqlx::query("SELECT 1").execute(&replica.pool),
+        )
+        .await
+        .map(|r| r.is_ok())
+        .unwrap_or(false);
+        let was = replica.healthy.swap(ok, Ordering::Relaxed);
+        if was != ok {
+            if ok {
+                tracing::info!("Read replica reachable — routing analytics reads to it");
+            } else {
+                tracing::warn!(
+                    "Read replica unreachable — analytics reads fall back to the primary"
+                );
+            }
+        }
+    }
+
     /// Run pending migrations from the migrations/ directory.
     pub async fn migrate(&self) -> anyhow::Result<()> {
         sqlx::migrate!("./migrations").run(&self.pool).await?;
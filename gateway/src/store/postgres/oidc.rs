@@ -1,26 +1,139 @@
-use super::types::OidcProviderRow;
+use uuid::Uuid;
+
+use super::types::{NewOidcProvider, OidcProviderPatch, OidcProviderRow};
 use super::PgStore;
 
+const OIDC_COLUMNS: &str = "id, org_id, name, issuer_url, client_id, jwks_uri, audience, \
+     claim_mapping, default_role, default_scopes, enabled, created_at, updated_at";
+
 impl PgStore {
-    /// Find an enabled OIDC provider matching the given issuer URL.
-    /// Used by the auth middleware to validate JWT Bearer tokens.
+    /// Find the org's enabled OIDC provider for the given issuer URL
+    /// (unique per org). Used by the auth middleware to validate JWT Bearer
+    /// tokens.
     pub async fn get_oidc_provider_by_issuer(
         &self,
+        org_id: Uuid,
         issuer_url: &str,
     ) -> anyhow::Result<Option<OidcProviderRow>> {
-        let row = sqlx::query_as::<_, OidcProviderRow>(
-            r#"
-            SELECT id, org_id, name, issuer_url, client_id, jwks_uri,
-                   audience, claim_mapping, default_role, default_scopes, enabled
-            FROM oidc_providers
-            WHERE issuer_url = $1 AND enabled = true
-            LIMIT 1
-            "#,
-        )
+        let row = sqlx::query_as::<_, OidcProviderRow>(&format!(
+            "SELECT {} FROM oidc_providers WHERE org_id = $1 AND issuer_url = $2 AND enabled = true",
+            OIDC_COLUMNS
+        ))
+        .bind(org_id)
         .bind(issuer_url)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(row)
     }
+
+    /// All OIDC providers of an org, enabled or not.
+    pub async fn list_oidc_providers(&self, org_id: Uuid) -> anyhow::Result<Vec<OidcProviderRow>> {
+        let rows = sqlx::query_as::<_, OidcProviderRow>(&format!(
+            "SELECT {} FROM oidc_providers WHERE org_id = $1 ORDER BY created_at",
+            OIDC_COLUMNS
+        ))
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn get_oidc_provider(
+        &self,
+        id: Uuid,
+        org_id: Uuid,
+    ) -> anyhow::Result<Option<OidcProviderRow>> {
+        let row = sqlx::query_as::<_, OidcProviderRow>(&format!(
+            "SELECT {} FROM oidc_providers WHERE id = $1 AND org_id = $2",
+            OIDC_COLUMNS
+        ))
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Insert a provider. Fails with a unique violation if the org already
+    /// has one for the issuer.
+    pub async fn create_oidc_provider(
+        &self,
+        provider: &NewOidcProvider,
+    ) -> anyhow::Result<OidcProviderRow> {
+        let row = sqlx::query_as::<_, OidcProviderRow>(&format!(
+            r#"INSERT INTO oidc_providers (org_id, name, issuer_url, client_id, jwks_uri, audience,
+                   claim_mapping, default_role, default_scopes, enabled)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+               RETURNING {}"#,
+            OIDC_COLUMNS
+        ))
+        .bind(provider.org_id)
+        .bind(&provider.name)
+        .bind(&provider.issuer_url)
+        .bind(&provider.client_id)
+        .bind(&provider.jwks_uri)
+        .bind(&provider.audience)
+        .bind(&provider.claim_mapping)
+        .bind(&provider.default_role)
+        .bind(&provider.default_scopes)
+        .bind(provider.enabled)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Apply a partial update. Returns `None` if the provider doesn't exist
+    /// in the org.
+    pub async fn update_oidc_provider(
+        &self,
+        id: Uuid,
+        org_id: Uuid,
+        patch: &OidcProviderPatch,
+    ) -> anyhow::Result<Option<OidcProviderRow>> {
+        let row = sqlx::query_as::<_, OidcProviderRow>(&format!(
+            r#"UPDATE oidc_providers SET
+                   name = COALESCE($3, name),
+                   issuer_url = COALESCE($4, issuer_url),
+                   client_id = COALESCE($5, client_id),
+                   jwks_uri = COALESCE($6, jwks_uri),
+                   audience = COALESCE($7, audience),
+                   claim_mapping = COALESCE($8, claim_mapping),
+                   default_role = COALESCE($9, default_role),
+                   default_scopes = COALESCE($10, default_scopes),
+                   enabled = COALESCE($11, enabled),
+                   updated_at = NOW()
+               WHERE id = $1 AND org_id = $2
+               RETURNING {}"#,
+            OIDC_COLUMNS
+        ))
+        .bind(id)
+        .bind(org_id)
+        .bind(&patch.name)
+        .bind(&patch.issuer_url)
+        .bind(&patch.client_id)
+        .bind(&patch.jwks_uri)
+        .bind(&patch.audience)
+        .bind(&patch.claim_mapping)
+        .bind(&patch.default_role)
+        .bind(&patch.default_scopes)
+        .bind(patch.enabled)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn delete_oidc_provider(&self, id: Uuid, org_id: Uuid) -> anyhow::Result<bool> {
+        let result = sqlx::query("DELETE FROM oidc_providers WHERE id = $1 AND org_id = $2")
+            .bind(id)
+            .bind(org_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
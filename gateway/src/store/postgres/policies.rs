@@ -13,13 +13,23 @@ impl PgStore {
         }
 
         let rows = sqlx::query_as::<_, PolicyRow>(
-            "SELECT id, project_id, name, mode, phase, rules, retry, is_active, created_at FROM policies WHERE id = ANY($1) AND project_id = $2 AND is_active = true"
+            "SELECT id, project_id, name, mode, phase, rules, retry, priority, is_active, created_at FROM policies WHERE id = ANY($1) AND project_id = $2 AND is_active = true"
         )
         .bind(policy_ids)
         .bind(project_id)
         .fetch_all(&self.pool)
         .await?;
 
+        // Return policies in the token's attachment order so that the engine's
+        // priority sort has a deterministic tie-break.
+        let mut rows = rows;
+        rows.sort_by_key(|r| {
+            policy_ids
+                .iter()
+                .position(|id| *id == r.id)
+                .unwrap_or(usize::MAX)
+        });
+
         let mut policies = Vec::new();
         for row in rows {
             let mode = match row.mode.as_str() {
@@ -49,6 +59,7 @@ impl PgStore {
             policies.push(crate::models::policy::Policy {
                 id: row.id,
                 name: row.name,
+                priority: row.priority,
                 phase,
                 mode,
                 rules,
@@ -67,7 +78,7 @@ impl PgStore {
     ) -> anyhow::Result<Vec<PolicyRow>> {
         let limit = limit.clamp(1, 1000); // Cap at 1000, minimum 1
         let rows = sqlx::query_as::<_, PolicyRow>(
-            "SELECT id, project_id, name, mode, phase, rules, retry, is_active, created_at FROM policies WHERE project_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3"
+            "SELECT id, project_id, name, mode, phase, rules, retry, priority, is_active, created_at FROM policies WHERE project_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3"
         )
         .bind(project_id)
         .bind(limit)
@@ -77,6 +88,7 @@ impl PgStore {
         Ok(rows)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn insert_policy(
         &self,
         project_id: Uuid,
@@ -85,10 +97,11 @@ impl PgStore {
         phase: &str,
         rules: serde_json::Value,
         retry: Option<serde_json::Value>,
+        priority: i32,
     ) -> anyhow::Result<Uuid> {
         let id = sqlx::query_scalar::<_, Uuid>(
-            r#"INSERT INTO policies (project_id, name, mode, phase, rules, retry)
-               VALUES ($1, $2, $3, $4, $5, $6)
+            r#"INSERT INTO policies (project_id, name, mode, phase, rules, retry, priority)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
                RETURNING id"#,
         )
         .bind(project_id)
@@ -97,6 +110,7 @@ impl PgStore {
         .bind(phase)
         .bind(rules)
         .bind(retry)
+        .bind(priority)
         .fetch_one(&self.pool)
         .await?;
         Ok(id)
@@ -112,6 +126,7 @@ impl PgStore {
         rules: Option<serde_json::Value>,
         retry: Option<serde_json::Value>,
         name: Option<&str>,
+        priority: Option<i32>,
         expected_version: Option<i32>,
     ) -> anyhow::Result<Result<bool, ()>> {
         // Snapshot current state into policy_versions before updating
@@ -136,6 +151,7 @@ impl PgStore {
                        rules = COALESCE($3, rules),
                        retry = COALESCE($4, retry),
                        name = COALESCE($5, name),
+                       priority = COALESCE($9, priority),
                        version = version + 1
                    WHERE id = $6 AND project_id = $7 AND is_active = true AND version = $8"#,
             )
@@ -147,6 +163,7 @@ impl PgStore {
             .bind(id)
             .bind(project_id)
             .bind(ver)
+            .bind(priority)
             .execute(&self.pool)
             .await?
         } else {
@@ -157,6 +174,7 @@ impl PgStore {
                        rules = COALESCE($3, rules),
                        retry = COALESCE($4, retry),
                        name = COALESCE($5, name),
+                       priority = COALESCE($8, priority),
                        version = version + 1
                    WHERE id = $6 AND project_id = $7 AND is_active = true"#,
             )
@@ -167,6 +185,7 @@ impl PgStore {
             .bind(name)
             .bind(id)
             .bind(project_id)
+            .bind(priority)
             .execute(&self.pool)
             .await?
         };
@@ -7,7 +7,7 @@ impl PgStore {
 
     pub async fn list_model_pricing(&self) -> anyhow::Result<Vec<ModelPricingRow>> {
         let rows = sqlx::query_as::<_, ModelPricingRow>(
-            r#"SELECT id, provider, model_pattern, input_per_m, output_per_m, is_active, created_at, updated_at
+            r#"SELECT id, provider, model_pattern, input_per_m, output_per_m, image_per_unit, is_active, created_at, updated_at
                FROM model_pricing
                WHERE is_active = true
                ORDER BY provider ASC, model_pattern ASC"#
@@ -24,13 +24,15 @@ impl PgStore {
         model_pattern: &str,
         input_per_m: rust_decimal::Decimal,
         output_per_m: rust_decimal::Decimal,
+        image_per_unit: Option<rust_decimal::Decimal>,
     ) -> anyhow::Result<Uuid> {
         let id: Uuid = sqlx::query_scalar(
-            r#"INSERT INTO model_pricing (provider, model_pattern, input_per_m, output_per_m)
-               VALUES ($1, $2, $3, $4)
+            r#"INSERT INTO model_pricing (provider, model_pattern, input_per_m, output_per_m, image_per_unit)
+               VALUES ($1, $2, $3, $4, $5)
                ON CONFLICT (provider, model_pattern) DO UPDATE
                  SET input_per_m = EXCLUDED.input_per_m,
                      output_per_m = EXCLUDED.output_per_m,
+                     image_per_unit = EXCLUDED.image_per_unit,
                      is_active = true,
                      updated_at = NOW()
                RETURNING id"#,
@@ -39,11 +41,44 @@ impl PgStore {
         .bind(model_pattern)
         .bind(input_per_m)
         .bind(output_per_m)
+        .bind(image_per_unit)
         .fetch_one(&self.pool)
         .await?;
         Ok(id)
     }
 
+    /// Upsert many pricing entries in one transaction. Returns, per row,
+    /// whether it created a new entry (`true`) or updated an existing one.
+    pub async fn import_model_pricing(
+        &self,
+        rows: &[crate::models::pricing_import::PricingImportRow],
+    ) -> anyhow::Result<Vec<bool>> {
+        let mut tx = self.pool.begin().await?;
+        let mut created = Vec::with_capacity(rows.len());
+        for row in rows {
+            // xmax = 0 only for freshly inserted tuples.
+            let inserted: bool = sqlx::query_scalar(
+                r#"INSERT INTO model_pricing (provider, model_pattern, input_per_m, output_per_m)
+                   VALUES ($1, $2, $3, $4)
+                   ON CONFLICT (provider, model_pattern) DO UPDATE
+                     SET input_per_m = EXCLUDED.input_per_m,
+                         output_per_m = EXCLUDED.output_per_m,
+                         is_active = true,
+                         updated_at = NOW()
+                   RETURNING (xmax = 0)"#,
+            )
+            .bind(&row.provider)
+            .bind(&row.model_pattern)
+            .bind(row.input_per_m)
+            .bind(row.output_per_m)
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(inserted);
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
     /// Soft-delete a pricing entry (sets is_active = false).
     pub async fn delete_model_pricing(&self, id: Uuid) -> anyhow::Result<bool> {
         let result = sqlx::query(
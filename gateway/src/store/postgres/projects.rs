@@ -2,6 +2,18 @@ use super::types::ProjectRow;
 use super::PgStore;
 use uuid::Uuid;
 
+/// A new token's policy ids: the project defaults first (so baseline
+/// guardrails win priority ties), then the caller's, each id once.
+pub fn merge_default_policy_ids(defaults: &[Uuid], explicit: &[Uuid]) -> Vec<Uuid> {
+    let mut merged = Vec::with_capacity(defaults.len() + explicit.len());
+    for id in defaults.iter().chain(explicit) {
+        if !merged.contains(id) {
+            merged.push(*id);
+        }
+    }
+    merged
+}
+
 impl PgStore {
     pub async fn create_project(&self, org_id: Uuid, name: &str) -> anyhow::Result<Uuid> {
         let id = sqlx::query_scalar::<_, Uuid>(
@@ -16,7 +28,7 @@ impl PgStore {
 
     pub async fn list_projects(&self, org_id: Uuid) -> anyhow::Result<Vec<ProjectRow>> {
         let rows = sqlx::query_as::<_, ProjectRow>(
-            "SELECT id, org_id, name, created_at FROM projects WHERE org_id = $1 ORDER BY created_at ASC"
+            "SELECT id, org_id, name, created_at, default_policy_ids FROM projects WHERE org_id = $1 ORDER BY created_at ASC"
         )
         .bind(org_id)
         .fetch_all(&self.pool)
@@ -24,6 +36,17 @@ impl PgStore {
         Ok(rows)
     }
 
+    pub async fn get_project(&self, id: Uuid, org_id: Uuid) -> anyhow::Result<Option<ProjectRow>> {
+        let row = sqlx::query_as::<_, ProjectRow>(
+            "SELECT id, org_id, name, created_at, default_policy_ids FROM projects WHERE id = $1 AND org_id = $2",
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
     pub async fn update_project(&self, id: Uuid, org_id: Uuid, name: &str) -> anyhow::Result<bool> {
         let result = sqlx::query("UPDATE projects SET name = $1 WHERE id = $2 AND org_id = $3")
             .bind(name)
@@ -34,6 +57,63 @@ impl PgStore {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Replace the project's default policies.
+    pub async fn set_project_default_policy_ids(
+        &self,
+        id: Uuid,
+        org_id: Uuid,
+        policy_ids: &[Uuid],
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE projects SET default_policy_ids = $1 WHERE id = $2 AND org_id = $3",
+        )
+        .bind(policy_ids)
+        .bind(id)
+        .bind(org_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The project's default policies that are still active, in list order.
+    /// A deleted default is skipped here; tokens that already carry it are
+    /// unaffected (inactive policies are ignored at request time).
+    pub async fn get_project_default_policy_ids(
+        &self,
+        project_id: Uuid,
+    ) -> anyhow::Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT d.id
+            FROM projects pr
+            CROSS JOIN LATERAL unnest(pr.default_policy_ids) WITH ORDINALITY AS d(id, ord)
+            JOIN policies p ON p.id = d.id AND p.project_id = pr.id AND p.is_active = true
+            WHERE pr.id = $1
+            ORDER BY d.ord
+            "#,
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    /// The subset of `policy_ids` that are active policies of the project.
+    pub async fn active_policy_ids_in_project(
+        &self,
+        project_id: Uuid,
+        policy_ids: &[Uuid],
+    ) -> anyhow::Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM policies WHERE id = ANY($1) AND project_id = $2 AND is_active = true",
+        )
+        .bind(policy_ids)
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
     pub async fn delete_project(&self, id: Uuid, org_id: Uuid) -> anyhow::Result<bool> {
         let result = sqlx::query("DELETE FROM projects WHERE id = $1 AND org_id = $2")
             .bind(id)
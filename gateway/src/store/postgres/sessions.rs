@@ -104,14 +104,15 @@ impl PgStore {
     }
 
     /// Atomically increment session cost and tokens after a request completes.
+    /// Returns the session's new cumulative cost, or `None` if no session row exists.
     pub async fn increment_session_spend(
         &self,
         session_id: &str,
         project_id: Uuid,
         cost_usd: rust_decimal::Decimal,
         tokens: i64,
-    ) -> anyhow::Result<()> {
-        sqlx::query(
+    ) -> anyhow::Result<Option<rust_decimal::Decimal>> {
+        let total = sqlx::query_scalar::<_, rust_decimal::Decimal>(
             r#"
             UPDATE sessions
             SET total_cost_usd = total_cost_usd + $3,
@@ -119,16 +120,17 @@ impl PgStore {
                 total_requests = total_requests + 1,
                 updated_at = NOW()
             WHERE session_id = $1 AND project_id = $2
+            RETURNING total_cost_usd
             "#,
         )
         .bind(session_id)
         .bind(project_id)
         .bind(cost_usd)
         .bind(tokens)
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(total)
     }
 
     /// Get session entity for status/spend cap checks.
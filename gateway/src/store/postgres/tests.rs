@@ -135,3 +135,117 @@ fn test_dimension_handles_unknown_sentinel_value() {
     let json = serde_json::to_value(&row).unwrap();
     assert_eq!(json["dimension"], "unknown");
 }
+
+/// Reads go to the replica only while it is marked healthy, and always to the
+/// primary when no replica is configured.
+#[tokio::test]
+async fn test_read_pool_falls_back_to_primary() {
+    use super::{PgStore, ReadReplica};
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let lazy = |url: &str| PgPoolOptions::new().connect_lazy(url).unwrap();
+    let mut store = PgStore {
+        pool: lazy("postgres://primary/trueflow"),
+        read_replica: None,
+    };
+    assert_eq!(store.read_pool().connect_options().get_host(), "primary");
+
+    let healthy = Arc::new(AtomicBool::new(true));
+    store.read_replica = Some(ReadReplica {
+        pool: lazy("postgres://replica/trueflow"),
+        healthy: healthy.clone(),
+    });
+    assert_eq!(store.read_pool().connect_options().get_host(), "replica");
+    assert_eq!(store.pool().connect_options().get_host(), "primary");
+
+    healthy.store(false, Ordering::Relaxed);
+    assert_eq!(store.read_pool().connect_options().get_host(), "primary");
+}
+
+/// Project defaults come first, the caller's policies follow, and a policy
+/// listed by both is attached once.
+#[test]
+fn test_merge_default_policy_ids_dedupes() {
+    use super::merge_default_policy_ids;
+    use uuid::Uuid;
+
+    let (pii, rate, custom) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    assert_eq!(
+        merge_default_policy_ids(&[pii, rate], &[custom]),
+        vec![pii, rate, custom]
+    );
+    assert_eq!(
+        merge_default_policy_ids(&[pii, rate], &[custom, pii]),
+        vec![pii, rate, custom]
+    );
+    assert_eq!(merge_default_policy_ids(&[], &[custom]), vec![custom]);
+    assert_eq!(merge_default_policy_ids(&[pii], &[]), vec![pii]);
+    assert_eq!(merge_default_policy_ids(&[pii, pii], &[]), vec![pii]);
+    assert!(merge_default_policy_ids(&[], &[]).is_empty());
+}
+
+/// Rotation carries the token's spend caps, with their usage, to the new ID.
+#[tokio::test]
+#[ignore = "needs a Postgres database at TEST_DATABASE_URL"]
+async fn test_rotate_token_copies_spend_caps() {
+    use super::PgStore;
+    use uuid::Uuid;
+
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+    let store = PgStore::connect(&url).await.unwrap();
+    store.migrate().await.unwrap();
+
+    let project_id = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+    let old_id = store
+        .insert_token_stub(
+            project_id,
+            "rotating",
+            "https://api.openai.com",
+            vec![],
+            1,
+            None,
+        )
+        .await
+        .unwrap();
+    for (period, limit, usage) in [("daily", "10", "2.5"), ("lifetime", "500", "123.4")] {
+        sqlx::query(
+            "INSERT INTO spend_caps (project_id, token_id, period, limit_usd, usage_usd, reset_at) \
+             VALUES ($1, $2, $3, $4::numeric, $5::numeric, NOW() + INTERVAL '1 day')",
+        )
+        .bind(project_id)
+        .bind(&old_id)
+        .bind(period)
+        .bind(limit)
+        .bind(usage)
+        .execute(store.pool())
+        .await
+        .unwrap();
+    }
+
+    let old = store.get_token(&old_id).await.unwrap().unwrap();
+    let new_id = format!("tf_v1_rotated_{}", Uuid::new_v4().simple());
+    assert!(store.rotate_token(&old, &new_id, None).await.unwrap());
+
+    let caps: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT period, limit_usd::float8::text, usage_usd::float8::text \
+         FROM spend_caps WHERE token_id = $1 ORDER BY period",
+    )
+    .bind(&new_id)
+    .fetch_all(store.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        caps,
+        vec![
+            ("daily".to_string(), "10".to_string(), "2.5".to_string()),
+            (
+                "lifetime".to_string(),
+                "500".to_string(),
+                "123.4".to_string()
+            ),
+        ]
+    );
+}
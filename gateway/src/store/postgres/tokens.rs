@@ -2,35 +2,87 @@ use super::types::{NewToken, TokenRow};
 use super::PgStore;
 use uuid::Uuid;
 
+/// Token columns carried over to the replacement by [`PgStore::rotate_token`]:
+/// everything except identity, name, status and timestamps.
+const ROTATION_COPIED_COLUMNS: &str = "project_id, credential_id, upstream_url, scopes, policy_ids, created_by, log_level, upstreams, circuit_breaker, allowed_models, allowed_model_group_ids, team_id, tags, mcp_allowed_tools, mcp_blocked_tools, stream_flush, provider_hint, request_budget_secs, param_defaults, session_cost_header, strip_body_fields, budget_pressure_model_map, budget_pressure_threshold_pct, stream_ttft_comment, test_upstream_override, context_window_action, enforcement_order, forward_trace_headers, adaptive_rate_limit, migration, max_output_tokens_ceiling, stream_output_format, serve_stale_on_error, max_concurrent_streams, max_cost_per_request_usd, cache_key_ignore_paths, json_mode_fallback, replay_window_secs, capability_action";
+
+/// Insert one token row on `executor` (the pool or a transaction).
+async fn insert_token_on<'e, E>(executor: E, token: &NewToken) -> anyhow::Result<()>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query(
+        r#"INSERT INTO tokens (id, project_id, name, credential_id, upstream_url, scopes, policy_ids, log_level, circuit_breaker, allowed_models, team_id, tags, mcp_allowed_tools, mcp_blocked_tools, stream_flush, provider_hint, request_budget_secs, param_defaults, session_cost_header, strip_body_fields, budget_pressure_model_map, budget_pressure_threshold_pct, stream_ttft_comment, test_upstream_override, context_window_action, enforcement_order, forward_trace_headers, adaptive_rate_limit, migration, max_output_tokens_ceiling, stream_output_format, serve_stale_on_error, max_concurrent_streams, max_cost_per_request_usd, cache_key_ignore_paths, json_mode_fallback, replay_window_secs, capability_action)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, 1::SMALLINT), $9, $10, $11, COALESCE($12, '{}'::jsonb), $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33, $34, $35, $36, $37, $38)"#
+    )
+    .bind(&token.id)
+    .bind(token.project_id)
+    .bind(&token.name)
+    .bind(token.credential_id)
+    .bind(&token.upstream_url)
+    .bind(&token.scopes)
+    .bind(&token.policy_ids)
+    .bind(token.log_level)
+    .bind(&token.circuit_breaker)
+    .bind(&token.allowed_models)
+    .bind(token.team_id)
+    .bind(&token.tags)
+    .bind(&token.mcp_allowed_tools)
+    .bind(&token.mcp_blocked_tools)
+    .bind(&token.stream_flush)
+    .bind(&token.provider_hint)
+    .bind(token.request_budget_secs)
+    .bind(&token.param_defaults)
+    .bind(token.session_cost_header)
+    .bind(&token.strip_body_fields)
+    .bind(&token.budget_pressure_model_map)
+    .bind(token.budget_pressure_threshold_pct)
+    .bind(token.stream_ttft_comment)
+    .bind(&token.test_upstream_override)
+    .bind(&token.context_window_action)
+    .bind(&token.enforcement_order)
+    .bind(&token.forward_trace_headers)
+    .bind(&token.adaptive_rate_limit)
+    .bind(&token.migration)
+    .bind(token.max_output_tokens_ceiling)
+    .bind(&token.stream_output_format)
+    .bind(token.serve_stale_on_error)
+    .bind(token.max_concurrent_streams)
+    .bind(token.max_cost_per_request_usd)
+    .bind(&token.cache_key_ignore_paths)
+    .bind(token.json_mode_fallback)
+    .bind(token.replay_window_secs)
+    .bind(&token.capability_action)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
 impl PgStore {
     pub async fn insert_token(&self, token: &NewToken) -> anyhow::Result<()> {
-        sqlx::query(
-            r#"INSERT INTO tokens (id, project_id, name, credential_id, upstream_url, scopes, policy_ids, log_level, circuit_breaker, allowed_models, team_id, tags, mcp_allowed_tools, mcp_blocked_tools)
-               VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, 1::SMALLINT), $9, $10, $11, COALESCE($12, '{}'::jsonb), $13, $14)"#
-        )
-        .bind(&token.id)
-        .bind(token.project_id)
-        .bind(&token.name)
-        .bind(token.credential_id)
-        .bind(&token.upstream_url)
-        .bind(&token.scopes)
-        .bind(&token.policy_ids)
-        .bind(token.log_level)
-        .bind(&token.circuit_breaker)
-        .bind(&token.allowed_models)
-        .bind(token.team_id)
-        .bind(&token.tags)
-        .bind(&token.mcp_allowed_tools)
-        .bind(&token.mcp_blocked_tools)
-        .execute(&self.pool)
-        .await?;
+        insert_token_on(&self.pool, token).await
+    }
 
-        Ok(())
+    /// Insert `tokens` in one transaction: all of them or none. On failure
+    /// the error carries the index of the token the insert failed on (`None`
+    /// if the transaction itself failed).
+    pub async fn insert_tokens(
+        &self,
+        tokens: &[NewToken],
+    ) -> Result<(), (Option<usize>, anyhow::Error)> {
+        let mut tx = self.pool.begin().await.map_err(|e| (None, e.into()))?;
+        for (i, token) in tokens.iter().enumerate() {
+            insert_token_on(&mut *tx, token)
+                .await
+                .map_err(|e| (Some(i), e))?;
+        }
+        tx.commit().await.map_err(|e| (None, e.into()))
     }
 
     pub async fn get_token(&self, token_id: &str) -> anyhow::Result<Option<TokenRow>> {
         let row = sqlx::query_as::<_, TokenRow>(
-            "SELECT id, project_id, name, credential_id, upstream_url, scopes, policy_ids, is_active, expires_at, created_at, COALESCE(log_level, 1::SMALLINT) as log_level, upstreams, circuit_breaker, allowed_models, allowed_model_group_ids, team_id, tags, mcp_allowed_tools, mcp_blocked_tools FROM tokens WHERE id = $1"
+            "SELECT id, project_id, name, credential_id, upstream_url, scopes, policy_ids, is_active, expires_at, created_at, COALESCE(log_level, 1::SMALLINT) as log_level, upstreams, circuit_breaker, allowed_models, allowed_model_group_ids, team_id, tags, mcp_allowed_tools, mcp_blocked_tools, stream_flush, provider_hint, request_budget_secs, param_defaults, session_cost_header, strip_body_fields, budget_pressure_model_map, budget_pressure_threshold_pct, stream_ttft_comment, test_upstream_override, context_window_action, enforcement_order, forward_trace_headers, adaptive_rate_limit, migration, max_output_tokens_ceiling, stream_output_format, serve_stale_on_error, max_concurrent_streams, max_cost_per_request_usd, cache_key_ignore_paths, json_mode_fallback, replay_window_secs, capability_action FROM tokens WHERE id = $1"
         )
         .bind(token_id)
         .fetch_optional(&self.pool)
@@ -39,6 +91,22 @@ impl PgStore {
         Ok(row)
     }
 
+    /// Names of the tokens among `ids` that belong to `project_id`.
+    pub async fn get_token_names_in_project(
+        &self,
+        project_id: Uuid,
+        ids: &[String],
+    ) -> anyhow::Result<Vec<(String, String)>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, name FROM tokens WHERE project_id = $1 AND id = ANY($2)",
+        )
+        .bind(project_id)
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
     pub async fn list_tokens(
         &self,
         project_id: Uuid,
@@ -47,7 +115,7 @@ impl PgStore {
     ) -> anyhow::Result<Vec<TokenRow>> {
         let limit = limit.clamp(1, 1000); // Cap at 1000, minimum 1
         let rows = sqlx::query_as::<_, TokenRow>(
-            "SELECT id, project_id, name, credential_id, upstream_url, scopes, policy_ids, is_active, expires_at, created_at, COALESCE(log_level, 1::SMALLINT) as log_level, upstreams, circuit_breaker, allowed_models, allowed_model_group_ids, team_id, tags, mcp_allowed_tools, mcp_blocked_tools FROM tokens WHERE project_id = $1 AND is_active = true ORDER BY created_at DESC LIMIT $2 OFFSET $3"
+            "SELECT id, project_id, name, credential_id, upstream_url, scopes, policy_ids, is_active, expires_at, created_at, COALESCE(log_level, 1::SMALLINT) as log_level, upstreams, circuit_breaker, allowed_models, allowed_model_group_ids, team_id, tags, mcp_allowed_tools, mcp_blocked_tools, stream_flush, provider_hint, request_budget_secs, param_defaults, session_cost_header, strip_body_fields, budget_pressure_model_map, budget_pressure_threshold_pct, stream_ttft_comment, test_upstream_override, context_window_action, enforcement_order, forward_trace_headers, adaptive_rate_limit, migration, max_output_tokens_ceiling, stream_output_format, serve_stale_on_error, max_concurrent_streams, max_cost_per_request_usd, cache_key_ignore_paths, json_mode_fallback, replay_window_secs, capability_action FROM tokens WHERE project_id = $1 AND is_active = true ORDER BY created_at DESC LIMIT $2 OFFSET $3"
         )
         .bind(project_id)
         .bind(limit)
@@ -69,6 +137,74 @@ impl PgStore {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Replace an active token with `new_id`, copying its configuration.
+    ///
+    /// The new token takes over the name and expiry. The old one is renamed
+    /// `"<name> (rotated <suffix>)"` (names are unique per project) and
+    /// revoked, or, with `grace_until`, left active until then. Returns
+    /// `false` if the old token is missing or already revoked.
+    pub async fn rotate_token(
+        &self,
+        old: &TokenRow,
+        new_id: &str,
+        grace_until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> anyhow::Result<bool> {
+        let suffix = &old.id[old.id.len().saturating_sub(8)..];
+        let retired_name: String = format!("{} (rotated {})", old.name, suffix)
+            .chars()
+            .take(255)
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+        let renamed = sqlx::query(
+            "UPDATE tokens SET name = $2, updated_at = NOW() WHERE id = $1 AND is_active = true",
+        )
+        .bind(&old.id)
+        .bind(&retired_name)
+        .execute(&mut *tx)
+        .await?;
+        if renamed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(&format!(
+            "INSERT INTO tokens (id, name, expires_at, {cols}) SELECT $2, $3, expires_at, {cols} FROM tokens WHERE id = $1",
+            cols = ROTATION_COPIED_COLUMNS
+        ))
+        .bind(&old.id)
+        .bind(new_id)
+        .bind(&old.name)
+        .execute(&mut *tx)
+        .await?;
+
+        // Spend caps live in their own table; carry them over with their
+        // accumulated usage so rotation doesn't lift or reset any limit.
+        sqlx::query(
+            "INSERT INTO spend_caps (project_id, token_id, period, limit_usd, usage_usd, reset_at) \
+             SELECT project_id, $2, period, limit_usd, usage_usd, reset_at \
+             FROM spend_caps WHERE token_id = $1",
+        )
+        .bind(&old.id)
+        .bind(new_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // A grace window can shorten the old token's life, never extend it.
+        sqlx::query(
+            "UPDATE tokens SET is_active = ($2::timestamptz IS NOT NULL), \
+             expires_at = CASE WHEN $2::timestamptz IS NULL THEN expires_at \
+                               ELSE LEAST(COALESCE(expires_at, $2), $2) END, \
+             updated_at = NOW() WHERE id = $1",
+        )
+        .bind(&old.id)
+        .bind(grace_until)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
     /// Update the circuit breaker configuration for a token.
     /// Returns `true` if the token was found and updated, `false` if not found.
     pub async fn update_circuit_breaker(
@@ -88,6 +224,25 @@ impl PgStore {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Set (or clear, with `None`) a token's gradual migration config.
+    /// Returns `true` if the token was found and updated.
+    pub async fn update_token_migration(
+        &self,
+        token_id: &str,
+        project_id: Uuid,
+        config: Option<serde_json::Value>,
+    ) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE tokens SET migration = $1 WHERE id = $2 AND project_id = $3 AND is_active = true",
+        )
+        .bind(&config)
+        .bind(token_id)
+        .bind(project_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Replace the `policy_ids` array on a token.
     /// Used by the guardrail presets API to attach auto-generated policies.
     pub async fn set_token_policy_ids(
@@ -108,7 +263,7 @@ impl PgStore {
     }
 
     /// Update a token's upstream URL, policy bindings, and log level.
-    /// Used by config import to update an existing token without touching its credentials.
+    /// Used by config import; the credential is only replaced when one is given.
     pub async fn update_token_config(
         &self,
         token_id: &str,
@@ -116,22 +271,24 @@ impl PgStore {
         policy_ids: Vec<Uuid>,
         log_level: i16,
         upstream_url: &str,
+        credential_id: Option<Uuid>,
     ) -> anyhow::Result<bool> {
         let result = sqlx::query(
-            "UPDATE tokens SET policy_ids = $1, log_level = $2, upstream_url = $3, updated_at = NOW() WHERE id = $4 AND project_id = $5 AND is_active = true"
+            "UPDATE tokens SET policy_ids = $1, log_level = $2, upstream_url = $3, credential_id = COALESCE($6, credential_id), updated_at = NOW() WHERE id = $4 AND project_id = $5 AND is_active = true"
         )
         .bind(&policy_ids)
         .bind(log_level)
         .bind(upstream_url)
         .bind(token_id)
         .bind(project_id)
+        .bind(credential_id)
         .execute(&self.pool)
         .await?;
         Ok(result.rows_affected() > 0)
     }
 
-    /// Insert a new token without credentials (used by config-as-code import).
-    /// The token will work in passthrough mode until a credential is attached.
+    /// Insert a new token from config-as-code import. Without a credential
+    /// the token works in passthrough mode until one is attached.
     /// Returns the generated token ID (format: `tok_import_<uuid>`).
     pub async fn insert_token_stub(
         &self,
@@ -140,13 +297,14 @@ impl PgStore {
         upstream_url: &str,
         policy_ids: Vec<Uuid>,
         log_level: i16,
+        credential_id: Option<Uuid>,
     ) -> anyhow::Result<String> {
         let id = format!("tok_import_{}", Uuid::new_v4().simple());
         let token = NewToken {
             id: id.clone(),
             project_id,
             name: name.to_string(),
-            credential_id: None, // no credential — passthrough mode
+            credential_id,
             upstream_url: upstream_url.to_string(),
             scopes: serde_json::json!([]),
             policy_ids,
@@ -157,6 +315,30 @@ impl PgStore {
             tags: None,
             mcp_allowed_tools: None,
             mcp_blocked_tools: None,
+            stream_flush: None,
+            provider_hint: None,
+            request_budget_secs: None,
+            param_defaults: None,
+            session_cost_header: false,
+            strip_body_fields: None,
+            budget_pressure_model_map: None,
+            budget_pressure_threshold_pct: None,
+            stream_ttft_comment: false,
+            test_upstream_override: None,
+            context_window_action: None,
+            enforcement_order: None,
+            forward_trace_headers: None,
+            adaptive_rate_limit: None,
+            migration: None,
+            max_output_tokens_ceiling: None,
+            stream_output_format: None,
+            serve_stale_on_error: false,
+            max_concurrent_streams: None,
+            max_cost_per_request_usd: None,
+            cache_key_ignore_paths: None,
+            json_mode_fallback: false,
+            replay_window_secs: None,
+            capability_action: None,
         };
         self.insert_token(&token).await?;
         Ok(id)
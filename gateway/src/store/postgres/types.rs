@@ -2,6 +2,16 @@ use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Per-tool invocation counts and attributed cost (from `audit_logs.tool_calls`).
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+pub struct ToolUsageStat {
+    pub tool_name: String,
+    pub call_count: i64,
+    pub request_count: i64,
+    pub estimated_tokens: i64,
+    pub estimated_cost_usd: f64,
+}
+
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
 pub struct SpendByDimension {
     pub dimension: String,
@@ -23,6 +33,10 @@ pub struct NewCredential {
     pub secret_nonce: Vec<u8>,
     pub injection_mode: String,
     pub injection_header: String,
+    /// Model patterns this credential may serve (globs). `None` = unrestricted.
+    pub allowed_models: Option<Vec<String>>,
+    /// Shared per-model RPM/TPM limits for the provider account.
+    pub model_rate_limits: Option<serde_json::Value>,
 }
 
 pub struct NewToken {
@@ -46,10 +60,80 @@ pub struct NewToken {
     pub mcp_allowed_tools: Option<serde_json::Value>,
     /// MCP tool blocklist. Takes priority over allowlist. Supports glob patterns.
     pub mcp_blocked_tools: Option<serde_json::Value>,
+    /// SSE write coalescing config. `None` flushes every chunk immediately.
+    pub stream_flush: Option<serde_json::Value>,
+    /// Forces provider classification (e.g. `openai`, `anthropic`, `bedrock`),
+    /// bypassing model-name detection. `None` uses detection.
+    pub provider_hint: Option<String>,
+    /// End-to-end request budget in seconds, including any HITL approval wait.
+    /// NULL = no budget (HITL and upstream timeouts apply independently).
+    pub request_budget_secs: Option<i32>,
+    /// Request-body defaults filled in when the client omits a field (e.g. temperature).
+    /// Unlike an override policy these never replace a client-supplied value.
+    pub param_defaults: Option<serde_json::Value>,
+    /// Return the session's cumulative cost in X-TrueFlow-Session-Cost-USD
+    /// on non-streaming responses that carry X-Session-Id.
+    pub session_cost_header: bool,
+    /// Top-level request body fields removed before forwarding (e.g. client-internal keys
+    /// that upstreams reject).
+    pub strip_body_fields: Option<Vec<String>>,
+    /// Cheaper substitutes applied when remaining budget is low, e.g.
+    /// `{"gpt-4o": "gpt-4o-mini"}`.
+    pub budget_pressure_model_map: Option<serde_json::Value>,
+    /// Remaining-budget percentage at or below which `budget_pressure_model_map`
+    /// applies. Defaults to 20.
+    pub budget_pressure_threshold_pct: Option<i32>,
+    /// Prefix streaming responses with a `: ttft=<ms>ms` SSE comment carrying
+    /// the time-to-first-token measured at the gateway.
+    pub stream_ttft_comment: bool,
+    /// Replacement upstream URL for integration tests. Only honored when
+    /// the gateway runs with TRUEFLOW_ALLOW_TEST_OVERRIDES set.
+    pub test_upstream_override: Option<String>,
+    /// Pre-flight action when the estimated prompt exceeds the model's
+    /// context window: "reject" or "trim". None skips the check.
+    pub context_window_action: Option<String>,
+    /// When spend caps are checked: `policies_first` (default, after the
+    /// policy loop and rate limits) or `budget_first` (before policies and usage counters).
+    pub enforcement_order: Option<String>,
+    /// Client correlation headers (e.g. `X-Correlation-Id`) copied to the
+    /// upstream request alongside the W3C trace context.
+    pub forward_trace_headers: Option<Vec<String>>,
+    /// AIMD adaptive rate limit config (see `proxy::adaptive_limit`). NULL = disabled.
+    pub adaptive_rate_limit: Option<serde_json::Value>,
+    /// Gradual migration to another credential/upstream; see `MigrationConfig`.
+    pub migration: Option<serde_json::Value>,
+    /// Upper bound on output tokens per request: larger `max_tokens` values are
+    /// clamped and requests without one get the ceiling. NULL = no ceiling.
+    pub max_output_tokens_ceiling: Option<i32>,
+    /// Client-facing framing for streamed responses: openai_sse (default),
+    /// ndjson or jsonlines.
+    pub stream_output_format: Option<String>,
+    /// On upstream failure for a cacheable request, answer with the last cached
+    /// response (kept past its TTL for a grace window) instead of an error.
+    pub serve_stale_on_error: bool,
+    /// Cap on this token's concurrent streaming responses. NULL = unlimited.
+    pub max_concurrent_streams: Option<i32>,
+    /// Reject any single request whose worst-case estimated cost exceeds this (USD). NULL = no limit.
+    pub max_cost_per_request_usd: Option<rust_decimal::Decimal>,
+    /// JSON pointer paths (e.g. `/metadata/request_id`) removed from the
+    /// request body before the response cache key is hashed.
+    pub cache_key_ignore_paths: Option<Vec<String>>,
+    /// Emulate `response_format: json_object` for providers without native
+    /// JSON mode: system instruction pre-flight, JSON extraction post-flight.
+    pub json_mode_fallback: bool,
+    /// Replay protection: requests must carry a unique `X-TrueFlow-Nonce` and an
+    /// `X-TrueFlow-Timestamp` within this many seconds. NULL = disabled.
+    pub replay_window_secs: Option<i32>,
+    /// Model capability pre-flight: `strip` or `reject` requests using tools,
+    /// images or JSON mode the model doesn't support. NULL = no check.
+    pub capability_action: Option<String>,
 }
 
 // -- Output structs --
 
+/// A credential's name, `allowed_models` and `model_rate_limits`.
+pub type CredentialModelRestriction = (String, Option<Vec<String>>, Option<serde_json::Value>);
+
 #[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
 pub struct CredentialMeta {
     pub id: Uuid,
@@ -58,9 +142,11 @@ pub struct CredentialMeta {
     pub version: i32,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
+    pub allowed_models: Option<Vec<String>>,
+    pub model_rate_limits: Option<serde_json::Value>,
 }
 
-#[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
 pub struct TokenRow {
     pub id: String,
     pub project_id: Uuid,
@@ -91,6 +177,73 @@ pub struct TokenRow {
     pub mcp_allowed_tools: Option<serde_json::Value>,
     /// MCP tool blocklist. Takes priority over allowlist. Supports glob patterns.
     pub mcp_blocked_tools: Option<serde_json::Value>,
+    /// SSE write coalescing config. `None` flushes every chunk immediately.
+    pub stream_flush: Option<serde_json::Value>,
+    /// Forces provider classification (e.g. `openai`, `anthropic`, `bedrock`),
+    /// bypassing model-name detection. `None` uses detection.
+    pub provider_hint: Option<String>,
+    /// End-to-end request budget in seconds, including any HITL approval wait.
+    /// NULL = no budget (HITL and upstream timeouts apply independently).
+    pub request_budget_secs: Option<i32>,
+    /// Request-body defaults filled in when the client omits a field (e.g. temperature).
+    /// Unlike an override policy these never replace a client-supplied value.
+    pub param_defaults: Option<serde_json::Value>,
+    /// Return the session's cumulative cost in X-TrueFlow-Session-Cost-USD
+    /// on non-streaming responses that carry X-Session-Id.
+    pub session_cost_header: bool,
+    /// Top-level request body fields removed before forwarding (e.g. client-internal keys
+    /// that upstreams reject).
+    pub strip_body_fields: Option<Vec<String>>,
+    /// Cheaper substitutes applied when remaining budget is low, e.g.
+    /// `{"gpt-4o": "gpt-4o-mini"}`.
+    pub budget_pressure_model_map: Option<serde_json::Value>,
+    /// Remaining-budget percentage at or below which `budget_pressure_model_map`
+    /// applies. Defaults to 20.
+    pub budget_pressure_threshold_pct: Option<i32>,
+    /// Prefix streaming responses with a `: ttft=<ms>ms` SSE comment carrying
+    /// the time-to-first-token measured at the gateway.
+    pub stream_ttft_comment: bool,
+    /// Replacement upstream URL for integration tests. Only honored when
+    /// the gateway runs with TRUEFLOW_ALLOW_TEST_OVERRIDES set.
+    pub test_upstream_override: Option<String>,
+    /// Pre-flight action when the estimated prompt exceeds the model's
+    /// context window: "reject" or "trim". None skips the check.
+    pub context_window_action: Option<String>,
+    /// When spend caps are checked: `policies_first` (default, after the
+    /// policy loop and rate limits) or `budget_first` (before policies and usage counters).
+    pub enforcement_order: Option<String>,
+    /// Client correlation headers (e.g. `X-Correlation-Id`) copied to the
+    /// upstream request alongside the W3C trace context.
+    pub forward_trace_headers: Option<Vec<String>>,
+    /// AIMD adaptive rate limit config (see `proxy::adaptive_limit`). NULL = disabled.
+    pub adaptive_rate_limit: Option<serde_json::Value>,
+    /// Gradual migration to another credential/upstream; see `MigrationConfig`.
+    pub migration: Option<serde_json::Value>,
+    /// Upper bound on output tokens per request: larger `max_tokens` values are
+    /// clamped and requests without one get the ceiling. NULL = no ceiling.
+    pub max_output_tokens_ceiling: Option<i32>,
+    /// Client-facing framing for streamed responses: openai_sse (default),
+    /// ndjson or jsonlines.
+    pub stream_output_format: Option<String>,
+    /// On upstream failure for a cacheable request, answer with the last cached
+    /// response (kept past its TTL for a grace window) instead of an error.
+    pub serve_stale_on_error: bool,
+    /// Cap on this token's concurrent streaming responses. NULL = unlimited.
+    pub max_concurrent_streams: Option<i32>,
+    /// Reject any single request whose worst-case estimated cost exceeds this (USD). NULL = no limit.
+    pub max_cost_per_request_usd: Option<rust_decimal::Decimal>,
+    /// JSON pointer paths (e.g. `/metadata/request_id`) removed from the
+    /// request body before the response cache key is hashed.
+    pub cache_key_ignore_paths: Option<Vec<String>>,
+    /// Emulate `response_format: json_object` for providers without native
+    /// JSON mode: system instruction pre-flight, JSON extraction post-flight.
+    pub json_mode_fallback: bool,
+    /// Replay protection: requests must carry a unique `X-TrueFlow-Nonce` and an
+    /// `X-TrueFlow-Timestamp` within this many seconds. NULL = disabled.
+    pub replay_window_secs: Option<i32>,
+    /// Model capability pre-flight: `strip` or `reject` requests using tools,
+    /// images or JSON mode the model doesn't support. NULL = no check.
+    pub capability_action: Option<String>,
 }
 
 #[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
@@ -102,6 +255,7 @@ pub struct PolicyRow {
     pub phase: String,
     pub rules: serde_json::Value,
     pub retry: Option<serde_json::Value>,
+    pub priority: i32,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
 }
@@ -173,6 +327,37 @@ pub struct OidcProviderRow {
     pub default_role: String,
     pub default_scopes: String,
     pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fields for a new OIDC provider.
+#[derive(Debug)]
+pub struct NewOidcProvider {
+    pub org_id: Uuid,
+    pub name: String,
+    pub issuer_url: String,
+    pub client_id: String,
+    pub jwks_uri: Option<String>,
+    pub audience: Option<String>,
+    pub claim_mapping: serde_json::Value,
+    pub default_role: String,
+    pub default_scopes: String,
+    pub enabled: bool,
+}
+
+/// Partial update for an OIDC provider. `None` keeps the current value.
+#[derive(Debug, Default)]
+pub struct OidcProviderPatch {
+    pub name: Option<String>,
+    pub issuer_url: Option<String>,
+    pub client_id: Option<String>,
+    pub jwks_uri: Option<String>,
+    pub audience: Option<String>,
+    pub claim_mapping: Option<serde_json::Value>,
+    pub default_role: Option<String>,
+    pub default_scopes: Option<String>,
+    pub enabled: Option<bool>,
 }
 
 #[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
@@ -258,6 +443,8 @@ pub struct ProjectRow {
     pub org_id: Uuid,
     pub name: String,
     pub created_at: DateTime<Utc>,
+    /// Policies merged into new tokens' `policy_ids` (see `create_token`).
+    pub default_policy_ids: Vec<Uuid>,
 }
 
 #[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
@@ -350,6 +537,7 @@ pub struct ModelPricingRow {
     pub model_pattern: String,
     pub input_per_m: rust_decimal::Decimal,
     pub output_per_m: rust_decimal::Decimal,
+    pub image_per_unit: Option<rust_decimal::Decimal>,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -410,3 +598,48 @@ pub struct NewPromptVersion {
     pub commit_message: String,
     pub created_by: String,
 }
+
+// ── Upstream Health History ──────────────────────────────────
+
+/// One aggregation window of real traffic to an upstream.
+#[derive(Debug, Clone)]
+pub struct NewUpstreamHealthSample {
+    pub upstream_url: String,
+    pub bucket_start: DateTime<Utc>,
+    pub requests: i32,
+    pub failures: i32,
+    /// Bounded reservoir of observed upstream latencies in the window.
+    pub latencies_ms: Vec<i32>,
+}
+
+/// A circuit-breaker state change for one token's view of an upstream.
+#[derive(Debug, Clone)]
+pub struct NewUpstreamTransition {
+    pub upstream_url: String,
+    pub token_id: String,
+    pub healthy: bool,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct UpstreamTransitionRow {
+    pub token_id: String,
+    pub healthy: bool,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Availability and latency for an upstream over a time window.
+#[derive(Debug, Serialize)]
+pub struct UpstreamHealthHistory {
+    pub upstream_url: String,
+    pub window_hours: i32,
+    pub requests: i64,
+    pub failures: i64,
+    /// Share of requests that did not fail, 0–100. `None` when there was no traffic.
+    pub availability_pct: Option<f64>,
+    pub latency_p50_ms: Option<f64>,
+    pub latency_p90_ms: Option<f64>,
+    pub latency_p99_ms: Option<f64>,
+    /// Circuit open/close events in the window, newest first.
+    pub transitions: Vec<UpstreamTransitionRow>,
+}
@@ -0,0 +1,126 @@
+use super::types::{
+    NewUpstreamHealthSample, NewUpstreamTransition, UpstreamHealthHistory, UpstreamTransitionRow,
+};
+use super::PgStore;
+
+/// Cap on transitions returned by the history endpoint.
+const MAX_TRANSITIONS: i64 = 500;
+
+impl PgStore {
+    /// Persist a batch of per-upstream traffic samples and circuit transitions.
+    pub async fn insert_upstream_health(
+        &self,
+        samples: &[NewUpstreamHealthSample],
+        transitions: &[NewUpstreamTransition],
+    ) -> anyhow::Result<()> {
+        if samples.is_empty() && transitions.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+        for s in samples {
+            sqlx::query(
+                "INSERT INTO upstream_health_samples (upstream_url, bucket_start, requests, failures, latencies_ms)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(&s.upstream_url)
+            .bind(s.bucket_start)
+            .bind(s.requests)
+            .bind(s.failures)
+            .bind(&s.latencies_ms)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for t in transitions {
+            sqlx::query(
+                "INSERT INTO upstream_health_transitions (upstream_url, token_id, healthy, occurred_at)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(&t.upstream_url)
+            .bind(&t.token_id)
+            .bind(t.healthy)
+            .bind(t.occurred_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Availability, latency percentiles and transitions for one upstream.
+    pub async fn get_upstream_health_history(
+        &self,
+        upstream_url: &str,
+        hours: i32,
+    ) -> anyhow::Result<UpstreamHealthHistory> {
+        let (requests, failures): (i64, i64) = sqlx::query_as(
+            r#"SELECT COALESCE(SUM(requests), 0)::BIGINT, COALESCE(SUM(failures), 0)::BIGINT
+               FROM upstream_health_samples
+               WHERE upstream_url = $1
+                 AND bucket_start > NOW() - make_interval(hours => $2)"#,
+        )
+        .bind(upstream_url)
+        .bind(hours)
+        .fetch_one(self.read_pool())
+        .await?;
+
+        let (p50, p90, p99): (Option<f64>, Option<f64>, Option<f64>) = sqlx::query_as(
+            r#"SELECT
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY l),
+                percentile_cont(0.9) WITHIN GROUP (ORDER BY l),
+                percentile_cont(0.99) WITHIN GROUP (ORDER BY l)
+               FROM upstream_health_samples, unnest(latencies_ms) AS l
+               WHERE upstream_url = $1
+                 AND bucket_start > NOW() - make_interval(hours => $2)"#,
+        )
+        .bind(upstream_url)
+        .bind(hours)
+        .fetch_one(self.read_pool())
+        .await?;
+
+        let transitions = sqlx::query_as::<_, UpstreamTransitionRow>(
+            r#"SELECT token_id, healthy, occurred_at
+               FROM upstream_health_transitions
+               WHERE upstream_url = $1
+                 AND occurred_at > NOW() - make_interval(hours => $2)
+               ORDER BY occurred_at DESC
+               LIMIT $3"#,
+        )
+        .bind(upstream_url)
+        .bind(hours)
+        .bind(MAX_TRANSITIONS)
+        .fetch_all(self.read_pool())
+        .await?;
+
+        let availability_pct =
+            (requests > 0).then(|| (requests - failures) as f64 / requests as f64 * 100.0);
+
+        Ok(UpstreamHealthHistory {
+            upstream_url: upstream_url.to_string(),
+            window_hours: hours,
+            requests,
+            failures,
+            availability_pct,
+            latency_p50_ms: p50,
+            latency_p90_ms: p90,
+            latency_p99_ms: p99,
+            transitions,
+        })
+    }
+
+    /// Delete health history older than `retention_days`. Returns rows removed.
+    pub async fn prune_upstream_health(&self, retention_days: i32) -> anyhow::Result<u64> {
+        let samples = sqlx::query(
+            "DELETE FROM upstream_health_samples WHERE bucket_start < NOW() - make_interval(days => $1)",
+        )
+        .bind(retention_days)
+        .execute(&self.pool)
+        .await?;
+        let transitions = sqlx::query(
+            "DELETE FROM upstream_health_transitions WHERE occurred_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(retention_days)
+        .execute(&self.pool)
+        .await?;
+        Ok(samples.rows_affected() + transitions.rows_affected())
+    }
+}
@@ -89,7 +89,7 @@ impl PgStore {
         .bind(org_id)
         .bind(period_start_dt)
         .bind(period_end_dt)
-        .fetch_one(&self.pool)
+        .fetch_one(self.read_pool())
         .await?;
 
         use sqlx::Row;
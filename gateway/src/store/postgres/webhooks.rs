@@ -0,0 +1,29 @@
+use super::PgStore;
+use crate::notification::webhook::WebhookTarget;
+use uuid::Uuid;
+
+impl PgStore {
+    /// Active webhooks for a project, with their signing secrets and event
+    /// subscriptions, for delivery.
+    pub async fn list_webhook_targets(
+        &self,
+        project_id: Uuid,
+    ) -> anyhow::Result<Vec<WebhookTarget>> {
+        let rows = sqlx::query_as::<_, (String, Option<String>, Vec<String>)>(
+            r#"SELECT url, signing_secret, events
+               FROM webhooks
+               WHERE project_id = $1 AND is_active = true"#,
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(url, signing_secret, events)| WebhookTarget {
+                url,
+                signing_secret,
+                events,
+            })
+            .collect())
+    }
+}
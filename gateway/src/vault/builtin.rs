@@ -6,24 +6,79 @@ use async_trait::async_trait;
 use rand::RngCore;
 use sqlx::PgPool;
 
+use super::cache::SecretCache;
+
 pub type EncryptedBlob = (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>);
 
+/// Authenticated decryption of a stored credential failed.
+///
+/// AES-GCM only fails here when the ciphertext, nonce or master key don't
+/// match — i.e. `TRUEFLOW_MASTER_KEY` was changed/misconfigured or the row is
+/// corrupted. Callers can `downcast_ref` the `anyhow::Error` to tell this apart
+/// from a missing row or a database failure.
+#[derive(Debug, thiserror::Error)]
+#[error("credential decryption failed: {0}")]
+pub struct DecryptionError(pub String);
+
 /// Built-in vault using AES-256-GCM envelope encryption in PostgreSQL.
 pub struct BuiltinStore {
     crypto: VaultCrypto,
     pool: PgPool,
+    cache: SecretCache,
 }
 
 impl BuiltinStore {
     pub fn new(master_key_hex: &str, pool: PgPool) -> anyhow::Result<Self> {
         let crypto = VaultCrypto::new(master_key_hex)?;
-        Ok(Self { crypto, pool })
+        Ok(Self {
+            crypto,
+            pool,
+            cache: SecretCache::disabled(),
+        })
+    }
+
+    /// Keep decrypted credentials in memory for `ttl_secs` (0 = disabled).
+    pub fn with_cache_ttl(mut self, ttl_secs: u64) -> Self {
+        self.cache = SecretCache::new(ttl_secs);
+        self
+    }
+
+    /// Shared handle to the decrypted-credential cache, for invalidation from
+    /// code paths that modify credentials without going through the store.
+    pub fn secret_cache(&self) -> SecretCache {
+        self.cache.clone()
     }
 
     /// Delegate to VaultCrypto for API handler use.
     pub fn encrypt_string(&self, plaintext: &str) -> anyhow::Result<EncryptedBlob> {
         self.crypto.encrypt_string(plaintext)
     }
+
+    /// Test-decrypt one active credential to verify the master key.
+    ///
+    /// Returns `Ok(None)` when there are no credentials to check, `Ok(Some(id))`
+    /// when the sampled credential decrypts, and the `DecryptionError` otherwise.
+    pub async fn self_test(&self) -> anyhow::Result<Option<uuid::Uuid>> {
+        let row = sqlx::query_as::<_, SampleCredentialRow>(
+            "SELECT id, encrypted_dek, dek_nonce, encrypted_secret, secret_nonce FROM credentials WHERE is_active = true ORDER BY created_at DESC LIMIT 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        self.crypto
+            .decrypt_string(
+                &row.encrypted_dek,
+                &row.dek_nonce,
+                &row.encrypted_secret,
+                &row.secret_nonce,
+            )
+            .map_err(|e| e.context(format!("credential {}", row.id)))?;
+        Ok(Some(row.id))
+    }
 }
 
 pub struct VaultCrypto {
@@ -87,8 +142,13 @@ impl VaultCrypto {
         let d_nonce = Nonce::from_slice(dek_nonce);
         let dek_bytes = kek_cipher
             .decrypt(d_nonce, encrypted_dek)
-            .map_err(|e| anyhow::anyhow!("DEK decryption failed: {}", e))?;
+            .map_err(|e| DecryptionError(format!("DEK decryption failed: {}", e)))?;
 
+        if dek_bytes.len() != 32 {
+            return Err(
+                DecryptionError(format!("DEK has invalid length {}", dek_bytes.len())).into(),
+            );
+        }
         let mut dek = [0u8; 32];
         dek.copy_from_slice(&dek_bytes);
 
@@ -105,12 +165,13 @@ impl VaultCrypto {
         let s_nonce = Nonce::from_slice(secret_nonce);
         let plaintext_bytes = secret_cipher
             .decrypt(s_nonce, encrypted_secret)
-            .map_err(|e| anyhow::anyhow!("secret decryption failed: {}", e))?;
+            .map_err(|e| DecryptionError(format!("secret decryption failed: {}", e)))?;
 
         // Zero the DEK (zeroize prevents optimizer dead-store elimination)
         dek.zeroize();
 
-        Ok(String::from_utf8(plaintext_bytes)?)
+        String::from_utf8(plaintext_bytes)
+            .map_err(|_| DecryptionError("decrypted secret is not valid UTF-8".into()).into())
     }
 }
 
@@ -131,6 +192,10 @@ impl super::SecretStore for BuiltinStore {
     }
 
     async fn retrieve(&self, id: &str) -> anyhow::Result<(String, String, String, String)> {
+        if let Some(hit) = self.cache.get(id) {
+            return Ok(hit);
+        }
+
         let row = sqlx::query_as::<_, CredentialRow>(
             "SELECT encrypted_dek, dek_nonce, encrypted_secret, secret_nonce, provider, injection_mode, injection_header FROM credentials WHERE id = $1 AND is_active = true"
         )
@@ -145,6 +210,14 @@ impl super::SecretStore for BuiltinStore {
             &row.secret_nonce,
         )?;
 
+        self.cache.insert(
+            id,
+            &secret,
+            &row.provider,
+            &row.injection_mode,
+            &row.injection_header,
+        );
+
         Ok((
             secret,
             row.provider,
@@ -159,8 +232,13 @@ impl super::SecretStore for BuiltinStore {
             .bind(project_id)
             .execute(&self.pool)
             .await?;
+        self.cache.invalidate(id);
         Ok(())
     }
+
+    fn invalidate_cached(&self, id: &str) {
+        self.cache.invalidate(id);
+    }
 }
 
 #[derive(sqlx::FromRow)]
@@ -174,6 +252,15 @@ struct CredentialRow {
     injection_header: String,
 }
 
+#[derive(sqlx::FromRow)]
+struct SampleCredentialRow {
+    id: uuid::Uuid,
+    encrypted_dek: Vec<u8>,
+    dek_nonce: Vec<u8>,
+    encrypted_secret: Vec<u8>,
+    secret_nonce: Vec<u8>,
+}
+
 fn generate_nonce() -> [u8; 12] {
     let mut nonce = [0u8; 12];
     OsRng.fill_bytes(&mut nonce);
@@ -291,6 +378,28 @@ mod tests {
         );
     }
 
+    /// Decryption failures are distinguishable from other vault errors.
+    #[test]
+    fn test_decryption_failure_is_typed() {
+        let crypto_a = VaultCrypto::new(TEST_KEY).unwrap();
+        let crypto_b =
+            VaultCrypto::new("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")
+                .unwrap();
+        let (enc_dek, dek_nonce, mut enc_secret, secret_nonce) =
+            crypto_a.encrypt_string("sk-secret").unwrap();
+
+        let err = crypto_b
+            .decrypt_string(&enc_dek, &dek_nonce, &enc_secret, &secret_nonce)
+            .unwrap_err();
+        assert!(err.downcast_ref::<DecryptionError>().is_some());
+
+        enc_secret[0] ^= 0x01;
+        let err = crypto_a
+            .decrypt_string(&enc_dek, &dek_nonce, &enc_secret, &secret_nonce)
+            .unwrap_err();
+        assert!(err.downcast_ref::<DecryptionError>().is_some());
+    }
+
     /// Truncated ciphertext should fail, not panic.
     #[test]
     fn test_truncated_ciphertext_rejected() {
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use zeroize::Zeroizing;
+
+/// Upper bound on the decrypted-credential TTL. Plaintext secrets should only
+/// live in memory for seconds, so larger configured values are clamped.
+pub const MAX_SECRET_CACHE_TTL_SECS: u64 = 60;
+
+/// Short-lived in-memory cache of decrypted credentials, keyed by credential id.
+///
+/// Lets hot credentials skip the per-request AES-GCM (or KMS/Vault) round-trip.
+/// Plaintext is held in `Zeroizing` buffers so it is wiped when an entry is
+/// evicted, invalidated or the cache is dropped. A TTL of zero disables caching.
+///
+/// Clones share the same underlying map, so the rotation scheduler and the API
+/// handlers can invalidate entries the proxy path reads from.
+#[derive(Clone)]
+pub struct SecretCache {
+    entries: Arc<DashMap<String, CachedSecret>>,
+    ttl: Duration,
+}
+
+struct CachedSecret {
+    secret: Zeroizing<String>,
+    provider: String,
+    injection_mode: String,
+    injection_header: String,
+    expires_at: Instant,
+}
+
+impl SecretCache {
+    pub fn new(ttl_secs: u64) -> Self {
+        Self {
+            entries: Arc::new(DashMap::new()),
+            ttl: Duration::from_secs(ttl_secs.min(MAX_SECRET_CACHE_TTL_SECS)),
+        }
+    }
+
+    /// A cache that never stores anything.
+    pub fn disabled() -> Self {
+        Self::new(0)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Return a copy of the cached credential if it hasn't expired.
+    /// Expired entries are removed (and zeroized) on lookup.
+    pub fn get(&self, id: &str) -> Option<(String, String, String, String)> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let now = Instant::now();
+        if let Some(entry) = self.entries.get(id) {
+            if entry.expires_at > now {
+                return Some((
+                    entry.secret.to_string(),
+                    entry.provider.clone(),
+                    entry.injection_mode.clone(),
+                    entry.injection_header.clone(),
+                ));
+            }
+        }
+        self.entries.remove_if(id, |_, e| e.expires_at <= now);
+        None
+    }
+
+    pub fn insert(
+        &self,
+        id: &str,
+        secret: &str,
+        provider: &str,
+        injection_mode: &str,
+        injection_header: &str,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let now = Instant::now();
+        // Opportunistic sweep so credentials that stop being requested don't
+        // keep their plaintext around indefinitely.
+        self.entries.retain(|_, e| e.expires_at > now);
+        self.entries.insert(
+            id.to_string(),
+            CachedSecret {
+                secret: Zeroizing::new(secret.to_string()),
+                provider: provider.to_string(),
+                injection_mode: injection_mode.to_string(),
+                injection_header: injection_header.to_string(),
+                expires_at: now + self.ttl,
+            },
+        );
+    }
+
+    /// Drop a single credential, e.g. after it was rotated or deleted.
+    pub fn invalidate(&self, id: &str) {
+        self.entries.remove(id);
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_cache_stores_nothing() {
+        let cache = SecretCache::disabled();
+        cache.insert("c1", "sk-secret", "openai", "bearer", "Authorization");
+        assert!(cache.get("c1").is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_hit_and_invalidate() {
+        let cache = SecretCache::new(5);
+        cache.insert("c1", "sk-secret", "openai", "bearer", "Authorization");
+        let (secret, provider, mode, header) = cache.get("c1").unwrap();
+        assert_eq!(secret, "sk-secret");
+        assert_eq!(provider, "openai");
+        assert_eq!(mode, "bearer");
+        assert_eq!(header, "Authorization");
+
+        // Clones share storage, so invalidation from another handle is visible.
+        cache.clone().invalidate("c1");
+        assert!(cache.get("c1").is_none());
+
+        cache.insert("c2", "sk-other", "anthropic", "header", "x-api-key");
+        cache.clear();
+        assert!(cache.get("c2").is_none());
+    }
+
+    #[test]
+    fn test_expired_entries_are_evicted() {
+        let cache = SecretCache::new(1);
+        cache.insert("c1", "sk-secret", "openai", "bearer", "Authorization");
+        if let Some(mut e) = cache.entries.get_mut("c1") {
+            e.expires_at = Instant::now() - Duration::from_millis(1);
+        }
+        assert!(cache.get("c1").is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_ttl_is_clamped() {
+        let cache = SecretCache::new(3600);
+        assert_eq!(cache.ttl, Duration::from_secs(MAX_SECRET_CACHE_TTL_SECS));
+    }
+}
@@ -0,0 +1,457 @@
+//! GCP Secret Manager backend (`TRUEFLOW_VAULT_BACKEND=gcp`).
+//!
+//! The secret of credential `<id>` is the latest version of the secret
+//! `<prefix><id>` in the configured project. Injection metadata lives in the
+//! secret's labels; label values only allow lowercase letters, digits, `_`
+//! and `-`, so header names are stored lowercased (HTTP headers are
+//! case-insensitive anyway):
+//!
+//! | Label | Default when absent |
+//! |---|---|
+//! | `provider` | `custom` |
+//! | `injection_mode` | `bearer` |
+//! | `injection_header` | `Authorization` |
+//! | `project_id` | any project may delete the secret |
+//!
+//! Access tokens come from the service-account key file named by
+//! `GOOGLE_APPLICATION_CREDENTIALS`, else from the GCE/GKE metadata server,
+//! and are reused until a minute before they expire.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use super::cache::SecretCache;
+
+/// Secret Manager REST endpoint.
+const ENDPOINT: &str = "https://secretmanager.googleapis.com";
+
+/// Default secret name prefix (`<prefix><credential_id>`).
+pub const DEFAULT_PREFIX: &str = "trueflow-";
+
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// Refresh access tokens this long before they expire.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+const DEFAULT_PROVIDER: &str = "custom";
+const DEFAULT_INJECTION_MODE: &str = "bearer";
+const DEFAULT_INJECTION_HEADER: &str = "Authorization";
+
+/// Maximum length of a GCP label value.
+const MAX_LABEL_LEN: usize = 63;
+
+/// Injection metadata of a stored credential, kept in secret labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretMetadata {
+    pub provider: String,
+    pub injection_mode: String,
+    pub injection_header: String,
+    /// Project allowed to delete the secret; `None` allows any.
+    pub project_id: Option<uuid::Uuid>,
+}
+
+impl Default for SecretMetadata {
+    fn default() -> Self {
+        Self {
+            provider: DEFAULT_PROVIDER.to_string(),
+            injection_mode: DEFAULT_INJECTION_MODE.to_string(),
+            injection_header: DEFAULT_INJECTION_HEADER.to_string(),
+            project_id: None,
+        }
+    }
+}
+
+impl SecretMetadata {
+    /// Secret labels for this metadata. Fails on values GCP labels can't hold.
+    pub fn to_labels(&self) -> anyhow::Result<BTreeMap<String, String>> {
+        let mut labels = BTreeMap::new();
+        for (key, value) in [
+            ("provider", self.provider.as_str()),
+            ("injection_mode", self.injection_mode.as_str()),
+            ("injection_header", self.injection_header.as_str()),
+        ] {
+            labels.insert(key.to_string(), label_value(key, value)?);
+        }
+        if let Some(project_id) = self.project_id {
+            labels.insert("project_id".to_string(), project_id.to_string());
+        }
+        Ok(labels)
+    }
+
+    /// Metadata from secret labels, with defaults for missing ones. An
+    /// unparsable `project_id` is an error rather than "any project".
+    pub fn from_labels(labels: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let get = |key: &str, default: &str| {
+            labels
+                .get(key)
+                .filter(|v| !v.is_empty())
+                .cloned()
+                .unwrap_or_else(|| default.to_string())
+        };
+        let project_id = labels
+            .get("project_id")
+            .map(|id| uuid::Uuid::parse_str(id).context("invalid project_id label"))
+            .transpose()?;
+        Ok(Self {
+            provider: get("provider", DEFAULT_PROVIDER),
+            injection_mode: get("injection_mode", DEFAULT_INJECTION_MODE),
+            injection_header: get("injection_header", DEFAULT_INJECTION_HEADER),
+            project_id,
+        })
+    }
+}
+
+/// Lowercase `value` and check it against GCP's label value rules.
+fn label_value(key: &str, value: &str) -> anyhow::Result<String> {
+    let value = value.to_ascii_lowercase();
+    if value.len() > MAX_LABEL_LEN
+        || !value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+    {
+        anyhow::bail!(
+            "{} '{}' can't be stored as a secret label (lowercase letters, digits, '_' and '-', max {} chars)",
+            key,
+            value,
+            MAX_LABEL_LEN
+        );
+    }
+    Ok(value)
+}
+
+/// Fields used from a service-account key file.
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+enum TokenSource {
+    MetadataServer,
+    ServiceAccount(ServiceAccountKey),
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(serde::Serialize)]
+struct JwtClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+/// Credentials stored in GCP Secret Manager.
+pub struct GcpSecretManagerStore {
+    project: String,
+    prefix: String,
+    http: reqwest::Client,
+    token_source: TokenSource,
+    token: Mutex<Option<(String, Instant)>>,
+    cache: SecretCache,
+}
+
+impl GcpSecretManagerStore {
+    /// Store for secrets in `project`, authenticating with
+    /// `GOOGLE_APPLICATION_CREDENTIALS` if set, else the metadata server.
+    pub fn new(project: &str, prefix: &str) -> anyhow::Result<Self> {
+        if project.trim().is_empty() {
+            anyhow::bail!("GCP Secret Manager project must be set");
+        }
+        let token_source = match std::env::var("GOOGLE_APPLICATION_CREDENTIALS") {
+            Ok(path) if !path.is_empty() => {
+                let raw = std::fs::read_to_string(&path)
+                    .with_context(|| format!("reading GOOGLE_APPLICATION_CREDENTIALS {}", path))?;
+                let key: ServiceAccountKey = serde_json::from_str(&raw)
+                    .context("GOOGLE_APPLICATION_CREDENTIALS is not a service-account key")?;
+                TokenSource::ServiceAccount(key)
+            }
+            _ => TokenSource::MetadataServer,
+        };
+        Ok(Self {
+            project: project.to_string(),
+            prefix: prefix.to_string(),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()?,
+            token_source,
+            token: Mutex::new(None),
+            cache: SecretCache::disabled(),
+        })
+    }
+
+    /// Keep fetched credentials in memory for `ttl_secs` (0 = disabled).
+    pub fn with_cache_ttl(mut self, ttl_secs: u64) -> Self {
+        self.cache = SecretCache::new(ttl_secs);
+        self
+    }
+
+    fn secret_url(&self, id: &str) -> String {
+        format!(
+            "{}/v1/projects/{}/secrets/{}{}",
+            ENDPOINT, self.project, self.prefix, id
+        )
+    }
+
+    /// A valid access token, fetched again shortly before the cached one expires.
+    async fn access_token(&self) -> anyhow::Result<String> {
+        let mut cached = self.token.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() + TOKEN_REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+        let response: TokenResponse = match &self.token_source {
+            TokenSource::MetadataServer => {
+                self.http
+                    .get(METADATA_TOKEN_URL)
+                    .header("Metadata-Flavor", "Google")
+                    .send()
+                    .await
+                    .context("GCP metadata server unreachable")?
+                    .error_for_status()?
+                    .json()
+                    .await?
+            }
+            TokenSource::ServiceAccount(key) => {
+                let token_uri = key.token_uri.as_deref().unwrap_or(DEFAULT_TOKEN_URI);
+                let now = chrono::Utc::now().timestamp();
+                let claims = JwtClaims {
+                    iss: &key.client_email,
+                    scope: SCOPE,
+                    aud: token_uri,
+                    iat: now,
+                    exp: now + 3600,
+                };
+                let assertion = jsonwebtoken::encode(
+                    &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+                    &claims,
+                    &jsonwebtoken::EncodingKey::from_rsa_pem(key.private_key.as_bytes())?,
+                )?;
+                self.http
+                    .post(token_uri)
+                    .form(&[
+                        ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                        ("assertion", assertion.as_str()),
+                    ])
+                    .send()
+                    .await
+                    .context("GCP token endpoint unreachable")?
+                    .error_for_status()?
+                    .json()
+                    .await?
+            }
+        };
+        let expires_at = Instant::now() + Duration::from_secs(response.expires_in);
+        *cached = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+
+    /// Send a Secret Manager request and return the JSON response body.
+    async fn call(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<Value> {
+        let mut request = self
+            .http
+            .request(method, url)
+            .bearer_auth(self.access_token().await?);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            anyhow::bail!("secret manager returned {}: {}", status, detail);
+        }
+        let text = response.text().await?;
+        if text.is_empty() {
+            return Ok(Value::Null);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    async fn metadata(&self, id: &str) -> anyhow::Result<SecretMetadata> {
+        let secret = self
+            .call(reqwest::Method::GET, &self.secret_url(id), None)
+            .await
+            .with_context(|| format!("reading secret for credential {}", id))?;
+        let labels: HashMap<String, String> = secret
+            .get("labels")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default();
+        SecretMetadata::from_labels(&labels)
+    }
+
+    /// Create the secret for credential `id` with its first version.
+    #[allow(dead_code)]
+    pub async fn store_credential(
+        &self,
+        id: &str,
+        plaintext: &str,
+        metadata: &SecretMetadata,
+    ) -> anyhow::Result<()> {
+        let create_url = format!(
+            "{}/v1/projects/{}/secrets?secretId={}{}",
+            ENDPOINT, self.project, self.prefix, id
+        );
+        let secret = serde_json::json!({
+            "replication": {"automatic": {}},
+            "labels": metadata.to_labels()?,
+        });
+        self.call(reqwest::Method::POST, &create_url, Some(secret))
+            .await
+            .with_context(|| format!("creating secret for credential {}", id))?;
+        let payload = base64::engine::general_purpose::STANDARD.encode(plaintext);
+        self.call(
+            reqwest::Method::POST,
+            &format!("{}:addVersion", self.secret_url(id)),
+            Some(serde_json::json!({"payload": {"data": payload}})),
+        )
+        .await
+        .with_context(|| format!("adding secret version for credential {}", id))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl super::SecretStore for GcpSecretManagerStore {
+    async fn store(&self, plaintext: &str) -> anyhow::Result<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.store_credential(&id, plaintext, &SecretMetadata::default())
+            .await?;
+        Ok(id)
+    }
+
+    async fn retrieve(&self, id: &str) -> anyhow::Result<(String, String, String, String)> {
+        if let Some(hit) = self.cache.get(id) {
+            return Ok(hit);
+        }
+        let metadata = self.metadata(id).await?;
+        let version = self
+            .call(
+                reqwest::Method::GET,
+                &format!("{}/versions/latest:access", self.secret_url(id)),
+                None,
+            )
+            .await
+            .with_context(|| format!("accessing secret for credential {}", id))?;
+        let data = version
+            .pointer("/payload/data")
+            .and_then(Value::as_str)
+            .context("secret version has no payload")?;
+        let secret = String::from_utf8(base64::engine::general_purpose::STANDARD.decode(data)?)
+            .context("secret payload is not valid UTF-8")?;
+
+        self.cache.insert(
+            id,
+            &secret,
+            &metadata.provider,
+            &metadata.injection_mode,
+            &metadata.injection_header,
+        );
+        Ok((
+            secret,
+            metadata.provider,
+            metadata.injection_mode,
+            metadata.injection_header,
+        ))
+    }
+
+    async fn delete(&self, id: &str, project_id: uuid::Uuid) -> anyhow::Result<()> {
+        let metadata = self.metadata(id).await?;
+        if metadata.project_id.is_some_and(|owner| owner != project_id) {
+            anyhow::bail!("credential {} belongs to another project", id);
+        }
+        self.call(reqwest::Method::DELETE, &self.secret_url(id), None)
+            .await
+            .with_context(|| format!("deleting secret for credential {}", id))?;
+        self.cache.invalidate(id);
+        Ok(())
+    }
+
+    fn invalidate_cached(&self, id: &str) {
+        self.cache.invalidate(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_round_trip_to_metadata() {
+        let project_id = uuid::Uuid::new_v4();
+        let metadata = SecretMetadata {
+            provider: "anthropic".to_string(),
+            injection_mode: "header".to_string(),
+            injection_header: "X-Api-Key".to_string(),
+            project_id: Some(project_id),
+        };
+        let labels = metadata.to_labels().unwrap();
+        assert_eq!(labels["injection_header"], "x-api-key");
+        assert_eq!(labels["project_id"], project_id.to_string());
+
+        let labels: HashMap<_, _> = labels.into_iter().collect();
+        let parsed = SecretMetadata::from_labels(&labels).unwrap();
+        assert_eq!(parsed.provider, "anthropic");
+        assert_eq!(parsed.injection_mode, "header");
+        assert_eq!(parsed.injection_header, "x-api-key");
+        assert_eq!(parsed.project_id, Some(project_id));
+    }
+
+    #[test]
+    fn test_missing_labels_use_defaults() {
+        let parsed = SecretMetadata::from_labels(&HashMap::new()).unwrap();
+        assert_eq!(parsed, SecretMetadata::default());
+        assert_eq!(parsed.injection_mode, "bearer");
+        assert_eq!(parsed.injection_header, "Authorization");
+
+        let labels = HashMap::from([
+            ("provider".to_string(), "openai".to_string()),
+            ("injection_mode".to_string(), String::new()),
+        ]);
+        let parsed = SecretMetadata::from_labels(&labels).unwrap();
+        assert_eq!(parsed.provider, "openai");
+        assert_eq!(parsed.injection_mode, "bearer");
+    }
+
+    #[test]
+    fn test_invalid_labels_are_rejected() {
+        let metadata = SecretMetadata {
+            injection_header: "x.api.key".to_string(),
+            ..Default::default()
+        };
+        assert!(metadata.to_labels().is_err());
+        let metadata = SecretMetadata {
+            provider: "p".repeat(64),
+            ..Default::default()
+        };
+        assert!(metadata.to_labels().is_err());
+
+        let labels = HashMap::from([("project_id".to_string(), "not-a-uuid".to_string())]);
+        assert!(SecretMetadata::from_labels(&labels).is_err());
+    }
+}
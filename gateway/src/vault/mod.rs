@@ -1,10 +1,13 @@
 pub mod builtin;
+pub mod cache;
+pub mod gcp_secret_manager;
 
 use async_trait::async_trait;
 use uuid::Uuid;
 
 /// Abstraction over secret storage backends.
-/// Implementation: BuiltinStore (AES-256-GCM in PG).
+/// Implementations: BuiltinStore (AES-256-GCM in PG) and
+/// GcpSecretManagerStore, selected by `TRUEFLOW_VAULT_BACKEND`.
 /// Future: HashiCorp Vault, AWS KMS.
 #[async_trait]
 pub trait SecretStore: Send + Sync {
@@ -19,4 +22,8 @@ pub trait SecretStore: Send + Sync {
     /// Delete a stored secret. Requires project_id for authorization.
     #[allow(dead_code)]
     async fn delete(&self, id: &str, project_id: Uuid) -> anyhow::Result<()>;
+
+    /// Drop any cached plaintext for `id`, e.g. after its credential was
+    /// deleted, so the next `retrieve` goes back to the backend.
+    fn invalidate_cached(&self, id: &str);
 }
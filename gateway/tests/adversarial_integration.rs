@@ -36,6 +36,7 @@ fn make_ctx<'a>(
         headers,
         body,
         body_size: body.map(|b| b.to_string().len()).unwrap_or(0),
+        estimated_cost_usd: None,
         agent_name: Some("test-agent"),
         token_id: "tok_integ_test",
         token_name: "Integration Test Token",
@@ -116,6 +117,7 @@ fn test_redact_and_sanitize_no_double_redaction() {
         fields: vec![],
         on_match: RedactOnMatch::Redact,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({
         "messages": [{"role": "user", "content": "SSN: 123-45-6789"}]
@@ -297,6 +299,7 @@ fn test_redact_both_direction_applies_to_request_and_response() {
         fields: vec![],
         on_match: RedactOnMatch::Redact,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
 
     // Request body
@@ -343,6 +346,7 @@ fn test_block_mode_short_circuits_on_match() {
         fields: vec![],
         on_match: RedactOnMatch::Block,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({
         "messages": [{"role": "user", "content": "Card 4111111111111111 and SSN 123-45-6789"}]
@@ -153,6 +153,13 @@ fn test_every_app_error_variant_correct_status() {
             StatusCode::BAD_GATEWAY,
             "CredentialMissing → 502",
         ),
+        (
+            AppError::CredentialDecryptionFailed {
+                credential_id: "c".into(),
+            },
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "CredentialDecryptionFailed → 500",
+        ),
         (
             AppError::PolicyDenied {
                 policy: "p".into(),
@@ -166,24 +173,62 @@ fn test_every_app_error_variant_correct_status() {
             StatusCode::FORBIDDEN,
             "Forbidden → 403",
         ),
+        (
+            AppError::ModelNotAllowed {
+                model: "gpt-4o".into(),
+                allowed: vec!["claude-*".into()],
+            },
+            StatusCode::FORBIDDEN,
+            "ModelNotAllowed → 403",
+        ),
         (
             AppError::ApprovalTimeout,
             StatusCode::REQUEST_TIMEOUT,
             "ApprovalTimeout → 408",
         ),
+        (
+            AppError::RequestBudgetExceeded {
+                budget_secs: 60,
+                elapsed_ms: 61_000,
+            },
+            StatusCode::REQUEST_TIMEOUT,
+            "RequestBudgetExceeded → 408",
+        ),
+        (
+            AppError::PolicyEvalTimeout {
+                policy: Some("slow-regex".into()),
+                budget_ms: 50,
+                elapsed_ms: 340,
+            },
+            StatusCode::SERVICE_UNAVAILABLE,
+            "PolicyEvalTimeout → 503",
+        ),
         (
             AppError::ApprovalRejected,
             StatusCode::FORBIDDEN,
             "ApprovalRejected → 403",
         ),
         (
-            AppError::RateLimitExceeded { retry_after_secs: 60 },
+            AppError::RateLimitExceeded {
+                retry_after_secs: 60,
+                limit: None,
+            },
             StatusCode::TOO_MANY_REQUESTS,
             "RateLimitExceeded → 429",
         ),
+        (
+            AppError::StreamLimitExceeded {
+                scope: "global",
+                limit: 100,
+            },
+            StatusCode::TOO_MANY_REQUESTS,
+            "StreamLimitExceeded → 429",
+        ),
         (
             AppError::SpendCapReached {
                 message: "cap hit".into(),
+                cap: None,
+                resets_at: None,
             },
             StatusCode::PAYMENT_REQUIRED,
             "SpendCapReached → 402",
@@ -193,6 +238,23 @@ fn test_every_app_error_variant_correct_status() {
             StatusCode::PAYLOAD_TOO_LARGE,
             "PayloadTooLarge → 413",
         ),
+        (
+            AppError::PayloadTooComplex {
+                limit: "depth",
+                max: 64,
+            },
+            StatusCode::BAD_REQUEST,
+            "PayloadTooComplex → 400",
+        ),
+        (
+            AppError::ContextWindowExceeded {
+                model: "gpt-4".into(),
+                estimated_tokens: 9_000,
+                context_window: 8_192,
+            },
+            StatusCode::BAD_REQUEST,
+            "ContextWindowExceeded → 400",
+        ),
         (
             AppError::ContentBlocked {
                 reason: "x".into(),
@@ -244,7 +306,10 @@ fn test_every_app_error_variant_correct_status() {
 /// ASSERT: Response includes Retry-After header with value "60" and X-RateLimit-Reset.
 #[test]
 fn test_rate_limit_has_retry_after_header() {
-    let error = AppError::RateLimitExceeded { retry_after_secs: 60 };
+    let error = AppError::RateLimitExceeded {
+        retry_after_secs: 60,
+        limit: Some(100),
+    };
     let response = error.into_response();
     let retry_after = response.headers().get("retry-after");
     assert!(
@@ -258,6 +323,161 @@ fn test_rate_limit_has_retry_after_header() {
     );
 }
 
+async fn error_body(error: AppError) -> serde_json::Value {
+    let bytes = axum::body::to_bytes(error.into_response().into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+/// STATE: Every denial carries `error.remediation` with an `action` and the
+///        fields an agent needs to recover (retry delay, cap, what to remove).
+/// BREAK: A variant without a remediation arm, or a renamed key, leaves agent
+///        frameworks guessing how to recover.
+/// ASSERT: Each denial type's remediation has the documented shape.
+#[tokio::test]
+async fn test_denial_remediation_shapes() {
+    let body = error_body(AppError::RateLimitExceeded {
+        retry_after_secs: 60,
+        limit: Some(100),
+    })
+    .await;
+    assert_eq!(
+        body["error"]["remediation"],
+        serde_json::json!({"action": "retry_after", "retry_after_secs": 60, "limit": 100})
+    );
+
+    let resets_at = chrono::Utc::now() + chrono::Duration::hours(2);
+    let body = error_body(AppError::SpendCapReached {
+        message: "cap hit".into(),
+        cap: Some("daily".into()),
+        resets_at: Some(resets_at),
+    })
+    .await;
+    let r = &body["error"]["remediation"];
+    assert_eq!(r["action"], "wait_for_reset");
+    assert_eq!(r["cap"], "daily");
+    assert!(r["resets_at"].is_string());
+    let retry = r["retry_after_secs"].as_i64().unwrap();
+    assert!(
+        (7_100..=7_200).contains(&retry),
+        "retry_after_secs = {retry}"
+    );
+
+    let body = error_body(AppError::SpendCapReached {
+        message: "cap hit".into(),
+        cap: Some("lifetime".into()),
+        resets_at: None,
+    })
+    .await;
+    assert_eq!(body["error"]["remediation"]["action"], "raise_cap");
+    assert!(body["error"]["remediation"]["resets_at"].is_null());
+
+    let body = error_body(AppError::ContentBlocked {
+        reason: "pii".into(),
+        details: Some(serde_json::json!({"policy": "no-pii", "detected_pii": ["email"]})),
+    })
+    .await;
+    assert_eq!(
+        body["error"]["remediation"],
+        serde_json::json!({"action": "remove_content", "policy": "no-pii", "matched": ["email"]})
+    );
+
+    let body = error_body(AppError::ContentBlocked {
+        reason: "toxic".into(),
+        details: Some(serde_json::json!({
+            "phase": "response",
+            "policy": "output-guard",
+            "matched_patterns": ["harmful"],
+        })),
+    })
+    .await;
+    assert_eq!(body["error"]["remediation"]["action"], "rephrase_request");
+    assert_eq!(
+        body["error"]["remediation"]["matched"],
+        serde_json::json!(["harmful"])
+    );
+
+    let body = error_body(AppError::ContextWindowExceeded {
+        model: "gpt-4".into(),
+        estimated_tokens: 9_000,
+        context_window: 8_192,
+    })
+    .await;
+    assert_eq!(body["error"]["remediation"]["action"], "shorten_request");
+    assert_eq!(body["error"]["remediation"]["excess_tokens"], 808);
+
+    let body = error_body(AppError::PolicyDenied {
+        policy: "block-prod".into(),
+        reason: "r".into(),
+    })
+    .await;
+    assert_eq!(
+        body["error"]["remediation"],
+        serde_json::json!({"action": "contact_admin", "policy": "block-prod"})
+    );
+
+    let body = error_body(AppError::ModelNotAllowed {
+        model: "gpt-4o".into(),
+        allowed: vec!["claude-*".into(), "gpt-3.5*".into()],
+    })
+    .await;
+    assert_eq!(body["error"]["type"], "permission_error");
+    assert_eq!(body["error"]["code"], "model_not_allowed");
+    assert_eq!(
+        body["error"]["details"],
+        serde_json::json!({"model": "gpt-4o", "allowed_models": ["claude-*", "gpt-3.5*"]})
+    );
+    assert_eq!(
+        body["error"]["remediation"],
+        serde_json::json!({"action": "contact_admin", "allowed_models": ["claude-*", "gpt-3.5*"]})
+    );
+
+    let body = error_body(AppError::StreamLimitExceeded {
+        scope: "token",
+        limit: 4,
+    })
+    .await;
+    assert_eq!(body["error"]["code"], "concurrent_stream_limit");
+    assert_eq!(
+        body["error"]["remediation"],
+        serde_json::json!({"action": "retry_after", "retry_after_secs": 1, "limit": 4, "scope": "token"})
+    );
+
+    let body = error_body(AppError::PayloadTooLarge).await;
+    assert_eq!(body["error"]["remediation"]["action"], "reduce_payload");
+
+    let body = error_body(AppError::PayloadTooComplex {
+        limit: "array_length",
+        max: 10_000,
+    })
+    .await;
+    assert_eq!(body["error"]["code"], "payload_too_complex");
+    assert_eq!(
+        body["error"]["remediation"],
+        serde_json::json!({"action": "reduce_payload", "limit": "array_length", "max": 10_000})
+    );
+
+    let body = error_body(AppError::ApprovalTimeout).await;
+    assert_eq!(body["error"]["remediation"]["action"], "retry");
+
+    let body = error_body(AppError::PolicyEvalTimeout {
+        policy: Some("slow-regex".into()),
+        budget_ms: 50,
+        elapsed_ms: 340,
+    })
+    .await;
+    assert_eq!(body["error"]["code"], "policy_evaluation_timeout");
+    assert_eq!(
+        body["error"]["remediation"],
+        serde_json::json!({"action": "contact_admin", "policy": "slow-regex"})
+    );
+
+    // Non-denials carry no remediation.
+    let body = error_body(AppError::Upstream("boom".into())).await;
+    assert!(body["error"].get("remediation").is_none());
+}
+
 /// STATE: Every error response must include x-request-id header.
 /// BREAK: Missing header insertion leaves errors untrackable in distributed systems.
 /// ASSERT: Response includes x-request-id header.
@@ -289,6 +509,7 @@ fn make_ctx<'a>(
         headers,
         body,
         body_size: body.map(|b| b.to_string().len()).unwrap_or(0),
+        estimated_cost_usd: None,
         agent_name: Some("test-agent"),
         token_id: "tok_123",
         token_name: "Test Token",
@@ -480,6 +701,7 @@ fn test_shadow_mode_does_not_block() {
     let policy = Policy {
         id: uuid::Uuid::nil(),
         name: "shadow-deny-all".to_string(),
+        priority: 0,
         mode: PolicyMode::Shadow,
         phase: Phase::Pre,
         rules: vec![Rule {
@@ -944,6 +1166,7 @@ fn test_redact_field_specific_key() {
         fields: vec!["password".to_string()],
         on_match: RedactOnMatch::Redact,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({
         "messages": [{"role": "user", "content": "test"}],
@@ -974,6 +1197,7 @@ fn test_redact_block_mode_triggers_on_pii() {
         fields: vec![],
         on_match: RedactOnMatch::Block,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({
         "messages": [{"role": "user", "content": "My SSN is 123-45-6789"}]
@@ -996,6 +1220,7 @@ fn test_redact_block_mode_no_false_positive() {
         fields: vec![],
         on_match: RedactOnMatch::Block,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({
         "messages": [{"role": "user", "content": "What is the weather today?"}]
@@ -1018,6 +1243,7 @@ fn test_redact_direction_request_skips_response() {
         fields: vec![],
         on_match: RedactOnMatch::Redact,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({
         "messages": [{"role": "assistant", "content": "SSN is 123-45-6789"}]
@@ -16,6 +16,7 @@ fn cc_redact_action() -> Action {
         fields: vec![],
         on_match: RedactOnMatch::Redact,
         nlp_backend: None,
+        custom_regexes: vec![],
     }
 }
 
@@ -86,6 +87,7 @@ fn test_multiple_pii_types_in_one_message() {
         fields: vec![],
         on_match: RedactOnMatch::Redact,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({
         "messages": [{"role": "user",
@@ -113,6 +115,7 @@ fn test_pii_block_mode_triggers_on_match() {
         fields: vec![],
         on_match: RedactOnMatch::Block,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({"messages": [{"role": "user", "content": "Card: 4111111111111111"}]});
     let result = apply_redact(&mut body, &action, true);
@@ -131,6 +134,7 @@ fn test_pii_block_mode_no_false_positive() {
         fields: vec![],
         on_match: RedactOnMatch::Block,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
     let mut body = json!({"messages": [{"role": "user", "content": "What is the weather today?"}]});
     let result = apply_redact(&mut body, &action, true);
@@ -209,6 +209,7 @@ fn test_destructive_redact_unaffected_by_tokenize_addition() {
         fields: vec![],
         on_match: RedactOnMatch::Redact,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
 
     let mut body = json!({
@@ -239,6 +240,7 @@ fn test_block_mode_unaffected_by_tokenize_addition() {
         fields: vec![],
         on_match: RedactOnMatch::Block,
         nlp_backend: None,
+        custom_regexes: vec![],
     };
 
     let mut body = json!({
@@ -523,6 +525,48 @@ fn test_tool_scope_empty_lists_allow_all_no_false_positive() {
     );
 }
 
+#[test]
+fn test_require_properties_reports_missing_keys() {
+    use gateway::middleware::engine::evaluate_required_properties;
+
+    let props = serde_json::json!({"cost_center": "cc-42"});
+    let required = vec!["cost_center".to_string(), "environment".to_string()];
+    let offending = evaluate_required_properties(Some(&props), &required, None);
+    assert_eq!(offending, vec!["environment"]);
+
+    // No X-Properties header at all → every required key is missing
+    let offending = evaluate_required_properties(None, &required, None);
+    assert_eq!(offending, vec!["cost_center", "environment"]);
+}
+
+#[test]
+fn test_require_properties_enforces_allowed_values() {
+    use gateway::middleware::engine::evaluate_required_properties;
+    use std::collections::HashMap;
+
+    let required = vec!["environment".to_string()];
+    let allowed = HashMap::from([
+        (
+            "environment".to_string(),
+            vec!["dev".to_string(), "prod".to_string()],
+        ),
+        ("tier".to_string(), vec!["1".to_string(), "2".to_string()]),
+    ]);
+
+    let ok = serde_json::json!({"environment": "prod", "tier": 2});
+    assert!(evaluate_required_properties(Some(&ok), &required, Some(&allowed)).is_empty());
+
+    let bad = serde_json::json!({"environment": "qa", "tier": 3});
+    assert_eq!(
+        evaluate_required_properties(Some(&bad), &required, Some(&allowed)),
+        vec!["environment", "tier"]
+    );
+
+    // Keys only listed in allowed_values are optional
+    let absent = serde_json::json!({"environment": "dev"});
+    assert!(evaluate_required_properties(Some(&absent), &required, Some(&allowed)).is_empty());
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Anomaly Detection — statistical correctness + false positive checks
 // ═══════════════════════════════════════════════════════════════════════════
@@ -30,6 +30,7 @@ fn test_ctx<'a>(
         headers,
         body,
         body_size: body.map(|b| b.to_string().len()).unwrap_or(0),
+        estimated_cost_usd: None,
         agent_name: Some("test-agent"),
         token_id: "tok_integ_test",
         token_name: "Integration Test Token",
@@ -0,0 +1,65 @@
+//! GCP Secret Manager vault backend against a real project.
+//!
+//! Run with `TRUEFLOW_GCP_SECRET_PROJECT=<project> cargo test --features
+//! gcp-integration --test gcp_secret_manager`, with credentials from
+//! `GOOGLE_APPLICATION_CREDENTIALS` or the metadata server. Each run creates
+//! and deletes its own secrets.
+#![cfg(feature = "gcp-integration")]
+
+use gateway::vault::gcp_secret_manager::{GcpSecretManagerStore, SecretMetadata};
+use gateway::vault::SecretStore;
+use uuid::Uuid;
+
+fn store() -> GcpSecretManagerStore {
+    let project = std::env::var("TRUEFLOW_GCP_SECRET_PROJECT")
+        .expect("TRUEFLOW_GCP_SECRET_PROJECT must be set");
+    GcpSecretManagerStore::new(&project, "trueflow-it-").unwrap()
+}
+
+#[tokio::test]
+async fn test_store_retrieve_delete_through_trait() {
+    let store = store();
+    let secrets: &dyn SecretStore = &store;
+
+    let id = secrets.store("sk-it-default").await.unwrap();
+    let (secret, provider, mode, header) = secrets.retrieve(&id).await.unwrap();
+    assert_eq!(secret, "sk-it-default");
+    assert_eq!(provider, "custom");
+    assert_eq!(mode, "bearer");
+    assert_eq!(header, "authorization");
+
+    secrets.delete(&id, Uuid::new_v4()).await.unwrap();
+    assert!(secrets.retrieve(&id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_metadata_labels_and_project_scoped_delete() {
+    let store = store();
+    let id = Uuid::new_v4().to_string();
+    let project_id = Uuid::new_v4();
+    let metadata = SecretMetadata {
+        provider: "anthropic".to_string(),
+        injection_mode: "header".to_string(),
+        injection_header: "x-api-key".to_string(),
+        project_id: Some(project_id),
+    };
+    store
+        .store_credential(&id, "sk-ant-it", &metadata)
+        .await
+        .unwrap();
+
+    let secrets: &dyn SecretStore = &store;
+    let retrieved = secrets.retrieve(&id).await.unwrap();
+    assert_eq!(
+        retrieved,
+        (
+            "sk-ant-it".to_string(),
+            "anthropic".to_string(),
+            "header".to_string(),
+            "x-api-key".to_string()
+        )
+    );
+
+    assert!(secrets.delete(&id, Uuid::new_v4()).await.is_err());
+    secrets.delete(&id, project_id).await.unwrap();
+}
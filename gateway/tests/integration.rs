@@ -417,6 +417,8 @@ mod error_tests {
         // Verify the error variant exists and can be constructed
         let err = AppError::SpendCapReached {
             message: "daily spend cap exceeded".into(),
+            cap: Some("daily".into()),
+            resets_at: None,
         };
         let err_str = format!("{}", err);
         assert!(
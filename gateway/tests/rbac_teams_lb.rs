@@ -280,6 +280,10 @@ fn test_routing_strategy_all_variants_deserialize() {
         ("\"round_robin\"", RoutingStrategy::RoundRobin),
         ("\"least_busy\"", RoutingStrategy::LeastBusy),
         ("\"weighted_random\"", RoutingStrategy::WeightedRandom),
+        (
+            "\"by_prompt_complexity\"",
+            RoutingStrategy::ByPromptComplexity,
+        ),
     ];
 
     for (json_str, expected) in strategies {
@@ -308,6 +312,7 @@ fn test_routing_strategy_round_trip() {
         RoutingStrategy::RoundRobin,
         RoutingStrategy::LeastBusy,
         RoutingStrategy::WeightedRandom,
+        RoutingStrategy::ByPromptComplexity,
     ];
 
     for strategy in strategies {
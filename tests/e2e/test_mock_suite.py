@@ -4489,10 +4489,49 @@ def t36_audit_has_model_and_status():
     return f"Audit fields: keys={list(latest.keys())[:6]} ✓"
 
 
+def t36_audit_replay_reruns_original_body():
+    """Replay sends the client's body, not the policy-rewritten one, and the
+    policy applies again on the way out."""
+    p = admin.policies.create(
+        name=f"replay-xform-{RUN_ID}",
+        rules=[{"when": {"always": True}, "then": {
+            "action": "transform",
+            "operations": [{"type": "set_body_field", "path": "temperature", "value": 0.1}],
+        }}],
+    )
+    _cleanup_policies.append(p.id)
+    t = admin.tokens.create(
+        name=f"replay-tok-{RUN_ID}", upstream_url=MOCK_GATEWAY,
+        credential_id=_mock_cred_id, policy_ids=[p.id], log_level="full",
+    )
+    _cleanup_tokens.append(t.token_id)
+
+    r = chat(t.token_id, "replay me", model="gpt-4o", temperature=0.9)
+    assert r.status_code == 200, f"Chat failed: {r.status_code}"
+    audit_id = r.headers.get("x-request-id")
+    assert audit_id, "missing x-request-id"
+    time.sleep(1.0)
+
+    detail = gw("GET", f"/api/v1/audit/{audit_id}", headers={"x-admin-key": ADMIN_KEY})
+    assert detail.status_code == 200, f"Get audit: {detail.status_code}"
+    logged = json.loads(detail.json()["request_body"])
+    assert logged["temperature"] == 0.9, f"logged body was rewritten: {logged}"
+
+    replay = gw("POST", f"/api/v1/audit/{audit_id}/replay", headers={"x-admin-key": ADMIN_KEY})
+    assert replay.status_code == 200, f"Replay failed: {replay.status_code} {replay.text[:200]}"
+    outcome = replay.json()
+    assert outcome["status"] == 200, f"Replayed request: {outcome}"
+    received = outcome["body"].get("_debug", {}).get("received_body", {})
+    assert received.get("temperature") == 0.1, f"policy not re-applied: {received}"
+    assert outcome["audit_id"] != audit_id
+    return "Replay re-ran the original body through the transform ✓"
+
+
 test("Audit: list returns entries with required fields", t36_audit_list_returns_entries)
 test("Audit: get by ID returns full detail", t36_audit_get_by_id)
 test("Audit: scope denial without audit:read", t36_audit_scope_denied)
 test("Audit: entries have model and status fields", t36_audit_has_model_and_status)
+test("Audit: replay re-runs the pre-policy body", t36_audit_replay_reruns_original_body)
 
 # ═══════════════════════════════════════════════════════════════
 #  Phase 37 — Analytics Endpoints